@@ -0,0 +1,191 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use comrade::events::{AlertId, Event, EventKind};
+use comrade::{Character, Priority, Trigger};
+
+use crate::clock;
+
+/// How long an alert stays on screen, unless its trigger says otherwise.
+const DEFAULT_ALERT_DURATION: Duration = Duration::from_secs(5);
+
+/// High priority popups hang around for longer by default, since the user
+/// might be looking at another tab when they arrive.
+const DEFAULT_POPUP_DURATION: Duration = Duration::from_secs(15);
+
+/// The most popups that are kept around at once, older ones are dropped to
+/// make room for new ones.
+const MAX_POPUPS: usize = 5;
+
+/// Text from a trigger that's important enough to be shown prominently.
+pub(crate) struct Alert {
+    /// Set for alerts that stay on screen until they're acknowledged.
+    pub(crate) id: Option<AlertId>,
+    pub(crate) title: String,
+    pub(crate) text: Arc<String>,
+    pub(crate) color: Option<String>,
+    /// The character whose log this alert came from, if any.
+    pub(crate) character: Option<Arc<Character>>,
+    pub(crate) started: Instant,
+    pub(crate) until: Instant,
+}
+
+impl Alert {
+    pub(crate) fn new(
+        text: Arc<String>,
+        trigger: &Trigger,
+        id: Option<AlertId>,
+        character: Option<Arc<Character>>,
+    ) -> Alert {
+        Alert::with_duration(text, trigger, id, character, DEFAULT_ALERT_DURATION)
+    }
+
+    fn with_duration(
+        text: Arc<String>,
+        trigger: &Trigger,
+        id: Option<AlertId>,
+        character: Option<Arc<Character>>,
+        default: Duration,
+    ) -> Alert {
+        let duration = trigger
+            .style
+            .seconds
+            .map(Duration::from_secs)
+            .unwrap_or(default);
+        let started = clock::now();
+
+        Alert {
+            id,
+            title: trigger.name.clone(),
+            text,
+            color: trigger.style.color.clone(),
+            character,
+            started,
+            until: started + duration,
+        }
+    }
+
+    pub(crate) fn expired(&self) -> bool {
+        self.id.is_none() && clock::now() >= self.until
+    }
+
+    /// Alerts flash on and off while they're on screen, this says which half
+    /// of the flash we're in.
+    pub(crate) fn flash(&self) -> bool {
+        clock::now()
+            .saturating_duration_since(self.started)
+            .as_millis()
+            % 1000
+            < 500
+    }
+}
+
+/// The alerts currently on screen, these are tracked by the app rather than
+/// by any one tab so that they can be shown over whichever tab is current.
+#[derive(Default)]
+pub(crate) struct Alerts {
+    big_text: Option<Arc<Alert>>,
+    popups: Vec<Arc<Alert>>,
+}
+
+impl Alerts {
+    pub(crate) fn event(&mut self, event: &Event) {
+        match event.kind() {
+            EventKind::DisplayText {
+                text,
+                trigger: Some(trigger),
+                alert,
+                character,
+            } => {
+                if trigger.wants_big_text() {
+                    self.big_text = Some(Arc::new(Alert::new(
+                        text.clone(),
+                        trigger,
+                        *alert,
+                        character.clone(),
+                    )));
+                }
+
+                if trigger.priority == Priority::High || alert.is_some() {
+                    self.popup(Alert::with_duration(
+                        text.clone(),
+                        trigger,
+                        *alert,
+                        character.clone(),
+                        DEFAULT_POPUP_DURATION,
+                    ));
+                }
+            }
+            EventKind::Acknowledged { alert } => self.dismiss(*alert),
+            _ => {}
+        }
+    }
+
+    fn popup(&mut self, alert: Alert) {
+        self.popups.retain(|p| !p.expired());
+
+        // A repeat of an alert that's still waiting to be acknowledged takes
+        // the place of the original, rather than stacking up another popup.
+        if alert.id.is_some() {
+            self.popups.retain(|p| p.id != alert.id);
+        }
+        self.popups.push(Arc::new(alert));
+
+        // Make room by dropping the oldest popups that will go away on their
+        // own anyways, the ones that need acknowledging have to stay.
+        if self.popups.len() > MAX_POPUPS {
+            if let Some(idx) = self.popups.iter().position(|p| p.id.is_none()) {
+                self.popups.remove(idx);
+            }
+        }
+    }
+
+    /// Removes an alert that has been acknowledged from the screen.
+    pub(crate) fn dismiss(&mut self, id: AlertId) {
+        self.popups.retain(|p| p.id != Some(id));
+        if self.big_text.as_ref().map(|a| a.id) == Some(Some(id)) {
+            self.big_text = None;
+        }
+    }
+
+    /// The most recent big text alert, if it's still meant to be on screen.
+    pub(crate) fn big_text(&self) -> Option<Arc<Alert>> {
+        self.big_text.as_ref().filter(|a| !a.expired()).cloned()
+    }
+
+    /// The popups that are still meant to be on screen, oldest first.
+    pub(crate) fn popups(&self) -> Vec<Arc<Alert>> {
+        self.popups
+            .iter()
+            .filter(|p| !p.expired())
+            .cloned()
+            .collect()
+    }
+
+    /// Dismisses the most recent popup, returning the alert that now needs
+    /// to be acknowledged so that it stops repeating.
+    pub(crate) fn acknowledge(&mut self) -> Option<AlertId> {
+        self.popups.retain(|p| !p.expired());
+        let id = self.popups.pop()?.id?;
+        self.dismiss(id);
+        Some(id)
+    }
+
+    /// How many alerts from the given character are still waiting to be
+    /// acknowledged.
+    pub(crate) fn unacknowledged(&self, character: &Character) -> usize {
+        self.popups
+            .iter()
+            .filter(|p| p.id.is_some())
+            .filter(|p| {
+                p.character
+                    .as_ref()
+                    .is_some_and(|c| c.filename == character.filename)
+            })
+            .count()
+    }
+
+    pub(crate) fn active(&self) -> bool {
+        self.big_text().is_some() || self.popups.iter().any(|p| !p.expired())
+    }
+}
@@ -0,0 +1,147 @@
+use std::collections::BTreeMap;
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use comrade::{Action, Priority, Trigger, TriggerId, TriggerStyle};
+
+pub(crate) const FIELDS: [&str; 4] = ["Id", "Name", "Pattern", "Display Text"];
+
+#[derive(Debug, Default, Clone)]
+pub(crate) struct TriggerDraft {
+    pub(crate) id: String,
+    pub(crate) name: String,
+    pub(crate) pattern: String,
+    pub(crate) text: String,
+    /// The log line that the draft was made from, if it was, which the
+    /// pattern's captures are previewed against.
+    pub(crate) sample: Option<String>,
+}
+
+impl TriggerDraft {
+    /// Creates a draft whose pattern matches the given log line, with the
+    /// parts that look like they'll vary replaced by capture groups.
+    pub(crate) fn from_line(line: &str) -> TriggerDraft {
+        TriggerDraft {
+            pattern: comrade::suggest_pattern(line),
+            sample: Some(line.to_string()),
+            ..TriggerDraft::default()
+        }
+    }
+
+    pub(crate) fn build(&self) -> Result<(TriggerId, Trigger), String> {
+        let id = self.id.trim();
+        if id.is_empty() {
+            return Err("an id is required".to_string());
+        }
+        if self.pattern.is_empty() {
+            return Err("a pattern is required".to_string());
+        }
+
+        let name = match self.name.trim() {
+            "" => id.to_string(),
+            name => name.to_string(),
+        };
+
+        let mut actions = Vec::new();
+        if !self.text.is_empty() {
+            actions.push(Action::DisplayText {
+                text: self.text.clone(),
+                delay: None,
+                collapse: None,
+            });
+        }
+
+        Ok((
+            TriggerId::new(id),
+            Trigger {
+                name,
+                comment: String::new(),
+                search_text: self.pattern.clone(),
+                tags: Vec::new(),
+                disabled: false,
+                priority: Priority::default(),
+                style: TriggerStyle::default(),
+                acknowledge: false,
+                repeat: None,
+                captures: BTreeMap::new(),
+                conditions: Vec::new(),
+                active: Vec::new(),
+                actions,
+            },
+        ))
+    }
+
+    fn field_mut(&mut self, idx: usize) -> &mut String {
+        match idx {
+            0 => &mut self.id,
+            1 => &mut self.name,
+            2 => &mut self.pattern,
+            _ => &mut self.text,
+        }
+    }
+}
+
+pub(crate) enum EditorOutcome {
+    Editing,
+    Save,
+    Cancel,
+}
+
+pub(crate) struct TriggerEditor {
+    draft: TriggerDraft,
+    focus: usize,
+    error: Option<String>,
+}
+
+impl TriggerEditor {
+    pub(crate) fn new(draft: TriggerDraft) -> TriggerEditor {
+        TriggerEditor {
+            draft,
+            focus: 0,
+            error: None,
+        }
+    }
+
+    pub(crate) fn on_key(&mut self, key: KeyEvent) -> EditorOutcome {
+        match key.code {
+            KeyCode::Tab | KeyCode::Down => self.focus = (self.focus + 1) % FIELDS.len(),
+            KeyCode::BackTab | KeyCode::Up => {
+                self.focus = (self.focus + FIELDS.len() - 1) % FIELDS.len()
+            }
+            KeyCode::Enter => return EditorOutcome::Save,
+            KeyCode::Esc => return EditorOutcome::Cancel,
+            KeyCode::Char(c) => self.draft.field_mut(self.focus).push(c),
+            KeyCode::Backspace => {
+                self.draft.field_mut(self.focus).pop();
+            }
+            _ => {}
+        }
+
+        EditorOutcome::Editing
+    }
+
+    pub(crate) fn draft(&self) -> &TriggerDraft {
+        &self.draft
+    }
+
+    pub(crate) fn fields(&self) -> [&str; 4] {
+        [
+            self.draft.id.as_str(),
+            self.draft.name.as_str(),
+            self.draft.pattern.as_str(),
+            self.draft.text.as_str(),
+        ]
+    }
+
+    pub(crate) fn focus(&self) -> usize {
+        self.focus
+    }
+
+    pub(crate) fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub(crate) fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
+}
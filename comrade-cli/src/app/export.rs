@@ -0,0 +1,376 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use humantime::format_rfc3339_seconds;
+use serde::Serialize;
+
+use comrade::{AttendanceReport, FightSummary, RecipeStats, ResistStats, TriggerRef};
+
+use crate::errors::ExportError;
+
+const EXPORT_DIRNAME: &str = "exports";
+
+/// A single trigger match, as shown in the events tab.
+#[derive(Serialize, Clone)]
+pub(crate) struct Matched {
+    #[serde(serialize_with = "rfc3339")]
+    pub(crate) time: SystemTime,
+    pub(crate) character: String,
+    pub(crate) trigger: String,
+    pub(crate) text: String,
+    /// Which trigger matched, so that it can be acted on from the events tab.
+    #[serde(skip)]
+    pub(crate) tref: TriggerRef,
+}
+
+impl Row for Matched {
+    fn header() -> &'static [&'static str] {
+        &["time", "character", "trigger", "text"]
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            format_rfc3339_seconds(self.time).to_string(),
+            self.character.clone(),
+            self.trigger.clone(),
+            self.text.clone(),
+        ]
+    }
+}
+
+/// How a single attacker did in a single fight, with the same columns that
+/// EQLogParser and GamParse use for their DPS summaries, so that exports can
+/// be compared against theirs.
+#[derive(Serialize)]
+pub(crate) struct FightRow {
+    pub(crate) encounter: String,
+    #[serde(serialize_with = "rfc3339")]
+    pub(crate) start: SystemTime,
+    pub(crate) character: String,
+    pub(crate) name: String,
+    pub(crate) damage: u64,
+    pub(crate) dps: u64,
+    pub(crate) seconds: u64,
+    /// The share of the fight's total damage that this attacker did.
+    pub(crate) percent: u64,
+    pub(crate) hits: u64,
+    pub(crate) max: u64,
+    pub(crate) avg: u64,
+}
+
+impl FightRow {
+    pub(crate) fn rows(fights: &[FightSummary]) -> Vec<FightRow> {
+        let mut rows = Vec::new();
+        for fight in fights.iter() {
+            let total = fight.damage().max(1);
+            let seconds = fight.duration.as_secs().max(1);
+            for attacker in fight.attackers.iter() {
+                rows.push(FightRow {
+                    encounter: fight.target.clone(),
+                    start: fight.started,
+                    character: fight.character.to_string(),
+                    name: attacker.name.clone(),
+                    damage: attacker.damage,
+                    dps: attacker.damage / seconds,
+                    seconds,
+                    percent: (attacker.damage * 100 + total / 2) / total,
+                    hits: attacker.hits,
+                    max: attacker.max,
+                    avg: attacker.damage / attacker.hits.max(1),
+                });
+            }
+        }
+        rows
+    }
+}
+
+impl Row for FightRow {
+    fn header() -> &'static [&'static str] {
+        &[
+            "Encounter",
+            "Start",
+            "Character",
+            "Name",
+            "Damage",
+            "DPS",
+            "Sec",
+            "%",
+            "Hits",
+            "Max",
+            "Avg",
+        ]
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.encounter.clone(),
+            format_rfc3339_seconds(self.start).to_string(),
+            self.character.clone(),
+            self.name.clone(),
+            self.damage.to_string(),
+            self.dps.to_string(),
+            self.seconds.to_string(),
+            self.percent.to_string(),
+            self.hits.to_string(),
+            self.max.to_string(),
+            self.avg.to_string(),
+        ]
+    }
+}
+
+/// How often a single player was present for a raid, with the times of the
+/// checkpoints they were present for, which is what most DKP tools want.
+#[derive(Serialize)]
+pub(crate) struct AttendanceRow {
+    pub(crate) name: String,
+    pub(crate) attended: usize,
+    pub(crate) checkpoints: usize,
+    pub(crate) percent: usize,
+    pub(crate) present: Vec<String>,
+}
+
+impl AttendanceRow {
+    pub(crate) fn rows(report: &AttendanceReport) -> Vec<AttendanceRow> {
+        let checkpoints = report.checkpoints.len();
+        report
+            .players
+            .iter()
+            .map(|player| {
+                let attended = player.attended();
+                AttendanceRow {
+                    name: player.name.clone(),
+                    attended,
+                    checkpoints,
+                    percent: (attended * 100 + checkpoints / 2) / checkpoints.max(1),
+                    present: report
+                        .checkpoints
+                        .iter()
+                        .zip(player.present.iter())
+                        .filter(|(_, present)| **present)
+                        .filter_map(|(checkpoint, _)| checkpoint.time())
+                        .map(|time| time.to_string())
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Row for AttendanceRow {
+    fn header() -> &'static [&'static str] {
+        &["Name", "Attended", "Checkpoints", "%", "Present"]
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.attended.to_string(),
+            self.checkpoints.to_string(),
+            self.percent.to_string(),
+            self.present.join(" "),
+        ]
+    }
+}
+
+/// How a single character has done at making a single recipe.
+#[derive(Serialize)]
+pub(crate) struct RecipeRow {
+    pub(crate) character: String,
+    pub(crate) recipe: String,
+    pub(crate) attempts: u64,
+    pub(crate) successes: u64,
+    pub(crate) failures: u64,
+    pub(crate) trivials: u64,
+    pub(crate) skill_ups: u64,
+    pub(crate) success_rate: f64,
+}
+
+impl RecipeRow {
+    pub(crate) fn rows(recipes: &[RecipeStats]) -> Vec<RecipeRow> {
+        recipes
+            .iter()
+            .map(|r| RecipeRow {
+                character: r.character.to_string(),
+                recipe: r.recipe.clone(),
+                attempts: r.attempts,
+                successes: r.successes,
+                failures: r.failures(),
+                trivials: r.trivials,
+                skill_ups: r.skill_ups,
+                success_rate: r.success_rate(),
+            })
+            .collect()
+    }
+}
+
+impl Row for RecipeRow {
+    fn header() -> &'static [&'static str] {
+        &[
+            "Character",
+            "Recipe",
+            "Attempts",
+            "Successes",
+            "Failures",
+            "Trivials",
+            "Skill-ups",
+            "Success %",
+        ]
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.character.clone(),
+            self.recipe.clone(),
+            self.attempts.to_string(),
+            self.successes.to_string(),
+            self.failures.to_string(),
+            self.trivials.to_string(),
+            self.skill_ups.to_string(),
+            format!("{:.1}", self.success_rate),
+        ]
+    }
+}
+
+/// How often a single target has resisted a single character's spell.
+#[derive(Serialize)]
+pub(crate) struct ResistRow {
+    pub(crate) character: String,
+    pub(crate) target: String,
+    pub(crate) spell: String,
+    pub(crate) casts: u64,
+    pub(crate) resists: u64,
+    pub(crate) interrupts: u64,
+    pub(crate) fizzles: u64,
+    pub(crate) resist_rate: f64,
+}
+
+impl ResistRow {
+    pub(crate) fn rows(resists: &[ResistStats]) -> Vec<ResistRow> {
+        resists
+            .iter()
+            .map(|r| ResistRow {
+                character: r.character.to_string(),
+                target: r.target.clone(),
+                spell: r.spell.clone(),
+                casts: r.casts,
+                resists: r.resists,
+                interrupts: r.interrupts,
+                fizzles: r.fizzles,
+                resist_rate: r.resist_rate(),
+            })
+            .collect()
+    }
+}
+
+impl Row for ResistRow {
+    fn header() -> &'static [&'static str] {
+        &[
+            "Character",
+            "Target",
+            "Spell",
+            "Casts",
+            "Resists",
+            "Interrupts",
+            "Fizzles",
+            "Resist %",
+        ]
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.character.clone(),
+            self.target.clone(),
+            self.spell.clone(),
+            self.casts.to_string(),
+            self.resists.to_string(),
+            self.interrupts.to_string(),
+            self.fizzles.to_string(),
+            format!("{:.1}", self.resist_rate),
+        ]
+    }
+}
+
+/// Something that can be exported, as a row of a CSV file or as an object in
+/// a JSON array.
+pub(crate) trait Row: Serialize {
+    fn header() -> &'static [&'static str];
+    fn fields(&self) -> Vec<String>;
+}
+
+fn rfc3339<S: serde::Serializer>(time: &SystemTime, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&format_rfc3339_seconds(*time))
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = ExportError;
+
+    fn from_str(s: &str) -> Result<ExportFormat, ExportError> {
+        match s.to_lowercase().as_str() {
+            "" | "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            _ => Err(ExportError::UnknownFormat(s.to_string())),
+        }
+    }
+}
+
+/// Writes the given rows out to a new file within the data directory, named
+/// after what they are, returning where it was written to.
+pub(crate) fn export<T: Row>(
+    data_dir: &Path,
+    name: &str,
+    rows: &[T],
+    format: ExportFormat,
+) -> Result<PathBuf, ExportError> {
+    let dir = data_dir.join(EXPORT_DIRNAME);
+    fs::create_dir_all(dir.as_path())?;
+
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let filename = dir.join(format!("{}-{}.{}", name, stamp, format.extension()));
+
+    let contents = match format {
+        ExportFormat::Csv => csv(rows),
+        ExportFormat::Json => serde_json::to_string_pretty(rows)?,
+    };
+    fs::write(filename.as_path(), contents)?;
+
+    Ok(filename)
+}
+
+fn csv<T: Row>(rows: &[T]) -> String {
+    let mut out = T::header().join(",");
+    out.push('\n');
+    for row in rows.iter() {
+        let line: Vec<String> = row.fields().iter().map(|f| csv_field(f)).collect();
+        out.push_str(line.join(",").as_str());
+        out.push('\n');
+    }
+    out
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
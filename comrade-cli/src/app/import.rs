@@ -0,0 +1,151 @@
+use std::path::Path;
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use comrade::{Character, CharacterId, GinaImport, ImportedTrigger};
+
+use crate::errors::describe_error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ImportStep {
+    /// Picking the file to import from.
+    File,
+    /// Looking over what will be imported, and what couldn't be.
+    Preview,
+    /// Choosing which characters the imported triggers are enabled for.
+    Characters,
+}
+
+pub(crate) enum ImportOutcome {
+    Importing,
+    Import(Vec<ImportedTrigger>, Vec<CharacterId>),
+    Cancel,
+}
+
+/// Walks the user through importing triggers from GINA.
+pub(crate) struct ImportWizard {
+    step: ImportStep,
+    path: String,
+    import: Option<GinaImport>,
+    characters: Vec<(CharacterId, Character, bool)>,
+    selected: usize,
+    scroll: usize,
+    error: Option<String>,
+}
+
+impl ImportWizard {
+    pub(crate) fn new(characters: Vec<(CharacterId, Character)>) -> ImportWizard {
+        ImportWizard {
+            step: ImportStep::File,
+            path: String::new(),
+            import: None,
+            characters: characters
+                .into_iter()
+                .map(|(id, c)| (id, c, false))
+                .collect(),
+            selected: 0,
+            scroll: 0,
+            error: None,
+        }
+    }
+
+    pub(crate) fn on_key(&mut self, key: KeyEvent) -> ImportOutcome {
+        match self.step {
+            ImportStep::File => match key.code {
+                KeyCode::Char(c) => self.path.push(c),
+                KeyCode::Backspace => {
+                    self.path.pop();
+                }
+                KeyCode::Enter => self.load(),
+                KeyCode::Esc => return ImportOutcome::Cancel,
+                _ => {}
+            },
+            ImportStep::Preview => match key.code {
+                KeyCode::Up => self.scroll = self.scroll.saturating_sub(1),
+                KeyCode::Down => self.scroll += 1,
+                KeyCode::Enter => self.step = ImportStep::Characters,
+                KeyCode::Esc => self.step = ImportStep::File,
+                _ => {}
+            },
+            ImportStep::Characters => match key.code {
+                KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+                KeyCode::Down => {
+                    self.selected = (self.selected + 1).min(self.characters.len().saturating_sub(1))
+                }
+                KeyCode::Char(' ') => {
+                    if let Some((_, _, picked)) = self.characters.get_mut(self.selected) {
+                        *picked = !*picked;
+                    }
+                }
+                KeyCode::Char('a') => {
+                    let all = self.characters.iter().all(|(_, _, picked)| *picked);
+                    for (_, _, picked) in self.characters.iter_mut() {
+                        *picked = !all;
+                    }
+                }
+                KeyCode::Enter => {
+                    let triggers = self
+                        .import
+                        .as_ref()
+                        .map(|i| i.triggers.clone())
+                        .unwrap_or_default();
+                    let characters = self
+                        .characters
+                        .iter()
+                        .filter(|(_, _, picked)| *picked)
+                        .map(|(id, _, _)| id.clone())
+                        .collect();
+                    return ImportOutcome::Import(triggers, characters);
+                }
+                KeyCode::Esc => self.step = ImportStep::Preview,
+                _ => {}
+            },
+        }
+
+        ImportOutcome::Importing
+    }
+
+    fn load(&mut self) {
+        match comrade::import_gina(Path::new(self.path.trim())) {
+            Ok(import) => {
+                self.import = Some(import);
+                self.step = ImportStep::Preview;
+                self.scroll = 0;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(describe_error(&e)),
+        }
+    }
+
+    pub(crate) fn step(&self) -> ImportStep {
+        self.step
+    }
+
+    pub(crate) fn path(&self) -> &str {
+        self.path.as_str()
+    }
+
+    pub(crate) fn import(&self) -> Option<&GinaImport> {
+        self.import.as_ref()
+    }
+
+    pub(crate) fn characters(&self) -> &[(CharacterId, Character, bool)] {
+        &self.characters
+    }
+
+    pub(crate) fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub(crate) fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    pub(crate) fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    pub(crate) fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
+}
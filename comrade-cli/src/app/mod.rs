@@ -1,24 +1,90 @@
+use std::sync::Arc;
+use std::thread;
 use std::time::{Duration, Instant};
 
 use crossterm::event;
 use crossterm::event::{KeyCode, KeyModifiers};
 use downcast_rs::{impl_downcast, Downcast};
+use humantime::format_duration;
 use indexmap::map::IndexMap;
-use log::debug;
-
-use comrade::Comrade;
-
-pub(crate) use crate::app::tabs::{ConfigTab, DebugTab, EventsTab, LogsTab};
-use crate::errors::{ApplicationError, TerminalError};
+use log::{debug, error, info, warn};
+
+use comrade::errors::ComradeError;
+use comrade::events::{Continuity, EventKind, EventStreams};
+use comrade::{
+    parse_duration, CharacterId, ComplianceReport, Comrade, ImportedTrigger, ManualTimer, Release,
+    Trigger, TriggerId, TriggerRef, UiConfig,
+};
+
+use crate::app::alerts::Alerts;
+pub(crate) use crate::app::editor::{TriggerDraft, TriggerEditor, FIELDS as EDITOR_FIELDS};
+use crate::app::export::{export, AttendanceRow, ExportFormat, FightRow, RecipeRow, ResistRow};
+pub(crate) use crate::app::import::{ImportStep, ImportWizard};
+use crate::app::state::{state_file, UiState};
+pub(crate) use crate::app::tabs::{
+    ConfigTab, DebugTab, EventsTab, LogsTab, SourcesTab, TriggersTab,
+};
+pub(crate) use crate::app::timers::{Timer, TimerRow};
+use crate::errors::{describe_error, ApplicationError, TerminalError};
 use crate::terminal::ComradeTerminal;
 use crate::ui;
 
+mod alerts;
+mod editor;
+mod export;
+mod import;
+#[cfg(test)]
+mod snapshots;
+mod state;
 mod tabs;
+mod timers;
 
 type Result<T, E = ApplicationError> = core::result::Result<T, E>;
 
+/// How often to redraw while there are timers running or alerts flashing,
+/// regardless of the tick rate, so that they animate smoothly.
+const ANIMATION_FRAME_RATE: Duration = Duration::from_millis(50);
+
+/// The least amount of time between frames, however much is going on.
+const FRAME_INTERVAL: Duration = Duration::from_millis(33);
+
+/// The longest we'll go without checking whether Comrade has events for us.
+const EVENT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The most events from Comrade that are handled between checks for input.
+const MAX_EVENTS_PER_PASS: usize = 500;
+
+/// How much the volume hotkeys change the volume by, in percentage points.
+const VOLUME_STEP: i16 = 10;
+
+/// How long a trigger is snoozed for when it's snoozed with a key press.
+const DEFAULT_SNOOZE: Duration = Duration::from_secs(10 * 60);
+
+/// What `/profile` calls the main configuration, outside of any profile.
+const MAIN_PROFILE: &str = "default";
+
+/// Things that a tab can ask the application to do on its behalf, in
+/// response to an event.
+pub(crate) enum AppCommand {
+    /// A command line typed in by the user, e.g. `timer 6m30s Pick respawn`.
+    Run(String),
+    NewTriggerFromLine(String),
+    AddTrigger(TriggerId, Trigger),
+    OpenImport,
+    ImportTriggers(Vec<ImportedTrigger>, Vec<CharacterId>),
+    SetTriggersEnabled(Vec<TriggerRef>, bool),
+    RemoveTrigger(TriggerRef),
+    SnoozeTrigger(TriggerRef, Duration),
+    WakeTrigger(TriggerRef),
+    SyncSource(String),
+    SetSourceDisabled(String, bool),
+    RemoveSource(String),
+    Undo,
+    Redo,
+}
+
 pub(crate) trait Eventable {
-    fn on_event(&self, event: event::Event) -> Result<()>;
+    fn on_event(&self, event: event::Event) -> Result<Option<AppCommand>>;
 }
 
 pub(crate) trait Tab: Eventable + Downcast {
@@ -30,19 +96,43 @@ impl_downcast!(Tab);
 
 pub(crate) struct Tabs {
     tabs: IndexMap<String, Box<dyn Tab>>,
+    /// The tabs that are actually shown, in order, as positions within tabs.
+    /// Hidden tabs still exist, so that they can keep track of things in the
+    /// background, they just can't be switched to.
+    shown: Vec<usize>,
     index: usize,
 }
 
 impl Tabs {
-    fn new(tabs: Vec<Box<dyn Tab>>) -> Tabs {
+    fn new(tabs: Vec<Box<dyn Tab>>, shown: &[String]) -> Tabs {
+        let tabs: IndexMap<String, Box<dyn Tab>> =
+            tabs.into_iter().map(|t| (t.id().to_string(), t)).collect();
+
+        let mut order: Vec<usize> = Vec::new();
+        for id in shown.iter() {
+            match tabs.get_index_of(id.as_str()) {
+                Some(idx) if !order.contains(&idx) => order.push(idx),
+                Some(_) => {}
+                None => warn!("ignoring unknown tab {:?} in ui config", id),
+            }
+        }
+        if order.is_empty() {
+            order = (0..tabs.len()).collect();
+        }
+
         Tabs {
-            tabs: tabs.into_iter().map(|t| (t.id().to_string(), t)).collect(),
+            tabs,
+            shown: order,
             index: 0,
         }
     }
 
     pub(crate) fn titles(&self) -> Vec<&str> {
-        self.tabs.values().map(|t| t.title()).collect()
+        self.shown
+            .iter()
+            .filter_map(|idx| self.tabs.get_index(*idx))
+            .map(|(_, t)| t.title())
+            .collect()
     }
 
     pub(crate) fn index(&self) -> usize {
@@ -51,7 +141,7 @@ impl Tabs {
 
     pub(crate) fn next(&mut self) {
         self.index += 1;
-        if self.index >= self.tabs.len() {
+        if self.index >= self.shown.len() {
             self.index = 0;
         }
     }
@@ -60,16 +150,27 @@ impl Tabs {
         if self.index > 0 {
             self.index -= 1;
         } else {
-            self.index = self.tabs.len() - 1;
+            self.index = self.shown.len() - 1;
+        }
+    }
+
+    /// Switches to the given tab, if it's one that's being shown.
+    pub(crate) fn select(&mut self, id: &str) {
+        if let Some(index) = self
+            .tabs
+            .get_index_of(id)
+            .and_then(|idx| self.shown.iter().position(|s| *s == idx))
+        {
+            self.index = index;
         }
     }
 
     pub(crate) fn current(&self) -> &dyn Tab {
         &**self
             .tabs
-            .values()
-            .nth(self.index)
+            .get_index(self.shown[self.index])
             .expect("no tab for index")
+            .1
     }
 
     pub(crate) fn tab<T: Tab>(&self, id: &str) -> Option<&T> {
@@ -83,21 +184,56 @@ impl Tabs {
 pub(crate) struct App {
     title: String,
     finished: bool,
+    // Set while we're asking the user whether they really want to quit.
+    confirming_quit: bool,
+    big_text: bool,
+    alerts: Alerts,
+    // Set when the last pass over Comrade's events stopped short of taking
+    // all of them.
+    backlogged: bool,
+    // Where each character's events are up to, to notice any that were
+    // dropped before they reached us.
+    streams: EventStreams,
     tabs: Tabs,
-    comrade: Comrade,
+    ui: UiConfig,
+    // How many trigger sources have been loaded out of how many there are,
+    // while they're still loading.
+    loading: Option<(usize, usize)>,
+    // The newer release of Comrade, once we've been told there is one.
+    update: Option<Arc<Release>>,
+    // How the characters measure up against the guild's required triggers,
+    // if a manifest of them has been imported.
+    compliance: Option<ComplianceReport>,
+    comrade: Arc<Comrade>,
 }
 
 impl App {
-    pub(crate) fn new<T: Into<String>>(title: T, comrade: Comrade) -> App {
+    pub(crate) fn new<T: Into<String>>(title: T, comrade: Arc<Comrade>) -> App {
+        let ui = comrade.ui();
+
         App {
             title: title.into(),
             finished: false,
-            tabs: Tabs::new(vec![
-                EventsTab::init("Events"),
-                ConfigTab::init("Config"),
-                LogsTab::init("Logs"),
-                DebugTab::init("Debug"),
-            ]),
+            confirming_quit: false,
+            big_text: false,
+            alerts: Alerts::default(),
+            backlogged: false,
+            streams: EventStreams::new(),
+            tabs: Tabs::new(
+                vec![
+                    EventsTab::init("Events", ui.events.retention),
+                    TriggersTab::init("Triggers"),
+                    SourcesTab::init("Sources"),
+                    ConfigTab::init("Config"),
+                    LogsTab::init("Logs"),
+                    DebugTab::init("Debug"),
+                ],
+                &ui.tabs,
+            ),
+            ui,
+            loading: None,
+            update: None,
+            compliance: None,
             comrade,
         }
     }
@@ -105,20 +241,69 @@ impl App {
     pub(crate) fn run(&mut self, term: &mut ComradeTerminal, tick_rate: Duration) -> Result<()> {
         self.on_start()?;
 
+        // We only redraw when something has changed, and even then no more
+        // often than the frame rate allows, so that when a raid is filling
+        // up the logs our time goes into keeping up with them rather than
+        // into drawing frames that are immediately replaced.
+        let mut dirty = true;
+        let mut last_draw = Instant::now();
+        let mut next_frame = Instant::now();
         let mut last_tick = Instant::now();
         while !self.finished {
-            term.draw(|f| ui::draw(f, self))
-                .map_err(TerminalError::IOError)?;
+            let now = Instant::now();
+            if self.animating() && now >= last_draw + ANIMATION_FRAME_RATE {
+                dirty = true;
+            }
+            if dirty && now >= next_frame {
+                term.draw(|f| ui::draw(f, self))
+                    .map_err(TerminalError::IOError)?;
+                dirty = false;
+                last_draw = now;
+                next_frame = now + FRAME_INTERVAL;
+            }
 
-            let timeout = tick_rate
+            // Comrade doesn't have a way to wake us up when it has events
+            // for us, so we never wait on the terminal for so long that they
+            // would back up.
+            let mut timeout = tick_rate
                 .checked_sub(last_tick.elapsed())
-                .unwrap_or_else(|| Duration::from_secs(0));
+                .unwrap_or_else(|| Duration::from_secs(0))
+                .min(EVENT_POLL_INTERVAL);
+            if self.backlogged {
+                // There's still a backlog to work through, so we only check
+                // for input rather than waiting on it.
+                timeout = Duration::ZERO;
+            } else if dirty {
+                timeout = timeout.min(next_frame.saturating_duration_since(Instant::now()));
+            } else if self.animating() {
+                timeout = timeout.min(
+                    (last_draw + ANIMATION_FRAME_RATE).saturating_duration_since(Instant::now()),
+                );
+            }
+
             if event::poll(timeout).map_err(TerminalError::IOError)? {
-                self.on_event(event::read().map_err(TerminalError::IOError)?)?;
+                // Handle every key press that's waiting, not just the first,
+                // so that input doesn't lag behind while we're busy.
+                loop {
+                    self.on_event(event::read().map_err(TerminalError::IOError)?)?;
+                    if self.finished
+                        || !event::poll(Duration::ZERO).map_err(TerminalError::IOError)?
+                    {
+                        break;
+                    }
+                }
+                dirty = true;
+            }
+
+            if self.drain_events() {
+                dirty = true;
             }
 
             if last_tick.elapsed() >= tick_rate {
-                self.on_tick();
+                // Even when nothing has happened, things like the logs and
+                // how long ago an alert happened keep changing, so we redraw
+                // at least once a tick.
+                dirty = true;
                 last_tick = Instant::now();
             }
         }
@@ -137,59 +322,276 @@ impl App {
     pub(crate) fn tabs(&self) -> &Tabs {
         &self.tabs
     }
+
+    pub(crate) fn comrade(&self) -> &Comrade {
+        &self.comrade
+    }
+
+    /// Whether the whole screen is given over to showing alerts in big text.
+    pub(crate) fn big_text(&self) -> bool {
+        self.big_text
+    }
+
+    pub(crate) fn set_big_text(&mut self, big_text: bool) {
+        self.big_text = big_text;
+    }
+
+    /// Whether we're waiting on the user to confirm that they want to quit.
+    pub(crate) fn confirming_quit(&self) -> bool {
+        self.confirming_quit
+    }
+
+    pub(crate) fn ui(&self) -> &UiConfig {
+        &self.ui
+    }
+
+    pub(crate) fn alerts(&self) -> &Alerts {
+        &self.alerts
+    }
+
+    /// How many trigger sources have been loaded out of how many there are,
+    /// while they're still loading.
+    pub(crate) fn loading(&self) -> Option<(usize, usize)> {
+        self.loading
+    }
+
+    /// The newer release of Comrade, if there is one.
+    pub(crate) fn update(&self) -> Option<&Release> {
+        self.update.as_deref()
+    }
+
+    pub(crate) fn compliance(&self) -> Option<&ComplianceReport> {
+        self.compliance.as_ref()
+    }
 }
 
 impl App {
+    fn animating(&self) -> bool {
+        let tab: &EventsTab = self.tabs.tab("events").expect("could not find events tab");
+        tab.has_timers() || self.alerts.active()
+    }
+
     fn quit(&mut self) {
         self.finished = true;
     }
 
+    /// Quits, unless there are timers that would be lost by doing so, in
+    /// which case we ask first. Asking to quit a second time means yes.
+    fn request_quit(&mut self) {
+        let tab: &EventsTab = self.tabs.tab("events").expect("could not find events tab");
+        if self.confirming_quit || !tab.has_timers() {
+            self.quit();
+        } else {
+            self.confirming_quit = true;
+        }
+    }
+
+    /// Switches to the next group of characters, in order of their names,
+    /// and from the last one back to no group at all.
+    fn next_group(&mut self) {
+        let groups: Vec<String> = self
+            .comrade
+            .groups()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        let next = match self.comrade.active_group() {
+            Some(active) => groups.iter().skip_while(|g| **g != active).nth(1).cloned(),
+            None => groups.first().cloned(),
+        };
+
+        match self.comrade.switch_group(next.as_deref()) {
+            Ok(()) => info!(
+                "switched to {}",
+                next.map_or("every character".to_string(), |g| format!("group {}", g))
+            ),
+            Err(e) => error!("could not switch groups: {}", describe_error(&e)),
+        }
+    }
+
     fn on_start(&mut self) -> Result<()> {
+        self.restore_state();
+
         self.comrade.init()?;
         self.comrade.start()?;
+        #[cfg(feature = "dashboard")]
+        if let Err(e) = self.comrade.start_dashboard() {
+            error!("could not start the dashboard: {}", describe_error(&e));
+        }
+
+        let comrade = self.comrade.clone();
+        self.loading = Some((0, 0));
+        thread::spawn(move || {
+            if let Err(e) = comrade.load_triggers() {
+                error!("could not load triggers: {}", describe_error(&e));
+            }
+        });
+
+        let comrade = self.comrade.clone();
+        thread::spawn(move || {
+            if let Err(e) = comrade.check_for_updates() {
+                warn!("could not check for updates: {}", describe_error(&e));
+            }
+        });
 
         Ok(())
     }
 
+    /// Checks the required triggers again, which has to happen whenever the
+    /// triggers, or which of them are on, could have changed.
+    fn refresh_compliance(&mut self) {
+        self.compliance = match self.comrade.compliance() {
+            Ok(report) => report,
+            Err(e) => {
+                warn!("could not check required triggers: {}", describe_error(&e));
+                None
+            }
+        };
+    }
+
     fn on_end(&mut self) -> Result<()> {
         self.comrade.stop()?;
+        if !self.comrade.is_read_only() {
+            self.save_state()?;
+        }
+
+        Ok(())
+    }
+
+    fn restore_state(&mut self) {
+        let state = UiState::load(state_file(self.comrade.data_dir().as_path()).as_path());
+
+        if let Some(tab) = state.tab {
+            self.tabs.select(tab.as_str());
+        }
+
+        let events: &EventsTab = self.tabs.tab("events").expect("could not find events tab");
+        events.set_timer_view(state.timers);
+        events.set_split(state.events_split);
+
+        let triggers: &TriggersTab = self
+            .tabs
+            .tab("triggers")
+            .expect("could not find triggers tab");
+        triggers.set_query(state.triggers_query);
+
+        let logs: &LogsTab = self.tabs.tab("logs").expect("could not find logs tab");
+        logs.set_hidden(state.logs_hidden);
+    }
+
+    fn save_state(&self) -> Result<()> {
+        // Another comrade started since this one owns the saved state now,
+        // and shouldn't have it overwritten by whichever of us quits last.
+        let _lock = match self.comrade.lock_data() {
+            Some(lock) => lock,
+            None => {
+                warn!("another comrade has been started, not saving the ui's state");
+                return Ok(());
+            }
+        };
+
+        let events: &EventsTab = self.tabs.tab("events").expect("could not find events tab");
+        let triggers: &TriggersTab = self
+            .tabs
+            .tab("triggers")
+            .expect("could not find triggers tab");
+        let logs: &LogsTab = self.tabs.tab("logs").expect("could not find logs tab");
+
+        let state = UiState {
+            tab: Some(self.tabs.current().id().to_string()),
+            triggers_query: triggers.query(),
+            timers: events.timer_view().clone(),
+            events_split: events.split(),
+            logs_hidden: logs.hidden(),
+        };
+        state.save(state_file(self.comrade.data_dir().as_path()).as_path())?;
 
         Ok(())
     }
 
-    fn on_tick(&mut self) {
-        let tab: &EventsTab = self
-            .tabs()
-            .tab("events")
-            .expect("could not find events tab");
-
-        // In theory if there is a constant stream of events, then we will
-        // never finish this tick, which we don't want to happen because
-        // we only render the UI between ticks. Thus we'll only read so many
-        // of these before we bail out and let the next set happen.
-        //
-        // Another possible solution to this would be to spin this off into
-        // it's own thread, and have it update our data structures, and remove
-        // the concept of ticks in the UI all together. However for ease of
-        // implementation we're going with the tick approach for now.
-        let mut processed = 0;
-        while let Some(event) = self.comrade.event() {
+    /// Handles the events that Comrade has for us, returning whether there
+    /// were any.
+    ///
+    /// Only so many of them are handled each pass, so that a burst of events
+    /// can't keep us from getting back around to drawing or handling input.
+    /// The rest stay in Comrade's channel for the next pass, rather than
+    /// piling up here, so that once it's full Comrade is held up instead of
+    /// us running out of memory.
+    fn drain_events(&mut self) -> bool {
+        let tab: &EventsTab = self.tabs.tab("events").expect("could not find events tab");
+        let mut count = 0;
+        let mut loaded = false;
+        while count < MAX_EVENTS_PER_PASS {
+            let event = match self.comrade.event() {
+                Some(event) => event,
+                None => break,
+            };
+            count += 1;
             debug!("received event: {:?}", event);
 
-            tab.event(event);
+            if let Continuity::Missed(missed) = self.streams.observe(&event) {
+                let from = event.kind().character().map(|c| c.display_name());
+                warn!(
+                    "missed {} events from {}",
+                    missed,
+                    from.unwrap_or("Comrade")
+                );
+            }
 
-            processed += 1;
-            if processed > 1000 {
-                break;
+            match event.kind() {
+                EventKind::LoadingProgress {
+                    loaded: n, total, ..
+                } => {
+                    self.loading = (n < total).then_some((*n, *total));
+                    loaded |= n >= total;
+                }
+                EventKind::UpdateAvailable { release } => self.update = Some(release.clone()),
+                _ => {}
             }
+            self.alerts.event(&event);
+            tab.event(event);
         }
+
+        self.backlogged = count == MAX_EVENTS_PER_PASS;
+        if loaded {
+            self.refresh_compliance();
+        }
+
+        count > 0
     }
 
     fn on_event(&mut self, event: event::Event) -> Result<()> {
         if let event::Event::Key(key) = event {
+            // Nothing else gets to see key presses while we're waiting on the
+            // user to tell us whether they meant to quit.
+            if self.confirming_quit {
+                match (key.modifiers, key.code) {
+                    (KeyModifiers::CONTROL, KeyCode::Char('c' | 'q')) => self.request_quit(),
+                    (_, KeyCode::Char('y' | 'Y') | KeyCode::Enter) => self.quit(),
+                    (_, KeyCode::Char('n' | 'N') | KeyCode::Esc) => self.confirming_quit = false,
+                    _ => {}
+                }
+                return Ok(());
+            }
+
             match (key.modifiers, key.code) {
-                (KeyModifiers::CONTROL, KeyCode::Char('c')) => self.quit(),
-                (KeyModifiers::CONTROL, KeyCode::Char('q')) => self.quit(),
+                (KeyModifiers::CONTROL, KeyCode::Char('c')) => self.request_quit(),
+                (KeyModifiers::CONTROL, KeyCode::Char('q')) => self.request_quit(),
+                (KeyModifiers::CONTROL, KeyCode::Char('b')) => self.big_text = !self.big_text,
+                (KeyModifiers::CONTROL, KeyCode::Char('a')) => {
+                    if let Some(alert) = self.alerts.acknowledge() {
+                        self.comrade.acknowledge(alert);
+                    }
+                }
+                (_, KeyCode::F(5)) => self.comrade.update_audio(|a| a.toggle_muted()),
+                (_, KeyCode::F(6)) => self.comrade.update_audio(|a| a.toggle_speech_muted()),
+                (_, KeyCode::F(7)) => {
+                    self.comrade.update_audio(|a| a.adjust_volume(-VOLUME_STEP));
+                }
+                (_, KeyCode::F(8)) => {
+                    self.comrade.update_audio(|a| a.adjust_volume(VOLUME_STEP));
+                }
+                (_, KeyCode::F(9)) => self.next_group(),
                 (KeyModifiers::CONTROL, KeyCode::Right) => self.tabs.next(),
                 (KeyModifiers::CONTROL, KeyCode::Left) => self.tabs.previous(),
                 _ => {}
@@ -197,8 +599,334 @@ impl App {
         }
 
         // Our current tab needs to be able to respond to any events as well.
-        self.tabs.current().on_event(event)?;
+        if let Some(command) = self.tabs.current().on_event(event)? {
+            self.on_command(command);
+        }
 
         Ok(())
     }
+
+    fn on_command(&mut self, command: AppCommand) {
+        match command {
+            AppCommand::Run(line) => self.run_command_line(line.as_str()),
+            AppCommand::NewTriggerFromLine(line) => {
+                self.tabs.select("triggers");
+                let tab: &TriggersTab = self
+                    .tabs
+                    .tab("triggers")
+                    .expect("could not find triggers tab");
+                tab.open_editor(TriggerDraft::from_line(line.as_str()));
+            }
+            AppCommand::AddTrigger(id, trigger) => {
+                let tab: &TriggersTab = self
+                    .tabs
+                    .tab("triggers")
+                    .expect("could not find triggers tab");
+                match self.comrade.add_trigger(id, trigger) {
+                    Ok(()) => tab.close_editor(),
+                    Err(e) => tab.set_editor_error(describe_error(&e)),
+                }
+            }
+            AppCommand::OpenImport => {
+                let tab: &TriggersTab = self
+                    .tabs
+                    .tab("triggers")
+                    .expect("could not find triggers tab");
+                tab.open_import(ImportWizard::new(self.comrade.characters()));
+            }
+            AppCommand::ImportTriggers(triggers, characters) => {
+                let tab: &TriggersTab = self
+                    .tabs
+                    .tab("triggers")
+                    .expect("could not find triggers tab");
+                match self.comrade.import_triggers(&triggers, &characters) {
+                    Ok(()) => {
+                        tab.close_import();
+                        tab.set_status(format!(
+                            "imported {} triggers, enabled for {} characters",
+                            triggers.len(),
+                            characters.len()
+                        ));
+                    }
+                    Err(e) => tab.set_import_error(describe_error(&e)),
+                }
+            }
+            AppCommand::SetTriggersEnabled(trefs, enabled) => {
+                let result = self.comrade.set_triggers_enabled(&trefs, &[], enabled);
+                let verb = if enabled { "enabled" } else { "disabled" };
+                self.set_triggers_status(result.map(|()| match trefs.as_slice() {
+                    [tref] => format!("{} {}", verb, tref),
+                    _ => format!("{} {} triggers", verb, trefs.len()),
+                }));
+            }
+            AppCommand::SnoozeTrigger(tref, duration) => {
+                let result = self.snooze_trigger(&tref, duration);
+                self.set_snooze_status(result);
+            }
+            AppCommand::WakeTrigger(tref) => {
+                let status = self.wake_trigger(&tref);
+                self.set_snooze_status(Ok(status));
+            }
+            AppCommand::RemoveTrigger(tref) => {
+                let result = self.comrade.remove_trigger(&tref);
+                self.set_triggers_status(result.map(|()| format!("removed {}", tref)));
+            }
+            AppCommand::SyncSource(name) => {
+                let result = self.comrade.sync_source(name.as_str());
+                self.set_sources_status(
+                    result.map(|count| format!("synced remote:{}, {} triggers", name, count)),
+                );
+            }
+            AppCommand::SetSourceDisabled(name, disabled) => {
+                let result = self.comrade.set_source_disabled(name.as_str(), disabled);
+                let verb = if disabled { "disabled" } else { "enabled" };
+                self.set_sources_status(result.map(|()| format!("{} remote:{}", verb, name)));
+            }
+            AppCommand::RemoveSource(name) => {
+                let result = self.comrade.remove_source(name.as_str());
+                self.set_sources_status(result.map(|()| format!("removed remote:{}", name)));
+            }
+            AppCommand::Undo => {
+                let result = self.comrade.undo();
+                self.set_edit_status(result.map(|undone| match undone {
+                    Some(description) => format!("undid: {}", description),
+                    None => "nothing to undo".to_string(),
+                }));
+            }
+            AppCommand::Redo => {
+                let result = self.comrade.redo();
+                self.set_edit_status(result.map(|redone| match redone {
+                    Some(description) => format!("redid: {}", description),
+                    None => "nothing to redo".to_string(),
+                }));
+            }
+        }
+
+        self.refresh_compliance();
+    }
+
+    fn run_command_line(&mut self, line: &str) {
+        let tab: &EventsTab = self.tabs.tab("events").expect("could not find events tab");
+
+        let (command, args) = line.trim().split_once(' ').unwrap_or((line.trim(), ""));
+        let status = match command {
+            "" => return,
+            "timer" => match args.parse::<ManualTimer>() {
+                Ok(timer) => {
+                    let status = format!(
+                        "started timer {:?} for {}",
+                        timer.text,
+                        format_duration(timer.duration)
+                    );
+                    self.comrade.start_timer(timer);
+                    status
+                }
+                Err(e) => format!("error: {}", e),
+            },
+            "waypoint" => {
+                let name = args.trim();
+                let waypoints: Vec<String> = self
+                    .comrade
+                    .waypoints()
+                    .into_iter()
+                    .filter(|(_, w)| name.is_empty() || w.name == name)
+                    .map(|(id, w)| format!("{} for {}: {}", w.name, id, w.position))
+                    .collect();
+                if waypoints.is_empty() {
+                    "no waypoints have been recorded".to_string()
+                } else {
+                    waypoints.join("; ")
+                }
+            }
+            "snooze" => {
+                let (duration, target) = args.trim().split_once(' ').unwrap_or((args.trim(), ""));
+                let tref = match target.trim() {
+                    "" => tab.selected_ref().ok_or(None),
+                    target => target
+                        .parse::<TriggerRef>()
+                        .map_err(|e| Some(e.to_string())),
+                };
+                let duration = match duration {
+                    "" => Ok(DEFAULT_SNOOZE),
+                    duration => parse_duration(duration).map_err(|e| e.to_string()),
+                };
+                match (tref, duration) {
+                    (Ok(tref), Ok(duration)) => match self.snooze_trigger(&tref, duration) {
+                        Ok(status) => status,
+                        Err(e) => format!("error: {}", describe_error(&e)),
+                    },
+                    (Err(None), _) => "error: select a match, or name a trigger".to_string(),
+                    (Err(Some(e)), _) | (_, Err(e)) => format!("error: {}", e),
+                }
+            }
+            "wake" => match args.trim() {
+                "" => match tab.selected_ref() {
+                    Some(tref) => self.wake_trigger(&tref),
+                    None => "error: select a match, or name a trigger".to_string(),
+                },
+                target => match target.parse::<TriggerRef>() {
+                    Ok(tref) => self.wake_trigger(&tref),
+                    Err(e) => format!("error: {}", e),
+                },
+            },
+            "export" if self.comrade.is_read_only() => {
+                "error: exports are off in read-only mode".to_string()
+            }
+            "export" => {
+                let data_dir = self.comrade.data_dir();
+                let args = args.trim();
+                let result = match args.split_once(' ').unwrap_or((args, "")) {
+                    ("fights", format) => {
+                        let rows = FightRow::rows(&self.comrade.fights());
+                        format.trim().parse::<ExportFormat>().and_then(|format| {
+                            export(data_dir.as_path(), "fights", &rows, format)
+                                .map(|f| (rows.len(), "fight rows", f))
+                        })
+                    }
+                    ("attendance", format) => {
+                        let rows = AttendanceRow::rows(&self.comrade.attendance());
+                        format.trim().parse::<ExportFormat>().and_then(|format| {
+                            export(data_dir.as_path(), "attendance", &rows, format)
+                                .map(|f| (rows.len(), "players", f))
+                        })
+                    }
+                    ("tradeskills", format) => {
+                        let rows = RecipeRow::rows(&self.comrade.recipes());
+                        format.trim().parse::<ExportFormat>().and_then(|format| {
+                            export(data_dir.as_path(), "tradeskills", &rows, format)
+                                .map(|f| (rows.len(), "recipes", f))
+                        })
+                    }
+                    ("resists", format) => {
+                        let rows = ResistRow::rows(&self.comrade.resists());
+                        format.trim().parse::<ExportFormat>().and_then(|format| {
+                            export(data_dir.as_path(), "resists", &rows, format)
+                                .map(|f| (rows.len(), "spells", f))
+                        })
+                    }
+                    _ => {
+                        let matches = tab.matches();
+                        args.parse::<ExportFormat>().and_then(|format| {
+                            export(data_dir.as_path(), "events", &matches, format)
+                                .map(|f| (matches.len(), "matches", f))
+                        })
+                    }
+                };
+                match result {
+                    Ok((count, what, filename)) => {
+                        format!("exported {} {} to {}", count, what, filename.display())
+                    }
+                    Err(e) => format!("error: {}", describe_error(&e)),
+                }
+            }
+            "profile" => match args.trim() {
+                "" => {
+                    let profiles = self.comrade.profiles();
+                    format!(
+                        "using {}, profiles: {}",
+                        self.comrade.profile().as_deref().unwrap_or(MAIN_PROFILE),
+                        if profiles.is_empty() {
+                            "none".to_string()
+                        } else {
+                            profiles.join(", ")
+                        }
+                    )
+                }
+                MAIN_PROFILE => self.switch_profile(None),
+                name => self.switch_profile(Some(name)),
+            },
+            _ => format!("unknown command /{}", command),
+        };
+
+        let tab: &EventsTab = self.tabs.tab("events").expect("could not find events tab");
+        tab.set_status(status);
+    }
+
+    /// Switches to another profile, or back to the main configuration, with
+    /// the ui's state saved to the old profile's data directory and restored
+    /// from the new one's.
+    fn switch_profile(&mut self, profile: Option<&str>) -> String {
+        if !self.comrade.is_read_only() {
+            if let Err(e) = self.save_state() {
+                warn!("could not save the ui's state: {}", describe_error(&e));
+            }
+        }
+
+        match self.comrade.switch_profile(profile) {
+            Ok(()) => {
+                self.ui = self.comrade.ui();
+                self.restore_state();
+                self.refresh_compliance();
+                format!("switched to {}", profile.unwrap_or(MAIN_PROFILE))
+            }
+            Err(e) => format!("error: {}", describe_error(&e)),
+        }
+    }
+
+    fn snooze_trigger(
+        &self,
+        tref: &TriggerRef,
+        duration: Duration,
+    ) -> core::result::Result<String, ComradeError> {
+        self.comrade.snooze_trigger(tref, duration)?;
+        Ok(format!(
+            "snoozed {} for {}",
+            tref,
+            format_duration(duration)
+        ))
+    }
+
+    fn wake_trigger(&self, tref: &TriggerRef) -> String {
+        if self.comrade.wake_trigger(tref) {
+            format!("woke up {}", tref)
+        } else {
+            format!("{} is not snoozed", tref)
+        }
+    }
+
+    /// Triggers can be snoozed from either the events or the triggers tab,
+    /// so like undo and redo, the result goes back to whichever one it was.
+    fn set_snooze_status(&self, result: core::result::Result<String, ComradeError>) {
+        if self.tabs.current().id() == "events" {
+            let tab: &EventsTab = self.tabs.tab("events").expect("could not find events tab");
+            match result {
+                Ok(status) => tab.set_status(status),
+                Err(e) => tab.set_status(format!("error: {}", describe_error(&e))),
+            }
+        } else {
+            self.set_triggers_status(result);
+        }
+    }
+
+    fn set_sources_status(&self, result: core::result::Result<String, ComradeError>) {
+        let tab: &SourcesTab = self
+            .tabs
+            .tab("sources")
+            .expect("could not find sources tab");
+        match result {
+            Ok(status) => tab.set_status(status),
+            Err(e) => tab.set_status(format!("error: {}", describe_error(&e))),
+        }
+    }
+
+    /// Undo and redo can happen from either the triggers or the sources tab,
+    /// so the result goes to whichever one of them it came from.
+    fn set_edit_status(&self, result: core::result::Result<String, ComradeError>) {
+        if self.tabs.current().id() == "sources" {
+            self.set_sources_status(result);
+        } else {
+            self.set_triggers_status(result);
+        }
+    }
+
+    fn set_triggers_status(&self, result: core::result::Result<String, ComradeError>) {
+        let tab: &TriggersTab = self
+            .tabs
+            .tab("triggers")
+            .expect("could not find triggers tab");
+        match result {
+            Ok(status) => tab.set_status(status),
+            Err(e) => tab.set_status(format!("error: {}", describe_error(&e))),
+        }
+    }
 }
@@ -0,0 +1,172 @@
+//! UI Snapshots
+//!
+//! Draws the UI onto tui's `TestBackend`, after replaying a pull through
+//! Comrade's demo configuration, and compares what was drawn against the
+//! snapshots in `snapshots/`, so that a timer bar that's been cut short or
+//! a table that's lost a column turns up in `cargo test`, rather than in the
+//! middle of a raid. The lines go through the same triggers and driver as
+//! real ones do, and the clock is stopped while they're replayed, so the
+//! frames come out the same every time. The driver still reports countdowns
+//! by its own clock, a moment after the stopped one, but what's left of a
+//! countdown is never drawn as more than all of it, so they're all drawn as
+//! having just started. Only the text of each frame is compared, not its
+//! colors.
+//!
+//! A snapshot that doesn't exist yet is written out, to be looked over and
+//! committed along with whatever changed the UI, except in CI, where that's
+//! a failure. Setting `UPDATE_SNAPSHOTS` writes them all out again, for when
+//! the UI was meant to change.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tui::backend::TestBackend;
+use tui::buffer::Buffer;
+use tui::Terminal;
+
+use comrade::{CharacterId, Comrade, DEMO_CHARACTER};
+
+use crate::app::{App, EventsTab};
+use crate::clock;
+use crate::ui;
+
+/// A pull, like the one demo mode makes up, but all at once.
+const PULL: &[&str] = &[
+    "[Sat Oct 17 20:15:00 2026] You feel the spirit of wolf enter you.",
+    "[Sat Oct 17 20:15:02 2026] Xanthe tells you, 'Pulling in 10, get ready.'",
+    "[Sat Oct 17 20:15:06 2026] You slash a fire drake for 312 points of damage.",
+    "[Sat Oct 17 20:15:07 2026] Xanthe hits a fire drake for 254 points of damage.",
+    "[Sat Oct 17 20:15:08 2026] A fire drake draws a deep breath, flames flicker between its teeth.",
+    "[Sat Oct 17 20:15:10 2026] A fire drake has become ENRAGED.",
+    "[Sat Oct 17 20:15:12 2026] You have slain a fire drake!",
+    "[Sat Oct 17 20:15:16 2026] Xanthe tells you, 'Nice, on to the next one.'",
+];
+
+/// Just long enough for a buff to land, without any alerts to acknowledge
+/// covering up its timer.
+const BUFFED: &[&str] = &[
+    "[Sat Oct 17 20:15:00 2026] You feel the spirit of wolf enter you.",
+    "[Sat Oct 17 20:15:16 2026] Xanthe tells you, 'Nice, on to the next one.'",
+];
+
+/// What the last line of the pull displays, which is the last of its events.
+const LAST_MESSAGE: &str = "Tell from Xanthe: Nice, on to the next one.";
+
+/// Replays the lines, and hands every event that comes out of them to a new
+/// App, with the clock stopped from before the first of them.
+fn replay(lines: &[&str]) -> App {
+    clock::freeze();
+
+    let comrade = Arc::new(Comrade::new());
+    comrade.load_demo();
+    comrade.load_triggers().expect("demo triggers should load");
+    let id = CharacterId::new(DEMO_CHARACTER);
+    for line in lines {
+        comrade.process_line(&id, line);
+    }
+
+    // The driver gets to the lines on a thread of its own, but a character's
+    // events always come out in order, so once the last line's message is
+    // in, so is everything before it.
+    let mut app = App::new("Comrade", comrade);
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        while app.drain_events() {}
+
+        let tab: &EventsTab = app.tabs.tab("events").expect("could not find events tab");
+        if tab.messages(None).iter().any(|m| m == LAST_MESSAGE) {
+            break;
+        }
+
+        assert!(Instant::now() < deadline, "the replay never finished");
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    app
+}
+
+fn draw(app: &mut App, width: u16, height: u16) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    terminal.draw(|f| ui::draw(f, app)).unwrap();
+
+    text(terminal.backend().buffer())
+}
+
+/// The text of each line of the buffer, without trailing spaces.
+fn text(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut text = String::new();
+    for y in area.top()..area.bottom() {
+        let line: String = (area.left()..area.right())
+            .map(|x| buffer.get(x, y).symbol.as_str())
+            .collect();
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+
+    text
+}
+
+fn assert_snapshot(name: &str, actual: &str) {
+    let filename = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/app/snapshots")
+        .join(format!("{}.txt", name));
+
+    let recorded = match fs::read_to_string(filename.as_path()) {
+        Ok(_) if env::var_os("UPDATE_SNAPSHOTS").is_some() => None,
+        Ok(expected) => Some(expected),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            assert!(
+                env::var_os("CI").is_none(),
+                "there's no snapshot at {}, run the tests to record it",
+                filename.display()
+            );
+            None
+        }
+        Err(e) => panic!("could not read {}: {}", filename.display(), e),
+    };
+
+    match recorded {
+        Some(expected) => assert!(
+            expected == actual,
+            "{} no longer matches, run the tests with UPDATE_SNAPSHOTS=1 if it's meant to\n\nexpected:\n{}\nactual:\n{}",
+            filename.display(),
+            expected,
+            actual
+        ),
+        None => {
+            fs::create_dir_all(filename.parent().unwrap()).unwrap();
+            fs::write(filename.as_path(), actual).unwrap();
+        }
+    }
+}
+
+#[test]
+fn events_tab() {
+    let mut app = replay(PULL);
+    assert_snapshot("events_tab", draw(&mut app, 120, 40).as_str());
+}
+
+#[test]
+fn events_tab_narrow() {
+    let mut app = replay(PULL);
+    assert_snapshot("events_tab_narrow", draw(&mut app, 60, 24).as_str());
+}
+
+#[test]
+fn big_text() {
+    let mut app = replay(PULL);
+    app.set_big_text(true);
+    assert_snapshot("big_text", draw(&mut app, 120, 40).as_str());
+}
+
+#[test]
+fn timers() {
+    let mut app = replay(BUFFED);
+    assert_snapshot("timers", draw(&mut app, 120, 40).as_str());
+}
@@ -0,0 +1,58 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::app::timers::TimerView;
+use crate::errors::StateError;
+
+const STATE_FILENAME: &str = "ui-state.json";
+
+pub(crate) fn state_file(data_dir: &Path) -> PathBuf {
+    data_dir.join(STATE_FILENAME)
+}
+
+/// The parts of the UI that are worth bringing back the way the user left
+/// them, the next time that they start it up.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub(crate) struct UiState {
+    pub(crate) tab: Option<String>,
+    pub(crate) triggers_query: String,
+    pub(crate) timers: TimerView,
+    pub(crate) events_split: bool,
+    pub(crate) logs_hidden: bool,
+}
+
+impl UiState {
+    /// Loads the saved state, a missing or broken state file isn't worth
+    /// stopping over, so we just start from scratch instead.
+    pub(crate) fn load(filename: &Path) -> UiState {
+        let contents = match fs::read_to_string(filename) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return UiState::default(),
+            Err(e) => {
+                warn!("could not read {}: {}", filename.display(), e);
+                return UiState::default();
+            }
+        };
+
+        serde_json::from_str(contents.as_str()).unwrap_or_else(|e| {
+            warn!("could not parse {}: {}", filename.display(), e);
+            UiState::default()
+        })
+    }
+
+    pub(crate) fn save(&self, filename: &Path) -> Result<(), StateError> {
+        if let Some(parent) = filename.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let contents = serde_json::to_string_pretty(self)?;
+        fs::write(filename, contents)?;
+
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use crossterm::event;
 
-use crate::app::{Eventable, Result, Tab};
+use crate::app::{AppCommand, Eventable, Result, Tab};
 
 pub(crate) struct ConfigTab {
     title: String,
@@ -15,8 +15,8 @@ impl ConfigTab {
 }
 
 impl Eventable for ConfigTab {
-    fn on_event(&self, _event: event::Event) -> Result<()> {
-        Ok(())
+    fn on_event(&self, _event: event::Event) -> Result<Option<AppCommand>> {
+        Ok(None)
     }
 }
 
@@ -1,41 +1,56 @@
 use crossterm::event;
-use std::cell::RefCell;
-use std::collections::HashMap;
+use crossterm::event::{KeyCode, KeyModifiers};
+use std::cell::{Cell, Ref, RefCell};
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 use comrade::events::{Event, EventKind};
+use comrade::{Broadcast, TriggerRef};
+use humantime::format_duration;
 
-use crate::app::{Eventable, Result, Tab};
+use crate::app::export::Matched;
+use crate::app::timers::{Timer, TimerRow, TimerView};
+use crate::app::{AppCommand, Eventable, Result, Tab, DEFAULT_SNOOZE};
 
-pub(crate) struct Timer {
-    pub(crate) text: Arc<String>,
-    pub(crate) duration: Duration,
-    pub(crate) remaining: Duration,
-}
-
-impl Timer {
-    pub(crate) fn percent(&self) -> u16 {
-        let percent_f = (self.remaining.as_secs() as f64 / self.duration.as_secs() as f64) * 100.0;
-        let percent: u16 = percent_f as u16;
-        percent
-    }
-}
+/// How many messages a single press of PgUp/PgDn scrolls by.
+const SCROLL_PAGE: usize = 10;
 
 pub(crate) struct EventsTab {
     title: String,
-    messages: RefCell<Vec<Arc<String>>>,
-    triggereds: RefCell<Vec<Vec<String>>>,
-    timers: RefCell<HashMap<String, Arc<Timer>>>,
+    // How many messages and matches to keep around, the oldest are dropped
+    // once there are more than this.
+    retention: usize,
+    // Each message along with the name of the character that it's for, and
+    // how many times it's been displayed in a row, newest first.
+    messages: RefCell<VecDeque<(Option<String>, Arc<String>, u32)>>,
+    triggereds: RefCell<VecDeque<Matched>>,
+    broadcasts: RefCell<VecDeque<Arc<Broadcast>>>,
+    // How many of the newest messages are scrolled out of view.
+    scroll: Cell<usize>,
+    selected: Cell<Option<usize>>,
+    timers: RefCell<HashMap<(String, String), Arc<Timer>>>,
+    timer_view: RefCell<TimerView>,
+    split: Cell<bool>,
+    command: RefCell<Option<String>>,
+    status: RefCell<Option<String>>,
 }
 
 impl EventsTab {
-    pub(in crate::app) fn init<T: Into<String>>(title: T) -> Box<dyn Tab> {
+    pub(in crate::app) fn init<T: Into<String>>(title: T, retention: usize) -> Box<dyn Tab> {
         Box::new(EventsTab {
             title: title.into(),
-            messages: RefCell::new(Vec::new()),
-            triggereds: RefCell::new(Vec::new()),
+            retention,
+            messages: RefCell::new(VecDeque::new()),
+            triggereds: RefCell::new(VecDeque::new()),
+            broadcasts: RefCell::new(VecDeque::new()),
+            scroll: Cell::new(0),
+            selected: Cell::new(None),
             timers: RefCell::new(HashMap::new()),
+            timer_view: RefCell::new(TimerView::default()),
+            split: Cell::new(false),
+            command: RefCell::new(None),
+            status: RefCell::new(None),
         })
     }
 
@@ -43,70 +58,427 @@ impl EventsTab {
         match event.kind() {
             EventKind::Triggered {
                 character,
+                tref,
                 trigger,
                 log,
             } => {
                 let mut triggereds = self.triggereds.borrow_mut();
-                triggereds.insert(
-                    0,
-                    vec![
-                        format!("{} ({})", character.name, character.server),
-                        trigger.name.clone(),
-                        log.message().to_string(),
-                    ],
-                );
-                let len = triggereds.len();
-                if len > 100 {
-                    triggereds.drain(100..len);
-                }
-            }
-            EventKind::DisplayText(text) => {
-                let mut messages = self.messages.borrow_mut();
-                messages.insert(0, text.clone());
+                triggereds.push_front(Matched {
+                    time: SystemTime::now(),
+                    character: format!("{} ({})", character.display_name(), character.server),
+                    trigger: trigger.name.clone(),
+                    text: log.message().to_string(),
+                    tref: TriggerRef::clone(tref),
+                });
+                triggereds.truncate(self.retention);
 
-                let len = messages.len();
-                if len > 100 {
-                    messages.drain(100..len);
+                // Keep the selection on the same match, rather than letting
+                // it slide along as new ones come in.
+                if let Some(idx) = self.selected.get() {
+                    self.selected
+                        .set(Some((idx + 1).min(triggereds.len().saturating_sub(1))));
                 }
             }
+            EventKind::DisplayText {
+                text, character, ..
+            } => self.push_message(
+                character.as_ref().map(|c| c.display_name().to_string()),
+                text.clone(),
+            ),
+            EventKind::DisplayTextRepeated {
+                text,
+                character,
+                count,
+                ..
+            } => self.repeat_message(character.display_name(), text, *count),
             EventKind::Countdown {
                 text,
+                template,
                 duration,
                 remaining,
+                category,
+                character,
+                ..
             } => {
                 let mut timers = self.timers.borrow_mut();
                 let timer = Arc::new(Timer {
                     text: text.clone(),
                     duration: *duration,
-                    remaining: *remaining,
+                    ends_at: event.created() + *remaining,
+                    category: category.clone(),
+                    character: character.clone(),
                 });
 
-                timers.insert(timer.text.to_string(), timer);
-                timers.retain(|_k, t| !t.remaining.is_zero());
+                // The text can change from one report to the next, when it
+                // says how long is left, so it's the template that the timer
+                // is known by.
+                timers.insert(
+                    (timer.character_name().to_string(), template.to_string()),
+                    timer,
+                );
+                timers.retain(|_k, t| !t.remaining().is_zero());
+            }
+            EventKind::TimerEnded {
+                template,
+                character,
+                ..
+            } => {
+                let name = character.as_ref().map_or("Manual", |c| c.display_name());
+                self.timers
+                    .borrow_mut()
+                    .remove(&(name.to_string(), template.to_string()));
+            }
+            EventKind::Acknowledged { .. }
+            | EventKind::LocationUpdated { .. }
+            | EventKind::FightStarted { .. }
+            | EventKind::FightEnded { .. }
+            | EventKind::LoadingProgress { .. }
+            | EventKind::UpdateAvailable { .. } => {}
+            EventKind::Lagging { latency, .. } => self.push_message(
+                None,
+                Arc::new(format!(
+                    "Callouts are running {:.1}s behind the logs",
+                    latency.as_secs_f64()
+                )),
+            ),
+            EventKind::ExecutorFailed {
+                executor,
+                error,
+                character,
+                tripped,
+                ..
+            } => self.push_message(
+                Some(character.display_name().to_string()),
+                Arc::new(if *tripped {
+                    format!("{} failed, skipping it for now: {}", executor, error)
+                } else {
+                    format!("{} failed: {}", executor, error)
+                }),
+            ),
+            EventKind::Broadcast { broadcast, .. } => {
+                // Every character in the raid or guild sees the same
+                // broadcast, but it only needs showing the once.
+                let mut broadcasts = self.broadcasts.borrow_mut();
+                if broadcasts.front() != Some(broadcast) {
+                    broadcasts.push_front(broadcast.clone());
+                    broadcasts.truncate(self.retention);
+                }
+            }
+            EventKind::ResistStreak {
+                character,
+                target,
+                spell,
+                streak,
+            } => self.push_message(
+                character.as_ref().map(|c| c.display_name().to_string()),
+                Arc::new(format!(
+                    "{} resisted {} {} times in a row",
+                    target, spell, streak
+                )),
+            ),
+            EventKind::TradeskillSummary { character, session } => {
+                let recipes: Vec<String> = session
+                    .recipes
+                    .iter()
+                    .map(|r| {
+                        format!(
+                            "{} {}/{} ({:.0}%)",
+                            r.recipe,
+                            r.successes,
+                            r.attempts,
+                            r.success_rate()
+                        )
+                    })
+                    .collect();
+                let text = format!(
+                    "Tradeskills: {}, {} skill-ups",
+                    recipes.join(", "),
+                    session.skill_ups()
+                );
+                self.push_message(
+                    character.as_ref().map(|c| c.display_name().to_string()),
+                    Arc::new(text),
+                );
+            }
+            EventKind::EarningsSummary { character, session } => {
+                let text = format!(
+                    "Earned {:.1}pp in {} over {} ({:.1}pp/hour)",
+                    session.platinum(),
+                    session.zone.as_deref().unwrap_or("an unknown zone"),
+                    format_duration(Duration::from_secs(session.duration.as_secs())),
+                    session.platinum_per_hour()
+                );
+                self.push_message(
+                    character.as_ref().map(|c| c.display_name().to_string()),
+                    Arc::new(text),
+                );
             }
         }
     }
 
-    pub(crate) fn messages(&self) -> Vec<String> {
+    fn push_message(&self, character: Option<String>, text: Arc<String>) {
+        self.push_counted_message(character, text, 1);
+    }
+
+    fn push_counted_message(&self, character: Option<String>, text: Arc<String>, count: u32) {
+        let mut messages = self.messages.borrow_mut();
+        messages.push_front((character, text, count));
+        messages.truncate(self.retention);
+
+        // Likewise, when scrolled back keep the same messages in view as new
+        // ones come in.
+        if self.scroll.get() > 0 {
+            self.scroll
+                .set((self.scroll.get() + 1).min(messages.len().saturating_sub(1)));
+        }
+    }
+
+    /// Counts a repeat against the newest matching message, or if it's gone
+    /// from our messages, displays it again with its count.
+    fn repeat_message(&self, character: &str, text: &Arc<String>, count: u32) {
+        if let Some(message) = self
+            .messages
+            .borrow_mut()
+            .iter_mut()
+            .find(|(c, t, _)| c.as_deref() == Some(character) && t == text)
+        {
+            message.2 = count;
+            return;
+        }
+
+        self.push_counted_message(Some(character.to_string()), text.clone(), count);
+    }
+
+    /// The messages for the given character, or for every character if
+    /// there isn't one. Messages that aren't for any character in particular
+    /// are always included. Messages that have been scrolled past are left
+    /// out.
+    pub(crate) fn messages(&self, character: Option<&str>) -> Vec<String> {
         self.messages
             .borrow()
             .iter()
-            .map(|t| t.to_string())
+            .skip(self.scroll.get())
+            .filter(|(c, _, _)| character.is_none() || c.is_none() || c.as_deref() == character)
+            .map(|(_, t, count)| match count {
+                1 => t.to_string(),
+                _ => format!("{} x{}", t, count),
+            })
             .collect()
     }
 
+    /// How far back the messages have been scrolled, along with how many
+    /// messages there are in total.
+    pub(crate) fn scroll(&self) -> (usize, usize) {
+        (self.scroll.get(), self.messages.borrow().len())
+    }
+
+    fn scroll_by(&self, offset: isize) {
+        let max = self.messages.borrow().len().saturating_sub(1);
+        let scroll = (self.scroll.get() as isize + offset).clamp(0, max as isize);
+        self.scroll.set(scroll as usize);
+    }
+
     pub(crate) fn triggereds(&self) -> Vec<Vec<String>> {
+        self.triggereds
+            .borrow()
+            .iter()
+            .map(|m| vec![m.character.clone(), m.trigger.clone(), m.text.clone()])
+            .collect()
+    }
+
+    /// The broadcasts as rows of channel, speaker, and text, newest first.
+    pub(crate) fn broadcasts(&self) -> Vec<Vec<String>> {
+        self.broadcasts
+            .borrow()
+            .iter()
+            .map(|b| vec![b.channel.to_string(), b.speaker.clone(), b.text.clone()])
+            .collect()
+    }
+
+    /// Every match that's still being kept around, newest first.
+    pub(crate) fn matches(&self) -> Vec<Matched> {
         self.triggereds.borrow().iter().cloned().collect()
     }
 
+    pub(crate) fn selected(&self) -> Option<usize> {
+        self.selected.get()
+    }
+
+    /// The trigger behind the selected match, if there is one.
+    pub(crate) fn selected_ref(&self) -> Option<TriggerRef> {
+        let triggereds = self.triggereds.borrow();
+        let idx = self.selected.get()?;
+        triggereds.get(idx).map(|m| m.tref.clone())
+    }
+
+    fn select(&self, offset: isize) {
+        let len = self.triggereds.borrow().len();
+        if len == 0 {
+            return;
+        }
+
+        let selected = match self.selected.get() {
+            Some(idx) => (idx as isize + offset).clamp(0, len as isize - 1) as usize,
+            None => 0,
+        };
+        self.selected.set(Some(selected));
+    }
+
     pub(crate) fn timers(&self) -> Vec<Arc<Timer>> {
-        self.timers.borrow().values().cloned().collect()
+        self.timers
+            .borrow()
+            .values()
+            .filter(|t| !t.remaining().is_zero())
+            .cloned()
+            .collect()
+    }
+
+    pub(crate) fn has_timers(&self) -> bool {
+        self.timers
+            .borrow()
+            .values()
+            .any(|t| !t.remaining().is_zero())
+    }
+
+    /// Returns the timers laid out into rows, grouped into the panes that
+    /// their categories say they belong in. Like messages, these can be
+    /// narrowed down to a single character.
+    pub(crate) fn timer_panes(&self, character: Option<&str>) -> Vec<(String, Vec<TimerRow>)> {
+        let timers = self
+            .timers()
+            .into_iter()
+            .filter(|t| {
+                character.is_none()
+                    || t.character.is_none()
+                    || Some(t.character_name()) == character
+            })
+            .collect();
+        self.timer_view.borrow().panes(timers)
+    }
+
+    /// Whether each character gets their own messages and timers, rather
+    /// than them all being interleaved together.
+    pub(crate) fn split(&self) -> bool {
+        self.split.get()
+    }
+
+    pub(crate) fn set_split(&self, split: bool) {
+        self.split.set(split);
+    }
+
+    pub(crate) fn timer_view(&self) -> Ref<TimerView> {
+        self.timer_view.borrow()
+    }
+
+    pub(crate) fn set_timer_view(&self, view: TimerView) {
+        *self.timer_view.borrow_mut() = view;
+    }
+
+    /// The categories of the current timers, in the order they're displayed,
+    /// so that they can be collapsed by number.
+    fn timer_categories(&self) -> Vec<String> {
+        let mut categories: Vec<(i64, String)> = self
+            .timers()
+            .iter()
+            .filter(|t| t.category.is_some())
+            .map(|t| (t.order(), t.category_name().to_string()))
+            .collect();
+        categories.sort();
+        categories.dedup();
+        categories.into_iter().map(|(_, name)| name).collect()
+    }
+
+    fn on_timer_key(&self, code: KeyCode) -> bool {
+        let mut view = self.timer_view.borrow_mut();
+
+        match code {
+            KeyCode::Char('s') => view.next_sort(),
+            KeyCode::Char('g') => view.toggle_by_character(),
+            KeyCode::Char('c') => view.toggle_all_collapsed(self.timer_categories()),
+            KeyCode::Char('v') => self.split.set(!self.split.get()),
+            KeyCode::Char(c @ '1'..='9') => {
+                let idx = c as usize - '1' as usize;
+                if let Some(category) = self.timer_categories().get(idx) {
+                    view.toggle_collapsed(category.as_str());
+                }
+            }
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// The command currently being typed in, if any.
+    pub(crate) fn command(&self) -> Option<String> {
+        self.command.borrow().clone()
+    }
+
+    pub(crate) fn status(&self) -> Option<String> {
+        self.status.borrow().clone()
+    }
+
+    pub(crate) fn set_status(&self, status: String) {
+        *self.status.borrow_mut() = Some(status);
+    }
+
+    fn on_command_key(&self, code: KeyCode) -> Option<AppCommand> {
+        let mut command = self.command.borrow_mut();
+        let line = command.as_mut()?;
+
+        match code {
+            KeyCode::Char(c) => line.push(c),
+            KeyCode::Backspace if line.is_empty() => *command = None,
+            KeyCode::Backspace => {
+                line.pop();
+            }
+            KeyCode::Esc => *command = None,
+            KeyCode::Enter => return command.take().map(AppCommand::Run),
+            _ => {}
+        }
+
+        None
     }
 }
 
 impl Eventable for EventsTab {
-    fn on_event(&self, _event: event::Event) -> Result<()> {
-        Ok(())
+    fn on_event(&self, event: event::Event) -> Result<Option<AppCommand>> {
+        if let event::Event::Key(key) = event {
+            if key.modifiers != KeyModifiers::NONE && key.modifiers != KeyModifiers::SHIFT {
+                return Ok(None);
+            }
+
+            if self.command.borrow().is_some() {
+                return Ok(self.on_command_key(key.code));
+            }
+
+            if key.modifiers == KeyModifiers::NONE && !self.on_timer_key(key.code) {
+                match key.code {
+                    KeyCode::Char('/') => *self.command.borrow_mut() = Some(String::new()),
+                    KeyCode::Char('x') => return Ok(Some(AppCommand::Run("export".to_string()))),
+                    KeyCode::PageUp => self.scroll_by(SCROLL_PAGE as isize),
+                    KeyCode::PageDown => self.scroll_by(-(SCROLL_PAGE as isize)),
+                    KeyCode::End => self.scroll.set(0),
+                    KeyCode::Up => self.select(-1),
+                    KeyCode::Down => self.select(1),
+                    KeyCode::Esc => self.selected.set(None),
+                    // Make a new trigger from the log line of the selected match.
+                    KeyCode::Char('n') => {
+                        let triggereds = self.triggereds.borrow();
+                        if let Some(row) = self.selected.get().and_then(|idx| triggereds.get(idx)) {
+                            return Ok(Some(AppCommand::NewTriggerFromLine(row.text.clone())));
+                        }
+                    }
+                    KeyCode::Char('z') => {
+                        if let Some(tref) = self.selected_ref() {
+                            return Ok(Some(AppCommand::SnoozeTrigger(tref, DEFAULT_SNOOZE)));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(None)
     }
 }
 
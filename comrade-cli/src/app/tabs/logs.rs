@@ -1,15 +1,18 @@
-use std::cell::{Ref, RefCell};
+use std::cell::{Cell, Ref, RefCell};
 
 use crossterm::event;
 use crossterm::event::{KeyCode, KeyModifiers};
 use tui_logger::{TuiWidgetEvent, TuiWidgetState};
 
-use crate::app::{Eventable, Result, Tab};
+use crate::app::{AppCommand, Eventable, Result, Tab};
 use crate::ui;
 
 pub(crate) struct LogsTab {
     title: String,
     state: RefCell<TuiWidgetState>,
+    // The widget state can't tell us whether the target selector is hidden,
+    // so we keep track of it ourselves in order to be able to restore it.
+    hidden: Cell<bool>,
 }
 
 impl LogsTab {
@@ -17,6 +20,7 @@ impl LogsTab {
         Box::new(LogsTab {
             title: title.into(),
             state: RefCell::new(ui::init_logger_state()),
+            hidden: Cell::new(false),
         })
     }
 
@@ -24,6 +28,21 @@ impl LogsTab {
         self.state.borrow()
     }
 
+    pub(crate) fn hidden(&self) -> bool {
+        self.hidden.get()
+    }
+
+    pub(crate) fn set_hidden(&self, hidden: bool) {
+        if self.hidden.get() != hidden {
+            self.toggle_hidden();
+        }
+    }
+
+    fn toggle_hidden(&self) {
+        self.hidden.set(!self.hidden.get());
+        self.transition(&TuiWidgetEvent::HideKey);
+    }
+
     fn transition(&self, event: &TuiWidgetEvent) {
         let state = &mut *self.state.borrow_mut();
         state.transition(event);
@@ -31,7 +50,7 @@ impl LogsTab {
 }
 
 impl Eventable for LogsTab {
-    fn on_event(&self, event: event::Event) -> Result<()> {
+    fn on_event(&self, event: event::Event) -> Result<Option<AppCommand>> {
         if let event::Event::Key(key) = event {
             if key.modifiers == KeyModifiers::NONE {
                 match key.code {
@@ -47,14 +66,14 @@ impl Eventable for LogsTab {
                         self.transition(&TuiWidgetEvent::PlusKey)
                     }
                     KeyCode::Char('-') => self.transition(&TuiWidgetEvent::MinusKey),
-                    KeyCode::Char('h') => self.transition(&TuiWidgetEvent::HideKey),
+                    KeyCode::Char('h') => self.toggle_hidden(),
                     KeyCode::Char('f') => self.transition(&TuiWidgetEvent::FocusKey),
                     _ => {}
                 }
             }
         }
 
-        Ok(())
+        Ok(None)
     }
 }
 
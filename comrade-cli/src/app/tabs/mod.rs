@@ -2,8 +2,12 @@ pub(crate) use crate::app::tabs::config::ConfigTab;
 pub(crate) use crate::app::tabs::debug::DebugTab;
 pub(crate) use crate::app::tabs::events::EventsTab;
 pub(crate) use crate::app::tabs::logs::LogsTab;
+pub(crate) use crate::app::tabs::sources::SourcesTab;
+pub(crate) use crate::app::tabs::triggers::TriggersTab;
 
 mod config;
 mod debug;
 mod events;
 mod logs;
+mod sources;
+mod triggers;
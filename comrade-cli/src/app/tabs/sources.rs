@@ -0,0 +1,109 @@
+use std::cell::{Cell, RefCell};
+
+use crossterm::event;
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use comrade::{SourceInfo, TriggerSource};
+
+use crate::app::{AppCommand, Eventable, Result, Tab};
+
+pub(crate) struct SourcesTab {
+    title: String,
+    selected: Cell<usize>,
+    sources: RefCell<Vec<SourceInfo>>,
+    status: RefCell<Option<String>>,
+}
+
+impl SourcesTab {
+    pub(in crate::app) fn init<T: Into<String>>(title: T) -> Box<dyn Tab> {
+        Box::new(SourcesTab {
+            title: title.into(),
+            selected: Cell::new(0),
+            sources: RefCell::new(Vec::new()),
+            status: RefCell::new(None),
+        })
+    }
+
+    /// Records which sources are currently being displayed, so that key
+    /// presses can act on them.
+    pub(crate) fn set_sources(&self, sources: Vec<SourceInfo>) {
+        *self.sources.borrow_mut() = sources;
+    }
+
+    pub(crate) fn status(&self) -> Option<String> {
+        self.status.borrow().clone()
+    }
+
+    pub(crate) fn set_status(&self, status: String) {
+        *self.status.borrow_mut() = Some(status);
+    }
+
+    pub(crate) fn selected(&self, len: usize) -> Option<usize> {
+        if len == 0 {
+            return None;
+        }
+
+        let selected = self.selected.get().min(len - 1);
+        self.selected.set(selected);
+        Some(selected)
+    }
+
+    /// The name of the selected source, as long as it's a remote one, since
+    /// the local source can't be synced, disabled, or removed.
+    fn selected_remote(&self) -> Option<(String, bool)> {
+        let sources = self.sources.borrow();
+        let source = &sources[self.selected(sources.len())?];
+        match source.source {
+            TriggerSource::Remote(ref name) => Some((name.clone(), source.disabled)),
+            TriggerSource::Local => {
+                self.set_status("the local source can't be synced, disabled, or removed".into());
+                None
+            }
+        }
+    }
+}
+
+impl Eventable for SourcesTab {
+    fn on_event(&self, event: event::Event) -> Result<Option<AppCommand>> {
+        if let event::Event::Key(key) = event {
+            if key.modifiers != KeyModifiers::NONE && key.modifiers != KeyModifiers::SHIFT {
+                return Ok(None);
+            }
+
+            match key.code {
+                KeyCode::Char('s') => {
+                    return Ok(self
+                        .selected_remote()
+                        .map(|(name, _)| AppCommand::SyncSource(name)))
+                }
+                KeyCode::Char('d') => {
+                    return Ok(self
+                        .selected_remote()
+                        .map(|(name, disabled)| AppCommand::SetSourceDisabled(name, !disabled)))
+                }
+                KeyCode::Delete => {
+                    return Ok(self
+                        .selected_remote()
+                        .map(|(name, _)| AppCommand::RemoveSource(name)))
+                }
+                KeyCode::Char('u') => return Ok(Some(AppCommand::Undo)),
+                KeyCode::Char('r') => return Ok(Some(AppCommand::Redo)),
+                KeyCode::Up => self.selected.set(self.selected.get().saturating_sub(1)),
+                KeyCode::Down => self.selected.set(self.selected.get() + 1),
+                _ => {}
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl Tab for SourcesTab {
+    fn id(&self) -> &str {
+        "sources"
+    }
+
+    fn title(&self) -> &str {
+        self.title.as_str()
+    }
+}
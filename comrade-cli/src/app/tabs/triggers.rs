@@ -0,0 +1,227 @@
+use std::cell::{Cell, Ref, RefCell};
+
+use crossterm::event;
+use crossterm::event::{KeyCode, KeyModifiers};
+
+use comrade::errors::TriggerError;
+use comrade::{TriggerFilter, TriggerRef};
+
+use crate::app::editor::EditorOutcome;
+use crate::app::import::{ImportOutcome, ImportWizard};
+use crate::app::{AppCommand, Eventable, Result, Tab, TriggerDraft, TriggerEditor, DEFAULT_SNOOZE};
+
+pub(crate) struct TriggersTab {
+    title: String,
+    query: RefCell<String>,
+    editing: Cell<bool>,
+    selected: Cell<usize>,
+    editor: RefCell<Option<TriggerEditor>>,
+    import: RefCell<Option<ImportWizard>>,
+    results: RefCell<Vec<TriggerRef>>,
+    status: RefCell<Option<String>>,
+}
+
+impl TriggersTab {
+    pub(in crate::app) fn init<T: Into<String>>(title: T) -> Box<dyn Tab> {
+        Box::new(TriggersTab {
+            title: title.into(),
+            query: RefCell::new(String::new()),
+            editing: Cell::new(false),
+            selected: Cell::new(0),
+            editor: RefCell::new(None),
+            import: RefCell::new(None),
+            results: RefCell::new(Vec::new()),
+            status: RefCell::new(None),
+        })
+    }
+
+    pub(crate) fn editor(&self) -> Ref<Option<TriggerEditor>> {
+        self.editor.borrow()
+    }
+
+    pub(crate) fn open_editor(&self, draft: TriggerDraft) {
+        *self.editor.borrow_mut() = Some(TriggerEditor::new(draft));
+    }
+
+    pub(crate) fn close_editor(&self) {
+        *self.editor.borrow_mut() = None;
+    }
+
+    pub(crate) fn set_editor_error(&self, error: String) {
+        if let Some(ref mut editor) = *self.editor.borrow_mut() {
+            editor.set_error(error);
+        }
+    }
+
+    pub(crate) fn import(&self) -> Ref<Option<ImportWizard>> {
+        self.import.borrow()
+    }
+
+    pub(crate) fn open_import(&self, wizard: ImportWizard) {
+        *self.import.borrow_mut() = Some(wizard);
+    }
+
+    pub(crate) fn close_import(&self) {
+        *self.import.borrow_mut() = None;
+    }
+
+    pub(crate) fn set_import_error(&self, error: String) {
+        if let Some(ref mut wizard) = *self.import.borrow_mut() {
+            wizard.set_error(error);
+        }
+    }
+
+    /// Records which triggers are currently being displayed, so that key
+    /// presses can act on them.
+    pub(crate) fn set_results(&self, results: Vec<TriggerRef>) {
+        *self.results.borrow_mut() = results;
+    }
+
+    pub(crate) fn status(&self) -> Option<String> {
+        self.status.borrow().clone()
+    }
+
+    pub(crate) fn set_status(&self, status: String) {
+        *self.status.borrow_mut() = Some(status);
+    }
+
+    pub(crate) fn query(&self) -> String {
+        self.query.borrow().clone()
+    }
+
+    pub(crate) fn set_query(&self, query: String) {
+        *self.query.borrow_mut() = query;
+    }
+
+    pub(crate) fn filter(&self) -> core::result::Result<TriggerFilter, TriggerError> {
+        self.query.borrow().parse()
+    }
+
+    pub(crate) fn editing(&self) -> bool {
+        self.editing.get()
+    }
+
+    /// Returns the selected row, clamped to the number of rows that are
+    /// actually available to select.
+    pub(crate) fn selected(&self, len: usize) -> Option<usize> {
+        if len == 0 {
+            return None;
+        }
+
+        let selected = self.selected.get().min(len - 1);
+        self.selected.set(selected);
+        Some(selected)
+    }
+
+    fn selected_ref(&self) -> Option<TriggerRef> {
+        let results = self.results.borrow();
+        self.selected(results.len()).map(|idx| results[idx].clone())
+    }
+
+    fn set_enabled(&self, all: bool, enabled: bool) -> Option<AppCommand> {
+        let trefs = if all {
+            self.results.borrow().clone()
+        } else {
+            self.selected_ref().into_iter().collect()
+        };
+
+        if trefs.is_empty() {
+            None
+        } else {
+            Some(AppCommand::SetTriggersEnabled(trefs, enabled))
+        }
+    }
+}
+
+impl Eventable for TriggersTab {
+    fn on_event(&self, event: event::Event) -> Result<Option<AppCommand>> {
+        if let event::Event::Key(key) = event {
+            if key.modifiers != KeyModifiers::NONE && key.modifiers != KeyModifiers::SHIFT {
+                return Ok(None);
+            }
+
+            let outcome = self
+                .import
+                .borrow_mut()
+                .as_mut()
+                .map(|wizard| wizard.on_key(key));
+            match outcome {
+                Some(ImportOutcome::Importing) => return Ok(None),
+                Some(ImportOutcome::Cancel) => {
+                    self.close_import();
+                    return Ok(None);
+                }
+                Some(ImportOutcome::Import(triggers, characters)) => {
+                    return Ok(Some(AppCommand::ImportTriggers(triggers, characters)))
+                }
+                None => {}
+            }
+
+            let outcome = self
+                .editor
+                .borrow_mut()
+                .as_mut()
+                .map(|editor| (editor.on_key(key), editor.draft().build()));
+            match outcome {
+                Some((EditorOutcome::Editing, _)) => {}
+                Some((EditorOutcome::Cancel, _)) => self.close_editor(),
+                Some((EditorOutcome::Save, Ok((id, trigger)))) => {
+                    return Ok(Some(AppCommand::AddTrigger(id, trigger)))
+                }
+                Some((EditorOutcome::Save, Err(error))) => self.set_editor_error(error),
+                None if self.editing.get() => match key.code {
+                    KeyCode::Char(c) => self.query.borrow_mut().push(c),
+                    KeyCode::Backspace => {
+                        self.query.borrow_mut().pop();
+                    }
+                    KeyCode::Enter | KeyCode::Esc => {
+                        self.editing.set(false);
+                        self.selected.set(0);
+                    }
+                    _ => {}
+                },
+                None => match key.code {
+                    KeyCode::Char('/') => self.editing.set(true),
+                    KeyCode::Char('n') => self.open_editor(TriggerDraft::default()),
+                    KeyCode::Char('i') => return Ok(Some(AppCommand::OpenImport)),
+                    KeyCode::Char('e') => return Ok(self.set_enabled(false, true)),
+                    KeyCode::Char('d') => return Ok(self.set_enabled(false, false)),
+                    KeyCode::Char('E') => return Ok(self.set_enabled(true, true)),
+                    KeyCode::Char('D') => return Ok(self.set_enabled(true, false)),
+                    KeyCode::Char('z') => {
+                        return Ok(self
+                            .selected_ref()
+                            .map(|tref| AppCommand::SnoozeTrigger(tref, DEFAULT_SNOOZE)))
+                    }
+                    KeyCode::Char('Z') => {
+                        return Ok(self.selected_ref().map(AppCommand::WakeTrigger))
+                    }
+                    KeyCode::Delete => {
+                        return Ok(self.selected_ref().map(AppCommand::RemoveTrigger))
+                    }
+                    KeyCode::Char('u') => return Ok(Some(AppCommand::Undo)),
+                    KeyCode::Char('r') => return Ok(Some(AppCommand::Redo)),
+                    KeyCode::Esc => {
+                        self.query.borrow_mut().clear();
+                        self.selected.set(0);
+                    }
+                    KeyCode::Up => self.selected.set(self.selected.get().saturating_sub(1)),
+                    KeyCode::Down => self.selected.set(self.selected.get() + 1),
+                    _ => {}
+                },
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+impl Tab for TriggersTab {
+    fn id(&self) -> &str {
+        "triggers"
+    }
+
+    fn title(&self) -> &str {
+        self.title.as_str()
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use comrade::{Character, TimerCategory, DEFAULT_PANE};
+
+use crate::clock;
+
+pub(crate) struct Timer {
+    pub(crate) text: Arc<String>,
+    pub(crate) duration: Duration,
+    /// When the countdown finishes, remaining time is worked out from this
+    /// whenever it's needed rather than waiting on the next Countdown event,
+    /// which only arrive every so often.
+    pub(crate) ends_at: Instant,
+    pub(crate) category: Option<Arc<TimerCategory>>,
+    pub(crate) character: Option<Arc<Character>>,
+}
+
+impl Timer {
+    pub(crate) fn order(&self) -> i64 {
+        self.category.as_ref().map(|c| c.order).unwrap_or(0)
+    }
+
+    pub(crate) fn category_name(&self) -> &str {
+        self.category
+            .as_ref()
+            .map(|c| c.name.as_str())
+            .unwrap_or("")
+    }
+
+    pub(crate) fn character_name(&self) -> &str {
+        self.character
+            .as_ref()
+            .map(|c| c.display_name())
+            .unwrap_or("Manual")
+    }
+
+    pub(crate) fn pane(&self) -> &str {
+        self.category
+            .as_ref()
+            .map(|c| c.pane())
+            .unwrap_or(DEFAULT_PANE)
+    }
+
+    /// How long is left, which is never more than the whole countdown, even
+    /// if the clock was read a moment before the countdown was reported.
+    pub(crate) fn remaining(&self) -> Duration {
+        self.ends_at
+            .saturating_duration_since(clock::now())
+            .min(self.duration)
+    }
+
+    pub(crate) fn percent(&self) -> u16 {
+        if self.duration.is_zero() {
+            return 0;
+        }
+
+        let percent_f = (self.remaining().as_secs_f64() / self.duration.as_secs_f64()) * 100.0;
+        let percent: u16 = percent_f.min(100.0) as u16;
+        percent
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TimerSort {
+    Category,
+    Remaining,
+    Name,
+}
+
+impl TimerSort {
+    fn next(self) -> TimerSort {
+        match self {
+            TimerSort::Category => TimerSort::Remaining,
+            TimerSort::Remaining => TimerSort::Name,
+            TimerSort::Name => TimerSort::Category,
+        }
+    }
+}
+
+impl fmt::Display for TimerSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimerSort::Category => f.write_str("category"),
+            TimerSort::Remaining => f.write_str("remaining"),
+            TimerSort::Name => f.write_str("name"),
+        }
+    }
+}
+
+/// A single line within a timer pane.
+pub(crate) enum TimerRow {
+    Header(String),
+    Timer(Arc<Timer>),
+    /// A collapsed category, represented by whichever of its timers will
+    /// finish first.
+    Collapsed {
+        category: String,
+        count: usize,
+        next: Arc<Timer>,
+    },
+}
+
+/// How the timers are currently being displayed.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct TimerView {
+    sort: TimerSort,
+    by_character: bool,
+    collapsed: BTreeSet<String>,
+}
+
+impl Default for TimerView {
+    fn default() -> TimerView {
+        TimerView {
+            sort: TimerSort::Category,
+            by_character: false,
+            collapsed: BTreeSet::new(),
+        }
+    }
+}
+
+impl TimerView {
+    pub(crate) fn sort(&self) -> TimerSort {
+        self.sort
+    }
+
+    pub(crate) fn by_character(&self) -> bool {
+        self.by_character
+    }
+
+    pub(crate) fn next_sort(&mut self) {
+        self.sort = self.sort.next();
+    }
+
+    pub(crate) fn toggle_by_character(&mut self) {
+        self.by_character = !self.by_character;
+    }
+
+    pub(crate) fn toggle_collapsed(&mut self, category: &str) {
+        if !self.collapsed.remove(category) {
+            self.collapsed.insert(category.to_string());
+        }
+    }
+
+    /// Collapses every one of the given categories, unless they're all
+    /// collapsed already, in which case they're all expanded.
+    pub(crate) fn toggle_all_collapsed(&mut self, categories: Vec<String>) {
+        if categories.iter().all(|c| self.collapsed.contains(c)) {
+            self.collapsed.clear();
+        } else {
+            self.collapsed.extend(categories);
+        }
+    }
+
+    /// Lays the given timers out into rows, grouped into the panes that their
+    /// categories say they belong in. The default pane always comes first.
+    pub(crate) fn panes(&self, mut timers: Vec<Arc<Timer>>) -> Vec<(String, Vec<TimerRow>)> {
+        timers.sort_by(|a, b| {
+            let by_character = if self.by_character {
+                a.character_name().cmp(b.character_name())
+            } else {
+                std::cmp::Ordering::Equal
+            };
+            let by_sort = match self.sort {
+                TimerSort::Category => a
+                    .order()
+                    .cmp(&b.order())
+                    .then_with(|| a.category_name().cmp(b.category_name()))
+                    .then_with(|| a.ends_at.cmp(&b.ends_at)),
+                TimerSort::Remaining => a.ends_at.cmp(&b.ends_at),
+                TimerSort::Name => a.text.cmp(&b.text),
+            };
+
+            by_character.then(by_sort).then_with(|| a.text.cmp(&b.text))
+        });
+
+        let mut panes: Vec<(String, Vec<Arc<Timer>>)> =
+            vec![(DEFAULT_PANE.to_string(), Vec::new())];
+        for timer in timers.into_iter() {
+            let pane = timer.pane();
+            match panes.iter_mut().find(|(name, _)| name == pane) {
+                Some((_, timers)) => timers.push(timer),
+                None => panes.push((pane.to_string(), vec![timer])),
+            }
+        }
+
+        panes
+            .into_iter()
+            .map(|(name, timers)| (name, self.rows(timers)))
+            .collect()
+    }
+
+    fn rows(&self, timers: Vec<Arc<Timer>>) -> Vec<TimerRow> {
+        let mut rows = Vec::new();
+        let mut character: Option<String> = None;
+        // Where the summary row for each collapsed category is, within the
+        // current character group.
+        let mut collapsed: HashMap<String, usize> = HashMap::new();
+
+        for timer in timers.into_iter() {
+            if self.by_character && character.as_deref() != Some(timer.character_name()) {
+                character = Some(timer.character_name().to_string());
+                rows.push(TimerRow::Header(timer.character_name().to_string()));
+                collapsed.clear();
+            }
+
+            let category = timer.category_name();
+            if category.is_empty() || !self.collapsed.contains(category) {
+                rows.push(TimerRow::Timer(timer));
+                continue;
+            }
+
+            match collapsed.get(category) {
+                Some(idx) => {
+                    if let TimerRow::Collapsed { count, next, .. } = &mut rows[*idx] {
+                        *count += 1;
+                        if timer.ends_at < next.ends_at {
+                            *next = timer;
+                        }
+                    }
+                }
+                None => {
+                    collapsed.insert(category.to_string(), rows.len());
+                    rows.push(TimerRow::Collapsed {
+                        category: category.to_string(),
+                        count: 1,
+                        next: timer,
+                    });
+                }
+            }
+        }
+
+        rows
+    }
+}
@@ -0,0 +1,122 @@
+//! A small block font for rendering alerts in large text, so that they can be
+//! read at a glance from a terminal sitting off to the side of the game.
+
+const HEIGHT: usize = 5;
+
+const GLYPHS: &[(char, [&str; HEIGHT])] = &[
+    ('A', [" ### ", "#   #", "#####", "#   #", "#   #"]),
+    ('B', ["#### ", "#   #", "#### ", "#   #", "#### "]),
+    ('C', [" ####", "#    ", "#    ", "#    ", " ####"]),
+    ('D', ["#### ", "#   #", "#   #", "#   #", "#### "]),
+    ('E', ["#####", "#    ", "#### ", "#    ", "#####"]),
+    ('F', ["#####", "#    ", "#### ", "#    ", "#    "]),
+    ('G', [" ####", "#    ", "#  ##", "#   #", " ####"]),
+    ('H', ["#   #", "#   #", "#####", "#   #", "#   #"]),
+    ('I', ["#####", "  #  ", "  #  ", "  #  ", "#####"]),
+    ('J', ["#####", "   # ", "   # ", "#  # ", " ##  "]),
+    ('K', ["#   #", "#  # ", "###  ", "#  # ", "#   #"]),
+    ('L', ["#    ", "#    ", "#    ", "#    ", "#####"]),
+    ('M', ["#   #", "## ##", "# # #", "#   #", "#   #"]),
+    ('N', ["#   #", "##  #", "# # #", "#  ##", "#   #"]),
+    ('O', [" ### ", "#   #", "#   #", "#   #", " ### "]),
+    ('P', ["#### ", "#   #", "#### ", "#    ", "#    "]),
+    ('Q', [" ### ", "#   #", "# # #", "#  # ", " ## #"]),
+    ('R', ["#### ", "#   #", "#### ", "#  # ", "#   #"]),
+    ('S', [" ####", "#    ", " ### ", "    #", "#### "]),
+    ('T', ["#####", "  #  ", "  #  ", "  #  ", "  #  "]),
+    ('U', ["#   #", "#   #", "#   #", "#   #", " ### "]),
+    ('V', ["#   #", "#   #", "#   #", " # # ", "  #  "]),
+    ('W', ["#   #", "#   #", "# # #", "## ##", "#   #"]),
+    ('X', ["#   #", " # # ", "  #  ", " # # ", "#   #"]),
+    ('Y', ["#   #", " # # ", "  #  ", "  #  ", "  #  "]),
+    ('Z', ["#####", "   # ", "  #  ", " #   ", "#####"]),
+    ('0', [" ### ", "#  ##", "# # #", "##  #", " ### "]),
+    ('1', ["  #  ", " ##  ", "  #  ", "  #  ", " ### "]),
+    ('2', [" ### ", "#   #", "  ## ", " #   ", "#####"]),
+    ('3', ["#### ", "    #", " ### ", "    #", "#### "]),
+    ('4', ["#   #", "#   #", "#####", "    #", "    #"]),
+    ('5', ["#####", "#    ", "#### ", "    #", "#### "]),
+    ('6', [" ### ", "#    ", "#### ", "#   #", " ### "]),
+    ('7', ["#####", "   # ", "  #  ", " #   ", " #   "]),
+    ('8', [" ### ", "#   #", " ### ", "#   #", " ### "]),
+    ('9', [" ### ", "#   #", " ####", "    #", " ### "]),
+    ('!', ["#", "#", "#", " ", "#"]),
+    ('?', [" ### ", "#   #", "  ## ", "     ", "  #  "]),
+    ('.', [" ", " ", " ", " ", "#"]),
+    (',', ["  ", "  ", "  ", " #", "# "]),
+    (':', [" ", "#", " ", "#", " "]),
+    ('-', ["    ", "    ", "####", "    ", "    "]),
+    ('\'', ["#", "#", " ", " ", " "]),
+    ('/', ["    #", "   # ", "  #  ", " #   ", "#    "]),
+];
+
+fn glyph(c: char) -> &'static [&'static str; HEIGHT] {
+    GLYPHS
+        .iter()
+        .find(|(g, _)| *g == c)
+        .or_else(|| GLYPHS.iter().find(|(g, _)| *g == '?'))
+        .map(|(_, rows)| rows)
+        .expect("the fallback glyph should always exist")
+}
+
+fn word_width(word: &str) -> usize {
+    let chars: usize = word.chars().map(|c| glyph(c)[0].len()).sum();
+    chars + word.chars().count().saturating_sub(1)
+}
+
+/// Renders the given text in the block font, wrapped to fit within the given
+/// width. Returns None if there's a word that won't fit at all, in which case
+/// the text should be displayed normally instead.
+pub(crate) fn render(text: &str, width: usize) -> Option<Vec<String>> {
+    // Words are separated by the width of three spaces.
+    const SPACE: usize = 3;
+
+    let text = text.to_uppercase();
+
+    let mut lines: Vec<Vec<&str>> = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut current_width = 0;
+    for word in text.split_whitespace() {
+        let w = word_width(word);
+        if w > width {
+            return None;
+        }
+
+        if !current.is_empty() && current_width + SPACE + w > width {
+            lines.push(std::mem::take(&mut current));
+            current_width = 0;
+        }
+
+        if !current.is_empty() {
+            current_width += SPACE;
+        }
+        current_width += w;
+        current.push(word);
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+
+    let mut rendered = Vec::new();
+    for (idx, words) in lines.iter().enumerate() {
+        if idx > 0 {
+            rendered.push(String::new());
+        }
+
+        for row in 0..HEIGHT {
+            let line = words
+                .iter()
+                .map(|word| {
+                    word.chars()
+                        .map(|c| glyph(c)[row])
+                        .collect::<Vec<&str>>()
+                        .join(" ")
+                })
+                .collect::<Vec<String>>()
+                .join(" ".repeat(SPACE).as_str());
+            rendered.push(line.replace('#', "█"));
+        }
+    }
+
+    Some(rendered)
+}
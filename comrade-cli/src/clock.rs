@@ -0,0 +1,36 @@
+//! Clock
+//!
+//! How long is left on a timer, and whether an alert is flashing on or off,
+//! is worked out from the time whenever it's drawn. Anything that's drawn
+//! that way reads the time from here, rather than from `Instant::now`, so
+//! that the UI snapshot tests can stop the clock and have every frame come
+//! out the same.
+
+use std::time::Instant;
+
+#[cfg(test)]
+use std::cell::Cell;
+
+#[cfg(test)]
+thread_local! {
+    static FROZEN: Cell<Option<Instant>> = Cell::new(None);
+}
+
+#[cfg(not(test))]
+pub(crate) fn now() -> Instant {
+    Instant::now()
+}
+
+#[cfg(test)]
+pub(crate) fn now() -> Instant {
+    FROZEN.with(Cell::get).unwrap_or_else(Instant::now)
+}
+
+/// Stops the clock at the current time, for this thread only, since each
+/// test runs on a thread of its own.
+#[cfg(test)]
+pub(crate) fn freeze() -> Instant {
+    let now = Instant::now();
+    FROZEN.with(|frozen| frozen.set(Some(now)));
+    now
+}
@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::Args;
+use humantime::format_duration;
+
+use crate::commands::{print_table, Result};
+
+#[derive(Debug, Args)]
+pub(crate) struct AnalyzeCommand {
+    /// The log file to replay
+    log: PathBuf,
+
+    /// The trigger file to try out against the log
+    #[clap(long)]
+    pack: PathBuf,
+
+    /// Who the log is from, if its filename doesn't say
+    #[clap(long)]
+    character: Option<String>,
+
+    /// How many of the lines that each trigger fired on to show
+    #[clap(long, default_value_t = 3)]
+    examples: usize,
+
+    /// Also list the triggers that never fired
+    #[clap(long)]
+    all: bool,
+
+    #[clap(long)]
+    json: bool,
+}
+
+impl AnalyzeCommand {
+    pub(crate) fn run(self) -> Result<()> {
+        let mut analysis = comrade::analyze(
+            self.log.as_path(),
+            self.pack.as_path(),
+            self.character.as_deref(),
+            self.examples,
+        )?;
+        if !self.all {
+            analysis.triggers.retain(|t| t.count > 0);
+        }
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&analysis)?);
+            return Ok(());
+        }
+
+        let rows: Vec<Vec<String>> = analysis
+            .triggers
+            .iter()
+            .map(|t| {
+                vec![
+                    t.trigger.to_string(),
+                    t.name.clone(),
+                    t.count.to_string(),
+                    analysis
+                        .per_hour(t)
+                        .map(|rate| format!("{:.1}", rate))
+                        .unwrap_or_default(),
+                    if t.disabled { "off" } else { "on" }.to_string(),
+                ]
+            })
+            .collect();
+        print_table(&["TRIGGER", "NAME", "FIRED", "PER HOUR", "DEFAULT"], &rows);
+
+        for t in analysis.triggers.iter().filter(|t| !t.examples.is_empty()) {
+            println!();
+            println!("{} ({}):", t.trigger, t.name);
+            for example in t.examples.iter() {
+                println!("  {}", example);
+            }
+        }
+
+        println!();
+        println!(
+            "{} line(s) from {} over {}",
+            analysis.lines,
+            analysis.character,
+            format_duration(Duration::from_secs(analysis.seconds))
+        );
+
+        Ok(())
+    }
+}
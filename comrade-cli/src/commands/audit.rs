@@ -0,0 +1,72 @@
+use std::time::SystemTime;
+
+use clap::Args;
+use humantime::{format_rfc3339_seconds, Duration};
+use serde_json::json;
+
+use comrade::Comrade;
+
+use crate::commands::{print_table, Result};
+
+#[derive(Debug, Args)]
+pub(crate) struct AuditCommand {
+    /// Only changes that mention this, ignoring case, e.g. a trigger, a
+    /// source, or a character
+    #[clap(long)]
+    filter: Option<String>,
+
+    /// How far back to look, e.g. 2d
+    #[clap(long)]
+    since: Option<Duration>,
+
+    #[clap(long)]
+    json: bool,
+}
+
+impl AuditCommand {
+    pub(crate) fn run(self, comrade: &Comrade) -> Result<()> {
+        let filter = self.filter.map(|f| f.to_lowercase());
+        let since = self
+            .since
+            .and_then(|since| SystemTime::now().checked_sub(*since));
+        let entries: Vec<_> = comrade
+            .audit_log()?
+            .into_iter()
+            .filter(|e| since.is_none_or(|since| e.time >= since))
+            .filter(|e| {
+                filter
+                    .as_ref()
+                    .is_none_or(|f| e.what.to_lowercase().contains(f.as_str()))
+            })
+            .collect();
+
+        if self.json {
+            let entries: Vec<_> = entries
+                .iter()
+                .map(|e| {
+                    json!({
+                        "time": format_rfc3339_seconds(e.time).to_string(),
+                        "who": e.who,
+                        "what": e.what,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+        } else {
+            let rows: Vec<Vec<String>> = entries
+                .iter()
+                .map(|e| {
+                    vec![
+                        format_rfc3339_seconds(e.time).to_string(),
+                        e.who.clone(),
+                        e.what.clone(),
+                    ]
+                })
+                .collect();
+            print_table(&["TIME", "WHO", "WHAT"], &rows);
+            println!("{} change(s)", entries.len());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use std::path::PathBuf;
+
+use clap::Subcommand;
+use serde_json::json;
+
+use comrade::{ComplianceIssue, Comrade};
+
+use crate::commands::triggers::characters;
+use crate::commands::Result;
+use crate::errors::CommandError;
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum ComplianceCommand {
+    /// Import a guild's manifest of required triggers, replacing the one
+    /// that was imported before, if any
+    Import { manifest: PathBuf },
+    /// Report which required triggers are missing or turned off
+    Check {
+        /// Only check this character, can be given more than once
+        #[clap(long)]
+        character: Vec<String>,
+
+        #[clap(long)]
+        json: bool,
+    },
+}
+
+impl ComplianceCommand {
+    pub(crate) fn run(self, comrade: &Comrade) -> Result<()> {
+        match self {
+            ComplianceCommand::Import { manifest } => {
+                let manifest = comrade.import_manifest(manifest.as_path())?;
+                println!(
+                    "imported {:?}, requiring {} trigger(s) and {} source(s)",
+                    manifest.name,
+                    manifest.triggers.len(),
+                    manifest.sources.len()
+                );
+            }
+            ComplianceCommand::Check { character, json } => {
+                let report = comrade.compliance()?.ok_or(CommandError::NoManifest)?;
+                let characters = characters(comrade, character)?;
+                let issues: Vec<&ComplianceIssue> = report
+                    .issues
+                    .iter()
+                    .filter(|issue| match issue {
+                        ComplianceIssue::DisabledTrigger { character, .. } => {
+                            characters.contains(character)
+                        }
+                        _ => true,
+                    })
+                    .collect();
+
+                if json {
+                    let output = json!({
+                        "manifest": report.manifest,
+                        "required": report.required,
+                        "issues": issues.iter().map(|i| i.to_string()).collect::<Vec<String>>(),
+                    });
+                    println!("{}", serde_json::to_string_pretty(&output)?);
+                } else {
+                    for issue in issues.iter() {
+                        println!("{}", issue);
+                    }
+                    println!(
+                        "{}: {} required trigger(s), {} issue(s)",
+                        report.manifest,
+                        report.required,
+                        issues.len()
+                    );
+                }
+
+                if !issues.is_empty() {
+                    return Err(CommandError::NotCompliant {
+                        issues: issues.len(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
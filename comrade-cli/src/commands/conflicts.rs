@@ -0,0 +1,70 @@
+use clap::Args;
+use serde_json::json;
+
+use comrade::{Comrade, Overlap};
+
+use crate::commands::{print_table, Result};
+
+#[derive(Debug, Args)]
+pub(crate) struct ConflictsCommand {
+    /// Only triggers from different sources, e.g. after importing another
+    /// guild's pack
+    #[clap(long)]
+    across_sources: bool,
+
+    #[clap(long)]
+    json: bool,
+}
+
+impl ConflictsCommand {
+    pub(crate) fn run(self, comrade: &Comrade) -> Result<()> {
+        let mut conflicts = comrade.conflicts();
+        if self.across_sources {
+            conflicts.retain(|c| c.first.source != c.second.source);
+        }
+
+        let why = |overlap: &Overlap| match overlap {
+            Overlap::Identical => ("identical".to_string(), "same pattern".to_string()),
+            Overlap::Line(line) => ("line".to_string(), format!("both match {:?}", line)),
+            Overlap::Text(text) => ("text".to_string(), format!("both need {:?}", text)),
+        };
+
+        if self.json {
+            let conflicts: Vec<_> = conflicts
+                .iter()
+                .map(|c| {
+                    let (kind, why) = why(&c.overlap);
+                    json!({
+                        "first": c.first.to_string(),
+                        "first_name": c.first_name,
+                        "second": c.second.to_string(),
+                        "second_name": c.second_name,
+                        "overlap": kind,
+                        "why": why,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&conflicts)?);
+        } else {
+            let rows: Vec<Vec<String>> = conflicts
+                .iter()
+                .map(|c| {
+                    vec![
+                        c.first.to_string(),
+                        c.first_name.clone(),
+                        c.second.to_string(),
+                        c.second_name.clone(),
+                        why(&c.overlap).1,
+                    ]
+                })
+                .collect();
+            print_table(&["TRIGGER", "NAME", "OTHER", "OTHER NAME", "WHY"], &rows);
+            println!(
+                "{} pair(s) of triggers that look like they match the same lines",
+                conflicts.len()
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+use clap::Args;
+
+use comrade::{Comrade, HistoryFilter, LogTime, Since, VacuumReport};
+
+use crate::commands::triggers::characters;
+use crate::commands::{print_table, Result};
+
+#[derive(Debug, Args)]
+pub(crate) struct HistoryCommand {
+    /// Only look through this character's log, can be given more than once
+    #[clap(long)]
+    character: Vec<String>,
+
+    /// Only triggers whose name or id contains this, ignoring case
+    #[clap(long)]
+    trigger: Option<String>,
+
+    /// Only while in a zone whose name contains this, ignoring case
+    #[clap(long)]
+    zone: Option<String>,
+
+    /// How far back to look, either a duration like 2d, counted back from
+    /// the newest line in the logs, or a time like "2026-10-17 20:15"
+    #[clap(long)]
+    since: Option<String>,
+
+    /// Only up until a time like "2026-10-17 20:15"
+    #[clap(long)]
+    until: Option<LogTime>,
+
+    /// Replay the logs against the triggers as they are now, rather than
+    /// searching what was recorded as they fired, e.g. for times before
+    /// Comrade was recording them
+    #[clap(long)]
+    replay: bool,
+
+    #[clap(long)]
+    json: bool,
+}
+
+impl HistoryCommand {
+    pub(crate) fn run(self, comrade: &Comrade) -> Result<()> {
+        let since = match self.since {
+            Some(since) => Some(match humantime::parse_duration(since.as_str()) {
+                Ok(duration) => Since::Ago(duration),
+                Err(_) => Since::At(since.parse()?),
+            }),
+            None => None,
+        };
+
+        let filter = HistoryFilter {
+            characters: characters(comrade, self.character)?,
+            trigger: self.trigger,
+            zone: self.zone,
+            since,
+            until: self.until,
+        };
+        let entries = if self.replay {
+            comrade.replay_history(&filter)?
+        } else {
+            comrade.history(&filter)?
+        };
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+        } else {
+            let rows: Vec<Vec<String>> = entries
+                .iter()
+                .map(|e| {
+                    vec![
+                        e.timestamp.clone(),
+                        e.character.clone(),
+                        e.trigger.clone(),
+                        e.zone.clone().unwrap_or_default(),
+                        e.message.clone(),
+                    ]
+                })
+                .collect();
+            print_table(&["TIME", "CHARACTER", "TRIGGER", "ZONE", "MESSAGE"], &rows);
+            println!("{} time(s)", entries.len());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+pub(crate) struct VacuumCommand {
+    #[clap(long)]
+    json: bool,
+}
+
+impl VacuumCommand {
+    pub(crate) fn run(self, comrade: &Comrade) -> Result<()> {
+        let report = comrade.vacuum_history()?;
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_report(&report);
+        }
+
+        Ok(())
+    }
+}
+
+fn print_report(report: &VacuumReport) {
+    let list = |months: &[String]| match months {
+        [] => "none".to_string(),
+        months => months.join(", "),
+    };
+    println!("Compressed: {}", list(report.archived.as_slice()));
+    println!("Deleted:    {}", list(report.deleted.as_slice()));
+    println!(
+        "Size:       {:.1} MiB, down from {:.1} MiB",
+        report.after as f64 / 1024.0 / 1024.0,
+        report.before as f64 / 1024.0 / 1024.0
+    );
+}
@@ -0,0 +1,35 @@
+use crate::commands::{Options, Result};
+
+pub(crate) fn run(options: Options, with_defaults: bool) -> Result<()> {
+    // A profile is scaffolded like any other configuration directory, just
+    // inside of the main one.
+    let config_dir = match options.profile {
+        Some(name) => Some(comrade::profile_dir(
+            options.config_dir.as_deref(),
+            name.as_str(),
+        )),
+        None => options.config_dir,
+    };
+    let scaffold = comrade::scaffold(config_dir, with_defaults)?;
+
+    for path in scaffold.created.iter() {
+        println!("created {}", path.display());
+    }
+
+    if !scaffold.triggers.is_empty() {
+        println!(
+            "installed {} starter triggers, all disabled by default:",
+            scaffold.triggers.len()
+        );
+        for id in scaffold.triggers.iter() {
+            println!("  local/{}", id);
+        }
+        println!("enable them with `comrade triggers enable <trigger>`");
+    }
+
+    if scaffold.created.is_empty() && scaffold.triggers.is_empty() {
+        println!("nothing to do, already initialized");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,148 @@
+use std::path::PathBuf;
+
+use clap::Subcommand;
+
+use comrade::errors::{ComradeError, ConfigError};
+use comrade::Comrade;
+
+use crate::errors::CommandError;
+
+pub(crate) mod analyze;
+pub(crate) mod audit;
+pub(crate) mod compliance;
+pub(crate) mod conflicts;
+pub(crate) mod history;
+pub(crate) mod init;
+pub(crate) mod pack;
+pub(crate) mod service;
+pub(crate) mod soak;
+pub(crate) mod test_pack;
+pub(crate) mod triggers;
+
+type Result<T, E = CommandError> = core::result::Result<T, E>;
+
+/// The global flags that decide which configuration the commands load, and
+/// whether they may write anything.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Options {
+    pub(crate) config_dir: Option<PathBuf>,
+    pub(crate) profile: Option<String>,
+    pub(crate) read_only: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum Command {
+    /// Create the configuration and data directories
+    Init {
+        /// Also install the starter pack of triggers, disabled by default
+        #[clap(long)]
+        with_defaults: bool,
+    },
+    /// Inspect and manage triggers
+    #[clap(subcommand)]
+    Triggers(Box<triggers::TriggersCommand>),
+    /// Replay a trigger pack's fixtures and report which of them failed
+    TestPack {
+        /// The trigger file to test
+        pack: PathBuf,
+
+        /// A directory of fixtures, each a TOML file of log lines and the
+        /// triggers that are expected to fire on them
+        fixtures: PathBuf,
+
+        #[clap(long)]
+        json: bool,
+    },
+    /// Search for the times that triggers fired
+    History(history::HistoryCommand),
+    /// Compress the months of history that are over, and delete the ones
+    /// past keeping, rather than waiting for Comrade to get around to it
+    Vacuum(history::VacuumCommand),
+    /// Replay a past log against a trigger pack, to see which of its triggers
+    /// would have fired and how often
+    Analyze(analyze::AnalyzeCommand),
+    /// Throw made up log lines at the loaded triggers at a steady rate, to
+    /// see whether they keep up
+    Soak(soak::SoakCommand),
+    /// Find loaded triggers that look like they match the same lines, to
+    /// tidy up after importing packs that cover the same things
+    Conflicts(conflicts::ConflictsCommand),
+    /// Show every change that's been made to which triggers are on, and to
+    /// what they do, including snoozes and syncs
+    Audit(audit::AuditCommand),
+    /// Check the characters against a guild's manifest of required triggers
+    #[clap(subcommand)]
+    Compliance(compliance::ComplianceCommand),
+    /// Run Comrade unattended, as a systemd or Windows service
+    #[clap(subcommand)]
+    Service(service::ServiceCommand),
+    /// Build and install .comradepack files, which bundle triggers with
+    /// their sounds
+    #[clap(subcommand)]
+    Pack(pack::PackCommand),
+    /// Try Comrade out with a made up character, without the game running
+    Demo,
+}
+
+impl Command {
+    pub(crate) fn run(self, options: Options) -> Result<()> {
+        match self {
+            Command::Init { .. } if options.read_only => {
+                Err(ComradeError::from(ConfigError::ReadOnly).into())
+            }
+            Command::Init { with_defaults } => init::run(options, with_defaults),
+            Command::Triggers(cmd) => cmd.run(&load(&options)?),
+            Command::TestPack {
+                pack,
+                fixtures,
+                json,
+            } => test_pack::run(pack, fixtures, json),
+            Command::History(cmd) => cmd.run(&load(&options)?),
+            Command::Vacuum(cmd) => cmd.run(&load(&options)?),
+            Command::Analyze(cmd) => cmd.run(),
+            Command::Soak(cmd) => cmd.run(&load(&options)?),
+            Command::Conflicts(cmd) => cmd.run(&load(&options)?),
+            Command::Audit(cmd) => cmd.run(&load(&options)?),
+            Command::Compliance(cmd) => cmd.run(&load(&options)?),
+            Command::Service(cmd) => cmd.run(options),
+            Command::Pack(cmd) => cmd.run(|| load(&options)),
+            Command::Demo => unreachable!("demo mode runs the terminal UI"),
+        }
+    }
+}
+
+fn load(options: &Options) -> Result<Comrade> {
+    let comrade = Comrade::new();
+    comrade.set_read_only(options.read_only);
+    comrade.set_profile(options.profile.as_deref());
+    comrade.load(options.config_dir.clone())?;
+
+    Ok(comrade)
+}
+
+/// Prints rows of text as a simple table, with each column padded to the
+/// width of its widest cell.
+pub(crate) fn print_table(headers: &[&str], rows: &[Vec<String>]) {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.len()).collect();
+    for row in rows.iter() {
+        for (idx, cell) in row.iter().enumerate() {
+            widths[idx] = widths[idx].max(cell.chars().count());
+        }
+    }
+
+    let format_row = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<String>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    println!("{}", format_row(headers.to_vec()));
+    for row in rows.iter() {
+        println!("{}", format_row(row.iter().map(|c| c.as_str()).collect()));
+    }
+}
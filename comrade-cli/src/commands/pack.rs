@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+use clap::Subcommand;
+
+use comrade::Comrade;
+
+use crate::commands::Result;
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum PackCommand {
+    /// Build a .comradepack from a directory with a Pack.toml, a
+    /// Triggers.toml, and optionally a sounds directory and a Pack.sig
+    Build {
+        dir: PathBuf,
+
+        /// Where to write the pack, by default <name>-<version>.comradepack
+        #[clap(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Install a .comradepack as a remote source named after the pack
+    Install { pack: PathBuf },
+}
+
+impl PackCommand {
+    pub(crate) fn run(self, load: impl FnOnce() -> Result<Comrade>) -> Result<()> {
+        match self {
+            PackCommand::Build { dir, output } => {
+                let (meta, filename) = comrade::build_pack(dir.as_path(), output.as_deref())?;
+                println!(
+                    "built {} {} to {}",
+                    meta.name,
+                    meta.version,
+                    filename.display()
+                );
+            }
+            PackCommand::Install { pack } => {
+                let (meta, count) = load()?.install_pack(pack.as_path())?;
+                println!(
+                    "installed {} {} as remote:{} with {} trigger(s)",
+                    meta.name, meta.version, meta.name, count
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
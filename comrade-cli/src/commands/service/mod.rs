@@ -0,0 +1,139 @@
+//! Running Comrade as a service, without a terminal, for machines that are
+//! left running unattended. Under systemd this is meant for a `Type=notify`
+//! unit, where SIGTERM stops Comrade cleanly and SIGHUP reloads its
+//! configuration. On Windows it can be installed as a service instead.
+
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Duration;
+
+use clap::Subcommand;
+use log::{debug, error, info, LevelFilter};
+
+use crate::commands::{load, Options, Result};
+use crate::errors::{describe_error, CommandError};
+use crate::logging;
+
+#[cfg(unix)]
+mod unix;
+#[cfg(windows)]
+mod windows;
+
+/// How long we wait on a request before checking Comrade for events, which
+/// have to be taken even though nothing is shown, so that they don't back up.
+const EVENT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum ServiceCommand {
+    /// Run without a terminal until stopped, writing logs to the data directory
+    Run {
+        /// Set for the service that `service install` creates, since Windows
+        /// has to be talked to differently when it starts us
+        #[cfg(windows)]
+        #[clap(long, hide = true)]
+        windows_service: bool,
+    },
+    /// Install Comrade as a Windows service that starts with the computer
+    #[cfg(windows)]
+    Install,
+}
+
+impl ServiceCommand {
+    pub(crate) fn run(self, options: Options) -> Result<()> {
+        match self {
+            #[cfg(windows)]
+            ServiceCommand::Run {
+                windows_service: true,
+            } => windows::dispatch(options),
+            ServiceCommand::Run { .. } => run(options),
+            #[cfg(windows)]
+            ServiceCommand::Install => windows::install(options),
+        }
+    }
+}
+
+/// Something that the service has been asked to do, either by a signal or by
+/// Windows.
+pub(crate) enum Request {
+    Stop,
+    Reload,
+}
+
+/// What the service is doing, for whatever is supervising it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Status {
+    Running,
+    Reloading,
+    Stopping,
+}
+
+fn run(options: Options) -> Result<()> {
+    let (sender, requests) = mpsc::channel();
+
+    #[cfg(unix)]
+    {
+        unix::forward_signals(sender)?;
+        serve(options, requests, unix::notify_systemd)
+    }
+
+    // Without signals to listen for, we run until we're killed.
+    #[cfg(not(unix))]
+    {
+        let _sender = sender;
+        serve(options, requests, |_| {})
+    }
+}
+
+/// Runs Comrade until it's asked to stop, telling whatever is supervising us
+/// how things are going along the way.
+pub(crate) fn serve(
+    options: Options,
+    requests: Receiver<Request>,
+    notify: impl Fn(Status),
+) -> Result<()> {
+    logging::init(LevelFilter::Info).map_err(CommandError::LoggingError)?;
+
+    // In read-only mode the logs only go to stderr, which a supervisor like
+    // systemd keeps anyway.
+    let comrade = Arc::new(load(&options)?);
+    if !options.read_only {
+        let filename = logging::log_to_file(comrade.data_dir().as_path())?;
+        info!("writing logs to {}", filename.display());
+    }
+
+    comrade.init()?;
+    comrade.start()?;
+    #[cfg(feature = "dashboard")]
+    if let Err(e) = comrade.start_dashboard() {
+        error!("could not start the dashboard: {}", describe_error(&e));
+    }
+    info!("running as a service");
+    notify(Status::Running);
+
+    loop {
+        match requests.recv_timeout(EVENT_POLL_INTERVAL) {
+            Ok(Request::Stop) | Err(RecvTimeoutError::Disconnected) => break,
+            Ok(Request::Reload) => {
+                notify(Status::Reloading);
+                info!("reloading configuration");
+                if let Err(e) = comrade.reload() {
+                    error!("could not reload configuration: {}", describe_error(&e));
+                }
+                notify(Status::Running);
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+
+        while let Some(event) = comrade.event() {
+            debug!("received event: {:?}", event);
+        }
+        logging::flush();
+    }
+
+    notify(Status::Stopping);
+    info!("stopping");
+    comrade.stop()?;
+    logging::flush();
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use std::env;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::net::UnixDatagram;
+use std::sync::mpsc::Sender;
+use std::thread;
+
+use log::warn;
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+
+use crate::commands::service::{Request, Status};
+
+/// Turns SIGTERM and SIGINT into requests to stop, and SIGHUP into a request
+/// to reload the configuration.
+pub(super) fn forward_signals(sender: Sender<Request>) -> io::Result<()> {
+    let mut signals = Signals::new([SIGTERM, SIGINT, SIGHUP])?;
+    thread::spawn(move || {
+        for signal in signals.forever() {
+            let request = match signal {
+                SIGHUP => Request::Reload,
+                _ => Request::Stop,
+            };
+            if sender.send(request).is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Tells systemd what we're doing, when it started us with a `Type=notify`
+/// unit, otherwise there's nobody to tell and this does nothing.
+pub(super) fn notify_systemd(status: Status) {
+    let path = match env::var_os("NOTIFY_SOCKET") {
+        Some(path) => path,
+        None => return,
+    };
+    let state = match status {
+        Status::Running => "READY=1",
+        Status::Reloading => "RELOADING=1",
+        Status::Stopping => "STOPPING=1",
+    };
+
+    let send = || -> io::Result<()> {
+        let socket = UnixDatagram::unbound()?;
+        match path.as_bytes().strip_prefix(b"@") {
+            // Abstract sockets only exist on Linux, where systemd prefers them.
+            #[cfg(target_os = "linux")]
+            Some(name) => {
+                use std::os::linux::net::SocketAddrExt;
+                use std::os::unix::net::SocketAddr;
+
+                let addr = SocketAddr::from_abstract_name(name)?;
+                socket.send_to_addr(state.as_bytes(), &addr)?;
+            }
+            _ => {
+                socket.send_to(state.as_bytes(), &path)?;
+            }
+        }
+
+        Ok(())
+    };
+
+    if let Err(e) = send() {
+        warn!("could not notify systemd: {}", e);
+    }
+}
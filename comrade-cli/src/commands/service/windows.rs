@@ -0,0 +1,139 @@
+use std::env;
+use std::ffi::OsString;
+use std::sync::mpsc;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use log::error;
+use windows_service::service::{
+    ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode,
+    ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+use windows_service::{define_windows_service, service_dispatcher};
+
+use crate::commands::service::{serve, Request, Status};
+use crate::commands::{Options, Result};
+use crate::errors::describe_error;
+
+const SERVICE_NAME: &str = "comrade";
+const SERVICE_DISPLAY_NAME: &str = "Comrade";
+
+// Windows runs the service on a thread of its own that can only be handed
+// arguments, and those are the ones it was installed with, so where the
+// configuration lives, which profile, and whether it's read-only, is left
+// here for it instead.
+static OPTIONS: OnceLock<Options> = OnceLock::new();
+
+define_windows_service!(ffi_service_main, service_main);
+
+/// Installs a service that starts with the computer and runs this copy of
+/// Comrade, with the given configuration directory and profile.
+pub(super) fn install(options: Options) -> Result<()> {
+    let manager = ServiceManager::local_computer(
+        None::<&str>,
+        ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+    )?;
+
+    let mut launch_arguments = Vec::new();
+    if let Some(dir) = options.config_dir {
+        launch_arguments.push(OsString::from("--config-dir"));
+        launch_arguments.push(dir.into_os_string());
+    }
+    if let Some(profile) = options.profile {
+        launch_arguments.push(OsString::from("--profile"));
+        launch_arguments.push(OsString::from(profile));
+    }
+    if options.read_only {
+        launch_arguments.push(OsString::from("--read-only"));
+    }
+    launch_arguments.extend(["service", "run", "--windows-service"].map(OsString::from));
+
+    let info = ServiceInfo {
+        name: OsString::from(SERVICE_NAME),
+        display_name: OsString::from(SERVICE_DISPLAY_NAME),
+        service_type: ServiceType::OWN_PROCESS,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path: env::current_exe()?,
+        launch_arguments,
+        dependencies: vec![],
+        account_name: None,
+        account_password: None,
+    };
+    manager.create_service(&info, ServiceAccess::QUERY_STATUS)?;
+
+    println!("Installed the {} service", SERVICE_NAME);
+
+    Ok(())
+}
+
+/// Hands this thread over to Windows, which calls back into `service_main`
+/// and only returns once the service has stopped.
+pub(super) fn dispatch(options: Options) -> Result<()> {
+    OPTIONS.get_or_init(|| options);
+    service_dispatcher::start(SERVICE_NAME, ffi_service_main)?;
+
+    Ok(())
+}
+
+fn service_main(_arguments: Vec<OsString>) {
+    if let Err(e) = run_service() {
+        error!("service failed: {}", describe_error(&e));
+    }
+}
+
+fn run_service() -> Result<()> {
+    let (sender, requests) = mpsc::channel();
+    let handle = service_control_handler::register(SERVICE_NAME, move |control| {
+        let request = match control {
+            ServiceControl::Stop | ServiceControl::Shutdown => Request::Stop,
+            ServiceControl::ParamChange => Request::Reload,
+            ServiceControl::Interrogate => return ServiceControlHandlerResult::NoError,
+            _ => return ServiceControlHandlerResult::NotImplemented,
+        };
+        let _ = sender.send(request);
+        ServiceControlHandlerResult::NoError
+    })?;
+
+    let report = |state: ServiceState, exit_code: ServiceExitCode| {
+        let controls_accepted = match state {
+            ServiceState::Running => {
+                ServiceControlAccept::STOP
+                    | ServiceControlAccept::SHUTDOWN
+                    | ServiceControlAccept::PARAM_CHANGE
+            }
+            _ => ServiceControlAccept::empty(),
+        };
+        let res = handle.set_service_status(ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted,
+            exit_code,
+            checkpoint: 0,
+            wait_hint: Duration::default(),
+            process_id: None,
+        });
+        if let Err(e) = res {
+            error!("could not report service status: {}", e);
+        }
+    };
+
+    let options = OPTIONS.get().cloned().unwrap_or_default();
+    let res = serve(options, requests, |status| {
+        let state = match status {
+            Status::Running | Status::Reloading => ServiceState::Running,
+            Status::Stopping => ServiceState::StopPending,
+        };
+        report(state, ServiceExitCode::NO_ERROR);
+    });
+
+    let exit_code = match res {
+        Ok(()) => ServiceExitCode::NO_ERROR,
+        Err(_) => ServiceExitCode::ServiceSpecific(1),
+    };
+    report(ServiceState::Stopped, exit_code);
+
+    res
+}
@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use clap::Args;
+
+use comrade::{Comrade, SoakOptions, SoakReport};
+
+#[cfg(feature = "count-allocations")]
+use crate::allocations;
+use crate::commands::Result;
+use crate::errors::CommandError;
+
+#[derive(Debug, Args)]
+pub(crate) struct SoakCommand {
+    /// How many lines a second to make up, across every character
+    #[clap(long, default_value_t = 5000)]
+    rate: u32,
+
+    /// How many made up characters to spread the lines across
+    #[clap(long, default_value_t = 6)]
+    characters: usize,
+
+    /// How long to keep it up for, e.g. `5m`
+    #[clap(long, default_value = "60s")]
+    duration: humantime::Duration,
+
+    /// A log to take the lines from, instead of a built in mix of chatter
+    /// and combat
+    #[clap(long)]
+    log: Option<PathBuf>,
+
+    #[clap(long)]
+    json: bool,
+}
+
+impl SoakCommand {
+    pub(crate) fn run(self, comrade: &Comrade) -> Result<()> {
+        let options = SoakOptions {
+            rate: self.rate,
+            characters: self.characters,
+            duration: self.duration.into(),
+            log: self.log,
+            #[cfg(feature = "count-allocations")]
+            allocations: Some(allocations::count),
+            #[cfg(not(feature = "count-allocations"))]
+            allocations: None,
+        };
+        let report = comrade.soak(&options)?;
+
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print_report(&report);
+        }
+
+        if !report.kept_up() {
+            return Err(CommandError::FellBehind {
+                dropped: report.dropped,
+                lines: report.lines,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+fn print_report(report: &SoakReport) {
+    println!(
+        "{} line(s) across {} character(s) over {:.1}s, asked for {}/s",
+        report.lines, report.characters, report.seconds, report.rate
+    );
+    println!("Throughput: {:.0} lines/s", report.throughput());
+    println!("Dropped:    {}", report.dropped);
+    println!("Backlog:    {} line(s) at most", report.backlog);
+    println!("Events:     {}", report.events);
+    if let Some(per_line) = report.allocations_per_line() {
+        println!("Allocs:     {:.1} per line", per_line);
+    }
+    match report.memory_growth() {
+        Some(growth) => println!("Memory:     {:+.1} MiB", growth as f64 / 1024.0 / 1024.0),
+        None => println!("Memory:     unknown"),
+    }
+}
@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+
+use crate::commands::Result;
+use crate::errors::CommandError;
+
+pub(crate) fn run(pack: PathBuf, fixtures: PathBuf, json: bool) -> Result<()> {
+    let results = comrade::test_pack(pack.as_path(), fixtures.as_path())?;
+    let failed = results.iter().filter(|r| !r.passed()).count();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    } else {
+        for result in results.iter() {
+            if result.passed() {
+                println!("ok    {} ({} fired)", result.name, result.fired);
+            } else {
+                println!("FAIL  {}", result.name);
+                for failure in result.failures.iter() {
+                    println!("        {}", failure);
+                }
+            }
+        }
+        println!("{} passed, {} failed", results.len() - failed, failed);
+    }
+
+    if failed > 0 {
+        return Err(CommandError::FixturesFailed {
+            failed,
+            total: results.len(),
+        });
+    }
+
+    Ok(())
+}
@@ -0,0 +1,639 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::io::prelude::*;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::Subcommand;
+use serde::Serialize;
+
+use comrade::{
+    Action, CaptureGroup, CapturePreview, CharacterId, Comrade, Priority, Trigger, TriggerChange,
+    TriggerFilter, TriggerId, TriggerRef, TriggerSource, TriggerStyle,
+};
+
+use crate::commands::{print_table, Result};
+use crate::errors::CommandError;
+
+#[derive(Debug, Subcommand)]
+pub(crate) enum TriggersCommand {
+    /// List triggers, optionally narrowed down by a search filter
+    List {
+        /// Search filter, e.g. `tag:raid source:local flurry`
+        #[clap(long)]
+        filter: Option<String>,
+
+        /// Only report enablement for this character
+        #[clap(long)]
+        character: Option<String>,
+
+        #[clap(long)]
+        json: bool,
+    },
+    /// Show a single trigger in detail
+    Show {
+        /// The trigger to show, as `source/id` or just `id` for local triggers
+        trigger: String,
+
+        /// How many recent matching lines from the logs to show what the
+        /// pattern captured from, or 0 to skip searching the logs
+        #[clap(long, default_value_t = 3)]
+        samples: usize,
+
+        #[clap(long)]
+        json: bool,
+    },
+    /// Enable a trigger
+    Enable {
+        trigger: String,
+
+        /// Only enable the trigger for these characters, defaults to all
+        #[clap(long)]
+        character: Vec<String>,
+    },
+    /// Disable a trigger
+    Disable {
+        trigger: String,
+
+        /// Only disable the trigger for these characters, defaults to all
+        #[clap(long)]
+        character: Vec<String>,
+    },
+    /// Remove a local trigger
+    Rm { trigger: String },
+    /// Add a new local trigger
+    Add {
+        id: String,
+
+        #[clap(long)]
+        name: String,
+
+        /// The regex that log lines are matched against
+        #[clap(long)]
+        pattern: String,
+
+        #[clap(long, default_value = "")]
+        comment: String,
+
+        #[clap(long)]
+        tag: Vec<String>,
+
+        /// One of low, normal, or high
+        #[clap(long, default_value = "normal")]
+        priority: Priority,
+
+        /// Keep repeating alerts until they're acknowledged
+        #[clap(long)]
+        acknowledge: bool,
+
+        /// How often unacknowledged alerts repeat, in seconds
+        #[clap(long, requires = "acknowledge")]
+        repeat: Option<u64>,
+
+        /// Display this text when the trigger matches
+        #[clap(long)]
+        display_text: Option<String>,
+
+        /// Start a countdown with this text when the trigger matches
+        #[clap(long, requires = "duration")]
+        countdown: Option<String>,
+
+        /// The duration of the countdown, in seconds
+        #[clap(long)]
+        duration: Option<u64>,
+
+        /// The timer category of the countdown, e.g. `buffs`
+        #[clap(long, requires = "countdown")]
+        category: Option<String>,
+    },
+    /// Compare two versions of a trigger file, such as a trigger pack update
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+
+        /// Interactively choose which changes to apply to the local triggers
+        #[clap(long, conflicts_with = "json")]
+        merge: bool,
+
+        /// Apply every change without asking, when merging
+        #[clap(long, requires = "merge")]
+        yes: bool,
+
+        #[clap(long)]
+        json: bool,
+    },
+}
+
+#[derive(Serialize)]
+struct TriggerSummary {
+    trigger: String,
+    name: String,
+    tags: Vec<String>,
+    enabled: BTreeMap<String, bool>,
+}
+
+#[derive(Serialize)]
+struct TriggerDetails {
+    trigger: String,
+    enabled: BTreeMap<String, bool>,
+    groups: Vec<CaptureGroup>,
+    samples: Vec<CapturePreview>,
+    #[serde(flatten)]
+    details: Trigger,
+}
+
+impl TriggersCommand {
+    pub(crate) fn run(self, comrade: &Comrade) -> Result<()> {
+        match self {
+            TriggersCommand::List {
+                filter,
+                character,
+                json,
+            } => list(comrade, filter, character, json),
+            TriggersCommand::Show {
+                trigger,
+                samples,
+                json,
+            } => show(comrade, trigger, samples, json),
+            TriggersCommand::Enable { trigger, character } => {
+                set_enabled(comrade, trigger, character, true)
+            }
+            TriggersCommand::Disable { trigger, character } => {
+                set_enabled(comrade, trigger, character, false)
+            }
+            TriggersCommand::Rm { trigger } => {
+                let tref: TriggerRef = trigger.parse()?;
+                comrade.remove_trigger(&tref)?;
+                println!("removed {}", tref);
+                Ok(())
+            }
+            TriggersCommand::Add {
+                id,
+                name,
+                pattern,
+                comment,
+                tag,
+                priority,
+                acknowledge,
+                repeat,
+                display_text,
+                countdown,
+                duration,
+                category,
+            } => {
+                let mut actions = Vec::new();
+                if let Some(text) = display_text {
+                    actions.push(Action::DisplayText {
+                        text,
+                        delay: None,
+                        collapse: None,
+                    });
+                }
+                if let (Some(text), Some(duration)) = (countdown, duration) {
+                    actions.push(Action::Countdown {
+                        text,
+                        duration: Duration::from_secs(duration),
+                        delay: None,
+                        category,
+                        icon: None,
+                        spell: None,
+                        end_early_text: None,
+                        restart: false,
+                        repeat: 0,
+                    });
+                }
+
+                let trigger = Trigger {
+                    name,
+                    comment,
+                    search_text: pattern,
+                    tags: tag,
+                    disabled: false,
+                    priority,
+                    style: TriggerStyle::default(),
+                    acknowledge,
+                    repeat: repeat.map(Duration::from_secs),
+                    captures: BTreeMap::new(),
+                    conditions: Vec::new(),
+                    active: Vec::new(),
+                    actions,
+                };
+
+                comrade.add_trigger(TriggerId::new(id.as_str()), trigger)?;
+                println!("added local/{}", id);
+                Ok(())
+            }
+            TriggersCommand::Diff {
+                old,
+                new,
+                merge,
+                yes,
+                json,
+            } => diff(comrade, old, new, merge, yes, json),
+        }
+    }
+}
+
+fn list(
+    comrade: &Comrade,
+    filter: Option<String>,
+    character: Option<String>,
+    json: bool,
+) -> Result<()> {
+    let filter: TriggerFilter = match filter {
+        Some(filter) => filter.parse()?,
+        None => TriggerFilter::default(),
+    };
+    let characters = characters(comrade, character.into_iter().collect())?;
+
+    let summaries: Vec<TriggerSummary> = comrade
+        .search_triggers(&filter)
+        .into_iter()
+        .map(|(tref, trigger)| TriggerSummary {
+            enabled: enabled(comrade, &characters, &tref),
+            trigger: tref.to_string(),
+            name: trigger.name,
+            tags: trigger.tags,
+        })
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&summaries)?);
+    } else {
+        let rows: Vec<Vec<String>> = summaries
+            .iter()
+            .map(|s| {
+                let enabled = s.enabled.values().filter(|e| **e).count();
+                vec![
+                    s.trigger.clone(),
+                    s.name.clone(),
+                    format!("{}/{}", enabled, s.enabled.len()),
+                    s.tags.join(", "),
+                ]
+            })
+            .collect();
+        print_table(&["TRIGGER", "NAME", "ENABLED", "TAGS"], &rows);
+    }
+
+    Ok(())
+}
+
+fn show(comrade: &Comrade, trigger: String, samples: usize, json: bool) -> Result<()> {
+    let tref: TriggerRef = trigger.parse()?;
+    let details = comrade
+        .trigger(&tref)
+        .ok_or_else(|| CommandError::UnknownTrigger(tref.to_string()))?;
+    let characters = characters(comrade, Vec::new())?;
+    let enabled = enabled(comrade, &characters, &tref);
+    let groups = comrade::explain_pattern(details.search_text.as_str())?;
+    let samples = if samples > 0 && !groups.is_empty() {
+        comrade.recent_captures(details.search_text.as_str(), samples)?
+    } else {
+        Vec::new()
+    };
+
+    if json {
+        let details = TriggerDetails {
+            trigger: tref.to_string(),
+            enabled,
+            groups,
+            samples,
+            details,
+        };
+        println!("{}", serde_json::to_string_pretty(&details)?);
+        return Ok(());
+    }
+
+    println!("Trigger:  {}", tref);
+    println!("Name:     {}", details.name);
+    if !details.comment.is_empty() {
+        println!("Comment:  {}", details.comment);
+    }
+    println!("Pattern:  {}", details.search_text);
+    for group in groups.iter() {
+        match group.name.as_ref() {
+            Some(name) => println!("Group:    ${} ({}) is {}", group.index, name, group.pattern),
+            None => println!("Group:    ${} is {}", group.index, group.pattern),
+        }
+    }
+    for (name, capture) in details.captures.iter() {
+        println!("Capture:  {} is {}", name, capture);
+    }
+    for condition in details.conditions.iter() {
+        println!("Where:    {}", condition);
+    }
+    for window in details.active.iter() {
+        println!("Active:   {}", window);
+    }
+    println!("Tags:     {}", details.tags.join(", "));
+    println!("Priority: {}", details.priority);
+    if let Some(repeat) = details.repeat_interval() {
+        println!("Repeats:  every {}s until acknowledged", repeat.as_secs());
+    }
+    println!("Actions:");
+    for action in details.actions.iter() {
+        println!("  - {}", describe_action(action));
+    }
+    println!("Enabled:");
+    for (character, enabled) in enabled.iter() {
+        println!("  {}: {}", character, if *enabled { "yes" } else { "no" });
+    }
+    if !samples.is_empty() {
+        println!("Recent matches:");
+    }
+    for sample in samples.iter() {
+        println!(
+            "  [{}] {}: {}",
+            sample.timestamp.as_deref().unwrap_or_default(),
+            sample.character.as_deref().unwrap_or_default(),
+            sample.message
+        );
+        for (group, captured) in groups.iter().zip(sample.captures.iter()) {
+            match captured {
+                Some(captured) => println!("    ${} = {:?}", group.index, captured),
+                None => println!("    ${} did not match", group.index),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn set_enabled(
+    comrade: &Comrade,
+    trigger: String,
+    characters: Vec<String>,
+    enabled: bool,
+) -> Result<()> {
+    let tref: TriggerRef = trigger.parse()?;
+    let characters = if characters.is_empty() {
+        Vec::new()
+    } else {
+        self::characters(comrade, characters)?
+    };
+
+    comrade.set_trigger_enabled(&tref, &characters, enabled)?;
+    println!("{} {}", if enabled { "enabled" } else { "disabled" }, tref);
+
+    Ok(())
+}
+
+fn diff(
+    comrade: &Comrade,
+    old: PathBuf,
+    new: PathBuf,
+    merge: bool,
+    yes: bool,
+    json: bool,
+) -> Result<()> {
+    let changes = comrade::diff_trigger_files(old.as_path(), new.as_path())?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&changes)?);
+        return Ok(());
+    }
+
+    if changes.is_empty() {
+        println!("no changes");
+        return Ok(());
+    }
+
+    if !merge {
+        for change in changes.iter() {
+            println!("{}", describe_change(change));
+        }
+        return Ok(());
+    }
+
+    let mut accepted = Vec::new();
+    let mut accept_all = yes;
+    for change in changes.into_iter() {
+        let local = comrade.trigger(&TriggerRef::new(TriggerSource::Local, change.id().clone()));
+
+        // Nothing to do if the local triggers already look like the result of
+        // this change.
+        if local.as_ref() == change.trigger() {
+            continue;
+        }
+
+        println!("{}", describe_change(&change));
+        if let Some(note) = local_note(&change, local.as_ref()) {
+            println!("    ({})", note);
+        }
+
+        if !accept_all {
+            match prompt("apply this change? [y/N/a/q] ")?.as_str() {
+                "y" | "yes" => {}
+                "a" | "all" => accept_all = true,
+                "q" | "quit" => break,
+                _ => continue,
+            }
+        }
+
+        accepted.push(change);
+    }
+
+    if accepted.is_empty() {
+        println!("no changes applied");
+    } else {
+        comrade.merge_triggers(&accepted)?;
+        println!("applied {} change(s) to the local triggers", accepted.len());
+    }
+
+    Ok(())
+}
+
+fn describe_change(change: &TriggerChange) -> String {
+    match change {
+        TriggerChange::Added { id, trigger } => format!("+ {}  {}", id, trigger.name),
+        TriggerChange::Changed { id, new, .. } => format!(
+            "~ {}  {}  [{}]",
+            id,
+            new.name,
+            change.changed_fields().join(", ")
+        ),
+        TriggerChange::Removed { id, trigger } => format!("- {}  {}", id, trigger.name),
+    }
+}
+
+/// Explains how the local copy of a trigger differs from what the change
+/// expects it to be, since applying the change will overwrite it.
+fn local_note(change: &TriggerChange, local: Option<&Trigger>) -> Option<&'static str> {
+    match (change, local) {
+        (TriggerChange::Added { .. }, Some(_)) => {
+            Some("a different local trigger will be replaced")
+        }
+        (TriggerChange::Changed { .. }, None) => Some("not installed locally, it will be added"),
+        (TriggerChange::Changed { old, .. }, Some(local)) if local != old => {
+            Some("modified locally, local changes will be lost")
+        }
+        (TriggerChange::Removed { trigger, .. }, Some(local)) if local != trigger => {
+            Some("modified locally, local changes will be lost")
+        }
+        _ => None,
+    }
+}
+
+fn prompt(message: &str) -> Result<String> {
+    print!("{}", message);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+
+    Ok(answer.trim().to_lowercase())
+}
+
+/// Resolves the given character names, or group names, or every configured
+/// character if none were given.
+pub(crate) fn characters(comrade: &Comrade, names: Vec<String>) -> Result<Vec<CharacterId>> {
+    if names.is_empty() {
+        return Ok(comrade.characters().into_iter().map(|(id, _)| id).collect());
+    }
+
+    let mut ids = Vec::new();
+    for name in names {
+        let group = comrade
+            .character_ids(name.as_str())
+            .ok_or(CommandError::UnknownCharacter(name))?;
+        for id in group {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+
+    Ok(ids)
+}
+
+fn enabled(
+    comrade: &Comrade,
+    characters: &[CharacterId],
+    tref: &TriggerRef,
+) -> BTreeMap<String, bool> {
+    characters
+        .iter()
+        .map(|id| (id.to_string(), comrade.is_trigger_enabled(id, tref)))
+        .collect()
+}
+
+fn describe_action(action: &Action) -> String {
+    match action {
+        Action::DisplayText {
+            text,
+            delay,
+            collapse,
+        } => {
+            let mut description = format!("DisplayText {:?}", text);
+            if let Some(delay) = delay {
+                description.push_str(format!(" after {}s", delay.as_secs()).as_str());
+            }
+            if let Some(collapse) = collapse {
+                description.push_str(
+                    format!(", collapsing repeats within {}s", collapse.as_secs()).as_str(),
+                );
+            }
+            description
+        }
+        Action::Countdown {
+            text,
+            duration,
+            delay,
+            category,
+            icon,
+            spell,
+            end_early_text,
+            restart,
+            repeat,
+        } => {
+            let mut description = format!("Countdown {:?} for {}s", text, duration.as_secs());
+            if let Some(delay) = delay {
+                description.push_str(format!(" after {}s", delay.as_secs()).as_str());
+            }
+            if let Some(category) = category {
+                description.push_str(format!(" in {}", category).as_str());
+            }
+            if let Some(name) = spell.as_ref().and_then(|s| s.name.as_ref()) {
+                description.push_str(format!(" for spell {:?}", name).as_str());
+            }
+            if let Some(icon) = icon {
+                description.push_str(format!(" with icon {}", icon).as_str());
+            }
+            if let Some(text) = end_early_text {
+                description.push_str(format!(", ending early on {:?}", text).as_str());
+            }
+            if *restart {
+                description.push_str(", restarting on a match");
+            }
+            if *repeat > 0 {
+                description.push_str(format!(", repeating {} times", repeat).as_str());
+            }
+            description
+        }
+        Action::RecordWaypoint { name } => format!("RecordWaypoint {:?}", name),
+        Action::RecallWaypoint { name } => format!("RecallWaypoint {:?}", name),
+        Action::Signal {
+            output,
+            morse,
+            pulses,
+            delay,
+            ..
+        } => {
+            let mut description = match morse {
+                Some(morse) => format!("Signal {} with {:?} in morse", output, morse),
+                None => format!("Signal {} with pulses {:?}", output, pulses),
+            };
+            if let Some(delay) = delay {
+                description.push_str(format!(" after {}s", delay.as_secs()).as_str());
+            }
+            description
+        }
+        Action::Custom {
+            executor,
+            options,
+            delay,
+        } => {
+            let options: Vec<String> = options
+                .iter()
+                .map(|(k, v)| format!("{}={:?}", k, v))
+                .collect();
+            let mut description = format!("Custom {} with {}", executor, options.join(" "));
+            if let Some(delay) = delay {
+                description.push_str(format!(" after {}s", delay.as_secs()).as_str());
+            }
+            description
+        }
+        Action::FireTrigger { id, delay } => match delay {
+            Some(delay) => format!("FireTrigger {} after {}s", id, delay.as_secs()),
+            None => format!("FireTrigger {}", id),
+        },
+        Action::Random { choices } => {
+            let total: u32 = choices.iter().map(|c| c.weight).sum();
+            let described: Vec<String> = choices
+                .iter()
+                .map(|c| format!("{} ({}/{})", describe_action(&c.action), c.weight, total))
+                .collect();
+            format!("Random of {}", described.join(", "))
+        }
+        Action::If {
+            when,
+            then,
+            otherwise,
+        } => {
+            let describe_all = |actions: &[Action]| match actions {
+                [] => "nothing".to_string(),
+                actions => actions
+                    .iter()
+                    .map(describe_action)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            };
+            format!(
+                "If {} then {} else {}",
+                when,
+                describe_all(then),
+                describe_all(otherwise)
+            )
+        }
+    }
+}
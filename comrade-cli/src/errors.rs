@@ -1,5 +1,20 @@
+use std::error::Error as StdError;
+
 use thiserror::Error;
 
+/// Formats an error along with all of its sources, since many of our errors
+/// only make sense with the context of what caused them.
+pub(crate) fn describe_error(error: &dyn StdError) -> String {
+    let mut description = error.to_string();
+    let mut source = error.source();
+    while let Some(e) = source {
+        description.push_str(": ");
+        description.push_str(e.to_string().as_str());
+        source = e.source();
+    }
+    description
+}
+
 #[derive(Error, Debug)]
 pub(crate) enum TerminalError {
     #[error(transparent)]
@@ -14,4 +29,71 @@ pub(crate) enum ApplicationError {
 
     #[error(transparent)]
     ComradeError(#[from] comrade::errors::ComradeError),
+
+    #[error("could not save ui state")]
+    StateError(#[from] StateError),
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum StateError {
+    #[error(transparent)]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    IOError(#[from] std::io::Error),
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum ExportError {
+    #[error("unknown export format {0:?}, expected csv or json")]
+    UnknownFormat(String),
+
+    #[error(transparent)]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    IOError(#[from] std::io::Error),
+}
+
+#[derive(Error, Debug)]
+pub(crate) enum CommandError {
+    #[error("unknown character {0}")]
+    UnknownCharacter(String),
+
+    #[error("unknown trigger {0}")]
+    UnknownTrigger(String),
+
+    #[error("{failed} of {total} fixture(s) failed")]
+    FixturesFailed { failed: usize, total: usize },
+
+    #[error("no manifest of required triggers has been imported")]
+    NoManifest,
+
+    #[error("{issues} required trigger issue(s)")]
+    NotCompliant { issues: usize },
+
+    #[error("fell behind, dropping {dropped} of {lines} line(s)")]
+    FellBehind { dropped: u64, lines: u64 },
+
+    #[error(transparent)]
+    TriggerError(#[from] comrade::errors::TriggerError),
+
+    #[error(transparent)]
+    HistoryError(#[from] comrade::errors::HistoryError),
+
+    #[error(transparent)]
+    ComradeError(#[from] comrade::errors::ComradeError),
+
+    #[error("could not serialize output")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("could not set up logging: {0}")]
+    LoggingError(anyhow::Error),
+
+    #[cfg(windows)]
+    #[error(transparent)]
+    WindowsServiceError(#[from] windows_service::Error),
+
+    #[error(transparent)]
+    IOError(#[from] std::io::Error),
 }
@@ -0,0 +1,58 @@
+//! Internal logs normally only end up in the logs tab, but they can also be
+//! written out to a file so that problems can still be looked into when the
+//! TUI isn't around to show them, e.g. a crash on exit.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use log::LevelFilter;
+
+const LOG_DIRNAME: &str = "logs";
+const LOG_FILENAME: &str = "comrade.log";
+/// How many of the previous log files to keep around.
+const KEEP_LOGS: usize = 5;
+
+pub(crate) fn init(level: LevelFilter) -> anyhow::Result<()> {
+    tui_logger::init_logger(level)?;
+    tui_logger::set_default_level(level);
+
+    Ok(())
+}
+
+/// Moves logs along to the log file, which otherwise only happens as the logs
+/// tab is drawn, so it's up to anything running without the TUI to call this.
+pub(crate) fn flush() {
+    tui_logger::move_events();
+}
+
+/// Starts writing logs to a file within the data directory, returning where.
+/// Each launch gets a new file, with the previous ones being numbered.
+pub(crate) fn log_to_file(data_dir: &Path) -> io::Result<PathBuf> {
+    let dir = data_dir.join(LOG_DIRNAME);
+    fs::create_dir_all(dir.as_path())?;
+    rotate(dir.as_path())?;
+
+    let filename = dir.join(LOG_FILENAME);
+    tui_logger::set_log_file(filename.to_string_lossy().as_ref())?;
+
+    Ok(filename)
+}
+
+fn rotate(dir: &Path) -> io::Result<()> {
+    let numbered = |n: usize| dir.join(format!("{}.{}", LOG_FILENAME, n));
+
+    for n in (1..KEEP_LOGS).rev() {
+        let from = numbered(n);
+        if from.exists() {
+            fs::rename(from, numbered(n + 1))?;
+        }
+    }
+
+    let current = dir.join(LOG_FILENAME);
+    if current.exists() {
+        fs::rename(current, numbered(1))?;
+    }
+
+    Ok(())
+}
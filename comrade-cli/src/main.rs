@@ -3,39 +3,161 @@
 use std::env;
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
 use std::time::Duration;
 
 use anyhow::Result;
 use clap::Parser;
+use log::{info, warn, LevelFilter};
 use path_clean::PathClean;
 
 use comrade::meta;
-use comrade::Comrade;
+use comrade::{CharacterId, Comrade, DemoLog, DEMO_CHARACTER};
 
 use crate::app::App;
+use crate::commands::{Command, Options};
 
+#[cfg(feature = "count-allocations")]
+mod allocations;
 mod app;
+mod bigtext;
+mod clock;
+mod commands;
 mod errors;
+mod logging;
 mod terminal;
 mod ui;
 
 #[derive(Debug, Parser)]
-#[clap(version)]
+#[clap(version, disable_version_flag = true)]
 struct Cli {
+    /// Print version information
+    #[clap(long, short = 'V')]
+    version: bool,
+
+    /// With --version, also print the commit, target, and compiler it was
+    /// built with, e.g. for a bug report
+    #[clap(long, requires = "version")]
+    verbose: bool,
+
     #[clap(long, default_value_t = 250)]
     tick_rate: u64,
 
+    /// Only show alerts, in big text, e.g. for a small window next to the game
+    #[clap(long)]
+    big_text: bool,
+
+    /// The most detailed level of internal logs to keep, e.g. info or debug
+    #[clap(long, default_value = "trace")]
+    log_level: LevelFilter,
+
+    /// Also write internal logs to a file in the data directory
+    #[clap(long)]
+    log_file: bool,
+
+    /// Start out switched to this group of characters, with everyone outside
+    /// of it paused
     #[clap(long)]
+    group: Option<String>,
+
+    #[clap(long, global = true)]
     config_dir: Option<PathBuf>,
+
+    /// Never write anything, e.g. when looking at someone else's profile or
+    /// a backup, so edits, syncing sources and saving the UI's state are off
+    #[clap(long, global = true)]
+    read_only: bool,
+
+    /// Use one of the profiles kept in the configuration directory, each
+    /// with its own characters, triggers, audio settings and data
+    #[clap(long, global = true)]
+    profile: Option<String>,
+
+    #[clap(subcommand)]
+    command: Option<Command>,
 }
 
 fn main() -> Result<()> {
     // Parse CLI flags/args
     let cli = Cli::parse();
 
+    if cli.version {
+        print_version(cli.verbose);
+        return Ok(());
+    }
+
+    // Get our configuration directory
+    let config_dir = match cli.config_dir {
+        Some(path) => Some(absolute_path(path)?),
+        None => None,
+    };
+
+    match cli.command {
+        Some(Command::Demo) => run_tui(
+            Duration::from_millis(cli.tick_rate),
+            cli.big_text,
+            cli.log_level,
+            false,
+            Source::Demo,
+        ),
+        Some(command) => run_command(
+            command,
+            Options {
+                config_dir,
+                profile: cli.profile,
+                read_only: cli.read_only,
+            },
+        ),
+        None => run_tui(
+            Duration::from_millis(cli.tick_rate),
+            cli.big_text,
+            cli.log_level,
+            cli.log_file,
+            Source::Config {
+                config_dir,
+                profile: cli.profile,
+                read_only: cli.read_only,
+                group: cli.group,
+            },
+        ),
+    }
+}
+
+fn print_version(verbose: bool) {
+    println!("{} {}", meta::PKG_NAME, meta::PKG_VERSION);
+    if verbose {
+        println!("commit: {}", meta::GIT_COMMIT_HASH.unwrap_or("unknown"));
+        println!("target: {} ({})", meta::TARGET, meta::PROFILE);
+        println!("features: {}", meta::FEATURES_STR);
+        println!("compiler: {}", meta::RUSTC_VERSION);
+    }
+}
+
+fn run_command(command: Command, options: Options) -> Result<()> {
+    command.run(options).map_err(From::from)
+}
+
+/// Where the terminal UI gets its configuration and characters from.
+enum Source {
+    Config {
+        config_dir: Option<PathBuf>,
+        profile: Option<String>,
+        read_only: bool,
+        group: Option<String>,
+    },
+    Demo,
+}
+
+fn run_tui(
+    tick_rate: Duration,
+    big_text: bool,
+    log_level: LevelFilter,
+    log_file: bool,
+    source: Source,
+) -> Result<()> {
     // Setup our logger
-    tui_logger::init_logger(log::LevelFilter::Trace)?;
-    tui_logger::set_default_level(log::LevelFilter::Trace);
+    logging::init(log_level)?;
 
     // Setup our terminal
     let mut term = terminal::setup_terminal()?;
@@ -44,20 +166,46 @@ fn main() -> Result<()> {
     // we can use ? without returning early, in effect we've created
     // a psuedo try ... finally block.
     let res = (|| -> Result<()> {
-        let tick_rate = Duration::from_millis(cli.tick_rate);
-
-        // Get our configuration directory
-        let config_dir = match cli.config_dir {
-            Some(path) => Some(absolute_path(path)?),
-            None => None,
-        };
-
         // Setup Comrade
-        let mut comrade = Comrade::new();
-        comrade.load(config_dir)?;
+        // The triggers are loaded once we're up and running, so that large
+        // packs don't leave the terminal blank while they load.
+        let comrade = Arc::new(Comrade::new());
+        match source {
+            Source::Config {
+                config_dir,
+                profile,
+                read_only,
+                group,
+            } => {
+                comrade.set_read_only(read_only);
+                comrade.set_profile(profile.as_deref());
+                comrade.load_config(config_dir)?;
+                if let Some(group) = group {
+                    comrade.switch_group(Some(group.as_str()))?;
+                }
+            }
+            Source::Demo => {
+                comrade.load_demo();
+                let demo = comrade.clone();
+                thread::spawn(move || {
+                    let id = CharacterId::new(DEMO_CHARACTER);
+                    for line in DemoLog::new() {
+                        demo.process_line(&id, line.as_str());
+                    }
+                });
+            }
+        }
+
+        if log_file && comrade.is_read_only() {
+            warn!("not writing logs to a file in read-only mode");
+        } else if log_file {
+            let filename = logging::log_to_file(comrade.data_dir().as_path())?;
+            info!("writing logs to {}", filename.display());
+        }
 
         // Actually run our application
         let mut app = App::new(meta::PKG_NAME_DISPLAY, comrade);
+        app.set_big_text(big_text);
         let res = app.run(&mut term, tick_rate);
 
         res.map_err(From::from)
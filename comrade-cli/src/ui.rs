@@ -1,96 +1,626 @@
+use std::collections::HashMap;
 use std::time::Duration;
 
 use humantime::format_duration;
 use tui::backend::Backend;
-use tui::layout::{Constraint, Corner, Direction, Layout, Rect};
-use tui::style::{Color, Style};
+use tui::layout::{Alignment, Constraint, Corner, Direction, Layout, Rect};
+use tui::style::{Color, Modifier, Style};
 use tui::text::{Span, Spans};
-use tui::widgets::{Block, Borders, Gauge, List, ListItem, Row, Table, Tabs};
+use tui::widgets::{
+    Block, Borders, Clear, Gauge, List, ListItem, Paragraph, Row, Table, TableState, Tabs, Wrap,
+};
 use tui::Frame;
 use tui_logger::{TuiLoggerSmartWidget, TuiWidgetState};
 
-use crate::app::{App, EventsTab, LogsTab};
+use comrade::{CharacterId, TriggerRef, WatchStatus};
+
+use crate::app::{
+    App, EventsTab, ImportStep, LogsTab, SourcesTab, Timer, TimerRow, TriggersTab, EDITOR_FIELDS,
+};
+use crate::bigtext;
+use crate::errors::describe_error;
 
 pub(crate) fn init_logger_state() -> TuiWidgetState {
     TuiWidgetState::new().set_default_display_level(log::LevelFilter::Debug)
 }
 
 pub(crate) fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    if app.big_text() {
+        draw_big_text(f, app, f.size());
+    } else {
+        draw_tabs(f, app);
+    }
+
+    if app.confirming_quit() {
+        draw_confirm_quit(f, app, f.size());
+    }
+}
+
+fn draw_tabs<B: Backend>(f: &mut Frame<B>, app: &mut App) {
     let chunks = Layout::default()
         .constraints([Constraint::Length(3), Constraint::Min(0)].as_ref())
         .split(f.size());
+    let badges = character_badges(app);
+    let badges_width = (badges.width() as u16 + 2).min(chunks[0].width / 2);
+    let header = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(badges_width)].as_ref())
+        .split(chunks[0]);
     let titles = app
         .tabs()
         .titles()
         .iter()
         .map(|t| Spans::from(Span::styled(*t, Style::default().fg(Color::Green))))
         .collect();
+    let loading = match app.loading() {
+        Some((loaded, total)) if total > 0 => {
+            format!(" loading triggers, {}/{} sources...", loaded, total)
+        }
+        Some(_) => " loading triggers...".to_string(),
+        None => String::new(),
+    };
+    let update = match app.update() {
+        Some(release) => format!(" v{} available", release.version),
+        None => String::new(),
+    };
+    let group = match (app.comrade().profile(), app.comrade().active_group()) {
+        (Some(profile), Some(group)) => format!(" [{}/{}]", profile, group),
+        (Some(profile), None) => format!(" [{}]", profile),
+        (None, Some(group)) => format!(" [{}]", group),
+        (None, None) => String::new(),
+    };
+    let latency = app.comrade().latency();
+    let lagging = if latency.lagging() {
+        format!(" lagging {:.1}s", latency.worst.as_secs_f64())
+    } else {
+        String::new()
+    };
+    let compliance = match app.compliance() {
+        Some(report) if !report.is_compliant() => {
+            format!(" {} required trigger issue(s)", report.issues.len())
+        }
+        _ => String::new(),
+    };
     let tabs = Tabs::new(titles)
-        .block(Block::default().borders(Borders::ALL).title(app.title()))
+        .block(Block::default().borders(Borders::ALL).title(format!(
+            "{}{} ({}, F5: mute, F6: mute tts, F7/F8: volume, F9: group){}{}{}{}",
+            app.title(),
+            group,
+            app.comrade().audio(),
+            loading,
+            lagging,
+            update,
+            compliance
+        )))
         .highlight_style(Style::default().fg(Color::Yellow))
         .select(app.tabs().index());
 
-    f.render_widget(tabs, chunks[0]);
+    f.render_widget(tabs, header[0]);
+    f.render_widget(
+        Paragraph::new(badges).block(Block::default().borders(Borders::ALL)),
+        header[1],
+    );
 
     match app.tabs().current().id() {
         "events" => draw_events_tab(f, app, chunks[1]),
+        "triggers" => draw_triggers_tab(f, app, chunks[1]),
+        "sources" => draw_sources_tab(f, app, chunks[1]),
         "logs" => draw_logs_tab(f, app, chunks[1]),
         _ => {}
     }
+
+    draw_popups(f, app, chunks[1]);
+}
+
+/// A badge for each character, so that it's obvious at a glance when one of
+/// them has stopped alerting, either because their log isn't being read or
+/// because there are alerts for them that haven't been acknowledged.
+fn character_badges(app: &App) -> Spans<'static> {
+    let characters = app.comrade().characters();
+    let mut spans = Vec::new();
+
+    for (id, status) in app.comrade().watch_status() {
+        let character = match characters.iter().find(|(c, _)| *c == id) {
+            Some((_, character)) => character,
+            None => continue,
+        };
+
+        if !spans.is_empty() {
+            spans.push(Span::raw(" "));
+        }
+
+        let (color, text) = match status {
+            WatchStatus::Watching => (Color::Green, character.display_name().to_string()),
+            status => (
+                match status {
+                    WatchStatus::Lagging => Color::Yellow,
+                    WatchStatus::Paused => Color::DarkGray,
+                    _ => Color::Red,
+                },
+                format!("{} ({})", character.display_name(), status),
+            ),
+        };
+        spans.push(Span::styled(text, Style::default().fg(color)));
+
+        let unacknowledged = app.alerts().unacknowledged(character);
+        if unacknowledged > 0 {
+            spans.push(Span::styled(
+                format!(" !{}", unacknowledged),
+                Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+            ));
+        }
+    }
+
+    Spans::from(spans)
+}
+
+/// How many of the running timers to list when asking whether to quit.
+const CONFIRM_QUIT_TIMERS: usize = 5;
+
+fn draw_confirm_quit<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let tab: &EventsTab = app.tabs().tab("events").expect("could not find events tab");
+
+    let mut timers = tab.timers();
+    timers.sort_by_key(|t| t.ends_at);
+
+    let mut lines = vec![
+        Spans::from(format!(
+            "There {} still running, they won't be saved.",
+            match timers.len() {
+                1 => "is 1 timer".to_string(),
+                n => format!("are {} timers", n),
+            }
+        )),
+        Spans::from(""),
+    ];
+    for timer in timers.iter().take(CONFIRM_QUIT_TIMERS) {
+        lines.push(Spans::from(format!(
+            "  {} ({}) {}",
+            timer.text,
+            timer.character_name(),
+            format_remaining(timer)
+        )));
+    }
+    if timers.len() > CONFIRM_QUIT_TIMERS {
+        lines.push(Spans::from(format!(
+            "  ... and {} more",
+            timers.len() - CONFIRM_QUIT_TIMERS
+        )));
+    }
+    lines.push(Spans::from(""));
+    lines.push(Spans::from(Span::styled(
+        "Quit anyways? y: quit  n: cancel",
+        Style::default().fg(Color::Yellow),
+    )));
+
+    let popup = centered_rect(area, 60, lines.len() as u16 + 2);
+    let paragraph =
+        Paragraph::new(lines).block(Block::default().title("Quit").borders(Borders::ALL));
+
+    f.render_widget(Clear, popup);
+    f.render_widget(paragraph, popup);
+}
+
+/// Draws any high priority alerts as popups stacked in the top right corner,
+/// over whatever tab is currently being shown, newest first.
+fn draw_popups<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let width = (area.width * 40 / 100).max(30).min(area.width);
+    let mut y = area.y;
+
+    for alert in app.alerts().popups().iter().rev() {
+        let text = Paragraph::new(alert.text.as_str()).wrap(Wrap { trim: true });
+        let lines = wrapped_height(alert.text.as_str(), width.saturating_sub(2));
+        let height = lines + 3;
+        if y + height > area.y + area.height {
+            break;
+        }
+
+        let popup = Rect {
+            x: area.x + area.width - width,
+            y,
+            width,
+            height,
+        };
+        y += height;
+
+        let color = alert
+            .color
+            .as_deref()
+            .and_then(parse_color)
+            .unwrap_or(Color::Red);
+        let border = if alert.flash() {
+            Style::default().fg(color).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(color)
+        };
+        let block = Block::default()
+            .title(alert.title.as_str())
+            .borders(Borders::ALL)
+            .border_style(border);
+        let inner = block.inner(popup);
+
+        f.render_widget(Clear, popup);
+        f.render_widget(block, popup);
+
+        let chunks = Layout::default()
+            .constraints([Constraint::Min(0), Constraint::Length(1)].as_ref())
+            .split(inner);
+        f.render_widget(text, chunks[0]);
+        f.render_widget(
+            Paragraph::new("ctrl-a: acknowledge").style(Style::default().fg(Color::DarkGray)),
+            chunks[1],
+        );
+    }
+}
+
+/// Roughly how many lines the given text takes up once it's wrapped to the
+/// given width.
+fn wrapped_height(text: &str, width: u16) -> u16 {
+    let width = width.max(1) as usize;
+    text.chars().count().max(1).div_ceil(width) as u16
+}
+
+fn draw_big_text<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let alert = match app.alerts().big_text() {
+        Some(alert) => alert,
+        None => {
+            let waiting = Paragraph::new("Waiting for alerts (ctrl-b to leave big text mode)")
+                .style(Style::default().fg(Color::DarkGray))
+                .alignment(Alignment::Center);
+            f.render_widget(waiting, area);
+            return;
+        }
+    };
+
+    let lines = bigtext::render(alert.text.as_str(), area.width as usize)
+        .unwrap_or_else(|| vec![alert.text.to_string()]);
+
+    let color = alert
+        .color
+        .as_deref()
+        .and_then(parse_color)
+        .unwrap_or(Color::Red);
+    let style = if alert.flash() {
+        Style::default().fg(color).add_modifier(Modifier::BOLD)
+    } else {
+        Style::default()
+            .fg(Color::White)
+            .add_modifier(Modifier::BOLD)
+    };
+
+    // Center the text vertically as well as horizontally.
+    let height = (lines.len() as u16).min(area.height);
+    let area = Rect {
+        y: area.y + (area.height - height) / 2,
+        height,
+        ..area
+    };
+
+    let text: Vec<Spans> = lines.into_iter().map(Spans::from).collect();
+    let paragraph = Paragraph::new(text)
+        .style(style)
+        .alignment(Alignment::Center);
+    f.render_widget(paragraph, area);
 }
 
 fn draw_events_tab<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let tab: &EventsTab = app.tabs().tab("events").expect("could not find events tab");
+    let matches = match app.ui().events.matches {
+        0 => 0,
+        rows => rows + 2,
+    };
+    let broadcasts = match app.ui().events.broadcasts {
+        0 => 0,
+        _ if tab.broadcasts().is_empty() => 0,
+        rows => rows + 2,
+    };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(0), Constraint::Length(6)])
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(broadcasts),
+            Constraint::Length(matches),
+            Constraint::Length(1),
+        ])
         .split(area);
 
     draw_events_tab_overlay(f, app, chunks[0]);
-    draw_events_tab_matches(f, app, chunks[1]);
+    if broadcasts > 0 {
+        draw_events_tab_broadcasts(f, app, chunks[1]);
+    }
+    if matches > 0 {
+        draw_events_tab_matches(f, app, chunks[2]);
+    }
+    draw_events_tab_command(f, app, chunks[3]);
+}
+
+fn draw_events_tab_command<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let tab: &EventsTab = app.tabs().tab("events").expect("could not find events tab");
+
+    let line = match (tab.command(), tab.status()) {
+        (Some(command), _) => {
+            Paragraph::new(format!("/{}", command)).style(Style::default().fg(Color::Yellow))
+        }
+        (None, Some(status)) => Paragraph::new(status).style(Style::default().fg(Color::DarkGray)),
+        (None, None) => Paragraph::new(
+            "/: command (e.g. /timer 6m30s Pick respawn, /export json, /export fights csv, /export attendance, /export tradeskills, /export resists, /waypoint corpse, /snooze 10m, /profile raid)",
+        )
+        .style(Style::default().fg(Color::DarkGray)),
+    };
+
+    f.render_widget(line, area);
 }
 
 fn draw_events_tab_overlay<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
     let tab: &EventsTab = app.tabs().tab("events").expect("could not find events tab");
 
+    let characters = if tab.split() {
+        split_characters(app)
+    } else {
+        Vec::new()
+    };
+    if characters.is_empty() {
+        draw_character_overlay(f, app, None, area);
+        return;
+    }
+
+    let constraints: Vec<Constraint> = characters
+        .iter()
+        .map(|_| Constraint::Ratio(1, characters.len() as u32))
+        .collect();
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+        .constraints(constraints.as_ref())
         .split(area);
 
-    let items: Vec<ListItem> = tab.messages().into_iter().map(ListItem::new).collect();
+    for (name, area) in characters.iter().zip(chunks) {
+        draw_character_overlay(f, app, Some(name.as_str()), area);
+    }
+}
+
+/// The names of the characters that get their own pane when the events tab
+/// is split, either the ones picked in the config, by id, alias or group, or
+/// else all of them, leaving out anyone outside the active group.
+fn split_characters(app: &App) -> Vec<String> {
+    let characters = app.comrade().characters();
+    let mut picked = app.ui().events.characters.clone();
+    if picked.is_empty() {
+        picked = characters.iter().map(|(id, _)| id.to_string()).collect();
+    }
+
+    // Characters outside of the group that's been switched to are paused, so
+    // there's nothing to show for them.
+    let group = app
+        .comrade()
+        .active_group()
+        .and_then(|g| app.comrade().character_ids(g.as_str()));
+    let mut ids: Vec<CharacterId> = Vec::new();
+    for id in picked
+        .iter()
+        .filter_map(|name| app.comrade().character_ids(name))
+        .flatten()
+    {
+        if !ids.contains(&id) && group.as_ref().is_none_or(|g| g.contains(&id)) {
+            ids.push(id);
+        }
+    }
+
+    ids.iter()
+        .filter_map(|id| characters.iter().find(|(cid, _)| cid == id))
+        .map(|(_, c)| c.display_name().to_string())
+        .collect()
+}
+
+/// Draws the messages and timers, for a single character or for all of
+/// them together.
+fn draw_character_overlay<B: Backend>(
+    f: &mut Frame<B>,
+    app: &mut App,
+    character: Option<&str>,
+    area: Rect,
+) {
+    let tab: &EventsTab = app.tabs().tab("events").expect("could not find events tab");
+
+    let layout = &app.ui().events;
+    let split = layout.split.min(100);
+    let direction = if layout.stacked {
+        Direction::Vertical
+    } else {
+        Direction::Horizontal
+    };
+    let chunks = Layout::default()
+        .direction(direction)
+        .constraints(
+            [
+                Constraint::Percentage(split),
+                Constraint::Percentage(100 - split),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+
+    let mut title = match character {
+        Some(name) => format!("Messages: {}", name),
+        None => "Messages".to_string(),
+    };
+    match tab.scroll() {
+        (0, _) if character.is_none() => title.push_str(" (PgUp/PgDn: scroll)"),
+        (0, _) => {}
+        (scroll, total) => {
+            title.push_str(format!(" (scrolled back {}/{}, End: latest)", scroll, total).as_str())
+        }
+    }
+    let items: Vec<ListItem> = tab
+        .messages(character)
+        .into_iter()
+        .map(ListItem::new)
+        .collect();
     let list = List::new(items)
-        .block(Block::default().title("Messages").borders(Borders::ALL))
+        .block(Block::default().title(title).borders(Borders::ALL))
         .style(Style::default().fg(Color::White))
         .start_corner(Corner::BottomLeft);
 
     f.render_widget(list, chunks[0]);
 
-    let block = Block::default().title("Timers").borders(Borders::ALL);
-    let timer_area = block.inner(chunks[1]);
-    f.render_widget(block, chunks[1]);
+    // Every pane other than the default one is only as tall as it needs to
+    // be, the default pane gets whatever is left over.
+    let panes = tab.timer_panes(character);
+    let constraints: Vec<Constraint> = panes
+        .iter()
+        .enumerate()
+        .map(|(idx, (_, timers))| match idx {
+            0 => Constraint::Min(3),
+            _ => Constraint::Length(timers.len() as u16 + 2),
+        })
+        .collect();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints.as_ref())
+        .split(chunks[1]);
 
-    let timers = tab.timers();
+    let view = tab.timer_view();
+    for (idx, ((name, rows), area)) in panes.iter().zip(chunks).enumerate() {
+        let title = match idx {
+            0 if character.is_some() => name.clone(),
+            0 => format!(
+                "{} (s: sort by {}, g: {}, c/1-9: collapse, v: split)",
+                name,
+                view.sort(),
+                if view.by_character() {
+                    "ungroup"
+                } else {
+                    "group by character"
+                }
+            ),
+            _ => name.clone(),
+        };
+        draw_timer_pane(f, title.as_str(), rows, area);
+    }
+}
 
-    let mut constraints: Vec<Constraint> = timers.iter().map(|_| Constraint::Length(1)).collect();
+fn draw_timer_pane<B: Backend>(f: &mut Frame<B>, title: &str, rows: &[TimerRow], area: Rect) {
+    let block = Block::default().title(title).borders(Borders::ALL);
+    let timer_area = block.inner(area);
+    f.render_widget(block, area);
+
+    let mut constraints: Vec<Constraint> = rows.iter().map(|_| Constraint::Length(1)).collect();
     constraints.push(Constraint::Length(1));
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(constraints.as_ref())
         .split(timer_area);
 
-    for (idx, timer) in timers.iter().enumerate() {
-        let gauge = Gauge::default()
-            .label(format!(
-                "{} {}",
-                timer.text,
-                format_duration(Duration::from_secs(timer.remaining.as_secs()))
-            ))
-            .gauge_style(Style::default().fg(Color::Red).bg(Color::Black))
-            .percent(timer.percent());
-        f.render_widget(gauge, chunks[idx])
+    for (idx, row) in rows.iter().enumerate() {
+        match row {
+            TimerRow::Header(name) => {
+                let header = Paragraph::new(name.as_str()).style(
+                    Style::default()
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                );
+                f.render_widget(header, chunks[idx]);
+            }
+            TimerRow::Timer(timer) => {
+                let label = format!("{} {}", timer.text, format_remaining(timer));
+                f.render_widget(timer_gauge(timer, label), chunks[idx]);
+            }
+            TimerRow::Collapsed {
+                category,
+                count,
+                next,
+            } => {
+                let label = format!(
+                    "+ {} ({}), next: {} {}",
+                    category,
+                    count,
+                    next.text,
+                    format_remaining(next)
+                );
+                f.render_widget(timer_gauge(next, label), chunks[idx]);
+            }
+        }
     }
 }
 
+fn timer_gauge(timer: &Timer, label: String) -> Gauge {
+    let color = timer
+        .category
+        .as_ref()
+        .and_then(|c| c.color.as_deref())
+        .and_then(parse_color)
+        .unwrap_or(Color::Red);
+
+    Gauge::default()
+        .label(label)
+        .gauge_style(Style::default().fg(color).bg(Color::Black))
+        .percent(timer.percent())
+}
+
+/// Formats the time left on a timer, with tenths of a second once it gets
+/// down to its final seconds.
+fn format_remaining(timer: &Timer) -> String {
+    let remaining = timer.remaining();
+    if remaining < Duration::from_secs(10) {
+        format!("{:.1}s", remaining.as_secs_f64())
+    } else {
+        format_duration(Duration::from_secs(remaining.as_secs())).to_string()
+    }
+}
+
+/// Parses a color from the configuration, either a name or a `#rrggbb` hex
+/// code.
+fn parse_color(color: &str) -> Option<Color> {
+    if let Some(hex) = color.strip_prefix('#') {
+        if hex.len() != 6 {
+            return None;
+        }
+        let channel = |idx: usize| u8::from_str_radix(&hex[idx..idx + 2], 16).ok();
+        return Some(Color::Rgb(channel(0)?, channel(2)?, channel(4)?));
+    }
+
+    let color = match color.to_lowercase().replace(['-', '_', ' '], "").as_str() {
+        "black" => Color::Black,
+        "red" => Color::Red,
+        "green" => Color::Green,
+        "yellow" => Color::Yellow,
+        "blue" => Color::Blue,
+        "magenta" => Color::Magenta,
+        "cyan" => Color::Cyan,
+        "gray" | "grey" => Color::Gray,
+        "darkgray" | "darkgrey" => Color::DarkGray,
+        "lightred" => Color::LightRed,
+        "lightgreen" => Color::LightGreen,
+        "lightyellow" => Color::LightYellow,
+        "lightblue" => Color::LightBlue,
+        "lightmagenta" => Color::LightMagenta,
+        "lightcyan" => Color::LightCyan,
+        "white" => Color::White,
+        _ => return None,
+    };
+
+    Some(color)
+}
+
+fn draw_events_tab_broadcasts<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let tab: &EventsTab = app.tabs().tab("events").expect("could not find events tab");
+
+    let rows: Vec<Row> = tab.broadcasts().into_iter().map(Row::new).collect();
+    let table = Table::new(rows)
+        .header(
+            Row::new(vec!["Channel", "Speaker", "Text"])
+                .style(Style::default().fg(Color::DarkGray)),
+        )
+        .block(Block::default().title("Broadcasts").borders(Borders::ALL))
+        .style(Style::default().fg(Color::White))
+        .widths(&[
+            Constraint::Length(10),
+            Constraint::Length(20),
+            Constraint::Length(250),
+        ]);
+
+    f.render_widget(table, area);
+}
+
 fn draw_events_tab_matches<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
     let tab: &EventsTab = app.tabs().tab("events").expect("could not find events tab");
 
@@ -100,8 +630,13 @@ fn draw_events_tab_matches<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Re
             Row::new(vec!["Character", "Trigger", "Matched Text"])
                 .style(Style::default().fg(Color::DarkGray)),
         )
-        .block(Block::default().title("Triggers").borders(Borders::ALL))
+        .block(
+            Block::default()
+                .title("Triggers (n: new trigger from line, z: snooze, x: export)")
+                .borders(Borders::ALL),
+        )
         .style(Style::default().fg(Color::White))
+        .highlight_style(Style::default().fg(Color::Yellow))
         .widths(&[
             Constraint::Length(25),
             Constraint::Length(25),
@@ -109,15 +644,458 @@ fn draw_events_tab_matches<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Re
         ]);
     // .column_spacing(1);
 
-    f.render_widget(table, area);
+    let mut state = TableState::default();
+    state.select(tab.selected());
+
+    f.render_stateful_widget(table, area, &mut state);
+}
+
+fn draw_triggers_tab<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let tab: &TriggersTab = app
+        .tabs()
+        .tab("triggers")
+        .expect("could not find triggers tab");
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(area);
+
+    let (results, filter_title) = match tab.filter() {
+        Ok(filter) => (
+            app.comrade().search_triggers(&filter),
+            "Filter (/ to edit)".to_string(),
+        ),
+        Err(e) => (Vec::new(), format!("Filter ({})", e)),
+    };
+
+    let query_style = if tab.editing() {
+        Style::default().fg(Color::Yellow)
+    } else {
+        Style::default().fg(Color::White)
+    };
+    let query = Paragraph::new(tab.query())
+        .style(query_style)
+        .block(Block::default().title(filter_title).borders(Borders::ALL));
+
+    f.render_widget(query, chunks[0]);
+
+    let snoozed: HashMap<TriggerRef, Duration> =
+        app.comrade().snoozed_triggers().into_iter().collect();
+    let rows: Vec<Row> = results
+        .iter()
+        .map(|(tref, trigger)| {
+            let snooze = snoozed
+                .get(tref)
+                .map(|d| format_duration(Duration::from_secs(d.as_secs().max(1))).to_string())
+                .unwrap_or_default();
+            Row::new(vec![
+                tref.source.to_string(),
+                tref.id.to_string(),
+                trigger.name.clone(),
+                snooze,
+                trigger.tags.join(", "),
+            ])
+        })
+        .collect();
+    let table = Table::new(rows)
+        .header(
+            Row::new(vec!["Source", "Id", "Name", "Snoozed", "Tags"])
+                .style(Style::default().fg(Color::DarkGray)),
+        )
+        .block(
+            Block::default()
+                .title(format!("Triggers ({})", results.len()))
+                .borders(Borders::ALL),
+        )
+        .style(Style::default().fg(Color::White))
+        .highlight_style(Style::default().fg(Color::Yellow))
+        .widths(&[
+            Constraint::Length(20),
+            Constraint::Length(25),
+            Constraint::Length(40),
+            Constraint::Length(10),
+            Constraint::Length(100),
+        ]);
+
+    let mut state = TableState::default();
+    state.select(tab.selected(results.len()));
+
+    f.render_stateful_widget(table, chunks[1], &mut state);
+
+    tab.set_results(results.into_iter().map(|(tref, _)| tref).collect());
+
+    let status = Paragraph::new(tab.status().unwrap_or_else(|| {
+        "n: new  i: import  e/d: enable/disable  E/D: enable/disable all  z/Z: snooze/wake  del: remove  u/r: undo/redo"
+            .to_string()
+    }))
+    .style(Style::default().fg(Color::DarkGray));
+
+    f.render_widget(status, chunks[2]);
+
+    draw_trigger_editor(f, app, area);
+    draw_import_wizard(f, app, area);
+}
+
+fn draw_import_wizard<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let tab: &TriggersTab = app
+        .tabs()
+        .tab("triggers")
+        .expect("could not find triggers tab");
+    let wizard = tab.import();
+    let wizard = match *wizard {
+        Some(ref wizard) => wizard,
+        None => return,
+    };
+
+    let label = Style::default().fg(Color::DarkGray);
+    let mut lines: Vec<Spans> = Vec::new();
+    let (title, help) = match wizard.step() {
+        ImportStep::File => {
+            lines.push(Spans::from(vec![
+                Span::styled("File: ", label),
+                Span::styled(
+                    format!("{}_", wizard.path()),
+                    Style::default().fg(Color::Yellow),
+                ),
+            ]));
+            (
+                "Import from GINA: choose a file",
+                "The path to an XML export of GINA triggers  Enter: preview  Esc: cancel",
+            )
+        }
+        ImportStep::Preview => {
+            if let Some(import) = wizard.import() {
+                let groups = import.groups();
+                let mut preview = vec![Spans::from(format!(
+                    "{} triggers from {} groups, into the local triggers, disabled by default",
+                    import.triggers.len(),
+                    groups.len()
+                ))];
+                for (group, count) in groups.iter() {
+                    preview.push(Spans::from(format!("  {} ({})", group, count)));
+                }
+                if !import.warnings.is_empty() {
+                    preview.push(Spans::from(""));
+                    preview.push(Spans::from(Span::styled(
+                        format!("Warnings ({})", import.warnings.len()),
+                        Style::default().fg(Color::Yellow),
+                    )));
+                    for warning in import.warnings.iter() {
+                        preview.push(Spans::from(format!("  {}", warning)));
+                    }
+                }
+                lines.extend(preview.into_iter().skip(wizard.scroll()));
+            }
+            (
+                "Import from GINA: preview",
+                "Up/Down: scroll  Enter: choose characters  Esc: back",
+            )
+        }
+        ImportStep::Characters => {
+            lines.push(Spans::from(Span::styled("Enable for:", label)));
+            for (idx, (_, character, picked)) in wizard.characters().iter().enumerate() {
+                let style = if idx == wizard.selected() {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+                lines.push(Spans::from(Span::styled(
+                    format!(
+                        "  [{}] {} ({})",
+                        if *picked { "x" } else { " " },
+                        character.display_name(),
+                        character.server
+                    ),
+                    style,
+                )));
+            }
+            (
+                "Import from GINA: characters",
+                "Space: toggle  a: all  Enter: import  Esc: back",
+            )
+        }
+    };
+
+    // Leave room for the help line, and for the borders.
+    lines.truncate(area.height.saturating_sub(6) as usize);
+    lines.push(Spans::from(""));
+    match wizard.error() {
+        Some(error) => lines.push(Spans::from(Span::styled(
+            error.to_string(),
+            Style::default().fg(Color::Red),
+        ))),
+        None => lines.push(Spans::from(Span::styled(help, label))),
+    }
+
+    let popup = centered_rect(area, 80, lines.len() as u16 + 2);
+    let paragraph = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(Block::default().title(title).borders(Borders::ALL));
+
+    f.render_widget(Clear, popup);
+    f.render_widget(paragraph, popup);
+}
+
+fn draw_trigger_editor<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let tab: &TriggersTab = app
+        .tabs()
+        .tab("triggers")
+        .expect("could not find triggers tab");
+    let editor = tab.editor();
+    let editor = match *editor {
+        Some(ref editor) => editor,
+        None => return,
+    };
+
+    let mut lines: Vec<Spans> = Vec::new();
+    for (idx, (label, value)) in EDITOR_FIELDS.iter().zip(editor.fields()).enumerate() {
+        let style = if idx == editor.focus() {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::White)
+        };
+        let cursor = if idx == editor.focus() { "_" } else { "" };
+
+        lines.push(Spans::from(vec![
+            Span::styled(
+                format!("{:>13}: ", label),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::styled(format!("{}{}", value, cursor), style),
+        ]));
+    }
+
+    // What the pattern's groups are, and what they capture from the line
+    // that the trigger is being made from, as the pattern is typed.
+    let label = |text: &str| {
+        Span::styled(
+            format!("{:>13}: ", text),
+            Style::default().fg(Color::DarkGray),
+        )
+    };
+    let draft = editor.draft();
+    match comrade::explain_pattern(draft.pattern.as_str()) {
+        Ok(groups) => {
+            let preview = draft.sample.as_ref().and_then(|sample| {
+                comrade::preview_captures(draft.pattern.as_str(), sample.as_str())
+                    .ok()
+                    .flatten()
+            });
+            if draft.sample.is_some() && preview.is_none() {
+                lines.push(Spans::from(vec![
+                    label("Sample"),
+                    Span::styled("does not match", Style::default().fg(Color::Red)),
+                ]));
+            }
+            for group in groups.iter() {
+                let mut spans = vec![
+                    label(&format!("${}", group.index)),
+                    Span::raw(group.pattern.clone()),
+                ];
+                if let Some(name) = group.name.as_ref() {
+                    spans.push(Span::styled(
+                        format!(" ({})", name),
+                        Style::default().fg(Color::DarkGray),
+                    ));
+                }
+                match preview
+                    .as_ref()
+                    .map(|p| p.captures[group.index - 1].as_ref())
+                {
+                    Some(Some(captured)) => spans.push(Span::styled(
+                        format!(" = {:?}", captured),
+                        Style::default().fg(Color::Green),
+                    )),
+                    Some(None) => spans.push(Span::styled(
+                        " did not match",
+                        Style::default().fg(Color::DarkGray),
+                    )),
+                    None => {}
+                }
+                lines.push(Spans::from(spans));
+            }
+        }
+        Err(_) => lines.push(Spans::from(vec![
+            label("Groups"),
+            Span::styled("the pattern is not valid", Style::default().fg(Color::Red)),
+        ])),
+    }
+
+    lines.push(Spans::from(""));
+    match editor.error() {
+        Some(error) => lines.push(Spans::from(Span::styled(
+            error.to_string(),
+            Style::default().fg(Color::Red),
+        ))),
+        None => lines.push(Spans::from(Span::styled(
+            "Tab: next field  Enter: save  Esc: cancel",
+            Style::default().fg(Color::DarkGray),
+        ))),
+    }
+
+    let popup = centered_rect(area, 80, lines.len() as u16 + 2);
+    let paragraph =
+        Paragraph::new(lines).block(Block::default().title("New Trigger").borders(Borders::ALL));
+
+    f.render_widget(Clear, popup);
+    f.render_widget(paragraph, popup);
+}
+
+/// Returns a rect centered within the given area, using a percentage of the
+/// available width and a fixed height.
+fn centered_rect(area: Rect, percent_x: u16, height: u16) -> Rect {
+    let width = area.width * percent_x / 100;
+    let height = height.min(area.height);
+
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+fn draw_sources_tab<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let tab: &SourcesTab = app
+        .tabs()
+        .tab("sources")
+        .expect("could not find sources tab");
+
+    // The required triggers get a pane of their own, once there are some.
+    let required = app.compliance().map(|report| {
+        let items: Vec<ListItem> = match report.issues.as_slice() {
+            [] => vec![ListItem::new(format!(
+                "all {} required trigger(s) are on",
+                report.required
+            ))
+            .style(Style::default().fg(Color::Green))],
+            issues => issues
+                .iter()
+                .map(|issue| {
+                    ListItem::new(issue.to_string()).style(Style::default().fg(Color::Red))
+                })
+                .collect(),
+        };
+        let height = items.len().min(8) as u16 + 2;
+        let list = List::new(items).block(
+            Block::default()
+                .title(format!("Required Triggers ({})", report.manifest))
+                .borders(Borders::ALL),
+        );
+        (list, height)
+    });
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(required.as_ref().map_or(0, |(_, height)| *height)),
+            Constraint::Length(1),
+        ])
+        .split(area);
+
+    let sources = app.comrade().sources();
+    let rows: Vec<Row> = sources
+        .iter()
+        .map(|source| {
+            let last_sync = match source.last_sync.map(|t| t.elapsed()) {
+                Some(Ok(elapsed)) => format!(
+                    "{} ago",
+                    format_duration(Duration::from_secs(elapsed.as_secs()))
+                ),
+                Some(Err(_)) => "just now".to_string(),
+                None if source.url.is_some() => "never".to_string(),
+                None => "-".to_string(),
+            };
+
+            let triggers = match source.error {
+                Some(_) => "error".to_string(),
+                None => source.triggers.to_string(),
+            };
+            let row = Row::new(vec![
+                source.source.to_string(),
+                source.url.clone().unwrap_or_else(|| "-".to_string()),
+                last_sync,
+                triggers,
+                source.signature.to_string(),
+            ]);
+            if source.disabled {
+                row.style(Style::default().fg(Color::DarkGray))
+            } else if source.error.is_some() {
+                row.style(Style::default().fg(Color::Red))
+            } else {
+                row
+            }
+        })
+        .collect();
+    let table = Table::new(rows)
+        .header(
+            Row::new(vec!["Source", "URL", "Last Sync", "Triggers", "Signature"])
+                .style(Style::default().fg(Color::DarkGray)),
+        )
+        .block(
+            Block::default()
+                .title(format!("Trigger Sources ({})", sources.len()))
+                .borders(Borders::ALL),
+        )
+        .style(Style::default().fg(Color::White))
+        .highlight_style(Style::default().fg(Color::Yellow))
+        .widths(&[
+            Constraint::Length(20),
+            Constraint::Length(50),
+            Constraint::Length(20),
+            Constraint::Length(10),
+            Constraint::Length(12),
+        ]);
+
+    let selected = tab.selected(sources.len());
+    let mut state = TableState::default();
+    state.select(selected);
+
+    f.render_stateful_widget(table, chunks[0], &mut state);
+
+    // Sources that couldn't be loaded say why when they're selected.
+    let error = selected
+        .and_then(|idx| sources[idx].error.as_ref())
+        .map(|e| format!("could not load triggers: {}", describe_error(&**e)));
+
+    tab.set_sources(sources);
+
+    let status = Paragraph::new(tab.status().or(error).unwrap_or_else(|| {
+        "s: sync now  d: disable/enable  del: remove  u/r: undo/redo  (disabled sources are grayed out)"
+            .to_string()
+    }))
+    .style(Style::default().fg(Color::DarkGray));
+
+    if let Some((list, _)) = required {
+        f.render_widget(list, chunks[1]);
+    }
+    f.render_widget(status, chunks[2]);
 }
 
 fn draw_logs_tab<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
     let tab: &LogsTab = app.tabs().tab("logs").expect("could not find logs tab");
 
+    let stats = app.comrade().memory_stats();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Min(0),
+                Constraint::Length(stats.len() as u16 + 3),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+
     let block = Block::default().borders(Borders::ALL);
-    let inner_area = block.inner(area);
-    f.render_widget(block, area);
+    let inner_area = block.inner(chunks[0]);
+    f.render_widget(block, chunks[0]);
 
     let tui_sm = TuiLoggerSmartWidget::default()
         .title_target("Targets")
@@ -134,4 +1112,46 @@ fn draw_logs_tab<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
         .output_line(false)
         .state(&*tab.state());
     f.render_widget(tui_sm, inner_area);
+
+    let rows: Vec<Row> = stats
+        .iter()
+        .map(|store| {
+            let style = if store.bytes > store.budget {
+                Style::default().fg(Color::Yellow)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            Row::new(vec![
+                store.store.to_string(),
+                store.entries.to_string(),
+                format_bytes(store.bytes),
+                format_bytes(store.budget),
+                store.evicted.to_string(),
+            ])
+            .style(style)
+        })
+        .collect();
+    let table = Table::new(rows)
+        .header(
+            Row::new(vec!["Store", "Entries", "Size", "Budget", "Evicted"])
+                .style(Style::default().fg(Color::DarkGray)),
+        )
+        .block(Block::default().title("Memory").borders(Borders::ALL))
+        .widths(&[
+            Constraint::Length(10),
+            Constraint::Length(8),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(8),
+        ]);
+    f.render_widget(table, chunks[1]);
+}
+
+/// A number of bytes in whichever unit keeps it short.
+fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        b if b >= 1024 * 1024 => format!("{:.1} MiB", b as f64 / 1024.0 / 1024.0),
+        b if b >= 1024 => format!("{:.1} KiB", b as f64 / 1024.0),
+        b => format!("{} B", b),
+    }
 }
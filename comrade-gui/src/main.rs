@@ -0,0 +1,94 @@
+#![warn(clippy::disallowed_types)]
+
+//! Comrade GUI
+//!
+//! A minimal graphical overlay for Comrade, an always on top, translucent
+//! window that shows the running timers and alerts, for anyone who would
+//! rather not keep a terminal open next to the game. It's also a reference
+//! for embedding Comrade in a graphical frontend: the engine runs on its own
+//! threads, a worker moves its events into the overlay, and the UI thread
+//! acknowledges alerts through the same shared handle.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use eframe::egui;
+use parking_lot::Mutex;
+
+use comrade::Comrade;
+
+use crate::overlay::{Overlay, OverlayApp};
+
+mod overlay;
+
+/// How often the worker checks whether Comrade has events for the overlay.
+const EVENT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Parser)]
+#[clap(version)]
+struct Cli {
+    #[clap(long)]
+    config_dir: Option<PathBuf>,
+}
+
+fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    let comrade = Arc::new(Comrade::new());
+    comrade.load(cli.config_dir)?;
+    comrade.init()?;
+    comrade.start()?;
+
+    let overlay = Arc::new(Mutex::new(Overlay::default()));
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_title("Comrade")
+            .with_inner_size([320.0, 240.0])
+            .with_always_on_top()
+            .with_decorations(false)
+            .with_transparent(true),
+        ..Default::default()
+    };
+
+    let res = eframe::run_native(
+        "Comrade",
+        options,
+        Box::new({
+            let comrade = comrade.clone();
+            move |cc| {
+                let ctx = cc.egui_ctx.clone();
+                let events = comrade.clone();
+                let weak = Arc::downgrade(&overlay);
+                thread::spawn(move || forward_events(events, weak, ctx));
+
+                Ok(Box::new(OverlayApp::new(comrade, overlay)))
+            }
+        }),
+    );
+
+    comrade.stop()?;
+
+    res.map_err(|e| anyhow!("could not run the overlay: {}", e))
+}
+
+/// Moves events from Comrade into the overlay, asking for a repaint whenever
+/// there were any, until the overlay has gone away.
+fn forward_events(comrade: Arc<Comrade>, overlay: Weak<Mutex<Overlay>>, ctx: egui::Context) {
+    while let Some(overlay) = overlay.upgrade() {
+        let mut changed = false;
+        while let Some(event) = comrade.event() {
+            overlay.lock().handle(&event);
+            changed = true;
+        }
+        drop(overlay);
+
+        if changed {
+            ctx.request_repaint();
+        }
+        thread::sleep(EVENT_POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,259 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+use eframe::egui::{Color32, RichText};
+use parking_lot::Mutex;
+
+use comrade::events::{AlertId, Event, EventKind};
+use comrade::{Character, Comrade};
+
+/// How long an alert stays on screen, unless its trigger says otherwise.
+const DEFAULT_ALERT_DURATION: Duration = Duration::from_secs(5);
+
+/// The most alerts that are shown at once, older ones are dropped to make
+/// room for new ones.
+const MAX_ALERTS: usize = 5;
+
+/// How often the overlay is redrawn while something is counting down.
+const FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+const BACKGROUND: Color32 = Color32::from_rgba_premultiplied(0, 0, 0, 160);
+const ALERT_COLOR: Color32 = Color32::from_rgb(255, 210, 60);
+const LAGGING_COLOR: Color32 = Color32::from_rgb(255, 110, 90);
+
+struct Timer {
+    text: Arc<String>,
+    template: Arc<String>,
+    duration: Duration,
+    ends_at: Instant,
+    character: Option<Arc<Character>>,
+}
+
+impl Timer {
+    fn character_name(&self) -> &str {
+        self.character
+            .as_ref()
+            .map(|c| c.display_name())
+            .unwrap_or("Manual")
+    }
+
+    fn remaining(&self) -> Duration {
+        self.ends_at.saturating_duration_since(Instant::now())
+    }
+
+    /// How much of the countdown is left, from 1.0 down to 0.0.
+    fn fraction(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 0.0;
+        }
+
+        (self.remaining().as_secs_f32() / self.duration.as_secs_f32()).min(1.0)
+    }
+}
+
+struct Alert {
+    /// Set for alerts that stay on screen until they're acknowledged.
+    id: Option<AlertId>,
+    text: Arc<String>,
+    /// How many times the text has been displayed in a row.
+    count: u32,
+    until: Instant,
+}
+
+impl Alert {
+    fn expired(&self) -> bool {
+        self.id.is_none() && Instant::now() >= self.until
+    }
+}
+
+/// What the overlay is showing, built up from Comrade's events.
+#[derive(Default)]
+pub(crate) struct Overlay {
+    timers: Vec<Timer>,
+    alerts: Vec<Alert>,
+}
+
+impl Overlay {
+    pub(crate) fn handle(&mut self, event: &Event) {
+        match event.kind() {
+            EventKind::Countdown {
+                text,
+                template,
+                duration,
+                remaining,
+                character,
+                ..
+            } => {
+                let timer = Timer {
+                    text: text.clone(),
+                    template: template.clone(),
+                    duration: *duration,
+                    ends_at: event.created() + *remaining,
+                    character: character.clone(),
+                };
+
+                // Every countdown is reported again every so often, so this
+                // replaces the timer that's already being shown for it.
+                self.timers.retain(|t| {
+                    t.template != timer.template || t.character_name() != timer.character_name()
+                });
+                if !remaining.is_zero() {
+                    self.timers.push(timer);
+                    self.timers.sort_by_key(|t| t.ends_at);
+                }
+            }
+            EventKind::DisplayText {
+                text,
+                trigger,
+                alert,
+                ..
+            } => {
+                let duration = trigger
+                    .as_ref()
+                    .and_then(|t| t.style.seconds)
+                    .map(Duration::from_secs)
+                    .unwrap_or(DEFAULT_ALERT_DURATION);
+
+                // A repeat of an alert that's still waiting to be
+                // acknowledged takes the place of the original.
+                if alert.is_some() {
+                    self.alerts.retain(|a| a.id != *alert);
+                }
+                self.alerts.push(Alert {
+                    id: *alert,
+                    text: text.clone(),
+                    count: 1,
+                    until: Instant::now() + duration,
+                });
+
+                let excess = self.alerts.len().saturating_sub(MAX_ALERTS);
+                self.alerts.drain(..excess);
+            }
+            EventKind::DisplayTextRepeated {
+                text,
+                trigger,
+                count,
+                ..
+            } => {
+                let duration = trigger
+                    .style
+                    .seconds
+                    .map(Duration::from_secs)
+                    .unwrap_or(DEFAULT_ALERT_DURATION);
+
+                // The repeat keeps the alert it's counted against around for
+                // as long as the original would have been.
+                if let Some(alert) = self.alerts.iter_mut().rev().find(|a| a.text == *text) {
+                    alert.count = *count;
+                    alert.until = Instant::now() + duration;
+                }
+            }
+            EventKind::TimerEnded {
+                template,
+                character,
+                ..
+            } => {
+                let name = character.as_ref().map_or("Manual", |c| c.display_name());
+                self.timers
+                    .retain(|t| t.template != *template || t.character_name() != name);
+            }
+            EventKind::Acknowledged { alert } => self.alerts.retain(|a| a.id != Some(*alert)),
+            _ => {}
+        }
+    }
+
+    fn expire(&mut self) {
+        self.timers.retain(|t| !t.remaining().is_zero());
+        self.alerts.retain(|a| !a.expired());
+    }
+
+    fn is_empty(&self) -> bool {
+        self.timers.is_empty() && self.alerts.is_empty()
+    }
+}
+
+pub(crate) struct OverlayApp {
+    comrade: Arc<Comrade>,
+    overlay: Arc<Mutex<Overlay>>,
+}
+
+impl OverlayApp {
+    pub(crate) fn new(comrade: Arc<Comrade>, overlay: Arc<Mutex<Overlay>>) -> OverlayApp {
+        OverlayApp { comrade, overlay }
+    }
+}
+
+impl eframe::App for OverlayApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let mut overlay = self.overlay.lock();
+        overlay.expire();
+        let latency = self.comrade.latency();
+
+        let frame = egui::Frame::none().fill(BACKGROUND).inner_margin(8.0);
+        egui::CentralPanel::default().frame(frame).show(ctx, |ui| {
+            // Without any decorations, the window is moved by dragging it
+            // from anywhere that isn't a button.
+            let background = ui.interact(
+                ui.max_rect(),
+                egui::Id::new("background"),
+                egui::Sense::drag(),
+            );
+            if background.drag_started() {
+                ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
+            }
+
+            // Late callouts look just like timely ones, so being behind is
+            // shown for as long as it lasts.
+            if latency.lagging() {
+                ui.label(
+                    RichText::new(format!(
+                        "Lagging {:.1}s behind the logs",
+                        latency.worst.as_secs_f64()
+                    ))
+                    .small()
+                    .color(LAGGING_COLOR),
+                );
+            }
+
+            for alert in overlay.alerts.iter() {
+                ui.horizontal(|ui| {
+                    let text = match alert.count {
+                        1 => alert.text.to_string(),
+                        count => format!("{} x{}", alert.text, count),
+                    };
+                    ui.label(RichText::new(text).size(20.0).strong().color(ALERT_COLOR));
+                    if let Some(id) = alert.id {
+                        if ui.small_button("OK").clicked() {
+                            self.comrade.acknowledge(id);
+                        }
+                    }
+                });
+            }
+
+            if !overlay.alerts.is_empty() && !overlay.timers.is_empty() {
+                ui.separator();
+            }
+
+            for timer in overlay.timers.iter() {
+                let remaining = timer.remaining().as_secs();
+                ui.add(egui::ProgressBar::new(timer.fraction()).text(format!(
+                    "{} {}:{:02}",
+                    timer.text,
+                    remaining / 60,
+                    remaining % 60
+                )));
+            }
+        });
+
+        if !overlay.is_empty() || latency.lagging() {
+            ctx.request_repaint_after(FRAME_INTERVAL);
+        }
+    }
+
+    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+        // Fully transparent, so that only the panel's translucent background
+        // covers the game.
+        [0.0; 4]
+    }
+}
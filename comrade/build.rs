@@ -3,6 +3,7 @@ use std::fs;
 use std::io;
 use std::io::Write;
 use std::path::Path;
+use std::process::Command;
 
 use built::write_built_file;
 
@@ -14,6 +15,22 @@ fn capitalize(s: &str) -> String {
     }
 }
 
+/// The commit that we're being built from, if we're being built from a git
+/// checkout and git is around to ask.
+fn git_commit_hash() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8(output.stdout)
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
 fn write_built_file_extras() -> io::Result<()> {
     let name = capitalize(env::var("CARGO_PKG_NAME").unwrap().as_str());
 
@@ -25,6 +42,20 @@ fn write_built_file_extras() -> io::Result<()> {
     writeln!(file, "#[allow(dead_code)]")?;
     writeln!(file, "pub const PKG_NAME_DISPLAY: &str = r\"{}\";", name)?;
 
+    writeln!(
+        file,
+        "#[doc=r#\"The git commit that was built, if known.\"#]"
+    )?;
+    writeln!(file, "#[allow(dead_code)]")?;
+    match git_commit_hash() {
+        Some(hash) => writeln!(
+            file,
+            "pub const GIT_COMMIT_HASH: Option<&str> = Some(r\"{}\");",
+            hash
+        )?,
+        None => writeln!(file, "pub const GIT_COMMIT_HASH: Option<&str> = None;")?,
+    }
+
     Ok(())
 }
 
@@ -0,0 +1,155 @@
+//! Raid Attendance
+//!
+//! Raid officers take attendance by having someone `/who` the zone every so
+//! often during the raid. Every `/who` that's read from a log is kept as a
+//! roster snapshot, and the ones taken during the raid window become the
+//! checkpoints of an attendance report.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+use log::debug;
+use parking_lot::Mutex;
+use regex::Regex;
+
+use crate::config::attendance::{AttendanceConfig, TimeOfDay};
+use crate::config::CharacterId;
+use crate::watcher::LogEvent;
+
+/// How many roster snapshots are kept around, the oldest are dropped once
+/// there are more than this.
+const MAX_SNAPSHOTS: usize = 1000;
+
+lazy_static! {
+    static ref WHO_START_RE: Regex = Regex::new(r"^Players (?:on|in) EverQuest:$").unwrap();
+    // e.g. "[65 Shadow Knight] Soandso (Dark Elf) <Guild>", which may be
+    // prefixed by flags like AFK, or "[ANONYMOUS] Soandso".
+    static ref WHO_PLAYER_RE: Regex =
+        Regex::new(r"^\s*(?:(?:AFK|LFG|<LINKDEAD>)\s*)*\[[^\]]+\] (?P<name>[A-Za-z]+)").unwrap();
+    static ref WHO_END_RE: Regex =
+        Regex::new(r"^There (?:are|is) (?:\d+|no) players? in (?P<zone>.+)\.$").unwrap();
+}
+
+/// Whether a log line is part of the output of `/who`.
+pub(crate) fn is_roster_line(line: &str) -> bool {
+    line.starts_with("---")
+        || WHO_START_RE.is_match(line)
+        || WHO_PLAYER_RE.is_match(line)
+        || WHO_END_RE.is_match(line)
+}
+
+/// Everyone that a single `/who` listed.
+#[derive(Debug, Clone)]
+pub struct RosterSnapshot {
+    /// The character whose log the `/who` was in.
+    pub character: CharacterId,
+    /// When the `/who` finished, as the game wrote it.
+    pub timestamp: String,
+    pub zone: String,
+    pub players: Vec<String>,
+}
+
+impl RosterSnapshot {
+    pub fn time(&self) -> Option<TimeOfDay> {
+        TimeOfDay::from_timestamp(self.timestamp.as_str())
+    }
+}
+
+pub(crate) type RosterLog = Arc<Mutex<Vec<RosterSnapshot>>>;
+
+/// Collects the lines of each character's `/who` output as they come in,
+/// moving them to the roster log once they're complete.
+pub(crate) struct Rosters {
+    pending: HashMap<CharacterId, Vec<String>>,
+    finished: RosterLog,
+}
+
+impl Rosters {
+    pub(crate) fn new(finished: RosterLog) -> Rosters {
+        Rosters {
+            pending: HashMap::new(),
+            finished,
+        }
+    }
+
+    pub(crate) fn log_event(&mut self, event: &LogEvent) {
+        let line = event.message();
+
+        if WHO_START_RE.is_match(line) {
+            self.pending.insert((*event.id).clone(), Vec::new());
+        } else if let Some(caps) = WHO_END_RE.captures(line) {
+            if let Some(players) = self.pending.remove(&*event.id) {
+                let zone = caps.name("zone").map(|m| m.as_str()).unwrap_or_default();
+                debug!("{} players in {} for {}", players.len(), zone, event.id);
+
+                let mut finished = self.finished.lock();
+                finished.push(RosterSnapshot {
+                    character: (*event.id).clone(),
+                    timestamp: event.timestamp().to_string(),
+                    zone: zone.to_string(),
+                    players,
+                });
+                if finished.len() > MAX_SNAPSHOTS {
+                    let excess = finished.len() - MAX_SNAPSHOTS;
+                    finished.drain(..excess);
+                }
+            }
+        } else if let Some(caps) = WHO_PLAYER_RE.captures(line) {
+            if let Some(players) = self.pending.get_mut(&*event.id) {
+                players.push(caps["name"].to_string());
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PlayerAttendance {
+    pub name: String,
+    /// Whether the player was present, for each checkpoint.
+    pub present: Vec<bool>,
+}
+
+impl PlayerAttendance {
+    /// How many checkpoints the player was present for.
+    pub fn attended(&self) -> usize {
+        self.present.iter().filter(|p| **p).count()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AttendanceReport {
+    /// The roster snapshots that were taken during the raid window, oldest
+    /// first.
+    pub checkpoints: Vec<RosterSnapshot>,
+    /// Everyone that was present for at least one checkpoint, by name.
+    pub players: Vec<PlayerAttendance>,
+}
+
+pub(crate) fn report(snapshots: &[RosterSnapshot], config: &AttendanceConfig) -> AttendanceReport {
+    let checkpoints: Vec<RosterSnapshot> = snapshots
+        .iter()
+        .filter(|s| s.time().is_some_and(|t| config.in_window(t)))
+        .cloned()
+        .collect();
+
+    let mut players: BTreeMap<&str, Vec<bool>> = BTreeMap::new();
+    for (idx, checkpoint) in checkpoints.iter().enumerate() {
+        for name in checkpoint.players.iter() {
+            players
+                .entry(name.as_str())
+                .or_insert_with(|| vec![false; checkpoints.len()])[idx] = true;
+        }
+    }
+
+    AttendanceReport {
+        players: players
+            .into_iter()
+            .map(|(name, present)| PlayerAttendance {
+                name: name.to_string(),
+                present,
+            })
+            .collect(),
+        checkpoints,
+    }
+}
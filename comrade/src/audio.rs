@@ -0,0 +1,218 @@
+//! Audio Controls
+//!
+//! Comrade doesn't make any sound on its own, that's left to whatever is
+//! handling its events, but the controls for it live here so that they can be
+//! changed at runtime and every output respects the same settings. The same
+//! goes for audio cues, Comrade works out which earcon an event should get
+//! from the cue profile, see `config::accessibility`, and what to play is
+//! left to the frontend.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::config::accessibility::{CueProfile, Earcon};
+use crate::config::triggers::Priority;
+use crate::events::EventKind;
+
+const DEFAULT_VOLUME: u8 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioKind {
+    Sound,
+    Speech,
+}
+
+#[derive(Debug, Clone)]
+pub struct AudioControls {
+    muted: bool,
+    speech_muted: bool,
+    volume: u8,
+}
+
+impl Default for AudioControls {
+    fn default() -> AudioControls {
+        AudioControls {
+            muted: false,
+            speech_muted: false,
+            volume: DEFAULT_VOLUME,
+        }
+    }
+}
+
+impl AudioControls {
+    pub fn muted(&self) -> bool {
+        self.muted
+    }
+
+    pub fn toggle_muted(&mut self) {
+        self.muted = !self.muted;
+    }
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted = muted;
+    }
+
+    pub fn speech_muted(&self) -> bool {
+        self.speech_muted
+    }
+
+    pub fn toggle_speech_muted(&mut self) {
+        self.speech_muted = !self.speech_muted;
+    }
+
+    pub fn set_speech_muted(&mut self, muted: bool) {
+        self.speech_muted = muted;
+    }
+
+    /// The master volume, as a percentage.
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    /// Sets the master volume, as a percentage, up to 100.
+    pub fn set_volume(&mut self, volume: u8) {
+        self.volume = volume.min(100);
+    }
+
+    /// Raises or lowers the master volume by the given number of percentage
+    /// points, returning the new volume.
+    pub fn adjust_volume(&mut self, by: i16) -> u8 {
+        self.volume = (self.volume as i16 + by).clamp(0, 100) as u8;
+        self.volume
+    }
+
+    /// The volume that the given kind of audio should actually be played at,
+    /// taking into account whether it's been muted.
+    pub fn effective_volume(&self, kind: AudioKind) -> u8 {
+        match kind {
+            _ if self.muted => 0,
+            AudioKind::Speech if self.speech_muted => 0,
+            _ => self.volume,
+        }
+    }
+}
+
+impl fmt::Display for AudioControls {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.muted {
+            f.write_str("muted")
+        } else if self.speech_muted {
+            write!(f, "vol {}%, tts muted", self.volume)
+        } else {
+            write!(f, "vol {}%", self.volume)
+        }
+    }
+}
+
+/// How much an event matters, which decides the cue it gets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Low,
+    Normal,
+    High,
+    /// Alerts that keep repeating until they're acknowledged.
+    Critical,
+}
+
+impl Severity {
+    /// How much the given event matters, or None if it isn't worth a cue at
+    /// all, like a countdown that's still counting down.
+    pub fn of(kind: &EventKind) -> Option<Severity> {
+        match kind {
+            EventKind::DisplayText { alert: Some(_), .. } => Some(Severity::Critical),
+            EventKind::DisplayText { trigger, .. } => Some(
+                trigger
+                    .as_ref()
+                    .map_or(Severity::Normal, |t| t.priority.into()),
+            ),
+            EventKind::DisplayTextRepeated { trigger, .. } => Some(trigger.priority.into()),
+            EventKind::Countdown { remaining, .. } if remaining.is_zero() => Some(Severity::Normal),
+            EventKind::Broadcast { .. }
+            | EventKind::FightStarted { .. }
+            | EventKind::FightEnded { .. } => Some(Severity::Low),
+            _ => None,
+        }
+    }
+}
+
+impl From<Priority> for Severity {
+    fn from(priority: Priority) -> Severity {
+        match priority {
+            Priority::Low => Severity::Low,
+            Priority::Normal => Severity::Normal,
+            Priority::High => Severity::High,
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Low => f.write_str("low"),
+            Severity::Normal => f.write_str("normal"),
+            Severity::High => f.write_str("high"),
+            Severity::Critical => f.write_str("critical"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CueSound {
+    File(PathBuf),
+    Tone { frequency: u32, length: Duration },
+}
+
+/// An earcon that's ready to be played.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioCue {
+    pub severity: Severity,
+    pub sound: CueSound,
+    /// From -1.0 for all the way left to 1.0 for all the way right.
+    pub pan: f32,
+    pub pitch: f32,
+    /// As a percentage, with the audio controls already taken into account.
+    pub volume: u8,
+}
+
+/// The cue for the given event from the given profile, if it gets one and
+/// the audio hasn't been muted.
+pub(crate) fn cue(
+    profile: &CueProfile,
+    kind: &EventKind,
+    controls: &AudioControls,
+    config_dir: &Path,
+) -> Option<AudioCue> {
+    let severity = Severity::of(kind)?;
+    let Earcon {
+        sound,
+        tone,
+        length,
+        pan,
+        pitch,
+    } = profile.earcon(severity)?;
+
+    let volume = controls.effective_volume(AudioKind::Sound);
+    if volume == 0 {
+        return None;
+    }
+    let sound = match (sound, tone) {
+        (Some(sound), _) => CueSound::File(config_dir.join(sound)),
+        (None, Some(frequency)) => CueSound::Tone {
+            frequency: *frequency,
+            length: *length,
+        },
+        (None, None) => return None,
+    };
+
+    Some(AudioCue {
+        severity,
+        sound,
+        pan: *pan,
+        pitch: *pitch,
+        volume,
+    })
+}
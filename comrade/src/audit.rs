@@ -0,0 +1,171 @@
+//! Audit Log
+//!
+//! Every change to which triggers are on, and what they do, is appended to an
+//! audit log in the data directory: each edit, undo and redo, each snooze, and
+//! each sync of a remote source along with what the sync changed. Unlike the
+//! edit journal, which only lives as long as Comrade is running, the audit log
+//! is kept for good, so that it can be checked later on, e.g. by a raid leader
+//! making sure that everyone actually has the required pack enabled.
+
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::config::diff::{diff, TriggerChange};
+use crate::config::triggers::TriggerSet;
+use crate::errors::ConfigError;
+use crate::inflight::millis;
+use crate::time::SystemTime;
+
+const AUDIT_FILENAME: &str = "Audit.toml";
+
+pub(crate) fn audit_file(data_dir: &Path) -> PathBuf {
+    data_dir.join(AUDIT_FILENAME)
+}
+
+/// A single change, as it was recorded in the audit log.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub time: SystemTime,
+    /// The user that Comrade was running as.
+    pub who: String,
+    pub what: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Record {
+    /// In milliseconds since the epoch.
+    time: u64,
+    who: String,
+    what: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Records {
+    #[serde(default)]
+    entry: Vec<Record>,
+}
+
+/// Appends a change to the audit log. A change that can't be recorded is
+/// only warned about, since it's already been made by the time it's recorded.
+pub(crate) fn record(data_dir: &Path, what: String) {
+    let record = Record {
+        time: millis(SystemTime::now()),
+        who: who(),
+        what,
+    };
+
+    // Like the in-flight journal, each entry is its own element of an array
+    // of tables, so that the log can only ever be appended to.
+    let result = toml_edit::ser::to_string(&record)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        .and_then(|s| {
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(audit_file(data_dir))
+                .and_then(|mut file| write!(file, "[[entry]]\n{}", s))
+        });
+    if let Err(e) = result {
+        warn!("could not record {:?} in the audit log: {}", record.what, e);
+    }
+}
+
+/// Every change in the audit log, oldest first.
+pub(crate) fn read(data_dir: &Path) -> Result<Vec<AuditEntry>, ConfigError> {
+    let filename = audit_file(data_dir);
+    let contents = match fs::read_to_string(filename.as_path()) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let records: Records = toml_edit::de::from_str(contents.as_str())
+        .map_err(|source| ConfigError::DeserializationError { source, filename })?;
+
+    Ok(records
+        .entry
+        .into_iter()
+        .map(|r| AuditEntry {
+            time: SystemTime::UNIX_EPOCH + Duration::from_millis(r.time),
+            who: r.who,
+            what: r.what,
+        })
+        .collect())
+}
+
+/// Describes what syncing a remote source changed about its triggers, given
+/// what it had before the sync, if it had been synced before at all.
+pub(crate) fn describe_sync(name: &str, before: Option<&TriggerSet>, after: &TriggerSet) -> String {
+    let changes = match before {
+        Some(before) => diff(before, after),
+        None => {
+            return format!(
+                "sync remote:{}: {} trigger(s) added",
+                name,
+                after.triggers.len()
+            )
+        }
+    };
+    if changes.is_empty() {
+        return format!("sync remote:{}: no changes", name);
+    }
+
+    let (mut added, mut changed, mut removed) = (Vec::new(), Vec::new(), Vec::new());
+    for change in changes.iter() {
+        let ids = match change {
+            TriggerChange::Added { .. } => &mut added,
+            TriggerChange::Changed { .. } => &mut changed,
+            TriggerChange::Removed { .. } => &mut removed,
+        };
+        ids.push(change.id().to_string());
+    }
+    let parts: Vec<String> = [("added", added), ("changed", changed), ("removed", removed)]
+        .into_iter()
+        .filter(|(_, ids)| !ids.is_empty())
+        .map(|(verb, ids)| format!("{} {}", verb, ids.join(", ")))
+        .collect();
+
+    format!("sync remote:{}: {}", name, parts.join("; "))
+}
+
+/// Who's making changes, as far as the operating system knows.
+fn who() -> String {
+    ["USER", "USERNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok())
+        .filter(|user| !user.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use super::*;
+
+    #[test]
+    fn records_are_read_back_in_order() {
+        let dir = env::temp_dir().join(format!("comrade-audit-{}", process::id()));
+        fs::create_dir_all(dir.as_path()).unwrap();
+
+        assert!(read(dir.as_path()).unwrap().is_empty());
+        record(dir.as_path(), "enable remote:guild/adds".to_string());
+        record(dir.as_path(), "snooze local/tells for 10m".to_string());
+
+        let entries = read(dir.as_path()).unwrap();
+        let whats: Vec<&str> = entries.iter().map(|e| e.what.as_str()).collect();
+        assert_eq!(
+            whats,
+            ["enable remote:guild/adds", "snooze local/tells for 10m"]
+        );
+        assert!(entries[0].time <= entries[1].time);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,101 @@
+//! Raid and Guild Broadcasts
+//!
+//! Raid chat, guild chat, and the guild's message of the day are how raids
+//! and guilds get the word out, so they're picked out of the logs as their
+//! own events, with who said it and where, rather than leaving every
+//! frontend to write triggers for them.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::config::triggers::{Priority, Trigger, TriggerStyle};
+
+lazy_static! {
+    // e.g. "Soandso tells the raid,  'Clear the platform'", which has two
+    // spaces for some reason.
+    static ref RAID_RE: Regex =
+        Regex::new(r"^(?P<speaker>[A-Za-z]+) tells the raid, +'(?P<text>.*)'$").unwrap();
+    static ref GUILD_RE: Regex =
+        Regex::new(r"^(?P<speaker>[A-Za-z]+) tells the guild, '(?P<text>.*)'$").unwrap();
+    static ref OFFICERS_RE: Regex =
+        Regex::new(r"^(?P<speaker>[A-Za-z]+) tells the (?:guild )?officers, '(?P<text>.*)'$")
+            .unwrap();
+    static ref MOTD_RE: Regex =
+        Regex::new(r"^GUILD MOTD: (?P<speaker>[A-Za-z]+) - (?P<text>.*)$").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BroadcastChannel {
+    Raid,
+    Guild,
+    Officers,
+    /// The guild's message of the day, which is shown on login and whenever
+    /// it's changed.
+    Motd,
+}
+
+impl fmt::Display for BroadcastChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let channel = match self {
+            BroadcastChannel::Raid => "raid",
+            BroadcastChannel::Guild => "guild",
+            BroadcastChannel::Officers => "officers",
+            BroadcastChannel::Motd => "motd",
+        };
+        f.write_str(channel)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Broadcast {
+    pub channel: BroadcastChannel,
+    pub speaker: String,
+    pub text: String,
+}
+
+/// Pulls the broadcast out of a log line, if it is one.
+pub(crate) fn parse_broadcast(line: &str) -> Option<Broadcast> {
+    let channels: [(&Regex, BroadcastChannel); 4] = [
+        (&RAID_RE, BroadcastChannel::Raid),
+        (&GUILD_RE, BroadcastChannel::Guild),
+        (&OFFICERS_RE, BroadcastChannel::Officers),
+        (&MOTD_RE, BroadcastChannel::Motd),
+    ];
+
+    channels.iter().find_map(|(re, channel)| {
+        re.captures(line).map(|caps| Broadcast {
+            channel: *channel,
+            speaker: caps["speaker"].to_string(),
+            text: caps["text"].to_string(),
+        })
+    })
+}
+
+/// The trigger that what raid leaders say is displayed as, so that it's shown
+/// like any other high priority alert.
+pub(crate) fn leader_trigger(name: String) -> Arc<Trigger> {
+    Arc::new(Trigger {
+        name,
+        comment: String::new(),
+        search_text: RAID_RE.as_str().to_string(),
+        tags: Vec::new(),
+        disabled: false,
+        priority: Priority::High,
+        style: TriggerStyle::default(),
+        acknowledge: false,
+        repeat: None,
+        captures: BTreeMap::new(),
+        conditions: Vec::new(),
+        active: Vec::new(),
+        actions: Vec::new(),
+    })
+}
+
+/// Whether a log line is one that broadcast capture needs to see.
+pub(crate) fn is_broadcast_line(line: &str) -> bool {
+    parse_broadcast(line).is_some()
+}
@@ -0,0 +1,315 @@
+//! Combat Tracking
+//!
+//! Damage lines from each character's log are grouped into fights, one per
+//! target, which end once the target has gone long enough without taking any
+//! damage. Finished fights are summarized per attacker, the same way that
+//! parsers like EQLogParser and GamParse summarize them, so that the numbers
+//! can be compared with theirs.
+//!
+//! Damage done by pets, including swarm pets like doppelgangers, is counted
+//! as their owner's, so that pet classes aren't split up into a handful of
+//! attackers that don't mean anything on their own.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use log::debug;
+use parking_lot::Mutex;
+use regex::Regex;
+
+use crate::config::combat::CombatConfig;
+use crate::config::{Character, CharacterId};
+use crate::time::{Instant, SystemTime};
+use crate::watcher::LogEvent;
+
+/// How many finished fights are kept around, the oldest are dropped once
+/// there are more than this.
+const MAX_FIGHTS: usize = 500;
+
+lazy_static! {
+    // e.g. "You slash a gnoll for 25 points of damage." or, in newer logs,
+    // "Soandso hit a gnoll for 1500 points of fire damage by Flame Lick."
+    static ref MELEE_RE: Regex = Regex::new(
+        r"^(?P<attacker>.+?) (?:hits?|slash(?:es)?|crush(?:es)?|pierces?|kicks?|bash(?:es)?|punch(?:es)?|backstabs?|strikes?|bites?|claws?|mauls?|gores?|stings?|shoots?|smash(?:es)?|slams?|sweeps?|rends?|slices?|stabs?|burns?|frenz(?:y|ies) on) (?P<target>.+?) for (?P<amount>\d+) points? of (?:\w+ )?damage(?: by .+?)?\.(?: \(.+\))?$"
+    )
+    .unwrap();
+    // Older logs don't say whose spell it was, but they only ever report
+    // our own, e.g. "a gnoll was hit by non-melee for 100 points of damage."
+    static ref NON_MELEE_RE: Regex = Regex::new(
+        r"^(?P<target>.+?) was hit by non-melee for (?P<amount>\d+) points? of damage\.$"
+    )
+    .unwrap();
+    // Damage over time, e.g. "a gnoll has taken 50 damage from your Flame
+    // Lick." or "a gnoll has taken 50 damage from Flame Lick by Soandso."
+    static ref DOT_RE: Regex = Regex::new(
+        r"^(?P<target>.+?) has taken (?P<amount>\d+) damage from (?:your .+?|.+? by (?P<attacker>.+?))\.(?: \(.+\))?$"
+    )
+    .unwrap();
+    // The answer to /pet leader, e.g. "Gobaner says, 'My leader is Soandso.'"
+    static ref LEADER_RE: Regex =
+        Regex::new(r"^(?P<pet>.+?) says,? '[Mm]y leader is (?P<owner>[A-Za-z]+)\.'$").unwrap();
+    // Swarm pets are named after their owner, e.g. "Soandso`s doppelganger".
+    static ref SWARM_PET_RE: Regex = Regex::new(r"^(?P<owner>[A-Za-z]+)`s .+$").unwrap();
+}
+
+/// A single instance of damage, with `None` as the attacker when it was the
+/// character whose log it's from.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Hit<'a> {
+    pub(crate) attacker: Option<&'a str>,
+    pub(crate) target: &'a str,
+    pub(crate) amount: u64,
+}
+
+pub(crate) fn parse_hit(line: &str) -> Option<Hit<'_>> {
+    if !line.contains("damage") {
+        return None;
+    }
+
+    // Non-melee damage has to be checked for first, since it otherwise looks
+    // like the target hitting something.
+    let hit = if let Some(caps) = NON_MELEE_RE.captures(line) {
+        Hit {
+            attacker: None,
+            target: caps.name("target")?.as_str(),
+            amount: caps.name("amount")?.as_str().parse().ok()?,
+        }
+    } else if let Some(caps) = MELEE_RE.captures(line) {
+        let attacker = caps.name("attacker").map(|m| m.as_str());
+        Hit {
+            attacker: attacker.filter(|a| !a.eq_ignore_ascii_case("you")),
+            target: caps.name("target")?.as_str(),
+            amount: caps.name("amount")?.as_str().parse().ok()?,
+        }
+    } else if let Some(caps) = DOT_RE.captures(line) {
+        Hit {
+            attacker: caps.name("attacker").map(|m| m.as_str()),
+            target: caps.name("target")?.as_str(),
+            amount: caps.name("amount")?.as_str().parse().ok()?,
+        }
+    } else {
+        return None;
+    };
+
+    // Damage done to us is part of someone else's fight, not ours.
+    if matches!(hit.target, "YOU" | "you" | "yourself") {
+        return None;
+    }
+
+    Some(hit)
+}
+
+/// Whether a log line is one that combat tracking needs to see.
+pub(crate) fn is_combat_line(line: &str) -> bool {
+    parse_hit(line).is_some() || LEADER_RE.is_match(line)
+}
+
+#[derive(Debug, Clone)]
+pub struct AttackerStats {
+    pub name: String,
+    pub damage: u64,
+    pub hits: u64,
+    /// The biggest single hit.
+    pub max: u64,
+    /// How much of the damage was done by their pets.
+    pub pet_damage: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FightSummary {
+    /// The character whose log the fight was seen in.
+    pub character: CharacterId,
+    pub target: String,
+    pub started: SystemTime,
+    /// The time from the first hit to the last, which is never less than a
+    /// second since that's as precise as the logs are.
+    pub duration: Duration,
+    /// Everyone that damaged the target, most damage first.
+    pub attackers: Vec<AttackerStats>,
+}
+
+impl AttackerStats {
+    fn new(name: &str) -> AttackerStats {
+        AttackerStats {
+            name: name.to_string(),
+            damage: 0,
+            hits: 0,
+            max: 0,
+            pet_damage: 0,
+        }
+    }
+}
+
+impl FightSummary {
+    /// The total damage done to the target.
+    pub fn damage(&self) -> u64 {
+        self.attackers.iter().map(|a| a.damage).sum()
+    }
+}
+
+pub(crate) type FightLog = Arc<Mutex<VecDeque<FightSummary>>>;
+
+struct Fight {
+    started: SystemTime,
+    first: Instant,
+    last: Instant,
+    attackers: HashMap<String, AttackerStats>,
+}
+
+impl Fight {
+    /// Summarizes the fight, with any pets' damage added to their owner's.
+    fn summarize(
+        self,
+        character: CharacterId,
+        target: String,
+        owner: impl Fn(&str) -> Option<String>,
+    ) -> FightSummary {
+        let mut merged: HashMap<String, AttackerStats> = HashMap::new();
+        for stats in self.attackers.into_values() {
+            let pet_of = owner(stats.name.as_str());
+            let name = pet_of.as_deref().unwrap_or(stats.name.as_str());
+            let entry = merged
+                .entry(name.to_string())
+                .or_insert_with(|| AttackerStats::new(name));
+            entry.damage += stats.damage;
+            entry.hits += stats.hits;
+            entry.max = entry.max.max(stats.max);
+            if pet_of.is_some() {
+                entry.pet_damage += stats.damage;
+            }
+        }
+
+        let mut attackers: Vec<AttackerStats> = merged.into_values().collect();
+        attackers.sort_by(|a, b| b.damage.cmp(&a.damage).then_with(|| a.name.cmp(&b.name)));
+
+        FightSummary {
+            character,
+            target,
+            started: self.started,
+            duration: (self.last - self.first).max(Duration::from_secs(1)),
+            attackers,
+        }
+    }
+}
+
+/// Tracks the fights that are in progress, moving them to the fight log once
+/// they're over.
+pub(crate) struct Combat {
+    fights: HashMap<(CharacterId, String), Fight>,
+    /// The owners of pets that have been learned from the logs, by the pet's
+    /// name.
+    pets: HashMap<String, String>,
+    finished: FightLog,
+}
+
+impl Combat {
+    pub(crate) fn new(finished: FightLog) -> Combat {
+        Combat {
+            fights: HashMap::new(),
+            pets: HashMap::new(),
+            finished,
+        }
+    }
+
+    /// Adds the damage from the given event to its fight, returning the
+    /// target if it's the first damage of a new fight.
+    pub(crate) fn log_event(&mut self, character: &Character, event: &LogEvent) -> Option<String> {
+        if let Some(caps) = LEADER_RE.captures(event.message()) {
+            debug!(
+                "learned that {} is owned by {}",
+                &caps["pet"], &caps["owner"]
+            );
+            self.pets
+                .insert(caps["pet"].to_string(), caps["owner"].to_string());
+            return None;
+        }
+
+        let hit = parse_hit(event.message())?;
+        let attacker = hit.attacker.unwrap_or(character.name.as_str());
+
+        let now = Instant::now();
+        let mut started = None;
+        let fight = self
+            .fights
+            .entry(((*event.id).clone(), hit.target.to_string()))
+            .or_insert_with(|| {
+                debug!("fight against {} started for {}", hit.target, event.id);
+                started = Some(hit.target.to_string());
+                Fight {
+                    started: SystemTime::now(),
+                    first: now,
+                    last: now,
+                    attackers: HashMap::new(),
+                }
+            });
+        fight.last = now;
+
+        let stats = fight
+            .attackers
+            .entry(attacker.to_string())
+            .or_insert_with(|| AttackerStats::new(attacker));
+        stats.damage += hit.amount;
+        stats.hits += 1;
+        stats.max = stats.max.max(hit.amount);
+
+        started
+    }
+
+    /// The target of the character's current fight, which is whichever of
+    /// their fights most recently took damage.
+    pub(crate) fn current(&self, id: &CharacterId) -> Option<&str> {
+        self.fights
+            .iter()
+            .filter(|((character, _), _)| character == id)
+            .max_by_key(|(_, fight)| fight.last)
+            .map(|((_, target), _)| target.as_str())
+    }
+
+    /// Ends every fight whose target has gone at least the timeout without
+    /// taking any damage, returning their summaries.
+    pub(crate) fn expire(&mut self, config: &CombatConfig) -> Vec<FightSummary> {
+        let expired: Vec<(CharacterId, String)> = self
+            .fights
+            .iter()
+            .filter(|(_, fight)| fight.last.elapsed() >= config.timeout)
+            .map(|(key, _)| key.clone())
+            .collect();
+        if expired.is_empty() {
+            return Vec::new();
+        }
+
+        // Configured pets take priority over learned ones, since the same pet
+        // name can be used by more than one character.
+        let pets = &self.pets;
+        let owner = |name: &str| {
+            config
+                .pets
+                .get(name)
+                .or_else(|| pets.get(name))
+                .cloned()
+                .or_else(|| {
+                    SWARM_PET_RE
+                        .captures(name)
+                        .map(|caps| caps["owner"].to_string())
+                })
+        };
+
+        let mut summaries = Vec::new();
+        let mut finished = self.finished.lock();
+        for key in expired {
+            if let Some(fight) = self.fights.remove(&key) {
+                let (character, target) = key;
+                debug!("fight against {} ended for {}", target, character);
+                let summary = fight.summarize(character, target, owner);
+                finished.push_back(summary.clone());
+                summaries.push(summary);
+            }
+        }
+        while finished.len() > MAX_FIGHTS {
+            finished.pop_front();
+        }
+        summaries
+    }
+}
@@ -0,0 +1,173 @@
+//! Accessibility Configuration
+//!
+//! For players who can't rely on the overlay, a cue profile gives each
+//! severity of event its own earcon, a short sound that's recognizable
+//! without anything being read out, played from its own place in the stereo
+//! field and at its own pitch so that they can be told apart at a glance of
+//! the ear. There's a built in profile of tones, `earcons`, and profiles of
+//! sound files can be added alongside it.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_with::{serde_as, DurationMilliSeconds};
+
+use crate::audio::Severity;
+use crate::errors::ConfigError;
+
+/// The name of the profile that's always there, without being configured.
+pub const BUILTIN_PROFILE: &str = "earcons";
+
+#[derive(Deserialize, Debug, Default, Clone)]
+pub(crate) struct AccessibilityConfig {
+    /// The profile to play cues from, no cues are played without one.
+    #[serde(default)]
+    pub(crate) profile: Option<String>,
+    #[serde(default)]
+    pub(crate) profiles: BTreeMap<String, CueProfile>,
+}
+
+impl AccessibilityConfig {
+    /// The profile that's been picked, if any.
+    pub(crate) fn active(&self) -> Option<CueProfile> {
+        let name = self.profile.as_deref()?;
+        match self.profiles.get(name) {
+            Some(profile) => Some(profile.clone()),
+            None if name == BUILTIN_PROFILE => Some(builtin_profile()),
+            None => None,
+        }
+    }
+
+    /// Makes sure that the profile that's been picked exists, and that every
+    /// cue in every profile can actually be played.
+    pub(crate) fn check(&self) -> Result<(), ConfigError> {
+        if let Some(ref name) = self.profile {
+            if name != BUILTIN_PROFILE && !self.profiles.contains_key(name) {
+                return Err(ConfigError::UnknownCueProfile { name: name.clone() });
+            }
+        }
+
+        for (name, profile) in self.profiles.iter() {
+            for (severity, earcon) in profile.earcons() {
+                if let Err(reason) = earcon.check() {
+                    return Err(ConfigError::InvalidCue {
+                        profile: name.clone(),
+                        severity,
+                        reason,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The earcon for each severity of event, a severity without one is silent.
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct CueProfile {
+    #[serde(default)]
+    pub low: Option<Earcon>,
+    #[serde(default)]
+    pub normal: Option<Earcon>,
+    #[serde(default)]
+    pub high: Option<Earcon>,
+    #[serde(default)]
+    pub critical: Option<Earcon>,
+}
+
+impl CueProfile {
+    pub fn earcon(&self, severity: Severity) -> Option<&Earcon> {
+        match severity {
+            Severity::Low => self.low.as_ref(),
+            Severity::Normal => self.normal.as_ref(),
+            Severity::High => self.high.as_ref(),
+            Severity::Critical => self.critical.as_ref(),
+        }
+    }
+
+    fn earcons(&self) -> impl Iterator<Item = (Severity, &Earcon)> {
+        [
+            Severity::Low,
+            Severity::Normal,
+            Severity::High,
+            Severity::Critical,
+        ]
+        .into_iter()
+        .filter_map(|s| self.earcon(s).map(|e| (s, e)))
+    }
+}
+
+/// A short sound, either a file or a tone, along with where to play it.
+#[serde_as]
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Earcon {
+    /// A sound file to play, relative to the configuration directory.
+    #[serde(default)]
+    pub sound: Option<PathBuf>,
+    /// A tone to play instead of a file, as its frequency in Hz.
+    #[serde(default)]
+    pub tone: Option<u32>,
+    /// How long to play a tone for, in milliseconds.
+    #[serde_as(as = "DurationMilliSeconds<u64>")]
+    #[serde(default = "default_length")]
+    pub length: Duration,
+    /// Where to play it, from -1.0 for all the way left to 1.0 for all the
+    /// way right.
+    #[serde(default)]
+    pub pan: f32,
+    /// How much to speed up or slow down the sound by, 2.0 plays it an
+    /// octave higher.
+    #[serde(default = "default_pitch")]
+    pub pitch: f32,
+}
+
+impl Earcon {
+    fn tone(tone: u32, pan: f32) -> Earcon {
+        Earcon {
+            sound: None,
+            tone: Some(tone),
+            length: default_length(),
+            pan,
+            pitch: default_pitch(),
+        }
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if self.sound.is_some() == self.tone.is_some() {
+            return Err("must have either a sound or a tone".to_string());
+        }
+        if !(-1.0..=1.0).contains(&self.pan) {
+            return Err("pan must be between -1.0 and 1.0".to_string());
+        }
+        if self.pitch <= 0.0 {
+            return Err("pitch must be more than 0".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+fn default_length() -> Duration {
+    Duration::from_millis(150)
+}
+
+fn default_pitch() -> f32 {
+    1.0
+}
+
+/// Rising tones for rising severity, with the least severe off to the left
+/// and the most severe dead center, where it's hardest to miss.
+fn builtin_profile() -> CueProfile {
+    CueProfile {
+        low: Some(Earcon::tone(440, -0.6)),
+        normal: Some(Earcon::tone(660, -0.3)),
+        high: Some(Earcon::tone(880, 0.3)),
+        critical: Some(Earcon {
+            length: Duration::from_millis(400),
+            ..Earcon::tone(1320, 0.0)
+        }),
+    }
+}
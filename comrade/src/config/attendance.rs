@@ -0,0 +1,79 @@
+//! Attendance Configuration
+//!
+//! Attendance is taken from `/who` output, but only the ones done during the
+//! raid count as checkpoints, so the raid window is set here as times of day
+//! on the game's clock.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer};
+
+#[derive(Deserialize, Debug, Default, Clone)]
+pub(crate) struct AttendanceConfig {
+    #[serde(default)]
+    pub(crate) enabled: bool,
+    /// When the raid starts, if this isn't set then every `/who` counts.
+    #[serde(default)]
+    pub(crate) start: Option<TimeOfDay>,
+    /// When the raid ends, which may be after midnight.
+    #[serde(default)]
+    pub(crate) end: Option<TimeOfDay>,
+}
+
+impl AttendanceConfig {
+    /// Whether the given time falls within the raid window.
+    pub(crate) fn in_window(&self, time: TimeOfDay) -> bool {
+        match (self.start, self.end) {
+            (Some(start), Some(end)) if start <= end => start <= time && time <= end,
+            (Some(start), Some(end)) => start <= time || time <= end,
+            (Some(start), None) => start <= time,
+            (None, Some(end)) => time <= end,
+            (None, None) => true,
+        }
+    }
+}
+
+/// A time of day, to the minute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeOfDay(u16);
+
+impl TimeOfDay {
+    /// Pulls the time of day out of a log timestamp, which look like
+    /// `Sat Oct 17 20:15:00 2026`.
+    pub(crate) fn from_timestamp(timestamp: &str) -> Option<TimeOfDay> {
+        let time = timestamp.split_whitespace().nth(3)?;
+        let (hm, _seconds) = time.rsplit_once(':')?;
+        hm.parse().ok()
+    }
+}
+
+impl FromStr for TimeOfDay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<TimeOfDay, String> {
+        let invalid = || format!("invalid time {:?}, expected something like 20:30", s);
+        let (hours, minutes) = s.trim().split_once(':').ok_or_else(invalid)?;
+        let hours: u16 = hours.parse().map_err(|_| invalid())?;
+        let minutes: u16 = minutes.parse().map_err(|_| invalid())?;
+        if hours > 23 || minutes > 59 {
+            return Err(invalid());
+        }
+
+        Ok(TimeOfDay(hours * 60 + minutes))
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.0 / 60, self.0 % 60)
+    }
+}
+
+impl<'de> Deserialize<'de> for TimeOfDay {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<TimeOfDay, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
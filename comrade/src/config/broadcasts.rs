@@ -0,0 +1,23 @@
+//! Broadcast Configuration
+//!
+//! Raid and guild chat is only captured once it's been turned on, and only
+//! the raid leaders listed here get an alert for what they say, since nobody
+//! wants an alert for every line of raid chat.
+
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Default, Clone)]
+pub(crate) struct BroadcastsConfig {
+    #[serde(default)]
+    pub(crate) enabled: bool,
+    /// The names of the raid leaders, anything they say to the raid is
+    /// shown as an alert.
+    #[serde(default)]
+    pub(crate) leaders: Vec<String>,
+}
+
+impl BroadcastsConfig {
+    pub(crate) fn is_leader(&self, name: &str) -> bool {
+        self.leaders.iter().any(|l| l.eq_ignore_ascii_case(name))
+    }
+}
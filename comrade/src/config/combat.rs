@@ -0,0 +1,44 @@
+//! Combat Configuration
+//!
+//! Tracking combat means reading every damage line from the logs, rather than
+//! just the ones that some trigger is looking for, so it's off unless it has
+//! been turned on.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_with::{serde_as, DurationSeconds};
+
+/// How long a target has to go without taking damage before the fight
+/// against it is over, unless the configuration says otherwise.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[serde_as]
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct CombatConfig {
+    #[serde(default)]
+    pub(crate) enabled: bool,
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[serde(default = "default_timeout")]
+    pub(crate) timeout: Duration,
+    /// The owners of pets that should have their damage counted as their
+    /// owner's, by the pet's name. Pets are also learned from the logs, but
+    /// only once they've been asked who their leader is.
+    #[serde(default)]
+    pub(crate) pets: HashMap<String, String>,
+}
+
+impl Default for CombatConfig {
+    fn default() -> CombatConfig {
+        CombatConfig {
+            enabled: false,
+            timeout: DEFAULT_TIMEOUT,
+            pets: HashMap::new(),
+        }
+    }
+}
+
+fn default_timeout() -> Duration {
+    DEFAULT_TIMEOUT
+}
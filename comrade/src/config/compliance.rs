@@ -0,0 +1,214 @@
+//! Required Triggers
+//!
+//! A guild can publish a manifest of the triggers that everyone is expected
+//! to have on for raids, either trigger by trigger, or as whole sources. Once
+//! imported, it's compared against which triggers each character actually has
+//! on, so that anything missing or turned off can be fixed before the pull
+//! rather than noticed after the wipe.
+//!
+//! ```toml
+//! name = "Raid night"
+//! triggers = ["remote:guild/adds", "remote:guild/rampage"]
+//! sources = ["remote:guild"]
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::config::triggers::{TriggerRef, TriggerSource, Triggers};
+use crate::config::{Character, CharacterId, Config};
+use crate::errors::{ComplianceError, TriggerError};
+
+type Result<T, E = ComplianceError> = core::result::Result<T, E>;
+
+const MANIFEST_FILENAME: &str = "Required.toml";
+
+pub(crate) fn manifest_file(data_dir: &Path) -> PathBuf {
+    data_dir.join(MANIFEST_FILENAME)
+}
+
+#[derive(Deserialize, Debug)]
+struct RawManifest {
+    name: String,
+    #[serde(default)]
+    triggers: Vec<String>,
+    #[serde(default)]
+    sources: Vec<String>,
+}
+
+/// The triggers that a guild requires.
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    pub name: String,
+    /// Triggers that have to be on.
+    pub triggers: Vec<TriggerRef>,
+    /// Sources whose triggers all have to be on, other than the ones that
+    /// are off by default.
+    pub sources: Vec<TriggerSource>,
+}
+
+impl Manifest {
+    pub(crate) fn load(filename: &Path) -> Result<Manifest> {
+        let contents = fs::read_to_string(filename).map_err(|source| ComplianceError::IOError {
+            source,
+            filename: filename.to_path_buf(),
+        })?;
+        let raw: RawManifest = toml_edit::de::from_str(contents.as_str()).map_err(|source| {
+            ComplianceError::DeserializationError {
+                source,
+                filename: filename.to_path_buf(),
+            }
+        })?;
+
+        Ok(Manifest {
+            name: raw.name,
+            triggers: parse_all(raw.triggers.as_slice())?,
+            sources: parse_all(raw.sources.as_slice())?,
+        })
+    }
+}
+
+fn parse_all<T: FromStr<Err = TriggerError>>(values: &[String]) -> Result<Vec<T>> {
+    values
+        .iter()
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|source| ComplianceError::InvalidRequirement {
+                    source,
+                    value: value.clone(),
+                })
+        })
+        .collect()
+}
+
+/// Something that keeps a character from meeting a manifest.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ComplianceIssue {
+    /// A required source isn't configured at all.
+    MissingSource { source: TriggerSource },
+    /// A required source is configured, but turned off.
+    DisabledSource { source: TriggerSource },
+    /// A required trigger isn't in its source, which is usually down to the
+    /// source not having been synced since it was added.
+    MissingTrigger { tref: TriggerRef },
+    /// A required trigger is turned off for a character.
+    DisabledTrigger {
+        tref: TriggerRef,
+        character: CharacterId,
+    },
+}
+
+impl fmt::Display for ComplianceIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComplianceIssue::MissingSource { source } => write!(f, "{} is not configured", source),
+            ComplianceIssue::DisabledSource { source } => write!(f, "{} is disabled", source),
+            ComplianceIssue::MissingTrigger { tref } => {
+                write!(f, "{} is missing, try syncing its source", tref)
+            }
+            ComplianceIssue::DisabledTrigger { tref, character } => {
+                write!(f, "{} is disabled for {}", tref, character)
+            }
+        }
+    }
+}
+
+/// How the configured characters measure up against a manifest.
+#[derive(Debug, Clone)]
+pub struct ComplianceReport {
+    pub manifest: String,
+    /// How many triggers are required of each character.
+    pub required: usize,
+    pub issues: Vec<ComplianceIssue>,
+}
+
+impl ComplianceReport {
+    pub fn is_compliant(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Compares every configured character against the manifest.
+pub(crate) fn check(manifest: &Manifest, config: &Config) -> ComplianceReport {
+    let mut issues = Vec::new();
+    let mut required: Vec<TriggerRef> = manifest.triggers.clone();
+
+    for source in manifest.sources.iter() {
+        if let Some(issue) = source_issue(source, config) {
+            issues.push(issue);
+            continue;
+        }
+        if let Some(pack) = config.triggers.pack(source) {
+            required.extend(
+                pack.iter()
+                    .filter(|(_, trigger)| !trigger.disabled)
+                    .map(|(id, _)| TriggerRef::new(source.clone(), id.clone())),
+            );
+        }
+    }
+    required.sort();
+    required.dedup();
+
+    for tref in required.iter() {
+        if let Some(issue) = source_issue(&tref.source, config) {
+            // The source is already being reported on, there's no need to
+            // report every required trigger in it as well.
+            if !manifest.sources.contains(&tref.source) {
+                issues.push(issue);
+            }
+            continue;
+        }
+        issues.extend(trigger_issues(tref, &config.triggers, &config.characters));
+    }
+    issues.sort();
+    issues.dedup();
+
+    ComplianceReport {
+        manifest: manifest.name.clone(),
+        required: required.len(),
+        issues,
+    }
+}
+
+fn source_issue(source: &TriggerSource, config: &Config) -> Option<ComplianceIssue> {
+    let name = match source {
+        TriggerSource::Local => return None,
+        TriggerSource::Remote(name) => name,
+    };
+
+    match config.sources.get(name) {
+        None => Some(ComplianceIssue::MissingSource {
+            source: source.clone(),
+        }),
+        Some(s) if s.disabled => Some(ComplianceIssue::DisabledSource {
+            source: source.clone(),
+        }),
+        Some(_) => None,
+    }
+}
+
+fn trigger_issues(
+    tref: &TriggerRef,
+    triggers: &Triggers,
+    characters: &HashMap<CharacterId, Character>,
+) -> Vec<ComplianceIssue> {
+    let trigger = match triggers.get(tref) {
+        Some(trigger) => trigger,
+        None => return vec![ComplianceIssue::MissingTrigger { tref: tref.clone() }],
+    };
+
+    characters
+        .iter()
+        .filter(|(_, c)| !c.is_trigger_enabled(tref, trigger))
+        .map(|(id, _)| ComplianceIssue::DisabledTrigger {
+            tref: tref.clone(),
+            character: id.clone(),
+        })
+        .collect()
+}
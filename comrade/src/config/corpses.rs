@@ -0,0 +1,49 @@
+//! Corpse Recovery Configuration
+//!
+//! How long corpses take to decay depends on the server's rules, and whether
+//! they're reminded of at all is down to taste, so both are set here.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_with::{serde_as, DurationSeconds};
+
+/// How long a corpse lasts before it decays, unless the configuration says
+/// otherwise. This is how long they last on live servers once the character
+/// is past the first few levels.
+const DEFAULT_DECAY: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// How often the reminder about a corpse is repeated until it's
+/// acknowledged, unless the configuration says otherwise.
+const DEFAULT_REMIND: Duration = Duration::from_secs(5 * 60);
+
+#[serde_as]
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct CorpsesConfig {
+    #[serde(default)]
+    pub(crate) enabled: bool,
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[serde(default = "default_decay")]
+    pub(crate) decay: Duration,
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[serde(default = "default_remind")]
+    pub(crate) remind: Duration,
+}
+
+impl Default for CorpsesConfig {
+    fn default() -> CorpsesConfig {
+        CorpsesConfig {
+            enabled: false,
+            decay: DEFAULT_DECAY,
+            remind: DEFAULT_REMIND,
+        }
+    }
+}
+
+fn default_decay() -> Duration {
+    DEFAULT_DECAY
+}
+
+fn default_remind() -> Duration {
+    DEFAULT_REMIND
+}
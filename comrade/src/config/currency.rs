@@ -0,0 +1,36 @@
+//! Currency Configuration
+//!
+//! Tracking currency means reading every coin loot and split from the logs,
+//! so like combat tracking it's off unless it has been turned on.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_with::{serde_as, DurationSeconds};
+
+/// How long a character has to go without picking up any coin before their
+/// farming session is over, unless the configuration says otherwise.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+#[serde_as]
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct CurrencyConfig {
+    #[serde(default)]
+    pub(crate) enabled: bool,
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[serde(default = "default_timeout")]
+    pub(crate) timeout: Duration,
+}
+
+impl Default for CurrencyConfig {
+    fn default() -> CurrencyConfig {
+        CurrencyConfig {
+            enabled: false,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+fn default_timeout() -> Duration {
+    DEFAULT_TIMEOUT
+}
@@ -0,0 +1,73 @@
+//! Dashboard Configuration
+//!
+//! The dashboard is a page that's served over http, for checking on a
+//! Comrade that's left running, like a trader box, from a phone on the same
+//! network. Anyone that can reach it can see what it shows, so it's off
+//! unless it's turned on, and it won't start without a token to ask for,
+//! which `init` generates into the secrets file.
+//!
+//! The same server has a control API, for starting timers, acknowledging
+//! alerts, and turning triggers on and off from a script or another device,
+//! which is off unless it's turned on as well, since it changes things.
+//!
+//! It's served over plain http unless it's been given a certificate and key,
+//! and Comrade was built with the `tls` feature, so otherwise the token can
+//! be seen by anyone watching the network's traffic.
+
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+/// Where the dashboard listens unless the configuration says otherwise,
+/// which is only this computer, so that it's only reachable from the rest
+/// of the network once that's been asked for, e.g. with `0.0.0.0:8180`.
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8180";
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DashboardConfig {
+    #[serde(default)]
+    pub(crate) enabled: bool,
+    /// The address and port to listen on, which only takes effect when
+    /// Comrade starts.
+    #[serde(default = "default_address")]
+    pub(crate) address: String,
+    /// What has to be given, as a bearer token or else as `?token=`, to see
+    /// the dashboard, which is taken from the secrets file unless it's set
+    /// here.
+    #[serde(default)]
+    pub(crate) token: Option<String>,
+    /// Whether the control API can be used, with the same token, which has to
+    /// be given as a bearer token.
+    #[serde(default)]
+    pub(crate) control: bool,
+    /// The certificate and key to serve over https with, rather than http,
+    /// which only takes effect when Comrade starts.
+    #[serde(default)]
+    pub(crate) tls: Option<TlsConfig>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TlsConfig {
+    /// A PEM file with the certificate, followed by any intermediate ones,
+    /// relative to the configuration directory.
+    pub(crate) certificate: PathBuf,
+    /// A PEM file with the certificate's private key, relative to the
+    /// configuration directory.
+    pub(crate) key: PathBuf,
+}
+
+impl Default for DashboardConfig {
+    fn default() -> DashboardConfig {
+        DashboardConfig {
+            enabled: false,
+            address: DEFAULT_ADDRESS.to_string(),
+            token: None,
+            control: false,
+            tls: None,
+        }
+    }
+}
+
+fn default_address() -> String {
+    DEFAULT_ADDRESS.to_string()
+}
@@ -0,0 +1,132 @@
+//! Trigger Diffs
+//!
+//! Compares two versions of a set of triggers, typically an old and a new
+//! release of a trigger pack, so that updates can be reviewed and merged into
+//! the local triggers one change at a time rather than overwriting them.
+
+use serde::Serialize;
+
+use crate::config::triggers::{Trigger, TriggerId, TriggerSet};
+
+// Diffs are short lived and only made while reviewing an update, so keeping
+// both versions of a changed trigger inline isn't worth boxing them over.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "change", rename_all = "lowercase")]
+pub enum TriggerChange {
+    Added {
+        id: TriggerId,
+        trigger: Trigger,
+    },
+    Changed {
+        id: TriggerId,
+        old: Trigger,
+        new: Trigger,
+    },
+    Removed {
+        id: TriggerId,
+        trigger: Trigger,
+    },
+}
+
+impl TriggerChange {
+    pub fn id(&self) -> &TriggerId {
+        match self {
+            TriggerChange::Added { id, .. } => id,
+            TriggerChange::Changed { id, .. } => id,
+            TriggerChange::Removed { id, .. } => id,
+        }
+    }
+
+    /// The trigger as it will be once this change has been applied, if it
+    /// still exists at all.
+    pub fn trigger(&self) -> Option<&Trigger> {
+        match self {
+            TriggerChange::Added { trigger, .. } => Some(trigger),
+            TriggerChange::Changed { new, .. } => Some(new),
+            TriggerChange::Removed { .. } => None,
+        }
+    }
+
+    /// The names of the fields that differ between the old and new versions
+    /// of a changed trigger.
+    pub fn changed_fields(&self) -> Vec<&'static str> {
+        let (old, new) = match self {
+            TriggerChange::Changed { old, new, .. } => (old, new),
+            _ => return Vec::new(),
+        };
+
+        let mut fields = Vec::new();
+        if old.name != new.name {
+            fields.push("name");
+        }
+        if old.comment != new.comment {
+            fields.push("comment");
+        }
+        if old.search_text != new.search_text {
+            fields.push("search_text");
+        }
+        if old.tags != new.tags {
+            fields.push("tags");
+        }
+        if old.disabled != new.disabled {
+            fields.push("disabled");
+        }
+        if old.priority != new.priority {
+            fields.push("priority");
+        }
+        if old.style != new.style {
+            fields.push("style");
+        }
+        if old.acknowledge != new.acknowledge {
+            fields.push("acknowledge");
+        }
+        if old.repeat != new.repeat {
+            fields.push("repeat");
+        }
+        if old.captures != new.captures {
+            fields.push("captures");
+        }
+        if old.conditions != new.conditions {
+            fields.push("where");
+        }
+        if old.active != new.active {
+            fields.push("active");
+        }
+        if old.actions != new.actions {
+            fields.push("actions");
+        }
+        fields
+    }
+}
+
+pub(crate) fn diff(old: &TriggerSet, new: &TriggerSet) -> Vec<TriggerChange> {
+    let mut changes = Vec::new();
+
+    for (id, trigger) in new.triggers.iter() {
+        match old.triggers.get(id) {
+            None => changes.push(TriggerChange::Added {
+                id: id.clone(),
+                trigger: trigger.clone(),
+            }),
+            Some(existing) if existing != trigger => changes.push(TriggerChange::Changed {
+                id: id.clone(),
+                old: existing.clone(),
+                new: trigger.clone(),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    for (id, trigger) in old.triggers.iter() {
+        if !new.triggers.contains_key(id) {
+            changes.push(TriggerChange::Removed {
+                id: id.clone(),
+                trigger: trigger.clone(),
+            });
+        }
+    }
+
+    changes.sort_by(|a, b| a.id().cmp(b.id()));
+    changes
+}
@@ -0,0 +1,50 @@
+//! Digest Configuration
+//!
+//! Traders left in the bazaar and campers left running overnight aren't
+//! watched while Comrade runs, so it can sum the session up once it stops:
+//! what fired, what was looted, how much experience came in, and how many
+//! deaths there were. The digest is written to the data directory, mailed
+//! to an address through `sendmail`, or both.
+
+use serde::Deserialize;
+
+/// What mail is handed to unless the configuration says otherwise, which
+/// has to be on the `PATH`.
+const DEFAULT_SENDMAIL: &str = "sendmail";
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct DigestConfig {
+    #[serde(default)]
+    pub(crate) enabled: bool,
+    /// Whether each digest is written to the `digests` directory, within the
+    /// data directory.
+    #[serde(default = "default_write")]
+    pub(crate) write: bool,
+    /// Who each digest is mailed to, if anyone.
+    #[serde(default)]
+    pub(crate) email: Option<String>,
+    /// The program that mail is handed to, which is given the recipient and
+    /// the message on stdin, the way that `sendmail` and `msmtp` take it.
+    #[serde(default = "default_sendmail")]
+    pub(crate) sendmail: String,
+}
+
+impl Default for DigestConfig {
+    fn default() -> DigestConfig {
+        DigestConfig {
+            enabled: false,
+            write: true,
+            email: None,
+            sendmail: DEFAULT_SENDMAIL.to_string(),
+        }
+    }
+}
+
+fn default_write() -> bool {
+    true
+}
+
+fn default_sendmail() -> String {
+    DEFAULT_SENDMAIL.to_string()
+}
@@ -0,0 +1,325 @@
+//! Configuration Editing
+//!
+//! Configuration and trigger files are meant to be edited by hand, so when
+//! Comrade has to change them itself it goes through toml_edit, which keeps
+//! the comments and formatting of the existing documents intact.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use toml_edit::{Array, Document, InlineTable, Item, Table, TableLike, Value};
+
+use crate::config::triggers::{Trigger, TriggerId, TriggerRef, TriggerSource};
+use crate::config::{create_dir, CharacterId, Result};
+use crate::errors::ConfigError;
+
+pub(crate) const DISABLED_TRIGGERS_KEY: &str = "disabled-triggers";
+pub(crate) const ENABLED_TRIGGERS_KEY: &str = "enabled-triggers";
+
+pub(crate) struct TomlFile {
+    filename: PathBuf,
+    doc: Document,
+    modified: bool,
+}
+
+impl TomlFile {
+    pub(crate) fn open(filename: &Path, allow_missing: bool) -> Result<TomlFile> {
+        let contents = match fs::read_to_string(filename) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && allow_missing => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        let doc = contents
+            .parse::<Document>()
+            .map_err(|source| ConfigError::DocumentError {
+                source,
+                filename: filename.to_path_buf(),
+            })?;
+
+        Ok(TomlFile {
+            filename: filename.to_path_buf(),
+            doc,
+            modified: false,
+        })
+    }
+
+    /// Writes the document back to disk, if anything has been modified.
+    pub(crate) fn save(&self) -> Result<()> {
+        if !self.modified {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.filename.parent() {
+            create_dir(parent)?;
+        }
+        fs::write(self.filename.as_path(), self.doc.to_string())?;
+
+        Ok(())
+    }
+}
+
+/// Adds or removes the given trigger from one of the trigger lists (either
+/// the disabled or enabled triggers) of a single character, returning whether
+/// anything actually changed.
+pub(crate) fn set_trigger_listed(
+    file: &mut TomlFile,
+    character: &CharacterId,
+    key: &str,
+    tref: &TriggerRef,
+    listed: bool,
+) -> Result<bool> {
+    let table = file
+        .doc
+        .get_mut("characters")
+        .and_then(|c| c.get_mut(character.as_str()))
+        .and_then(|c| c.as_table_like_mut())
+        .ok_or_else(|| ConfigError::UnknownCharacter {
+            id: character.clone(),
+        })?;
+
+    if !table.contains_key(key) {
+        if !listed {
+            return Ok(false);
+        }
+        table.insert(key, Item::Value(Value::Array(Array::new())));
+    }
+
+    let changed = match table.get_mut(key) {
+        Some(Item::Value(Value::Array(array))) => {
+            let existing = array.iter().position(|v| {
+                v.as_inline_table()
+                    .map(|t| disabled_entry_matches(t, tref))
+                    .unwrap_or(false)
+            });
+
+            match (existing, listed) {
+                (Some(idx), false) => {
+                    array.remove(idx);
+                    true
+                }
+                (None, true) => {
+                    array.push(disabled_entry(tref));
+                    true
+                }
+                _ => false,
+            }
+        }
+        Some(Item::ArrayOfTables(tables)) => {
+            let existing = tables.iter().position(|t| disabled_entry_matches(t, tref));
+
+            match (existing, listed) {
+                (Some(idx), false) => {
+                    tables.remove(idx);
+                    true
+                }
+                (None, true) => {
+                    let mut table = Table::new();
+                    for (key, value) in disabled_entry(tref).iter() {
+                        table.insert(key, Item::Value(value.clone()));
+                    }
+                    tables.push(table);
+                    true
+                }
+                _ => false,
+            }
+        }
+        _ => {
+            return Err(ConfigError::InvalidDocument {
+                filename: file.filename.clone(),
+                reason: format!("{} must be an array", key),
+            })
+        }
+    };
+
+    file.modified |= changed;
+
+    Ok(changed)
+}
+
+/// Adds a new trigger to a trigger file, which may be empty.
+pub(crate) fn insert_trigger(
+    file: &mut TomlFile,
+    source: &TriggerSource,
+    id: &TriggerId,
+    trigger: &Trigger,
+) -> Result<()> {
+    write_trigger(file, source, id, trigger, false)
+}
+
+/// Adds a trigger to a trigger file, replacing any existing trigger with the
+/// same id in place.
+pub(crate) fn replace_trigger(
+    file: &mut TomlFile,
+    source: &TriggerSource,
+    id: &TriggerId,
+    trigger: &Trigger,
+) -> Result<()> {
+    write_trigger(file, source, id, trigger, true)
+}
+
+fn write_trigger(
+    file: &mut TomlFile,
+    source: &TriggerSource,
+    id: &TriggerId,
+    trigger: &Trigger,
+    replace: bool,
+) -> Result<()> {
+    if !file.doc.contains_key("meta") {
+        let mut meta = Table::new();
+        meta.insert("source", Item::Value(source_value(source)));
+        file.doc.insert("meta", Item::Table(meta));
+    }
+
+    let triggers = file
+        .doc
+        .entry("triggers")
+        .or_insert_with(|| {
+            let mut table = Table::new();
+            table.set_implicit(true);
+            Item::Table(table)
+        })
+        .as_table_like_mut()
+        .ok_or_else(|| ConfigError::InvalidDocument {
+            filename: file.filename.clone(),
+            reason: "triggers must be a table".to_string(),
+        })?;
+
+    if !replace && triggers.contains_key(id.as_str()) {
+        return Err(ConfigError::DuplicateTrigger {
+            tref: TriggerRef::new(source.clone(), id.clone()),
+        });
+    }
+
+    let doc = toml_edit::ser::to_document(trigger)?;
+    triggers.insert(id.as_str(), Item::Table(doc.as_table().clone()));
+    file.modified = true;
+
+    Ok(())
+}
+
+/// Removes a trigger from a trigger file, returning whether it existed.
+pub(crate) fn remove_trigger(file: &mut TomlFile, id: &TriggerId) -> Result<bool> {
+    let removed = file
+        .doc
+        .get_mut("triggers")
+        .and_then(|t| t.as_table_like_mut())
+        .and_then(|t| t.remove(id.as_str()))
+        .is_some();
+
+    file.modified |= removed;
+
+    Ok(removed)
+}
+
+/// Disables or enables a trigger source, returning whether anything actually
+/// changed.
+pub(crate) fn set_source_disabled(file: &mut TomlFile, name: &str, disabled: bool) -> Result<bool> {
+    let table = file
+        .doc
+        .get_mut("sources")
+        .and_then(|s| s.get_mut(name))
+        .and_then(|s| s.as_table_like_mut())
+        .ok_or_else(|| ConfigError::UnknownSource {
+            name: name.to_string(),
+        })?;
+
+    let current = table
+        .get("disabled")
+        .and_then(|d| d.as_bool())
+        .unwrap_or(false);
+    if current == disabled {
+        return Ok(false);
+    }
+
+    if disabled {
+        table.insert("disabled", Item::Value(Value::from(true)));
+    } else {
+        table.remove("disabled");
+    }
+    file.modified = true;
+
+    Ok(true)
+}
+
+/// Adds a trigger source to the configuration, or if there already is one
+/// with the given name, points it at the given url instead.
+pub(crate) fn set_source_url(file: &mut TomlFile, name: &str, url: &str) -> Result<()> {
+    let sources = file
+        .doc
+        .entry("sources")
+        .or_insert_with(|| {
+            let mut table = Table::new();
+            table.set_implicit(true);
+            Item::Table(table)
+        })
+        .as_table_like_mut()
+        .ok_or_else(|| ConfigError::InvalidDocument {
+            filename: file.filename.clone(),
+            reason: "sources must be a table".to_string(),
+        })?;
+    let source = sources
+        .entry(name)
+        .or_insert_with(|| Item::Table(Table::new()))
+        .as_table_like_mut()
+        .ok_or_else(|| ConfigError::InvalidDocument {
+            filename: file.filename.clone(),
+            reason: format!("sources.{} must be a table", name),
+        })?;
+
+    source.insert("url", Item::Value(Value::from(url)));
+    file.modified = true;
+
+    Ok(())
+}
+
+/// Removes a trigger source from the configuration.
+pub(crate) fn remove_source(file: &mut TomlFile, name: &str) -> Result<()> {
+    file.doc
+        .get_mut("sources")
+        .and_then(|s| s.as_table_like_mut())
+        .and_then(|s| s.remove(name))
+        .ok_or_else(|| ConfigError::UnknownSource {
+            name: name.to_string(),
+        })?;
+    file.modified = true;
+
+    Ok(())
+}
+
+fn disabled_entry(tref: &TriggerRef) -> InlineTable {
+    let mut entry = InlineTable::new();
+    entry.insert("source", source_value(&tref.source));
+    entry.insert("id", Value::from(tref.id.as_str()));
+    entry
+}
+
+fn disabled_entry_matches(entry: &dyn TableLike, tref: &TriggerRef) -> bool {
+    let id = entry.get("id").and_then(|i| i.as_str());
+    let source = entry.get("source").and_then(source_from_item);
+
+    id == Some(tref.id.as_str()) && source.as_ref() == Some(&tref.source)
+}
+
+fn source_value(source: &TriggerSource) -> Value {
+    match source {
+        TriggerSource::Local => Value::from("local"),
+        TriggerSource::Remote(name) => {
+            let mut table = InlineTable::new();
+            table.insert("remote", Value::from(name.as_str()));
+            Value::InlineTable(table)
+        }
+    }
+}
+
+fn source_from_item(item: &Item) -> Option<TriggerSource> {
+    match item.as_str() {
+        Some(source) => source.parse().ok(),
+        None => item
+            .as_table_like()?
+            .get("remote")?
+            .as_str()
+            .map(|name| TriggerSource::Remote(name.to_string())),
+    }
+}
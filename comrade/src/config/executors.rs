@@ -0,0 +1,82 @@
+//! Executor Configuration
+//!
+//! Each executor that carries out `Custom` actions has a queue and a worker
+//! of its own, so that one that's stuck, like a webhook to a Discord that's
+//! down, only ever holds up its own actions. How long each attempt gets, how
+//! many times a failure is retried, and how many failures in a row it takes
+//! before the executor is left alone for a while can be set for each of them
+//! by name, and anything not set here has the defaults.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_with::{serde_as, DurationMilliSeconds, DurationSeconds};
+
+#[serde_as]
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct ExecutorConfig {
+    /// How long each attempt can take before it's given up on, in seconds.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[serde(default = "default_timeout")]
+    pub(crate) timeout: Duration,
+    /// How many times an action that failed is tried again.
+    #[serde(default = "default_retries")]
+    pub(crate) retries: u32,
+    /// How long to wait before the first retry, in milliseconds, which
+    /// doubles for each one after it.
+    #[serde_as(as = "DurationMilliSeconds<u64>")]
+    #[serde(default = "default_backoff")]
+    pub(crate) backoff: Duration,
+    /// How many actions in a row can fail, after their retries, before the
+    /// executor's actions are skipped rather than queued, or zero to keep
+    /// trying no matter what.
+    #[serde(default = "default_failures")]
+    pub(crate) failures: u32,
+    /// How long actions are skipped for once too many have failed, in
+    /// seconds, after which the next one is tried again.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[serde(default = "default_cooldown")]
+    pub(crate) cooldown: Duration,
+    /// How many actions can be waiting on the executor before any more are
+    /// dropped.
+    #[serde(default = "default_queue")]
+    pub(crate) queue: usize,
+}
+
+impl Default for ExecutorConfig {
+    fn default() -> ExecutorConfig {
+        ExecutorConfig {
+            timeout: default_timeout(),
+            retries: default_retries(),
+            backoff: default_backoff(),
+            failures: default_failures(),
+            cooldown: default_cooldown(),
+            queue: default_queue(),
+        }
+    }
+}
+
+fn default_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+fn default_retries() -> u32 {
+    2
+}
+
+fn default_backoff() -> Duration {
+    Duration::from_millis(500)
+}
+
+fn default_failures() -> u32 {
+    5
+}
+
+fn default_cooldown() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn default_queue() -> usize {
+    32
+}
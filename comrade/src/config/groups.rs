@@ -0,0 +1,101 @@
+//! Character Groups
+//!
+//! Someone playing several characters doesn't play all of them all the time,
+//! the same evening can start with farming on a few boxes and end with a raid
+//! on just the one. Groups name the characters that go together, like
+//! `raid-team` or `trader-boxes`, and anywhere that takes a character's id or
+//! alias takes a group's name as well, meaning every character in it.
+//!
+//! Switching to a group pauses every character outside of it, and switches to
+//! whichever audio controls the group sets, so going from one way of playing
+//! to another is the one switch.
+
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Default, Clone)]
+pub(crate) struct GroupConfig {
+    /// The ids or aliases of the characters in the group.
+    #[serde(default)]
+    pub(crate) characters: Vec<String>,
+    /// The audio controls to switch to along with the group, whichever of
+    /// them are set, the rest are left as they are.
+    #[serde(default)]
+    pub(crate) muted: Option<bool>,
+    #[serde(default, rename = "speech-muted")]
+    pub(crate) speech_muted: Option<bool>,
+    #[serde(default)]
+    pub(crate) volume: Option<u8>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{CharacterId, Config};
+    use crate::errors::ConfigError;
+
+    const CONFIG: &str = r#"
+        [characters.main]
+        name = "Soandso"
+        server = "teek"
+        filename = "eqlog_Soandso_teek.txt"
+        aliases = ["cleric"]
+        disabled-triggers = []
+
+        [characters.box1]
+        name = "Xanthe"
+        server = "teek"
+        filename = "eqlog_Xanthe_teek.txt"
+        disabled-triggers = []
+
+        [characters.box2]
+        name = "Vulak"
+        server = "teek"
+        filename = "eqlog_Vulak_teek.txt"
+        disabled-triggers = []
+
+        [groups.raid]
+        characters = ["cleric"]
+        volume = 80
+
+        [groups.boxes]
+        characters = ["box1", "box2"]
+        muted = true
+    "#;
+
+    #[test]
+    fn groups_stand_in_for_their_characters() {
+        let config: Config = toml_edit::de::from_str(CONFIG).unwrap();
+        config.check_groups().unwrap();
+
+        assert_eq!(
+            config.character_ids("raid"),
+            Some(vec![CharacterId::new("main")])
+        );
+        assert_eq!(
+            config.character_ids("boxes"),
+            Some(vec![CharacterId::new("box1"), CharacterId::new("box2")])
+        );
+        assert_eq!(
+            config.character_ids("box1"),
+            Some(vec![CharacterId::new("box1")])
+        );
+        assert_eq!(config.character_ids("nobody"), None);
+
+        let clashing: Config =
+            toml_edit::de::from_str(&format!("{}\n[groups.cleric]\ncharacters = []", CONFIG))
+                .unwrap();
+        assert!(matches!(
+            clashing.check_groups(),
+            Err(ConfigError::InvalidGroup { .. })
+        ));
+
+        let unknown: Config = toml_edit::de::from_str(&format!(
+            "{}\n[groups.alts]\ncharacters = [\"nobody\"]",
+            CONFIG
+        ))
+        .unwrap();
+        assert!(matches!(
+            unknown.check_groups(),
+            Err(ConfigError::InvalidGroup { .. })
+        ));
+    }
+}
@@ -0,0 +1,40 @@
+//! History Configuration
+//!
+//! Every time a trigger fires it's recorded in the event store, in the data
+//! directory, which is what `history` searches. Each month's records are
+//! compressed once the month is over, and whole months are deleted once
+//! they're older than the store keeps them for, or, oldest first, once the
+//! store is bigger than it's allowed to grow.
+
+use serde::Deserialize;
+
+use crate::config::memory::ByteSize;
+
+/// How many months are kept unless the configuration says otherwise.
+const DEFAULT_KEEP_MONTHS: u32 = 12;
+
+/// How much the store can take up unless the configuration says otherwise.
+const DEFAULT_MAX_SIZE: ByteSize = ByteSize(256 * 1024 * 1024);
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, rename_all = "kebab-case")]
+pub(crate) struct HistoryConfig {
+    /// Whether triggers that fire are recorded at all.
+    pub(crate) record: bool,
+    /// How many months of records are kept, counting the current one, or
+    /// zero to keep them for good.
+    pub(crate) keep_months: u32,
+    /// How much the records can take up between them, or zero for as much as
+    /// they need. The current month is never deleted to make room.
+    pub(crate) max_size: ByteSize,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> HistoryConfig {
+        HistoryConfig {
+            record: true,
+            keep_months: DEFAULT_KEEP_MONTHS,
+            max_size: DEFAULT_MAX_SIZE,
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! Edit Journal
+//!
+//! Every change that Comrade makes to the configuration or trigger files is
+//! recorded as a snapshot of the affected files from before and after the
+//! change, so that a mistaken edit can be undone (and redone) without having
+//! to dig through backups. The pre-edit contents of a file are also written
+//! next to it as a `.bak` file, in case the journal itself is lost.
+//!
+//! Since every edit goes through the journal, it's also where read-only mode
+//! is enforced, by refusing to run any edit at all.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::config::Result;
+use crate::errors::ConfigError;
+
+/// How many edits are kept around to be undone.
+const MAX_ENTRIES: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Snapshot {
+    filename: PathBuf,
+    // None if the file did not exist.
+    contents: Option<String>,
+}
+
+impl Snapshot {
+    fn take(filename: &Path) -> Result<Snapshot> {
+        let contents = match fs::read_to_string(filename) {
+            Ok(contents) => Some(contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Snapshot {
+            filename: filename.to_path_buf(),
+            contents,
+        })
+    }
+
+    fn restore(&self) -> Result<()> {
+        match self.contents {
+            Some(ref contents) => fs::write(self.filename.as_path(), contents)?,
+            None => match fs::remove_file(self.filename.as_path()) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            },
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    description: String,
+    before: Vec<Snapshot>,
+    after: Vec<Snapshot>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Journal {
+    undo: VecDeque<Entry>,
+    redo: Vec<Entry>,
+    read_only: bool,
+}
+
+impl Journal {
+    pub(crate) fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    pub(crate) fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn check_writable(&self) -> Result<()> {
+        match self.read_only {
+            true => Err(ConfigError::ReadOnly),
+            false => Ok(()),
+        }
+    }
+
+    /// Runs an edit that touches the given files, recording their contents
+    /// from before and after it so that it can be undone later.
+    pub(crate) fn record<T, F>(
+        &mut self,
+        description: String,
+        files: &[&Path],
+        edit: F,
+    ) -> Result<T>
+    where
+        F: FnOnce() -> Result<T>,
+    {
+        self.check_writable()?;
+
+        let before = files
+            .iter()
+            .map(|f| Snapshot::take(f))
+            .collect::<Result<Vec<Snapshot>>>()?;
+
+        for snapshot in before.iter() {
+            if let Some(ref contents) = snapshot.contents {
+                fs::write(backup_filename(snapshot.filename.as_path()), contents)?;
+            }
+        }
+
+        // If the edit fails part of the way through, we still want to be able
+        // to undo whatever it did manage to do, so we record it either way.
+        let result = edit();
+
+        let after = files
+            .iter()
+            .map(|f| Snapshot::take(f))
+            .collect::<Result<Vec<Snapshot>>>()?;
+
+        if before != after {
+            if self.undo.len() >= MAX_ENTRIES {
+                self.undo.pop_front();
+            }
+            self.undo.push_back(Entry {
+                description,
+                before,
+                after,
+            });
+            self.redo.clear();
+        }
+
+        result
+    }
+
+    /// Reverts the most recent edit, returning its description or None if
+    /// there was nothing to undo.
+    pub(crate) fn undo(&mut self) -> Result<Option<String>> {
+        self.check_writable()?;
+        let entry = match self.undo.pop_back() {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        match swap(&entry.after, &entry.before) {
+            Ok(()) => {
+                let description = entry.description.clone();
+                self.redo.push(entry);
+                Ok(Some(description))
+            }
+            Err(e) => {
+                self.undo.push_back(entry);
+                Err(e)
+            }
+        }
+    }
+
+    /// Reapplies the most recently undone edit, returning its description or
+    /// None if there was nothing to redo.
+    pub(crate) fn redo(&mut self) -> Result<Option<String>> {
+        self.check_writable()?;
+        let entry = match self.redo.pop() {
+            Some(entry) => entry,
+            None => return Ok(None),
+        };
+
+        match swap(&entry.before, &entry.after) {
+            Ok(()) => {
+                let description = entry.description.clone();
+                self.undo.push_back(entry);
+                Ok(Some(description))
+            }
+            Err(e) => {
+                self.redo.push(entry);
+                Err(e)
+            }
+        }
+    }
+
+    pub(crate) fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub(crate) fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+/// Replaces the files in `expected` with the files in `replacement`, so long
+/// as they haven't been changed by anything else in the meantime.
+fn swap(expected: &[Snapshot], replacement: &[Snapshot]) -> Result<()> {
+    for snapshot in expected.iter() {
+        if Snapshot::take(snapshot.filename.as_path())? != *snapshot {
+            return Err(ConfigError::EditConflict {
+                filename: snapshot.filename.clone(),
+            });
+        }
+    }
+
+    for (current, snapshot) in expected.iter().zip(replacement.iter()) {
+        if let Some(ref contents) = current.contents {
+            fs::write(backup_filename(current.filename.as_path()), contents)?;
+        }
+        snapshot.restore()?;
+    }
+
+    Ok(())
+}
+
+fn backup_filename(filename: &Path) -> PathBuf {
+    let mut name = filename.as_os_str().to_os_string();
+    name.push(".bak");
+    PathBuf::from(name)
+}
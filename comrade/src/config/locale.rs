@@ -0,0 +1,230 @@
+//! Locale Configuration
+//!
+//! The text of Comrade's own alerts, like where a corpse is, comes from the
+//! catalogs in `locales.toml`, in whichever language has been picked here, or
+//! for the character the alert is for. Any of the texts can be replaced, in
+//! any language, and a language can be added by giving its texts here, with
+//! anything that's missing from a language falling back to English.
+//!
+//! Reading alerts out is up to frontends, so the voice for text to speech is
+//! only passed along, together with the language, for them to pick from what
+//! the platform has.
+
+use std::collections::BTreeMap;
+
+use lazy_static::lazy_static;
+use serde::Deserialize;
+
+use crate::config::Character;
+use crate::errors::ConfigError;
+
+/// The language that texts fall back to, which every text is in.
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+const BUILTIN_CATALOGS: &str = include_str!("../locales.toml");
+
+type Catalog = BTreeMap<String, String>;
+
+lazy_static! {
+    static ref CATALOGS: BTreeMap<String, Catalog> =
+        toml_edit::de::from_str(BUILTIN_CATALOGS).expect("built in locales should be valid");
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct LocaleConfig {
+    /// A language tag like `de` or `pt-BR`, a tag for a region falls back to
+    /// the language on its own.
+    #[serde(default = "default_language")]
+    pub(crate) language: String,
+    #[serde(default)]
+    pub(crate) voice: Option<String>,
+    /// Texts that replace or add to the built in ones, by language then key.
+    #[serde(default)]
+    pub(crate) texts: BTreeMap<String, Catalog>,
+}
+
+impl Default for LocaleConfig {
+    fn default() -> LocaleConfig {
+        LocaleConfig {
+            language: default_language(),
+            voice: None,
+            texts: BTreeMap::new(),
+        }
+    }
+}
+
+/// The language and voice that frontends should read a character's alerts
+/// out with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Speech {
+    pub language: String,
+    /// The name of a voice, as the platform's text to speech knows it, if
+    /// one has been picked.
+    pub voice: Option<String>,
+}
+
+impl LocaleConfig {
+    pub(crate) fn speech(&self, character: Option<&Character>) -> Speech {
+        Speech {
+            language: character
+                .and_then(|c| c.language.clone())
+                .unwrap_or_else(|| self.language.clone()),
+            voice: character
+                .and_then(|c| c.voice.clone())
+                .or_else(|| self.voice.clone()),
+        }
+    }
+
+    /// The text for the given key, in the character's language, with each
+    /// `{placeholder}` in it filled in from `args`.
+    pub(crate) fn text(&self, character: &Character, key: &str, args: &[(&str, &str)]) -> String {
+        let language = character
+            .language
+            .as_deref()
+            .unwrap_or(self.language.as_str());
+        let mut languages = vec![language];
+        if let Some((base, _)) = language.split_once('-') {
+            languages.push(base);
+        }
+        languages.push(DEFAULT_LANGUAGE);
+
+        let template = languages
+            .iter()
+            .find_map(|lang| {
+                [&self.texts, &*CATALOGS]
+                    .iter()
+                    .find_map(|catalogs| catalogs.get(*lang)?.get(key))
+            })
+            .unwrap_or_else(|| panic!("no text for {:?} in the built in locales", key));
+
+        args.iter().fold(template.clone(), |text, (name, value)| {
+            text.replace(format!("{{{}}}", name).as_str(), value)
+        })
+    }
+
+    /// Makes sure that every text that's been configured replaces one that
+    /// Comrade actually has, so that a typo in a key isn't silently ignored.
+    pub(crate) fn check(&self) -> Result<(), ConfigError> {
+        let known = &CATALOGS[DEFAULT_LANGUAGE];
+        for (language, texts) in self.texts.iter() {
+            if let Some(key) = texts.keys().find(|k| !known.contains_key(*k)) {
+                return Err(ConfigError::UnknownText {
+                    language: language.clone(),
+                    key: key.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn default_language() -> String {
+    DEFAULT_LANGUAGE.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use crate::config::ClockOffset;
+
+    use super::*;
+
+    fn character(language: Option<&str>) -> Character {
+        Character {
+            name: "Soandso".to_string(),
+            server: "test".to_string(),
+            filename: PathBuf::new(),
+            aliases: Vec::new(),
+            display_name: None,
+            clock_offset: ClockOffset::default(),
+            language: language.map(str::to_string),
+            voice: None,
+            disabled_triggers: HashMap::new(),
+            enabled_triggers: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn every_language_has_only_known_texts() {
+        let known = &CATALOGS[DEFAULT_LANGUAGE];
+        for (language, catalog) in CATALOGS.iter() {
+            for key in catalog.keys() {
+                assert!(
+                    known.contains_key(key),
+                    "{} has unknown text {}",
+                    language,
+                    key
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn texts_fall_back_to_english() {
+        let config: LocaleConfig = toml_edit::de::from_str(
+            r#"
+            language = "de-AT"
+            voice = "Anna"
+
+            [texts.de-AT]
+            corpse-decays = "Die Leich' von {name} verfällt"
+
+            [texts.nl]
+            corpse-in = "Het lijk van {name} ligt in {zone}"
+            "#,
+        )
+        .unwrap();
+        config.check().unwrap();
+
+        let args = [("name", "Soandso"), ("zone", "Lavastorm")];
+        let text = |language, key| config.text(&character(language), key, &args);
+        assert_eq!(
+            text(None, "corpse-decays"),
+            "Die Leich' von Soandso verfällt"
+        );
+        assert_eq!(
+            text(None, "corpse-in"),
+            "Die Leiche von Soandso liegt in Lavastorm"
+        );
+        assert_eq!(
+            text(Some("nl"), "corpse-in"),
+            "Het lijk van Soandso ligt in Lavastorm"
+        );
+        assert_eq!(text(Some("nl"), "corpse-decays"), "Soandso's corpse decays");
+
+        let mut nl = character(Some("nl"));
+        nl.voice = Some("Xander".to_string());
+        assert_eq!(
+            config.speech(Some(&nl)),
+            Speech {
+                language: "nl".to_string(),
+                voice: Some("Xander".to_string())
+            }
+        );
+        assert_eq!(
+            config.speech(None),
+            Speech {
+                language: "de-AT".to_string(),
+                voice: Some("Anna".to_string())
+            }
+        );
+    }
+
+    #[test]
+    fn unknown_texts_are_rejected() {
+        let config: LocaleConfig = toml_edit::de::from_str(
+            r#"
+            [texts.fr]
+            corpse-decay = "Le corps de {name} se décompose"
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config.check(),
+            Err(ConfigError::UnknownText { ref key, .. }) if key == "corpse-decay"
+        ));
+    }
+}
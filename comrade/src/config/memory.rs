@@ -0,0 +1,103 @@
+//! Memory Configuration
+//!
+//! Comrade is meant to be left running, and everything that it keeps track of
+//! along the way, like finished fights and /who snapshots, is kept in memory
+//! for the frontends to show. Each of those stores is given a budget, and once
+//! one is over its budget, its least recently used entries are evicted until
+//! it isn't, so that a session that's gone on for days doesn't keep growing.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer};
+
+use crate::errors::ConfigError;
+
+const KIB: usize = 1024;
+const MIB: usize = 1024 * KIB;
+const GIB: usize = 1024 * MIB;
+
+/// A number of bytes, like `4MiB`, or just `4194304`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct ByteSize(pub(crate) usize);
+
+impl FromStr for ByteSize {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<ByteSize, ConfigError> {
+        let invalid = || ConfigError::InvalidSize {
+            value: s.to_string(),
+        };
+
+        let s = s.trim();
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number: usize = number.parse().map_err(|_| invalid())?;
+        let unit = match unit.trim().to_lowercase().as_str() {
+            "" | "b" => 1,
+            "k" | "kb" | "kib" => KIB,
+            "m" | "mb" | "mib" => MIB,
+            "g" | "gb" | "gib" => GIB,
+            _ => return Err(invalid()),
+        };
+
+        number.checked_mul(unit).map(ByteSize).ok_or_else(invalid)
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteSize {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Size {
+            Bytes(usize),
+            Text(String),
+        }
+
+        match Size::deserialize(deserializer)? {
+            Size::Bytes(bytes) => Ok(ByteSize(bytes)),
+            Size::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+/// The budget for each of the stores, by how much memory their entries can
+/// take up between them.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct MemoryConfig {
+    pub(crate) fights: ByteSize,
+    pub(crate) rosters: ByteSize,
+    pub(crate) recipes: ByteSize,
+    pub(crate) resists: ByteSize,
+    pub(crate) earnings: ByteSize,
+    /// How many of the most recent events are kept for frontends that attach
+    /// late, which is a count rather than a size.
+    pub(crate) events: usize,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> MemoryConfig {
+        MemoryConfig {
+            fights: ByteSize(8 * MIB),
+            rosters: ByteSize(4 * MIB),
+            recipes: ByteSize(MIB),
+            resists: ByteSize(MIB),
+            earnings: ByteSize(2 * MIB),
+            events: 500,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!("4MiB".parse::<ByteSize>().unwrap(), ByteSize(4 * MIB));
+        assert_eq!("512 kb".parse::<ByteSize>().unwrap(), ByteSize(512 * KIB));
+        assert_eq!("100".parse::<ByteSize>().unwrap(), ByteSize(100));
+        assert!("4 parsecs".parse::<ByteSize>().is_err());
+        assert!("MiB".parse::<ByteSize>().is_err());
+    }
+}
@@ -0,0 +1,55 @@
+//! Metrics Configuration
+//!
+//! Metrics are exported to a timeseries database for dashboards that go back
+//! further than anything Comrade keeps, like how raid DPS has changed over a
+//! season, or whether Comrade keeps up with the logs on raid nights. They're
+//! written in InfluxDB's line protocol, either straight to InfluxDB or to a
+//! file that something like Telegraf picks up.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_with::{serde_as, DurationSeconds};
+
+/// How often metrics are exported, unless the configuration says otherwise.
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub(crate) enum SinkConfig {
+    /// InfluxDB's write endpoint, like
+    /// `http://localhost:8086/api/v2/write?org=guild&bucket=comrade`, which
+    /// needs Comrade to have been built with the `metrics` feature.
+    Influxdb { url: String, token: Option<String> },
+    /// A file that each batch is appended to, which is relative to the data
+    /// directory unless it's absolute.
+    File { path: PathBuf },
+}
+
+#[serde_as]
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MetricsConfig {
+    #[serde(default)]
+    pub(crate) enabled: bool,
+    /// In seconds.
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[serde(default = "default_interval")]
+    pub(crate) interval: Duration,
+    #[serde(default)]
+    pub(crate) sink: Option<SinkConfig>,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> MetricsConfig {
+        MetricsConfig {
+            enabled: false,
+            interval: DEFAULT_INTERVAL,
+            sink: None,
+        }
+    }
+}
+
+fn default_interval() -> Duration {
+    DEFAULT_INTERVAL
+}
@@ -1,19 +1,87 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 use std::fs;
 use std::io;
 use std::io::prelude::*;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::Arc;
 
 use arc_swap::{ArcSwap, Cache, Guard};
 use platform_dirs::AppDirs;
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::config::triggers::{DisabledTrigger, TriggerRef, Triggers};
-use crate::errors::ConfigError;
+use crate::config::accessibility::AccessibilityConfig;
+use crate::config::attendance::AttendanceConfig;
+use crate::config::broadcasts::BroadcastsConfig;
+use crate::config::combat::CombatConfig;
+use crate::config::corpses::CorpsesConfig;
+use crate::config::currency::CurrencyConfig;
+#[cfg(feature = "dashboard")]
+use crate::config::dashboard::DashboardConfig;
+use crate::config::digest::DigestConfig;
+use crate::config::executors::ExecutorConfig;
+use crate::config::groups::GroupConfig;
+use crate::config::history::HistoryConfig;
+use crate::config::locale::LocaleConfig;
+use crate::config::memory::MemoryConfig;
+use crate::config::metrics::MetricsConfig;
+use crate::config::outputs::OutputConfig;
+use crate::config::profiles::{profile_dir, profiles, PROFILES_DIRNAME};
+use crate::config::resists::ResistsConfig;
+#[cfg(feature = "dashboard")]
+use crate::config::secrets::Secrets;
+use crate::config::sources::SourceConfig;
+use crate::config::timers::TimersConfig;
+use crate::config::tradeskills::TradeskillsConfig;
+use crate::config::triggers::{
+    local_triggers_file, DisabledTrigger, PatternCache, Trigger, TriggerRef, TriggerSource,
+    Triggers,
+};
+use crate::config::ui::UiConfig;
+use crate::config::updates::UpdatesConfig;
+use crate::config::watchdog::WatchdogConfig;
+use crate::config::zones::ZonesConfig;
+use crate::demo;
+use crate::errors::{ConfigError, TimerError};
 use crate::meta;
+use crate::timers::parse_duration;
 
+pub(crate) mod accessibility;
+pub(crate) mod attendance;
+pub(crate) mod broadcasts;
+pub(crate) mod combat;
+pub(crate) mod compliance;
+pub(crate) mod corpses;
+pub(crate) mod currency;
+#[cfg(feature = "dashboard")]
+pub(crate) mod dashboard;
+pub(crate) mod diff;
+pub(crate) mod digest;
+pub(crate) mod edit;
+pub(crate) mod executors;
+pub(crate) mod groups;
+pub(crate) mod history;
+pub(crate) mod journal;
+pub(crate) mod locale;
+pub(crate) mod memory;
+pub(crate) mod metrics;
+pub(crate) mod outputs;
+pub(crate) mod packs;
+pub(crate) mod profiles;
+pub(crate) mod resists;
+pub(crate) mod scaffold;
+pub(crate) mod schedule;
+pub(crate) mod search;
+pub(crate) mod secrets;
+pub(crate) mod sources;
+pub(crate) mod timers;
+pub(crate) mod tradeskills;
 pub(crate) mod triggers;
+pub(crate) mod ui;
+pub(crate) mod updates;
+pub(crate) mod watchdog;
+pub(crate) mod zones;
 
 const CONFIG_FILENAME: &str = "Config.toml";
 
@@ -28,7 +96,7 @@ fn default_dirs() -> AppDirs {
         .expect("could not determine application directories")
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub(crate) struct Directories {
     #[serde(skip)]
     pub(crate) config: PathBuf,
@@ -46,21 +114,113 @@ impl Default for Directories {
     }
 }
 
-#[derive(Deserialize, Debug, Default, PartialEq, Eq, Hash, Clone)]
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 #[serde(transparent)]
-pub(crate) struct CharacterId(String);
+pub struct CharacterId(String);
+
+impl CharacterId {
+    pub fn new<T: Into<String>>(id: T) -> CharacterId {
+        CharacterId(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl fmt::Display for CharacterId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0.as_str())
+    }
+}
+
+/// What to add to the timestamps in a log to get the time on this computer's
+/// clock, like `-3h` for a log written three hours ahead of us, or `+30m`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ClockOffset(i64);
+
+impl ClockOffset {
+    pub fn seconds(&self) -> i64 {
+        self.0
+    }
+}
+
+impl FromStr for ClockOffset {
+    type Err = TimerError;
+
+    fn from_str(s: &str) -> std::result::Result<ClockOffset, TimerError> {
+        let s = s.trim();
+        let (sign, duration) = match s.strip_prefix('-') {
+            Some(rest) => (-1, rest),
+            None => (1, s.strip_prefix('+').unwrap_or(s)),
+        };
+
+        Ok(ClockOffset(
+            sign * parse_duration(duration)?.as_secs() as i64,
+        ))
+    }
+}
+
+impl<'de> Deserialize<'de> for ClockOffset {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Character {
     pub name: String,
     pub server: String,
     pub filename: PathBuf,
+    /// Other names the character can be referred to by, like `main` or
+    /// `boxshm`, anywhere that its id can be.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// What frontends call the character, instead of its name.
+    #[serde(default, rename = "display-name")]
+    pub display_name: Option<String>,
+    /// How far off the clock of the computer writing the log is, for logs
+    /// forwarded from another computer or old logs from before a move.
+    #[serde(default, rename = "clock-offset")]
+    pub clock_offset: ClockOffset,
+    /// The language that Comrade's own alerts are in for this character,
+    /// instead of the one in `[locale]`.
+    #[serde(default)]
+    pub language: Option<String>,
+    /// The text to speech voice that frontends read this character's alerts
+    /// out with, instead of the one in `[locale]`.
+    #[serde(default)]
+    pub voice: Option<String>,
     #[serde(rename = "disabled-triggers")]
-    #[serde(with = "disabled_triggers")]
+    #[serde(with = "trigger_refs")]
     pub disabled_triggers: HashMap<TriggerRef, DisabledTrigger>,
+    #[serde(rename = "enabled-triggers")]
+    #[serde(default, with = "trigger_refs")]
+    pub enabled_triggers: HashMap<TriggerRef, DisabledTrigger>,
 }
 
-#[derive(Deserialize, Debug, Default)]
+impl Character {
+    pub fn display_name(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(self.name.as_str())
+    }
+
+    /// Triggers are enabled unless this character has explicitly disabled
+    /// them, except for triggers that are disabled by default, which have to
+    /// be explicitly enabled instead.
+    pub fn is_trigger_enabled(&self, tref: &TriggerRef, trigger: &Trigger) -> bool {
+        if self.disabled_triggers.contains_key(tref) {
+            false
+        } else if trigger.disabled {
+            self.enabled_triggers.contains_key(tref)
+        } else {
+            true
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
 pub(crate) struct Config {
     #[serde(default)]
     pub(crate) dirs: Directories,
@@ -68,17 +228,126 @@ pub(crate) struct Config {
     #[serde(default)]
     pub(crate) characters: HashMap<CharacterId, Character>,
 
+    /// The groups of characters, by name.
+    #[serde(default)]
+    pub(crate) groups: BTreeMap<String, GroupConfig>,
+
+    #[serde(default)]
+    pub(crate) timers: TimersConfig,
+
+    #[serde(default)]
+    pub(crate) ui: UiConfig,
+
+    #[serde(default)]
+    pub(crate) combat: CombatConfig,
+
+    #[serde(default)]
+    pub(crate) attendance: AttendanceConfig,
+
+    #[serde(default)]
+    pub(crate) tradeskills: TradeskillsConfig,
+
+    #[serde(default)]
+    pub(crate) currency: CurrencyConfig,
+
+    #[serde(default)]
+    pub(crate) resists: ResistsConfig,
+
+    #[serde(default)]
+    pub(crate) corpses: CorpsesConfig,
+
+    #[serde(default)]
+    pub(crate) broadcasts: BroadcastsConfig,
+
+    #[serde(default)]
+    pub(crate) updates: UpdatesConfig,
+
+    #[serde(default)]
+    pub(crate) accessibility: AccessibilityConfig,
+
+    #[serde(default)]
+    pub(crate) locale: LocaleConfig,
+
+    #[serde(default)]
+    pub(crate) memory: MemoryConfig,
+
+    #[serde(default)]
+    pub(crate) watchdog: WatchdogConfig,
+
+    #[serde(default)]
+    pub(crate) digest: DigestConfig,
+
+    #[serde(default)]
+    pub(crate) metrics: MetricsConfig,
+
+    #[serde(default)]
+    pub(crate) history: HistoryConfig,
+
+    #[cfg(feature = "dashboard")]
+    #[serde(default)]
+    pub(crate) dashboard: DashboardConfig,
+
+    /// The outputs, by name, that triggers can signal.
+    #[serde(default)]
+    pub(crate) outputs: BTreeMap<String, OutputConfig>,
+
+    /// How the executors that carry out `Custom` actions are run, by name.
+    #[serde(default)]
+    pub(crate) executors: BTreeMap<String, ExecutorConfig>,
+
+    /// The trigger groups to turn on or off in each zone.
+    #[serde(default)]
+    pub(crate) zones: ZonesConfig,
+
+    /// The remote trigger sources, by name.
+    #[serde(default)]
+    pub(crate) sources: BTreeMap<String, SourceConfig>,
+
+    /// Empty until `load_triggers` is called.
     #[serde(skip)]
     pub(crate) triggers: Triggers,
+
+    /// Set for the made up configuration of demo mode, whose triggers are
+    /// built in rather than loaded.
+    #[serde(skip)]
+    pub(crate) demo: bool,
 }
 
 impl Config {
     pub(crate) fn from_default_dir() -> Result<Config> {
         let filename = default_dirs().config_dir.join(CONFIG_FILENAME);
-        match try_open_config_file(filename.as_path(), true)? {
-            Some(file) => parse_config(filename.as_path(), file),
-            None => Ok(Config::default()),
+        let config = match try_open_config_file(filename.as_path(), true)? {
+            Some(file) => parse_config(filename.as_path(), file)?,
+            None => Config::default(),
+        };
+
+        Ok(config)
+    }
+
+    /// Loads the configuration from the given directory, or from the
+    /// default one, or the given profile's configuration within it.
+    pub(crate) fn load(config_dir: Option<PathBuf>, profile: Option<&str>) -> Result<Config> {
+        let name = match profile {
+            Some(name) => name,
+            None => {
+                return match config_dir {
+                    Some(path) => Config::from_config_dir(path),
+                    None => Config::from_default_dir(),
+                }
+            }
+        };
+        if !profiles(config_dir.as_deref()).iter().any(|p| p == name) {
+            return Err(ConfigError::UnknownProfile {
+                name: name.to_string(),
+            });
+        }
+
+        let mut config = Config::from_config_dir(profile_dir(config_dir.as_deref(), name))?;
+        if config.dirs.data == Directories::default().data {
+            config.dirs.data = config.dirs.data.join(PROFILES_DIRNAME).join(name);
         }
+
+        Ok(config)
     }
 
     pub(crate) fn from_config_dir(path: PathBuf) -> Result<Config> {
@@ -88,19 +357,179 @@ impl Config {
         let mut config = parse_config(filename.as_path(), file)?;
 
         config.dirs.config = path;
-        config.triggers = Triggers::load(config.dirs.data.as_path(), &config.characters)?;
 
         Ok(config)
     }
+
+    /// Loads the triggers from every source, which with large packs can
+    /// take a while, see `Triggers::load`. Patterns that are in the cache
+    /// aren't compiled again, and the ones that weren't are added to it.
+    pub(crate) fn load_triggers(
+        &mut self,
+        cache: &PatternCache,
+        mut progress: impl FnMut(&TriggerSource, usize, usize),
+    ) {
+        if self.demo {
+            let pack = demo::pack();
+            let source = pack.meta.source.clone();
+            self.triggers =
+                Triggers::from_set(pack, &self.characters, &self.timers, &self.zones, cache);
+            progress(&source, 1, 1);
+            return;
+        }
+
+        self.triggers = Triggers::load(
+            self.dirs.data.as_path(),
+            &self.characters,
+            &self.timers,
+            &self.sources,
+            &self.zones,
+            cache,
+            progress,
+        );
+    }
+
+    /// Finds the character with the given id, or failing that, the one with
+    /// the given alias, ignoring case.
+    pub(crate) fn character_id(&self, name: &str) -> Option<CharacterId> {
+        let id = CharacterId::new(name);
+        if self.characters.contains_key(&id) {
+            return Some(id);
+        }
+
+        self.characters
+            .iter()
+            .find(|(_, c)| c.aliases.iter().any(|a| a.eq_ignore_ascii_case(name)))
+            .map(|(id, _)| id.clone())
+    }
+
+    /// The characters that the given name stands for, which is every
+    /// character in the group if it's a group's name, or the character it's
+    /// the id or alias of.
+    pub(crate) fn character_ids(&self, name: &str) -> Option<Vec<CharacterId>> {
+        match self.groups.get(name) {
+            Some(group) => Some(
+                group
+                    .characters
+                    .iter()
+                    .filter_map(|c| self.character_id(c))
+                    .collect(),
+            ),
+            None => self.character_id(name).map(|id| vec![id]),
+        }
+    }
+
+    /// Makes sure that every group's characters exist, and that no group
+    /// could be mistaken for a character.
+    fn check_groups(&self) -> Result<()> {
+        for (name, group) in self.groups.iter() {
+            if self.character_id(name).is_some() {
+                return Err(ConfigError::InvalidGroup {
+                    name: name.clone(),
+                    reason: "it has the same name as a character".to_string(),
+                });
+            }
+            if let Some(missing) = group
+                .characters
+                .iter()
+                .find(|c| self.character_id(c).is_none())
+            {
+                return Err(ConfigError::InvalidGroup {
+                    name: name.clone(),
+                    reason: format!("there's no character {:?}", missing),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Makes sure that every alias refers to exactly one character, and that
+    /// none of them could be mistaken for another character's id.
+    fn check_aliases(&self) -> Result<()> {
+        let mut seen = HashSet::new();
+        for character in self.characters.values() {
+            for alias in character.aliases.iter() {
+                let lowered = alias.to_lowercase();
+                let is_id = self
+                    .characters
+                    .keys()
+                    .any(|id| id.as_str().eq_ignore_ascii_case(alias));
+                if is_id || !seen.insert(lowered) {
+                    return Err(ConfigError::DuplicateAlias {
+                        alias: alias.clone(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn config_file(&self) -> PathBuf {
+        self.dirs.config.join(CONFIG_FILENAME)
+    }
+
+    /// Creates whichever of the configuration, data, and local trigger
+    /// directories don't exist yet, so that the first time anything is
+    /// written to them it has somewhere to go.
+    pub(crate) fn create_dirs(&self) -> io::Result<()> {
+        create_dir(self.dirs.config.as_path())?;
+        create_dir(self.dirs.data.as_path())?;
+        if let Some(local_dir) = local_triggers_file(self.dirs.data.as_path()).parent() {
+            create_dir(local_dir)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Creates the given directory, along with any of its parents that are
+/// missing, so that only the user can get at them, since what's in them says a
+/// lot about where and when they play.
+pub(crate) fn create_dir(path: &Path) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+
+    builder.create(path)
 }
 
 fn parse_config(filename: &Path, mut file: fs::File) -> Result<Config> {
     let mut buffer = String::new();
     file.read_to_string(&mut buffer)?;
-    toml_edit::de::from_str(buffer.as_str()).map_err(|source| ConfigError::DeserializationError {
-        source,
-        filename: filename.to_path_buf(),
-    })
+    let config: Config = toml_edit::de::from_str(buffer.as_str()).map_err(|source| {
+        ConfigError::DeserializationError {
+            source,
+            filename: filename.to_path_buf(),
+        }
+    })?;
+    config.check_aliases()?;
+    config.check_groups()?;
+    config.accessibility.check()?;
+    config.locale.check()?;
+
+    #[cfg(feature = "dashboard")]
+    let config = load_secrets(config, filename);
+
+    Ok(config)
+}
+
+/// Fills in what the configuration file didn't set from the secrets that live
+/// next to it, since the configuration file can override them.
+#[cfg(feature = "dashboard")]
+fn load_secrets(mut config: Config, filename: &Path) -> Config {
+    if config.dashboard.token.is_none() {
+        if let Some(config_dir) = filename.parent() {
+            config.dashboard.token = Secrets::load(config_dir).dashboard_token;
+        }
+    }
+
+    config
 }
 
 fn try_open_config_file(filename: &Path, allow_missing: bool) -> Result<Option<fs::File>> {
@@ -120,7 +549,7 @@ fn try_open_config_file(filename: &Path, allow_missing: bool) -> Result<Option<f
     Ok(file)
 }
 
-mod disabled_triggers {
+mod trigger_refs {
     use std::collections::HashMap;
 
     use serde::de::{Deserialize, Deserializer};
@@ -0,0 +1,23 @@
+//! Output Configuration
+//!
+//! Besides the events that frontends show or read out, triggers can signal
+//! physical outputs, like a vibration motor on a microcontroller plugged in
+//! over USB, for alerts that can't be missed even with the sound off and the
+//! overlay out of sight. Each output is given a name here, which `Signal`
+//! actions refer to it by.
+
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub(crate) enum OutputConfig {
+    /// A serial device, like `/dev/ttyACM0` or `COM3`, that's sent each
+    /// pattern as a line of pulse lengths for it to play itself. It has to
+    /// already be set to the right baud rate.
+    Serial { path: PathBuf },
+    /// A GPIO pin's value file, like `/sys/class/gpio/gpio17/value`, which is
+    /// switched on and off as the pattern plays.
+    Gpio { path: PathBuf },
+}
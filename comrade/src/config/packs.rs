@@ -0,0 +1,424 @@
+//! Trigger Packs
+//!
+//! A pack bundles a trigger file with the sounds that its triggers use and a
+//! little about itself, so that sharing one is a single `.comradepack` file
+//! rather than a folder and a README. Packs are built from a directory that's
+//! laid out the same way as what ends up in them:
+//!
+//! - `Pack.toml`, the pack's name, version, and description.
+//! - `Triggers.toml`, the triggers themselves, along with the styles they
+//!   have unless they say otherwise, under `[styles.defaults]` for every
+//!   trigger and `[styles.groups.<tag>]` for the ones with a given tag.
+//! - `sounds/`, any sound files, which can't be nested any deeper.
+//! - `Pack.sig`, an optional signature, which is carried along with the pack
+//!   but can't be verified yet.
+//!
+//! Packs can say which version of Comrade they need at least, and which other
+//! packs they depend on, like a sound pack, which are checked when they're
+//! installed and again whenever their triggers are loaded, so that a pack
+//! that can't work fails loudly rather than half of it quietly not working.
+//!
+//! The archive is just those files one after another, each as its path and
+//! then its contents, both prefixed with their length, after a header that
+//! says what the file is. Installing a pack adds it as a remote source that
+//! syncs from the archive, so it's unpacked into the data directory like
+//! any other source.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::config::create_dir;
+use crate::config::sources::{is_valid_name, remote_triggers_file};
+use crate::config::triggers::{TriggerSet, TRIGGER_FILENAME};
+use crate::errors::PackError;
+use crate::meta;
+use crate::version;
+
+type Result<T, E = PackError> = core::result::Result<T, E>;
+
+pub(crate) const PACK_EXTENSION: &str = "comradepack";
+
+const MAGIC: &[u8] = b"COMRADEPACK";
+const FORMAT_VERSION: u8 = 1;
+
+const META_FILENAME: &str = "Pack.toml";
+const SIGNATURE_FILENAME: &str = "Pack.sig";
+const SOUNDS_DIRNAME: &str = "sounds";
+
+/// What a pack says about itself.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PackMeta {
+    /// Also the name of the source that the pack is installed as.
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    /// The oldest version of Comrade that the pack works with.
+    #[serde(default, rename = "min-comrade-version")]
+    pub min_comrade_version: Option<String>,
+    /// The other packs that have to be installed for this one to work, by
+    /// name, along with the oldest version of each that will do.
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, String>,
+}
+
+/// A single file within a pack.
+struct Entry {
+    name: String,
+    contents: Vec<u8>,
+}
+
+/// Whether the given path looks like a pack, rather than a trigger file.
+pub(crate) fn is_pack(path: &Path) -> bool {
+    path.extension().is_some_and(|e| e == PACK_EXTENSION)
+}
+
+/// Builds a pack from the given directory, writing it to `output` or, if
+/// that isn't given, to `<name>-<version>.comradepack` in the current
+/// directory.
+pub(crate) fn build(dir: &Path, output: Option<&Path>) -> Result<(PackMeta, PathBuf)> {
+    let mut entries = Vec::new();
+    for name in [META_FILENAME, TRIGGER_FILENAME] {
+        entries.push(Entry {
+            name: name.to_string(),
+            contents: read_file(dir.join(name).as_path())?,
+        });
+    }
+
+    let signature = dir.join(SIGNATURE_FILENAME);
+    if signature.exists() {
+        entries.push(Entry {
+            name: SIGNATURE_FILENAME.to_string(),
+            contents: read_file(signature.as_path())?,
+        });
+    }
+
+    let sounds_dir = dir.join(SOUNDS_DIRNAME);
+    if sounds_dir.is_dir() {
+        let io_error = |source| PackError::IOError {
+            source,
+            filename: sounds_dir.clone(),
+        };
+        let mut sounds = fs::read_dir(sounds_dir.as_path())
+            .map_err(io_error)?
+            .collect::<io::Result<Vec<_>>>()
+            .map_err(io_error)?;
+        sounds.sort_by_key(|e| e.file_name());
+
+        for sound in sounds {
+            let path = sound.path();
+            if !path.is_file() {
+                continue;
+            }
+            let name = sound
+                .file_name()
+                .into_string()
+                .map_err(|_| PackError::InvalidPath {
+                    path: path.to_string_lossy().into_owned(),
+                })?;
+            entries.push(Entry {
+                name: format!("{}/{}", SOUNDS_DIRNAME, name),
+                contents: read_file(path.as_path())?,
+            });
+        }
+    }
+
+    let (meta, _) = check(&entries)?;
+    let output = match output {
+        Some(output) => output.to_path_buf(),
+        None => PathBuf::from(format!("{}-{}.{}", meta.name, meta.version, PACK_EXTENSION)),
+    };
+    write_file(output.as_path(), encode(&entries).as_slice())?;
+
+    Ok((meta, output))
+}
+
+/// Reads what the pack at the given path says about itself, making sure that
+/// the rest of it is valid too.
+pub(crate) fn read_meta(filename: &Path) -> Result<PackMeta> {
+    let entries = decode(filename, read_file(filename)?.as_slice())?;
+    let (meta, _) = check(&entries)?;
+
+    Ok(meta)
+}
+
+/// Unpacks the pack at the given path into a source's directory, with its
+/// triggers in the given trigger file and its sounds next to it, returning
+/// how many triggers it has. Sounds from an older version of the pack that
+/// aren't in this one are removed.
+pub(crate) fn unpack(filename: &Path, dest: &Path) -> Result<usize> {
+    let entries = decode(filename, read_file(filename)?.as_slice())?;
+    let (_, triggers) = check(&entries)?;
+
+    let dir = dest.parent().unwrap_or(Path::new("."));
+    let sounds_dir = dir.join(SOUNDS_DIRNAME);
+    match fs::remove_dir_all(sounds_dir.as_path()) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+            return Err(PackError::IOError {
+                source: e,
+                filename: sounds_dir,
+            })
+        }
+        _ => {}
+    }
+
+    for entry in entries.iter() {
+        let path = match entry.name.as_str() {
+            TRIGGER_FILENAME => dest.to_path_buf(),
+            name => dir.join(name),
+        };
+        if let Some(parent) = path.parent() {
+            create_dir(parent).map_err(|source| PackError::IOError {
+                source,
+                filename: parent.to_path_buf(),
+            })?;
+        }
+        write_file(path.as_path(), entry.contents.as_slice())?;
+    }
+
+    Ok(triggers.triggers.len())
+}
+
+/// What the pack installed as the given source says about itself, or None if
+/// the source isn't a pack.
+pub(crate) fn installed(data_dir: &Path, name: &str) -> Result<Option<PackMeta>> {
+    let filename = remote_triggers_file(data_dir, name).with_file_name(META_FILENAME);
+    let contents = match fs::read_to_string(filename.as_path()) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(source) => return Err(PackError::IOError { source, filename }),
+    };
+
+    toml_edit::de::from_str(contents.as_str())
+        .map(Some)
+        .map_err(|source| PackError::DeserializationError {
+            source,
+            name: filename.to_string_lossy().into_owned(),
+        })
+}
+
+/// Makes sure that the pack works with this version of Comrade, and that the
+/// packs it depends on are installed and new enough.
+pub(crate) fn check_compatible(pack: &PackMeta, data_dir: &Path) -> Result<()> {
+    if let Some(ref required) = pack.min_comrade_version {
+        if !at_least(meta::PKG_VERSION, required.as_str())? {
+            return Err(PackError::NeedsNewerComrade {
+                pack: pack.name.clone(),
+                required: required.clone(),
+                current: meta::PKG_VERSION.to_string(),
+            });
+        }
+    }
+
+    for (dependency, required) in pack.dependencies.iter() {
+        let installed = match installed(data_dir, dependency.as_str())? {
+            Some(installed) => installed,
+            None => {
+                return Err(PackError::MissingDependency {
+                    pack: pack.name.clone(),
+                    dependency: dependency.clone(),
+                    required: required.clone(),
+                })
+            }
+        };
+        if !at_least(installed.version.as_str(), required.as_str())? {
+            return Err(PackError::OutdatedDependency {
+                pack: pack.name.clone(),
+                dependency: dependency.clone(),
+                required: required.clone(),
+                installed: installed.version,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Like `version::at_least`, but for versions from a pack.
+fn at_least(version: &str, required: &str) -> Result<bool> {
+    check_version(version)?;
+    check_version(required)?;
+
+    Ok(version::at_least(version, required).unwrap_or(false))
+}
+
+fn check_version(version: &str) -> Result<()> {
+    match version::parse(version) {
+        Some(_) => Ok(()),
+        None => Err(PackError::InvalidVersion {
+            version: version.to_string(),
+        }),
+    }
+}
+
+/// Makes sure that a pack has everything that it needs, and nothing that it
+/// shouldn't, returning its metadata and triggers.
+fn check(entries: &[Entry]) -> Result<(PackMeta, TriggerSet)> {
+    for entry in entries.iter() {
+        let valid = match entry.name.split_once('/') {
+            Some((SOUNDS_DIRNAME, sound)) => {
+                !sound.is_empty() && !sound.contains(['/', '\\']) && !sound.starts_with('.')
+            }
+            Some(_) => false,
+            None => {
+                [META_FILENAME, TRIGGER_FILENAME, SIGNATURE_FILENAME].contains(&entry.name.as_str())
+            }
+        };
+        if !valid {
+            return Err(PackError::InvalidPath {
+                path: entry.name.clone(),
+            });
+        }
+    }
+
+    let meta: PackMeta = parse(entries, META_FILENAME)?;
+    for name in std::iter::once(&meta.name).chain(meta.dependencies.keys()) {
+        if !is_valid_name(name.as_str()) {
+            return Err(PackError::InvalidName { name: name.clone() });
+        }
+    }
+    check_version(meta.version.as_str())?;
+    for version in meta
+        .min_comrade_version
+        .iter()
+        .chain(meta.dependencies.values())
+    {
+        check_version(version.as_str())?;
+    }
+    let triggers: TriggerSet = parse(entries, TRIGGER_FILENAME)?;
+
+    Ok((meta, triggers))
+}
+
+fn parse<T: for<'de> Deserialize<'de>>(entries: &[Entry], name: &str) -> Result<T> {
+    let entry = entries
+        .iter()
+        .find(|e| e.name == name)
+        .ok_or_else(|| PackError::MissingFile {
+            name: name.to_string(),
+        })?;
+    let contents =
+        std::str::from_utf8(entry.contents.as_slice()).map_err(|_| PackError::InvalidArchive {
+            reason: format!("{} isn't text", name),
+        })?;
+
+    toml_edit::de::from_str(contents).map_err(|source| PackError::DeserializationError {
+        source,
+        name: name.to_string(),
+    })
+}
+
+fn encode(entries: &[Entry]) -> Vec<u8> {
+    let mut buffer = Vec::from(MAGIC);
+    buffer.push(FORMAT_VERSION);
+    for entry in entries.iter() {
+        buffer.extend((entry.name.len() as u16).to_le_bytes());
+        buffer.extend(entry.name.as_bytes());
+        buffer.extend((entry.contents.len() as u64).to_le_bytes());
+        buffer.extend(entry.contents.as_slice());
+    }
+
+    buffer
+}
+
+fn decode(filename: &Path, mut data: &[u8]) -> Result<Vec<Entry>> {
+    let invalid = |reason: &str| PackError::InvalidArchive {
+        reason: format!("{}, in {}", reason, filename.display()),
+    };
+
+    data = data
+        .strip_prefix(MAGIC)
+        .ok_or_else(|| invalid("not a comrade pack"))?;
+    match data.split_first() {
+        Some((&FORMAT_VERSION, rest)) => data = rest,
+        _ => return Err(invalid("unsupported pack format")),
+    }
+
+    let mut entries = Vec::new();
+    while !data.is_empty() {
+        let name_len = take(&mut data, 2).ok_or_else(|| invalid("truncated"))?;
+        let name_len = u16::from_le_bytes([name_len[0], name_len[1]]) as usize;
+        let name = take(&mut data, name_len).ok_or_else(|| invalid("truncated"))?;
+        let name = String::from_utf8(name.to_vec()).map_err(|_| invalid("invalid file name"))?;
+
+        let len = take(&mut data, 8).ok_or_else(|| invalid("truncated"))?;
+        let len = u64::from_le_bytes(len.try_into().expect("took 8 bytes"));
+        let len = usize::try_from(len).map_err(|_| invalid("truncated"))?;
+        let contents = take(&mut data, len).ok_or_else(|| invalid("truncated"))?;
+
+        entries.push(Entry {
+            name,
+            contents: contents.to_vec(),
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Splits `len` bytes off the front of `data`, if there are that many.
+fn take<'a>(data: &mut &'a [u8], len: usize) -> Option<&'a [u8]> {
+    if data.len() < len {
+        return None;
+    }
+    let (taken, rest) = data.split_at(len);
+    *data = rest;
+    Some(taken)
+}
+
+fn read_file(filename: &Path) -> Result<Vec<u8>> {
+    fs::read(filename).map_err(|source| PackError::IOError {
+        source,
+        filename: filename.to_path_buf(),
+    })
+}
+
+fn write_file(filename: &Path, contents: &[u8]) -> Result<()> {
+    fs::write(filename, contents).map_err(|source| PackError::IOError {
+        source,
+        filename: filename.to_path_buf(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_and_rejects_bad_paths() {
+        let entries = vec![
+            Entry {
+                name: META_FILENAME.to_string(),
+                contents: b"name = \"raid\"\nversion = \"1.0\"\n".to_vec(),
+            },
+            Entry {
+                name: TRIGGER_FILENAME.to_string(),
+                contents: b"[meta]\nsource = \"local\"\n".to_vec(),
+            },
+            Entry {
+                name: "sounds/gong.wav".to_string(),
+                contents: vec![0, 1, 2, 255],
+            },
+        ];
+
+        let decoded = decode(Path::new("raid.comradepack"), encode(&entries).as_slice()).unwrap();
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[2].name, "sounds/gong.wav");
+        assert_eq!(decoded[2].contents, vec![0, 1, 2, 255]);
+        let (meta, _) = check(&decoded).unwrap();
+        assert_eq!(meta.name, "raid");
+
+        let mut escaping = decoded;
+        escaping[2].name = "sounds/../../config.toml".to_string();
+        assert!(matches!(
+            check(&escaping),
+            Err(PackError::InvalidPath { .. })
+        ));
+
+        let truncated = &encode(&entries)[..20];
+        assert!(decode(Path::new("raid.comradepack"), truncated).is_err());
+    }
+}
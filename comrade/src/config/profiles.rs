@@ -0,0 +1,101 @@
+//! Configuration Profiles
+//!
+//! One install can hold more than one way of playing, like a raid profile
+//! with a single character and the guild's trigger sources, and a farming one
+//! with every box and its own sounds. Each profile is a configuration
+//! directory of its own, under `profiles` in the main one, so it has its own
+//! characters, sources, audio and everything else.
+//!
+//! Everything that's persisted, like saved state, the in-flight journal and
+//! the audit log, goes in the profile's data directory. A profile that doesn't
+//! say where that is gets one of its own, rather than sharing, and writing
+//! over, the state of every other profile.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{default_dirs, CONFIG_FILENAME};
+
+pub(crate) const PROFILES_DIRNAME: &str = "profiles";
+
+/// Where the given profile's configuration lives, within the given
+/// configuration directory, or within the default one.
+pub(crate) fn profile_dir(config_dir: Option<&Path>, name: &str) -> PathBuf {
+    profiles_dir(config_dir).join(name)
+}
+
+/// The names of the profiles within the given configuration directory, or
+/// within the default one, which are the directories under `profiles` that
+/// have a configuration file.
+pub(crate) fn profiles(config_dir: Option<&Path>) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(profiles_dir(config_dir))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().join(CONFIG_FILENAME).is_file())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    names.sort();
+
+    names
+}
+
+fn profiles_dir(config_dir: Option<&Path>) -> PathBuf {
+    match config_dir {
+        Some(path) => path.join(PROFILES_DIRNAME),
+        None => default_dirs().config_dir.join(PROFILES_DIRNAME),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use super::*;
+    use crate::config::{Config, Directories};
+    use crate::errors::ConfigError;
+
+    #[test]
+    fn profiles_have_their_own_data() {
+        let dir = env::temp_dir().join(format!("comrade-profiles-{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let raid = profile_dir(Some(&dir), "raid");
+        let boxes = profile_dir(Some(&dir), "boxes");
+        fs::create_dir_all(&raid).unwrap();
+        fs::create_dir_all(&boxes).unwrap();
+        fs::create_dir_all(dir.join(PROFILES_DIRNAME).join("empty")).unwrap();
+        fs::write(raid.join(CONFIG_FILENAME), "").unwrap();
+        fs::write(
+            boxes.join(CONFIG_FILENAME),
+            format!("[dirs]\ndata = {:?}\n", dir.join("boxes-data")),
+        )
+        .unwrap();
+
+        assert_eq!(profiles(Some(&dir)), ["boxes", "raid"]);
+
+        let config = Config::load(Some(dir.clone()), Some("raid")).unwrap();
+        assert_eq!(config.dirs.config, raid);
+        assert_eq!(
+            config.dirs.data,
+            Directories::default()
+                .data
+                .join(PROFILES_DIRNAME)
+                .join("raid")
+        );
+
+        let config = Config::load(Some(dir.clone()), Some("boxes")).unwrap();
+        assert_eq!(config.dirs.data, dir.join("boxes-data"));
+
+        assert!(matches!(
+            Config::load(Some(dir.clone()), Some("empty")),
+            Err(ConfigError::UnknownProfile { .. })
+        ));
+        assert!(matches!(
+            Config::load(Some(dir.clone()), Some("../raid")),
+            Err(ConfigError::UnknownProfile { .. })
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
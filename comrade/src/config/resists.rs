@@ -0,0 +1,18 @@
+//! Resist Configuration
+//!
+//! Tracking resists means reading every spell that's cast from the logs, so
+//! like the other trackers it's off unless it has been turned on, and telling
+//! the character about a run of resists is up to them as well.
+
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Default, Clone)]
+pub(crate) struct ResistsConfig {
+    #[serde(default)]
+    pub(crate) enabled: bool,
+    /// How many times in a row a target has to resist the same spell before
+    /// there's an event for it, and again for every resist after that, with
+    /// no events at all if it isn't set.
+    #[serde(default)]
+    pub(crate) streak: Option<u32>,
+}
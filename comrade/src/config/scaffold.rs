@@ -0,0 +1,373 @@
+//! Configuration Scaffolding
+//!
+//! Creates the directory tree and configuration file for a new installation,
+//! optionally seeding the local trigger source with a starter pack.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::edit::{insert_trigger, TomlFile};
+use crate::config::secrets::{secrets_file, Secrets};
+use crate::config::triggers::{local_triggers_file, TriggerId, TriggerSet};
+use crate::config::{
+    self, default_dirs, parse_config, try_open_config_file, Result, CONFIG_FILENAME,
+};
+use crate::errors::ConfigError;
+
+const STARTER_PACK: &str = include_str!("starter.toml");
+
+/// What was created while scaffolding, anything that already existed is left
+/// untouched and is not reported.
+#[derive(Debug, Default)]
+pub struct Scaffold {
+    pub created: Vec<PathBuf>,
+    pub triggers: Vec<TriggerId>,
+}
+
+pub(crate) fn scaffold(config_dir: Option<PathBuf>, with_defaults: bool) -> Result<Scaffold> {
+    let mut scaffold = Scaffold::default();
+
+    let (config_dir, data_dir) = match config_dir {
+        Some(path) => {
+            let data = path.join("data");
+            (path, data)
+        }
+        None => {
+            let dirs = default_dirs();
+            (dirs.config_dir, dirs.data_dir)
+        }
+    };
+
+    create_dir(config_dir.as_path(), &mut scaffold)?;
+
+    let filename = config_dir.join(CONFIG_FILENAME);
+    if !filename.exists() {
+        fs::write(filename.as_path(), default_config(data_dir.as_path()))?;
+        scaffold.created.push(filename.clone());
+    }
+
+    if !secrets_file(config_dir.as_path()).exists() {
+        let filename = Secrets::generate()?.save(config_dir.as_path())?;
+        scaffold.created.push(filename);
+    }
+
+    // The configuration may have already existed, in which case the data
+    // directory is whatever it says it is.
+    let file = try_open_config_file(filename.as_path(), false)?
+        .expect("None from try_open_config_file with allow_missing=false?");
+    let config = parse_config(filename.as_path(), file)?;
+
+    let triggers_file = local_triggers_file(config.dirs.data.as_path());
+    create_dir(config.dirs.data.as_path(), &mut scaffold)?;
+    if let Some(local_dir) = triggers_file.parent() {
+        create_dir(local_dir, &mut scaffold)?;
+    }
+
+    if with_defaults {
+        let starter: TriggerSet =
+            toml_edit::de::from_str(STARTER_PACK).expect("starter pack should be valid");
+
+        let mut file = TomlFile::open(triggers_file.as_path(), true)?;
+        for (id, trigger) in starter.triggers.iter() {
+            match insert_trigger(&mut file, &starter.meta.source, id, trigger) {
+                Ok(()) => scaffold.triggers.push(id.clone()),
+                Err(ConfigError::DuplicateTrigger { .. }) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        file.save()?;
+    }
+
+    Ok(scaffold)
+}
+
+fn create_dir(path: &Path, scaffold: &mut Scaffold) -> Result<()> {
+    if !path.exists() {
+        config::create_dir(path)?;
+        scaffold.created.push(path.to_path_buf());
+    }
+
+    Ok(())
+}
+
+fn default_config(data_dir: &Path) -> String {
+    let data = toml_edit::Value::from(data_dir.to_string_lossy().as_ref());
+
+    format!(
+        r#"[dirs]
+data = {}
+
+# Add a section like this for every character whose log you want to watch.
+#
+# [characters.main]
+# name = "Soandso"
+# server = "teek"
+# filename = "C:/EverQuest/Logs/eqlog_Soandso_teek.txt"
+# aliases = ["cleric"]
+# display-name = "Soandso (cleric)"
+# clock-offset = "-3h"
+# disabled-triggers = []
+
+# Groups name characters that are played together, and can be used anywhere a
+# character's id or alias can. Switching to a group pauses everyone outside of
+# it, and switches to whichever of muted, speech-muted and volume it sets.
+#
+# [groups.raid-team]
+# characters = ["main"]
+# volume = 100
+#
+# [groups.trader-boxes]
+# characters = ["box1", "box2"]
+# speech-muted = true
+
+# Profiles are whole configurations of their own, each in a directory under
+# profiles/ next to this file, e.g. profiles/raid/Config.toml, with their own
+# characters, trigger sources, audio and data. Create one with
+# `comrade --profile raid init`, and use it with `comrade --profile raid`, or
+# switch to it with /profile raid in the terminal UI.
+
+# A countdown's text can include how long it has left with {{remaining}}, e.g.
+# "Rampage in {{remaining}}", which is written out with this pattern, where %H,
+# %M and %S are the hours, minutes and seconds left.
+#
+# [timers]
+# remaining = "%M:%S"
+#
+# Countdowns can be given a category, which controls how they're displayed.
+#
+# [timers.categories.buffs]
+# color = "green"
+# order = 1
+# pane = "Buffs"
+# icon = "131"
+
+# Combat tracking groups damage into fights, which can be exported from the
+# events tab for comparing with other parsers.
+#
+# [combat]
+# enabled = true
+# timeout = 30
+#
+# Pets have their damage counted as their owner's, which is learned from
+# /pet leader, or can be set here by the pet's name.
+#
+# [combat.pets]
+# Gobaner = "Soandso"
+
+# Attendance is taken from every /who that's done during the raid, which can
+# be exported from the events tab for DKP. The raid window is in the game's
+# clock and can end after midnight.
+#
+# [attendance]
+# enabled = true
+# start = "20:00"
+# end = "23:30"
+
+# Tradeskill tracking tallies combines per recipe, summarizing each session
+# once nothing has been combined for the timeout, in seconds.
+#
+# [tradeskills]
+# enabled = true
+# timeout = 120
+
+# Currency tracking adds up the coin looted and split in each zone, ending a
+# farming session on zoning or once no coin has been picked up for the
+# timeout, in seconds.
+#
+# [currency]
+# enabled = true
+# timeout = 900
+
+# Resist tracking tallies the spells each character casts per target, with
+# how often they were resisted, interrupted, or fizzled, and optionally says
+# so once a target has resisted the same spell a number of times in a row.
+#
+# [resists]
+# enabled = true
+# streak = 3
+
+# Corpse recovery records where a character died, as of their last /loc, and
+# keeps reminding them where their corpse is until it's acknowledged. The
+# decay and how often to remind are in seconds.
+#
+# [corpses]
+# enabled = true
+# decay = 604800
+# remind = 300
+
+# Broadcast capture picks raid chat, guild chat, and the guild MOTD out of the
+# logs for the broadcasts pane, and alerts on anything the raid leaders say.
+#
+# [broadcasts]
+# enabled = true
+# leaders = ["Soandso"]
+
+# The language of Comrade's own alerts, like corpse reminders, which can be
+# set for a character too, with language = "fr" alongside its name. Any of
+# the texts can be replaced, and the voice is for frontends that read alerts
+# out, as their platform's text to speech names it.
+#
+# [locale]
+# language = "de"
+# voice = "Anna"
+#
+# [locale.texts.de]
+# corpse-decays = "Die Leiche von {{name}} ist bald weg"
+
+# Everything that's tracked, like fights and /who snapshots, is kept in memory
+# for as long as Comrade runs, up to a budget for each kind. Once one is over
+# its budget, whatever was least recently used is let go of first. The most
+# recent events are kept too, for frontends that attach late, up to a count.
+#
+# [memory]
+# fights = "8MiB"
+# rosters = "4MiB"
+# recipes = "1MiB"
+# resists = "1MiB"
+# earnings = "2MiB"
+# events = 500
+
+# Callouts that come too late are worse than none, so Comrade warns when the
+# time from a line being read to its callout goes past a threshold, in
+# milliseconds.
+#
+# [watchdog]
+# enabled = true
+# threshold = 1000
+
+# Zone profiles turn groups of triggers, by tag, on or off while a character
+# is in a zone, so raid triggers only run on raid night. The zone is only known
+# once the character has zoned since Comrade started.
+#
+# [zones."The Plane of Time"]
+# enable = ["Raids/PoP/Time"]
+# disable = ["farming"]
+
+# Remote trigger sources, such as a guild's trigger pack, are synced from the
+# sources tab into the data directory.
+#
+# [sources.guild]
+# url = "/path/to/guild/Triggers.toml"
+#
+# A pack's styles, like the sound its triggers play and whether they're read
+# out, can be refined without editing the pack, for all of its triggers, the
+# ones with a given tag, or a single trigger, each taking precedence over the
+# one before.
+#
+# [sources.guild.styles.defaults]
+# speak = false
+#
+# [sources.guild.styles.groups.raid]
+# sound = "sounds/raid.wav"
+#
+# [sources.guild.styles.triggers.fire-breath]
+# speak = true
+
+# A cue profile gives each severity of event its own earcon, so that Comrade
+# can be followed by ear alone. `earcons` is built in, or make your own from
+# sound files or tones, placed anywhere from -1.0 (left) to 1.0 (right).
+#
+# [accessibility]
+# profile = "mine"
+#
+# [accessibility.profiles.mine]
+# low = {{ tone = 440, pan = -0.6 }}
+# high = {{ sound = "sounds/warning.wav", pan = 0.3, pitch = 1.2 }}
+# critical = {{ tone = 1320, length = 400 }}
+
+# Outputs that triggers can signal, such as a vibration motor driven from a
+# serial device, or a pin exported through sysfs. A trigger signals one with
+# an action like {{ type = "Signal", output = "rumble", morse = "SOS" }}.
+#
+# [outputs.rumble]
+# type = "serial"
+# path = "/dev/ttyACM0"
+
+# Executors, like the webhook one, carry out Custom actions on a worker of
+# their own. Each attempt times out, failures are retried with a backoff, and
+# after enough failures in a row the executor's actions are skipped for a
+# cooldown, with a warning, rather than piling up behind a service that's down.
+#
+# [executors.webhook]
+# timeout = 5
+# retries = 2
+# backoff = 500
+# failures = 5
+# cooldown = 60
+# queue = 32
+
+# A digest of each session, with the triggers that fired, the loot, experience
+# and deaths, can be written to the data directory and mailed out when Comrade
+# stops, for traders and campers that are left running overnight. Mail is
+# handed to sendmail, or anything that takes mail the same way, like msmtp.
+#
+# [digest]
+# enabled = true
+# email = "me@example.com"
+# sendmail = "msmtp"
+
+# Metrics, like lines read, trigger matches, DPS and experience per character,
+# along with how well Comrade is keeping up, can be exported every interval
+# (in seconds) for long-term dashboards. They're written in InfluxDB's line
+# protocol, either to InfluxDB itself, or to a file for something like
+# Telegraf to pick up.
+#
+# [metrics]
+# enabled = true
+# interval = 10
+# sink = {{ type = "influxdb", url = "http://localhost:8086/api/v2/write?org=guild&bucket=comrade", token = "..." }}
+#
+# A file sink is relative to the data directory, and needs nothing else.
+#
+# sink = {{ type = "file", path = "metrics.lp" }}
+
+# Every time a trigger fires it's recorded in the data directory, for the
+# history command to search. Each month is compressed once it's over, and
+# months are deleted once they're older than keep-months, or, oldest first,
+# once they take up more than max-size. `comrade vacuum` does that straight
+# away, rather than waiting for Comrade to get around to it.
+#
+# [history]
+# record = true
+# keep-months = 12
+# max-size = "256MiB"
+
+# A dashboard page, for checking on a Comrade that's left running from a phone
+# on the same network, showing each character's log, the timers running and
+# the most recent alerts. It's only shown to whoever has the token, e.g. at
+# http://192.168.1.20:8180/?token=... which is generated into Secrets.toml,
+# next to this file, unless it's set here. It only listens on this computer
+# unless it's told to listen on the rest of the network too.
+#
+# The control API, e.g. `POST /api/timers` with "6m30s Pick respawn", takes
+# the same token as a bearer token. It's served over https once it's given a
+# certificate and key, relative to this file, if Comrade was built for it.
+#
+# [dashboard]
+# enabled = true
+# address = "0.0.0.0:8180"
+# control = true
+# tls = {{ certificate = "dashboard.crt", key = "dashboard.key" }}
+
+# Comrade can check its releases for a newer version when it starts, which is
+# off unless it's turned on here.
+#
+# [updates]
+# enabled = true
+
+# The layout of the terminal UI, e.g. for a narrow window on a second monitor.
+#
+# [ui]
+# tabs = ["events", "triggers", "sources", "logs"]
+#
+# [ui.events]
+# split = 70
+# matches = 0
+# broadcasts = 4
+# stacked = true
+# characters = ["main", "alt"]
+# retention = 1000
+"#,
+        data.to_string().trim()
+    )
+}
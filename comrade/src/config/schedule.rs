@@ -0,0 +1,211 @@
+//! Trigger Schedules
+//!
+//! Triggers can be limited to certain days and times, e.g. a raid pack that
+//! only has to be listening during raid hours, so that it can sit alongside
+//! packs for farming on alts without either having to be turned off by hand.
+//! Windows look like `Tue,Thu 20:00-23:30`, `Mon-Fri 18:00-02:00`, or just
+//! `20:00-23:30` for every day, and a window that ends after midnight carries
+//! on into the next day. They're checked against the timestamp of the line
+//! that matched, which is the game's clock, so replaying an old log checks
+//! them the same way as it happened.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::attendance::TimeOfDay;
+use crate::errors::TriggerError;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const FULL_WEEKDAYS: [&str; 7] = [
+    "monday",
+    "tuesday",
+    "wednesday",
+    "thursday",
+    "friday",
+    "saturday",
+    "sunday",
+];
+
+/// A day of the week, counting from Monday.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Weekday(u8);
+
+impl Weekday {
+    /// Pulls the day of the week out of a log timestamp, which look like
+    /// `Sat Oct 17 20:15:00 2026`.
+    fn from_timestamp(timestamp: &str) -> Option<Weekday> {
+        timestamp.split_whitespace().next()?.parse().ok()
+    }
+
+    fn previous(self) -> Weekday {
+        Weekday((self.0 + 6) % 7)
+    }
+}
+
+impl FromStr for Weekday {
+    type Err = ();
+
+    /// Parses a day by its name, or the first three letters of it.
+    fn from_str(s: &str) -> Result<Weekday, ()> {
+        let s = s.trim().to_lowercase();
+        WEEKDAYS
+            .iter()
+            .position(|day| day.eq_ignore_ascii_case(s.as_str()))
+            .or_else(|| FULL_WEEKDAYS.iter().position(|day| *day == s))
+            .map(|idx| Weekday(idx as u8))
+            .ok_or(())
+    }
+}
+
+impl fmt::Display for Weekday {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(WEEKDAYS[self.0 as usize])
+    }
+}
+
+/// When a trigger is active, by the game's clock.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ActiveWindow {
+    // Which days the window starts on, as a bit for each, from Monday.
+    days: u8,
+    start: TimeOfDay,
+    end: TimeOfDay,
+}
+
+const EVERY_DAY: u8 = 0b111_1111;
+
+impl ActiveWindow {
+    fn starts_on(&self, day: Weekday) -> bool {
+        self.days & (1 << day.0) != 0
+    }
+
+    /// Whether the line with the given timestamp falls within this window,
+    /// which it does when it's a timestamp that can't be made sense of, so
+    /// that nothing is missed over it.
+    pub(crate) fn contains(&self, timestamp: &str) -> bool {
+        let (day, time) = match (
+            Weekday::from_timestamp(timestamp),
+            TimeOfDay::from_timestamp(timestamp),
+        ) {
+            (Some(day), Some(time)) => (day, time),
+            _ => return true,
+        };
+
+        if self.start <= self.end {
+            self.starts_on(day) && self.start <= time && time <= self.end
+        } else {
+            (self.starts_on(day) && self.start <= time)
+                || (self.starts_on(day.previous()) && time <= self.end)
+        }
+    }
+}
+
+impl FromStr for ActiveWindow {
+    type Err = TriggerError;
+
+    fn from_str(s: &str) -> Result<ActiveWindow, TriggerError> {
+        let invalid = |reason: &str| TriggerError::InvalidWindow {
+            value: s.to_string(),
+            reason: reason.to_string(),
+        };
+
+        let (days, times) = match s.trim().rsplit_once(char::is_whitespace) {
+            Some((days, times)) => (parse_days(days).map_err(|e| invalid(e.as_str()))?, times),
+            None => (EVERY_DAY, s.trim()),
+        };
+        let (start, end) = times
+            .split_once('-')
+            .ok_or_else(|| invalid("expected something like Tue,Thu 20:00-23:30"))?;
+
+        Ok(ActiveWindow {
+            days,
+            start: start.parse().map_err(|e: String| invalid(e.as_str()))?,
+            end: end.parse().map_err(|e: String| invalid(e.as_str()))?,
+        })
+    }
+}
+
+/// Parses days like `Tue,Thu` or `Mon-Fri` into a bit for each day.
+fn parse_days(s: &str) -> Result<u8, String> {
+    let day = |s: &str| {
+        s.parse::<Weekday>()
+            .map_err(|_| format!("unknown day {:?}", s.trim()))
+    };
+
+    let mut days = 0;
+    for part in s.split(',') {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (day(first)?, day(last)?);
+                // Ranges can wrap around the end of the week, like Fri-Mon.
+                let mut current = first;
+                loop {
+                    days |= 1 << current.0;
+                    if current == last {
+                        break;
+                    }
+                    current = Weekday((current.0 + 1) % 7);
+                }
+            }
+            None => days |= 1 << day(part)?.0,
+        }
+    }
+
+    Ok(days)
+}
+
+impl fmt::Display for ActiveWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.days != EVERY_DAY {
+            let days: Vec<String> = (0..7)
+                .map(Weekday)
+                .filter(|day| self.starts_on(*day))
+                .map(|day| day.to_string())
+                .collect();
+            write!(f, "{} ", days.join(","))?;
+        }
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+impl TryFrom<String> for ActiveWindow {
+    type Error = TriggerError;
+
+    fn try_from(value: String) -> Result<ActiveWindow, TriggerError> {
+        value.parse()
+    }
+}
+
+impl From<ActiveWindow> for String {
+    fn from(window: ActiveWindow) -> String {
+        window.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_carry_on_past_midnight() {
+        let raid: ActiveWindow = "Tue,Thu-Fri 20:00-01:30".parse().unwrap();
+        assert_eq!(raid.to_string(), "Tue,Thu,Fri 20:00-01:30");
+
+        assert!(raid.contains("Tue Oct 13 20:00:00 2026"));
+        assert!(raid.contains("Wed Oct 14 01:15:00 2026"));
+        assert!(!raid.contains("Wed Oct 14 20:30:00 2026"));
+        assert!(raid.contains("Sat Oct 17 00:45:00 2026"));
+        assert!(!raid.contains("Sat Oct 17 02:00:00 2026"));
+
+        let daily: ActiveWindow = "9:00-17:00".parse().unwrap();
+        assert_eq!(daily.to_string(), "09:00-17:00");
+        assert!(daily.contains("Sun Oct 18 12:00:00 2026"));
+        assert!(!daily.contains("Sun Oct 18 18:00:00 2026"));
+
+        assert!("Someday 20:00-21:00".parse::<ActiveWindow>().is_err());
+        assert!("Mon 20:00".parse::<ActiveWindow>().is_err());
+    }
+}
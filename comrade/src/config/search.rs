@@ -0,0 +1,148 @@
+//! Trigger Search
+//!
+//! Imported trigger packs can easily contain thousands of triggers, so finding
+//! a specific one requires some help. A filter is written as a whitespace
+//! separated list of terms, where prefixed terms narrow the results and any
+//! bare terms are fuzzy matched against the trigger name:
+//!
+//! ```text
+//! tag:raid source:local pattern:"tells you" flurry
+//! ```
+
+use std::str::FromStr;
+
+use crate::config::triggers::{Trigger, TriggerRef, TriggerSource};
+use crate::errors::TriggerError;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TriggerFilter {
+    pub name: Option<String>,
+    pub tags: Vec<String>,
+    pub pattern: Option<String>,
+    pub source: Option<TriggerSource>,
+}
+
+impl TriggerFilter {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.tags.is_empty()
+            && self.pattern.is_none()
+            && self.source.is_none()
+    }
+
+    /// Returns None if the trigger does not match this filter, otherwise
+    /// returns a score where a higher score is a better match.
+    pub(crate) fn score(&self, tref: &TriggerRef, trigger: &Trigger) -> Option<i64> {
+        if let Some(ref source) = self.source {
+            if *source != tref.source {
+                return None;
+            }
+        }
+
+        for tag in self.tags.iter() {
+            if !trigger.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                return None;
+            }
+        }
+
+        if let Some(ref pattern) = self.pattern {
+            if !trigger
+                .search_text
+                .to_lowercase()
+                .contains(pattern.to_lowercase().as_str())
+            {
+                return None;
+            }
+        }
+
+        match self.name {
+            Some(ref name) => fuzzy_score(name, trigger.name.as_str()),
+            None => Some(0),
+        }
+    }
+}
+
+impl FromStr for TriggerFilter {
+    type Err = TriggerError;
+
+    fn from_str(s: &str) -> Result<TriggerFilter, TriggerError> {
+        let mut filter = TriggerFilter::default();
+        let mut names = Vec::new();
+
+        for term in split_terms(s) {
+            match term.split_once(':') {
+                Some(("tag", tag)) => filter.tags.push(tag.to_string()),
+                Some(("pattern", pattern)) => filter.pattern = Some(pattern.to_string()),
+                Some(("source", source)) => filter.source = Some(source.parse()?),
+                _ => names.push(term),
+            }
+        }
+
+        if !names.is_empty() {
+            filter.name = Some(names.join(" "));
+        }
+
+        Ok(filter)
+    }
+}
+
+/// Splits a filter into terms on whitespace, treating anything wrapped in
+/// double quotes as part of a single term.
+fn split_terms(s: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in s.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    terms.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+
+    if !current.is_empty() {
+        terms.push(current);
+    }
+
+    terms
+}
+
+/// A simple subsequence based fuzzy matcher, every character of the needle
+/// must appear in the haystack in order. Consecutive characters and matches
+/// at the start of a word score higher, and a plain substring match always
+/// beats a scattered one.
+fn fuzzy_score(needle: &str, haystack: &str) -> Option<i64> {
+    let needle = needle.to_lowercase();
+    let haystack = haystack.to_lowercase();
+
+    if let Some(pos) = haystack.find(needle.as_str()) {
+        return Some(1000 - pos as i64);
+    }
+
+    let hay: Vec<char> = haystack.chars().collect();
+    let mut score = 0;
+    let mut idx = 0;
+    let mut last: Option<usize> = None;
+
+    for nc in needle.chars().filter(|c| !c.is_whitespace()) {
+        let found = hay[idx..].iter().position(|hc| *hc == nc)? + idx;
+
+        score += 1;
+        if last.map(|l| l + 1 == found).unwrap_or(false) {
+            score += 5;
+        }
+        if found == 0 || !hay[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+
+        last = Some(found);
+        idx = found + 1;
+    }
+
+    Some(score)
+}
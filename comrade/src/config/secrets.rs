@@ -0,0 +1,186 @@
+//! Secrets
+//!
+//! Secrets, like the token that the dashboard asks for, are kept out of the
+//! configuration file, which gets shared around when helping someone set up
+//! their triggers, and in a file of their own next to it instead, which only
+//! the user can read. `init` generates them, and the configuration file can
+//! still set its own to override them.
+
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+const SECRETS_FILENAME: &str = "Secrets.toml";
+
+/// How many random bytes go into a generated token.
+const TOKEN_BYTES: usize = 24;
+
+pub(crate) fn secrets_file(config_dir: &Path) -> PathBuf {
+    config_dir.join(SECRETS_FILENAME)
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) struct Secrets {
+    #[serde(default)]
+    pub(crate) dashboard_token: Option<String>,
+}
+
+impl Secrets {
+    /// Reads the secrets next to the given configuration file, if there are
+    /// any. Secrets that can't be read are warned about, and left out, so
+    /// whatever needs them stays off.
+    // The dashboard's token is the only secret so far, and it's still
+    // generated without the `dashboard` feature, for a build that has it.
+    #[cfg_attr(not(feature = "dashboard"), allow(dead_code))]
+    pub(crate) fn load(config_dir: &Path) -> Secrets {
+        let filename = secrets_file(config_dir);
+        let contents = match fs::read_to_string(filename.as_path()) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Secrets::default(),
+            Err(e) => {
+                warn!("could not read {}: {}", filename.display(), e);
+                return Secrets::default();
+            }
+        };
+
+        toml_edit::de::from_str(contents.as_str()).unwrap_or_else(|e| {
+            warn!("could not parse {}: {}", filename.display(), e);
+            Secrets::default()
+        })
+    }
+
+    /// Generates a new set of secrets.
+    pub(crate) fn generate() -> io::Result<Secrets> {
+        Ok(Secrets {
+            dashboard_token: Some(generate_token()?),
+        })
+    }
+
+    /// Writes the secrets next to the given configuration file, so that only
+    /// the user can read them, even if the file was already there and could
+    /// be read by anyone.
+    pub(crate) fn save(&self, config_dir: &Path) -> io::Result<PathBuf> {
+        let filename = secrets_file(config_dir);
+        let contents = toml_edit::ser::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(filename.as_path())?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        }
+        file.write_all(contents.as_bytes())?;
+
+        Ok(filename)
+    }
+}
+
+/// A token that's hard to guess, as hex, from the operating system's
+/// randomness.
+fn generate_token() -> io::Result<String> {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    getrandom::getrandom(&mut bytes).map_err(|e| io::Error::other(e.to_string()))?;
+
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use super::*;
+
+    /// A directory of its own for the test to keep secrets in.
+    fn dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("comrade-secrets-{}-{}", name, process::id()));
+        fs::create_dir_all(dir.as_path()).unwrap();
+        dir
+    }
+
+    #[test]
+    fn secrets_round_trip() {
+        let dir = dir("round-trip");
+        let secrets = Secrets::generate().unwrap();
+        secrets.save(dir.as_path()).unwrap();
+        assert_eq!(Secrets::load(dir.as_path()), secrets);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn generates_a_new_token_every_time() {
+        let secrets = Secrets::generate().unwrap();
+        let token = secrets.dashboard_token.clone().unwrap();
+        assert_eq!(token.len(), TOKEN_BYTES * 2);
+        assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(Secrets::generate().unwrap(), secrets);
+    }
+
+    #[test]
+    fn missing_secrets_are_left_out() {
+        let dir = dir("missing");
+        assert_eq!(Secrets::load(dir.as_path()), Secrets::default());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn malformed_secrets_are_left_out() {
+        let dir = dir("malformed");
+        fs::write(secrets_file(dir.as_path()), "dashboard-token = [").unwrap();
+        assert_eq!(Secrets::load(dir.as_path()), Secrets::default());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn saving_somewhere_that_does_not_exist_fails() {
+        let dir = env::temp_dir().join(format!("comrade-secrets-nowhere-{}", process::id()));
+        let saved = Secrets::default().save(dir.as_path());
+        assert_eq!(saved.unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn only_the_user_can_read_saved_secrets() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = dir("mode");
+        let filename = Secrets::generate().unwrap().save(dir.as_path()).unwrap();
+        let mode = fs::metadata(filename).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn saving_over_a_readable_file_locks_it_down() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = dir("open");
+        let filename = secrets_file(dir.as_path());
+        fs::write(filename.as_path(), "").unwrap();
+        fs::set_permissions(filename.as_path(), fs::Permissions::from_mode(0o644)).unwrap();
+
+        Secrets::generate().unwrap().save(dir.as_path()).unwrap();
+        let mode = fs::metadata(filename).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,137 @@
+//! Trigger Sources
+//!
+//! Besides the local triggers, triggers can come from remote sources, such
+//! as a guild's trigger pack. Each remote source is synced into a directory
+//! of its own within the data directory, and its triggers are loaded from
+//! there, so a source that can't be reached doesn't stop its triggers from
+//! working, they're just as old as the last sync.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use serde::Deserialize;
+
+use crate::config::create_dir;
+use crate::config::packs;
+use crate::config::triggers::{
+    load_triggers_from_file, StyleOverrides, TriggerSource, TRIGGER_FILENAME,
+};
+use crate::errors::{ConfigError, SourceError};
+
+type Result<T, E = SourceError> = core::result::Result<T, E>;
+
+const REMOTE_DIRNAME: &str = "remote";
+
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct SourceConfig {
+    /// Where the source's trigger file is synced from.
+    pub(crate) url: String,
+    /// Disabled sources are still synced, but none of their triggers are
+    /// loaded.
+    #[serde(default)]
+    pub(crate) disabled: bool,
+    /// Refinements to the styles of the source's triggers, which take
+    /// precedence over the pack's own.
+    #[serde(default)]
+    pub(crate) styles: StyleOverrides,
+}
+
+pub(crate) fn remote_triggers_file(data_dir: &Path, name: &str) -> PathBuf {
+    data_dir
+        .join(REMOTE_DIRNAME)
+        .join(name)
+        .join(TRIGGER_FILENAME)
+}
+
+/// Source names end up as directory names, so they're kept to something that
+/// is safe to use as one everywhere.
+pub(crate) fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureStatus {
+    /// Local triggers are written by the user, so there's nothing to verify.
+    Local,
+    /// Trigger packs can't be signed yet, so nothing that has been synced
+    /// has been verified.
+    Unverified,
+}
+
+impl fmt::Display for SignatureStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureStatus::Local => f.write_str("-"),
+            SignatureStatus::Unverified => f.write_str("unverified"),
+        }
+    }
+}
+
+/// Everything there is to know about a trigger source, for managing it.
+#[derive(Debug, Clone)]
+pub struct SourceInfo {
+    pub source: TriggerSource,
+    /// Where the source is synced from, local triggers aren't synced.
+    pub url: Option<String>,
+    pub disabled: bool,
+    /// When the source was last synced, if it ever has been.
+    pub last_sync: Option<SystemTime>,
+    /// How many triggers were loaded from the source.
+    pub triggers: usize,
+    pub signature: SignatureStatus,
+    /// Why the source's triggers couldn't be loaded, if they couldn't be, in
+    /// which case it doesn't have any.
+    pub error: Option<Arc<ConfigError>>,
+}
+
+/// When the given trigger file was last written to, which for a remote
+/// source is when it was last synced.
+pub(crate) fn last_sync(filename: &Path) -> Option<SystemTime> {
+    fs::metadata(filename).and_then(|m| m.modified()).ok()
+}
+
+/// Fetches a source's trigger file from its url and replaces the synced copy
+/// with it, returning how many triggers it has. Only files can be synced for
+/// now, either as a path or as a file:// url, and those files can be packs,
+/// which are refused if they don't work with what's already installed.
+pub(crate) fn sync(url: &str, data_dir: &Path, name: &str) -> Result<usize> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return Err(SourceError::UnsupportedUrl {
+            url: url.to_string(),
+        });
+    }
+    let path = Path::new(url.strip_prefix("file://").unwrap_or(url));
+    let dest = remote_triggers_file(data_dir, name);
+    let dest = dest.as_path();
+    if packs::is_pack(path) {
+        packs::check_compatible(&packs::read_meta(path)?, data_dir)?;
+        return Ok(packs::unpack(path, dest)?);
+    }
+
+    // Make sure that what we're syncing is actually a trigger file before we
+    // replace a working copy with it.
+    let set = load_triggers_from_file(path)?;
+    let contents = fs::read_to_string(path).map_err(|source| SourceError::IOError {
+        source,
+        filename: path.to_path_buf(),
+    })?;
+
+    if let Some(parent) = dest.parent() {
+        create_dir(parent).map_err(|source| SourceError::IOError {
+            source,
+            filename: parent.to_path_buf(),
+        })?;
+    }
+    fs::write(dest, contents).map_err(|source| SourceError::IOError {
+        source,
+        filename: dest.to_path_buf(),
+    })?;
+
+    Ok(set.triggers.len())
+}
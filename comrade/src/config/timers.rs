@@ -0,0 +1,194 @@
+//! Timer Configuration
+//!
+//! Countdowns can be assigned to named categories (buffs, debuffs, respawns,
+//! etc), which are defined in the configuration so that every frontend groups,
+//! orders, and colors them the same way.
+//!
+//! A countdown's text can also say how long it has left with `{remaining}`,
+//! e.g. "Rampage in {remaining}", which is filled in again every time the
+//! countdown is reported, written out according to the configured pattern.
+//! In the pattern, `%H`, `%M` and `%S` are the hours, minutes and seconds
+//! left, the largest of them is the whole of what's left in that unit, and
+//! the rest are padded to two digits, so the default of `%M:%S` shows `0:42`
+//! or `75:00`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer};
+
+/// The pane that timers are shown in, when their category doesn't say.
+pub const DEFAULT_PANE: &str = "Timers";
+
+/// What a countdown's text says in place of how long it has left.
+pub(crate) const REMAINING_PLACEHOLDER: &str = "{remaining}";
+
+const DEFAULT_REMAINING_FORMAT: &str = "%M:%S";
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TimerCategory {
+    #[serde(skip)]
+    pub name: String,
+    /// A color name (e.g. "green") or a `#rrggbb` hex code.
+    #[serde(default)]
+    pub color: Option<String>,
+    /// Categories with a lower order are shown first.
+    #[serde(default)]
+    pub order: i64,
+    #[serde(default)]
+    pub pane: Option<String>,
+    /// The icon for countdowns in this category that don't have their own.
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+impl TimerCategory {
+    fn named(name: &str) -> TimerCategory {
+        TimerCategory {
+            name: name.to_string(),
+            color: None,
+            order: 0,
+            pane: None,
+            icon: None,
+        }
+    }
+
+    pub fn pane(&self) -> &str {
+        self.pane.as_deref().unwrap_or(DEFAULT_PANE)
+    }
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+pub(crate) struct TimersConfig {
+    #[serde(default, deserialize_with = "named_categories")]
+    categories: HashMap<String, Arc<TimerCategory>>,
+    #[serde(default)]
+    remaining: RemainingFormat,
+}
+
+impl TimersConfig {
+    /// Looks up a category by name, a category that hasn't been configured
+    /// still groups timers together, it just doesn't get any styling.
+    pub(crate) fn category(&self, name: &str) -> Arc<TimerCategory> {
+        self.categories
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| Arc::new(TimerCategory::named(name)))
+    }
+
+    /// Fills in how long a countdown has left, if its text asks for it.
+    pub(crate) fn expand_remaining(&self, text: &Arc<String>, remaining: Duration) -> Arc<String> {
+        if text.contains(REMAINING_PLACEHOLDER) {
+            let formatted = self.remaining.format(remaining);
+            Arc::new(text.replace(REMAINING_PLACEHOLDER, formatted.as_str()))
+        } else {
+            text.clone()
+        }
+    }
+}
+
+/// The pattern that `{remaining}` is written out with.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(transparent)]
+pub(crate) struct RemainingFormat(String);
+
+impl Default for RemainingFormat {
+    fn default() -> RemainingFormat {
+        RemainingFormat(DEFAULT_REMAINING_FORMAT.to_string())
+    }
+}
+
+impl RemainingFormat {
+    pub(crate) fn format(&self, remaining: Duration) -> String {
+        // Rounded up, so that a countdown only says it has nothing left once
+        // it's actually done.
+        let total = remaining.as_millis().div_ceil(1000);
+        let has = |unit: char| self.units().any(|u| u == unit);
+        let (hours, minutes) = (has('H'), has('M'));
+
+        let mut formatted = String::new();
+        let mut chars = self.0.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                formatted.push(c);
+                continue;
+            }
+            let (value, largest) = match chars.next() {
+                Some('H') => (total / 3600, true),
+                Some('M') if hours => ((total / 60) % 60, false),
+                Some('M') => (total / 60, true),
+                Some('S') if hours || minutes => (total % 60, false),
+                Some('S') => (total, true),
+                Some(other) => {
+                    if other != '%' {
+                        formatted.push('%');
+                    }
+                    formatted.push(other);
+                    continue;
+                }
+                None => {
+                    formatted.push('%');
+                    break;
+                }
+            };
+            if largest {
+                formatted.push_str(value.to_string().as_str());
+            } else {
+                formatted.push_str(format!("{:02}", value).as_str());
+            }
+        }
+
+        formatted
+    }
+
+    /// Which of the units the pattern uses.
+    fn units(&self) -> impl Iterator<Item = char> + '_ {
+        let mut chars = self.0.chars();
+        std::iter::from_fn(move || loop {
+            match chars.next()? {
+                '%' => match chars.next()? {
+                    '%' => continue,
+                    unit => return Some(unit),
+                },
+                _ => continue,
+            }
+        })
+    }
+}
+
+fn named_categories<'de, D>(
+    deserializer: D,
+) -> Result<HashMap<String, Arc<TimerCategory>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(HashMap::<String, TimerCategory>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(name, mut category)| {
+            category.name = name.clone();
+            (name, Arc::new(category))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_remaining() {
+        let format = |pattern: &str, secs: u64| {
+            RemainingFormat(pattern.to_string()).format(Duration::from_secs(secs))
+        };
+
+        assert_eq!(format("%M:%S", 42), "0:42");
+        assert_eq!(format("%M:%S", 4500), "75:00");
+        assert_eq!(format("%H:%M:%S", 3725), "1:02:05");
+        assert_eq!(format("%Ss (100%%)", 125), "125s (100%)");
+        assert_eq!(
+            RemainingFormat::default().format(Duration::from_millis(41_200)),
+            "0:42"
+        );
+    }
+}
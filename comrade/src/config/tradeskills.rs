@@ -0,0 +1,36 @@
+//! Tradeskill Configuration
+//!
+//! Tracking tradeskills means reading every combine from the logs, so like
+//! combat tracking it's off unless it has been turned on.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_with::{serde_as, DurationSeconds};
+
+/// How long a character has to go without combining anything before their
+/// tradeskill session is over, unless the configuration says otherwise.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[serde_as]
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct TradeskillsConfig {
+    #[serde(default)]
+    pub(crate) enabled: bool,
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[serde(default = "default_timeout")]
+    pub(crate) timeout: Duration,
+}
+
+impl Default for TradeskillsConfig {
+    fn default() -> TradeskillsConfig {
+        TradeskillsConfig {
+            enabled: false,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+fn default_timeout() -> Duration {
+    DEFAULT_TIMEOUT
+}
@@ -1,20 +1,39 @@
 use std::collections::BTreeMap;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs;
+use std::io;
 use std::io::prelude::*;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
 use std::time::Duration;
 
-use log::{debug, error};
-use regex::RegexSet;
-use serde::Deserialize;
-use serde_with::{serde_as, DurationSeconds};
+use lazy_static::lazy_static;
+use log::{debug, error, warn};
+use parking_lot::Mutex;
+use regex::{Regex, RegexSet};
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DurationMilliSeconds, DurationSeconds};
 
+use crate::config::packs;
+use crate::config::schedule::ActiveWindow;
+use crate::config::search::TriggerFilter;
+use crate::config::sources::{is_valid_name, remote_triggers_file, SourceConfig};
+use crate::config::timers::TimersConfig;
+use crate::config::zones::ZonesConfig;
 use crate::config::{Character, CharacterId, Result};
-use crate::errors::ConfigError;
-use crate::triggers::CompiledTrigger;
+use crate::errors::{ConfigError, TriggerError};
+use crate::triggers::{chained_actions, CompiledTrigger};
 
-const TRIGGER_FILENAME: &str = "Triggers.toml";
+pub(crate) const TRIGGER_FILENAME: &str = "Triggers.toml";
+/// How often an unacknowledged alert repeats, unless its trigger says.
+const DEFAULT_REPEAT: Duration = Duration::from_secs(10);
+const LOCAL_DIRNAME: &str = "local";
+
+pub(crate) fn local_triggers_file(data_dir: &Path) -> PathBuf {
+    data_dir.join(LOCAL_DIRNAME).join(TRIGGER_FILENAME)
+}
 
 #[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Hash, Clone)]
 pub struct TriggerRef {
@@ -23,11 +42,29 @@ pub struct TriggerRef {
 }
 
 impl TriggerRef {
-    pub(crate) fn new(source: TriggerSource, id: TriggerId) -> TriggerRef {
+    pub fn new(source: TriggerSource, id: TriggerId) -> TriggerRef {
         TriggerRef { source, id }
     }
 }
 
+impl fmt::Display for TriggerRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.source, self.id)
+    }
+}
+
+/// Parses a `source/id` reference, a bare id refers to a local trigger.
+impl FromStr for TriggerRef {
+    type Err = TriggerError;
+
+    fn from_str(s: &str) -> Result<TriggerRef, TriggerError> {
+        match s.rsplit_once('/') {
+            Some((source, id)) => Ok(TriggerRef::new(source.parse()?, TriggerId::new(id))),
+            None => Ok(TriggerRef::new(TriggerSource::Local, TriggerId::new(s))),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct DisabledTrigger {
     pub source: TriggerSource,
@@ -35,39 +72,673 @@ pub struct DisabledTrigger {
 }
 
 #[serde_as]
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 #[serde(tag = "type")]
 pub enum Action {
     DisplayText {
         text: String,
         #[serde_as(as = "Option<DurationSeconds<u64>>")]
-        #[serde(default)]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         delay: Option<Duration>,
+        /// Repeats of the exact same text within this long of the last time
+        /// it was displayed are counted against it, rather than being
+        /// displayed again.
+        #[serde_as(as = "Option<DurationSeconds<u64>>")]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        collapse: Option<Duration>,
     },
     Countdown {
         text: String,
         #[serde_as(as = "DurationSeconds<u64>")]
         duration: Duration,
         #[serde_as(as = "Option<DurationSeconds<u64>>")]
-        #[serde(default)]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        delay: Option<Duration>,
+        /// The name of one of the configured timer categories.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        category: Option<String>,
+        /// An identifier for the icon that graphical frontends should show
+        /// alongside the countdown, what it identifies is up to them, but
+        /// EverQuest's own spell icon numbers are the obvious choice.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        icon: Option<String>,
+        /// The spell that this countdown is tracking, if any.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        spell: Option<SpellInfo>,
+        /// A regex that ends the countdown early when a later line from the
+        /// same character's log matches it, like a mez breaking.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        end_early_text: Option<String>,
+        /// Whether matching again while the countdown is still going starts
+        /// it over, rather than starting another one alongside it.
+        #[serde(default, skip_serializing_if = "is_false")]
+        restart: bool,
+        /// How many more times the countdown starts over by itself when it
+        /// runs out, like a respawn timer.
+        #[serde(default, skip_serializing_if = "is_default")]
+        repeat: u32,
+    },
+    /// Records where the character is, as of their latest `/loc`, under the
+    /// given name, e.g. where they died.
+    RecordWaypoint { name: String },
+    /// Displays the position that was recorded under the given name, if
+    /// there is one.
+    RecallWaypoint { name: String },
+    /// Plays a pattern on one of the configured outputs, either some text
+    /// in Morse code, or pulses given by hand.
+    Signal {
+        /// The name of one of the configured outputs.
+        output: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        morse: Option<String>,
+        /// How long each pulse lasts in milliseconds, alternating between on
+        /// and off, starting with on.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pulses: Vec<u64>,
+        /// How long a Morse code dot lasts.
+        #[serde_as(as = "Option<DurationMilliSeconds<u64>>")]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        unit: Option<Duration>,
+        #[serde_as(as = "Option<DurationSeconds<u64>>")]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        delay: Option<Duration>,
+    },
+    /// Hands the options, with whatever the search text captured expanded in
+    /// them, to the executor registered under the given name, for things
+    /// that aren't built in, like calling a webhook.
+    Custom {
+        executor: String,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        options: BTreeMap<String, String>,
+        #[serde_as(as = "Option<DurationSeconds<u64>>")]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
         delay: Option<Duration>,
     },
+    /// Carries out the actions of another trigger from the same source, as
+    /// if it had matched too, for mechanics that play out in stages. The
+    /// other trigger's own delays count from when this one's is up.
+    FireTrigger {
+        id: TriggerId,
+        #[serde_as(as = "Option<DurationSeconds<u64>>")]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        delay: Option<Duration>,
+    },
+    /// Carries out one of several actions, picked at random each time the
+    /// trigger matches, for some variety in alerts that come up often.
+    Random { choices: Vec<Choice> },
+    /// Carries out one list of actions or the other, depending on what the
+    /// search text captured, e.g. whether it was the character that was
+    /// targeted, rather than having near duplicate triggers for each.
+    If {
+        when: CaptureCondition,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        then: Vec<Action>,
+        #[serde(default, rename = "else", skip_serializing_if = "Vec::is_empty")]
+        otherwise: Vec<Action>,
+    },
 }
 
-#[derive(Deserialize, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
+/// One of the actions that a random action picks from, which is picked in
+/// proportion to its weight.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct Choice {
+    #[serde(default = "default_weight", skip_serializing_if = "is_default_weight")]
+    pub weight: u32,
+    pub action: Action,
+}
+
+/// What's known about the spell behind a countdown, for frontends that want
+/// to show more than just some text and a bar.
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Eq, Clone)]
+pub struct SpellInfo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The spell gem that the spell is memorized in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gem: Option<u8>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone)]
 #[serde(transparent)]
 pub struct TriggerId(String);
 
-#[derive(Debug, Deserialize, Clone)]
+impl TriggerId {
+    pub fn new<T: Into<String>>(id: T) -> TriggerId {
+        TriggerId(id.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl fmt::Display for TriggerId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0.as_str())
+    }
+}
+
+#[serde_as]
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone)]
 pub struct Trigger {
     pub name: String,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub comment: String,
     pub search_text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Disabled triggers have to be explicitly enabled per character.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub disabled: bool,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub priority: Priority,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub style: TriggerStyle,
+    /// Alerts from this trigger keep repeating until they're acknowledged,
+    /// for mechanics where missing the callout isn't an option.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub acknowledge: bool,
+    /// How often an unacknowledged alert repeats.
+    #[serde_as(as = "Option<DurationSeconds<u64>>")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat: Option<Duration>,
+    /// The type that capture groups, by name or number, have to hold, like
+    /// `amount = "int"`, the trigger is skipped when one doesn't.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub captures: BTreeMap<String, CaptureType>,
+    /// Conditions on the structured fields of the line, like `damage > 5000`,
+    /// that all have to hold on top of the search text matching.
+    #[serde(default, rename = "where", skip_serializing_if = "Vec::is_empty")]
+    pub conditions: Vec<Condition>,
+    /// When the trigger is active, like `Tue,Thu 20:00-23:30`, by the game's
+    /// clock. A trigger without any is always active.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub active: Vec<ActiveWindow>,
     pub actions: Vec<Action>,
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Clone)]
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+fn is_default_weight(weight: &u32) -> bool {
+    *weight == default_weight()
+}
+
+/// What a capture group has to hold for its trigger to fire.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureType {
+    /// A whole number, like `42` or `-3`.
+    Int,
+    /// Any number, like `42` or `2.5`.
+    Number,
+    /// The name of a player, which is a single capitalized word of 4 to 15
+    /// letters, so that e.g. "a gnoll pup" isn't mistaken for one.
+    PlayerName,
+}
+
+impl CaptureType {
+    pub(crate) fn check(&self, value: &str) -> bool {
+        match self {
+            CaptureType::Int => value.parse::<i64>().is_ok(),
+            CaptureType::Number => {
+                value.chars().any(|c| c.is_ascii_digit())
+                    && value
+                        .chars()
+                        .all(|c| c.is_ascii_digit() || c == '.' || c == '-')
+                    && value.parse::<f64>().is_ok()
+            }
+            CaptureType::PlayerName => {
+                let mut chars = value.chars();
+                (4..=15).contains(&value.len())
+                    && chars.next().is_some_and(|c| c.is_ascii_uppercase())
+                    && chars.all(|c| c.is_ascii_lowercase())
+            }
+        }
+    }
+}
+
+impl fmt::Display for CaptureType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CaptureType::Int => f.write_str("int"),
+            CaptureType::Number => f.write_str("number"),
+            CaptureType::PlayerName => f.write_str("player_name"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Priority::Low => f.write_str("low"),
+            Priority::Normal => f.write_str("normal"),
+            Priority::High => f.write_str("high"),
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = TriggerError;
+
+    fn from_str(s: &str) -> Result<Priority, TriggerError> {
+        match s {
+            "low" => Ok(Priority::Low),
+            "normal" => Ok(Priority::Normal),
+            "high" => Ok(Priority::High),
+            _ => Err(TriggerError::InvalidPriority {
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// A field that Comrade's parsers can pull out of a log line.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Field {
+    /// The chat channel, like "tell", "say", "guild", or "raid", or the name
+    /// of a custom channel.
+    Channel,
+    /// Whoever said something on a chat channel.
+    Speaker,
+    /// What was said on a chat channel.
+    Text,
+    /// The zone that the character is in.
+    Zone,
+    /// The target of the fight that the character is in, so `fight ~ ""`
+    /// holds whenever they're fighting anything.
+    Fight,
+    /// Whoever did some damage, which is the character's name when it's them.
+    Attacker,
+    /// Whoever some damage was done to.
+    Target,
+    /// How much damage was done.
+    Damage,
+}
+
+impl Field {
+    fn is_numeric(&self) -> bool {
+        matches!(self, Field::Damage)
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let field = match self {
+            Field::Channel => "channel",
+            Field::Speaker => "speaker",
+            Field::Text => "text",
+            Field::Zone => "zone",
+            Field::Fight => "fight",
+            Field::Attacker => "attacker",
+            Field::Target => "target",
+            Field::Damage => "damage",
+        };
+        f.write_str(field)
+    }
+}
+
+impl FromStr for Field {
+    type Err = TriggerError;
+
+    fn from_str(s: &str) -> Result<Field, TriggerError> {
+        match s {
+            "channel" => Ok(Field::Channel),
+            "speaker" => Ok(Field::Speaker),
+            "text" => Ok(Field::Text),
+            "zone" => Ok(Field::Zone),
+            "fight" => Ok(Field::Fight),
+            "attacker" => Ok(Field::Attacker),
+            "target" => Ok(Field::Target),
+            "damage" => Ok(Field::Damage),
+            _ => Err(TriggerError::InvalidCondition {
+                value: s.to_string(),
+                reason: "unknown field".to_string(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    /// The field contains the value, ignoring case.
+    Contains,
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Comparison::Equal => "=",
+            Comparison::NotEqual => "!=",
+            Comparison::Greater => ">",
+            Comparison::GreaterOrEqual => ">=",
+            Comparison::Less => "<",
+            Comparison::LessOrEqual => "<=",
+            Comparison::Contains => "~",
+        };
+        f.write_str(op)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FieldValue {
+    Number(u64),
+    /// Text, which is always compared ignoring case.
+    Text(String),
+}
+
+/// A single condition on a structured field, written like `channel = "tell"`
+/// or `damage > 5000`.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Condition {
+    pub field: Field,
+    pub comparison: Comparison,
+    pub value: FieldValue,
+}
+
+lazy_static! {
+    static ref CONDITION_RE: Regex =
+        Regex::new(r"^\s*(?P<field>[a-z]+)\s*(?P<op>!=|>=|<=|=|>|<|~)\s*(?P<value>.*?)\s*$")
+            .unwrap();
+}
+
+impl FromStr for Condition {
+    type Err = TriggerError;
+
+    fn from_str(s: &str) -> Result<Condition, TriggerError> {
+        let invalid = |reason: &str| TriggerError::InvalidCondition {
+            value: s.to_string(),
+            reason: reason.to_string(),
+        };
+
+        let caps = CONDITION_RE
+            .captures(s)
+            .ok_or_else(|| invalid("expected something like damage > 5000"))?;
+        let field: Field = caps["field"].parse()?;
+        let comparison = match &caps["op"] {
+            "=" => Comparison::Equal,
+            "!=" => Comparison::NotEqual,
+            ">" => Comparison::Greater,
+            ">=" => Comparison::GreaterOrEqual,
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessOrEqual,
+            _ => Comparison::Contains,
+        };
+
+        let value = &caps["value"];
+        let value = ["\"", "'"]
+            .iter()
+            .find_map(|q| value.strip_prefix(q)?.strip_suffix(q))
+            .unwrap_or(value);
+        let value = if field.is_numeric() {
+            if comparison == Comparison::Contains {
+                return Err(invalid("numbers can't be searched with ~"));
+            }
+            FieldValue::Number(value.parse().map_err(|_| invalid("expected a number"))?)
+        } else {
+            if !matches!(
+                comparison,
+                Comparison::Equal | Comparison::NotEqual | Comparison::Contains
+            ) {
+                return Err(invalid("text can only be compared with =, !=, or ~"));
+            }
+            FieldValue::Text(value.to_string())
+        };
+
+        Ok(Condition {
+            field,
+            comparison,
+            value,
+        })
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            FieldValue::Number(n) => write!(f, "{} {} {}", self.field, self.comparison, n),
+            FieldValue::Text(ref t) => write!(f, "{} {} {:?}", self.field, self.comparison, t),
+        }
+    }
+}
+
+impl TryFrom<String> for Condition {
+    type Error = TriggerError;
+
+    fn try_from(value: String) -> Result<Condition, TriggerError> {
+        value.parse()
+    }
+}
+
+impl From<Condition> for String {
+    fn from(condition: Condition) -> String {
+        condition.to_string()
+    }
+}
+
+/// What an `If` action is deciding on, written like `${target} = {character}`
+/// or `$1 >= 3`. Both sides are expanded like any other text, which has
+/// `{character}` as another name for `{C}`, the character's name, and then
+/// compared as numbers if they both are, or as text, ignoring case, if they
+/// aren't.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CaptureCondition {
+    pub left: String,
+    pub comparison: Comparison,
+    pub right: String,
+}
+
+lazy_static! {
+    static ref CAPTURE_CONDITION_RE: Regex = Regex::new(
+        r#"^\s*(?P<left>"[^"]*"|'[^']*'|.*?)\s*(?P<op>==|!=|>=|<=|=|>|<|~)\s*(?P<right>.*?)\s*$"#
+    )
+    .unwrap();
+}
+
+impl FromStr for CaptureCondition {
+    type Err = TriggerError;
+
+    fn from_str(s: &str) -> Result<CaptureCondition, TriggerError> {
+        let caps =
+            CAPTURE_CONDITION_RE
+                .captures(s)
+                .ok_or_else(|| TriggerError::InvalidCondition {
+                    value: s.to_string(),
+                    reason: "expected something like ${target} = {character}".to_string(),
+                })?;
+        let comparison = match &caps["op"] {
+            "=" | "==" => Comparison::Equal,
+            "!=" => Comparison::NotEqual,
+            ">" => Comparison::Greater,
+            ">=" => Comparison::GreaterOrEqual,
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessOrEqual,
+            _ => Comparison::Contains,
+        };
+        let unquote = |side: &str| {
+            ["\"", "'"]
+                .iter()
+                .find_map(|q| side.strip_prefix(q)?.strip_suffix(q))
+                .unwrap_or(side)
+                .to_string()
+        };
+
+        Ok(CaptureCondition {
+            left: unquote(&caps["left"]),
+            comparison,
+            right: unquote(&caps["right"]),
+        })
+    }
+}
+
+impl fmt::Display for CaptureCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Sides that would otherwise be read back differently are quoted.
+        let side = |side: &str| {
+            if side.is_empty() || side.trim() != side || side.contains(['=', '!', '<', '>', '~']) {
+                format!("\"{}\"", side)
+            } else {
+                side.to_string()
+            }
+        };
+        write!(
+            f,
+            "{} {} {}",
+            side(self.left.as_str()),
+            self.comparison,
+            side(self.right.as_str())
+        )
+    }
+}
+
+impl TryFrom<String> for CaptureCondition {
+    type Error = TriggerError;
+
+    fn try_from(value: String) -> Result<CaptureCondition, TriggerError> {
+        value.parse()
+    }
+}
+
+impl From<CaptureCondition> for String {
+    fn from(condition: CaptureCondition) -> String {
+        condition.to_string()
+    }
+}
+
+/// Hints to frontends about how a trigger's alerts should be presented,
+/// frontends are free to ignore any that they don't support.
+#[derive(Debug, Serialize, Deserialize, Default, PartialEq, Eq, Clone)]
+pub struct TriggerStyle {
+    /// Whether to show this trigger's text in the big text alert display,
+    /// which by default is only used for high priority triggers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub big_text: Option<bool>,
+    /// A color name (e.g. "red") or a `#rrggbb` hex code.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    /// How long, in seconds, an alert from this trigger stays on screen.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seconds: Option<u64>,
+    /// A sound to play alongside this trigger's alerts, relative to the
+    /// directory of the pack it came from, e.g. `sounds/rampage.wav`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sound: Option<String>,
+    /// Whether this trigger's alerts are read out, in the voice of the
+    /// character they're for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speak: Option<bool>,
+}
+
+impl TriggerStyle {
+    /// This style, with anything it leaves unset taken from the given one.
+    pub(crate) fn or(&self, fallback: &TriggerStyle) -> TriggerStyle {
+        TriggerStyle {
+            big_text: self.big_text.or(fallback.big_text),
+            color: self.color.clone().or_else(|| fallback.color.clone()),
+            seconds: self.seconds.or(fallback.seconds),
+            sound: self.sound.clone().or_else(|| fallback.sound.clone()),
+            speak: self.speak.or(fallback.speak),
+        }
+    }
+}
+
+/// Styles that a pack gives its triggers unless they say otherwise, and that
+/// the configuration of a source can refine in turn, without having to edit
+/// the pack itself.
+#[derive(Debug, Deserialize, Default, PartialEq, Eq, Clone)]
+pub(crate) struct PackStyles {
+    /// The style of every trigger in the pack.
+    #[serde(default)]
+    pub(crate) defaults: TriggerStyle,
+    /// The style of every trigger with the given tag, which takes precedence
+    /// over the defaults. When a trigger has more than one tag with a style,
+    /// its first tag wins.
+    #[serde(default)]
+    pub(crate) groups: BTreeMap<String, TriggerStyle>,
+}
+
+impl PackStyles {
+    /// The given trigger's style, with anything it leaves unset taken from
+    /// its groups, and then from the defaults.
+    fn resolve(&self, style: &TriggerStyle, tags: &[String]) -> TriggerStyle {
+        tags.iter()
+            .filter_map(|tag| self.groups.get(tag))
+            .chain(std::iter::once(&self.defaults))
+            .fold(style.clone(), |style, fallback| style.or(fallback))
+    }
+}
+
+/// The local refinements to a source's styles, which take precedence over
+/// whatever its pack says, the trigger's own style included.
+#[derive(Debug, Deserialize, Default, PartialEq, Eq, Clone)]
+pub(crate) struct StyleOverrides {
+    #[serde(default, flatten)]
+    pub(crate) styles: PackStyles,
+    /// The style of a single trigger, which takes precedence over both of
+    /// the others.
+    #[serde(default)]
+    pub(crate) triggers: BTreeMap<TriggerId, TriggerStyle>,
+}
+
+impl StyleOverrides {
+    fn resolve(&self, id: &TriggerId, trigger: &Trigger) -> TriggerStyle {
+        let local = self.triggers.get(id).cloned().unwrap_or_default();
+        self.styles
+            .resolve(&local, &trigger.tags)
+            .or(&trigger.style)
+    }
+}
+
+impl Trigger {
+    /// Whether a line with the given timestamp falls within one of this
+    /// trigger's active windows, if it has any.
+    pub(crate) fn is_scheduled(&self, timestamp: &str) -> bool {
+        self.active.is_empty() || self.active.iter().any(|w| w.contains(timestamp))
+    }
+
+    pub fn wants_big_text(&self) -> bool {
+        self.style
+            .big_text
+            .unwrap_or(self.priority == Priority::High)
+    }
+
+    /// How often alerts from this trigger repeat, if they have to be
+    /// acknowledged.
+    pub fn repeat_interval(&self) -> Option<Duration> {
+        self.acknowledge
+            .then(|| self.repeat.unwrap_or(DEFAULT_REPEAT))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Clone)]
 pub enum TriggerSource {
     #[serde(rename = "local")]
     Local,
@@ -75,82 +746,272 @@ pub enum TriggerSource {
     Remote(String),
 }
 
-#[derive(Debug, Deserialize)]
+impl fmt::Display for TriggerSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TriggerSource::Local => f.write_str("local"),
+            TriggerSource::Remote(name) => write!(f, "remote:{}", name),
+        }
+    }
+}
+
+impl FromStr for TriggerSource {
+    type Err = TriggerError;
+
+    fn from_str(s: &str) -> Result<TriggerSource, TriggerError> {
+        match s.split_once(':') {
+            None if s == "local" => Ok(TriggerSource::Local),
+            Some(("remote", name)) if !name.is_empty() => {
+                Ok(TriggerSource::Remote(name.to_string()))
+            }
+            _ => Err(TriggerError::InvalidSource {
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
 pub(crate) struct TriggerMeta {
     pub(crate) source: TriggerSource,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 pub(crate) struct TriggerSet {
     pub(crate) meta: TriggerMeta,
 
+    #[serde(default)]
+    pub(crate) styles: PackStyles,
+
+    /// The source's local overrides, which come from its configuration
+    /// rather than the file.
+    #[serde(skip)]
+    pub(crate) overrides: StyleOverrides,
+
     #[serde(default)]
     pub(crate) triggers: BTreeMap<TriggerId, Trigger>,
 }
 
+/// The patterns that have been compiled, along with whether they could be,
+/// kept from one load of the triggers to the next so that only the patterns
+/// that are new since are compiled again, and each pattern is only compiled
+/// once however many characters have its trigger.
+///
+/// Compiled patterns can't be written out, so there's no cache of them in the
+/// data directory. This only lasts as long as Comrade is running, and the
+/// first load always compiles everything.
 #[derive(Default, Debug)]
+pub(crate) struct PatternCache {
+    packs: Mutex<HashMap<TriggerSource, CachedPack>>,
+    // Each character's filter, by the patterns that went into it, which
+    // is the slowest thing to compile, and doesn't change at all when a
+    // trigger's actions are all that's been edited.
+    filters: Mutex<HashMap<Vec<String>, RegexSet>>,
+}
+
+#[derive(Default, Debug)]
+struct CachedPack {
+    patterns: HashMap<String, std::result::Result<Regex, regex::Error>>,
+}
+
+impl CachedPack {
+    /// Forgets the patterns that are no longer in the given set.
+    fn refresh(&mut self, trg: &TriggerSet) {
+        let current: HashSet<&str> = trg
+            .triggers
+            .values()
+            .map(|t| t.search_text.as_str())
+            .collect();
+        self.patterns.retain(|p, _| current.contains(p.as_str()));
+    }
+
+    fn regex(&mut self, pattern: &str) -> std::result::Result<Regex, regex::Error> {
+        if let Some(compiled) = self.patterns.get(pattern) {
+            return compiled.clone();
+        }
+
+        let compiled = Regex::new(pattern);
+        self.patterns.insert(pattern.to_string(), compiled.clone());
+        compiled
+    }
+}
+
+#[derive(Default, Debug, Clone)]
 pub(crate) struct Triggers {
-    _triggers: BTreeMap<TriggerSource, TriggerSet>,
+    triggers: BTreeMap<TriggerSource, TriggerSet>,
     compiled: HashMap<CharacterId, Vec<CompiledTrigger>>,
     filters: HashMap<CharacterId, RegexSet>,
+    // Why each source that couldn't be loaded wasn't, those sources just
+    // don't have any triggers, rather than taking every other source down
+    // with them.
+    errors: BTreeMap<TriggerSource, Arc<ConfigError>>,
 }
 
 impl Triggers {
+    /// Loads the triggers from every source, calling `progress` with each
+    /// source once it's loaded, along with how many have been so far and how
+    /// many there are.
     pub(super) fn load(
         data_dir: &Path,
         characters: &HashMap<CharacterId, Character>,
-    ) -> Result<Triggers> {
-        let mut triggers = BTreeMap::new();
-        let mut compiled = HashMap::new();
+        timers: &TimersConfig,
+        sources: &BTreeMap<String, SourceConfig>,
+        zones: &ZonesConfig,
+        cache: &PatternCache,
+        mut progress: impl FnMut(&TriggerSource, usize, usize),
+    ) -> Triggers {
+        let mut triggers = Triggers::default();
         let mut filters = HashMap::new();
+        let mut packs = cache.packs.lock();
+        let mut loaded = HashSet::new();
+
+        let remotes: Vec<(&String, &SourceConfig)> =
+            sources.iter().filter(|(_, s)| !s.disabled).collect();
+        let total = remotes.len() + 1;
 
         // Load our local triggers
-        match load_triggers_from_dir(data_dir.join("local").as_path(), true)? {
-            Some(trg) => {
-                for (trigger_id, trigger) in trg.triggers.iter() {
-                    for (character_id, character) in characters {
-                        if !character.disabled_triggers.contains_key(&TriggerRef::new(
-                            trg.meta.source.clone(),
-                            trigger_id.clone(),
-                        )) {
-                            // Precompile our Trigger
-                            compiled
-                                .entry(character_id.clone())
-                                .or_insert_with(Vec::new)
-                                .push(CompiledTrigger::new(character, trigger)?);
-
-                            // Add this pattern to the list of patterns for this character
-                            // for later compilation of our filter function.
-                            filters
-                                .entry(character_id.clone())
-                                .or_insert_with(Vec::new)
-                                .push(trigger.search_text.clone());
-                        }
-                    }
+        let local = load_triggers_from_dir(data_dir.join(LOCAL_DIRNAME).as_path(), true);
+        match local {
+            Ok(Some(trg)) => {
+                loaded.insert(TriggerSource::Local);
+                let cached = packs.entry(TriggerSource::Local).or_default();
+                triggers.add(trg, characters, timers, zones, cached, &mut filters);
+            }
+            Ok(None) => {}
+            Err(e) => triggers.failed(TriggerSource::Local, e),
+        }
+        progress(&TriggerSource::Local, 1, total);
+
+        // Load whatever was last synced from our remote sources.
+        for (idx, (name, config)) in remotes.into_iter().enumerate() {
+            let source = TriggerSource::Remote(name.clone());
+            match load_remote_triggers(data_dir, name) {
+                Some(Ok(mut trg)) => {
+                    // The file says what source it is, but we know better,
+                    // since it's our own copy of it.
+                    trg.meta.source = source.clone();
+                    loaded.insert(source.clone());
+                    let cached = packs.entry(source.clone()).or_default();
+                    trg.overrides = config.styles.clone();
+                    triggers.add(trg, characters, timers, zones, cached, &mut filters);
                 }
-                triggers.insert(trg.meta.source.clone(), trg);
+                Some(Err(e)) => triggers.failed(source.clone(), e),
+                None => {}
             }
-            None => {}
+            progress(&source, idx + 2, total);
         }
 
-        // TODO: Load Remote Triggers
+        // Sources that have gone away, or couldn't be loaded this time,
+        // don't need their patterns kept around any more.
+        packs.retain(|source, _| loaded.contains(source));
+        drop(packs);
 
-        // Compile our filter functions
-        let filters = filters
-            .into_iter()
-            .map(|(k, v)| {
-                (
-                    k,
-                    RegexSet::new(v).expect("error compiling after validation?"),
-                )
-            })
-            .collect();
+        triggers.compile_filters(filters, cache);
+        triggers
+    }
 
-        Ok(Triggers {
-            _triggers: triggers,
-            compiled,
-            filters,
-        })
+    /// Makes triggers out of a single set that's already been loaded, such
+    /// as the built in ones for demo mode.
+    pub(super) fn from_set(
+        trg: TriggerSet,
+        characters: &HashMap<CharacterId, Character>,
+        timers: &TimersConfig,
+        zones: &ZonesConfig,
+        cache: &PatternCache,
+    ) -> Triggers {
+        let mut triggers = Triggers::default();
+        let mut filters = HashMap::new();
+        let mut packs = cache.packs.lock();
+        let source = trg.meta.source.clone();
+        packs.retain(|s, _| *s == source);
+        let cached = packs.entry(source).or_default();
+        triggers.add(trg, characters, timers, zones, cached, &mut filters);
+        drop(packs);
+        triggers.compile_filters(filters, cache);
+        triggers
+    }
+
+    /// Compiles each character's filter, reusing whichever ones were
+    /// compiled last time from the same patterns.
+    fn compile_filters(
+        &mut self,
+        filters: HashMap<CharacterId, Vec<String>>,
+        cache: &PatternCache,
+    ) {
+        let mut previous = std::mem::take(&mut *cache.filters.lock());
+        let mut current = HashMap::new();
+        for (id, patterns) in filters {
+            let set = previous
+                .remove(&patterns)
+                .or_else(|| current.get(&patterns).cloned())
+                .unwrap_or_else(|| {
+                    RegexSet::new(&patterns).expect("error compiling after validation?")
+                });
+            current.insert(patterns, set.clone());
+            self.filters.insert(id, set);
+        }
+        *cache.filters.lock() = current;
+    }
+
+    /// Adds the given set of triggers, unless any of them can't be compiled,
+    /// in which case none of them are, and the source is recorded as having
+    /// failed to load instead.
+    fn add(
+        &mut self,
+        trg: TriggerSet,
+        characters: &HashMap<CharacterId, Character>,
+        timers: &TimersConfig,
+        zones: &ZonesConfig,
+        cached: &mut CachedPack,
+        filters: &mut HashMap<CharacterId, Vec<String>>,
+    ) {
+        let mut compiled: HashMap<CharacterId, Vec<CompiledTrigger>> = HashMap::new();
+        let mut patterns: HashMap<CharacterId, Vec<String>> = HashMap::new();
+        cached.refresh(&trg);
+        if let Err(e) = compile(
+            &trg,
+            characters,
+            timers,
+            zones,
+            cached,
+            &mut compiled,
+            &mut patterns,
+        ) {
+            self.failed(trg.meta.source, e);
+            return;
+        }
+
+        for (character_id, triggers) in compiled {
+            self.compiled
+                .entry(character_id)
+                .or_default()
+                .extend(triggers);
+        }
+        for (character_id, patterns) in patterns {
+            filters.entry(character_id).or_default().extend(patterns);
+        }
+        self.triggers.insert(trg.meta.source.clone(), trg);
+    }
+
+    fn failed(&mut self, source: TriggerSource, error: ConfigError) {
+        error!(
+            "could not load triggers from {}, skipping them; error: {:?}",
+            source, error
+        );
+        self.errors.insert(source, Arc::new(error));
+    }
+
+    /// Why the given source couldn't be loaded, if it couldn't be.
+    pub(crate) fn error(&self, source: &TriggerSource) -> Option<Arc<ConfigError>> {
+        self.errors.get(source).cloned()
+    }
+
+    /// How many triggers were loaded from the given source.
+    pub(crate) fn count(&self, source: &TriggerSource) -> usize {
+        self.triggers
+            .get(source)
+            .map(|set| set.triggers.len())
+            .unwrap_or(0)
     }
 
     pub(crate) fn filter(&self, id: &CharacterId) -> Box<dyn Fn(&str) -> bool + Send> {
@@ -166,6 +1027,143 @@ impl Triggers {
     pub(crate) fn compiled(&self, id: &CharacterId) -> Option<&[CompiledTrigger]> {
         self.compiled.get(id).map(|v| v.as_slice())
     }
+
+    pub(crate) fn get(&self, tref: &TriggerRef) -> Option<&Trigger> {
+        self.triggers
+            .get(&tref.source)
+            .and_then(|set| set.triggers.get(&tref.id))
+    }
+
+    /// Every trigger from the given source, which are the ones that its
+    /// triggers can fire.
+    pub(crate) fn pack(&self, source: &TriggerSource) -> Option<&BTreeMap<TriggerId, Trigger>> {
+        self.triggers.get(source).map(|set| &set.triggers)
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (TriggerRef, &Trigger)> {
+        self.triggers.values().flat_map(|set| {
+            set.triggers.iter().map(|(id, trigger)| {
+                (
+                    TriggerRef::new(set.meta.source.clone(), id.clone()),
+                    trigger,
+                )
+            })
+        })
+    }
+
+    /// Returns every loaded trigger that matches the given filter, ordered so
+    /// that the best matches come first.
+    pub(crate) fn search(&self, filter: &TriggerFilter) -> Vec<(TriggerRef, &Trigger)> {
+        let mut results: Vec<(i64, TriggerRef, &Trigger)> = self
+            .iter()
+            .filter_map(|(tref, trigger)| {
+                filter
+                    .score(&tref, trigger)
+                    .map(|score| (score, tref, trigger))
+            })
+            .collect();
+
+        results.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        results
+            .into_iter()
+            .map(|(_, tref, trigger)| (tref, trigger))
+            .collect()
+    }
+}
+
+/// Compiles every trigger in the given set for each character that might
+/// need it, along with their patterns for the character's filter.
+///
+/// Triggers are compiled with their styles resolved, from the source's
+/// overrides, then the trigger itself, then its groups and then the pack's
+/// defaults, while the set itself keeps them as they were written, so that
+/// editing a trigger doesn't bake its pack's styles into it.
+fn compile(
+    trg: &TriggerSet,
+    characters: &HashMap<CharacterId, Character>,
+    timers: &TimersConfig,
+    zones: &ZonesConfig,
+    cached: &mut CachedPack,
+    compiled: &mut HashMap<CharacterId, Vec<CompiledTrigger>>,
+    patterns: &mut HashMap<CharacterId, Vec<String>>,
+) -> Result<()> {
+    let pack = resolve_styles(trg);
+    for (trigger_id, trigger) in pack.iter() {
+        // Check that the triggers it fires exist and never come back
+        // around to it, even if no character has it turned on.
+        chained_actions(trigger, &pack)?;
+
+        let tref = TriggerRef::new(trg.meta.source.clone(), trigger_id.clone());
+        for (character_id, character) in characters {
+            let enabled = character.is_trigger_enabled(&tref, trigger);
+            // Triggers that only some zones turn on are compiled as well,
+            // so they're ready whenever the character enters one of them,
+            // unless the character has turned them off entirely.
+            if enabled
+                || (!character.disabled_triggers.contains_key(&tref) && zones.may_enable(trigger))
+            {
+                // Precompile our Trigger
+                let regex = cached
+                    .regex(trigger.search_text.as_str())
+                    .map_err(TriggerError::from)?;
+                compiled.entry(character_id.clone()).or_default().push(
+                    CompiledTrigger::with_regex(
+                        character, &tref, trigger, &pack, timers, enabled, regex,
+                    )?,
+                );
+
+                // Add this pattern to the list of patterns for this character
+                // for later compilation of our filter function.
+                patterns
+                    .entry(character_id.clone())
+                    .or_default()
+                    .push(trigger.search_text.clone());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Every trigger in the given set, with its style resolved.
+fn resolve_styles(trg: &TriggerSet) -> BTreeMap<TriggerId, Trigger> {
+    trg.triggers
+        .iter()
+        .map(|(id, trigger)| {
+            let mut trigger = trigger.clone();
+            trigger.style = trg.styles.resolve(&trigger.style, &trigger.tags);
+            trigger.style = trg.overrides.resolve(id, &trigger);
+            (id.clone(), trigger)
+        })
+        .collect()
+}
+
+/// Loads the synced copy of a remote source, if there is one to load, a
+/// source that has never been synced just doesn't have any triggers yet.
+fn load_remote_triggers(data_dir: &Path, name: &str) -> Option<Result<TriggerSet>> {
+    if !is_valid_name(name) {
+        warn!("ignoring trigger source with invalid name {:?}", name);
+        return None;
+    }
+
+    let filename = remote_triggers_file(data_dir, name);
+    if !filename.exists() {
+        debug!("trigger source {} has not been synced yet", name);
+        return None;
+    }
+
+    // A pack whose dependencies went missing, or that was synced by a newer
+    // Comrade, can't be trusted to work.
+    let compatible = match packs::installed(data_dir, name) {
+        Ok(Some(pack)) => packs::check_compatible(&pack, data_dir),
+        Ok(None) => Ok(()),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = compatible {
+        return Some(Err(e.into()));
+    }
+
+    Some(load_triggers_from_file(filename.as_path()))
 }
 
 fn load_triggers_from_dir(dir: &Path, allow_missing: bool) -> Result<Option<TriggerSet>> {
@@ -175,16 +1173,11 @@ fn load_triggers_from_dir(dir: &Path, allow_missing: bool) -> Result<Option<Trig
     let file = fs::OpenOptions::new().read(true).open(path.as_path());
 
     match file {
-        Ok(mut f) => {
-            let mut buffer = String::new();
-            f.read_to_string(&mut buffer)?;
-
-            Ok(toml_edit::de::from_str(buffer.as_str()).map_err(|source| {
-                ConfigError::DeserializationError {
-                    source,
-                    filename: path.clone(),
-                }
-            })?)
+        Ok(f) => Ok(Some(read_triggers(path.as_path(), f)?)),
+        // Nobody has added any triggers here yet.
+        Err(e) if e.kind() == io::ErrorKind::NotFound && allow_missing => {
+            debug!("no triggers in {}", dir.display());
+            Ok(None)
         }
         Err(e) => {
             error!(
@@ -193,11 +1186,158 @@ fn load_triggers_from_dir(dir: &Path, allow_missing: bool) -> Result<Option<Trig
                 e
             );
 
-            if allow_missing {
-                Ok(None)
-            } else {
-                Err(e.into())
-            }
+            Err(e.into())
         }
     }
 }
+
+/// Loads a single trigger file, which unlike the trigger directories is
+/// required to exist.
+pub(crate) fn load_triggers_from_file(path: &Path) -> Result<TriggerSet> {
+    debug!("loading triggers from {}", path.display());
+
+    let file = fs::OpenOptions::new().read(true).open(path)?;
+    read_triggers(path, file)
+}
+
+fn read_triggers(path: &Path, mut file: fs::File) -> Result<TriggerSet> {
+    let mut buffer = String::new();
+    file.read_to_string(&mut buffer)?;
+
+    toml_edit::de::from_str(buffer.as_str()).map_err(|source| ConfigError::DeserializationError {
+        source,
+        filename: path.to_path_buf(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::demo;
+
+    use super::*;
+
+    fn load(pack: TriggerSet, cache: &PatternCache) -> Triggers {
+        let config = demo::config();
+        Triggers::from_set(
+            pack,
+            &config.characters,
+            &config.timers,
+            &config.zones,
+            cache,
+        )
+    }
+
+    #[test]
+    fn keeps_the_patterns_that_are_still_in_the_pack() {
+        let cache = PatternCache::default();
+        let mut pack = demo::pack();
+        let source = pack.meta.source.clone();
+        let patterns = |cache: &PatternCache| {
+            let mut patterns: Vec<String> = cache.packs.lock()[&source]
+                .patterns
+                .keys()
+                .cloned()
+                .collect();
+            patterns.sort();
+            patterns
+        };
+
+        let triggers = load(pack.clone(), &cache);
+        assert!(triggers.error(&source).is_none());
+        let before = patterns(&cache);
+        assert_eq!(cache.filters.lock().len(), 1);
+
+        let (_, removed) = pack.triggers.pop_first().unwrap();
+        load(pack, &cache);
+        let after = patterns(&cache);
+        assert!(!after.contains(&removed.search_text));
+        assert_eq!(after.len(), before.len() - 1);
+        assert!(after.iter().all(|p| before.contains(p)));
+        assert_eq!(cache.filters.lock().len(), 1);
+    }
+
+    #[test]
+    fn patterns_that_do_not_compile_fail_every_load() {
+        let cache = PatternCache::default();
+        let mut pack = demo::pack();
+        let source = pack.meta.source.clone();
+        pack.triggers.values_mut().next().unwrap().search_text = "(unclosed".to_string();
+
+        for _ in 0..2 {
+            let triggers = load(pack.clone(), &cache);
+            assert!(matches!(
+                triggers.error(&source).as_deref(),
+                Some(ConfigError::TriggerError(TriggerError::InvalidRegex(_)))
+            ));
+            assert_eq!(triggers.count(&source), 0);
+        }
+    }
+
+    #[test]
+    fn resolves_styles_from_overrides_triggers_groups_and_defaults() {
+        let mut pack: TriggerSet = toml_edit::de::from_str(
+            r#"
+            [meta]
+            source = "local"
+
+            [styles.defaults]
+            color = "white"
+            speak = true
+
+            [styles.groups.raid]
+            color = "red"
+            sound = "sounds/raid.wav"
+
+            [triggers.breath]
+            name = "Fire breath"
+            search_text = "draws a deep breath"
+            tags = ["raid"]
+            style = { speak = false }
+            actions = [{ type = "DisplayText", text = "Breath" }]
+
+            [triggers.tell]
+            name = "Tell received"
+            search_text = "tells you"
+            actions = [{ type = "DisplayText", text = "Tell" }]
+            "#,
+        )
+        .unwrap();
+        let id = |id: &str| TriggerId(id.to_string());
+
+        let styles = resolve_styles(&pack);
+        let breath = &styles[&id("breath")].style;
+        assert_eq!(breath.color.as_deref(), Some("red"));
+        assert_eq!(breath.sound.as_deref(), Some("sounds/raid.wav"));
+        assert_eq!(breath.speak, Some(false));
+        let tell = &styles[&id("tell")].style;
+        assert_eq!(tell.color.as_deref(), Some("white"));
+        assert_eq!(tell.sound, None);
+        assert_eq!(tell.speak, Some(true));
+
+        pack.overrides = toml_edit::de::from_str(
+            r#"
+            [defaults]
+            speak = false
+
+            [groups.raid]
+            sound = "sounds/mine.wav"
+
+            [triggers.tell]
+            color = "blue"
+            "#,
+        )
+        .unwrap();
+
+        let styles = resolve_styles(&pack);
+        let breath = &styles[&id("breath")].style;
+        assert_eq!(breath.color.as_deref(), Some("red"));
+        assert_eq!(breath.sound.as_deref(), Some("sounds/mine.wav"));
+        assert_eq!(breath.speak, Some(false));
+        let tell = &styles[&id("tell")].style;
+        assert_eq!(tell.color.as_deref(), Some("blue"));
+        assert_eq!(tell.speak, Some(false));
+
+        // The pack itself keeps its triggers as they were written.
+        assert_eq!(pack.triggers[&id("tell")].style, TriggerStyle::default());
+    }
+}
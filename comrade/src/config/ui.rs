@@ -0,0 +1,75 @@
+//! UI Configuration
+//!
+//! Comrade itself doesn't use any of this, it's how a frontend should lay
+//! itself out, but it lives alongside the rest of the configuration so that
+//! there's only the one file to edit.
+
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Default, Clone)]
+pub struct UiConfig {
+    /// The ids of the tabs to show, in the order to show them. When this is
+    /// empty, every tab is shown.
+    #[serde(default)]
+    pub tabs: Vec<String>,
+    #[serde(default)]
+    pub events: EventsLayout,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct EventsLayout {
+    /// The percentage of the events tab given to messages, the rest of it
+    /// goes to timers.
+    #[serde(default = "default_split")]
+    pub split: u16,
+    /// How many of the most recent matches to show, none are shown if this
+    /// is zero.
+    #[serde(default = "default_matches")]
+    pub matches: u16,
+    /// How many of the most recent raid and guild broadcasts to show, the
+    /// pane for them only shows up once there's been one.
+    #[serde(default = "default_broadcasts")]
+    pub broadcasts: u16,
+    /// Put the messages above the timers, rather than beside them, which
+    /// works better in a narrow window.
+    #[serde(default)]
+    pub stacked: bool,
+    /// The ids or aliases of the characters to give a pane each when the
+    /// events tab is split by character. When this is empty, every character
+    /// gets one.
+    #[serde(default)]
+    pub characters: Vec<String>,
+    /// How many messages and matches to keep around for scrolling back
+    /// through, the oldest are dropped once there are more than this.
+    #[serde(default = "default_retention")]
+    pub retention: usize,
+}
+
+impl Default for EventsLayout {
+    fn default() -> EventsLayout {
+        EventsLayout {
+            split: default_split(),
+            matches: default_matches(),
+            broadcasts: default_broadcasts(),
+            stacked: false,
+            characters: Vec::new(),
+            retention: default_retention(),
+        }
+    }
+}
+
+fn default_split() -> u16 {
+    40
+}
+
+fn default_matches() -> u16 {
+    4
+}
+
+fn default_broadcasts() -> u16 {
+    4
+}
+
+fn default_retention() -> usize {
+    1000
+}
@@ -0,0 +1,32 @@
+//! Update Check Configuration
+//!
+//! Checking for a newer version of Comrade means asking GitHub, so it's off
+//! until it's been turned on. The feed can be pointed somewhere else, such as
+//! a fork's releases, or a file for trying it out.
+
+use serde::Deserialize;
+
+const DEFAULT_FEED: &str = "https://github.com/dstufft/comrade/releases.atom";
+
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct UpdatesConfig {
+    #[serde(default)]
+    pub(crate) enabled: bool,
+    /// The Atom feed of releases, whose entries are titled with their
+    /// version, e.g. `v0.2.0`.
+    #[serde(default = "default_feed")]
+    pub(crate) feed: String,
+}
+
+impl Default for UpdatesConfig {
+    fn default() -> UpdatesConfig {
+        UpdatesConfig {
+            enabled: false,
+            feed: default_feed(),
+        }
+    }
+}
+
+fn default_feed() -> String {
+    DEFAULT_FEED.to_string()
+}
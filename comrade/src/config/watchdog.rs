@@ -0,0 +1,43 @@
+//! Watchdog Configuration
+//!
+//! How late is too late for a callout depends on what's being called out, a
+//! second might be fine for a tell but not for a spell that has to be
+//! interrupted, so the threshold that the latency watchdog warns past is set
+//! here.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_with::{serde_as, DurationMilliSeconds};
+
+/// How late an audible event can be before the watchdog warns about it,
+/// unless the configuration says otherwise.
+const DEFAULT_THRESHOLD: Duration = Duration::from_millis(1000);
+
+#[serde_as]
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct WatchdogConfig {
+    #[serde(default = "default_enabled")]
+    pub(crate) enabled: bool,
+    /// In milliseconds.
+    #[serde_as(as = "DurationMilliSeconds<u64>")]
+    #[serde(default = "default_threshold")]
+    pub(crate) threshold: Duration,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> WatchdogConfig {
+        WatchdogConfig {
+            enabled: true,
+            threshold: DEFAULT_THRESHOLD,
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_threshold() -> Duration {
+    DEFAULT_THRESHOLD
+}
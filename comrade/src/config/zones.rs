@@ -0,0 +1,71 @@
+//! Zone Profiles
+//!
+//! Raid triggers are noise while farming, and farming triggers are noise on
+//! raid night, so zones can be given a profile of trigger groups to turn on
+//! or off while a character is in them. A group is just a tag, so a profile
+//! like `enable = ["Raids/PoP/Time"]` turns on every trigger tagged with it,
+//! even ones that are disabled by default, for as long as the character is
+//! in that zone. Triggers that a character has explicitly disabled stay off.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::config::triggers::Trigger;
+
+#[derive(Deserialize, Debug, Default, Clone)]
+pub(crate) struct ZoneProfile {
+    /// The groups of triggers that are turned on in the zone.
+    #[serde(default)]
+    pub(crate) enable: Vec<String>,
+    /// The groups of triggers that are turned off in the zone, which wins
+    /// over a trigger also being in a group that's turned on.
+    #[serde(default)]
+    pub(crate) disable: Vec<String>,
+}
+
+impl ZoneProfile {
+    /// Whether the given trigger is on in the zone, or `None` if the profile
+    /// doesn't say, in which case it's up to the character.
+    pub(crate) fn is_enabled(&self, trigger: &Trigger) -> Option<bool> {
+        let in_any = |groups: &[String]| trigger.tags.iter().any(|t| groups.contains(t));
+        if in_any(&self.disable) {
+            Some(false)
+        } else if in_any(&self.enable) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+}
+
+/// The zone profiles, by the name of the zone as the game reports it when
+/// it's entered, e.g. "The Plane of Time".
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(transparent)]
+pub(crate) struct ZonesConfig {
+    profiles: BTreeMap<String, ZoneProfile>,
+}
+
+impl ZonesConfig {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.profiles.is_empty()
+    }
+
+    /// The profile for the given zone, the name of which is matched ignoring
+    /// case.
+    pub(crate) fn profile(&self, zone: &str) -> Option<&ZoneProfile> {
+        self.profiles
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(zone))
+            .map(|(_, profile)| profile)
+    }
+
+    /// Whether any zone's profile turns on the given trigger, which means it
+    /// has to be ready to go even if it's off everywhere else.
+    pub(crate) fn may_enable(&self, trigger: &Trigger) -> bool {
+        self.profiles
+            .values()
+            .any(|p| p.is_enabled(trigger) == Some(true))
+    }
+}
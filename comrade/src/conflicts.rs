@@ -0,0 +1,290 @@
+//! Trigger Conflicts
+//!
+//! Importing packs from more than one guild tends to leave several triggers
+//! looking for the same lines, so that a single emote sets off two callouts
+//! that talk over each other. Whether two regexes can ever match the same
+//! line isn't something the regex crate can answer, so conflicts are worked
+//! out the way someone reading the packs would: each pattern makes up the
+//! shortest line that it matches, which is tried against every other pattern,
+//! and patterns that both need the same long stretch of text are flagged as
+//! likely to match the same lines, even if no line was found that they both
+//! do.
+//!
+//! Both are guesses, a pair can be flagged that never actually matches the
+//! same line, and a pair that does can be missed, so what's found is for a
+//! person to look over rather than for anything to act on by itself.
+
+use regex::Regex;
+use regex_syntax::hir::{Class, Hir, HirKind};
+
+use crate::config::triggers::{Trigger, TriggerRef};
+
+/// How long a stretch of text two patterns need in common before they're
+/// flagged as likely to match the same lines, shorter ones, like "You ",
+/// are in too many patterns to mean anything.
+const MIN_SHARED_TEXT: usize = 12;
+
+/// The characters tried, in order, for a character class in a made up line,
+/// so that the line reads like a log line where it can.
+const PREFERRED_CHARS: &[char] = &['a', 'A', '0', ' '];
+
+/// Why two triggers look like they'd match the same lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Overlap {
+    /// Their patterns are exactly the same.
+    Identical,
+    /// A line that was made up to match one of them matches the other.
+    Line(String),
+    /// They both need the same stretch of text, though they might not match
+    /// the same lines.
+    Text(String),
+}
+
+/// A pair of triggers that look like they'd match the same lines, in the
+/// order they were loaded in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    pub first: TriggerRef,
+    pub first_name: String,
+    pub second: TriggerRef,
+    pub second_name: String,
+    pub overlap: Overlap,
+}
+
+struct Candidate<'a> {
+    tref: TriggerRef,
+    trigger: &'a Trigger,
+    regex: Regex,
+    /// The shortest line that the pattern matches, if one could be made up.
+    line: Option<String>,
+    /// The stretches of text that the pattern can't match without.
+    literals: Vec<String>,
+}
+
+impl<'a> Candidate<'a> {
+    /// Patterns that don't compile are left out, since they're already
+    /// reported when the triggers are loaded.
+    fn new(tref: TriggerRef, trigger: &'a Trigger) -> Option<Candidate<'a>> {
+        let regex = Regex::new(trigger.search_text.as_str()).ok()?;
+        let hir = regex_syntax::parse(trigger.search_text.as_str()).ok()?;
+
+        let mut line = String::new();
+        make_line(&hir, &mut line);
+        let mut literals = vec![String::new()];
+        required_literals(&hir, &mut literals);
+        literals.retain(|l| !l.is_empty());
+
+        Some(Candidate {
+            tref,
+            trigger,
+            line: regex.is_match(line.as_str()).then_some(line),
+            regex,
+            literals,
+        })
+    }
+
+    fn overlap(&self, other: &Candidate) -> Option<Overlap> {
+        if self.trigger.search_text == other.trigger.search_text {
+            return Some(Overlap::Identical);
+        }
+
+        for (candidate, matcher) in [(self, other), (other, self)] {
+            if let Some(ref line) = candidate.line {
+                if matcher.regex.is_match(line.as_str()) {
+                    return Some(Overlap::Line(line.clone()));
+                }
+            }
+        }
+
+        for (candidate, matcher) in [(self, other), (other, self)] {
+            let shared = candidate
+                .literals
+                .iter()
+                .filter(|l| l.chars().count() >= MIN_SHARED_TEXT)
+                .find(|l| matcher.literals.iter().any(|m| m.contains(l.as_str())));
+            if let Some(text) = shared {
+                return Some(Overlap::Text(text.clone()));
+            }
+        }
+
+        None
+    }
+}
+
+/// Finds every pair of the given triggers that look like they'd match the
+/// same lines.
+pub(crate) fn find<'a>(triggers: impl Iterator<Item = (TriggerRef, &'a Trigger)>) -> Vec<Conflict> {
+    let candidates: Vec<Candidate> = triggers
+        .filter_map(|(tref, trigger)| Candidate::new(tref, trigger))
+        .collect();
+
+    let mut conflicts = Vec::new();
+    for (idx, first) in candidates.iter().enumerate() {
+        for second in candidates[idx + 1..].iter() {
+            if let Some(overlap) = first.overlap(second) {
+                conflicts.push(Conflict {
+                    first: first.tref.clone(),
+                    first_name: first.trigger.name.clone(),
+                    second: second.tref.clone(),
+                    second_name: second.trigger.name.clone(),
+                    overlap,
+                });
+            }
+        }
+    }
+
+    conflicts
+}
+
+/// Makes up the shortest line that the pattern matches, taking the first of
+/// any alternatives, and reading characters from classes in the order of
+/// `PREFERRED_CHARS`. Anchors and word boundaries are left to the caller to
+/// check, by matching the line against the pattern.
+fn make_line(hir: &Hir, line: &mut String) {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => {}
+        HirKind::Literal(literal) => line.push_str(String::from_utf8_lossy(&literal.0).as_ref()),
+        HirKind::Class(Class::Unicode(class)) => {
+            let ranges = class.ranges();
+            let preferred = PREFERRED_CHARS
+                .iter()
+                .find(|c| ranges.iter().any(|r| r.start() <= **c && **c <= r.end()));
+            if let Some(c) = preferred
+                .copied()
+                .or_else(|| ranges.first().map(|r| r.start()))
+            {
+                line.push(c);
+            }
+        }
+        HirKind::Class(Class::Bytes(class)) => {
+            let ranges = class.ranges();
+            let preferred = PREFERRED_CHARS.iter().find(|c| {
+                ranges
+                    .iter()
+                    .any(|r| (r.start() as char) <= **c && **c <= (r.end() as char))
+            });
+            if let Some(c) = preferred
+                .copied()
+                .or_else(|| ranges.first().map(|r| r.start() as char))
+            {
+                line.push(c);
+            }
+        }
+        HirKind::Repetition(repetition) => {
+            for _ in 0..repetition.min {
+                make_line(&repetition.sub, line);
+            }
+        }
+        HirKind::Capture(capture) => make_line(&capture.sub, line),
+        HirKind::Concat(subs) => subs.iter().for_each(|sub| make_line(sub, line)),
+        HirKind::Alternation(subs) => {
+            if let Some(sub) = subs.first() {
+                make_line(sub, line);
+            }
+        }
+    }
+}
+
+/// Collects the stretches of literal text that the pattern can't match
+/// without, starting a new one, at the end of `literals`, wherever anything
+/// that isn't literal comes between them.
+fn required_literals(hir: &Hir, literals: &mut Vec<String>) {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => {}
+        HirKind::Literal(literal) => {
+            let text = String::from_utf8_lossy(&literal.0);
+            literals
+                .last_mut()
+                .expect("there's always a stretch to add to")
+                .push_str(text.as_ref());
+        }
+        HirKind::Capture(capture) => required_literals(&capture.sub, literals),
+        HirKind::Concat(subs) => subs.iter().for_each(|sub| required_literals(sub, literals)),
+        HirKind::Class(_) | HirKind::Repetition(_) | HirKind::Alternation(_) => {
+            literals.push(String::new())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use crate::config::triggers::{TriggerId, TriggerSource};
+
+    use super::*;
+
+    #[test]
+    fn finds_triggers_that_match_the_same_lines() {
+        let pack: BTreeMap<TriggerId, Trigger> = toml_edit::de::from_str(
+            r#"
+            [tell]
+            name = "Tell"
+            search_text = '''^(\w+) tells you, '(.+)'$'''
+            actions = []
+
+            [tell-inc]
+            name = "Incoming"
+            search_text = '''^(\w+) tells you, 'inc(?:oming)?!?'$'''
+            actions = []
+
+            [slain]
+            name = "Slain"
+            search_text = '^You have slain (.+)!$'
+            actions = []
+
+            [slain-again]
+            name = "Slain again"
+            search_text = '^You have slain (.+)!$'
+            actions = []
+
+            [rampage]
+            name = "Rampage"
+            search_text = '^(?:An? )?(.+) goes on a RAMPAGE!$'
+            actions = []
+
+            [rampage-guild]
+            name = "Rampage, the guild's"
+            search_text = '^The (?:fire|ice) giant goes on a RAMPAGE!'
+            actions = []
+
+            [quote]
+            name = "Quote"
+            search_text = '''^(\w+) says, 'The dragon goes on a RAMPAGE!'$'''
+            actions = []
+            "#,
+        )
+        .unwrap();
+        let conflicts = find(
+            pack.iter()
+                .map(|(id, trigger)| (TriggerRef::new(TriggerSource::Local, id.clone()), trigger)),
+        );
+
+        let found: Vec<(&str, &str, &Overlap)> = conflicts
+            .iter()
+            .map(|c| (c.first.id.as_str(), c.second.id.as_str(), &c.overlap))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                // The quote isn't a rampage, but it does need the same text.
+                (
+                    "quote",
+                    "rampage",
+                    &Overlap::Text(" goes on a RAMPAGE!".to_string())
+                ),
+                (
+                    "rampage",
+                    "rampage-guild",
+                    &Overlap::Line("The fire giant goes on a RAMPAGE!".to_string())
+                ),
+                ("slain", "slain-again", &Overlap::Identical),
+                (
+                    "tell",
+                    "tell-inc",
+                    &Overlap::Line("a tells you, 'inc'".to_string())
+                ),
+            ]
+        );
+    }
+}
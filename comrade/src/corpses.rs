@@ -0,0 +1,104 @@
+//! Corpse Recovery
+//!
+//! When a character dies, where they died is recorded as their corpse
+//! waypoint, a countdown is started for when the corpse will decay, and a
+//! reminder of where the corpse is stays up until it's acknowledged. Where
+//! they died is only as good as their last `/loc`, so players that want an
+//! exact location should `/loc` often, but the zone is always known once
+//! they've zoned at least once.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+use log::debug;
+use regex::Regex;
+
+use crate::config::corpses::CorpsesConfig;
+use crate::config::locale::LocaleConfig;
+use crate::config::triggers::{Priority, Trigger, TriggerStyle};
+use crate::config::{Character, CharacterId};
+use crate::locations::Locations;
+use crate::triggers::Action;
+use crate::watcher::LogEvent;
+
+/// The name of the waypoint that corpses are recorded as.
+pub(crate) const CORPSE_WAYPOINT: &str = "corpse";
+
+lazy_static! {
+    static ref DEATH_RE: Regex =
+        Regex::new(r"^(?:You have been slain by (?P<killer>.+)!|You died\.)$").unwrap();
+}
+
+/// Whether a log line is one that corpse recovery needs to see.
+pub(crate) fn is_death_line(line: &str) -> bool {
+    DEATH_RE.is_match(line)
+}
+
+/// The trigger that corpse reminders are displayed as, since they're shown
+/// the same way as any other alert that has to be acknowledged.
+fn reminder_trigger(config: &CorpsesConfig, name: String) -> Arc<Trigger> {
+    Arc::new(Trigger {
+        name,
+        comment: String::new(),
+        search_text: DEATH_RE.as_str().to_string(),
+        tags: Vec::new(),
+        disabled: false,
+        priority: Priority::High,
+        style: TriggerStyle::default(),
+        acknowledge: true,
+        repeat: Some(config.remind),
+        captures: BTreeMap::new(),
+        conditions: Vec::new(),
+        active: Vec::new(),
+        actions: Vec::new(),
+    })
+}
+
+/// The actions for the given event if it's the character dying, which also
+/// records where their corpse is.
+pub(crate) fn on_death(
+    config: &CorpsesConfig,
+    locale: &LocaleConfig,
+    character: &Character,
+    event: &LogEvent,
+    locations: &mut Locations,
+) -> Option<Vec<Action>> {
+    if !DEATH_RE.is_match(event.message()) {
+        return None;
+    }
+
+    let id: &CharacterId = &event.id;
+    let zone = locations.zone(id);
+    // A /loc from before the character last zoned says nothing about where
+    // they are now, and neither does the waypoint for an earlier corpse.
+    let waypoint = if locations.latest(id).is_some_and(|p| p.zone == zone) {
+        locations.record(id, CORPSE_WAYPOINT)
+    } else {
+        locations.forget(id, CORPSE_WAYPOINT);
+        None
+    };
+    debug!("{} died, corpse recorded at {:?}", id, waypoint);
+
+    let name = ("name", character.name.as_str());
+    let text = match (waypoint, zone) {
+        (Some(waypoint), _) => {
+            let position = waypoint.position.to_string();
+            locale.text(
+                character,
+                "corpse-at",
+                &[name, ("position", position.as_str())],
+            )
+        }
+        (None, Some(zone)) => locale.text(character, "corpse-in", &[name, ("zone", zone.as_str())]),
+        (None, None) => locale.text(character, "corpse-unknown", &[name]),
+    };
+    let decays = locale.text(character, "corpse-decays", &[name]);
+    let trigger = reminder_trigger(config, locale.text(character, "corpse-recovery", &[]));
+
+    let character = Arc::new(character.clone());
+    Some(vec![
+        Action::countdown(Arc::new(decays), config.decay, character.clone()),
+        Action::display(Arc::new(text), trigger, character),
+    ])
+}
@@ -0,0 +1,286 @@
+//! Currency Tracking
+//!
+//! Coin looted from corpses and split by the group is added up into farming
+//! sessions, so that camps can be compared by how much platinum they make an
+//! hour. A session is a character's time in a single zone, and ends when they
+//! zone or once they've gone long enough without picking up any coin.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use log::debug;
+use parking_lot::Mutex;
+use regex::Regex;
+
+use crate::config::CharacterId;
+use crate::time::{Instant, SystemTime};
+use crate::watcher::LogEvent;
+
+/// How many finished sessions are kept around, the oldest are dropped once
+/// there are more than this.
+const MAX_SESSIONS: usize = 500;
+
+pub(crate) const COPPER_PER_PLATINUM: u64 = 1000;
+
+lazy_static! {
+    // e.g. "You receive 1 platinum, 5 gold and 3 copper from the corpse." or
+    // "You receive 12 platinum as your split."
+    static ref COIN_RE: Regex = Regex::new(
+        r"^You receive (?P<coins>\d+ (?:platinum|gold|silver|copper)(?:(?:, | and )\d+ (?:platinum|gold|silver|copper))*) (?:from the corpse|(?P<split>as your split))(?: .+)?\.$"
+    )
+    .unwrap();
+    static ref COIN_PART_RE: Regex =
+        Regex::new(r"(?P<amount>\d+) (?P<kind>platinum|gold|silver|copper)").unwrap();
+    static ref ZONE_RE: Regex = Regex::new(r"^You have entered (?P<zone>.+)\.$").unwrap();
+}
+
+enum Activity<'a> {
+    Looted(u64),
+    Split(u64),
+    Zoned(&'a str),
+}
+
+/// Adds up coins like "1 platinum, 5 gold and 3 copper" in copper.
+fn parse_coins(coins: &str) -> u64 {
+    COIN_PART_RE
+        .captures_iter(coins)
+        .map(|caps| {
+            let amount: u64 = caps["amount"].parse().unwrap_or(0);
+            let value = match &caps["kind"] {
+                "platinum" => COPPER_PER_PLATINUM,
+                "gold" => 100,
+                "silver" => 10,
+                _ => 1,
+            };
+            amount * value
+        })
+        .sum()
+}
+
+fn parse_activity(line: &str) -> Option<Activity<'_>> {
+    if let Some(caps) = COIN_RE.captures(line) {
+        let copper = parse_coins(&caps["coins"]);
+        if caps.name("split").is_some() {
+            Some(Activity::Split(copper))
+        } else {
+            Some(Activity::Looted(copper))
+        }
+    } else {
+        ZONE_RE
+            .captures(line)
+            .and_then(|caps| caps.name("zone"))
+            .map(|m| Activity::Zoned(m.as_str()))
+    }
+}
+
+/// Whether a log line is one that currency tracking needs to see.
+pub(crate) fn is_currency_line(line: &str) -> bool {
+    parse_activity(line).is_some()
+}
+
+/// The coin that a log line says was received, looted or split, in copper.
+pub(crate) fn coin_received(line: &str) -> Option<u64> {
+    match parse_activity(line)? {
+        Activity::Looted(copper) | Activity::Split(copper) => Some(copper),
+        Activity::Zoned(_) => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EarningsSession {
+    pub character: CharacterId,
+    /// The zone the session was in, if the character was seen zoning into it.
+    pub zone: Option<String>,
+    pub started: SystemTime,
+    /// The time from the start of the session to the last coin picked up.
+    pub duration: Duration,
+    /// The coin looted from corpses, in copper.
+    pub looted: u64,
+    /// The coin from group splits, in copper.
+    pub split: u64,
+}
+
+impl EarningsSession {
+    /// Everything earned during the session, in copper.
+    pub fn total(&self) -> u64 {
+        self.looted + self.split
+    }
+
+    pub fn platinum(&self) -> f64 {
+        self.total() as f64 / COPPER_PER_PLATINUM as f64
+    }
+
+    pub fn platinum_per_hour(&self) -> f64 {
+        per_hour(self.platinum(), self.duration)
+    }
+}
+
+/// The sessions for a single zone added together.
+#[derive(Debug, Clone)]
+pub struct ZoneEarnings {
+    pub zone: Option<String>,
+    pub sessions: usize,
+    pub duration: Duration,
+    /// Everything earned in the zone, in copper.
+    pub total: u64,
+}
+
+impl ZoneEarnings {
+    pub fn platinum(&self) -> f64 {
+        self.total as f64 / COPPER_PER_PLATINUM as f64
+    }
+
+    pub fn platinum_per_hour(&self) -> f64 {
+        per_hour(self.platinum(), self.duration)
+    }
+}
+
+pub(crate) fn per_hour(platinum: f64, duration: Duration) -> f64 {
+    // Anything shorter than a minute would give wildly inflated rates.
+    platinum * 3600.0 / duration.as_secs_f64().max(60.0)
+}
+
+/// Adds up the given sessions by zone, the best earning zone first.
+pub(crate) fn by_zone(sessions: &[EarningsSession]) -> Vec<ZoneEarnings> {
+    let mut zones: BTreeMap<Option<&str>, ZoneEarnings> = BTreeMap::new();
+    for session in sessions.iter() {
+        let zone = zones
+            .entry(session.zone.as_deref())
+            .or_insert_with(|| ZoneEarnings {
+                zone: session.zone.clone(),
+                sessions: 0,
+                duration: Duration::ZERO,
+                total: 0,
+            });
+        zone.sessions += 1;
+        zone.duration += session.duration;
+        zone.total += session.total();
+    }
+
+    let mut zones: Vec<ZoneEarnings> = zones.into_values().collect();
+    zones.sort_by(|a, b| b.platinum_per_hour().total_cmp(&a.platinum_per_hour()));
+    zones
+}
+
+pub(crate) type EarningsLog = Arc<Mutex<VecDeque<EarningsSession>>>;
+
+struct Session {
+    zone: Option<String>,
+    started: SystemTime,
+    first: Instant,
+    last: Instant,
+    looted: u64,
+    split: u64,
+}
+
+impl Session {
+    fn new(zone: Option<String>) -> Session {
+        let now = Instant::now();
+        Session {
+            zone,
+            started: SystemTime::now(),
+            first: now,
+            last: now,
+            looted: 0,
+            split: 0,
+        }
+    }
+
+    fn summarize(self, character: CharacterId) -> EarningsSession {
+        EarningsSession {
+            character,
+            zone: self.zone,
+            started: self.started,
+            duration: self.last - self.first,
+            looted: self.looted,
+            split: self.split,
+        }
+    }
+}
+
+/// Tracks the farming session that each character is in, moving them to the
+/// earnings log once they're over.
+pub(crate) struct Currency {
+    sessions: HashMap<CharacterId, Session>,
+    finished: EarningsLog,
+}
+
+impl Currency {
+    pub(crate) fn new(finished: EarningsLog) -> Currency {
+        Currency {
+            sessions: HashMap::new(),
+            finished,
+        }
+    }
+
+    /// Adds the coin from the given event to the character's session,
+    /// returning the summary of their last session if they've zoned.
+    pub(crate) fn log_event(&mut self, event: &LogEvent) -> Option<EarningsSession> {
+        let id = &*event.id;
+        match parse_activity(event.message())? {
+            Activity::Zoned(zone) => {
+                let previous = self
+                    .sessions
+                    .insert(id.clone(), Session::new(Some(zone.to_string())))?;
+                self.finish(id.clone(), previous)
+            }
+            Activity::Looted(copper) => {
+                self.session(id).looted += copper;
+                None
+            }
+            Activity::Split(copper) => {
+                self.session(id).split += copper;
+                None
+            }
+        }
+    }
+
+    /// The character's current session, which is started if they're not in
+    /// one yet.
+    fn session(&mut self, id: &CharacterId) -> &mut Session {
+        let session = self
+            .sessions
+            .entry(id.clone())
+            .or_insert_with(|| Session::new(None));
+        session.last = Instant::now();
+        session
+    }
+
+    /// Ends every session whose character has gone at least the timeout
+    /// without picking up any coin, returning their summaries.
+    pub(crate) fn expire(&mut self, timeout: Duration) -> Vec<EarningsSession> {
+        let expired: Vec<CharacterId> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.last.elapsed() >= timeout)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        let mut summaries = Vec::new();
+        for id in expired {
+            if let Some(session) = self.sessions.remove(&id) {
+                summaries.extend(self.finish(id, session));
+            }
+        }
+        summaries
+    }
+
+    /// Moves a session to the earnings log, unless nothing was earned during
+    /// it, such as when a character passes through a zone.
+    fn finish(&mut self, id: CharacterId, session: Session) -> Option<EarningsSession> {
+        if session.looted == 0 && session.split == 0 {
+            return None;
+        }
+
+        debug!("farming session ended for {}", id);
+        let summary = session.summarize(id);
+        let mut finished = self.finished.lock();
+        finished.push_back(summary.clone());
+        while finished.len() > MAX_SESSIONS {
+            finished.pop_front();
+        }
+        Some(summary)
+    }
+}
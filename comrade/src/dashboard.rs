@@ -0,0 +1,1101 @@
+//! Remote Dashboard
+//!
+//! A page served over http that shows how a Comrade that's been left running
+//! is doing: whether each character's log is being watched, the timers that
+//! are counting down, and the most recent alerts. It's meant for a phone on
+//! the same network as a trader box, so it's a single page that refreshes
+//! itself, with nothing to install.
+//!
+//! The server only has the one page to serve, so rather than pulling in a
+//! web framework it reads just enough of each request to route it. Each
+//! connection gets a thread of its own, up to a handful at once, and a few
+//! seconds to send its request in, so a client that never finishes one only
+//! holds up itself. Every request has to carry the configured token, as a
+//! bearer token wherever the client can send one. A browser can't, so the
+//! page can also be opened with `?token=`, which trades it for a cookie and
+//! sends the browser straight on to the page without it, so it isn't left
+//! sitting in the address bar or the browser's history.
+//!
+//! Alongside the page is a small control API under `/api/`, when it's been
+//! turned on, that takes a `POST` for each thing it can do:
+//!
+//! - `/api/timers`, with a timer like `6m30s Pick respawn` as the body,
+//!   starts it.
+//! - `/api/acknowledge` acknowledges every alert that's waiting on it.
+//! - `/api/triggers/<source>/<id>/enable`, or `/disable`, turns a trigger on
+//!   or off for every character.
+//!
+//! It only takes a bearer token, never the cookie or `?token=`, so that no
+//! other page that the browser has open can make changes on its behalf.
+
+use std::fmt::Write as _;
+use std::io;
+use std::io::prelude::*;
+use std::io::BufReader;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use log::{debug, warn};
+
+use crate::config::dashboard::{DashboardConfig, TlsConfig};
+use crate::config::triggers::TriggerRef;
+use crate::config::CharacterId;
+use crate::errors::{ComradeError, ConfigError, DashboardError};
+use crate::events::{EventFilter, EventKind};
+use crate::time::Instant;
+use crate::timers::ManualTimer;
+use crate::watcher::WatchStatus;
+use crate::Comrade;
+
+/// How often the page reloads itself, in seconds.
+const REFRESH: u64 = 5;
+
+/// How long a client gets to send its whole request, or to take each part
+/// of the response.
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The most connections that are answered at once, any more are closed
+/// without an answer.
+const MAX_CONNECTIONS: usize = 8;
+
+/// The cookie that the token is kept in, once the page has been opened with
+/// it in the query string.
+const COOKIE: &str = "comrade-token";
+
+/// The most of a request that's read, anything longer isn't one of ours.
+const MAX_REQUEST: u64 = 8 * 1024;
+
+/// How many of the most recent alerts are shown.
+const ALERTS: usize = 20;
+
+/// How connections are spoken to, once they've been accepted.
+#[derive(Clone)]
+pub(crate) enum Transport {
+    Plain,
+    #[cfg(feature = "tls")]
+    Tls(Arc<rustls::ServerConfig>),
+}
+
+impl Transport {
+    /// Plain http, unless the configuration has a certificate and key to
+    /// serve https with, relative to the given configuration directory.
+    pub(crate) fn new(
+        config: &DashboardConfig,
+        config_dir: &Path,
+    ) -> Result<Transport, DashboardError> {
+        match config.tls {
+            Some(ref tls) => tls_transport(tls, config_dir),
+            None => Ok(Transport::Plain),
+        }
+    }
+}
+
+#[cfg(feature = "tls")]
+fn tls_transport(tls: &TlsConfig, config_dir: &Path) -> Result<Transport, DashboardError> {
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+    let file_error = |filename: &Path| {
+        let filename = filename.to_path_buf();
+        move |e: rustls::pki_types::pem::Error| DashboardError::TlsFileError {
+            source: io::Error::other(e.to_string()),
+            filename,
+        }
+    };
+    let certificate = config_dir.join(tls.certificate.as_path());
+    let key = config_dir.join(tls.key.as_path());
+    let certificates = CertificateDer::pem_file_iter(certificate.as_path())
+        .and_then(|certificates| certificates.collect::<Result<Vec<_>, _>>())
+        .map_err(file_error(certificate.as_path()))?;
+    let key = PrivateKeyDer::from_pem_file(key.as_path()).map_err(file_error(key.as_path()))?;
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let config = rustls::ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .with_no_client_auth()
+        .with_single_cert(certificates, key)?;
+
+    Ok(Transport::Tls(Arc::new(config)))
+}
+
+#[cfg(not(feature = "tls"))]
+fn tls_transport(_: &TlsConfig, _: &Path) -> Result<Transport, DashboardError> {
+    Err(DashboardError::TlsUnsupported)
+}
+
+/// The dashboard while it's being served, which stops once this is dropped.
+pub(crate) struct Server {
+    address: SocketAddr,
+    stopping: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        self.stopping.store(true, Ordering::Release);
+
+        // The listener only looks up from waiting on a connection when it
+        // gets one, so it's given one.
+        let ip = match self.address.ip() {
+            IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            ip => ip,
+        };
+        if let Err(e) =
+            TcpStream::connect_timeout(&SocketAddr::new(ip, self.address.port()), TIMEOUT)
+        {
+            warn!("could not wake the dashboard to stop it: {}", e);
+            return;
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Starts listening on the given address, serving the dashboard on a thread
+/// of its own until it's stopped, or Comrade has gone away.
+pub(crate) fn serve(
+    comrade: Weak<Comrade>,
+    address: &str,
+    transport: Transport,
+) -> Result<Server, DashboardError> {
+    let bind_error = |source| DashboardError::BindError {
+        source,
+        address: address.to_string(),
+    };
+    let listener = TcpListener::bind(address).map_err(bind_error)?;
+    let local = listener.local_addr().map_err(bind_error)?;
+
+    let stopping = Arc::new(AtomicBool::new(false));
+    let stop = stopping.clone();
+    let thread = thread::Builder::new()
+        .name("comrade dashboard".to_string())
+        .spawn(move || {
+            let connections = Arc::new(AtomicUsize::new(0));
+            for stream in listener.incoming() {
+                if stop.load(Ordering::Acquire) || comrade.strong_count() == 0 {
+                    break;
+                }
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        warn!("could not accept dashboard connection: {}", e);
+                        continue;
+                    }
+                };
+                if connections.fetch_add(1, Ordering::AcqRel) >= MAX_CONNECTIONS {
+                    connections.fetch_sub(1, Ordering::AcqRel);
+                    debug!("too many dashboard connections, closing one");
+                    continue;
+                }
+
+                let comrade = comrade.clone();
+                let transport = transport.clone();
+                let open = connections.clone();
+                let spawned = thread::Builder::new()
+                    .name("comrade dashboard connection".to_string())
+                    .spawn(move || {
+                        if let Some(comrade) = comrade.upgrade() {
+                            if let Err(e) = handle(stream, &transport, &comrade) {
+                                debug!("could not answer dashboard request: {}", e);
+                            }
+                        }
+                        open.fetch_sub(1, Ordering::AcqRel);
+                    });
+                if let Err(e) = spawned {
+                    warn!("could not answer dashboard connection: {}", e);
+                    connections.fetch_sub(1, Ordering::AcqRel);
+                }
+            }
+        })
+        .map_err(bind_error)?;
+
+    Ok(Server {
+        address: local,
+        stopping,
+        thread: Some(thread),
+    })
+}
+
+/// Reads from a connection until a deadline, however it's sent, so that a
+/// client can't keep a connection open by sending its request a byte at a
+/// time.
+struct Deadline {
+    stream: TcpStream,
+    until: Instant,
+}
+
+impl Read for Deadline {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let left = self.until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        self.stream.read(buf)
+    }
+}
+
+impl Write for Deadline {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+fn handle(stream: TcpStream, transport: &Transport, comrade: &Comrade) -> io::Result<()> {
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut connection = Deadline {
+        stream,
+        until: Instant::now() + TIMEOUT,
+    };
+
+    match transport {
+        Transport::Plain => exchange(&mut connection, comrade),
+        #[cfg(feature = "tls")]
+        Transport::Tls(config) => {
+            let tls = rustls::ServerConnection::new(config.clone()).map_err(io::Error::other)?;
+            let mut stream = rustls::StreamOwned::new(tls, connection);
+            exchange(&mut stream, comrade)?;
+            stream.conn.send_close_notify();
+            stream.flush()
+        }
+    }
+}
+
+/// Reads a request from the connection and writes back the response to it.
+fn exchange(connection: &mut (impl Read + Write), comrade: &Comrade) -> io::Result<()> {
+    let mut reader = BufReader::new((&mut *connection).take(MAX_REQUEST));
+    let request = read_request(&mut reader)?;
+    drop(reader);
+
+    let config = comrade.config().dashboard.clone();
+    let response = respond(
+        &config,
+        request.as_ref(),
+        || render(comrade),
+        |control| carry_out(comrade, control),
+    );
+    let head_only = request.is_some_and(|r| r.method == "HEAD");
+
+    write_response(connection, &response, head_only)
+}
+
+/// As much of a request as the dashboard cares about.
+#[derive(Debug, PartialEq, Eq)]
+struct Request {
+    method: String,
+    path: String,
+    /// The token from the `Authorization` header.
+    bearer: Option<String>,
+    /// The token from the cookie that the page sets.
+    cookie: Option<String>,
+    /// The token from the query string, which is only for opening the page
+    /// from a link or a bookmark.
+    query_token: Option<String>,
+    body: String,
+}
+
+/// Reads a request, returning None if it isn't one.
+fn read_request(reader: &mut impl BufRead) -> io::Result<Option<Request>> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let mut parts = line.split_whitespace();
+    let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/") => {
+            (method.to_string(), target.to_string())
+        }
+        _ => return Ok(None),
+    };
+
+    let (path, query) = target.split_once('?').unwrap_or((target.as_str(), ""));
+    let query_token = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == "token")
+        .map(|(_, value)| percent_decode(value));
+
+    let (mut bearer, mut cookie, mut length) = (None, None, 0);
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        let (name, value) = match line.split_once(':') {
+            Some((name, value)) => (name.trim(), value.trim()),
+            None => continue,
+        };
+        if name.eq_ignore_ascii_case("authorization") {
+            if let Some(token) = value.strip_prefix("Bearer ") {
+                bearer = Some(token.trim().to_string());
+            }
+        } else if name.eq_ignore_ascii_case("cookie") {
+            cookie = cookie.or_else(|| {
+                value
+                    .split(';')
+                    .filter_map(|pair| pair.trim().split_once('='))
+                    .find(|(key, _)| *key == COOKIE)
+                    .map(|(_, value)| percent_decode(value))
+            });
+        } else if name.eq_ignore_ascii_case("content-length") {
+            length = match value.parse::<u64>() {
+                Ok(length) if length <= MAX_REQUEST => length,
+                _ => return Ok(None),
+            };
+        }
+    }
+
+    let mut body = Vec::new();
+    reader.take(length).read_to_end(&mut body)?;
+    if body.len() as u64 != length {
+        return Ok(None);
+    }
+
+    Ok(Some(Request {
+        method,
+        path: path.to_string(),
+        bearer,
+        cookie,
+        query_token,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    }))
+}
+
+/// Decodes `%XX` escapes, and `+` as a space, leaving anything that isn't a
+/// valid escape as it is.
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' if i + 2 < bytes.len() => {
+                let hex = |b: u8| (b as char).to_digit(16);
+                match (hex(bytes[i + 1]), hex(bytes[i + 2])) {
+                    (Some(high), Some(low)) => {
+                        decoded.push((high * 16 + low) as u8);
+                        i += 3;
+                        continue;
+                    }
+                    _ => decoded.push(b'%'),
+                }
+            }
+            b'+' => decoded.push(b' '),
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Encodes everything but letters, digits and `-._~` as `%XX` escapes.
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            byte => {
+                let _ = write!(encoded, "%{:02X}", byte);
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Compares tokens without giving away, by how long it took, how much of
+/// one was right.
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    reason: &'static str,
+    content_type: &'static str,
+    /// Any headers besides the ones that every response has.
+    headers: Vec<(&'static str, String)>,
+    body: String,
+}
+
+impl Response {
+    fn text(status: u16, reason: &'static str, body: &str) -> Response {
+        Response {
+            status,
+            reason,
+            content_type: "text/plain; charset=utf-8",
+            headers: Vec::new(),
+            body: format!("{}\n", body),
+        }
+    }
+}
+
+/// Works out the response to a request, rendering the page with `page` only
+/// once the request has been let in.
+fn respond(
+    config: &DashboardConfig,
+    request: Option<&Request>,
+    page: impl FnOnce() -> String,
+    control: impl FnOnce(Control) -> Result<(), ComradeError>,
+) -> Response {
+    let request = match request {
+        Some(request) => request,
+        None => return Response::text(400, "Bad Request", "bad request"),
+    };
+    let expected = match config.token {
+        Some(ref token) if config.enabled => token,
+        _ => return Response::text(403, "Forbidden", "the dashboard is turned off"),
+    };
+    if let Some(path) = request.path.strip_prefix("/api/") {
+        return respond_control(config, request, expected, path, control);
+    }
+
+    // A token in the query string is only looked at when there isn't one
+    // that was sent properly.
+    let token = request.bearer.as_ref().or(request.cookie.as_ref());
+    let (token, from_query) = match (token, &request.query_token) {
+        (Some(token), _) => (token, false),
+        (None, Some(token)) => (token, true),
+        (None, None) => return Response::text(401, "Unauthorized", "a valid token is needed"),
+    };
+    if !tokens_match(token, expected) {
+        return Response::text(401, "Unauthorized", "a valid token is needed");
+    }
+    if request.method != "GET" && request.method != "HEAD" {
+        return Response::text(405, "Method Not Allowed", "method not allowed");
+    }
+    if request.path != "/" {
+        return Response::text(404, "Not Found", "not found");
+    }
+
+    if from_query {
+        let mut response = Response::text(303, "See Other", "see /");
+        response.headers = vec![
+            ("Location", request.path.clone()),
+            (
+                "Set-Cookie",
+                format!(
+                    "{}={}; Path=/; HttpOnly; SameSite=Strict",
+                    COOKIE,
+                    percent_encode(token)
+                ),
+            ),
+        ];
+        return response;
+    }
+
+    Response {
+        status: 200,
+        reason: "OK",
+        content_type: "text/html; charset=utf-8",
+        headers: Vec::new(),
+        body: page(),
+    }
+}
+
+/// What a request to the control API asks for.
+#[derive(Debug, PartialEq, Eq)]
+enum Control {
+    StartTimer(ManualTimer),
+    Acknowledge,
+    SetTriggerEnabled(TriggerRef, bool),
+}
+
+fn respond_control(
+    config: &DashboardConfig,
+    request: &Request,
+    expected: &str,
+    path: &str,
+    control: impl FnOnce(Control) -> Result<(), ComradeError>,
+) -> Response {
+    if !request
+        .bearer
+        .as_deref()
+        .is_some_and(|t| tokens_match(t, expected))
+    {
+        return Response::text(401, "Unauthorized", "a valid bearer token is needed");
+    }
+    if !config.control {
+        return Response::text(403, "Forbidden", "the control API is turned off");
+    }
+    if request.method != "POST" {
+        return Response::text(405, "Method Not Allowed", "method not allowed");
+    }
+
+    let parsed = match path.split_once('/') {
+        None if path == "timers" => request
+            .body
+            .parse()
+            .map(Control::StartTimer)
+            .map_err(|e| e.to_string()),
+        None if path == "acknowledge" => Ok(Control::Acknowledge),
+        Some(("triggers", rest)) => match rest.rsplit_once('/') {
+            Some((tref, "enable")) => percent_decode(tref)
+                .parse()
+                .map(|tref| Control::SetTriggerEnabled(tref, true))
+                .map_err(|e| e.to_string()),
+            Some((tref, "disable")) => percent_decode(tref)
+                .parse()
+                .map(|tref| Control::SetTriggerEnabled(tref, false))
+                .map_err(|e| e.to_string()),
+            _ => return Response::text(404, "Not Found", "not found"),
+        },
+        _ => return Response::text(404, "Not Found", "not found"),
+    };
+    let parsed = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => return Response::text(400, "Bad Request", e.as_str()),
+    };
+
+    match control(parsed) {
+        Ok(()) => Response {
+            status: 204,
+            reason: "No Content",
+            content_type: "text/plain; charset=utf-8",
+            headers: Vec::new(),
+            body: String::new(),
+        },
+        Err(e @ ComradeError::ConfigError(ConfigError::UnknownTrigger { .. })) => {
+            Response::text(404, "Not Found", e.to_string().as_str())
+        }
+        Err(e) => {
+            warn!("could not carry out dashboard request: {}", e);
+            Response::text(500, "Internal Server Error", e.to_string().as_str())
+        }
+    }
+}
+
+fn carry_out(comrade: &Comrade, control: Control) -> Result<(), ComradeError> {
+    match control {
+        Control::StartTimer(timer) => comrade.start_timer(timer),
+        Control::Acknowledge => comrade.acknowledge_all(),
+        Control::SetTriggerEnabled(tref, enabled) => {
+            return comrade.set_trigger_enabled(&tref, &[], enabled)
+        }
+    }
+
+    Ok(())
+}
+
+fn write_response(mut writer: impl Write, response: &Response, head_only: bool) -> io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nReferrer-Policy: no-referrer\r\nConnection: close\r\n",
+        response.status,
+        response.reason,
+        response.content_type,
+        response.body.len()
+    );
+    for (name, value) in response.headers.iter() {
+        let _ = write!(head, "{}: {}\r\n", name, value);
+    }
+    head.push_str("\r\n");
+    writer.write_all(head.as_bytes())?;
+    if !head_only {
+        writer.write_all(response.body.as_bytes())?;
+    }
+
+    writer.flush()
+}
+
+/// Escapes text for including in html.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+/// Writes a duration out like a timer, e.g. "6:30" or "1:02:03".
+fn format_clock(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds / 3600 {
+        0 => format!("{}:{:02}", seconds / 60, seconds % 60),
+        hours => format!("{}:{:02}:{:02}", hours, seconds % 3600 / 60, seconds % 60),
+    }
+}
+
+#[cfg(feature = "watcher")]
+fn watch_status(comrade: &Comrade) -> Vec<(CharacterId, WatchStatus)> {
+    comrade.watch_status()
+}
+
+/// Without the watcher, lines are handed to Comrade by whatever embeds it,
+/// so every character that isn't paused is as good as watched.
+#[cfg(not(feature = "watcher"))]
+fn watch_status(comrade: &Comrade) -> Vec<(CharacterId, WatchStatus)> {
+    comrade
+        .characters()
+        .into_iter()
+        .map(|(id, _)| {
+            let status = if comrade.is_paused(&id) {
+                WatchStatus::Paused
+            } else {
+                WatchStatus::Watching
+            };
+            (id, status)
+        })
+        .collect()
+}
+
+fn render(comrade: &Comrade) -> String {
+    let mut page = String::new();
+    let _ = write!(
+        page,
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\">\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+         <meta http-equiv=\"refresh\" content=\"{}\"><title>Comrade</title>\
+         <style>body{{font-family:sans-serif;margin:1em;background:#111;color:#ddd}}\
+         table{{border-collapse:collapse;width:100%}}td,th{{text-align:left;padding:.25em .5em;border-bottom:1px solid #333}}\
+         .bad{{color:#e66}}.good{{color:#6c6}}.muted{{color:#888}}</style></head><body>\n<h1>Comrade</h1>\n",
+        REFRESH
+    );
+
+    let mut status = Vec::new();
+    if let Some(profile) = comrade.profile() {
+        status.push(format!("profile {}", escape(profile.as_str())));
+    }
+    if let Some(group) = comrade.active_group() {
+        status.push(format!("group {}", escape(group.as_str())));
+    }
+    let latency = comrade.latency();
+    if latency.lagging() {
+        status.push(format!(
+            "<span class=\"bad\">running {:.1}s behind the logs</span>",
+            latency.worst.as_secs_f64()
+        ));
+    }
+    if !status.is_empty() {
+        let _ = writeln!(page, "<p>{}</p>", status.join(" &middot; "));
+    }
+
+    let _ = writeln!(
+        page,
+        "<h2>Characters</h2>\n<table><tr><th>Character</th><th>Log</th></tr>"
+    );
+    let names = comrade.characters();
+    for (id, status) in watch_status(comrade) {
+        let name = names
+            .iter()
+            .find(|(i, _)| *i == id)
+            .map_or(id.as_str(), |(_, c)| c.display_name());
+        let class = if status == WatchStatus::Watching {
+            "good"
+        } else {
+            "bad"
+        };
+        let _ = writeln!(
+            page,
+            "<tr><td>{}</td><td class=\"{}\">{}</td></tr>",
+            escape(name),
+            class,
+            status
+        );
+    }
+    let _ = writeln!(page, "</table>");
+
+    let mut timers: Vec<(Duration, String, Option<String>)> = comrade
+        .recent_events(&EventFilter {
+            kinds: vec!["Countdown".to_string()],
+            ..EventFilter::default()
+        })
+        .into_iter()
+        .filter_map(|event| match event.kind() {
+            EventKind::Countdown {
+                text,
+                remaining,
+                character,
+                ..
+            } => {
+                let left = remaining.saturating_sub(event.created().elapsed());
+                let character = character.as_ref().map(|c| c.display_name().to_string());
+                (!left.is_zero()).then(|| (left, text.to_string(), character))
+            }
+            _ => None,
+        })
+        .collect();
+    timers.sort_by_key(|(left, _, _)| *left);
+    let _ = writeln!(page, "<h2>Timers</h2>");
+    if timers.is_empty() {
+        let _ = writeln!(page, "<p class=\"muted\">No timers running.</p>");
+    } else {
+        let _ = writeln!(page, "<table>");
+        for (left, text, character) in timers {
+            let _ = writeln!(
+                page,
+                "<tr><td>{}</td><td class=\"muted\">{}</td><td>{}</td></tr>",
+                escape(text.as_str()),
+                escape(character.as_deref().unwrap_or_default()),
+                format_clock(left)
+            );
+        }
+        let _ = writeln!(page, "</table>");
+    }
+
+    let alerts = comrade.recent_events(&EventFilter {
+        kinds: vec!["DisplayText".to_string()],
+        limit: Some(ALERTS),
+        ..EventFilter::default()
+    });
+    let _ = writeln!(page, "<h2>Recent alerts</h2>");
+    if alerts.is_empty() {
+        let _ = writeln!(page, "<p class=\"muted\">No alerts yet.</p>");
+    } else {
+        let _ = writeln!(page, "<table>");
+        for event in alerts.iter().rev() {
+            if let EventKind::DisplayText {
+                text, character, ..
+            } = event.kind()
+            {
+                let _ = writeln!(
+                    page,
+                    "<tr><td class=\"muted\">{} ago</td><td class=\"muted\">{}</td><td>{}</td></tr>",
+                    format_clock(event.created().elapsed()),
+                    escape(character.as_ref().map_or("", |c| c.display_name())),
+                    escape(text.as_str())
+                );
+            }
+        }
+        let _ = writeln!(page, "</table>");
+    }
+
+    let _ = writeln!(page, "</body></html>");
+    page
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn request(raw: &str) -> Option<Request> {
+        read_request(&mut Cursor::new(raw.as_bytes())).unwrap()
+    }
+
+    fn config() -> DashboardConfig {
+        DashboardConfig {
+            enabled: true,
+            token: Some("s3cret token".to_string()),
+            ..DashboardConfig::default()
+        }
+    }
+
+    /// The response to a request for the page.
+    fn page(config: &DashboardConfig, raw: &str) -> Response {
+        respond(
+            config,
+            request(raw).as_ref(),
+            || "page".to_string(),
+            |control| panic!("unexpected control: {:?}", control),
+        )
+    }
+
+    #[test]
+    fn reads_the_token_from_the_query() {
+        assert_eq!(
+            request("GET /?token=s3cret%20token HTTP/1.1\r\nHost: box\r\n\r\n"),
+            Some(Request {
+                method: "GET".to_string(),
+                path: "/".to_string(),
+                bearer: None,
+                cookie: None,
+                query_token: Some("s3cret token".to_string()),
+                body: String::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn trades_a_token_in_the_query_for_a_cookie() {
+        let response = page(&config(), "GET /?token=s3cret%20token HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status, 303);
+        assert_eq!(
+            response.headers,
+            vec![
+                ("Location", "/".to_string()),
+                (
+                    "Set-Cookie",
+                    "comrade-token=s3cret%20token; Path=/; HttpOnly; SameSite=Strict".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn lets_in_requests_with_the_token_in_a_cookie() {
+        let response = page(
+            &config(),
+            "GET / HTTP/1.1\r\nCookie: theme=dark; comrade-token=s3cret%20token\r\n\r\n",
+        );
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "page");
+    }
+
+    #[test]
+    fn lets_in_requests_with_the_token_as_a_bearer() {
+        let response = page(
+            &config(),
+            "GET / HTTP/1.1\r\nAuthorization: Bearer s3cret token\r\n\r\n",
+        );
+        assert_eq!(response.status, 200);
+    }
+
+    #[test]
+    fn turns_away_the_wrong_token() {
+        let config = config();
+        assert_eq!(
+            page(&config, "GET /?token=guess HTTP/1.1\r\n\r\n").status,
+            401
+        );
+        assert_eq!(
+            page(
+                &config,
+                "GET / HTTP/1.1\r\nCookie: comrade-token=guess\r\n\r\n"
+            )
+            .status,
+            401
+        );
+        assert_eq!(
+            page(
+                &config,
+                "GET / HTTP/1.1\r\nAuthorization: Bearer guess\r\n\r\n"
+            )
+            .status,
+            401
+        );
+    }
+
+    #[test]
+    fn turns_away_requests_without_a_token() {
+        assert_eq!(page(&config(), "GET / HTTP/1.1\r\n\r\n").status, 401);
+    }
+
+    #[test]
+    fn only_has_the_one_page() {
+        let response = page(&config(), "GET /admin?token=s3cret+token HTTP/1.1\r\n\r\n");
+        assert_eq!(response.status, 404);
+    }
+
+    #[test]
+    fn malformed_requests_are_rejected() {
+        let config = config();
+        for raw in ["", "nonsense", "GET /\r\n\r\n", "GET / SMTP\r\n\r\n"] {
+            assert_eq!(request(raw), None, "{:?}", raw);
+            assert_eq!(page(&config, raw).status, 400, "{:?}", raw);
+        }
+    }
+
+    #[test]
+    fn nobody_gets_in_without_a_configured_token() {
+        let config = DashboardConfig {
+            token: None,
+            ..config()
+        };
+        assert_eq!(page(&config, "GET /?token= HTTP/1.1\r\n\r\n").status, 403);
+    }
+
+    #[test]
+    fn nobody_gets_in_while_turned_off() {
+        let config = DashboardConfig {
+            enabled: false,
+            ..config()
+        };
+        let raw = "GET / HTTP/1.1\r\nAuthorization: Bearer s3cret token\r\n\r\n";
+        assert_eq!(page(&config, raw).status, 403);
+    }
+
+    #[test]
+    fn escapes_html() {
+        assert_eq!(
+            escape("<b>\"Fire\" & 'Ice'</b>"),
+            "&lt;b&gt;&quot;Fire&quot; &amp; &#39;Ice&#39;&lt;/b&gt;"
+        );
+    }
+
+    #[test]
+    fn formats_how_long_is_left() {
+        assert_eq!(format_clock(Duration::from_secs(125)), "2:05");
+        assert_eq!(format_clock(Duration::from_secs(3723)), "1:02:03");
+    }
+
+    #[test]
+    fn stops_listening_once_dropped() {
+        let comrade = Arc::new(Comrade::new());
+        let server = serve(Arc::downgrade(&comrade), "127.0.0.1:0", Transport::Plain).unwrap();
+        let address = server.address;
+        assert!(TcpStream::connect(address).is_ok());
+
+        drop(server);
+        assert!(TcpStream::connect(address).is_err());
+    }
+
+    fn control_config() -> DashboardConfig {
+        DashboardConfig {
+            enabled: true,
+            token: Some("s3cret".to_string()),
+            control: true,
+            ..DashboardConfig::default()
+        }
+    }
+
+    /// The response to a control request, along with what it asked for.
+    fn control(config: &DashboardConfig, raw: &str) -> (u16, Option<Control>) {
+        let mut asked = None;
+        let response = respond(config, request(raw).as_ref(), String::new, |control| {
+            asked = Some(control);
+            Ok(())
+        });
+        (response.status, asked)
+    }
+
+    #[test]
+    fn control_api_only_takes_a_bearer_token() {
+        let config = control_config();
+        let post = |auth: &str| {
+            control(
+                &config,
+                format!(
+                    "POST /api/acknowledge?token=s3cret HTTP/1.1\r\n{}\r\n",
+                    auth
+                )
+                .as_str(),
+            )
+        };
+
+        assert_eq!(
+            post("Authorization: Bearer s3cret\r\n"),
+            (204, Some(Control::Acknowledge))
+        );
+        assert_eq!(post("Cookie: comrade-token=s3cret\r\n"), (401, None));
+        assert_eq!(post(""), (401, None));
+        assert_eq!(post("Authorization: Bearer guess\r\n"), (401, None));
+    }
+
+    #[test]
+    fn control_api_is_off_unless_turned_on() {
+        let config = DashboardConfig {
+            control: false,
+            ..control_config()
+        };
+        let raw = "POST /api/acknowledge HTTP/1.1\r\nAuthorization: Bearer s3cret\r\n\r\n";
+        assert_eq!(control(&config, raw), (403, None));
+    }
+
+    #[test]
+    fn control_api_reads_what_is_asked_for() {
+        let config = control_config();
+        let post = |path: &str, body: &str| {
+            control(
+                &config,
+                format!(
+                    "POST {} HTTP/1.1\r\nAuthorization: Bearer s3cret\r\nContent-Length: {}\r\n\r\n{}",
+                    path,
+                    body.len(),
+                    body
+                )
+                .as_str(),
+            )
+        };
+
+        assert_eq!(
+            post("/api/timers", "6m30s Pick respawn"),
+            (
+                204,
+                Some(Control::StartTimer("6m30s Pick respawn".parse().unwrap()))
+            )
+        );
+        assert_eq!(
+            post("/api/triggers/local/gaze/disable", ""),
+            (
+                204,
+                Some(Control::SetTriggerEnabled(
+                    "local/gaze".parse().unwrap(),
+                    false
+                ))
+            )
+        );
+        assert_eq!(post("/api/timers", "soon Pick respawn"), (400, None));
+        assert_eq!(post("/api/triggers/local/gaze/toggle", ""), (404, None));
+        assert_eq!(post("/api/reload", ""), (404, None));
+
+        let get = "GET /api/acknowledge HTTP/1.1\r\nAuthorization: Bearer s3cret\r\n\r\n";
+        assert_eq!(control(&config, get), (405, None));
+    }
+
+    #[test]
+    fn control_api_reports_unknown_triggers() {
+        let config = control_config();
+        let raw =
+            "POST /api/triggers/local/nope/enable HTTP/1.1\r\nAuthorization: Bearer s3cret\r\n\r\n";
+        let response = respond(
+            &config,
+            request(raw).as_ref(),
+            String::new,
+            |control| match control {
+                Control::SetTriggerEnabled(tref, _) => {
+                    Err(ConfigError::UnknownTrigger { tref }.into())
+                }
+                control => panic!("unexpected control: {:?}", control),
+            },
+        );
+        assert_eq!(response.status, 404);
+    }
+
+    #[test]
+    fn requests_with_a_short_body_are_rejected() {
+        assert_eq!(
+            request("POST /api/timers HTTP/1.1\r\nContent-Length: 20\r\n\r\n6m Pick"),
+            None
+        );
+        assert_eq!(
+            request("POST /api/timers HTTP/1.1\r\nContent-Length: lots\r\n\r\n"),
+            None
+        );
+    }
+
+    #[test]
+    fn tls_needs_a_certificate_it_can_read() {
+        let config = DashboardConfig {
+            tls: Some(TlsConfig {
+                certificate: "missing.crt".into(),
+                key: "missing.key".into(),
+            }),
+            ..DashboardConfig::default()
+        };
+        let transport = Transport::new(&config, Path::new("/nonexistent"));
+        #[cfg(feature = "tls")]
+        assert!(matches!(
+            transport,
+            Err(DashboardError::TlsFileError { ref filename, .. })
+                if filename.ends_with("missing.crt")
+        ));
+        #[cfg(not(feature = "tls"))]
+        assert!(matches!(transport, Err(DashboardError::TlsUnsupported)));
+    }
+}
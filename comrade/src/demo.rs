@@ -0,0 +1,124 @@
+//! Demo Mode
+//!
+//! So that Comrade can be tried out without the game running, demo mode swaps
+//! the configuration for one with a single made up character and a handful of
+//! triggers, then makes up a log for that character: tells, a raid emote, and
+//! a fight. Its lines are handed to Comrade like any other, so they go through
+//! everything that real lines do, from the triggers on through to whatever
+//! the frontend does with the events.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
+
+use crate::config::triggers::TriggerSet;
+use crate::config::{Character, CharacterId, ClockOffset, Config};
+use crate::history::LogTime;
+
+const DEMO_PACK: &str = include_str!("demo.toml");
+
+/// The id of the made up character.
+pub const DEMO_CHARACTER: &str = "demo";
+
+/// What the made up character says, over and over, with how long to wait
+/// before each line.
+pub(crate) const SCRIPT: &[(u64, &str)] = &[
+    (1, "You feel the spirit of wolf enter you."),
+    (2, "Xanthe tells you, 'Pulling in 10, get ready.'"),
+    (4, "You slash a fire drake for 312 points of damage."),
+    (1, "Xanthe hits a fire drake for 254 points of damage."),
+    (1, "A fire drake hits YOU for 150 points of damage."),
+    (1, "You slash a fire drake for 298 points of damage."),
+    (
+        1,
+        "A fire drake draws a deep breath, flames flicker between its teeth.",
+    ),
+    (2, "Xanthe hits a fire drake for 311 points of damage."),
+    (1, "A fire drake has become ENRAGED."),
+    (2, "You slash a fire drake for 305 points of damage."),
+    (1, "You have slain a fire drake!"),
+    (4, "Xanthe tells you, 'Nice, on to the next one.'"),
+];
+
+/// The configuration for demo mode, which doesn't touch anything on disk.
+pub(crate) fn config() -> Config {
+    let mut config = Config {
+        demo: true,
+        ..Config::default()
+    };
+    config.combat.enabled = true;
+    config.characters.insert(
+        CharacterId::new(DEMO_CHARACTER),
+        Character {
+            name: "Soandso".to_string(),
+            server: "demo".to_string(),
+            filename: PathBuf::new(),
+            aliases: Vec::new(),
+            display_name: None,
+            clock_offset: ClockOffset::default(),
+            language: None,
+            voice: None,
+            disabled_triggers: HashMap::new(),
+            enabled_triggers: HashMap::new(),
+        },
+    );
+
+    config
+}
+
+pub(crate) fn pack() -> TriggerSet {
+    toml_edit::de::from_str(DEMO_PACK).expect("demo pack should be valid")
+}
+
+/// The made up log, which blocks until each line is due, so that it can just
+/// be iterated over on a thread of its own. Lines are stamped with the time
+/// they're made up at, in UTC, since that's the only clock we know.
+#[derive(Debug, Default)]
+pub struct DemoLog {
+    step: usize,
+}
+
+impl DemoLog {
+    pub fn new() -> DemoLog {
+        DemoLog::default()
+    }
+}
+
+impl Iterator for DemoLog {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        let (wait, message) = SCRIPT[self.step % SCRIPT.len()];
+        self.step += 1;
+
+        thread::sleep(Duration::from_secs(wait));
+        Some(format!("[{}] {}", LogTime::now().to_timestamp(), message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use regex::Regex;
+
+    use crate::config::triggers::PatternCache;
+
+    use super::*;
+
+    #[test]
+    fn every_demo_trigger_fires() {
+        let mut config = config();
+        config.load_triggers(&PatternCache::default(), |_, _, _| {});
+        let pack = pack();
+        assert!(config.triggers.error(&pack.meta.source).is_none());
+
+        for (id, trigger) in pack.triggers.iter() {
+            let re = Regex::new(trigger.search_text.as_str()).unwrap();
+            assert!(
+                SCRIPT.iter().any(|(_, line)| re.is_match(line)),
+                "{} never fires",
+                id
+            );
+        }
+    }
+}
@@ -0,0 +1,458 @@
+//! Session Digests
+//!
+//! While Comrade runs, what each character got up to is tallied from their
+//! logs: which triggers fired and how often, the coin and items they looted,
+//! the experience they gained, and how often they died. Events that had to
+//! be dropped because nothing was keeping up with them are counted too, since
+//! an unattended Comrade is exactly the one where nobody would notice.
+//!
+//! When Comrade stops, the tally becomes the session's digest, which is
+//! written to the data directory and mailed, whichever the configuration
+//! asks for, so that a trader or camper left running overnight has a summary
+//! waiting in the morning. Mail is handed to `sendmail` on a thread of its
+//! own, so that stopping never waits on it, and a `sendmail` that hangs is
+//! killed once it's had long enough.
+//!
+//! The game doesn't say how much experience a kill was worth, only that some
+//! was gained, unless it's a client that also logs the percentage, so the
+//! digest counts gains and adds up the percentages when there are any.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use log::{info, warn};
+use parking_lot::Mutex;
+use regex::Regex;
+
+use crate::config::digest::DigestConfig;
+use crate::config::CharacterId;
+use crate::corpses;
+use crate::currency::{self, COPPER_PER_PLATINUM};
+use crate::history::LogTime;
+use crate::time::Instant;
+use crate::watcher::LogEvent;
+
+const DIGESTS_DIRNAME: &str = "digests";
+
+/// How long `sendmail` gets to take the digest before it's killed.
+const MAIL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often a `sendmail` that's still running is checked on.
+const MAIL_POLL: Duration = Duration::from_millis(50);
+
+lazy_static! {
+    // e.g. "You gain party experience!!" or "You gain experience! (1.25%)"
+    static ref EXPERIENCE_RE: Regex = Regex::new(
+        r"^You gain(?:ed)? (?:party |raid )?experience!+(?: \((?P<percent>\d+(?:\.\d+)?)%\))?$"
+    )
+    .unwrap();
+    // e.g. "--You have looted a Bone Chip.--" or, on newer clients,
+    // "--You have looted a Bone Chip from a decaying skeleton's corpse.--"
+    static ref LOOT_RE: Regex =
+        Regex::new(r"^--You have looted (?:an? )?(?P<item>.+?)(?: from .+)?\.--$").unwrap();
+}
+
+/// The experience that a log line says was gained, in percent of a level,
+/// which is zero for clients that don't log how much.
+pub(crate) fn experience_gained(line: &str) -> Option<f64> {
+    let caps = EXPERIENCE_RE.captures(line)?;
+
+    Some(
+        caps.name("percent")
+            .and_then(|p| p.as_str().parse::<f64>().ok())
+            .unwrap_or_default(),
+    )
+}
+
+/// Whether a log line is one that the digest needs to see.
+pub(crate) fn is_digest_line(line: &str) -> bool {
+    EXPERIENCE_RE.is_match(line)
+        || LOOT_RE.is_match(line)
+        || currency::is_currency_line(line)
+        || corpses::is_death_line(line)
+}
+
+/// What a single character got up to over a session.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CharacterDigest {
+    /// How often each trigger fired, by name.
+    pub triggers: BTreeMap<String, u64>,
+    /// The coin looted and split, in copper.
+    pub coin: u64,
+    /// How many of each item were looted, by name.
+    pub items: BTreeMap<String, u64>,
+    /// How many times experience was gained.
+    pub experience: u64,
+    /// The experience gained, in percent of a level, which is only known
+    /// for clients that log it.
+    pub experience_percent: f64,
+    pub deaths: u64,
+}
+
+impl CharacterDigest {
+    pub fn fired(&self) -> u64 {
+        self.triggers.values().sum()
+    }
+
+    pub fn platinum(&self) -> f64 {
+        self.coin as f64 / COPPER_PER_PLATINUM as f64
+    }
+}
+
+/// A summary of everything that was tallied between Comrade starting, or
+/// the last digest, and now.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionDigest {
+    /// When the session started, in UTC.
+    pub started: LogTime,
+    pub duration: Duration,
+    pub characters: BTreeMap<CharacterId, CharacterDigest>,
+    /// How many events were dropped, rather than handed to a frontend,
+    /// because they weren't being taken quickly enough.
+    pub dropped: u64,
+}
+
+impl SessionDigest {
+    /// Whether nothing at all happened during the session.
+    pub fn is_empty(&self) -> bool {
+        self.characters.is_empty() && self.dropped == 0
+    }
+}
+
+impl fmt::Display for SessionDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Session from {} UTC, for {}",
+            self.started,
+            format_duration(self.duration)
+        )?;
+        if self.characters.is_empty() {
+            writeln!(f, "\nNothing happened.")?;
+        }
+
+        for (id, character) in self.characters.iter() {
+            writeln!(f, "\n{}", id)?;
+            writeln!(f, "  Triggers fired: {}", character.fired())?;
+            let mut triggers: Vec<(&String, &u64)> = character.triggers.iter().collect();
+            triggers.sort_by(|a, b| b.1.cmp(a.1));
+            for (name, count) in triggers {
+                writeln!(f, "    {}: {}", name, count)?;
+            }
+            writeln!(
+                f,
+                "  Coin: {:.2} platinum ({:.2} an hour)",
+                character.platinum(),
+                currency::per_hour(character.platinum(), self.duration)
+            )?;
+            writeln!(
+                f,
+                "  Items looted: {}",
+                character.items.values().sum::<u64>()
+            )?;
+            for (item, count) in character.items.iter() {
+                writeln!(f, "    {}: {}", item, count)?;
+            }
+            write!(
+                f,
+                "  Experience: {} gains ({:.1} an hour)",
+                character.experience,
+                currency::per_hour(character.experience as f64, self.duration)
+            )?;
+            if character.experience_percent > 0.0 {
+                write!(
+                    f,
+                    ", {:.3}% ({:.3}% an hour)",
+                    character.experience_percent,
+                    currency::per_hour(character.experience_percent, self.duration)
+                )?;
+            }
+            writeln!(f)?;
+            writeln!(f, "  Deaths: {}", character.deaths)?;
+        }
+
+        writeln!(f, "\nDropped events: {}", self.dropped)
+    }
+}
+
+/// Writes a duration out like "8h 5m", which is as precise as a session
+/// needs.
+fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    format!("{}h {}m", minutes / 60, minutes % 60)
+}
+
+/// The tally for the session that's still going.
+pub(crate) struct Tally {
+    started: LogTime,
+    began: Instant,
+    characters: BTreeMap<CharacterId, CharacterDigest>,
+    dropped: u64,
+}
+
+pub(crate) type DigestLog = Arc<Mutex<Tally>>;
+
+impl Default for Tally {
+    fn default() -> Tally {
+        Tally {
+            started: LogTime::now(),
+            began: Instant::now(),
+            characters: BTreeMap::new(),
+            dropped: 0,
+        }
+    }
+}
+
+impl Tally {
+    fn character(&mut self, id: &CharacterId) -> &mut CharacterDigest {
+        self.characters.entry(id.clone()).or_default()
+    }
+
+    /// Tallies whatever the given log event says the character got.
+    pub(crate) fn log_event(&mut self, event: &LogEvent) {
+        let line = event.message();
+        if let Some(percent) = experience_gained(line) {
+            let character = self.character(&event.id);
+            character.experience += 1;
+            character.experience_percent += percent;
+        } else if let Some(caps) = LOOT_RE.captures(line) {
+            let character = self.character(&event.id);
+            match character.items.get_mut(&caps["item"]) {
+                Some(count) => *count += 1,
+                None => {
+                    character.items.insert(caps["item"].to_string(), 1);
+                }
+            }
+        } else if let Some(copper) = currency::coin_received(line) {
+            self.character(&event.id).coin += copper;
+        } else if corpses::is_death_line(line) {
+            self.character(&event.id).deaths += 1;
+        }
+    }
+
+    /// Counts a trigger firing for the given character.
+    pub(crate) fn triggered(&mut self, id: &CharacterId, name: &str) {
+        let character = self.character(id);
+        match character.triggers.get_mut(name) {
+            Some(count) => *count += 1,
+            None => {
+                character.triggers.insert(name.to_string(), 1);
+            }
+        }
+    }
+
+    pub(crate) fn dropped(&mut self) {
+        self.dropped += 1;
+    }
+
+    /// The digest of the session so far.
+    pub(crate) fn digest(&self) -> SessionDigest {
+        SessionDigest {
+            started: self.started,
+            duration: self.began.elapsed(),
+            characters: self.characters.clone(),
+            dropped: self.dropped,
+        }
+    }
+
+    /// Ends the session, returning its digest and starting a new one.
+    pub(crate) fn finish(&mut self) -> SessionDigest {
+        let digest = self.digest();
+        *self = Tally::default();
+
+        digest
+    }
+}
+
+/// Writes and mails the digest, whichever the configuration asks for. A
+/// digest that can't be delivered is only warned about, since Comrade is
+/// already stopping by the time there's one, and it's mailed in the
+/// background.
+pub(crate) fn deliver(digest: &SessionDigest, config: &DigestConfig, data_dir: &Path) {
+    if config.write {
+        match write(digest, data_dir) {
+            Ok(filename) => info!("wrote the session digest to {}", filename.display()),
+            Err(e) => warn!("could not write the session digest: {}", e),
+        }
+    }
+
+    if let Some(ref email) = config.email {
+        let email = email.clone();
+        let sendmail = config.sendmail.clone();
+        let message = format!(
+            "To: {}\nSubject: Comrade session digest for {}\n\n{}",
+            email, digest.started, digest
+        );
+        let spawned = thread::Builder::new()
+            .name("comrade digest mail".to_string())
+            .spawn(move || {
+                match mail(
+                    message.as_str(),
+                    email.as_str(),
+                    sendmail.as_str(),
+                    MAIL_TIMEOUT,
+                ) {
+                    Ok(()) => info!("mailed the session digest to {}", email),
+                    Err(e) => warn!("could not mail the session digest to {}: {}", email, e),
+                }
+            });
+        if let Err(e) = spawned {
+            warn!("could not start mailing the session digest: {}", e);
+        }
+    }
+}
+
+fn write(digest: &SessionDigest, data_dir: &Path) -> io::Result<PathBuf> {
+    let dir = data_dir.join(DIGESTS_DIRNAME);
+    fs::create_dir_all(dir.as_path())?;
+
+    // e.g. digests/2026-10-17-201500.txt
+    let filename = dir.join(format!(
+        "{}.txt",
+        digest
+            .started
+            .to_string()
+            .replace(' ', "-")
+            .replace(':', "")
+    ));
+    fs::write(filename.as_path(), digest.to_string())?;
+
+    Ok(filename)
+}
+
+/// Hands the message to `sendmail`, killing it if it hasn't finished within
+/// the timeout. It's always waited on, whether or not it took the message,
+/// so that it isn't left behind as a zombie.
+fn mail(message: &str, email: &str, sendmail: &str, timeout: Duration) -> io::Result<()> {
+    let mut child = Command::new(sendmail)
+        .arg(email)
+        .stdin(Stdio::piped())
+        .spawn()?;
+
+    // Dropping stdin closes it, which is how sendmail knows it has the whole
+    // message.
+    let written = match child.stdin.take() {
+        Some(mut stdin) => stdin.write_all(message.as_bytes()),
+        None => Ok(()),
+    };
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            // It may have only just exited, which is fine.
+            let _ = child.kill();
+            child.wait()?;
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("{} took longer than {:?}", sendmail, timeout),
+            ));
+        }
+        thread::sleep(MAIL_POLL);
+    };
+
+    written?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "{} exited with {}",
+            sendmail, status
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use super::*;
+
+    fn event(id: &Arc<CharacterId>, message: &str) -> LogEvent {
+        LogEvent::new(id.clone(), "[Sat Oct 17 20:15:00 2026] ", message, None)
+    }
+
+    #[test]
+    fn tallies_and_writes_sessions() {
+        let id = Arc::new(CharacterId::new("Soandso"));
+        let mut tally = Tally::default();
+        for line in [
+            "You gain party experience!!",
+            "You gain experience! (1.25%)",
+            "--You have looted a Bone Chip.--",
+            "--You have looted a Bone Chip from a decaying skeleton's corpse.--",
+            "You receive 1 platinum, 5 gold and 3 copper from the corpse.",
+            "You have been slain by a decaying skeleton!",
+            "Soandso tells you, 'hello'",
+        ] {
+            tally.log_event(&event(&id, line));
+        }
+        tally.triggered(&id, "Tells");
+        tally.triggered(&id, "Tells");
+        tally.dropped();
+
+        let digest = tally.finish();
+        let character = &digest.characters[&*id];
+        assert_eq!(character.experience, 2);
+        assert_eq!(character.experience_percent, 1.25);
+        assert_eq!(character.items["Bone Chip"], 2);
+        assert_eq!(character.coin, 1503);
+        assert_eq!(character.deaths, 1);
+        assert_eq!(character.fired(), 2);
+        assert_eq!(digest.dropped, 1);
+        // Finishing the session starts a new one from scratch.
+        assert!(tally.digest().is_empty());
+
+        let data_dir = env::temp_dir().join(format!("comrade-digest-{}", process::id()));
+        let filename = write(&digest, data_dir.as_path()).unwrap();
+        let written = fs::read_to_string(filename).unwrap();
+        assert!(written.contains("Soandso"));
+        assert!(written.contains("Tells: 2"));
+        assert!(written.contains("Bone Chip: 2"));
+        assert!(written.contains("Dropped events: 1"));
+
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn mails_through_sendmail() {
+        assert!(mail(
+            "Subject: hi\n\n",
+            "someone@example.com",
+            "true",
+            MAIL_TIMEOUT
+        )
+        .is_ok());
+        assert!(mail(
+            "Subject: hi\n\n",
+            "someone@example.com",
+            "false",
+            MAIL_TIMEOUT
+        )
+        .is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn kills_a_sendmail_that_hangs() {
+        // `sleep` takes the address as how long to sleep for, and never reads
+        // the message.
+        let started = Instant::now();
+        let mailed = mail("Subject: hi\n\n", "5", "sleep", Duration::from_millis(200));
+        assert_eq!(mailed.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -8,29 +8,78 @@
 //! handling these events and present them to the user in some fashion (TTS, Text,
 //! Timer Bar, etc).
 
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
 
 use arc_swap::Cache;
 use crossbeam_channel::{bounded, select, tick, Receiver, Sender};
-use log::{error, trace};
+use log::{error, info, trace};
 
+use crate::attendance::{RosterLog, Rosters};
+use crate::broadcasts::{self, BroadcastChannel};
+use crate::combat::{Combat, FightLog};
+use crate::config::timers::TimersConfig;
+use crate::config::CharacterId;
 use crate::config::{CachedConfig, ConfigRef};
+use crate::corpses;
+use crate::currency::{Currency, EarningsLog};
+use crate::digest::DigestLog;
 use crate::errors::DriverError;
-use crate::events::{Event, EventReceiver, EventSender};
-use crate::triggers::Action;
+use crate::events;
+use crate::events::{AlertId, Event, EventKind, EventReceiver, EventSender};
+use crate::executors::Executors;
+use crate::fields::LineFields;
+use crate::history::LogTime;
+use crate::inflight::InFlight;
+use crate::instance::Instance;
+use crate::locations::LocationLog;
+use crate::memory::{self, Evictions};
+use crate::metrics::{Health, Metrics};
+use crate::outputs::Outputs;
+use crate::random::Rng;
+use crate::resists::{ResistLog, Resists};
+use crate::snoozes::SnoozeLog;
+use crate::store::{EventStore, Record};
+use crate::tradeskills::{RecipeLog, Tradeskills};
+use crate::triggers::{Action, Repeats};
 use crate::watcher::{LogEvent, LogReceiver};
 
 type Result<T, E = DriverError> = core::result::Result<T, E>;
 
+/// How often the stores of what's been tracked are brought back within their
+/// memory budgets.
+const BUDGET_INTERVAL: Duration = Duration::from_secs(30);
+
 enum Commands {
     Stop,
+    StartTimer {
+        text: Arc<String>,
+        duration: Duration,
+    },
+    /// Acknowledges the given alert, or every alert if there isn't one.
+    Acknowledge(Option<AlertId>),
+    /// Recovers the delayed actions from the given in-flight journal, and
+    /// starts journaling to it, instead of to whichever journal it was
+    /// journaling to before.
+    Recover(PathBuf, Option<Arc<Instance>>),
+    /// Stops, or starts again, writing anything to the data directory other
+    /// than the in-flight journal, which is only recovered when it's not.
+    ReadOnly(bool),
 }
 
 #[inline(always)]
-fn action_events(sender: &EventSender, action: &mut Action) {
-    if let Some(events) = action.events() {
+fn action_events(
+    sender: &EventSender,
+    action: &mut Action,
+    locations: &LocationLog,
+    outputs: &Outputs,
+    executors: &Executors,
+    timers: &TimersConfig,
+) {
+    if let Some(events) = action.events(locations, outputs, executors, timers) {
         for event in events {
             if let Err(e) = sender.send(event) {
                 error!("error sending event error: {:?}", e);
@@ -39,6 +88,55 @@ fn action_events(sender: &EventSender, action: &mut Action) {
     }
 }
 
+/// Keeps track of an action that isn't finished yet, from the given
+/// character's log unless it was started by hand, journaling it if it's
+/// waiting on a delay.
+fn track(
+    actions: &mut Vec<Action>,
+    inflight: &mut Option<InFlight>,
+    events: &EventSender,
+    id: Option<&CharacterId>,
+    mut action: Action,
+) {
+    // A countdown that starts over takes the place of the one that's
+    // already going, rather than going alongside it.
+    for existing in actions.iter_mut() {
+        if let Some(event) = existing.restarted_by(&action) {
+            if let Err(e) = events.send(event) {
+                error!("error sending event error: {:?}", e);
+            }
+        }
+    }
+
+    if let Some(inflight) = inflight {
+        inflight.started(id, &mut action);
+    }
+    actions.push(action);
+}
+
+fn send_event(sender: &EventSender, kind: EventKind) {
+    if let Err(e) = sender.send(Event::new(kind)) {
+        error!("error sending event error: {:?}", e);
+    }
+}
+
+/// Everything that the driver keeps track of besides triggers, which is
+/// shared so that it can be read back while the driver is running.
+#[derive(Clone, Default)]
+pub(crate) struct Tracked {
+    pub(crate) fights: FightLog,
+    pub(crate) rosters: RosterLog,
+    pub(crate) recipes: RecipeLog,
+    pub(crate) resists: ResistLog,
+    pub(crate) earnings: EarningsLog,
+    pub(crate) locations: LocationLog,
+    pub(crate) snoozes: SnoozeLog,
+    pub(crate) digest: DigestLog,
+    /// How many events have been dropped since Comrade started.
+    pub(crate) dropped: Arc<AtomicU64>,
+    pub(crate) evictions: Evictions,
+}
+
 struct DriverThread {
     running: bool,
     config: CachedConfig,
@@ -46,7 +144,26 @@ struct DriverThread {
     logs: LogReceiver,
     events: EventSender,
     actions: Vec<Action>,
+    // Only journaled once there's somewhere to journal to.
+    inflight: Option<InFlight>,
+    repeats: Repeats,
+    combat: Combat,
+    rosters: Rosters,
+    tradeskills: Tradeskills,
+    resists: Resists,
+    currency: Currency,
+    locations: LocationLog,
+    outputs: Outputs,
+    executors: Executors,
+    snoozes: SnoozeLog,
+    metrics: Metrics,
+    store: EventStore,
+    read_only: bool,
+    rng: Rng,
     ticks: Receiver<Instant>,
+    // The stores, again, for keeping them within their budgets.
+    tracked: Tracked,
+    budgeted: Instant,
 }
 
 // Note: All of the methods, other than the start method, of this
@@ -56,12 +173,16 @@ impl DriverThread {
         config: ConfigRef,
         logs: LogReceiver,
         events: EventSender,
+        tracked: Tracked,
+        executors: Executors,
     ) -> Result<Sender<Commands>> {
         let (s_cmds, cmds) = bounded(0);
+        executors.connect(events.clone());
 
         thread::Builder::new()
             .name("comrade driver".to_string())
             .spawn(move || {
+                let stores = tracked.clone();
                 let worker = DriverThread {
                     running: true,
                     config: Cache::new(config),
@@ -69,7 +190,24 @@ impl DriverThread {
                     logs,
                     events,
                     actions: Vec::new(),
+                    inflight: None,
+                    repeats: Repeats::default(),
+                    combat: Combat::new(tracked.fights),
+                    rosters: Rosters::new(tracked.rosters),
+                    tradeskills: Tradeskills::new(tracked.recipes),
+                    resists: Resists::new(tracked.resists),
+                    currency: Currency::new(tracked.earnings),
+                    locations: tracked.locations,
+                    outputs: Outputs::default(),
+                    executors,
+                    snoozes: tracked.snoozes,
+                    metrics: Metrics::default(),
+                    store: EventStore::default(),
+                    read_only: false,
+                    rng: Rng::new(),
                     ticks: tick(Duration::from_millis(250)),
+                    tracked: stores,
+                    budgeted: Instant::now(),
                 };
                 worker.run();
             })?;
@@ -98,12 +236,243 @@ impl DriverThread {
     fn on_command(&mut self, command: Commands) {
         match command {
             Commands::Stop => self.running = false,
+            Commands::StartTimer { text, duration } => {
+                let config = self.config.load();
+                let mut action = Action::timer(text, duration, Instant::now() + duration);
+                action_events(
+                    &self.events,
+                    &mut action,
+                    &self.locations,
+                    &self.outputs,
+                    &self.executors,
+                    &config.timers,
+                );
+                track(
+                    &mut self.actions,
+                    &mut self.inflight,
+                    &self.events,
+                    None,
+                    action,
+                );
+            }
+            Commands::Acknowledge(target) => {
+                for action in self.actions.iter_mut() {
+                    if let Some(alert) = action.acknowledge(target) {
+                        let event = Event::new(EventKind::Acknowledged { alert });
+                        if let Err(e) = self.events.send(event) {
+                            error!("error sending event error: {:?}", e);
+                        }
+                    }
+                }
+                self.settle();
+                self.actions.retain(|action| !action.finished());
+                self.repeats.expire();
+            }
+            Commands::Recover(filename, instance) => {
+                if let Some(ref inflight) = self.inflight {
+                    // Whatever the journal has in it by now is already being
+                    // tracked, so recovering it again would carry it out
+                    // twice.
+                    if inflight.filename() == filename.as_path() {
+                        return;
+                    }
+
+                    // Switching to another data directory, like another
+                    // profile's, leaves the actions that are waiting in the
+                    // old one's journal, to be picked back up from it.
+                    self.settle();
+                    self.actions.retain(|action| !action.is_journaled());
+                }
+
+                let config = self.config.load();
+                let (inflight, recovered) =
+                    InFlight::recover(filename, instance, &config.characters, &config.timers);
+                self.inflight = Some(inflight);
+                self.actions
+                    .extend(recovered.into_iter().map(|(_, action)| action));
+            }
+            Commands::ReadOnly(read_only) => self.read_only = read_only,
+        }
+    }
+
+    /// Journals every action that's done waiting on its delay as such.
+    fn settle(&mut self) {
+        if let Some(ref mut inflight) = self.inflight {
+            for action in self.actions.iter_mut() {
+                inflight.settle(action);
+            }
         }
     }
 
     fn on_log_event(&mut self, matched: Arc<LogEvent>) {
         trace!("received log event: {:?}", matched);
         let config = self.config.load();
+        self.outputs.configure(&config.outputs);
+        self.executors.configure(&config.executors);
+
+        // Logs from a computer whose clock is set differently are brought in
+        // line with ours before anything looks at when they were written.
+        let offset = config
+            .characters
+            .get(&*matched.id)
+            .map_or(0, |c| c.clock_offset.seconds());
+        let matched = match offset {
+            0 => matched,
+            offset => Arc::new(LogEvent::clone(&matched).shifted(offset)),
+        };
+
+        // Countdowns that this line ends are ended before any triggers see
+        // it, so that a trigger starting the same countdown over again isn't
+        // ended along with the old one. They're dropped on the next tick.
+        for action in self.actions.iter_mut() {
+            if let Some(event) = action.end_early(&matched) {
+                if let Err(e) = self.events.send(event) {
+                    error!("error sending event error: {:?}", e);
+                }
+            }
+        }
+
+        let position = self.locations.lock().log_event(&matched);
+        if let Some(position) = position {
+            let character = config.characters.get(&*matched.id).cloned();
+            send_event(
+                &self.events,
+                EventKind::LocationUpdated {
+                    character: character.map(Arc::new),
+                    position: Arc::new(position),
+                },
+            );
+        }
+
+        if config.digest.enabled {
+            self.tracked.digest.lock().log_event(&matched);
+        }
+
+        if config.metrics.enabled {
+            let name = config
+                .characters
+                .get(&*matched.id)
+                .map(|c| c.name.as_str())
+                .unwrap_or_default();
+            self.metrics.log_event(name, &matched);
+        }
+
+        if config.broadcasts.enabled {
+            if let Some(broadcast) = broadcasts::parse_broadcast(matched.message()) {
+                let character = config.characters.get(&*matched.id).cloned().map(Arc::new);
+                if broadcast.channel == BroadcastChannel::Raid
+                    && config.broadcasts.is_leader(broadcast.speaker.as_str())
+                {
+                    if let Some(character) = character.clone() {
+                        let text = format!("{}: {}", broadcast.speaker, broadcast.text);
+                        let name = config.locale.text(&character, "raid-leader", &[]);
+                        let mut action = Action::display(
+                            Arc::new(text),
+                            broadcasts::leader_trigger(name),
+                            character,
+                        );
+                        action_events(
+                            &self.events,
+                            &mut action,
+                            &self.locations,
+                            &self.outputs,
+                            &self.executors,
+                            &config.timers,
+                        );
+                    }
+                }
+
+                send_event(
+                    &self.events,
+                    EventKind::Broadcast {
+                        character,
+                        broadcast: Arc::new(broadcast),
+                    },
+                );
+            }
+        }
+
+        if config.corpses.enabled {
+            if let Some(character) = config.characters.get(&*matched.id) {
+                let actions = corpses::on_death(
+                    &config.corpses,
+                    &config.locale,
+                    character,
+                    &matched,
+                    &mut self.locations.lock(),
+                );
+
+                for mut action in actions.into_iter().flatten() {
+                    action_events(
+                        &self.events,
+                        &mut action,
+                        &self.locations,
+                        &self.outputs,
+                        &self.executors,
+                        &config.timers,
+                    );
+                    if !action.finished() {
+                        track(
+                            &mut self.actions,
+                            &mut self.inflight,
+                            &self.events,
+                            Some(&matched.id),
+                            action,
+                        );
+                    }
+                }
+            }
+        }
+
+        if config.combat.enabled {
+            if let Some(character) = config.characters.get(&*matched.id) {
+                if let Some(target) = self.combat.log_event(character, &matched) {
+                    send_event(
+                        &self.events,
+                        EventKind::FightStarted {
+                            character: Some(Arc::new(character.clone())),
+                            target: Arc::new(target),
+                        },
+                    );
+                }
+            }
+        }
+
+        if config.attendance.enabled {
+            self.rosters.log_event(&matched);
+        }
+
+        if config.tradeskills.enabled {
+            self.tradeskills.log_event(&matched);
+        }
+
+        if config.resists.enabled {
+            if let Some(stats) = self.resists.log_event(&matched, config.resists.streak) {
+                let character = config.characters.get(&stats.character).cloned();
+                send_event(
+                    &self.events,
+                    EventKind::ResistStreak {
+                        character: character.map(Arc::new),
+                        target: Arc::new(stats.target),
+                        spell: Arc::new(stats.spell),
+                        streak: stats.streak,
+                    },
+                );
+            }
+        }
+
+        if config.currency.enabled {
+            if let Some(session) = self.currency.log_event(&matched) {
+                let character = config.characters.get(&session.character).cloned();
+                send_event(
+                    &self.events,
+                    EventKind::EarningsSummary {
+                        character: character.map(Arc::new),
+                        session: Arc::new(session),
+                    },
+                );
+            }
+        }
 
         // TODO: Could we do something smart here, and modify our filter so that
         //       instead of returning a bool, it returns the matched triggers and
@@ -115,14 +484,78 @@ impl DriverThread {
         //
         // If config.triggers.compiled() returns a None, then we don't have any
         // triggers for this character, so we'll jsut noop this event.
-        if let Some(triggers) = config.triggers.compiled(&*matched.id) {
-            for trigger in triggers {
-                if let Some(actions) = trigger.execute(&matched) {
+        if let Some(triggers) = config.triggers.compiled(&matched.id) {
+            let name = config
+                .characters
+                .get(&*matched.id)
+                .map(|c| c.name.as_str())
+                .unwrap_or_default();
+            let fight = self.combat.current(&matched.id);
+            let fields = LineFields::new(&matched, name, fight, &self.locations);
+            let recording = config.history.record && !self.read_only && !config.demo;
+            // Only look up where the character is when it could matter.
+            let zone = if config.zones.is_empty() && !recording {
+                None
+            } else {
+                self.locations.lock().zone(&matched.id)
+            };
+            let profile = zone.as_deref().and_then(|z| config.zones.profile(z));
+            let snoozes = self.snoozes.lock();
+            for trigger in triggers.iter().filter(|t| t.is_active(profile)) {
+                if snoozes.is_snoozed(trigger.tref()) {
+                    trace!("skipping snoozed trigger {}", trigger.tref());
+                    continue;
+                }
+                if let Some(actions) = trigger.execute(&matched, &fields, &mut self.rng) {
+                    if config.digest.enabled {
+                        self.tracked
+                            .digest
+                            .lock()
+                            .triggered(&matched.id, trigger.name());
+                    }
+                    if config.metrics.enabled {
+                        self.metrics.matched(&matched.id);
+                    }
+                    if recording {
+                        if let Some(time) = LogTime::from_timestamp(matched.timestamp()) {
+                            let record = Record {
+                                time: time.seconds(),
+                                timestamp: matched.timestamp().to_string(),
+                                character: matched.id.to_string(),
+                                name: name.to_string(),
+                                trigger: trigger.tref().to_string(),
+                                trigger_name: trigger.name().to_string(),
+                                zone: zone.clone(),
+                                message: matched.message().to_string(),
+                            };
+                            self.store.record(config.dirs.data.as_path(), &record);
+                        }
+                    }
                     for mut action in actions {
-                        action_events(&self.events, &mut action);
+                        if let Some(event) = action.repeat(&matched.id, &mut self.repeats) {
+                            if let Err(e) = self.events.send(event) {
+                                error!("error sending event error: {:?}", e);
+                            }
+                            continue;
+                        }
+
+                        action_events(
+                            &self.events,
+                            &mut action,
+                            &self.locations,
+                            &self.outputs,
+                            &self.executors,
+                            &config.timers,
+                        );
 
                         if !action.finished() {
-                            self.actions.push(action);
+                            track(
+                                &mut self.actions,
+                                &mut self.inflight,
+                                &self.events,
+                                Some(&matched.id),
+                                action,
+                            );
                         }
                     }
                 }
@@ -131,30 +564,156 @@ impl DriverThread {
     }
 
     fn on_tick(&mut self) {
+        let config = self.config.load().clone();
+        self.outputs.configure(&config.outputs);
+        self.executors.configure(&config.executors);
         for action in self.actions.iter_mut() {
-            action_events(&self.events, action);
+            action_events(
+                &self.events,
+                action,
+                &self.locations,
+                &self.outputs,
+                &self.executors,
+                &config.timers,
+            );
         }
+        self.settle();
         self.actions.retain(|action| !action.finished());
+        self.repeats.expire();
+
+        for tref in self.snoozes.lock().expire() {
+            info!("trigger {} is no longer snoozed", tref);
+        }
+
+        for summary in self.combat.expire(&config.combat) {
+            let character = config.characters.get(&summary.character).cloned();
+            send_event(
+                &self.events,
+                EventKind::FightEnded {
+                    character: character.map(Arc::new),
+                    summary: Arc::new(summary),
+                },
+            );
+        }
+
+        for session in self.tradeskills.expire(config.tradeskills.timeout) {
+            let character = config.characters.get(&session.character).cloned();
+            send_event(
+                &self.events,
+                EventKind::TradeskillSummary {
+                    character: character.map(Arc::new),
+                    session: Arc::new(session),
+                },
+            );
+        }
+
+        self.metrics.export(
+            &config.metrics,
+            config.dirs.data.as_path(),
+            !self.read_only && !config.demo,
+            Health {
+                backlog: self.logs.len(),
+                queued: self.events.len(),
+                actions: self.actions.len(),
+                dropped: self.tracked.dropped.load(Ordering::Relaxed),
+            },
+        );
+
+        if config.history.record && !self.read_only && !config.demo {
+            self.store
+                .maintain(&config.history, config.dirs.data.as_path());
+        }
+
+        if self.budgeted.elapsed() >= BUDGET_INTERVAL {
+            memory::enforce(&self.tracked, &config.memory);
+            self.budgeted = Instant::now();
+        }
+
+        for session in self.currency.expire(config.currency.timeout) {
+            let character = config.characters.get(&session.character).cloned();
+            send_event(
+                &self.events,
+                EventKind::EarningsSummary {
+                    character: character.map(Arc::new),
+                    session: Arc::new(session),
+                },
+            );
+        }
     }
 }
 
 pub(crate) struct Driver {
     cmds: Sender<Commands>,
     events: EventReceiver,
+    // For events that come from outside of the driver thread.
+    sender: EventSender,
+    // Where events that had to be dropped are counted.
+    digest: DigestLog,
+    dropped: Arc<AtomicU64>,
 }
 
 impl Driver {
-    pub(crate) fn create(config: ConfigRef, log_receiver: LogReceiver) -> Driver {
-        let (s_events, events) = bounded(1000);
-        let cmds = DriverThread::start(config, log_receiver, s_events)
+    pub(crate) fn create(
+        config: ConfigRef,
+        log_receiver: LogReceiver,
+        tracked: Tracked,
+        executors: Executors,
+    ) -> Driver {
+        let (s_events, events) = events::channel(1000);
+        let digest = tracked.digest.clone();
+        let dropped = tracked.dropped.clone();
+        let cmds = DriverThread::start(config, log_receiver, s_events.clone(), tracked, executors)
             .expect("could not start driver thread");
 
-        Driver { cmds, events }
+        Driver {
+            cmds,
+            events,
+            sender: s_events,
+            digest,
+            dropped,
+        }
     }
 
     pub(crate) fn event(&self) -> Option<Event> {
         self.events.try_recv().ok()
     }
+
+    /// Sends an event that's only of passing interest, so if nobody is
+    /// keeping up with events it's dropped rather than waited on.
+    pub(crate) fn notify(&self, kind: EventKind) {
+        if let Err(e) = self.sender.try_send(Event::new(kind)) {
+            trace!("dropped event: {:?}", e);
+            self.digest.lock().dropped();
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn start_timer(&self, text: String, duration: Duration) {
+        self.cmds
+            .send(Commands::StartTimer {
+                text: Arc::new(text),
+                duration,
+            })
+            .expect("driver thread should not stop before the driver is dropped");
+    }
+
+    pub(crate) fn recover(&self, filename: PathBuf, instance: Option<Arc<Instance>>) {
+        self.cmds
+            .send(Commands::Recover(filename, instance))
+            .expect("driver thread should not stop before the driver is dropped");
+    }
+
+    pub(crate) fn acknowledge(&self, alert: Option<AlertId>) {
+        self.cmds
+            .send(Commands::Acknowledge(alert))
+            .expect("driver thread should not stop before the driver is dropped");
+    }
+
+    pub(crate) fn set_read_only(&self, read_only: bool) {
+        self.cmds
+            .send(Commands::ReadOnly(read_only))
+            .expect("driver thread should not stop before the driver is dropped");
+    }
 }
 
 impl Drop for Driver {
@@ -1,8 +1,13 @@
 use std::path::PathBuf;
 use thiserror::Error;
 
+use crate::audio::Severity;
+use crate::config::triggers::{TriggerId, TriggerRef};
+use crate::config::CharacterId;
+
 #[derive(Error, Debug)]
 pub enum LogWatcherError {
+    #[cfg(feature = "watcher")]
     #[error("could not create file notifier")]
     FileNotifierError(#[from] notify::Error),
 
@@ -35,12 +40,324 @@ pub enum ConfigError {
 
     #[error("could not compile triggers")]
     TriggerError(#[from] TriggerError),
+
+    #[error("could not parse document {filename:?}")]
+    DocumentError {
+        source: toml_edit::TomlError,
+        filename: PathBuf,
+    },
+
+    #[error("invalid document {filename:?}: {reason}")]
+    InvalidDocument { filename: PathBuf, reason: String },
+
+    #[error("could not serialize configuration")]
+    SerializationError(#[from] toml_edit::ser::Error),
+
+    #[error("unknown character {id}")]
+    UnknownCharacter { id: CharacterId },
+
+    #[error("unknown trigger {tref}")]
+    UnknownTrigger { tref: TriggerRef },
+
+    #[error("trigger {tref} already exists")]
+    DuplicateTrigger { tref: TriggerRef },
+
+    #[error("{filename:?} was modified outside of comrade since it was last edited")]
+    EditConflict { filename: PathBuf },
+
+    #[error("trigger {tref} is not from an editable source")]
+    NotEditable { tref: TriggerRef },
+
+    #[error("unknown trigger source {name}")]
+    UnknownSource { name: String },
+
+    #[error("alias {alias:?} is used for more than one character")]
+    DuplicateAlias { alias: String },
+
+    #[error("invalid group {name:?}: {reason}")]
+    InvalidGroup { name: String, reason: String },
+
+    #[error("unknown group {name:?}")]
+    UnknownGroup { name: String },
+
+    #[error("unknown profile {name:?}")]
+    UnknownProfile { name: String },
+
+    #[error("invalid size {value:?}, expected something like 4MiB")]
+    InvalidSize { value: String },
+
+    #[error("comrade is in read-only mode")]
+    ReadOnly,
+
+    #[error("unknown cue profile {name:?}")]
+    UnknownCueProfile { name: String },
+
+    #[error("unknown text {key:?} in locale {language:?}")]
+    UnknownText { language: String, key: String },
+
+    #[error("invalid {severity} cue in profile {profile:?}: {reason}")]
+    InvalidCue {
+        profile: String,
+        severity: Severity,
+        reason: String,
+    },
+
+    #[error(transparent)]
+    PackError(#[from] PackError),
 }
 
 #[derive(Error, Debug)]
 pub enum TriggerError {
     #[error("invalid regex")]
     InvalidRegex(#[from] regex::Error),
+
+    #[error("invalid priority {value:?}, expected low, normal, or high")]
+    InvalidPriority { value: String },
+
+    #[error("invalid trigger source {value:?}")]
+    InvalidSource { value: String },
+
+    #[error("invalid condition {value:?}: {reason}")]
+    InvalidCondition { value: String, reason: String },
+
+    #[error("no capture group named {name:?}")]
+    UnknownCapture { name: String },
+
+    #[error("cannot fire unknown trigger {id}")]
+    UnknownChainedTrigger { id: TriggerId },
+
+    #[error("trigger {id} ends up firing itself")]
+    ChainCycle { id: TriggerId },
+
+    #[error("invalid signal for output {output:?}: {reason}")]
+    InvalidSignal { output: String, reason: String },
+
+    #[error("invalid random action: {reason}")]
+    InvalidChoice { reason: String },
+
+    #[error("invalid branch: {reason}")]
+    InvalidBranch { reason: String },
+
+    #[error("invalid active window {value:?}: {reason}")]
+    InvalidWindow { value: String, reason: String },
+}
+
+#[derive(Error, Debug)]
+pub enum TimerError {
+    #[error("invalid duration {value:?}, expected something like 6m30s")]
+    InvalidDuration { value: String },
+
+    #[error("a timer needs some text to display")]
+    MissingText,
+}
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("could not read {filename:?}")]
+    IOError {
+        source: std::io::Error,
+        filename: PathBuf,
+    },
+
+    #[error("could not parse GINA triggers")]
+    XmlError(#[from] roxmltree::Error),
+
+    #[error("no GINA triggers were found")]
+    NoTriggers,
+
+    #[error("importing GINA {what} isn't supported")]
+    Unsupported { what: String },
+}
+
+#[derive(Error, Debug)]
+pub enum SourceError {
+    #[error(transparent)]
+    ConfigError(#[from] ConfigError),
+
+    #[error("could not sync {filename:?}")]
+    IOError {
+        source: std::io::Error,
+        filename: PathBuf,
+    },
+
+    #[error("syncing from {url} isn't supported, only files can be synced")]
+    UnsupportedUrl { url: String },
+
+    #[error("invalid trigger source name {name:?}")]
+    InvalidName { name: String },
+
+    #[error(transparent)]
+    PackError(#[from] PackError),
+}
+
+#[derive(Error, Debug)]
+pub enum PackError {
+    #[error("could not read or write {filename:?}")]
+    IOError {
+        source: std::io::Error,
+        filename: PathBuf,
+    },
+
+    #[error("invalid pack: {reason}")]
+    InvalidArchive { reason: String },
+
+    #[error("pack is missing {name}")]
+    MissingFile { name: String },
+
+    #[error("pack can't contain {path:?}")]
+    InvalidPath { path: String },
+
+    #[error("invalid pack name {name:?}")]
+    InvalidName { name: String },
+
+    #[error("could not parse {name} in pack")]
+    DeserializationError {
+        source: toml_edit::de::Error,
+        name: String,
+    },
+
+    #[error("invalid version {version:?}, expected something like 1.2.0")]
+    InvalidVersion { version: String },
+
+    #[error("pack {pack} needs comrade {required} or newer, this is {current}")]
+    NeedsNewerComrade {
+        pack: String,
+        required: String,
+        current: String,
+    },
+
+    #[error("pack {pack} needs pack {dependency} {required} or newer, which isn't installed")]
+    MissingDependency {
+        pack: String,
+        dependency: String,
+        required: String,
+    },
+
+    #[error(
+        "pack {pack} needs pack {dependency} {required} or newer, but {installed} is installed"
+    )]
+    OutdatedDependency {
+        pack: String,
+        dependency: String,
+        required: String,
+        installed: String,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum FixtureError {
+    #[error("could not read {filename:?}")]
+    IOError {
+        source: std::io::Error,
+        filename: PathBuf,
+    },
+
+    #[error("could not parse fixture {filename:?}")]
+    DeserializationError {
+        source: toml_edit::de::Error,
+        filename: PathBuf,
+    },
+
+    #[error("could not parse trigger pack")]
+    InvalidPack(#[source] toml_edit::de::Error),
+
+    #[error("could not parse fixture")]
+    InvalidFixture(#[source] toml_edit::de::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum HistoryError {
+    #[error("could not read {filename:?}")]
+    IOError {
+        source: std::io::Error,
+        filename: PathBuf,
+    },
+
+    #[error("invalid time {value:?}, expected something like 2026-10-17 20:15")]
+    InvalidTime { value: String },
+
+    #[error("could not write {filename:?}")]
+    WriteError {
+        source: std::io::Error,
+        filename: PathBuf,
+    },
+
+    #[error("could not parse {filename:?}")]
+    InvalidRecords {
+        source: toml_edit::de::Error,
+        filename: PathBuf,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum UpdateError {
+    #[error("could not read {filename:?}")]
+    IOError {
+        source: std::io::Error,
+        filename: PathBuf,
+    },
+
+    #[cfg(feature = "updates")]
+    #[error("could not fetch {url}")]
+    RequestError {
+        source: Box<ureq::Error>,
+        url: String,
+    },
+
+    #[error("could not parse the release feed at {url}")]
+    InvalidFeed {
+        source: roxmltree::Error,
+        url: String,
+    },
+
+    #[error("checking {url} for updates isn't supported, only files can be checked")]
+    UnsupportedUrl { url: String },
+}
+
+#[derive(Error, Debug)]
+pub enum ComplianceError {
+    #[error("could not read or write {filename:?}")]
+    IOError {
+        source: std::io::Error,
+        filename: PathBuf,
+    },
+
+    #[error("could not parse manifest {filename:?}")]
+    DeserializationError {
+        source: toml_edit::de::Error,
+        filename: PathBuf,
+    },
+
+    #[error("invalid requirement {value:?} in manifest")]
+    InvalidRequirement { source: TriggerError, value: String },
+}
+
+#[cfg(feature = "dashboard")]
+#[derive(Error, Debug)]
+pub enum DashboardError {
+    #[error("could not listen on {address}")]
+    BindError {
+        source: std::io::Error,
+        address: String,
+    },
+
+    #[error("the dashboard needs a token before it can be turned on, run `comrade init` to generate one")]
+    MissingToken,
+
+    #[error("could not read {filename:?}")]
+    TlsFileError {
+        source: std::io::Error,
+        filename: PathBuf,
+    },
+
+    #[cfg(feature = "tls")]
+    #[error("could not serve the dashboard over https")]
+    TlsError(#[from] rustls::Error),
+
+    #[error(
+        "the dashboard can only be served over https when Comrade is built with the `tls` feature"
+    )]
+    TlsUnsupported,
 }
 
 #[derive(Error, Debug)]
@@ -53,4 +370,29 @@ pub enum ComradeError {
 
     #[error(transparent)]
     LogWatcherError(#[from] LogWatcherError),
+
+    #[error(transparent)]
+    ImportError(#[from] ImportError),
+
+    #[error(transparent)]
+    SourceError(#[from] SourceError),
+
+    #[error(transparent)]
+    FixtureError(#[from] FixtureError),
+
+    #[error(transparent)]
+    HistoryError(#[from] HistoryError),
+
+    #[error(transparent)]
+    PackError(#[from] PackError),
+
+    #[error(transparent)]
+    UpdateError(#[from] UpdateError),
+
+    #[error(transparent)]
+    ComplianceError(#[from] ComplianceError),
+
+    #[cfg(feature = "dashboard")]
+    #[error(transparent)]
+    DashboardError(#[from] DashboardError),
 }
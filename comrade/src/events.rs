@@ -1,34 +1,290 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
-use crossbeam_channel::{Receiver, Sender};
+use crossbeam_channel::{bounded, Receiver, SendError, Sender, TrySendError};
+use parking_lot::Mutex;
 
-use crate::config::triggers::Trigger;
+use crate::broadcasts::Broadcast;
+use crate::combat::FightSummary;
+use crate::config::timers::TimerCategory;
+use crate::config::triggers::{SpellInfo, Trigger, TriggerRef, TriggerSource};
 use crate::config::Character;
+use crate::currency::EarningsSession;
+use crate::locations::Position;
+use crate::time::Instant;
+use crate::tradeskills::TradeskillSession;
+use crate::updates::Release;
 use crate::watcher::LogEvent;
 
-pub(crate) type EventSender = Sender<Event>;
 pub(crate) type EventReceiver = Receiver<Event>;
 
-#[derive(Debug)]
+/// Which stream an event belongs to, the character it came from, by name and
+/// server, or none for the events that didn't come from any one character.
+type StreamKey = Option<(String, String)>;
+
+fn stream_key(kind: &EventKind) -> StreamKey {
+    kind.character().map(|c| (c.name.clone(), c.server.clone()))
+}
+
+/// Sends events on to the frontend, numbering each of them in its stream as
+/// it goes. The numbering and the sending happen together, under the same
+/// lock, so that however many threads are sending, each stream arrives in
+/// the order it was numbered in, and an event that has to be dropped leaves
+/// a gap behind it. The lock is never held while waiting for room though,
+/// since the frontend sends events of its own while it's taking them out,
+/// and would otherwise wait on a sender that's waiting on it. Instead, a
+/// send that has to wait takes its turn, and until it's done, every other
+/// event waits behind it or is dropped, the same as if there were no room.
+#[derive(Clone)]
+pub(crate) struct EventSender {
+    sender: Sender<Event>,
+    streams: Arc<Mutex<Streams>>,
+    turn: Arc<Mutex<()>>,
+}
+
+#[derive(Default)]
+struct Streams {
+    sequences: HashMap<StreamKey, u64>,
+    /// Whether a send is waiting for room, with its event already numbered.
+    waiting: bool,
+}
+
+impl Streams {
+    fn number(&mut self, mut event: Event) -> Event {
+        let sequence = self.sequences.entry(stream_key(&event.kind)).or_insert(0);
+        *sequence += 1;
+        event.sequence = *sequence;
+        event
+    }
+}
+
+impl EventSender {
+    /// How many events are waiting to be handed out.
+    pub(crate) fn len(&self) -> usize {
+        self.sender.len()
+    }
+
+    /// Sends the event, waiting for there to be room for it.
+    pub(crate) fn send(&self, event: Event) -> Result<(), SendError<()>> {
+        let event = {
+            let mut streams = self.streams.lock();
+            if streams.waiting {
+                event
+            } else {
+                let key = stream_key(&event.kind);
+                match self.sender.try_send(streams.number(event)) {
+                    Ok(()) => return Ok(()),
+                    Err(TrySendError::Full(full)) => {
+                        // It'll be numbered again once it's its turn.
+                        if let Some(sequence) = streams.sequences.get_mut(&key) {
+                            *sequence -= 1;
+                        }
+                        full
+                    }
+                    Err(TrySendError::Disconnected(_)) => return Err(SendError(())),
+                }
+            }
+        };
+
+        let _turn = self.turn.lock();
+        let event = {
+            let mut streams = self.streams.lock();
+            streams.waiting = true;
+            streams.number(event)
+        };
+        let sent = self.sender.send(event).map_err(|_| SendError(()));
+        self.streams.lock().waiting = false;
+        sent
+    }
+
+    /// Sends the event if there's room for it, otherwise it's dropped.
+    pub(crate) fn try_send(&self, event: Event) -> Result<(), TrySendError<()>> {
+        let mut streams = self.streams.lock();
+        let event = streams.number(event);
+        if streams.waiting {
+            return Err(TrySendError::Full(()));
+        }
+        self.sender.try_send(event).map_err(|e| match e {
+            TrySendError::Full(_) => TrySendError::Full(()),
+            TrySendError::Disconnected(_) => TrySendError::Disconnected(()),
+        })
+    }
+}
+
+/// A channel for events, that holds up to the given number of them.
+pub(crate) fn channel(capacity: usize) -> (EventSender, EventReceiver) {
+    let (sender, receiver) = bounded(capacity);
+    let sender = EventSender {
+        sender,
+        streams: Arc::new(Mutex::new(Streams::default())),
+        turn: Arc::new(Mutex::new(())),
+    };
+
+    (sender, receiver)
+}
+
+/// Identifies an alert that has to be acknowledged, every repeat of the
+/// alert carries the same id.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct AlertId(u64);
+
+impl AlertId {
+    pub(crate) fn next() -> AlertId {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        AlertId(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl fmt::Display for AlertId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
 pub enum EventKind {
     Triggered {
         character: Arc<Character>,
+        tref: Arc<TriggerRef>,
         trigger: Arc<Trigger>,
         log: Arc<LogEvent>,
     },
-    DisplayText(Arc<String>),
+    DisplayText {
+        text: Arc<String>,
+        /// The trigger that displayed this text, if any.
+        trigger: Option<Arc<Trigger>>,
+        /// Set when the text will keep being displayed again until it's
+        /// acknowledged.
+        alert: Option<AlertId>,
+        /// The character whose log displayed this text, if any.
+        character: Option<Arc<Character>>,
+    },
+    /// Text from a trigger that collapses repeats was displayed again soon
+    /// enough to be one, so rather than being displayed again, whatever is
+    /// showing it should count it, e.g. with "x3".
+    DisplayTextRepeated {
+        text: Arc<String>,
+        trigger: Arc<Trigger>,
+        character: Arc<Character>,
+        /// How many times the text has now been displayed, counting the
+        /// first.
+        count: u32,
+    },
     Countdown {
         text: Arc<String>,
+        /// The countdown's text before `{remaining}` was filled in, which
+        /// unlike the text stays the same every time it's reported.
+        template: Arc<String>,
         duration: Duration,
         remaining: Duration,
+        category: Option<Arc<TimerCategory>>,
+        /// The character whose log started this countdown, if any.
+        character: Option<Arc<Character>>,
+        /// The icon to show alongside the countdown, either its own or its
+        /// category's.
+        icon: Option<Arc<String>>,
+        spell: Option<Arc<SpellInfo>>,
+    },
+    /// A countdown was ended early, by a line that its action said would end
+    /// it, so it won't be reported again.
+    TimerEnded {
+        /// The countdown's template, which it's known by.
+        template: Arc<String>,
+        category: Option<Arc<TimerCategory>>,
+        character: Option<Arc<Character>>,
+    },
+    /// An alert has been acknowledged, and won't be repeated any more.
+    Acknowledged { alert: AlertId },
+    /// Something was said to the raid or guild, or the guild's message of
+    /// the day was shown.
+    Broadcast {
+        character: Option<Arc<Character>>,
+        broadcast: Arc<Broadcast>,
+    },
+    /// A character has used `/loc`, and this is where they are.
+    LocationUpdated {
+        character: Option<Arc<Character>>,
+        position: Arc<Position>,
+    },
+    /// A character has started fighting something, which is whenever they
+    /// first see it take damage.
+    FightStarted {
+        character: Option<Arc<Character>>,
+        target: Arc<String>,
+    },
+    /// A fight has gone long enough without any damage that it's over, and
+    /// this is how it went.
+    FightEnded {
+        character: Option<Arc<Character>>,
+        summary: Arc<FightSummary>,
+    },
+    /// A target has resisted the same spell from a character `streak`
+    /// times in a row, which is at least as many as the configuration asks
+    /// to hear about.
+    ResistStreak {
+        character: Option<Arc<Character>>,
+        target: Arc<String>,
+        spell: Arc<String>,
+        streak: u32,
+    },
+    /// A character has stopped combining things, and this is how their
+    /// tradeskill session went.
+    TradeskillSummary {
+        character: Option<Arc<Character>>,
+        session: Arc<TradeskillSession>,
+    },
+    /// A character's farming session has ended, either because they zoned or
+    /// because they stopped picking up coin, and this is what they earned.
+    EarningsSummary {
+        character: Option<Arc<Character>>,
+        session: Arc<EarningsSession>,
+    },
+    /// Another trigger source has been loaded, once `loaded` reaches
+    /// `total` every trigger is ready.
+    LoadingProgress {
+        source: TriggerSource,
+        loaded: usize,
+        total: usize,
+    },
+    /// There's a newer release of Comrade than the one that's running.
+    UpdateAvailable { release: Arc<Release> },
+    /// Audible events are being handed out later than the configured
+    /// threshold after the lines that set them off were read.
+    Lagging {
+        latency: Duration,
+        threshold: Duration,
+    },
+    /// A `Custom` action couldn't be carried out, even after retrying it.
+    ExecutorFailed {
+        executor: Arc<String>,
+        error: Arc<String>,
+        character: Arc<Character>,
+        trigger: Arc<Trigger>,
+        /// Set when it's failed enough times in a row that the executor's
+        /// actions are being skipped until its cooldown is up.
+        tripped: bool,
     },
 }
 
-#[derive(Debug)]
+/// Something for the frontends to show, read out or keep track of.
+///
+/// Events are in streams, one for each character, and one for the events
+/// that didn't come from any one character, and every event is numbered in
+/// its stream. Each stream is handed out in order, so events from the same
+/// character are always handed out in the order they happened in, while
+/// events from different characters can be interleaved. An event that has to
+/// be dropped, because nothing is keeping up with them, leaves a gap in its
+/// stream, which `EventStreams` picks up on.
+#[derive(Debug, Clone)]
 pub struct Event {
     created: Instant,
     kind: EventKind,
+    choice: Option<usize>,
+    due: Option<Instant>,
+    sequence: u64,
 }
 
 impl Event {
@@ -36,9 +292,22 @@ impl Event {
         Event {
             created: Instant::now(),
             kind,
+            choice: None,
+            due: None,
+            sequence: 0,
         }
     }
 
+    pub(crate) fn with_choice(mut self, choice: Option<usize>) -> Event {
+        self.choice = choice;
+        self
+    }
+
+    pub(crate) fn with_due(mut self, due: Option<Instant>) -> Event {
+        self.due = due;
+        self
+    }
+
     pub fn created(&self) -> Instant {
         self.created
     }
@@ -46,4 +315,397 @@ impl Event {
     pub fn kind(&self) -> &EventKind {
         &self.kind
     }
+
+    /// Which of a random action's choices this event came from, counting
+    /// from zero, if it came from one at all.
+    pub fn choice(&self) -> Option<usize> {
+        self.choice
+    }
+
+    /// When this event was due, which is when the line that set it off was
+    /// read plus whatever delay its action had, if it was set off by a line.
+    pub fn due(&self) -> Option<Instant> {
+        self.due
+    }
+
+    /// Where this event is in its stream, counting from one, or zero for an
+    /// event that was never sent.
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+}
+
+/// How an event follows on from the last one that was seen in its stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Continuity {
+    /// It's the next event, or the first one that's been seen.
+    Next,
+    /// This many events came before it that were never seen.
+    Missed(u64),
+    /// It's been seen already, or it's older than one that has, like when
+    /// the recent events are caught up on while new ones are coming in.
+    Seen,
+}
+
+/// Keeps track of the last event seen in each stream, for anything that
+/// passes events along, like history, overlays, or syncing to another
+/// device, to tell when some never made it, rather than silently missing
+/// alerts.
+#[derive(Debug, Default, Clone)]
+pub struct EventStreams {
+    last: HashMap<StreamKey, u64>,
+}
+
+impl EventStreams {
+    pub fn new() -> EventStreams {
+        EventStreams::default()
+    }
+
+    /// Takes note of the event, returning how it follows on from the last
+    /// one seen in its stream.
+    pub fn observe(&mut self, event: &Event) -> Continuity {
+        let last = self.last.entry(stream_key(&event.kind)).or_insert(0);
+        if *last == 0 || event.sequence == *last + 1 {
+            *last = event.sequence;
+            Continuity::Next
+        } else if event.sequence <= *last {
+            Continuity::Seen
+        } else {
+            let missed = event.sequence - *last - 1;
+            *last = event.sequence;
+            Continuity::Missed(missed)
+        }
+    }
+}
+
+impl EventKind {
+    /// The name of the kind of event, like `DisplayText`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventKind::Triggered { .. } => "Triggered",
+            EventKind::DisplayText { .. } => "DisplayText",
+            EventKind::DisplayTextRepeated { .. } => "DisplayTextRepeated",
+            EventKind::Countdown { .. } => "Countdown",
+            EventKind::TimerEnded { .. } => "TimerEnded",
+            EventKind::Acknowledged { .. } => "Acknowledged",
+            EventKind::Broadcast { .. } => "Broadcast",
+            EventKind::LocationUpdated { .. } => "LocationUpdated",
+            EventKind::FightStarted { .. } => "FightStarted",
+            EventKind::FightEnded { .. } => "FightEnded",
+            EventKind::ResistStreak { .. } => "ResistStreak",
+            EventKind::TradeskillSummary { .. } => "TradeskillSummary",
+            EventKind::EarningsSummary { .. } => "EarningsSummary",
+            EventKind::LoadingProgress { .. } => "LoadingProgress",
+            EventKind::UpdateAvailable { .. } => "UpdateAvailable",
+            EventKind::Lagging { .. } => "Lagging",
+            EventKind::ExecutorFailed { .. } => "ExecutorFailed",
+        }
+    }
+
+    /// The character whose log the event came from, if any.
+    pub fn character(&self) -> Option<&Character> {
+        match self {
+            EventKind::Triggered { character, .. }
+            | EventKind::DisplayTextRepeated { character, .. }
+            | EventKind::ExecutorFailed { character, .. } => Some(character),
+            EventKind::DisplayText { character, .. }
+            | EventKind::Countdown { character, .. }
+            | EventKind::TimerEnded { character, .. }
+            | EventKind::Broadcast { character, .. }
+            | EventKind::LocationUpdated { character, .. }
+            | EventKind::FightStarted { character, .. }
+            | EventKind::FightEnded { character, .. }
+            | EventKind::ResistStreak { character, .. }
+            | EventKind::TradeskillSummary { character, .. }
+            | EventKind::EarningsSummary { character, .. } => character.as_deref(),
+            EventKind::Acknowledged { .. }
+            | EventKind::LoadingProgress { .. }
+            | EventKind::UpdateAvailable { .. }
+            | EventKind::Lagging { .. } => None,
+        }
+    }
+}
+
+/// Narrows down which of the recent events are of interest.
+#[derive(Debug, Default, Clone)]
+pub struct EventFilter {
+    /// Only events from these characters' logs, by name ignoring case, or
+    /// every event if there are none.
+    pub characters: Vec<String>,
+    /// Only these kinds of event, by name, or every kind if there are none.
+    pub kinds: Vec<String>,
+    /// Only events created after this.
+    pub since: Option<Instant>,
+    /// Only this many of the most recent events that match.
+    pub limit: Option<usize>,
+}
+
+impl EventFilter {
+    fn matches(&self, event: &Event) -> bool {
+        if !self.characters.is_empty() {
+            match event.kind.character() {
+                Some(character)
+                    if self
+                        .characters
+                        .iter()
+                        .any(|c| c.eq_ignore_ascii_case(&character.name)) => {}
+                _ => return false,
+            }
+        }
+
+        if !self.kinds.is_empty() && !self.kinds.iter().any(|k| k == event.kind.name()) {
+            return false;
+        }
+
+        self.since.is_none_or(|since| event.created > since)
+    }
+}
+
+/// The most recent events that have been handed to the frontend, so that
+/// anything attaching late, like a control API client or an overlay that's
+/// reconnecting, has something to show straight away.
+#[derive(Debug, Default)]
+pub(crate) struct RecentEvents {
+    events: Mutex<VecDeque<Event>>,
+}
+
+impl RecentEvents {
+    /// Records an event, letting go of the oldest ones past the capacity.
+    pub(crate) fn record(&self, event: &Event, capacity: usize) {
+        let mut events = self.events.lock();
+
+        // A countdown is reported every tick while it runs, which would push
+        // everything else out, so only the latest report of each is kept, and
+        // once it's been ended early there's nothing of it to keep at all.
+        if let EventKind::Countdown {
+            template,
+            character,
+            ..
+        }
+        | EventKind::TimerEnded {
+            template,
+            character,
+            ..
+        } = &event.kind
+        {
+            events.retain(|e| match &e.kind {
+                EventKind::Countdown {
+                    template: t,
+                    character: c,
+                    ..
+                } => {
+                    !(t == template
+                        && c.as_ref().map(|c| &c.name) == character.as_ref().map(|c| &c.name))
+                }
+                _ => true,
+            });
+        }
+
+        events.push_back(event.clone());
+        while events.len() > capacity {
+            events.pop_front();
+        }
+    }
+
+    /// The recent events that match the filter, oldest first.
+    pub(crate) fn matching(&self, filter: &EventFilter) -> Vec<Event> {
+        let events = self.events.lock();
+        let mut matching: Vec<Event> = events
+            .iter()
+            .rev()
+            .filter(|e| filter.matches(e))
+            .take(filter.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect();
+        matching.reverse();
+
+        matching
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::thread;
+
+    use super::*;
+    use crate::config::ClockOffset;
+
+    fn character(name: &str) -> Arc<Character> {
+        Arc::new(Character {
+            name: name.to_string(),
+            server: "teek".to_string(),
+            filename: PathBuf::new(),
+            aliases: Vec::new(),
+            display_name: None,
+            clock_offset: ClockOffset::default(),
+            language: None,
+            voice: None,
+            disabled_triggers: HashMap::new(),
+            enabled_triggers: HashMap::new(),
+        })
+    }
+
+    fn countdown(character: &Arc<Character>, remaining: u64) -> Event {
+        Event::new(EventKind::Countdown {
+            text: Arc::new(format!("Mez {}", remaining)),
+            template: Arc::new("Mez {remaining}".to_string()),
+            duration: Duration::from_secs(10),
+            remaining: Duration::from_secs(remaining),
+            category: None,
+            character: Some(character.clone()),
+            icon: None,
+            spell: None,
+        })
+    }
+
+    #[test]
+    fn keeps_the_most_recent_events() {
+        let (soandso, xanthe) = (character("Soandso"), character("Xanthe"));
+        let recent = RecentEvents::default();
+        for n in 0..5 {
+            recent.record(
+                &Event::new(EventKind::DisplayText {
+                    text: Arc::new(format!("Tell {}", n)),
+                    trigger: None,
+                    alert: None,
+                    character: Some(soandso.clone()),
+                }),
+                4,
+            );
+        }
+        recent.record(&countdown(&xanthe, 10), 4);
+        recent.record(&countdown(&xanthe, 9), 4);
+
+        let all = recent.matching(&EventFilter::default());
+        let texts: Vec<&str> = all
+            .iter()
+            .map(|e| match e.kind() {
+                EventKind::DisplayText { text, .. } | EventKind::Countdown { text, .. } => {
+                    text.as_str()
+                }
+                _ => "",
+            })
+            .collect();
+        assert_eq!(texts, ["Tell 2", "Tell 3", "Tell 4", "Mez 9"]);
+
+        let filter = EventFilter {
+            characters: vec!["soandso".to_string()],
+            limit: Some(2),
+            ..EventFilter::default()
+        };
+        assert_eq!(recent.matching(&filter).len(), 2);
+
+        let filter = EventFilter {
+            kinds: vec!["Countdown".to_string()],
+            ..EventFilter::default()
+        };
+        assert_eq!(recent.matching(&filter).len(), 1);
+
+        let filter = EventFilter {
+            since: Some(all[3].created()),
+            ..EventFilter::default()
+        };
+        assert!(recent.matching(&filter).is_empty());
+    }
+
+    #[test]
+    fn numbers_each_stream() {
+        let (soandso, xanthe) = (character("Soandso"), character("Xanthe"));
+        let (sender, receiver) = channel(3);
+        let display = |character: &Arc<Character>| {
+            Event::new(EventKind::DisplayText {
+                text: Arc::new("Tell".to_string()),
+                trigger: None,
+                alert: None,
+                character: Some(character.clone()),
+            })
+        };
+        sender.try_send(display(&soandso)).unwrap();
+        sender.try_send(display(&xanthe)).unwrap();
+        sender.try_send(display(&soandso)).unwrap();
+        // There's no room for this one, so it's dropped.
+        assert!(sender.try_send(display(&soandso)).is_err());
+
+        let mut streams = EventStreams::new();
+        let received: Vec<Event> = receiver.try_iter().collect();
+        let sequences: Vec<u64> = received.iter().map(|e| e.sequence()).collect();
+        assert_eq!(sequences, [1, 1, 2]);
+        for event in received.iter() {
+            assert_eq!(streams.observe(event), Continuity::Next);
+        }
+
+        sender.try_send(display(&soandso)).unwrap();
+        let next = receiver.try_recv().unwrap();
+        assert_eq!(streams.observe(&next), Continuity::Missed(1));
+        assert_eq!(streams.observe(&received[2]), Continuity::Seen);
+    }
+
+    #[test]
+    fn waiting_for_room_leaves_other_senders_alone() {
+        let soandso = character("Soandso");
+        let (sender, receiver) = channel(1);
+        let lagging = || {
+            Event::new(EventKind::Lagging {
+                latency: Duration::from_secs(3),
+                threshold: Duration::from_secs(1),
+            })
+        };
+        sender.send(countdown(&soandso, 3)).unwrap();
+
+        // The channel's full, so this waits, like the driver would with a
+        // frontend that's fallen behind.
+        let waiting = {
+            let sender = sender.clone();
+            let soandso = soandso.clone();
+            thread::spawn(move || sender.send(countdown(&soandso, 2)))
+        };
+        thread::sleep(Duration::from_millis(20));
+
+        // While the frontend, which is sending one of its own, isn't held up
+        // by it.
+        assert!(matches!(
+            sender.try_send(lagging()),
+            Err(TrySendError::Full(()))
+        ));
+        let sequences: Vec<u64> = (0..2)
+            .map(|_| receiver.recv_timeout(Duration::from_secs(1)).unwrap())
+            .map(|e| e.sequence())
+            .collect();
+        waiting.join().unwrap().unwrap();
+        assert_eq!(sequences, [1, 2]);
+    }
+
+    #[test]
+    fn sends_that_wait_for_room_keep_their_order() {
+        let soandso = character("Soandso");
+        let (sender, receiver) = channel(1);
+        sender.send(countdown(&soandso, 3)).unwrap();
+
+        let mut waiting = Vec::new();
+        for remaining in [2, 1] {
+            let sender = sender.clone();
+            let soandso = soandso.clone();
+            waiting.push(thread::spawn(move || {
+                sender.send(countdown(&soandso, remaining))
+            }));
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        let received: Vec<(u64, Duration)> = (0..3)
+            .map(|_| receiver.recv_timeout(Duration::from_secs(1)).unwrap())
+            .map(|e| match e.kind() {
+                EventKind::Countdown { remaining, .. } => (e.sequence(), *remaining),
+                kind => panic!("unexpected event: {:?}", kind),
+            })
+            .collect();
+        for waiting in waiting {
+            waiting.join().unwrap().unwrap();
+        }
+        assert_eq!(
+            received,
+            [1, 2, 3].map(|n| (n, Duration::from_secs(4 - n))).to_vec()
+        );
+    }
 }
@@ -0,0 +1,497 @@
+//! Action Executors
+//!
+//! Only `Custom` actions go through executors, which whatever is embedding
+//! Comrade registers by name at runtime, and each `Custom` action names the
+//! executor that carries it out, for things that happen away from any
+//! frontend, like calling a webhook or running a script.
+//!
+//! The built in kinds of action, like displaying text, countdowns, waypoints
+//! and signals, aren't executors, and are still carried out by
+//! `Action::events`. They end up as events for the frontends to show or read
+//! out, in order with everything else the driver sends, and they depend on
+//! the driver's own state, like where each character last was, so they can't
+//! be handed off to a worker thread the way executors are.
+//!
+//! This keeps integrations that need dependencies of their own, or that not
+//! every embedder wants, out of the way. The only one that comes with
+//! Comrade, `Webhook`, is behind the `webhooks` feature, and no executor runs
+//! unless it's been registered.
+//!
+//! Each executor gets a queue and a worker thread of its own, so one that's
+//! slow or down never holds up the driver, or any other executor. Attempts
+//! that fail or run past their timeout are retried, and once enough actions
+//! in a row have failed the executor is left alone for a cooldown, with its
+//! actions skipped rather than queued. Failures are sent as
+//! `ExecutorFailed` events, for the frontends to warn about.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::error::Error;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, Receiver, RecvTimeoutError, Sender, TrySendError};
+use log::{debug, warn};
+use parking_lot::{Mutex, RwLock};
+
+use crate::config::executors::ExecutorConfig;
+use crate::config::triggers::Trigger;
+use crate::config::{Character, CharacterId};
+use crate::events::{Event, EventKind, EventSender};
+use crate::time::Instant;
+
+/// What went wrong carrying out an action, which is logged.
+pub type ExecutorError = Box<dyn Error + Send + Sync>;
+
+/// Carries out `Custom` actions. Each executor has a worker thread of its
+/// own that waits on `execute`, for as long as the executor's timeout, so it
+/// can block on things like network requests.
+pub trait ActionExecutor: Send + Sync {
+    fn execute(&self, request: &ExecutorRequest) -> Result<(), ExecutorError>;
+}
+
+impl<F> ActionExecutor for F
+where
+    F: Fn(&ExecutorRequest) -> Result<(), ExecutorError> + Send + Sync,
+{
+    fn execute(&self, request: &ExecutorRequest) -> Result<(), ExecutorError> {
+        self(request)
+    }
+}
+
+/// A `Custom` action that's due to be carried out.
+#[derive(Debug, Clone)]
+pub struct ExecutorRequest {
+    /// The character whose log matched.
+    pub id: Arc<CharacterId>,
+    pub character: Arc<Character>,
+    pub trigger: Arc<Trigger>,
+    /// The action's options, with whatever the trigger captured expanded.
+    pub options: BTreeMap<String, String>,
+}
+
+/// The executors that have been registered, by name, which is shared between
+/// Comrade and its driver.
+#[derive(Clone, Default)]
+pub(crate) struct Executors {
+    registered: Arc<RwLock<HashMap<String, Arc<dyn ActionExecutor>>>>,
+    // The executors that actions have asked for without them having been
+    // registered, so that's only warned about the once.
+    missing: Arc<Mutex<HashSet<String>>>,
+    workers: Arc<Mutex<Workers>>,
+}
+
+#[derive(Default)]
+struct Workers {
+    configs: BTreeMap<String, ExecutorConfig>,
+    running: HashMap<String, Worker>,
+    // Where failures are reported, once the driver has started.
+    events: Option<EventSender>,
+}
+
+struct Worker {
+    config: ExecutorConfig,
+    requests: Sender<ExecutorRequest>,
+    circuit: Arc<Mutex<Circuit>>,
+}
+
+/// How many actions in a row an executor has failed, and whether they're
+/// being skipped because of it.
+#[derive(Debug, Default)]
+struct Circuit {
+    failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl Circuit {
+    fn is_open(&self) -> bool {
+        self.open_until.is_some_and(|until| Instant::now() < until)
+    }
+
+    /// Counts an action's outcome, returning whether the executor's actions
+    /// are going to be skipped because of it.
+    fn record(&mut self, config: &ExecutorConfig, succeeded: bool) -> bool {
+        if succeeded {
+            self.failures = 0;
+            self.open_until = None;
+            return false;
+        }
+
+        // Once the cooldown is up only one action is tried, and if that
+        // fails too it's straight back to skipping them.
+        self.failures += 1;
+        let tripped = config.failures > 0 && self.failures >= config.failures;
+        if tripped {
+            self.open_until = Some(Instant::now() + config.cooldown);
+        }
+        tripped
+    }
+}
+
+impl Executors {
+    pub(crate) fn register(&self, name: &str, executor: Arc<dyn ActionExecutor>) {
+        self.missing.lock().remove(name);
+        self.registered.write().insert(name.to_string(), executor);
+        // Dropping the old worker hangs up on its thread, which lets it
+        // finish what's already queued.
+        self.workers.lock().running.remove(name);
+    }
+
+    pub(crate) fn unregister(&self, name: &str) -> bool {
+        self.workers.lock().running.remove(name);
+        self.registered.write().remove(name).is_some()
+    }
+
+    /// Sets where failures are reported.
+    pub(crate) fn connect(&self, events: EventSender) {
+        self.workers.lock().events = Some(events);
+    }
+
+    /// Keeps up with the executors in the configuration, which can change
+    /// whenever it's reloaded.
+    pub(crate) fn configure(&self, configs: &BTreeMap<String, ExecutorConfig>) {
+        let mut workers = self.workers.lock();
+        if workers.configs != *configs {
+            workers.configs = configs.clone();
+        }
+    }
+
+    pub(crate) fn execute(&self, name: &str, request: &ExecutorRequest) {
+        let executor = match self.registered.read().get(name) {
+            Some(executor) => executor.clone(),
+            None => {
+                if self.missing.lock().insert(name.to_string()) {
+                    warn!("no executor named {:?} has been registered", name);
+                }
+                return;
+            }
+        };
+
+        let mut workers = self.workers.lock();
+        let config = workers.configs.get(name).cloned().unwrap_or_default();
+        if workers.running.get(name).is_none_or(|w| w.config != config) {
+            let worker = start(name, executor, config, workers.events.clone());
+            workers.running.insert(name.to_string(), worker);
+        }
+
+        let worker = workers.running.get(name).expect("worker was just started");
+        if worker.circuit.lock().is_open() {
+            debug!("skipping executor {:?} until its cooldown is up", name);
+            return;
+        }
+        match worker.requests.try_send(request.clone()) {
+            Ok(()) => {}
+            Err(TrySendError::Full(request)) => {
+                let error = format!("{} actions are already waiting", worker.config.queue);
+                report(workers.events.as_ref(), name, &request, error, false);
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                warn!(
+                    "executor {:?} has stopped, starting it again next time",
+                    name
+                );
+                workers.running.remove(name);
+            }
+        }
+    }
+}
+
+fn start(
+    name: &str,
+    executor: Arc<dyn ActionExecutor>,
+    config: ExecutorConfig,
+    events: Option<EventSender>,
+) -> Worker {
+    let (requests, receiver) = bounded(config.queue);
+    let circuit = Arc::new(Mutex::new(Circuit::default()));
+
+    let owned = name.to_string();
+    let settings = config.clone();
+    let counted = circuit.clone();
+    let spawned = thread::Builder::new()
+        .name(format!("comrade executor {}", name))
+        .spawn(move || {
+            work(
+                owned.as_str(),
+                executor,
+                &settings,
+                &counted,
+                receiver,
+                events,
+            )
+        });
+    if let Err(e) = spawned {
+        warn!("could not start executor {:?}: {}", name, e);
+    }
+
+    Worker {
+        config,
+        requests,
+        circuit,
+    }
+}
+
+fn work(
+    name: &str,
+    executor: Arc<dyn ActionExecutor>,
+    config: &ExecutorConfig,
+    circuit: &Mutex<Circuit>,
+    requests: Receiver<ExecutorRequest>,
+    events: Option<EventSender>,
+) {
+    for request in requests.iter() {
+        // Whatever was queued before the executor started failing is
+        // skipped along with everything else.
+        if circuit.lock().is_open() {
+            continue;
+        }
+
+        let request = Arc::new(request);
+        let mut backoff = config.backoff;
+        let mut retries = config.retries;
+        let result = loop {
+            match attempt(&executor, &request, config.timeout) {
+                Ok(()) => break Ok(()),
+                Err(e) if retries > 0 => {
+                    debug!("executor {:?} failed, retrying: {}", name, e);
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                    retries -= 1;
+                }
+                Err(e) => break Err(e),
+            }
+        };
+
+        let tripped = circuit.lock().record(config, result.is_ok());
+        if let Err(error) = result {
+            report(events.as_ref(), name, &request, error, tripped);
+        }
+    }
+}
+
+/// Carries out the request once, on a thread of its own so that it can be
+/// given up on once the timeout is up, even though the thread carries on
+/// until the executor returns.
+fn attempt(
+    executor: &Arc<dyn ActionExecutor>,
+    request: &Arc<ExecutorRequest>,
+    timeout: Duration,
+) -> Result<(), String> {
+    let (sender, result) = bounded(1);
+    let executor = executor.clone();
+    let request = request.clone();
+    thread::Builder::new()
+        .name("comrade executor attempt".to_string())
+        .spawn(move || {
+            let _ = sender.send(executor.execute(&request).map_err(|e| e.to_string()));
+        })
+        .map_err(|e| e.to_string())?;
+
+    match result.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => {
+            Err(format!("timed out after {:.1}s", timeout.as_secs_f64()))
+        }
+        Err(RecvTimeoutError::Disconnected) => Err("panicked".to_string()),
+    }
+}
+
+fn report(
+    events: Option<&EventSender>,
+    name: &str,
+    request: &ExecutorRequest,
+    error: String,
+    tripped: bool,
+) {
+    warn!("executor {:?} failed: {}", name, error);
+    if let Some(events) = events {
+        let event = Event::new(EventKind::ExecutorFailed {
+            executor: Arc::new(name.to_string()),
+            error: Arc::new(error),
+            character: request.character.clone(),
+            trigger: request.trigger.clone(),
+            tripped,
+        });
+        if let Err(e) = events.try_send(event) {
+            debug!("dropped executor failure: {:?}", e);
+        }
+    }
+}
+
+/// Posts the `body` option to the `url` option, failing when the response
+/// is an error.
+#[cfg(feature = "webhooks")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Webhook;
+
+#[cfg(feature = "webhooks")]
+impl ActionExecutor for Webhook {
+    fn execute(&self, request: &ExecutorRequest) -> Result<(), ExecutorError> {
+        let url = request.options.get("url").ok_or("a webhook needs a url")?;
+        let body = request.options.get("body").cloned().unwrap_or_default();
+        ureq::post(url.as_str()).send_string(body.as_str())?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::config::ClockOffset;
+
+    fn request() -> ExecutorRequest {
+        ExecutorRequest {
+            id: Arc::new(CharacterId::new("soandso")),
+            character: Arc::new(Character {
+                name: "Soandso".to_string(),
+                server: "teek".to_string(),
+                filename: PathBuf::new(),
+                aliases: Vec::new(),
+                display_name: None,
+                clock_offset: ClockOffset::default(),
+                language: None,
+                voice: None,
+                disabled_triggers: HashMap::new(),
+                enabled_triggers: HashMap::new(),
+            }),
+            trigger: Arc::new(
+                toml_edit::de::from_str(
+                    "name = 'Tell'\n\
+                     search_text = '^(\\w+) tells you'\n\
+                     actions = [{ type = 'Custom', executor = 'record', options = { who = '$1' } }]",
+                )
+                .unwrap(),
+            ),
+            options: BTreeMap::from([("who".to_string(), "Xanthe".to_string())]),
+        }
+    }
+
+    /// Registers an executor named `record` that sends who each action was
+    /// for, returning where they're sent.
+    fn record(executors: &Executors) -> Receiver<String> {
+        let (sender, executed) = bounded(1);
+        executors.register(
+            "record",
+            Arc::new(
+                move |request: &ExecutorRequest| -> Result<(), ExecutorError> {
+                    sender.send(request.options["who"].clone())?;
+                    Ok(())
+                },
+            ),
+        );
+        executed
+    }
+
+    /// Registers an executor named `down` that always fails, counting how
+    /// many times it's been called.
+    fn down(executors: &Executors, config: ExecutorConfig, slow: bool) -> Arc<Mutex<u32>> {
+        executors.configure(&BTreeMap::from([("down".to_string(), config)]));
+        let calls = Arc::new(Mutex::new(0));
+        let counted = calls.clone();
+        executors.register(
+            "down",
+            Arc::new(move |_: &ExecutorRequest| -> Result<(), ExecutorError> {
+                *counted.lock() += 1;
+                // The first attempt takes longer than it's allowed to.
+                if slow && *counted.lock() == 1 {
+                    thread::sleep(Duration::from_millis(500));
+                }
+                Err("bad gateway".into())
+            }),
+        );
+        calls
+    }
+
+    fn config(failures: u32) -> ExecutorConfig {
+        ExecutorConfig {
+            timeout: Duration::from_millis(50),
+            retries: 1,
+            backoff: Duration::from_millis(1),
+            failures,
+            cooldown: Duration::from_secs(3600),
+            queue: 1,
+        }
+    }
+
+    /// Whether the event is a failure of `down`, and if it tripped it.
+    fn tripped(event: &Event) -> bool {
+        match event.kind() {
+            EventKind::ExecutorFailed {
+                executor,
+                error,
+                tripped,
+                ..
+            } => {
+                assert_eq!(executor.as_str(), "down");
+                assert_eq!(error.as_str(), "bad gateway");
+                *tripped
+            }
+            kind => panic!("unexpected event: {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn executes_whatever_is_registered() {
+        let executors = Executors::default();
+        let executed = record(&executors);
+
+        executors.execute("record", &request());
+        let who = executed.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(who, "Xanthe");
+    }
+
+    #[test]
+    fn skips_executors_that_are_not_registered() {
+        let executors = Executors::default();
+        let executed = record(&executors);
+
+        executors.execute("missing", &request());
+        assert!(executed.recv_timeout(Duration::from_millis(100)).is_err());
+        assert!(!executors.unregister("missing"));
+    }
+
+    #[test]
+    fn stops_executing_once_unregistered() {
+        let executors = Executors::default();
+        let executed = record(&executors);
+
+        assert!(executors.unregister("record"));
+        executors.execute("record", &request());
+        assert!(executed.recv_timeout(Duration::from_millis(100)).is_err());
+    }
+
+    #[test]
+    fn retries_failing_executors() {
+        let executors = Executors::default();
+        let (events, failures) = crate::events::channel(10);
+        executors.connect(events);
+        let calls = down(&executors, config(2), true);
+
+        executors.execute("down", &request());
+        let event = failures.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(!tripped(&event));
+        // Timing out counts as failing, and is retried as well.
+        assert_eq!(*calls.lock(), 2);
+    }
+
+    #[test]
+    fn skips_executors_that_keep_failing() {
+        let executors = Executors::default();
+        let (events, failures) = crate::events::channel(10);
+        executors.connect(events);
+        let calls = down(&executors, config(1), false);
+
+        executors.execute("down", &request());
+        let event = failures.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(tripped(&event));
+        assert_eq!(*calls.lock(), 2);
+
+        // Now that it's tripped, its actions are skipped.
+        executors.execute("down", &request());
+        assert!(failures.recv_timeout(Duration::from_millis(100)).is_err());
+        assert_eq!(*calls.lock(), 2);
+    }
+}
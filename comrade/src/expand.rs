@@ -0,0 +1,378 @@
+//! Expansions
+//!
+//! The text of a trigger's actions can refer to what its search text captured,
+//! with `$1`, `${name}` or `{name}`, to the line itself, with `{C}` for the
+//! character's name, `{S}` for their server and `{TS}` for when the line was
+//! logged, and on top of that can work things out from them between braces,
+//! like `{$1 * 2}` or `{duration($seconds - 5)}`. Anything in braces that
+//! isn't one of those, or an expression that can be worked out, because it
+//! refers to something that isn't a number or just isn't one at all, is left
+//! as it is, so that text with braces of its own isn't mangled.
+//!
+//! Expressions are made up of numbers, captures, the variables that an action
+//! provides, like `remaining_s` for countdowns, and `+`, `-`, `*`, `/`, `%`
+//! and parentheses, along with a couple of functions for formatting seconds:
+//! `duration(s)`, which says `1 minute 30 seconds`, and `clock(s)`, which
+//! says `1:30`.
+
+use std::fmt::Write;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use regex::Captures;
+
+use crate::config::Character;
+use crate::watcher::LogEvent;
+
+/// What's known about the line that text is being expanded for, apart from
+/// what it captured.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Builtins<'a> {
+    pub(crate) character: Option<&'a Character>,
+    pub(crate) event: Option<&'a LogEvent>,
+}
+
+impl<'a> Builtins<'a> {
+    pub(crate) fn new(character: &'a Character, event: &'a LogEvent) -> Builtins<'a> {
+        Builtins {
+            character: Some(character),
+            event: Some(event),
+        }
+    }
+
+    /// The value of the built in variable with the given name, `{character}`
+    /// is the name that `If` conditions had for the character before `{C}`.
+    fn get(&self, name: &str) -> Option<&'a str> {
+        match name {
+            "C" | "character" => self.character.map(|c| c.name.as_str()),
+            "S" => self.character.map(|c| c.server.as_str()),
+            "TS" => self.event.map(|e| e.timestamp()),
+            _ => None,
+        }
+    }
+}
+
+/// Expands the given text, with the given variables available to any
+/// expressions in it on top of the captures.
+pub(crate) fn expand(
+    text: &str,
+    caps: &Captures,
+    builtins: &Builtins,
+    variables: &[(&str, f64)],
+) -> String {
+    let mut expanded = String::new();
+    let mut rest = text;
+    while let Some(start) = find_expression(rest) {
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => break,
+        };
+
+        let source = &rest[start + 1..end];
+        let value = builtins
+            .get(source)
+            .or_else(|| caps.name(source).map(|m| m.as_str()))
+            .map(str::to_string)
+            .or_else(|| Parser::new(source, caps, variables).parse());
+        match value {
+            Some(value) => {
+                caps.expand(&rest[..start], &mut expanded);
+                expanded.push_str(value.as_str());
+            }
+            None => caps.expand(&rest[..=end], &mut expanded),
+        }
+        rest = &rest[end + 1..];
+    }
+    caps.expand(rest, &mut expanded);
+
+    expanded
+}
+
+/// Where the next brace that could start an expression is, skipping over
+/// the ones that are part of `${name}` and `$$`.
+fn find_expression(text: &str) -> Option<usize> {
+    let mut chars = text.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '$' => {
+                if let Some((_, '{' | '$')) = chars.peek() {
+                    chars.next();
+                }
+            }
+            '{' => return Some(idx),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 {
+        format!("{}", value as i64)
+    } else {
+        let formatted = format!("{:.2}", value);
+        formatted.trim_end_matches('0').to_string()
+    }
+}
+
+fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.max(0.0).round() as u64;
+    let mut formatted = String::new();
+    for (amount, unit) in [
+        (seconds / 3600, "hour"),
+        (seconds % 3600 / 60, "minute"),
+        (seconds % 60, "second"),
+    ] {
+        if amount == 0 {
+            continue;
+        }
+        if !formatted.is_empty() {
+            formatted.push(' ');
+        }
+        let plural = if amount == 1 { "" } else { "s" };
+        let _ = write!(formatted, "{} {}{}", amount, unit, plural);
+    }
+
+    if formatted.is_empty() {
+        formatted.push_str("0 seconds");
+    }
+    formatted
+}
+
+fn format_clock(seconds: f64) -> String {
+    let seconds = seconds.max(0.0).round() as u64;
+    match seconds / 3600 {
+        0 => format!("{}:{:02}", seconds / 60, seconds % 60),
+        hours => format!("{}:{:02}:{:02}", hours, seconds % 3600 / 60, seconds % 60),
+    }
+}
+
+/// A value that an expression works out to, functions that format seconds
+/// work out to text, which can only be displayed and not worked with.
+enum Value {
+    Number(f64),
+    Text(String),
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    caps: &'a Captures<'a>,
+    variables: &'a [(&'a str, f64)],
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str, caps: &'a Captures<'a>, variables: &'a [(&'a str, f64)]) -> Self {
+        Parser {
+            chars: source.chars().peekable(),
+            caps,
+            variables,
+        }
+    }
+
+    fn parse(mut self) -> Option<String> {
+        let value = self.value()?;
+        self.skip_whitespace();
+        if self.chars.next().is_some() {
+            return None;
+        }
+
+        match value {
+            Value::Number(n) => Some(format_number(n)),
+            Value::Text(text) => Some(text),
+        }
+    }
+
+    /// A whole expression, which is either a function that formats, or
+    /// something to work out.
+    fn value(&mut self) -> Option<Value> {
+        self.skip_whitespace();
+        let mut lookahead = self.chars.clone();
+        let name: String =
+            std::iter::from_fn(|| lookahead.next_if(|c| c.is_alphabetic())).collect();
+        let format = match name.as_str() {
+            "duration" => format_duration,
+            "clock" => format_clock,
+            _ => return self.sum().map(Value::Number),
+        };
+
+        self.chars = lookahead;
+        self.skip_whitespace();
+        if self.chars.next() != Some('(') {
+            return None;
+        }
+        let seconds = self.sum()?;
+        self.skip_whitespace();
+        if self.chars.next() != Some(')') {
+            return None;
+        }
+
+        Some(Value::Text(format(seconds)))
+    }
+
+    fn sum(&mut self) -> Option<f64> {
+        let mut value = self.product()?;
+        loop {
+            self.skip_whitespace();
+            match self.chars.peek() {
+                Some('+') => {
+                    self.chars.next();
+                    value += self.product()?;
+                }
+                Some('-') => {
+                    self.chars.next();
+                    value -= self.product()?;
+                }
+                _ => return Some(value),
+            }
+        }
+    }
+
+    fn product(&mut self) -> Option<f64> {
+        let mut value = self.factor()?;
+        loop {
+            self.skip_whitespace();
+            let op = match self.chars.peek() {
+                Some(op @ ('*' | '/' | '%')) => *op,
+                _ => return Some(value),
+            };
+            self.chars.next();
+
+            let rhs = self.factor()?;
+            value = match op {
+                '*' => value * rhs,
+                _ if rhs == 0.0 => return None,
+                '/' => value / rhs,
+                _ => value % rhs,
+            };
+        }
+    }
+
+    fn factor(&mut self) -> Option<f64> {
+        self.skip_whitespace();
+        match self.chars.next()? {
+            '-' => self.factor().map(|v| -v),
+            '(' => {
+                let value = self.sum()?;
+                self.skip_whitespace();
+                (self.chars.next()? == ')').then_some(value)
+            }
+            '$' => {
+                let name = self.word();
+                self.caps
+                    .name(name.as_str())
+                    .or_else(|| name.parse().ok().and_then(|i| self.caps.get(i)))
+                    .and_then(|m| m.as_str().trim().replace(',', "").parse().ok())
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let mut number = c.to_string();
+                while let Some(c) = self.chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+                    number.push(c);
+                }
+                number.parse().ok()
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let name = format!("{}{}", c, self.word());
+                self.variables
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, value)| *value)
+            }
+            _ => None,
+        }
+    }
+
+    fn word(&mut self) -> String {
+        std::iter::from_fn(|| self.chars.next_if(|c| c.is_alphanumeric() || *c == '_')).collect()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use regex::Regex;
+
+    use crate::config::CharacterId;
+
+    use super::*;
+
+    fn expand_line(text: &str, line: &str, variables: &[(&str, f64)]) -> String {
+        let re = Regex::new(r"^(?P<who>\w+) begins casting in (?P<seconds>\d+) seconds$").unwrap();
+        expand(
+            text,
+            &re.captures(line).unwrap(),
+            &Builtins::default(),
+            variables,
+        )
+    }
+
+    #[test]
+    fn works_out_expressions() {
+        let line = "Vulak begins casting in 45 seconds";
+        assert_eq!(
+            expand_line("$who casts in {$seconds - 5}s", line, &[]),
+            "Vulak casts in 40s"
+        );
+        assert_eq!(expand_line("{(1 + 2) * -$2 / 2}", line, &[]), "-67.5");
+        assert_eq!(
+            expand_line("in {duration($seconds * 2)}", line, &[]),
+            "in 1 minute 30 seconds"
+        );
+        assert_eq!(
+            expand_line(
+                "{clock(remaining_s)} left",
+                line,
+                &[("remaining_s", 3725.0)]
+            ),
+            "1:02:05 left"
+        );
+    }
+
+    #[test]
+    fn leaves_everything_else_alone() {
+        let line = "Vulak begins casting in 45 seconds";
+        assert_eq!(
+            expand_line("{C} ${who} {$who * 2} {1 / 0} $$ {", line, &[]),
+            "{C} Vulak {Vulak * 2} {1 / 0} $ {"
+        );
+        assert_eq!(expand_line("{unknown} {}", line, &[]), "{unknown} {}");
+    }
+
+    #[test]
+    fn expands_named_groups_and_builtins() {
+        let line = "Vulak begins casting in 45 seconds";
+        let character = Character {
+            name: "Soandso".to_string(),
+            server: "teek".to_string(),
+            filename: Default::default(),
+            aliases: Vec::new(),
+            display_name: None,
+            clock_offset: Default::default(),
+            language: None,
+            voice: None,
+            disabled_triggers: Default::default(),
+            enabled_triggers: Default::default(),
+        };
+        let event = LogEvent::parse(
+            Arc::new(CharacterId::new("soandso")),
+            format!("[Sat Oct 17 20:15:00 2026] {}", line).as_str(),
+        )
+        .unwrap();
+        let re = Regex::new(r"^(?P<who>\w+) begins casting in (?P<seconds>\d+) seconds$").unwrap();
+
+        assert_eq!(
+            expand(
+                "[{TS}] {who} on {C}@{S}, {character} has {$seconds - 5}s",
+                &re.captures(event.message()).unwrap(),
+                &Builtins::new(&character, &event),
+                &[],
+            ),
+            "[Sat Oct 17 20:15:00 2026] Vulak on Soandso@teek, Soandso has 40s"
+        );
+    }
+}
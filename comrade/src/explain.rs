@@ -0,0 +1,145 @@
+//! Pattern Explanations
+//!
+//! Getting a trigger's expansions right means knowing what each of its
+//! capture groups is going to hold, which is a lot easier to see than to work
+//! out from the regex. A pattern is broken down into its capture groups, by
+//! number and by name along with the part of the pattern that each one is,
+//! and can be run against log lines to show what each group captured.
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::errors::TriggerError;
+
+/// A capture group in a pattern.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CaptureGroup {
+    /// The group's number, which `$1` and the like refer to it by.
+    pub index: usize,
+    pub name: Option<String>,
+    /// The part of the pattern that the group is, parentheses and all.
+    pub pattern: String,
+}
+
+/// What each of a pattern's capture groups captured from a line.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CapturePreview {
+    /// Whose log the line is from, when it came from one.
+    pub character: Option<String>,
+    pub timestamp: Option<String>,
+    pub message: String,
+    /// What each group captured, in the same order as the groups, which is
+    /// nothing for a group that didn't take part in the match.
+    pub captures: Vec<Option<String>>,
+}
+
+/// Breaks the given pattern down into its capture groups.
+pub(crate) fn explain(pattern: &str) -> Result<Vec<CaptureGroup>, TriggerError> {
+    let regex = Regex::new(pattern)?;
+    let sources = group_sources(pattern);
+
+    Ok(regex
+        .capture_names()
+        .enumerate()
+        .skip(1)
+        .map(|(index, name)| CaptureGroup {
+            index,
+            name: name.map(|n| n.to_string()),
+            pattern: sources.get(index - 1).cloned().unwrap_or_default(),
+        })
+        .collect())
+}
+
+/// What each of the regex's groups captured from the given message, if it
+/// matches at all.
+pub(crate) fn capture(regex: &Regex, message: &str) -> Option<Vec<Option<String>>> {
+    let caps = regex.captures(message)?;
+    Some(
+        caps.iter()
+            .skip(1)
+            .map(|m| m.map(|m| m.as_str().to_string()))
+            .collect(),
+    )
+}
+
+/// The source of each capture group in the pattern, in the order that they
+/// open, which is the order that they're numbered in. Escapes and character
+/// classes are skipped over, so that the parentheses in them aren't counted.
+fn group_sources(pattern: &str) -> Vec<String> {
+    let mut sources: Vec<String> = Vec::new();
+    // Where each group that's open started, and which capture group it is,
+    // if it is one.
+    let mut open: Vec<(usize, Option<usize>)> = Vec::new();
+    let mut class = 0;
+
+    let mut chars = pattern.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' => {
+                class += 1;
+                // A `]` right at the start of a class is part of it.
+                chars.next_if(|(_, c)| *c == '^');
+                chars.next_if(|(_, c)| *c == ']');
+            }
+            ']' if class > 0 => class -= 1,
+            _ if class > 0 => {}
+            '(' => {
+                let rest = &pattern[idx + 1..];
+                let capturing = !rest.starts_with('?')
+                    || rest.starts_with("?P<")
+                    || (rest.starts_with("?<")
+                        && !rest.starts_with("?<=")
+                        && !rest.starts_with("?<!"));
+                if capturing {
+                    sources.push(String::new());
+                    open.push((idx, Some(sources.len() - 1)));
+                } else {
+                    open.push((idx, None));
+                }
+            }
+            ')' => {
+                if let Some((start, Some(group))) = open.pop() {
+                    sources[group] = pattern[start..=idx].to_string();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    sources
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explains_capture_groups() {
+        let groups =
+            explain(r"^(?P<who>\w+) hits (?:you|[a-z()]+) for (\d+) \((\w+(?i)(x)?)\)$").unwrap();
+        let described: Vec<(usize, Option<&str>, &str)> = groups
+            .iter()
+            .map(|g| (g.index, g.name.as_deref(), g.pattern.as_str()))
+            .collect();
+        assert_eq!(
+            described,
+            [
+                (1, Some("who"), r"(?P<who>\w+)"),
+                (2, None, r"(\d+)"),
+                (3, None, r"(\w+(?i)(x)?)"),
+                (4, None, r"(x)"),
+            ]
+        );
+
+        let regex = Regex::new(r"^(\w+) hits you(?: for (\d+))?$").unwrap();
+        assert_eq!(
+            capture(&regex, "Vulak hits you"),
+            Some(vec![Some("Vulak".to_string()), None])
+        );
+        assert_eq!(capture(&regex, "Vulak misses you"), None);
+        assert!(explain("(unclosed").is_err());
+    }
+}
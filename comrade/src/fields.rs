@@ -0,0 +1,196 @@
+//! Structured Fields
+//!
+//! Besides matching the raw text of a line, triggers can have conditions on
+//! the fields that Comrade's parsers pull out of it, like who said something
+//! and on which channel, or how much damage a hit did. Conditions are compiled
+//! into predicates when triggers are loaded, and are only checked once a
+//! trigger's search text has matched, so a line's fields are parsed at most
+//! once, and only for the lines that need them.
+
+use std::cell::OnceCell;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::broadcasts;
+use crate::combat;
+use crate::config::triggers::{Comparison, Condition, Field, FieldValue};
+use crate::locations::LocationLog;
+use crate::watcher::LogEvent;
+
+lazy_static! {
+    // The chat channels that aren't broadcasts, with the name of the channel
+    // that each is known by in conditions, or `None` for custom channels,
+    // e.g. "Soandso tells General:2, 'WTS Fungus Covered Scale Tunic'".
+    static ref CHAT_RES: Vec<(Regex, Option<&'static str>)> = [
+        (r"^(?P<speaker>.+?) tells you, '(?P<text>.*)'$", Some("tell")),
+        (r"^(?P<speaker>.+?) tells the group, '(?P<text>.*)'$", Some("group")),
+        (r"^(?P<speaker>.+?) says out of character, '(?P<text>.*)'$", Some("ooc")),
+        (r"^(?P<speaker>.+?) says, '(?P<text>.*)'$", Some("say")),
+        (r"^(?P<speaker>.+?) shouts, '(?P<text>.*)'$", Some("shout")),
+        (r"^(?P<speaker>.+?) auctions, '(?P<text>.*)'$", Some("auction")),
+        (r"^(?P<speaker>[A-Za-z]+) tells (?P<channel>[^ ,:]+):\d+, '(?P<text>.*)'$", None),
+    ]
+    .into_iter()
+    .map(|(re, channel)| (Regex::new(re).unwrap(), channel))
+    .collect();
+}
+
+/// Everything that could be pulled out of a single log line.
+#[derive(Debug, Default)]
+pub(crate) struct Fields {
+    channel: Option<String>,
+    speaker: Option<String>,
+    text: Option<String>,
+    zone: Option<String>,
+    fight: Option<String>,
+    attacker: Option<String>,
+    target: Option<String>,
+    damage: Option<u64>,
+}
+
+enum Value<'a> {
+    Number(u64),
+    Text(&'a str),
+}
+
+impl Fields {
+    fn parse(
+        event: &LogEvent,
+        character: &str,
+        fight: Option<&str>,
+        locations: &LocationLog,
+    ) -> Fields {
+        let line = event.message();
+        let mut fields = Fields {
+            zone: locations.lock().zone(&event.id),
+            fight: fight.map(|f| f.to_string()),
+            ..Fields::default()
+        };
+
+        if let Some(broadcast) = broadcasts::parse_broadcast(line) {
+            fields.channel = Some(broadcast.channel.to_string());
+            fields.speaker = Some(broadcast.speaker);
+            fields.text = Some(broadcast.text);
+        } else if let Some((caps, channel)) = CHAT_RES
+            .iter()
+            .find_map(|(re, channel)| re.captures(line).map(|caps| (caps, channel)))
+        {
+            let channel = channel.or_else(|| caps.name("channel").map(|m| m.as_str()));
+            fields.channel = channel.map(|c| c.to_lowercase());
+            fields.speaker = Some(caps["speaker"].to_string());
+            fields.text = Some(caps["text"].to_string());
+        } else if let Some(hit) = combat::parse_hit(line) {
+            fields.attacker = Some(hit.attacker.unwrap_or(character).to_string());
+            fields.target = Some(hit.target.to_string());
+            fields.damage = Some(hit.amount);
+        }
+
+        fields
+    }
+
+    fn get(&self, field: Field) -> Option<Value<'_>> {
+        let text = match field {
+            Field::Damage => return self.damage.map(Value::Number),
+            Field::Channel => &self.channel,
+            Field::Speaker => &self.speaker,
+            Field::Text => &self.text,
+            Field::Zone => &self.zone,
+            Field::Fight => &self.fight,
+            Field::Attacker => &self.attacker,
+            Field::Target => &self.target,
+        };
+        text.as_deref().map(Value::Text)
+    }
+}
+
+/// The fields of a log line, which are only parsed the first time a trigger
+/// asks for them.
+pub(crate) struct LineFields<'a> {
+    event: &'a LogEvent,
+    character: &'a str,
+    fight: Option<&'a str>,
+    locations: &'a LocationLog,
+    fields: OnceCell<Fields>,
+}
+
+impl<'a> LineFields<'a> {
+    pub(crate) fn new(
+        event: &'a LogEvent,
+        character: &'a str,
+        fight: Option<&'a str>,
+        locations: &'a LocationLog,
+    ) -> LineFields<'a> {
+        LineFields {
+            event,
+            character,
+            fight,
+            locations,
+            fields: OnceCell::new(),
+        }
+    }
+
+    fn get(&self) -> &Fields {
+        self.fields
+            .get_or_init(|| Fields::parse(self.event, self.character, self.fight, self.locations))
+    }
+}
+
+/// A condition, compiled ahead of time so that checking it doesn't allocate.
+#[derive(Debug, Clone)]
+pub(crate) struct Predicate {
+    field: Field,
+    comparison: Comparison,
+    value: PredicateValue,
+}
+
+#[derive(Debug, Clone)]
+enum PredicateValue {
+    Number(u64),
+    /// Lowercased, since text is compared ignoring case.
+    Text(String),
+}
+
+impl Predicate {
+    pub(crate) fn new(condition: &Condition) -> Predicate {
+        Predicate {
+            field: condition.field,
+            comparison: condition.comparison,
+            value: match condition.value {
+                FieldValue::Number(n) => PredicateValue::Number(n),
+                FieldValue::Text(ref t) => PredicateValue::Text(t.to_lowercase()),
+            },
+        }
+    }
+
+    /// Whether the line's field holds up to this predicate, which it never
+    /// does when the line doesn't have the field.
+    pub(crate) fn check(&self, fields: &LineFields<'_>) -> bool {
+        let actual = match fields.get().get(self.field) {
+            Some(actual) => actual,
+            None => return false,
+        };
+
+        match (actual, &self.value) {
+            (Value::Number(actual), PredicateValue::Number(expected)) => {
+                let expected = *expected;
+                match self.comparison {
+                    Comparison::Equal => actual == expected,
+                    Comparison::NotEqual => actual != expected,
+                    Comparison::Greater => actual > expected,
+                    Comparison::GreaterOrEqual => actual >= expected,
+                    Comparison::Less => actual < expected,
+                    Comparison::LessOrEqual => actual <= expected,
+                    Comparison::Contains => false,
+                }
+            }
+            (Value::Text(actual), PredicateValue::Text(expected)) => match self.comparison {
+                Comparison::Equal => actual.eq_ignore_ascii_case(expected),
+                Comparison::NotEqual => !actual.eq_ignore_ascii_case(expected),
+                Comparison::Contains => actual.to_lowercase().contains(expected.as_str()),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
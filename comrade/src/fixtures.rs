@@ -0,0 +1,293 @@
+//! Trigger Pack Fixtures
+//!
+//! A trigger pack can ship with fixtures, which are snippets of log along
+//! with what its triggers are expected to fire on them, so that changes to
+//! the pack can be checked before they're released. Each fixture is replayed
+//! a line at a time against every trigger in the pack, including the ones
+//! that are disabled by default, and whatever fired is compared, in order,
+//! against what was expected.
+//!
+//! A fixture is a TOML file, with the log lines either inline or in a `.txt`
+//! file next to it with the same name:
+//!
+//! ```toml
+//! lines = ["[Sat Oct 17 20:15:00 2026] You have been slowed."]
+//!
+//! [[expect]]
+//! trigger = "slowed"
+//! text = "Slowed!"
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use log::debug;
+use serde::{Deserialize, Serialize};
+
+use crate::combat::{Combat, FightLog};
+use crate::config::timers::TimersConfig;
+use crate::config::triggers::{load_triggers_from_file, TriggerId, TriggerRef, TriggerSet};
+use crate::config::{Character, CharacterId, ClockOffset};
+use crate::errors::{ComradeError, FixtureError};
+use crate::fields::LineFields;
+use crate::locations::LocationLog;
+use crate::random::{Rng, FIXTURE_SEED};
+use crate::triggers::CompiledTrigger;
+use crate::watcher::LogEvent;
+
+type Result<T, E = ComradeError> = core::result::Result<T, E>;
+
+const FIXTURE_EXTENSION: &str = "toml";
+const SNIPPET_EXTENSION: &str = "txt";
+
+#[derive(Debug, Deserialize)]
+struct Fixture {
+    /// The name of the character whose log this is, which matters for
+    /// triggers that look at who did some damage.
+    #[serde(default = "default_character")]
+    character: String,
+    #[serde(default)]
+    lines: Option<Vec<String>>,
+    #[serde(default)]
+    expect: Vec<Expectation>,
+}
+
+fn default_character() -> String {
+    "Tester".to_string()
+}
+
+/// A trigger that a fixture expects to fire.
+#[derive(Debug, Deserialize)]
+struct Expectation {
+    trigger: TriggerId,
+    /// Text that one of the trigger's actions displays or counts down with,
+    /// once it's been expanded.
+    #[serde(default)]
+    text: Option<String>,
+}
+
+impl Expectation {
+    fn matches(&self, fired: &Fired) -> bool {
+        self.trigger == fired.trigger
+            && self
+                .text
+                .as_ref()
+                .is_none_or(|text| fired.texts.contains(text))
+    }
+}
+
+impl fmt::Display for Expectation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.text {
+            Some(ref text) => write!(f, "{} ({:?})", self.trigger, text),
+            None => write!(f, "{}", self.trigger),
+        }
+    }
+}
+
+/// A trigger that fired while replaying a fixture.
+struct Fired {
+    line: usize,
+    trigger: TriggerId,
+    texts: Vec<String>,
+}
+
+impl fmt::Display for Fired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.texts.as_slice() {
+            [] => write!(f, "{}", self.trigger),
+            texts => write!(f, "{} ({:?})", self.trigger, texts.join(", ")),
+        }
+    }
+}
+
+/// How replaying a fixture went.
+#[derive(Debug, Serialize, Clone)]
+pub struct FixtureResult {
+    pub name: String,
+    /// How many times a trigger fired.
+    pub fired: usize,
+    /// Everything that didn't go as expected, so a fixture passed when
+    /// there's nothing here.
+    pub failures: Vec<String>,
+}
+
+impl FixtureResult {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Replays every fixture in the given directory against the triggers in the
+/// given trigger file, in order of their filenames.
+pub(crate) fn test_pack(pack: &Path, fixtures: &Path) -> Result<Vec<FixtureResult>> {
+    let pack = load_triggers_from_file(pack)?;
+
+    fixture_files(fixtures)?
+        .iter()
+        .map(|filename| {
+            debug!("replaying fixture {}", filename.display());
+            let (fixture, lines) = load_fixture(filename)?;
+            let name = filename
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            replay(name, &fixture, &lines, &pack)
+        })
+        .collect()
+}
+
+/// Replays a single fixture against a trigger pack, both of which are given
+/// as TOML rather than read from files. The log lines come from the fixture
+/// itself, if it has any, or the given snippet of log.
+pub(crate) fn test_fixture(pack: &str, fixture: &str, log: Option<&str>) -> Result<FixtureResult> {
+    let pack: TriggerSet = toml_edit::de::from_str(pack).map_err(FixtureError::InvalidPack)?;
+    let mut fixture: Fixture =
+        toml_edit::de::from_str(fixture).map_err(FixtureError::InvalidFixture)?;
+    let lines = match fixture.lines.take() {
+        Some(lines) => lines,
+        None => log
+            .unwrap_or_default()
+            .lines()
+            .map(|line| line.to_string())
+            .collect(),
+    };
+
+    replay("fixture".to_string(), &fixture, &lines, &pack)
+}
+
+fn fixture_files(dir: &Path) -> Result<Vec<PathBuf>, FixtureError> {
+    let io_error = |source| FixtureError::IOError {
+        source,
+        filename: dir.to_path_buf(),
+    };
+
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+        if path.is_file() && path.extension().is_some_and(|e| e == FIXTURE_EXTENSION) {
+            files.push(path);
+        }
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+fn load_fixture(filename: &Path) -> Result<(Fixture, Vec<String>), FixtureError> {
+    let read = |filename: &Path| {
+        fs::read_to_string(filename).map_err(|source| FixtureError::IOError {
+            source,
+            filename: filename.to_path_buf(),
+        })
+    };
+
+    let mut fixture: Fixture =
+        toml_edit::de::from_str(read(filename)?.as_str()).map_err(|source| {
+            FixtureError::DeserializationError {
+                source,
+                filename: filename.to_path_buf(),
+            }
+        })?;
+    let lines = match fixture.lines.take() {
+        Some(lines) => lines,
+        None => read(filename.with_extension(SNIPPET_EXTENSION).as_path())?
+            .lines()
+            .map(|line| line.to_string())
+            .collect(),
+    };
+
+    Ok((fixture, lines))
+}
+
+fn replay(
+    name: String,
+    fixture: &Fixture,
+    lines: &[String],
+    pack: &TriggerSet,
+) -> Result<FixtureResult> {
+    let id = Arc::new(CharacterId::new(fixture.character.as_str()));
+    let character = Character {
+        name: fixture.character.clone(),
+        server: String::new(),
+        filename: PathBuf::new(),
+        aliases: Vec::new(),
+        display_name: None,
+        clock_offset: ClockOffset::default(),
+        language: None,
+        voice: None,
+        disabled_triggers: HashMap::new(),
+        enabled_triggers: HashMap::new(),
+    };
+
+    let timers = TimersConfig::default();
+    let triggers = pack
+        .triggers
+        .iter()
+        .map(|(tid, trigger)| {
+            let tref = TriggerRef::new(pack.meta.source.clone(), tid.clone());
+            let compiled =
+                CompiledTrigger::new(&character, &tref, trigger, &pack.triggers, &timers, true)?;
+            Ok((tid, compiled))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let locations = LocationLog::default();
+    let mut combat = Combat::new(FightLog::default());
+    let mut rng = Rng::seeded(FIXTURE_SEED);
+    let mut fired = Vec::new();
+    let mut failures = Vec::new();
+    for (idx, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let event = match LogEvent::parse(id.clone(), line) {
+            Some(event) => Arc::new(event),
+            None => {
+                failures.push(format!("line {}: not a log line", idx + 1));
+                continue;
+            }
+        };
+
+        locations.lock().log_event(&event);
+        combat.log_event(&character, &event);
+
+        let fight = combat.current(&id);
+        let fields = LineFields::new(&event, character.name.as_str(), fight, &locations);
+        for (tid, trigger) in triggers.iter() {
+            if let Some(actions) = trigger.execute(&event, &fields, &mut rng) {
+                fired.push(Fired {
+                    line: idx + 1,
+                    trigger: (*tid).clone(),
+                    texts: actions
+                        .iter()
+                        .filter_map(|a| a.text())
+                        .map(|t| t.to_string())
+                        .collect(),
+                });
+            }
+        }
+    }
+
+    let mut expected = fixture.expect.iter();
+    for f in fired.iter() {
+        match expected.next() {
+            Some(e) if e.matches(f) => {}
+            Some(e) => failures.push(format!("line {}: expected {}, but {} fired", f.line, e, f)),
+            None => failures.push(format!("line {}: {} fired unexpectedly", f.line, f)),
+        }
+    }
+    for e in expected {
+        failures.push(format!("expected {}, but nothing else fired", e));
+    }
+
+    Ok(FixtureResult {
+        name,
+        fired: fired.len(),
+        failures,
+    })
+}
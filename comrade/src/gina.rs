@@ -0,0 +1,378 @@
+//! GINA Import
+//!
+//! Converts triggers that were exported from GINA, as XML, into Comrade
+//! triggers. GINA supports some things that Comrade doesn't (yet), so rather
+//! than failing outright those parts are dropped, with a warning explaining
+//! what was lost so that the user can decide whether it matters.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use roxmltree::Node;
+
+use crate::config::triggers::{Action, Priority, Trigger, TriggerId, TriggerStyle};
+use crate::errors::ImportError;
+
+type Result<T, E = ImportError> = core::result::Result<T, E>;
+
+lazy_static! {
+    // GINA's placeholders, {S} for any text, {N} for any number, and {C} for
+    // the name of the character whose log is being read.
+    static ref PLACEHOLDER_RE: Regex = Regex::new(r"\{([SN]\d*|C)\}").unwrap();
+    // .NET style named groups, which our regex engine spells differently.
+    static ref NAMED_GROUP_RE: Regex = Regex::new(r"\(\?<([A-Za-z])").unwrap();
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportedTrigger {
+    /// The names of the GINA trigger groups this trigger was in, joined
+    /// together with slashes.
+    pub group: String,
+    pub id: TriggerId,
+    pub trigger: Trigger,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct GinaImport {
+    pub triggers: Vec<ImportedTrigger>,
+    /// Everything that couldn't be converted exactly.
+    pub warnings: Vec<String>,
+}
+
+impl GinaImport {
+    /// The trigger groups that were imported, along with how many triggers
+    /// were imported from each.
+    pub fn groups(&self) -> Vec<(String, usize)> {
+        let mut groups: Vec<(String, usize)> = Vec::new();
+        for imported in self.triggers.iter() {
+            match groups.iter_mut().find(|(g, _)| *g == imported.group) {
+                Some((_, count)) => *count += 1,
+                None => groups.push((imported.group.clone(), 1)),
+            }
+        }
+        groups
+    }
+}
+
+/// Loads triggers from a GINA XML export.
+pub fn import_gina(path: &Path) -> Result<GinaImport> {
+    let display = path.to_string_lossy();
+    if display.starts_with("http://") || display.starts_with("https://") {
+        return Err(ImportError::Unsupported {
+            what: "share URLs".to_string(),
+        });
+    }
+    if path.extension().is_some_and(|ext| ext == "gtp") {
+        return Err(ImportError::Unsupported {
+            what: "trigger packages, extract the ShareData.xml from it".to_string(),
+        });
+    }
+
+    let xml = fs::read_to_string(path).map_err(|source| ImportError::IOError {
+        source,
+        filename: path.to_path_buf(),
+    })?;
+
+    parse_gina(xml.as_str())
+}
+
+/// Converts the triggers from the contents of a GINA XML export.
+pub fn parse_gina(xml: &str) -> Result<GinaImport> {
+    let doc = roxmltree::Document::parse(xml)?;
+
+    let mut importer = Importer::default();
+    importer.walk(doc.root_element(), &[]);
+
+    if importer.import.triggers.is_empty() && importer.import.warnings.is_empty() {
+        return Err(ImportError::NoTriggers);
+    }
+
+    Ok(importer.import)
+}
+
+#[derive(Default)]
+struct Importer {
+    import: GinaImport,
+    ids: HashSet<TriggerId>,
+}
+
+impl Importer {
+    fn walk(&mut self, node: Node, path: &[String]) {
+        for child in node.children().filter(|n| n.is_element()) {
+            match child.tag_name().name() {
+                "TriggerGroup" => self.group(child, path),
+                "Trigger" => self.trigger(child, path),
+                _ => self.walk(child, path),
+            }
+        }
+    }
+
+    fn group(&mut self, node: Node, path: &[String]) {
+        let mut path = path.to_vec();
+        path.push(text(node, "Name").unwrap_or("Unnamed").to_string());
+        self.walk(node, &path);
+    }
+
+    fn trigger(&mut self, node: Node, path: &[String]) {
+        let name = text(node, "Name").unwrap_or("Unnamed").to_string();
+        let group = path.join("/");
+        let qualified = if group.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", group, name)
+        };
+        let mut warn = |message: String| {
+            self.import
+                .warnings
+                .push(format!("{}: {}", qualified, message))
+        };
+
+        let search_text = match text(node, "TriggerText") {
+            Some(t) => convert_pattern(t, flag(node, "EnableRegex"), &mut warn),
+            None => {
+                warn("has no search text, skipped".to_string());
+                return;
+            }
+        };
+        if let Err(e) = Regex::new(search_text.as_str()) {
+            warn(format!("search text isn't a valid regex, skipped ({})", e));
+            return;
+        }
+
+        let mut actions = Vec::new();
+
+        let displayed = optional(node, "UseText", "DisplayText");
+        let spoken = optional(node, "UseTextToVoice", "TextToVoiceText");
+        match (displayed, spoken) {
+            (Some(displayed), spoken) => {
+                if spoken.is_some() {
+                    warn("text to speech isn't supported, it was dropped".to_string());
+                }
+                actions.push(Action::DisplayText {
+                    text: convert_text(displayed),
+                    delay: None,
+                    collapse: None,
+                });
+            }
+            (None, Some(spoken)) => {
+                warn("text to speech isn't supported, the text is displayed instead".to_string());
+                actions.push(Action::DisplayText {
+                    text: convert_text(spoken),
+                    delay: None,
+                    collapse: None,
+                });
+            }
+            (None, None) => {}
+        }
+        if flag(node, "PlayMediaFile") {
+            warn("playing sounds isn't supported, it was dropped".to_string());
+        }
+
+        match text(node, "TimerType").unwrap_or("NoTimer") {
+            "NoTimer" => {}
+            timer_type => {
+                let duration = text(node, "TimerMillisecondDuration")
+                    .and_then(|ms| ms.parse().ok())
+                    .map(Duration::from_millis)
+                    .or_else(|| {
+                        text(node, "TimerDuration")
+                            .and_then(|s| s.parse().ok())
+                            .map(Duration::from_secs)
+                    })
+                    .filter(|d| !d.is_zero());
+
+                match duration {
+                    Some(duration) => {
+                        if timer_type != "Timer" {
+                            warn(format!("{} timers are imported as countdowns", timer_type));
+                        }
+                        if duration.subsec_millis() != 0 {
+                            warn("timer durations are rounded down to the second".to_string());
+                        }
+                        let behavior = text(node, "TimerStartBehavior").unwrap_or("StartNewTimer");
+                        if behavior == "IgnoreIfRunning" {
+                            warn("timers that ignore matches while running are imported as ones that start another".to_string());
+                        }
+                        actions.push(Action::Countdown {
+                            text: convert_text(text(node, "TimerName").unwrap_or(name.as_str())),
+                            duration: Duration::from_secs(duration.as_secs().max(1)),
+                            delay: None,
+                            category: text(node, "Category")
+                                .filter(|c| *c != "Default")
+                                .map(|c| c.to_string()),
+                            icon: None,
+                            spell: None,
+                            end_early_text: early_enders(node, &mut warn),
+                            restart: behavior == "RestartTimer",
+                            repeat: 0,
+                        });
+                    }
+                    None => warn("has a timer without a duration, it was dropped".to_string()),
+                }
+            }
+        }
+
+        if actions.is_empty() {
+            warn("has nothing that could be imported for it to do".to_string());
+        }
+
+        let id = self.unique_id(slug(qualified.as_str()));
+        self.import.triggers.push(ImportedTrigger {
+            group,
+            id,
+            trigger: Trigger {
+                name,
+                comment: text(node, "Comments").unwrap_or("").to_string(),
+                search_text,
+                tags: path.iter().map(|p| p.to_lowercase()).collect(),
+                // Imported triggers have to be enabled for each character,
+                // the same as the starter pack.
+                disabled: true,
+                priority: Priority::default(),
+                style: TriggerStyle::default(),
+                acknowledge: false,
+                repeat: None,
+                captures: BTreeMap::new(),
+                conditions: Vec::new(),
+                active: Vec::new(),
+                actions,
+            },
+        });
+    }
+
+    fn unique_id(&mut self, base: String) -> TriggerId {
+        let mut id = TriggerId::new(base.as_str());
+        let mut n = 2;
+        while self.ids.contains(&id) {
+            id = TriggerId::new(format!("{}-{}", base, n));
+            n += 1;
+        }
+        self.ids.insert(id.clone());
+        id
+    }
+}
+
+fn text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|n| n.has_tag_name(name))
+        .and_then(|n| n.text())
+        .map(|t| t.trim())
+        .filter(|t| !t.is_empty())
+}
+
+fn flag(node: Node, name: &str) -> bool {
+    text(node, name).is_some_and(|t| t.eq_ignore_ascii_case("true"))
+}
+
+/// GINA keeps text around even when it's been turned off, so this only
+/// returns the text when its flag says that it's being used.
+fn optional<'a>(node: Node<'a, '_>, enabled: &str, name: &str) -> Option<&'a str> {
+    if flag(node, enabled) {
+        text(node, name)
+    } else {
+        None
+    }
+}
+
+/// Converts GINA search text into a regex, with the placeholders turned into
+/// named capture groups so that they can be used in the trigger's text.
+fn convert_pattern(pattern: &str, is_regex: bool, warn: &mut dyn FnMut(String)) -> String {
+    let mut converted = String::new();
+    let mut seen = HashSet::new();
+    let mut last = 0;
+
+    let literal = |s: &str| {
+        if is_regex {
+            NAMED_GROUP_RE.replace_all(s, "(?P<$1").to_string()
+        } else {
+            regex::escape(s)
+        }
+    };
+
+    for caps in PLACEHOLDER_RE.captures_iter(pattern) {
+        let whole = caps.get(0).expect("captures always have a whole match");
+        let name = &caps[1];
+        converted.push_str(literal(&pattern[last..whole.start()]).as_str());
+        last = whole.end();
+
+        let matcher = if name.starts_with('N') { r"\d+" } else { ".+" };
+        if name == "C" {
+            warn("{C} matches any name, not just the character's".to_string());
+            converted.push_str(r"\w+");
+        } else if seen.insert(name.to_string()) {
+            converted.push_str(format!("(?P<{}>{})", name, matcher).as_str());
+        } else {
+            converted.push_str(format!("(?:{})", matcher).as_str());
+        }
+    }
+    converted.push_str(literal(&pattern[last..]).as_str());
+
+    converted
+}
+
+/// Combines a timer's early enders, each of which is search text of its own,
+/// into the one regex that ends the countdown early.
+fn early_enders(node: Node, warn: &mut dyn FnMut(String)) -> Option<String> {
+    let enders: Vec<String> = node
+        .children()
+        .filter(|n| n.has_tag_name("TimerEarlyEnders"))
+        .flat_map(|n| n.children().filter(|n| n.has_tag_name("EarlyEnder")))
+        .filter_map(|ender| {
+            let pattern = text(ender, "EarlyEndText")?;
+            Some(convert_pattern(pattern, flag(ender, "EnableRegex"), warn))
+        })
+        .collect();
+    let combined = match enders.as_slice() {
+        [] => return None,
+        [ender] => ender.clone(),
+        enders => enders
+            .iter()
+            .map(|e| format!("(?:{})", e))
+            .collect::<Vec<_>>()
+            .join("|"),
+    };
+
+    match Regex::new(combined.as_str()) {
+        Ok(_) => Some(combined),
+        Err(e) => {
+            warn(format!(
+                "early enders aren't a valid regex, dropped ({})",
+                e
+            ));
+            None
+        }
+    }
+}
+
+/// Converts GINA's placeholders in display text into expansions of the named
+/// groups that `convert_pattern` created for them, except for `{C}`, which is
+/// the character's name in our text too.
+fn convert_text(text: &str) -> String {
+    let escaped = text.replace('$', "$$");
+    PLACEHOLDER_RE
+        .replace_all(escaped.as_str(), |caps: &Captures| match &caps[1] {
+            "C" => caps[0].to_string(),
+            name => format!("${{{}}}", name),
+        })
+        .to_string()
+}
+
+fn slug(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    match slug.trim_end_matches('-') {
+        "" => "gina".to_string(),
+        slug => slug.to_string(),
+    }
+}
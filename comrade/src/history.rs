@@ -0,0 +1,748 @@
+//! Trigger History
+//!
+//! Each time a trigger fires it's recorded in the event store, so history is
+//! found there, just as it was when it fired. Times from before Comrade was
+//! recording them, or what the triggers would fire on as they are now, are
+//! found by replaying the logs against the triggers instead, much like
+//! fixtures are. Zones are followed along the way, although conditions on the
+//! current fight never match, since fights aren't tracked while replaying.
+//!
+//! The same goes for trying out a new pack before a raid: replaying an old
+//! log against it shows which of its triggers would have fired, and how often,
+//! so that the noisy ones can be tuned before they're drowning out the rest.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use regex::Regex;
+use serde::Serialize;
+
+use crate::config::timers::TimersConfig;
+use crate::config::triggers::{load_triggers_from_file, TriggerId, TriggerRef, Triggers};
+use crate::config::{Character, CharacterId, ClockOffset};
+use crate::errors::{ComradeError, HistoryError, TriggerError};
+use crate::explain::{self, CapturePreview};
+use crate::fields::LineFields;
+use crate::locations::LocationLog;
+use crate::random::Rng;
+use crate::store::{self, Month};
+use crate::time::SystemTime;
+use crate::triggers::CompiledTrigger;
+use crate::watcher::LogEvent;
+
+type Result<T, E = ComradeError> = core::result::Result<T, E>;
+
+/// Who a log is taken to be from when analyzing it, if its filename doesn't
+/// say.
+const UNKNOWN_CHARACTER: &str = "You";
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// A time from a log, as seconds since 1970. Logs are written in the local
+/// time of whatever computer the game was running on, so these are too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LogTime(i64);
+
+impl LogTime {
+    /// Reads the time from a log timestamp, like `Sat Oct 17 20:15:00 2026`.
+    pub(crate) fn from_timestamp(timestamp: &str) -> Option<LogTime> {
+        let mut parts = timestamp.split_whitespace().skip(1);
+        let month = parts.next()?;
+        let month = MONTHS.iter().position(|m| *m == month)? as i64 + 1;
+        let day = parts.next()?.parse().ok()?;
+        let time = parts.next()?;
+        let year = parts.next()?.parse().ok()?;
+
+        LogTime::new(year, month, day, time)
+    }
+
+    /// The time now, in UTC.
+    pub(crate) fn now() -> LogTime {
+        let since_epoch = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        LogTime(since_epoch.as_secs() as i64)
+    }
+
+    /// Writes the time the way the game does, the inverse of `from_timestamp`.
+    pub(crate) fn to_timestamp(self) -> String {
+        let (days, seconds) = self.split();
+        let (year, month, day) = civil_from_days(days);
+
+        format!(
+            "{} {} {:02} {:02}:{:02}:{:02} {}",
+            WEEKDAYS[(days + 4).rem_euclid(7) as usize],
+            MONTHS[month as usize - 1],
+            day,
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60,
+            year
+        )
+    }
+
+    pub(crate) fn shifted(self, seconds: i64) -> LogTime {
+        LogTime(self.0 + seconds)
+    }
+
+    pub(crate) fn from_seconds(seconds: i64) -> LogTime {
+        LogTime(seconds)
+    }
+
+    /// Seconds since 1970.
+    pub(crate) fn seconds(self) -> i64 {
+        self.0
+    }
+
+    /// The year and month, like `(2026, 10)`.
+    pub(crate) fn month(self) -> (i64, i64) {
+        let (year, month, _) = civil_from_days(self.split().0);
+        (year, month)
+    }
+
+    fn split(self) -> (i64, i64) {
+        (
+            self.0.div_euclid(24 * 60 * 60),
+            self.0.rem_euclid(24 * 60 * 60),
+        )
+    }
+
+    fn new(year: i64, month: i64, day: i64, time: &str) -> Option<LogTime> {
+        if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+
+        let mut parts = time.split(':').map(|p| p.parse::<i64>().ok());
+        let hours = parts.next()??;
+        let minutes = parts.next()??;
+        let seconds = parts.next().unwrap_or(Some(0))?;
+        if parts.next().is_some() || hours > 23 || minutes > 59 || seconds > 59 {
+            return None;
+        }
+
+        let seconds = hours * 60 * 60 + minutes * 60 + seconds;
+        Some(LogTime(
+            days_since_epoch(year, month, day) * 24 * 60 * 60 + seconds,
+        ))
+    }
+}
+
+impl FromStr for LogTime {
+    type Err = HistoryError;
+
+    /// Reads a time like `2026-10-17 20:15`, with optional seconds, or just a
+    /// date, which is taken to be the start of that day.
+    fn from_str(s: &str) -> Result<LogTime, HistoryError> {
+        let invalid = || HistoryError::InvalidTime {
+            value: s.to_string(),
+        };
+
+        let (date, time) = s.trim().split_once(' ').unwrap_or((s.trim(), "00:00"));
+        let mut parts = date.split('-').map(|p| p.parse::<i64>().ok());
+        let year = parts.next().flatten().ok_or_else(invalid)?;
+        let month = parts.next().flatten().ok_or_else(invalid)?;
+        let day = parts.next().flatten().ok_or_else(invalid)?;
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        LogTime::new(year, month, day, time.trim()).ok_or_else(invalid)
+    }
+}
+
+impl fmt::Display for LogTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (days, seconds) = self.split();
+        let (year, month, day) = civil_from_days(days);
+
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            year,
+            month,
+            day,
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60
+        )
+    }
+}
+
+/// The date that's the given number of days after 1970-01-01, the inverse of
+/// `days_since_epoch`.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+/// The number of days between 1970-01-01 and the given date, in the
+/// proleptic Gregorian calendar.
+fn days_since_epoch(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = (month + 9) % 12;
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// How far back history goes.
+#[derive(Debug, Clone, Copy)]
+pub enum Since {
+    /// This long before the newest line in the logs, or the newest record,
+    /// that were searched.
+    Ago(Duration),
+    At(LogTime),
+}
+
+/// Narrows down which triggers fired that are of interest.
+#[derive(Debug, Default, Clone)]
+pub struct HistoryFilter {
+    /// Only these characters, or every character if there are none.
+    pub characters: Vec<CharacterId>,
+    /// Only triggers whose name or id contains this, ignoring case.
+    pub trigger: Option<String>,
+    /// Only while in a zone whose name contains this, ignoring case.
+    pub zone: Option<String>,
+    pub since: Option<Since>,
+    pub until: Option<LogTime>,
+}
+
+/// A time that a trigger fired, as it was recorded, or found in a character's
+/// log.
+#[derive(Debug, Serialize, Clone)]
+pub struct HistoryEntry {
+    pub character: String,
+    pub trigger: String,
+    /// The zone the character was in, if they'd been seen entering one.
+    pub zone: Option<String>,
+    pub timestamp: String,
+    pub message: String,
+    #[serde(skip)]
+    time: LogTime,
+}
+
+/// Searches the event store for every time that a trigger the filter asks
+/// for fired, oldest first. Only triggers that were on at the time count,
+/// since they're the ones that were recorded.
+pub(crate) fn recorded(data_dir: &Path, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>> {
+    let wanted = filter.trigger.as_ref().map(|t| t.to_lowercase());
+    let zone = filter.zone.as_ref().map(|z| z.to_lowercase());
+    let since = match filter.since {
+        Some(Since::At(time)) => Some(time),
+        _ => None,
+    };
+
+    let records: Vec<_> = store::read(data_dir, since.map(Month::of), filter.until.map(Month::of))?
+        .into_iter()
+        .filter(|r| {
+            filter.characters.is_empty()
+                || filter.characters.iter().any(|c| c.as_str() == r.character)
+        })
+        .collect();
+    let newest = records.iter().map(|r| r.time).max();
+
+    let mut entries: Vec<HistoryEntry> = records
+        .into_iter()
+        .filter(|r| {
+            wanted.as_ref().is_none_or(|w| {
+                let id = r
+                    .trigger
+                    .rsplit_once('/')
+                    .map_or(r.trigger.as_str(), |(_, id)| id);
+                r.trigger_name.to_lowercase().contains(w.as_str())
+                    || id.to_lowercase().contains(w.as_str())
+            })
+        })
+        .filter(|r| {
+            zone.as_ref().is_none_or(|z| {
+                r.zone
+                    .as_ref()
+                    .is_some_and(|c| c.to_lowercase().contains(z.as_str()))
+            })
+        })
+        .map(|r| HistoryEntry {
+            character: r.name,
+            trigger: r.trigger_name,
+            zone: r.zone,
+            timestamp: r.timestamp,
+            message: r.message,
+            time: LogTime(r.time),
+        })
+        .filter(|e| since.is_none_or(|s| e.time >= s) && filter.until.is_none_or(|u| e.time <= u))
+        .collect();
+
+    if let (Some(Since::Ago(ago)), Some(newest)) = (filter.since, newest) {
+        let cutoff = LogTime(newest - ago.as_secs() as i64);
+        entries.retain(|e| e.time >= cutoff);
+    }
+    entries.sort_by_key(|e| e.time);
+
+    Ok(entries)
+}
+
+/// Replays the logs of the characters that the filter asks for, returning
+/// every time that a trigger it asks for fired, oldest first. Every trigger
+/// counts, whether it's enabled or not.
+pub(crate) fn search(
+    characters: &[(CharacterId, Character)],
+    triggers: &Triggers,
+    timers: &TimersConfig,
+    filter: &HistoryFilter,
+) -> Result<Vec<HistoryEntry>> {
+    let wanted = filter.trigger.as_ref().map(|t| t.to_lowercase());
+    let zone = filter.zone.as_ref().map(|z| z.to_lowercase());
+    let since = match filter.since {
+        Some(Since::At(time)) => Some(time),
+        _ => None,
+    };
+
+    let mut entries = Vec::new();
+    let mut newest = None;
+    for (id, character) in characters.iter() {
+        if !filter.characters.is_empty() && !filter.characters.contains(id) {
+            continue;
+        }
+
+        let compiled = triggers
+            .iter()
+            .filter(|(tref, trigger)| {
+                wanted.as_ref().is_none_or(|w| {
+                    trigger.name.to_lowercase().contains(w.as_str())
+                        || tref.id.as_str().to_lowercase().contains(w.as_str())
+                })
+            })
+            .map(|(tref, trigger)| {
+                let pack = triggers
+                    .pack(&tref.source)
+                    .expect("trigger without a pack?");
+                let compiled = CompiledTrigger::new(character, &tref, trigger, pack, timers, true)?;
+                Ok((trigger.name.as_str(), compiled))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if compiled.is_empty() {
+            continue;
+        }
+
+        let id = Arc::new(id.clone());
+        let locations = LocationLog::default();
+        let mut rng = Rng::new();
+        let offset = character.clock_offset.seconds();
+        replay(character.filename.as_path(), &id, offset, |event| {
+            let time = LogTime::from_timestamp(event.timestamp());
+            if time > newest {
+                newest = time;
+            }
+            let time = match time {
+                Some(time) => time,
+                None => return,
+            };
+
+            locations.lock().log_event(event);
+            if since.is_some_and(|s| time < s) || filter.until.is_some_and(|u| time > u) {
+                return;
+            }
+
+            let current = locations.lock().zone(&id);
+            let in_zone = zone.as_ref().is_none_or(|z| {
+                current
+                    .as_ref()
+                    .is_some_and(|c| c.to_lowercase().contains(z.as_str()))
+            });
+            if !in_zone {
+                return;
+            }
+
+            let fields = LineFields::new(event, character.name.as_str(), None, &locations);
+            for (name, trigger) in compiled.iter() {
+                if trigger.execute(event, &fields, &mut rng).is_some() {
+                    entries.push(HistoryEntry {
+                        character: character.name.clone(),
+                        trigger: name.to_string(),
+                        zone: current.clone(),
+                        timestamp: event.timestamp().to_string(),
+                        message: event.message().to_string(),
+                        time,
+                    });
+                }
+            }
+        })?;
+    }
+
+    if let (Some(Since::Ago(ago)), Some(LogTime(newest))) = (filter.since, newest) {
+        let cutoff = LogTime(newest - ago.as_secs() as i64);
+        entries.retain(|e| e.time >= cutoff);
+    }
+    entries.sort_by_key(|e| e.time);
+
+    Ok(entries)
+}
+
+/// Replays the logs of the given characters, returning what the given
+/// pattern captured from the newest of the lines that it matches, up to the
+/// given number of them, oldest first.
+pub(crate) fn recent_captures(
+    characters: &[(CharacterId, Character)],
+    pattern: &str,
+    limit: usize,
+) -> Result<Vec<CapturePreview>> {
+    let regex = Regex::new(pattern).map_err(TriggerError::from)?;
+
+    let mut found = Vec::new();
+    for (id, character) in characters.iter() {
+        let id = Arc::new(id.clone());
+        let offset = character.clock_offset.seconds();
+        let mut matched = VecDeque::new();
+        replay(character.filename.as_path(), &id, offset, |event| {
+            if let Some(captures) = explain::capture(&regex, event.message()) {
+                if matched.len() == limit {
+                    matched.pop_front();
+                }
+                matched.push_back((
+                    LogTime::from_timestamp(event.timestamp()),
+                    CapturePreview {
+                        character: Some(character.name.clone()),
+                        timestamp: Some(event.timestamp().to_string()),
+                        message: event.message().to_string(),
+                        captures,
+                    },
+                ));
+            }
+        })?;
+        found.extend(matched);
+    }
+
+    found.sort_by_key(|(time, _)| *time);
+    let skip = found.len().saturating_sub(limit);
+    Ok(found.into_iter().skip(skip).map(|(_, p)| p).collect())
+}
+
+/// How often a trigger from a pack would have fired over a log.
+#[derive(Debug, Serialize, Clone)]
+pub struct TriggerActivity {
+    pub trigger: TriggerId,
+    pub name: String,
+    /// Whether the pack has the trigger off by default.
+    pub disabled: bool,
+    pub count: usize,
+    /// The first few lines that it fired on, as they were in the log.
+    pub examples: Vec<String>,
+}
+
+/// What replaying a log against a pack found.
+#[derive(Debug, Serialize, Clone)]
+pub struct Analysis {
+    /// The character the log was taken to be from.
+    pub character: String,
+    /// How many log lines were replayed.
+    pub lines: usize,
+    /// How long the log covers, from its first line to its last, in seconds.
+    pub seconds: u64,
+    /// Every trigger in the pack, the busiest first.
+    pub triggers: Vec<TriggerActivity>,
+}
+
+impl Analysis {
+    /// How many times an hour a trigger fired, over the whole of the log.
+    pub fn per_hour(&self, activity: &TriggerActivity) -> Option<f64> {
+        (self.seconds > 0).then(|| activity.count as f64 * 3600.0 / self.seconds as f64)
+    }
+}
+
+/// Replays a log against the triggers in the given trigger file, whether
+/// they're off by default or not, counting how often each would have fired
+/// and keeping up to the given number of lines that it fired on. The log is
+/// taken to be from the given character, or whoever its filename says.
+pub(crate) fn analyze(
+    log: &Path,
+    pack: &Path,
+    character: Option<&str>,
+    examples: usize,
+) -> Result<Analysis> {
+    let pack = load_triggers_from_file(pack)?;
+    let name = character
+        .map(|c| c.to_string())
+        .or_else(|| character_from_filename(log))
+        .unwrap_or_else(|| UNKNOWN_CHARACTER.to_string());
+    let id = Arc::new(CharacterId::new(name.as_str()));
+    let character = Character {
+        name,
+        server: String::new(),
+        filename: log.to_path_buf(),
+        aliases: Vec::new(),
+        display_name: None,
+        clock_offset: ClockOffset::default(),
+        language: None,
+        voice: None,
+        disabled_triggers: HashMap::new(),
+        enabled_triggers: HashMap::new(),
+    };
+
+    let timers = TimersConfig::default();
+    let compiled = pack
+        .triggers
+        .iter()
+        .map(|(tid, trigger)| {
+            let tref = TriggerRef::new(pack.meta.source.clone(), tid.clone());
+            CompiledTrigger::new(&character, &tref, trigger, &pack.triggers, &timers, true)
+                .map_err(ComradeError::from)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let mut activity: Vec<TriggerActivity> = pack
+        .triggers
+        .iter()
+        .map(|(tid, trigger)| TriggerActivity {
+            trigger: tid.clone(),
+            name: trigger.name.clone(),
+            disabled: trigger.disabled,
+            count: 0,
+            examples: Vec::new(),
+        })
+        .collect();
+
+    let locations = LocationLog::default();
+    let mut rng = Rng::new();
+    let (mut lines, mut first, mut last) = (0, None, None);
+    replay(log, &id, 0, |event| {
+        lines += 1;
+        if let Some(time) = LogTime::from_timestamp(event.timestamp()) {
+            first = first.or(Some(time));
+            last = Some(time);
+        }
+
+        locations.lock().log_event(event);
+        let fields = LineFields::new(event, character.name.as_str(), None, &locations);
+        for (trigger, activity) in compiled.iter().zip(activity.iter_mut()) {
+            if trigger.execute(event, &fields, &mut rng).is_some() {
+                activity.count += 1;
+                if activity.examples.len() < examples {
+                    activity
+                        .examples
+                        .push(format!("[{}] {}", event.timestamp(), event.message()));
+                }
+            }
+        }
+    })?;
+
+    activity.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.trigger.cmp(&b.trigger))
+    });
+    let seconds = match (first, last) {
+        (Some(LogTime(first)), Some(LogTime(last))) => (last - first).max(0) as u64,
+        _ => 0,
+    };
+
+    Ok(Analysis {
+        character: character.name,
+        lines,
+        seconds,
+        triggers: activity,
+    })
+}
+
+/// The character's name from a log filename like `eqlog_Soandso_teek.txt`.
+fn character_from_filename(filename: &Path) -> Option<String> {
+    let stem = filename.file_stem()?.to_str()?;
+    let name = stem.strip_prefix("eqlog_")?.split('_').next()?;
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Calls the given function with every line of the given log file, skipping
+/// any that aren't log lines at all, with their timestamps moved by the
+/// given number of seconds.
+pub(crate) fn replay(
+    filename: &Path,
+    id: &Arc<CharacterId>,
+    offset: i64,
+    mut f: impl FnMut(&Arc<LogEvent>),
+) -> Result<()> {
+    let io_error = |source| HistoryError::IOError {
+        source,
+        filename: filename.to_path_buf(),
+    };
+
+    let mut reader = BufReader::new(File::open(filename).map_err(io_error)?);
+    let mut buffer = Vec::new();
+    loop {
+        buffer.clear();
+        if reader.read_until(b'\n', &mut buffer).map_err(io_error)? == 0 {
+            break;
+        }
+
+        let line = String::from_utf8_lossy(&buffer);
+        if let Some(event) = LogEvent::parse(id.clone(), line.as_ref()) {
+            f(&Arc::new(event.shifted(offset)));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    use super::*;
+    use crate::store::{EventStore, Record};
+
+    #[test]
+    fn parses_log_timestamps() {
+        let time = LogTime::from_timestamp("Sat Oct 17 20:15:00 2026").unwrap();
+        assert_eq!(time, "2026-10-17 20:15".parse().unwrap());
+        assert_eq!(time.to_string(), "2026-10-17 20:15:00");
+        assert_eq!(time.0, 1_792_268_100);
+        assert_eq!(time.to_timestamp(), "Sat Oct 17 20:15:00 2026");
+        assert_eq!(
+            time.shifted(-21 * 60 * 60).to_timestamp(),
+            "Fri Oct 16 23:15:00 2026"
+        );
+
+        assert_eq!(
+            LogTime::from_timestamp("Thu Jan 01 00:00:00 1970"),
+            Some(LogTime(0))
+        );
+        assert_eq!(LogTime::from_timestamp("Sat Foo 17 20:15:00 2026"), None);
+        assert_eq!(LogTime::from_timestamp("Sat Oct 17 25:15:00 2026"), None);
+    }
+
+    #[test]
+    fn analyzes_a_log_against_a_pack() {
+        let dir = env::temp_dir().join(format!("comrade-analyze-{}", process::id()));
+        fs::create_dir_all(dir.as_path()).unwrap();
+        let log = dir.join("eqlog_Soandso_teek.txt");
+        let pack = dir.join("Triggers.toml");
+        fs::write(
+            log.as_path(),
+            "[Sat Oct 17 20:00:00 2026] You have been slowed.\n\
+             [Sat Oct 17 20:15:00 2026] Soandso tells you, 'hi'\n\
+             [Sat Oct 17 20:30:00 2026] You have been slowed.\n",
+        )
+        .unwrap();
+        fs::write(
+            pack.as_path(),
+            r#"
+            [meta]
+            source = "local"
+
+            [triggers.slowed]
+            name = "Slowed"
+            search_text = '^You have been slowed\.$'
+            actions = []
+
+            [triggers.rooted]
+            name = "Rooted"
+            search_text = '^You have been rooted\.$'
+            actions = []
+            "#,
+        )
+        .unwrap();
+
+        let analysis = analyze(log.as_path(), pack.as_path(), None, 1).unwrap();
+        assert_eq!(analysis.character, "Soandso");
+        assert_eq!((analysis.lines, analysis.seconds), (3, 30 * 60));
+        let slowed = &analysis.triggers[0];
+        assert_eq!((slowed.trigger.as_str(), slowed.count), ("slowed", 2));
+        assert_eq!(
+            slowed.examples,
+            ["[Sat Oct 17 20:00:00 2026] You have been slowed."]
+        );
+        assert_eq!(analysis.per_hour(slowed), Some(4.0));
+        assert_eq!(analysis.triggers[1].count, 0);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn searches_what_was_recorded() {
+        let data_dir = env::temp_dir().join(format!("comrade-recorded-{}", process::id()));
+        let events = EventStore::default();
+        for (time, name, zone) in [
+            ("2026-09-30 23:00", "Slowed", "The Plane of Fear"),
+            ("2026-10-17 20:00", "Slowed", "The Plane of Hate"),
+            ("2026-10-17 20:15", "Rooted", "The Plane of Hate"),
+            ("2026-10-17 20:30", "Slowed", "The Plane of Fear"),
+        ] {
+            let time: LogTime = time.parse().unwrap();
+            events.record(
+                data_dir.as_path(),
+                &Record {
+                    time: time.seconds(),
+                    timestamp: time.to_timestamp(),
+                    character: "Soandso".to_string(),
+                    name: "Soandso".to_string(),
+                    trigger: format!("local/{}", name.to_lowercase()),
+                    trigger_name: name.to_string(),
+                    zone: Some(zone.to_string()),
+                    message: format!("You have been {}.", name.to_lowercase()),
+                },
+            );
+        }
+
+        let search = |filter: HistoryFilter| -> Vec<String> {
+            recorded(data_dir.as_path(), &filter)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.timestamp)
+                .collect()
+        };
+        assert_eq!(
+            search(HistoryFilter {
+                trigger: Some("slow".to_string()),
+                since: Some(Since::Ago(Duration::from_secs(60 * 60))),
+                ..HistoryFilter::default()
+            }),
+            ["Sat Oct 17 20:00:00 2026", "Sat Oct 17 20:30:00 2026"]
+        );
+        assert_eq!(
+            search(HistoryFilter {
+                zone: Some("fear".to_string()),
+                until: Some("2026-10-01".parse().unwrap()),
+                ..HistoryFilter::default()
+            }),
+            ["Wed Sep 30 23:00:00 2026"]
+        );
+        assert!(search(HistoryFilter {
+            characters: vec![CharacterId::new("Someoneelse")],
+            ..HistoryFilter::default()
+        })
+        .is_empty());
+
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    #[test]
+    fn parses_dates() {
+        assert_eq!(
+            "2024-02-29".parse::<LogTime>().unwrap().to_string(),
+            "2024-02-29 00:00:00"
+        );
+        assert!("2024-13-01".parse::<LogTime>().is_err());
+        assert!("yesterday".parse::<LogTime>().is_err());
+    }
+}
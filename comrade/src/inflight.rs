@@ -0,0 +1,459 @@
+//! In-flight Actions
+//!
+//! Actions that wait on a delay, like a countdown that starts ten seconds
+//! after an emote, only exist in the driver's memory, so if Comrade crashes or
+//! is restarted while one is waiting, it would just be gone. To avoid that,
+//! the driver appends each step a delayed action takes to a journal in the
+//! data directory: when it was started, what it's waiting until, and when
+//! it's finished waiting.
+//!
+//! On start, whatever the journal says is still waiting is recovered, and the
+//! rule for what happens to it is simple enough to be predictable: if it's
+//! still not due, it's put back to wait out the rest of its delay, and if it
+//! became due while Comrade wasn't running, it's expired rather than fired
+//! late, since a callout for something that already happened is worse than
+//! none. Either way it happens exactly once, so nothing is fired twice.
+//!
+//! Timers that were started by hand are journaled the same way, only they
+//! wait until they run out, since unlike a trigger's countdown there's nothing
+//! that would start them again.
+//!
+//! The journal belongs to the latest instance of Comrade, see `instance`, an
+//! older one that's still running stops journaling once it's superseded.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::config::timers::TimersConfig;
+use crate::config::triggers::{SpellInfo, Trigger};
+use crate::config::{Character, CharacterId};
+use crate::instance::{DataLock, Instance};
+use crate::time::{Instant, SystemTime};
+use crate::triggers::Action;
+
+const INFLIGHT_FILENAME: &str = "InFlight.toml";
+
+pub(crate) fn inflight_file(data_dir: &Path) -> PathBuf {
+    data_dir.join(INFLIGHT_FILENAME)
+}
+
+/// What a delayed action will do once its delay is up, with everything it
+/// needs to be put back together after a restart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub(crate) enum Pending {
+    DisplayText {
+        text: String,
+        trigger: Box<Trigger>,
+    },
+    Countdown {
+        text: String,
+        /// In milliseconds.
+        duration: u64,
+        /// When the countdown ends, in milliseconds since the epoch.
+        ends_at: u64,
+        category: Option<String>,
+        icon: Option<String>,
+        spell: Option<SpellInfo>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        end_early_text: Option<String>,
+        #[serde(default)]
+        restart: bool,
+        /// How many more times the countdown starts over once it runs out.
+        #[serde(default)]
+        repeat: u32,
+    },
+    /// A countdown that was started by hand, which nothing would start again
+    /// after a restart, so it's journaled until it runs out rather than just
+    /// while it's waiting on a delay.
+    Timer {
+        text: String,
+        /// In milliseconds.
+        duration: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "kebab-case")]
+enum Record {
+    Started {
+        seq: u64,
+        /// The character whose log started the action, which timers started
+        /// by hand don't have.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        character: Option<CharacterId>,
+        action: Pending,
+    },
+    DelayedUntil {
+        seq: u64,
+        /// In milliseconds since the epoch.
+        until: u64,
+    },
+    Finished {
+        seq: u64,
+    },
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Records {
+    #[serde(default)]
+    record: Vec<Record>,
+}
+
+/// A delayed action that was still waiting when the journal was last
+/// written to.
+#[derive(Debug, PartialEq)]
+struct Waiting {
+    character: Option<CharacterId>,
+    until: SystemTime,
+    action: Pending,
+}
+
+/// The journal of the delayed actions that the driver is waiting on.
+pub(crate) struct InFlight {
+    filename: PathBuf,
+    instance: Option<Arc<Instance>>,
+    next: u64,
+    // Set once a newer instance has taken over the journal.
+    superseded: bool,
+}
+
+impl InFlight {
+    /// Opens the journal at the given path, recovering any actions that were
+    /// still waiting, for the characters that are still configured, as of
+    /// the last time it was written to. The journal is started over with
+    /// just those.
+    pub(crate) fn recover(
+        filename: PathBuf,
+        instance: Option<Arc<Instance>>,
+        characters: &HashMap<CharacterId, Character>,
+        timers: &TimersConfig,
+    ) -> (InFlight, Vec<(Option<CharacterId>, Action)>) {
+        let mut inflight = InFlight {
+            filename,
+            instance,
+            next: 1,
+            superseded: false,
+        };
+        let lock = match inflight.lock() {
+            Some(lock) => lock,
+            None => return (inflight, Vec::new()),
+        };
+        let filename = inflight.filename.as_path();
+
+        let records = match fs::read_to_string(filename) {
+            Ok(contents) => toml_edit::de::from_str(contents.as_str()).unwrap_or_else(|e| {
+                warn!("could not parse in-flight actions, dropping them: {}", e);
+                Records::default()
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Records::default(),
+            Err(e) => {
+                warn!("could not read in-flight actions, dropping them: {}", e);
+                Records::default()
+            }
+        };
+
+        let (waiting, mut expired) = replay(records.record, SystemTime::now());
+        let mut recovered = Vec::new();
+        for w in waiting {
+            let until = instant_at(w.until);
+            let character = w
+                .character
+                .as_ref()
+                .and_then(|id| Some((id, Arc::new(characters.get(id)?.clone()))));
+            match Action::recovered(w.action, until, character, timers) {
+                Some(action) => recovered.push((w.character, action)),
+                None => expired += 1,
+            }
+        }
+        if !recovered.is_empty() || expired > 0 {
+            info!(
+                "recovered {} delayed action(s), expired {} that came due while stopped",
+                recovered.len(),
+                expired
+            );
+        }
+
+        if let Err(e) = fs::write(filename, "") {
+            warn!("could not start over in-flight actions: {}", e);
+        }
+        drop(lock);
+
+        for (id, action) in recovered.iter_mut() {
+            inflight.started(id.as_ref(), action);
+        }
+
+        (inflight, recovered)
+    }
+
+    pub(crate) fn filename(&self) -> &Path {
+        self.filename.as_path()
+    }
+
+    /// Journals the given action if it's waiting on a delay, or is a timer
+    /// that was started by hand.
+    pub(crate) fn started(&mut self, id: Option<&CharacterId>, action: &mut Action) {
+        let (until, pending) = match action.pending() {
+            Some(pending) => pending,
+            None => return,
+        };
+
+        let seq = self.next;
+        self.next += 1;
+        action.set_journaled(seq);
+        self.append(&[
+            Record::Started {
+                seq,
+                character: id.cloned(),
+                action: pending,
+            },
+            Record::DelayedUntil {
+                seq,
+                until: millis(until),
+            },
+        ]);
+    }
+
+    /// Journals the given action as finished with, if it's done waiting.
+    pub(crate) fn settle(&mut self, action: &mut Action) {
+        if let Some(seq) = action.settled() {
+            self.append(&[Record::Finished { seq }]);
+        }
+    }
+
+    /// Locks the journal for writing, unless a newer instance has taken it
+    /// over.
+    fn lock(&mut self) -> Option<DataLock> {
+        if self.superseded {
+            return None;
+        }
+
+        let instance = match self.instance {
+            Some(ref instance) => instance,
+            None => return Some(DataLock::unlocked()),
+        };
+        match instance.lock() {
+            Ok(Some(lock)) => Some(lock),
+            Ok(None) => {
+                warn!("another comrade has been started, no longer journaling in-flight actions");
+                self.superseded = true;
+                None
+            }
+            Err(e) => {
+                warn!("could not lock in-flight actions: {}", e);
+                Some(DataLock::unlocked())
+            }
+        }
+    }
+
+    fn append(&mut self, records: &[Record]) {
+        let _lock = match self.lock() {
+            Some(lock) => lock,
+            None => return,
+        };
+
+        // Each record is its own element of an array of tables, so that the
+        // journal can only ever be appended to and still be one document.
+        let mut contents = String::new();
+        for record in records {
+            match toml_edit::ser::to_string(record) {
+                Ok(s) => {
+                    contents.push_str("[[record]]\n");
+                    contents.push_str(s.as_str());
+                }
+                Err(e) => warn!("could not serialize in-flight action: {}", e),
+            }
+        }
+
+        let result = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.filename.as_path())
+            .and_then(|mut file| file.write_all(contents.as_bytes()));
+        if let Err(e) = result {
+            warn!("could not journal in-flight action: {}", e);
+        }
+    }
+}
+
+/// Works out from the journal which actions were still waiting, and which are
+/// still not due as of `now`, along with how many of them had come due.
+fn replay(records: Vec<Record>, now: SystemTime) -> (Vec<Waiting>, usize) {
+    let mut started = BTreeMap::new();
+    let mut delays = BTreeMap::new();
+    for record in records {
+        match record {
+            Record::Started {
+                seq,
+                character,
+                action,
+            } => {
+                started.insert(seq, (character, action));
+            }
+            Record::DelayedUntil { seq, until } => {
+                delays.insert(seq, until);
+            }
+            Record::Finished { seq } => {
+                started.remove(&seq);
+            }
+        }
+    }
+
+    let mut waiting = Vec::new();
+    let mut expired = 0;
+    for (seq, (character, action)) in started {
+        // An action that was started without saying how long it waits can't
+        // be put back, so it's treated like one that's come due.
+        match delays.get(&seq).map(|until| from_millis(*until)) {
+            Some(until) if until > now => waiting.push(Waiting {
+                character,
+                until,
+                action,
+            }),
+            _ => expired += 1,
+        }
+    }
+
+    (waiting, expired)
+}
+
+pub(crate) fn millis(time: SystemTime) -> u64 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+fn from_millis(millis: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_millis(millis)
+}
+
+/// The given time on the monotonic clock, or now if it's already past.
+pub(crate) fn instant_at(time: SystemTime) -> Instant {
+    let remaining = time
+        .duration_since(SystemTime::now())
+        .unwrap_or(Duration::ZERO);
+    Instant::now() + remaining
+}
+
+/// The given time, in milliseconds since the epoch, on the monotonic clock.
+pub(crate) fn instant_at_millis(millis: u64) -> Instant {
+    instant_at(from_millis(millis))
+}
+
+/// The given time on the monotonic clock as a time on the wall clock.
+pub(crate) fn wall_time(instant: Instant) -> SystemTime {
+    SystemTime::now() + instant.saturating_duration_since(Instant::now())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn started(seq: u64, text: &str) -> Record {
+        Record::Started {
+            seq,
+            character: Some(CharacterId::new("main")),
+            action: Pending::Countdown {
+                text: text.to_string(),
+                duration: 30_000,
+                ends_at: 0,
+                category: None,
+                icon: None,
+                spell: None,
+                end_early_text: None,
+                restart: false,
+                repeat: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn recovers_only_what_is_still_waiting() {
+        let now = from_millis(1_000_000);
+        let records = vec![
+            started(1, "finished"),
+            Record::DelayedUntil {
+                seq: 1,
+                until: 1_010_000,
+            },
+            Record::Finished { seq: 1 },
+            started(2, "came due"),
+            Record::DelayedUntil {
+                seq: 2,
+                until: 990_000,
+            },
+            started(3, "still waiting"),
+            Record::DelayedUntil {
+                seq: 3,
+                until: 1_005_000,
+            },
+            started(4, "never delayed"),
+        ];
+
+        // The journal has to survive being written out and read back in.
+        let mut contents = String::new();
+        for record in records.iter() {
+            contents.push_str("[[record]]\n");
+            contents.push_str(toml_edit::ser::to_string(record).unwrap().as_str());
+        }
+        let parsed: Records = toml_edit::de::from_str(contents.as_str()).unwrap();
+        assert_eq!(parsed.record, records);
+
+        let (waiting, expired) = replay(parsed.record, now);
+        assert_eq!(expired, 2);
+        assert_eq!(waiting.len(), 1);
+        assert_eq!(waiting[0].until, from_millis(1_005_000));
+        assert!(matches!(
+            &waiting[0].action,
+            Pending::Countdown { text, .. } if text == "still waiting"
+        ));
+    }
+
+    #[test]
+    fn recovers_timers_started_by_hand() {
+        let records = vec![
+            Record::Started {
+                seq: 1,
+                character: None,
+                action: Pending::Timer {
+                    text: "Pick respawn".to_string(),
+                    duration: 390_000,
+                },
+            },
+            Record::DelayedUntil {
+                seq: 1,
+                until: 1_390_000,
+            },
+        ];
+
+        let mut contents = String::new();
+        for record in records.iter() {
+            contents.push_str("[[record]]\n");
+            contents.push_str(toml_edit::ser::to_string(record).unwrap().as_str());
+        }
+        assert!(!contents.contains("character"));
+        let parsed: Records = toml_edit::de::from_str(contents.as_str()).unwrap();
+        assert_eq!(parsed.record, records);
+
+        let (waiting, expired) = replay(parsed.record, from_millis(1_000_000));
+        assert_eq!(expired, 0);
+        assert_eq!(
+            waiting,
+            vec![Waiting {
+                character: None,
+                until: from_millis(1_390_000),
+                action: Pending::Timer {
+                    text: "Pick respawn".to_string(),
+                    duration: 390_000,
+                },
+            }]
+        );
+    }
+}
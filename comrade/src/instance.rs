@@ -0,0 +1,125 @@
+//! Instances
+//!
+//! Nothing stops Comrade from being started twice against the same data
+//! directory, and two instances writing the same state files would leave
+//! them a mix of both. So that can't happen, each instance claims a
+//! generation when it starts, by bumping a counter kept in a lock file in the
+//! data directory, and the state that an instance owns is only written while
+//! holding an advisory lock on that file, and only while the instance is the
+//! latest generation. An instance that's been superseded by a newer one
+//! carries on as it was, it just stops writing.
+
+use std::fs::{self, File};
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+
+use crate::config::create_dir;
+
+const LOCK_FILENAME: &str = "Instance.lock";
+
+pub(crate) fn lock_file(data_dir: &Path) -> PathBuf {
+    data_dir.join(LOCK_FILENAME)
+}
+
+/// Held while writing state that only the latest instance may write, the
+/// lock is let go of when this is dropped.
+#[derive(Debug)]
+pub struct DataLock {
+    _file: Option<File>,
+}
+
+impl DataLock {
+    /// A lock that doesn't actually lock anything, for when there's no
+    /// instance to lock for, e.g. when the lock file can't be opened.
+    pub(crate) fn unlocked() -> DataLock {
+        DataLock { _file: None }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Instance {
+    filename: PathBuf,
+    generation: u64,
+}
+
+impl Instance {
+    /// Claims the next generation, superseding whichever instance had the
+    /// last one.
+    pub(crate) fn claim(data_dir: &Path) -> io::Result<Instance> {
+        create_dir(data_dir)?;
+        let filename = lock_file(data_dir);
+        let mut file = open(filename.as_path())?;
+        file.lock()?;
+
+        let generation = read_generation(&mut file)? + 1;
+        file.set_len(0)?;
+        file.seek(io::SeekFrom::Start(0))?;
+        write!(file, "{}", generation)?;
+        file.sync_all()?;
+
+        Ok(Instance {
+            filename,
+            generation,
+        })
+    }
+
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Locks the state files, unless a newer instance has been started since
+    /// this one, in which case they're its to write and None is returned.
+    pub(crate) fn lock(&self) -> io::Result<Option<DataLock>> {
+        let mut file = open(self.filename.as_path())?;
+        file.lock()?;
+
+        if read_generation(&mut file)? != self.generation {
+            return Ok(None);
+        }
+
+        Ok(Some(DataLock { _file: Some(file) }))
+    }
+}
+
+fn open(filename: &Path) -> io::Result<File> {
+    fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(filename)
+}
+
+/// The generation in the lock file, a lock file that's new, or that's been
+/// mangled somehow, counts as generation zero.
+fn read_generation(file: &mut File) -> io::Result<u64> {
+    let mut contents = String::new();
+    file.seek(io::SeekFrom::Start(0))?;
+    file.read_to_string(&mut contents)?;
+
+    Ok(contents.trim().parse().unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use super::*;
+
+    #[test]
+    fn newer_instances_supersede_older_ones() {
+        let dir = env::temp_dir().join(format!("comrade-instance-{}", process::id()));
+
+        let first = Instance::claim(dir.as_path()).unwrap();
+        assert!(first.lock().unwrap().is_some());
+
+        let second = Instance::claim(dir.as_path()).unwrap();
+        assert_eq!(second.generation(), first.generation() + 1);
+        assert!(first.lock().unwrap().is_none());
+        assert!(second.lock().unwrap().is_some());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
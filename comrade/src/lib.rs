@@ -1,29 +1,232 @@
 #![warn(clippy::disallowed_types)]
 
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::Duration;
 
 use arc_swap::ArcSwap;
+use log::{info, warn};
+use parking_lot::Mutex;
 
+mod attendance;
+mod audio;
+mod audit;
+mod broadcasts;
+mod combat;
 mod config;
+mod conflicts;
+mod corpses;
+mod currency;
+#[cfg(feature = "dashboard")]
+mod dashboard;
+mod demo;
+mod digest;
 mod driver;
 pub mod errors;
 pub mod events;
+mod executors;
+mod expand;
+mod explain;
+mod fields;
+mod fixtures;
+mod gina;
+mod history;
+mod inflight;
+mod instance;
+mod locations;
+mod memory;
+mod metrics;
+mod outputs;
+mod random;
+mod resists;
+mod snoozes;
+mod soak;
+mod store;
+mod suggest;
+mod time;
+mod timers;
+mod tradeskills;
 mod triggers;
+mod updates;
+mod version;
+mod watchdog;
 mod watcher;
 
+use crate::config::compliance::{self, manifest_file};
+use crate::config::edit;
+use crate::config::journal::Journal;
+use crate::config::sources::{is_valid_name, last_sync, remote_triggers_file};
+use crate::config::triggers::{load_triggers_from_file, local_triggers_file};
+use crate::instance::Instance;
+
+pub use crate::attendance::{AttendanceReport, PlayerAttendance, RosterSnapshot};
+pub use crate::audio::{AudioControls, AudioCue, AudioKind, CueSound, Severity};
+pub use crate::audit::AuditEntry;
+pub use crate::broadcasts::{Broadcast, BroadcastChannel};
+pub use crate::combat::{AttackerStats, FightSummary};
+pub use crate::config::accessibility::{CueProfile, Earcon, BUILTIN_PROFILE};
+pub use crate::config::attendance::TimeOfDay;
+pub use crate::config::compliance::{ComplianceIssue, ComplianceReport, Manifest};
+pub use crate::config::diff::TriggerChange;
+pub use crate::config::locale::{Speech, DEFAULT_LANGUAGE};
+pub use crate::config::packs::PackMeta;
+pub use crate::config::scaffold::Scaffold;
+pub use crate::config::schedule::ActiveWindow;
+pub use crate::config::search::TriggerFilter;
+pub use crate::config::sources::{SignatureStatus, SourceInfo};
+pub use crate::config::timers::{TimerCategory, DEFAULT_PANE};
+pub use crate::config::triggers::{
+    Action, CaptureCondition, CaptureType, Choice, Comparison, Condition, Field, FieldValue,
+    Priority, SpellInfo, Trigger, TriggerId, TriggerRef, TriggerSource, TriggerStyle,
+};
+pub use crate::config::ui::{EventsLayout, UiConfig};
+pub use crate::config::{Character, CharacterId, ClockOffset};
+pub use crate::conflicts::{Conflict, Overlap};
+pub use crate::currency::{EarningsSession, ZoneEarnings};
+pub use crate::demo::{DemoLog, DEMO_CHARACTER};
+pub use crate::digest::{CharacterDigest, SessionDigest};
+#[cfg(feature = "webhooks")]
+pub use crate::executors::Webhook;
+pub use crate::executors::{ActionExecutor, ExecutorError, ExecutorRequest};
+pub use crate::explain::{CaptureGroup, CapturePreview};
+pub use crate::fixtures::FixtureResult;
+pub use crate::gina::{import_gina, parse_gina, GinaImport, ImportedTrigger};
+pub use crate::history::{Analysis, HistoryEntry, HistoryFilter, LogTime, Since, TriggerActivity};
+pub use crate::instance::DataLock;
+pub use crate::locations::{Location, Position, Waypoint};
+pub use crate::memory::StoreStats;
+pub use crate::resists::ResistStats;
+pub use crate::soak::{SoakOptions, SoakReport};
+pub use crate::store::VacuumReport;
+pub use crate::suggest::suggest_pattern;
+pub use crate::timers::{parse_duration, ManualTimer};
+pub use crate::tradeskills::{RecipeStats, TradeskillSession};
+pub use crate::updates::Release;
+pub use crate::watchdog::LatencyStatus;
+pub use crate::watcher::WatchStatus;
+
 pub mod meta {
     include!(concat!(env!("OUT_DIR"), "/built.rs"));
 }
 
 type Result<T, E = errors::ComradeError> = core::result::Result<T, E>;
 
+/// Creates the configuration and data directories, along with a default
+/// configuration file, for a new installation. Optionally this will also
+/// install a starter pack of (disabled) triggers into the local source.
+pub fn scaffold(config_dir: Option<PathBuf>, with_defaults: bool) -> Result<Scaffold> {
+    Ok(config::scaffold::scaffold(config_dir, with_defaults)?)
+}
+
+/// Where the given profile's configuration lives, within the given
+/// configuration directory, or within the default one, for creating it with
+/// `scaffold`.
+pub fn profile_dir(config_dir: Option<&Path>, name: &str) -> PathBuf {
+    config::profiles::profile_dir(config_dir, name)
+}
+
+/// Compares two trigger files, such as an old and new version of a trigger
+/// pack, returning every trigger that was added, changed, or removed.
+pub fn diff_trigger_files(old: &Path, new: &Path) -> Result<Vec<TriggerChange>> {
+    let old = load_triggers_from_file(old)?;
+    let new = load_triggers_from_file(new)?;
+
+    Ok(config::diff::diff(&old, &new))
+}
+
+/// Builds a `.comradepack` from the given directory, writing it to `output`,
+/// or next to where we're run from if that isn't given, and returning what
+/// the pack says about itself along with where it was written.
+pub fn build_pack(dir: &Path, output: Option<&Path>) -> Result<(PackMeta, PathBuf)> {
+    Ok(config::packs::build(dir, output)?)
+}
+
+/// Replays the fixtures in the given directory against a trigger pack,
+/// reporting whether each of them fired the triggers it expected to.
+pub fn test_pack(pack: &Path, fixtures: &Path) -> Result<Vec<FixtureResult>> {
+    fixtures::test_pack(pack, fixtures)
+}
+
+/// Replays a single fixture against a trigger pack, for frontends that have
+/// them in memory rather than in files, like a pack tester in the browser.
+/// The log lines come from the fixture, or the given snippet of log if it
+/// doesn't have any.
+pub fn test_fixture(pack: &str, fixture: &str, log: Option<&str>) -> Result<FixtureResult> {
+    fixtures::test_fixture(pack, fixture, log)
+}
+
+/// Replays a past log against a trigger pack that's being tried out, to see
+/// which of its triggers would have fired, how often, and on what. The log is
+/// taken to be from the given character, or whoever its filename says.
+pub fn analyze(
+    log: &Path,
+    pack: &Path,
+    character: Option<&str>,
+    examples: usize,
+) -> Result<Analysis> {
+    history::analyze(log, pack, character, examples)
+}
+
+/// Breaks a trigger's pattern down into its capture groups, by number and
+/// name, along with the part of the pattern that each of them is.
+pub fn explain_pattern(pattern: &str) -> Result<Vec<CaptureGroup>> {
+    Ok(explain::explain(pattern)?)
+}
+
+/// What each of a pattern's capture groups captures from the given message,
+/// or nothing if the pattern doesn't match it.
+pub fn preview_captures(pattern: &str, message: &str) -> Result<Option<CapturePreview>> {
+    let regex = regex::Regex::new(pattern).map_err(errors::TriggerError::from)?;
+    Ok(
+        explain::capture(&regex, message).map(|captures| CapturePreview {
+            character: None,
+            timestamp: None,
+            message: message.to_string(),
+            captures,
+        }),
+    )
+}
+
+/// A running instance of Comrade. Every method takes `&self`, with any state
+/// that changes behind a lock, so a `Comrade` can be shared between threads,
+/// such as a frontend's UI thread and its workers, with an `Arc`.
 pub struct Comrade {
+    config_dir: Mutex<Option<PathBuf>>,
     config: config::ConfigRef,
-    watchers: watcher::Watchers,
+    logs: watcher::LogSender,
+    #[cfg(feature = "watcher")]
+    watchers: Mutex<watcher::Watchers>,
     driver: driver::Driver,
+    journal: Mutex<Journal>,
+    audio: Mutex<AudioControls>,
+    tracked: driver::Tracked,
+    executors: executors::Executors,
+    recent: events::RecentEvents,
+    watchdog: watchdog::Watchdog,
+    // The characters whose logs aren't being acted on, and the group that
+    // was last switched to, if any.
+    paused: Mutex<HashSet<CharacterId>>,
+    group: Mutex<Option<String>>,
+    profile: Mutex<Option<String>>,
+    // The generation that this instance claimed, once it's loaded a config
+    // that isn't read-only.
+    instance: Mutex<Option<Arc<Instance>>>,
+    #[cfg(feature = "dashboard")]
+    dashboard: Mutex<Option<dashboard::Server>>,
+    // What was compiled the last time the triggers were loaded, so that
+    // loading them again only compiles what's changed.
+    patterns: config::triggers::PatternCache,
 }
 
+// Frontends depend on being able to share a Comrade between threads, so this
+// fails to compile if anything that isn't stops it from being Send + Sync.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Comrade>();
+};
+
 impl Default for Comrade {
     fn default() -> Comrade {
         Comrade::new()
@@ -33,51 +236,1193 @@ impl Default for Comrade {
 impl Comrade {
     pub fn new() -> Comrade {
         let config = Arc::new(ArcSwap::from_pointee(config::Config::default()));
-        let watchers = watcher::Watchers::default();
-        let driver = driver::Driver::create(config.clone(), watchers.receiver());
+        let (logs, log_receiver) = watcher::channel();
+        let tracked = driver::Tracked::default();
+        let executors = executors::Executors::default();
+        let driver = driver::Driver::create(
+            config.clone(),
+            log_receiver,
+            tracked.clone(),
+            executors.clone(),
+        );
 
         Comrade {
+            config_dir: Mutex::new(None),
             config,
-            watchers,
+            #[cfg(feature = "watcher")]
+            watchers: Mutex::new(watcher::Watchers::new(logs.clone())),
+            logs,
             driver,
+            journal: Mutex::new(Journal::default()),
+            audio: Mutex::new(AudioControls::default()),
+            tracked,
+            executors,
+            recent: events::RecentEvents::default(),
+            watchdog: watchdog::Watchdog::default(),
+            paused: Mutex::new(HashSet::new()),
+            group: Mutex::new(None),
+            profile: Mutex::new(None),
+            instance: Mutex::new(None),
+            #[cfg(feature = "dashboard")]
+            dashboard: Mutex::new(None),
+            patterns: config::triggers::PatternCache::default(),
+        }
+    }
+
+    /// Turns off everything that would write to the configuration or data
+    /// directories, for looking at someone else's profile or a backup without
+    /// changing it. Edits, undo and redo, and syncing sources all fail with
+    /// `ConfigError::ReadOnly`, missing directories aren't created, and
+    /// metrics aren't exported to a file, so this is best set before `load`.
+    pub fn set_read_only(&self, read_only: bool) {
+        self.journal.lock().set_read_only(read_only);
+        self.driver.set_read_only(read_only);
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.journal.lock().is_read_only()
+    }
+
+    pub fn load(&self, config_dir: Option<PathBuf>) -> Result<()> {
+        self.load_config(config_dir)?;
+        self.load_triggers()
+    }
+
+    /// Loads everything but the triggers, which with large packs can take a
+    /// while, so that a frontend can get started while `load_triggers` runs
+    /// on another thread. Until it's done there aren't any triggers.
+    pub fn load_config(&self, config_dir: Option<PathBuf>) -> Result<()> {
+        let profile = self.profile();
+        let config = config::Config::load(config_dir.clone(), profile.as_deref())?;
+        self.use_config(config, config_dir);
+
+        Ok(())
+    }
+
+    fn use_config(&self, config: config::Config, config_dir: Option<PathBuf>) {
+        if !self.is_read_only() {
+            if let Err(e) = config.create_dirs() {
+                warn!("could not create comrade's directories: {}", e);
+            }
+            self.claim_instance(config.dirs.data.as_path());
+        }
+
+        self.config.store(Arc::new(config));
+        *self.config_dir.lock() = config_dir;
+    }
+
+    /// Picks the profile whose configuration is loaded, see
+    /// `config::profiles`, rather than the main configuration, which is best
+    /// set before `load`. Use `switch_profile` once Comrade has started.
+    pub fn set_profile(&self, profile: Option<&str>) {
+        *self.profile.lock() = profile.map(|p| p.to_string());
+    }
+
+    /// The profile whose configuration is loaded, if it isn't the main one.
+    pub fn profile(&self) -> Option<String> {
+        self.profile.lock().clone()
+    }
+
+    /// The profiles that can be switched to, by name.
+    pub fn profiles(&self) -> Vec<String> {
+        config::profiles::profiles(self.config_dir.lock().as_deref())
+    }
+
+    /// Switches a Comrade that's been started to another profile, or back to
+    /// the main configuration, watching the new profile's characters from
+    /// here on and loading its triggers. Nothing changes if the profile's
+    /// configuration can't be loaded.
+    #[cfg(feature = "watcher")]
+    pub fn switch_profile(&self, profile: Option<&str>) -> Result<()> {
+        let config_dir = self.config_dir.lock().clone();
+        let config = config::Config::load(config_dir.clone(), profile)?;
+
+        {
+            let mut watchers = self.watchers.lock();
+            watchers.stop()?;
+            *watchers = watcher::Watchers::new(self.logs.clone());
         }
+        self.paused.lock().clear();
+        *self.group.lock() = None;
+        // The new profile's data directory is claimed from scratch, since
+        // it's a different one.
+        *self.instance.lock() = None;
+
+        self.set_profile(profile);
+        self.use_config(config, config_dir);
+        self.init()?;
+        self.start()?;
+
+        self.load_triggers()
     }
 
-    pub fn load(&mut self, config_dir: Option<PathBuf>) -> Result<()> {
-        let config = match config_dir {
-            Some(path) => Arc::new(config::Config::from_config_dir(path)?),
-            None => Arc::new(config::Config::from_default_dir()?),
+    /// Locks the state files that only one instance of Comrade at a time may
+    /// write, like a frontend's saved state, returning None if another
+    /// instance has been started against the same data directory since this
+    /// one, in which case they're its to write instead.
+    pub fn lock_data(&self) -> Option<DataLock> {
+        let instance = match self.instance.lock().clone() {
+            Some(instance) => instance,
+            None => return Some(DataLock::unlocked()),
         };
 
-        self.config.store(config);
+        match instance.lock() {
+            Ok(lock) => lock,
+            Err(e) => {
+                warn!("could not lock comrade's data directory: {}", e);
+                Some(DataLock::unlocked())
+            }
+        }
+    }
+
+    fn claim_instance(&self, data_dir: &Path) {
+        let mut instance = self.instance.lock();
+        if instance.is_some() {
+            return;
+        }
+
+        match Instance::claim(data_dir) {
+            Ok(claimed) => {
+                info!("running as generation {} of comrade", claimed.generation());
+                *instance = Some(Arc::new(claimed));
+            }
+            Err(e) => warn!("could not lock comrade's data directory: {}", e),
+        }
+    }
+
+    /// Loads the triggers for the configuration that was loaded last,
+    /// sending a `LoadingProgress` event as each source is loaded.
+    pub fn load_triggers(&self) -> Result<()> {
+        let current = self.config.load_full();
+        let mut config = config::Config::clone(&current);
+        config.load_triggers(&self.patterns, |source, loaded, total| {
+            self.driver.notify(events::EventKind::LoadingProgress {
+                source: source.clone(),
+                loaded,
+                total,
+            });
+        });
+
+        // If the configuration was loaded again while these triggers were
+        // loading, then it has newer triggers of its own.
+        let previous = self.config.compare_and_swap(&current, Arc::new(config));
+        if !Arc::ptr_eq(&previous, &current) {
+            return Ok(());
+        }
+
+        #[cfg(feature = "watcher")]
+        self.apply_watcher_filters()?;
 
         Ok(())
     }
 
-    pub fn init(&mut self) -> Result<()> {
+    /// Loads the made up configuration of demo mode instead of a real one,
+    /// with a single character whose log is a `DemoLog`, for handing to
+    /// `process_line`. Nothing is written in demo mode, see `set_read_only`.
+    pub fn load_demo(&self) {
+        self.set_read_only(true);
+        self.config.store(Arc::new(demo::config()));
+        *self.config_dir.lock() = None;
+    }
+
+    /// Checks the release feed for a newer version of Comrade, if checking
+    /// is turned on, sending an `UpdateAvailable` event when there is one.
+    /// This goes out over the network, so it's best done on another thread.
+    pub fn check_for_updates(&self) -> Result<Option<Release>> {
+        let config = self.config();
+        if !config.updates.enabled || config.demo {
+            return Ok(None);
+        }
+
+        let release = updates::check(config.updates.feed.as_str())?;
+        if let Some(ref release) = release {
+            self.driver.notify(events::EventKind::UpdateAvailable {
+                release: Arc::new(release.clone()),
+            });
+        }
+
+        Ok(release)
+    }
+
+    /// Loads the configuration again from wherever it was last loaded from,
+    /// such as after it's been edited by hand. Characters that were added
+    /// since `init` aren't watched until Comrade is restarted.
+    pub fn reload(&self) -> Result<()> {
+        if self.config().demo {
+            self.load_demo();
+            return self.load_triggers();
+        }
+
+        let config_dir = self.config_dir.lock().clone();
+        self.load(config_dir)
+    }
+
+    #[cfg(feature = "watcher")]
+    pub fn init(&self) -> Result<()> {
+        // The demo character doesn't have a log file to watch.
+        if self.config().demo {
+            return Ok(());
+        }
+
+        let mut watchers = self.watchers.lock();
         for (id, c) in self.config().characters.iter() {
-            self.watchers.add(id.clone(), c.filename.clone())?;
+            watchers.add(id.clone(), c.filename.clone())?;
         }
+        drop(watchers);
 
         self.apply_watcher_filters()?;
 
         Ok(())
     }
 
-    pub fn start(&mut self) -> Result<()> {
-        self.watchers.start()?;
+    /// Starts watching the characters' logs, after picking back up any
+    /// delayed actions, see `recover_actions`.
+    #[cfg(feature = "watcher")]
+    pub fn start(&self) -> Result<()> {
+        self.recover_actions();
+        self.watchers.lock().start()?;
+
+        Ok(())
+    }
+
+    /// Stops watching the characters' logs, and ends the session, see
+    /// `end_session`.
+    #[cfg(feature = "watcher")]
+    pub fn stop(&self) -> Result<()> {
+        self.watchers.lock().stop()?;
+        self.end_session();
 
         Ok(())
     }
 
-    pub fn stop(&mut self) -> Result<()> {
-        self.watchers.stop()?;
+    /// Starts serving the dashboard, if it's been turned on in the
+    /// configuration, on a thread of its own until it's stopped or Comrade
+    /// is dropped. Where it listens only changes when it's started again,
+    /// which stops the one that's already being served, but whether it's on,
+    /// and its token, change with the configuration.
+    #[cfg(feature = "dashboard")]
+    pub fn start_dashboard(self: &Arc<Self>) -> Result<()> {
+        let config = self.config().dashboard.clone();
+        self.stop_dashboard();
+        if !config.enabled {
+            return Ok(());
+        }
+        if config.token.is_none() {
+            return Err(errors::DashboardError::MissingToken.into());
+        }
+
+        let config_dir = self.config().dirs.config.clone();
+        let transport = dashboard::Transport::new(&config, config_dir.as_path())?;
+        let server = dashboard::serve(Arc::downgrade(self), config.address.as_str(), transport)?;
+        *self.dashboard.lock() = Some(server);
+        info!("serving the dashboard on {}", config.address);
 
         Ok(())
     }
 
+    /// Stops serving the dashboard, if it's being served.
+    #[cfg(feature = "dashboard")]
+    pub fn stop_dashboard(&self) {
+        // Taken out of the lock first, since stopping waits on the server.
+        let server = self.dashboard.lock().take();
+        drop(server);
+    }
+
+    /// What's been tallied for the session's digest so far. This is only
+    /// tallied when digests have been turned on in the configuration.
+    pub fn digest(&self) -> SessionDigest {
+        self.tracked.digest.lock().digest()
+    }
+
+    /// Ends the session, writing and mailing its digest as the configuration
+    /// asks, and starts a new one. This is part of `stop`, and nothing is
+    /// delivered in read-only or demo mode, or when the session was empty.
+    pub fn end_session(&self) -> SessionDigest {
+        let digest = self.tracked.digest.lock().finish();
+        let config = self.config();
+        if config.digest.enabled && !digest.is_empty() && !self.is_read_only() && !config.demo {
+            digest::deliver(&digest, &config.digest, config.dirs.data.as_path());
+        }
+
+        digest
+    }
+
+    /// Picks back up the delayed actions that were still waiting when
+    /// Comrade last stopped, putting back the ones that aren't due yet and
+    /// dropping the ones that came due in the meantime, then journals delayed
+    /// actions from here on so that the next start can do the same. This is
+    /// part of `start`, and does nothing in read-only or demo mode.
+    pub fn recover_actions(&self) {
+        let config = self.config();
+        if self.is_read_only() || config.demo {
+            return;
+        }
+
+        self.driver.recover(
+            inflight::inflight_file(config.dirs.data.as_path()),
+            self.instance.lock().clone(),
+        );
+    }
+
+    /// Hands Comrade a line from the given character's log, exactly as it
+    /// was written, as if it had just been read from their log file. This is
+    /// how lines get in when Comrade is built without the `watcher` feature.
+    pub fn process_line(&self, id: &CharacterId, line: &str) {
+        if self.paused.lock().contains(id) {
+            return;
+        }
+
+        let event = match watcher::LogEvent::parse(Arc::new(id.clone()), line) {
+            Some(event) => event,
+            None => return,
+        };
+
+        if self.line_filter(id)(event.message()) {
+            self.logs
+                .send(Arc::new(event))
+                .expect("driver thread should not stop before the driver is dropped");
+        }
+    }
+
+    /// The next event, if there is one. Events from the same character are
+    /// always handed out in the order they happened in, and are numbered so
+    /// that any that were dropped can be noticed with `EventStreams`.
     pub fn event(&self) -> Option<events::Event> {
-        self.driver.event()
+        let event = self.driver.event()?;
+        let config = self.config();
+        if let Some(warning) = self.watchdog.measure(&event, &config.watchdog) {
+            if let events::EventKind::Lagging { latency, .. } = warning {
+                warn!("callouts are running {:?} behind the logs", latency);
+            }
+            self.driver.notify(warning);
+        }
+        self.recent.record(&event, config.memory.events);
+        Some(event)
+    }
+
+    /// How late audible events have been handed out lately, for frontends to
+    /// show when Comrade is falling behind.
+    pub fn latency(&self) -> LatencyStatus {
+        self.watchdog.status(&self.config().watchdog)
+    }
+
+    /// The most recent events that have been handed out by `event`, oldest
+    /// first, for frontends that attach late to render what came before them.
+    /// Only the latest report of each countdown is kept, so there are gaps in
+    /// their numbering that don't mean anything was missed.
+    pub fn recent_events(&self, filter: &events::EventFilter) -> Vec<events::Event> {
+        self.recent.matching(filter)
+    }
+
+    /// Registers the executor that carries out `Custom` actions naming it,
+    /// replacing any that was registered under the same name before.
+    pub fn register_executor(&self, name: &str, executor: impl ActionExecutor + 'static) {
+        self.executors.register(name, Arc::new(executor));
+    }
+
+    /// Unregisters the executor with the given name, returning whether there
+    /// was one. Its actions are skipped until another is registered.
+    pub fn unregister_executor(&self, name: &str) -> bool {
+        self.executors.unregister(name)
+    }
+
+    /// Starts a countdown that isn't tied to any trigger, it is reported
+    /// through the same events as any other countdown.
+    pub fn start_timer(&self, timer: ManualTimer) {
+        self.driver.start_timer(timer.text, timer.duration);
+    }
+
+    /// Stops an alert from repeating, this is reported back through an
+    /// `Acknowledged` event so that every frontend can dismiss it.
+    pub fn acknowledge(&self, alert: events::AlertId) {
+        self.driver.acknowledge(Some(alert));
+    }
+
+    /// Stops every alert that's waiting to be acknowledged from repeating.
+    pub fn acknowledge_all(&self) {
+        self.driver.acknowledge(None);
+    }
+
+    /// The fights that have finished, oldest first. These are only tracked
+    /// when combat tracking has been turned on in the configuration.
+    pub fn fights(&self) -> Vec<FightSummary> {
+        self.tracked.fights.lock().iter().cloned().collect()
+    }
+
+    /// Every `/who` that's been seen, oldest first. These are only tracked
+    /// when attendance has been turned on in the configuration.
+    pub fn rosters(&self) -> Vec<RosterSnapshot> {
+        self.tracked.rosters.lock().clone()
+    }
+
+    /// The stats for every recipe that's been made, by character and then
+    /// recipe. These are only tracked when tradeskill tracking has been
+    /// turned on in the configuration.
+    pub fn recipes(&self) -> Vec<RecipeStats> {
+        self.tracked.recipes.lock().values().cloned().collect()
+    }
+
+    /// The stats for every spell that's been cast, by character, target, and
+    /// then spell. These are only tracked when resist tracking has been
+    /// turned on in the configuration.
+    pub fn resists(&self) -> Vec<ResistStats> {
+        self.tracked.resists.lock().values().cloned().collect()
+    }
+
+    /// The farming sessions that have finished, oldest first. These are only
+    /// tracked when currency tracking has been turned on in the
+    /// configuration.
+    pub fn earnings(&self) -> Vec<EarningsSession> {
+        self.tracked.earnings.lock().iter().cloned().collect()
+    }
+
+    /// The finished farming sessions added up by zone, for either a single
+    /// character or every character, the best earning zone first.
+    pub fn earnings_by_zone(&self, character: Option<&CharacterId>) -> Vec<ZoneEarnings> {
+        let sessions: Vec<EarningsSession> = self
+            .tracked
+            .earnings
+            .lock()
+            .iter()
+            .filter(|s| character.is_none() || character == Some(&s.character))
+            .cloned()
+            .collect();
+        currency::by_zone(&sessions)
+    }
+
+    /// Where the given character has been, as of each `/loc`, oldest first.
+    pub fn positions(&self, id: &CharacterId) -> Vec<Position> {
+        self.tracked.locations.lock().history(id)
+    }
+
+    /// The waypoint that the given character recorded under the given name,
+    /// if they have.
+    pub fn waypoint(&self, id: &CharacterId, name: &str) -> Option<Waypoint> {
+        self.tracked.locations.lock().waypoint(id, name)
+    }
+
+    /// Every time that a trigger fired, oldest first, narrowed down by the
+    /// given filter, as recorded in the event store. When triggers aren't
+    /// being recorded, the logs are replayed instead, like `replay_history`.
+    pub fn history(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>> {
+        let config = self.config();
+        if !config.history.record {
+            return self.replay_history(filter);
+        }
+
+        history::recorded(config.dirs.data.as_path(), filter)
+    }
+
+    /// Every time that a trigger, as it is now, would have fired in the
+    /// characters' logs, oldest first, narrowed down by the given filter.
+    /// This reads through each log that could match, so with months of logs
+    /// it can take a while.
+    pub fn replay_history(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>> {
+        let config = self.config();
+        history::search(&self.characters(), &config.triggers, &config.timers, filter)
+    }
+
+    /// Compacts the event store straight away, compressing the months that
+    /// are over and deleting the ones that it has no room for, rather than
+    /// waiting for Comrade to get around to it.
+    pub fn vacuum_history(&self) -> Result<VacuumReport> {
+        let config = self.config();
+        if self.is_read_only() || config.demo {
+            return Err(errors::ConfigError::ReadOnly.into());
+        }
+
+        let now = store::Month::of(LogTime::now());
+        Ok(store::compact(
+            &config.history,
+            config.dirs.data.as_path(),
+            now,
+        )?)
+    }
+
+    /// What the given pattern captured from the most recent lines in the
+    /// characters' logs that it matches, up to the given number of them,
+    /// oldest first. Like `replay_history`, this replays the logs, so it can
+    /// take a while.
+    pub fn recent_captures(&self, pattern: &str, limit: usize) -> Result<Vec<CapturePreview>> {
+        history::recent_captures(&self.characters(), pattern, limit)
+    }
+
+    /// Runs a soak test of the loaded triggers, making up lines for made up
+    /// characters as fast as asked and reporting whether they kept up. This
+    /// blocks for as long as the test runs, and runs a driver of its own, so
+    /// the frontend's events aren't affected.
+    pub fn soak(&self, options: &SoakOptions) -> Result<SoakReport> {
+        soak::run(&self.config(), options)
+    }
+
+    /// Every pair of loaded triggers that look like they'd match the same
+    /// lines, along with why, for tidying up after importing packs that
+    /// cover the same things. This is only ever a guess, see `Overlap`.
+    pub fn conflicts(&self) -> Vec<Conflict> {
+        conflicts::find(self.config().triggers.iter())
+    }
+
+    /// How much of its memory budget each store of what's been tracked is
+    /// using, and how much has been evicted from it to stay within it.
+    pub fn memory_stats(&self) -> Vec<StoreStats> {
+        memory::stats(&self.tracked, &self.config().memory)
+    }
+
+    /// Every waypoint that's been recorded, by character and then name.
+    pub fn waypoints(&self) -> Vec<(CharacterId, Waypoint)> {
+        self.tracked.locations.lock().waypoints()
+    }
+
+    /// Attendance for the configured raid window, with each `/who` taken
+    /// during it as a checkpoint.
+    pub fn attendance(&self) -> AttendanceReport {
+        attendance::report(&self.tracked.rosters.lock(), &self.config().attendance)
+    }
+
+    pub fn audio(&self) -> AudioControls {
+        self.audio.lock().clone()
+    }
+
+    /// Changes the audio controls, returning whatever the given function
+    /// does.
+    pub fn update_audio<T>(&self, f: impl FnOnce(&mut AudioControls) -> T) -> T {
+        f(&mut self.audio.lock())
+    }
+
+    /// The audio cue that the given event gets from the cue profile that's
+    /// been picked, if one has, for frontends to play in place of, or as
+    /// well as, showing the event.
+    pub fn cue(&self, event: &events::Event) -> Option<AudioCue> {
+        let config = self.config();
+        let profile = config.accessibility.active()?;
+
+        audio::cue(
+            &profile,
+            event.kind(),
+            &self.audio(),
+            config.dirs.config.as_path(),
+        )
+    }
+
+    /// The language and voice that the given character's alerts should be
+    /// read out with, by frontends that read them out.
+    pub fn speech(&self, id: &CharacterId) -> Speech {
+        let config = self.config();
+        config.locale.speech(config.characters.get(id))
+    }
+
+    /// How the user would like the frontend to be laid out.
+    pub fn ui(&self) -> UiConfig {
+        self.config().ui.clone()
+    }
+
+    /// Where Comrade keeps its data, frontends can keep their own state here
+    /// as well.
+    pub fn data_dir(&self) -> PathBuf {
+        self.config().dirs.data.clone()
+    }
+
+    /// Finds a character by its id or one of its aliases.
+    pub fn character_id(&self, name: &str) -> Option<CharacterId> {
+        self.config().character_id(name)
+    }
+
+    pub fn characters(&self) -> Vec<(CharacterId, Character)> {
+        let mut characters: Vec<(CharacterId, Character)> = self
+            .config()
+            .characters
+            .iter()
+            .map(|(id, c)| (id.clone(), c.clone()))
+            .collect();
+        characters.sort_by(|a, b| a.0.cmp(&b.0));
+        characters
+    }
+
+    /// The characters that the given name stands for, every character in the
+    /// group if it's a group's name, or else the character it's the id or
+    /// alias of.
+    pub fn character_ids(&self, name: &str) -> Option<Vec<CharacterId>> {
+        self.config().character_ids(name)
+    }
+
+    /// Every group of characters, by name, along with who's in it.
+    pub fn groups(&self) -> Vec<(String, Vec<CharacterId>)> {
+        let config = self.config();
+        config
+            .groups
+            .keys()
+            .map(|name| {
+                let ids = config.character_ids(name).unwrap_or_default();
+                (name.clone(), ids)
+            })
+            .collect()
+    }
+
+    /// The group that was last switched to, if any.
+    pub fn active_group(&self) -> Option<String> {
+        self.group.lock().clone()
+    }
+
+    /// Switches to the given group, pausing every character outside of it
+    /// and resuming those in it, and switching to whichever audio controls it
+    /// sets. Switching to no group at all resumes every character.
+    pub fn switch_group(&self, name: Option<&str>) -> Result<()> {
+        let config = self.config();
+        let group =
+            match name {
+                Some(name) => Some(config.groups.get(name).ok_or_else(|| {
+                    errors::ConfigError::UnknownGroup {
+                        name: name.to_string(),
+                    }
+                })?),
+                None => None,
+            };
+        let members = name
+            .and_then(|name| config.character_ids(name))
+            .unwrap_or_default();
+
+        for id in config.characters.keys() {
+            self.pause(id, group.is_some() && !members.contains(id))?;
+        }
+        if let Some(group) = group {
+            let mut audio = self.audio.lock();
+            if let Some(muted) = group.muted {
+                audio.set_muted(muted);
+            }
+            if let Some(muted) = group.speech_muted {
+                audio.set_speech_muted(muted);
+            }
+            if let Some(volume) = group.volume {
+                audio.set_volume(volume);
+            }
+        }
+        *self.group.lock() = name.map(|n| n.to_string());
+
+        Ok(())
+    }
+
+    /// Pauses or resumes the given character, or every character in the
+    /// given group. Whatever is written to a paused character's log is
+    /// skipped, rather than acted on late once they're resumed.
+    pub fn set_paused(&self, name: &str, paused: bool) -> Result<()> {
+        let ids =
+            self.character_ids(name)
+                .ok_or_else(|| errors::ConfigError::UnknownCharacter {
+                    id: CharacterId::new(name),
+                })?;
+        for id in ids.iter() {
+            self.pause(id, paused)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn is_paused(&self, id: &CharacterId) -> bool {
+        self.paused.lock().contains(id)
+    }
+
+    fn pause(&self, id: &CharacterId, paused: bool) -> Result<()> {
+        #[cfg(feature = "watcher")]
+        self.watchers.lock().set_paused(id, paused)?;
+
+        let mut ids = self.paused.lock();
+        if paused {
+            ids.insert(id.clone());
+        } else {
+            ids.remove(id);
+        }
+
+        Ok(())
+    }
+
+    /// Whether each character's log file is being watched, so that a
+    /// frontend can point out when a character has quietly stopped alerting.
+    /// Characters that were added since `init` aren't being watched yet.
+    #[cfg(feature = "watcher")]
+    pub fn watch_status(&self) -> Vec<(CharacterId, WatchStatus)> {
+        let watchers = self.watchers.lock();
+        self.characters()
+            .into_iter()
+            .map(|(id, _)| {
+                let status = watchers.status(&id).unwrap_or(WatchStatus::Paused);
+                (id, status)
+            })
+            .collect()
+    }
+
+    /// Every trigger source, local first and then the remote ones by name.
+    pub fn sources(&self) -> Vec<SourceInfo> {
+        let config = self.config();
+        let data_dir = config.dirs.data.as_path();
+
+        let mut sources = vec![SourceInfo {
+            source: TriggerSource::Local,
+            url: None,
+            disabled: false,
+            last_sync: None,
+            triggers: config.triggers.count(&TriggerSource::Local),
+            signature: SignatureStatus::Local,
+            error: config.triggers.error(&TriggerSource::Local),
+        }];
+        for (name, source) in config.sources.iter() {
+            let remote = TriggerSource::Remote(name.clone());
+            sources.push(SourceInfo {
+                triggers: config.triggers.count(&remote),
+                error: config.triggers.error(&remote),
+                source: remote,
+                url: Some(source.url.clone()),
+                disabled: source.disabled,
+                last_sync: last_sync(remote_triggers_file(data_dir, name).as_path()),
+                signature: SignatureStatus::Unverified,
+            });
+        }
+
+        sources
+    }
+
+    /// Syncs a remote trigger source from its url, returning how many
+    /// triggers it now has. Syncing isn't an edit that can be undone, since
+    /// it's only ever bringing the source up to date.
+    pub fn sync_source(&self, name: &str) -> Result<usize> {
+        if self.is_read_only() {
+            return Err(errors::ConfigError::ReadOnly.into());
+        }
+        if !is_valid_name(name) {
+            return Err(errors::SourceError::InvalidName {
+                name: name.to_string(),
+            }
+            .into());
+        }
+
+        let config = self.config();
+        let source =
+            config
+                .sources
+                .get(name)
+                .ok_or_else(|| errors::ConfigError::UnknownSource {
+                    name: name.to_string(),
+                })?;
+        let data_dir = config.dirs.data.as_path();
+        let filename = remote_triggers_file(data_dir, name);
+        let before = load_triggers_from_file(filename.as_path()).ok();
+        let count = config::sources::sync(source.url.as_str(), data_dir, name)?;
+        if let Ok(after) = load_triggers_from_file(filename.as_path()) {
+            self.audit(audit::describe_sync(name, before.as_ref(), &after));
+        }
+
+        self.reload()?;
+
+        Ok(count)
+    }
+
+    /// Disables or enables a remote trigger source, the triggers from a
+    /// disabled source aren't loaded at all.
+    pub fn set_source_disabled(&self, name: &str, disabled: bool) -> Result<()> {
+        let filename = self.config().config_file();
+        let description = format!(
+            "{} remote:{}",
+            if disabled { "disable" } else { "enable" },
+            name
+        );
+        self.journal
+            .lock()
+            .record(description.clone(), &[filename.as_path()], || {
+                let mut file = edit::TomlFile::open(filename.as_path(), false)?;
+                edit::set_source_disabled(&mut file, name, disabled)?;
+                file.save()
+            })?;
+        self.audit(description);
+
+        self.reload()
+    }
+
+    /// Installs the `.comradepack` at the given path as a remote source named
+    /// after the pack, which syncs from the pack, so that installing a newer
+    /// version of it over the same file only takes a sync. Returns what the
+    /// pack says about itself and how many triggers it has.
+    pub fn install_pack(&self, path: &Path) -> Result<(PackMeta, usize)> {
+        if self.is_read_only() {
+            return Err(errors::ConfigError::ReadOnly.into());
+        }
+
+        let meta = config::packs::read_meta(path)?;
+        config::packs::check_compatible(&meta, self.data_dir().as_path())?;
+        let path = path
+            .canonicalize()
+            .map_err(|source| errors::PackError::IOError {
+                source,
+                filename: path.to_path_buf(),
+            })?;
+
+        let filename = self.config().config_file();
+        let description = format!("install pack {} {}", meta.name, meta.version);
+        self.journal
+            .lock()
+            .record(description.clone(), &[filename.as_path()], || {
+                let mut file = edit::TomlFile::open(filename.as_path(), false)?;
+                edit::set_source_url(&mut file, meta.name.as_str(), &path.to_string_lossy())?;
+                file.save()
+            })?;
+        self.audit(description);
+        self.reload()?;
+
+        let count = self.sync_source(meta.name.as_str())?;
+
+        Ok((meta, count))
+    }
+
+    /// Removes a remote trigger source from the configuration. Whatever was
+    /// last synced from it is left alone, so that undoing this doesn't
+    /// require syncing it again.
+    pub fn remove_source(&self, name: &str) -> Result<()> {
+        let filename = self.config().config_file();
+        let description = format!("remove remote:{}", name);
+        self.journal
+            .lock()
+            .record(description.clone(), &[filename.as_path()], || {
+                let mut file = edit::TomlFile::open(filename.as_path(), false)?;
+                edit::remove_source(&mut file, name)?;
+                file.save()
+            })?;
+        self.audit(description);
+
+        self.reload()
+    }
+
+    pub fn trigger(&self, tref: &TriggerRef) -> Option<Trigger> {
+        self.config().triggers.get(tref).cloned()
+    }
+
+    pub fn is_trigger_enabled(&self, character: &CharacterId, tref: &TriggerRef) -> bool {
+        let config = self.config();
+        config
+            .characters
+            .get(character)
+            .zip(config.triggers.get(tref))
+            .map(|(c, trigger)| c.is_trigger_enabled(tref, trigger))
+            .unwrap_or(false)
+    }
+
+    /// Enables or disables a trigger for the given characters, or for every
+    /// character if none are given.
+    pub fn set_trigger_enabled(
+        &self,
+        tref: &TriggerRef,
+        characters: &[CharacterId],
+        enabled: bool,
+    ) -> Result<()> {
+        self.set_triggers_enabled(std::slice::from_ref(tref), characters, enabled)
+    }
+
+    /// Enables or disables a number of triggers at once, as a single edit
+    /// that can be undone in one step.
+    pub fn set_triggers_enabled(
+        &self,
+        trefs: &[TriggerRef],
+        characters: &[CharacterId],
+        enabled: bool,
+    ) -> Result<()> {
+        let config = self.config();
+
+        let characters = if characters.is_empty() {
+            config.characters.keys().cloned().collect()
+        } else {
+            characters.to_vec()
+        };
+
+        let description = match trefs {
+            [tref] => format!("{} {}", if enabled { "enable" } else { "disable" }, tref),
+            _ => format!(
+                "{} {} triggers",
+                if enabled { "enable" } else { "disable" },
+                trefs.len()
+            ),
+        };
+
+        let audited = format!(
+            "{} for {}",
+            description,
+            characters
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+
+        let config_file = config.config_file();
+        self.journal
+            .lock()
+            .record(description, &[config_file.as_path()], || {
+                let mut file = edit::TomlFile::open(config_file.as_path(), false)?;
+
+                for tref in trefs.iter() {
+                    let trigger = config.triggers.get(tref).ok_or_else(|| {
+                        errors::ConfigError::UnknownTrigger { tref: tref.clone() }
+                    })?;
+
+                    // Triggers that are disabled by default are opted into
+                    // with the enabled triggers list, everything else is opted
+                    // out of with the disabled triggers list.
+                    for id in characters.iter() {
+                        edit::set_trigger_listed(
+                            &mut file,
+                            id,
+                            edit::DISABLED_TRIGGERS_KEY,
+                            tref,
+                            !enabled,
+                        )?;
+                        edit::set_trigger_listed(
+                            &mut file,
+                            id,
+                            edit::ENABLED_TRIGGERS_KEY,
+                            tref,
+                            enabled && trigger.disabled,
+                        )?;
+                    }
+                }
+
+                file.save()
+            })?;
+        self.audit(audited);
+
+        self.reload()
+    }
+
+    /// Quiets a trigger for every character for the given duration, after
+    /// which it wakes back up on its own. Snoozing a trigger that's already
+    /// snoozed starts its snooze over.
+    pub fn snooze_trigger(&self, tref: &TriggerRef, duration: Duration) -> Result<()> {
+        if self.config().triggers.get(tref).is_none() {
+            return Err(errors::ConfigError::UnknownTrigger { tref: tref.clone() }.into());
+        }
+
+        self.tracked.snoozes.lock().snooze(tref.clone(), duration);
+        self.audit(format!("snooze {} for {}s", tref, duration.as_secs()));
+        Ok(())
+    }
+
+    /// Wakes a snoozed trigger back up early, returning whether it was
+    /// snoozed.
+    pub fn wake_trigger(&self, tref: &TriggerRef) -> bool {
+        let woken = self.tracked.snoozes.lock().wake(tref);
+        if woken {
+            self.audit(format!("wake {}", tref));
+        }
+        woken
+    }
+
+    /// Every trigger that's snoozed, along with how long it has left to go.
+    pub fn snoozed_triggers(&self) -> Vec<(TriggerRef, Duration)> {
+        self.tracked.snoozes.lock().remaining()
+    }
+
+    /// Adds a new trigger to the local trigger source.
+    pub fn add_trigger(&self, id: TriggerId, trigger: Trigger) -> Result<()> {
+        regex::Regex::new(trigger.search_text.as_str()).map_err(errors::TriggerError::from)?;
+
+        let filename = local_triggers_file(self.config().dirs.data.as_path());
+        let description = format!("add {}", TriggerRef::new(TriggerSource::Local, id.clone()));
+        self.journal
+            .lock()
+            .record(description.clone(), &[filename.as_path()], || {
+                let mut file = edit::TomlFile::open(filename.as_path(), true)?;
+                edit::insert_trigger(&mut file, &TriggerSource::Local, &id, &trigger)?;
+                file.save()
+            })?;
+        self.audit(description);
+
+        self.reload()
+    }
+
+    /// Adds imported triggers to the local trigger source, replacing any that
+    /// already exist, and enables them for the given characters. This is a
+    /// single edit that can be undone in one step.
+    pub fn import_triggers(
+        &self,
+        triggers: &[ImportedTrigger],
+        characters: &[CharacterId],
+    ) -> Result<()> {
+        let config = self.config();
+        let filename = local_triggers_file(config.dirs.data.as_path());
+        let config_file = config.config_file();
+        let description = format!("import {} triggers", triggers.len());
+
+        self.journal.lock().record(
+            description.clone(),
+            &[filename.as_path(), config_file.as_path()],
+            || {
+                let mut file = edit::TomlFile::open(filename.as_path(), true)?;
+                for imported in triggers.iter() {
+                    edit::replace_trigger(
+                        &mut file,
+                        &TriggerSource::Local,
+                        &imported.id,
+                        &imported.trigger,
+                    )?;
+                }
+                file.save()?;
+
+                if characters.is_empty() {
+                    return Ok(());
+                }
+
+                let mut file = edit::TomlFile::open(config_file.as_path(), false)?;
+                for imported in triggers.iter() {
+                    let tref = TriggerRef::new(TriggerSource::Local, imported.id.clone());
+                    for id in characters.iter() {
+                        edit::set_trigger_listed(
+                            &mut file,
+                            id,
+                            edit::ENABLED_TRIGGERS_KEY,
+                            &tref,
+                            imported.trigger.disabled,
+                        )?;
+                        edit::set_trigger_listed(
+                            &mut file,
+                            id,
+                            edit::DISABLED_TRIGGERS_KEY,
+                            &tref,
+                            false,
+                        )?;
+                    }
+                }
+                file.save()
+            },
+        )?;
+        self.audit(description);
+
+        self.reload()
+    }
+
+    /// Removes a trigger from the local trigger source, triggers from remote
+    /// sources can only be disabled.
+    pub fn remove_trigger(&self, tref: &TriggerRef) -> Result<()> {
+        if tref.source != TriggerSource::Local {
+            return Err(errors::ConfigError::NotEditable { tref: tref.clone() }.into());
+        }
+
+        let filename = local_triggers_file(self.config().dirs.data.as_path());
+        let description = format!("remove {}", tref);
+        self.journal
+            .lock()
+            .record(description.clone(), &[filename.as_path()], || {
+                let mut file = edit::TomlFile::open(filename.as_path(), false)?;
+                if !edit::remove_trigger(&mut file, &tref.id)? {
+                    return Err(errors::ConfigError::UnknownTrigger { tref: tref.clone() });
+                }
+                file.save()
+            })?;
+        self.audit(description);
+
+        self.reload()
+    }
+
+    /// Applies changes from a trigger pack to the local trigger source, as a
+    /// single edit that can be undone in one step.
+    pub fn merge_triggers(&self, changes: &[TriggerChange]) -> Result<()> {
+        let filename = local_triggers_file(self.config().dirs.data.as_path());
+        let description = match changes {
+            [change] => format!("merge {}", change.id()),
+            _ => format!("merge {} triggers", changes.len()),
+        };
+
+        self.journal
+            .lock()
+            .record(description.clone(), &[filename.as_path()], || {
+                let mut file = edit::TomlFile::open(filename.as_path(), true)?;
+                for change in changes.iter() {
+                    match change.trigger() {
+                        Some(trigger) => edit::replace_trigger(
+                            &mut file,
+                            &TriggerSource::Local,
+                            change.id(),
+                            trigger,
+                        )?,
+                        None => {
+                            edit::remove_trigger(&mut file, change.id())?;
+                        }
+                    }
+                }
+                file.save()
+            })?;
+        self.audit(description);
+
+        self.reload()
+    }
+
+    /// Reverts the most recent trigger edit, returning a description of what
+    /// was undone, or None if there was nothing to undo.
+    pub fn undo(&self) -> Result<Option<String>> {
+        let undone = self.journal.lock().undo()?;
+        if let Some(ref description) = undone {
+            self.audit(format!("undo {}", description));
+            self.reload()?;
+        }
+
+        Ok(undone)
+    }
+
+    /// Reapplies the most recently undone trigger edit, returning a
+    /// description of what was redone, or None if there was nothing to redo.
+    pub fn redo(&self) -> Result<Option<String>> {
+        let redone = self.journal.lock().redo()?;
+        if let Some(ref description) = redone {
+            self.audit(format!("redo {}", description));
+            self.reload()?;
+        }
+
+        Ok(redone)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.journal.lock().can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.journal.lock().can_redo()
+    }
+
+    /// Imports a guild's manifest of required triggers, replacing whichever
+    /// one was imported before.
+    pub fn import_manifest(&self, path: &Path) -> Result<Manifest> {
+        if self.is_read_only() {
+            return Err(errors::ConfigError::ReadOnly.into());
+        }
+
+        let manifest = Manifest::load(path)?;
+        let filename = manifest_file(self.data_dir().as_path());
+        fs::copy(path, filename.as_path())
+            .map_err(|source| errors::ComplianceError::IOError { source, filename })?;
+        self.audit(format!("import required triggers {:?}", manifest.name));
+
+        Ok(manifest)
+    }
+
+    /// How the characters measure up against the imported manifest of
+    /// required triggers, if one has been imported.
+    pub fn compliance(&self) -> Result<Option<ComplianceReport>> {
+        let filename = manifest_file(self.data_dir().as_path());
+        if !filename.exists() {
+            return Ok(None);
+        }
+
+        let manifest = Manifest::load(filename.as_path())?;
+        Ok(Some(compliance::check(&manifest, &self.config())))
+    }
+
+    /// Every change to the triggers that's been made, oldest first, see
+    /// `audit` for what counts as one.
+    pub fn audit_log(&self) -> Result<Vec<AuditEntry>> {
+        Ok(audit::read(self.data_dir().as_path())?)
+    }
+
+    pub fn search_triggers(&self, filter: &TriggerFilter) -> Vec<(TriggerRef, Trigger)> {
+        self.config()
+            .triggers
+            .search(filter)
+            .into_iter()
+            .map(|(tref, trigger)| (tref, trigger.clone()))
+            .collect()
     }
 }
 
@@ -86,13 +1431,57 @@ impl Comrade {
         self.config.load()
     }
 
-    fn apply_watcher_filters(&mut self) -> Result<()> {
+    /// Records a change in the audit log, unless nothing is to be written.
+    fn audit(&self, what: String) {
+        if !self.is_read_only() {
+            audit::record(self.data_dir().as_path(), what);
+        }
+    }
+
+    #[cfg(feature = "watcher")]
+    fn apply_watcher_filters(&self) -> Result<()> {
+        let watchers = self.watchers.lock();
         for id in self.config().characters.keys() {
-            // TODO: We need to let you turn these triggers on/off per character.
-            self.watchers
-                .set_filter(id, self.config().triggers.filter(id));
+            watchers.set_filter(id, self.line_filter(id));
         }
 
         Ok(())
     }
+
+    /// Whether a line from the given character's log is one that anything
+    /// might care about, so that the rest can be skipped before they ever
+    /// reach the driver.
+    fn line_filter(&self, id: &CharacterId) -> Box<dyn Fn(&str) -> bool + Send> {
+        line_filter(&self.config(), id)
+    }
+}
+
+/// The filter behind `Comrade::line_filter`, for configurations other than
+/// the loaded one, like a soak test's.
+fn line_filter(config: &config::Config, id: &CharacterId) -> Box<dyn Fn(&str) -> bool + Send> {
+    let filter = config.triggers.filter(id);
+    let combat = config.combat.enabled;
+    let attendance = config.attendance.enabled;
+    let tradeskills = config.tradeskills.enabled;
+    let resists = config.resists.enabled;
+    let currency = config.currency.enabled;
+    let corpses = config.corpses.enabled;
+    let broadcasts = config.broadcasts.enabled;
+    let digest = config.digest.enabled;
+    let metrics = config.metrics.enabled;
+    // Locations are always tracked, since /loc is only ever used on
+    // purpose and waypoints depend on it.
+    Box::new(move |line| {
+        filter(line)
+            || locations::is_location_line(line)
+            || (combat && combat::is_combat_line(line))
+            || (attendance && attendance::is_roster_line(line))
+            || (tradeskills && tradeskills::is_tradeskill_line(line))
+            || (resists && resists::is_resist_line(line))
+            || (currency && currency::is_currency_line(line))
+            || (corpses && corpses::is_death_line(line))
+            || (broadcasts && broadcasts::is_broadcast_line(line))
+            || (digest && digest::is_digest_line(line))
+            || (metrics && metrics::is_metrics_line(line))
+    })
 }
@@ -0,0 +1,178 @@
+//! Location Tracking
+//!
+//! Every `/loc` that's read from a log is kept as part of that character's
+//! position history, along with the zone they were last seen entering. Their
+//! latest position can be recorded as a named waypoint by a trigger, such as
+//! where they died, to be recalled later on when it's time to go back for
+//! their corpse.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+use log::debug;
+use parking_lot::Mutex;
+use regex::Regex;
+
+use crate::config::CharacterId;
+use crate::watcher::LogEvent;
+
+/// How many positions are kept for each character, the oldest are dropped
+/// once there are more than this.
+const MAX_HISTORY: usize = 100;
+
+lazy_static! {
+    // e.g. "Your Location is -123.45, 678.90, 12.34", which is y, x, z.
+    static ref LOC_RE: Regex = Regex::new(
+        r"^Your Location is (?P<y>-?\d+(?:\.\d+)?), (?P<x>-?\d+(?:\.\d+)?), (?P<z>-?\d+(?:\.\d+)?)$"
+    )
+    .unwrap();
+    static ref ZONE_RE: Regex = Regex::new(r"^You have entered (?P<zone>.+)\.$").unwrap();
+}
+
+/// Whether a log line is one that location tracking needs to see.
+pub(crate) fn is_location_line(line: &str) -> bool {
+    LOC_RE.is_match(line) || ZONE_RE.is_match(line)
+}
+
+/// A location as the game reports it, in the same order as `/loc`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    pub y: f64,
+    pub x: f64,
+    pub z: f64,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.2}, {:.2}, {:.2}", self.y, self.x, self.z)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    pub location: Location,
+    /// The zone the character was last seen entering, if they have been.
+    pub zone: Option<String>,
+    /// When the character was there, as the game wrote it.
+    pub timestamp: String,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.zone {
+            Some(ref zone) => write!(f, "{} in {}", self.location, zone),
+            None => write!(f, "{}", self.location),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Waypoint {
+    pub name: String,
+    pub position: Position,
+}
+
+#[derive(Debug, Default)]
+struct CharacterLocations {
+    zone: Option<String>,
+    history: VecDeque<Position>,
+    waypoints: HashMap<String, Waypoint>,
+}
+
+/// Where each character has been, and the waypoints they've recorded.
+#[derive(Debug, Default)]
+pub(crate) struct Locations {
+    characters: HashMap<CharacterId, CharacterLocations>,
+}
+
+pub(crate) type LocationLog = Arc<Mutex<Locations>>;
+
+impl Locations {
+    /// Updates the character's zone or position from the given event,
+    /// returning their new position if it's changed.
+    pub(crate) fn log_event(&mut self, event: &LogEvent) -> Option<Position> {
+        let line = event.message();
+        if let Some(caps) = ZONE_RE.captures(line) {
+            let character = self.characters.entry((*event.id).clone()).or_default();
+            character.zone = Some(caps["zone"].to_string());
+            return None;
+        }
+
+        let caps = LOC_RE.captures(line)?;
+        let character = self.characters.entry((*event.id).clone()).or_default();
+        let position = Position {
+            location: Location {
+                y: caps["y"].parse().ok()?,
+                x: caps["x"].parse().ok()?,
+                z: caps["z"].parse().ok()?,
+            },
+            zone: character.zone.clone(),
+            timestamp: event.timestamp().to_string(),
+        };
+
+        character.history.push_back(position.clone());
+        while character.history.len() > MAX_HISTORY {
+            character.history.pop_front();
+        }
+
+        Some(position)
+    }
+
+    /// The character's positions, oldest first.
+    pub(crate) fn history(&self, id: &CharacterId) -> Vec<Position> {
+        self.characters
+            .get(id)
+            .map(|c| c.history.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// The zone the character was last seen entering.
+    pub(crate) fn zone(&self, id: &CharacterId) -> Option<String> {
+        self.characters.get(id)?.zone.clone()
+    }
+
+    /// The character's latest position.
+    pub(crate) fn latest(&self, id: &CharacterId) -> Option<&Position> {
+        self.characters.get(id)?.history.back()
+    }
+
+    /// Records the character's latest position under the given name,
+    /// replacing any waypoint that already had it. Returns the waypoint, or
+    /// `None` if the character's position isn't known.
+    pub(crate) fn record(&mut self, id: &CharacterId, name: &str) -> Option<Waypoint> {
+        let character = self.characters.get_mut(id)?;
+        let waypoint = Waypoint {
+            name: name.to_string(),
+            position: character.history.back()?.clone(),
+        };
+
+        debug!("recorded waypoint {:?} for {}", name, id);
+        character
+            .waypoints
+            .insert(name.to_string(), waypoint.clone());
+        Some(waypoint)
+    }
+
+    pub(crate) fn forget(&mut self, id: &CharacterId, name: &str) {
+        if let Some(character) = self.characters.get_mut(id) {
+            character.waypoints.remove(name);
+        }
+    }
+
+    pub(crate) fn waypoint(&self, id: &CharacterId, name: &str) -> Option<Waypoint> {
+        self.characters.get(id)?.waypoints.get(name).cloned()
+    }
+
+    /// Every waypoint that's been recorded, by character and then name.
+    pub(crate) fn waypoints(&self) -> Vec<(CharacterId, Waypoint)> {
+        let mut waypoints: Vec<(CharacterId, Waypoint)> = self
+            .characters
+            .iter()
+            .flat_map(|(id, c)| c.waypoints.values().map(|w| (id.clone(), w.clone())))
+            .collect();
+        waypoints.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.name.cmp(&b.1.name)));
+        waypoints
+    }
+}
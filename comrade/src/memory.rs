@@ -0,0 +1,298 @@
+//! Memory Accounting
+//!
+//! Every store of things that have been tracked, like finished fights, is
+//! kept within the budget that the configuration gives it. What each entry
+//! takes up is worked out from what it holds, which is close enough to what
+//! it really takes to notice a store growing without bound, without having
+//! to keep count of every allocation.
+//!
+//! Stores are checked from the driver's tick, evicting their least recently
+//! used entries until they're within their budget again. Every store but the
+//! recipes and resists is only ever added to, so for them that's the oldest
+//! entries.
+
+use std::collections::BTreeMap;
+use std::mem::size_of;
+use std::sync::Arc;
+
+use log::debug;
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::attendance::RosterSnapshot;
+use crate::combat::{AttackerStats, FightSummary};
+use crate::config::memory::{ByteSize, MemoryConfig};
+use crate::config::CharacterId;
+use crate::currency::EarningsSession;
+use crate::driver::Tracked;
+use crate::resists::ResistStats;
+use crate::tradeskills::RecipeStats;
+
+/// How many entries have been evicted from each store, by its name.
+pub(crate) type Evictions = Arc<Mutex<BTreeMap<&'static str, u64>>>;
+
+/// How much of its budget a store is using.
+#[derive(Debug, Clone, Serialize)]
+pub struct StoreStats {
+    pub store: &'static str,
+    pub entries: usize,
+    /// Roughly how much memory the entries take up, in bytes.
+    pub bytes: usize,
+    pub budget: usize,
+    /// How many entries have been evicted to stay within the budget.
+    pub evicted: u64,
+}
+
+/// Roughly how much memory something takes up, including whatever it owns.
+pub(crate) trait Footprint {
+    fn footprint(&self) -> usize;
+}
+
+impl<T: Footprint> Footprint for &T {
+    fn footprint(&self) -> usize {
+        (*self).footprint()
+    }
+}
+
+impl Footprint for String {
+    fn footprint(&self) -> usize {
+        size_of::<String>() + self.capacity()
+    }
+}
+
+impl Footprint for CharacterId {
+    fn footprint(&self) -> usize {
+        size_of::<CharacterId>() + self.as_str().len()
+    }
+}
+
+impl<T: Footprint> Footprint for Vec<T> {
+    fn footprint(&self) -> usize {
+        size_of::<Vec<T>>()
+            + (self.capacity() - self.len()) * size_of::<T>()
+            + self.iter().map(Footprint::footprint).sum::<usize>()
+    }
+}
+
+impl Footprint for AttackerStats {
+    fn footprint(&self) -> usize {
+        size_of::<AttackerStats>() - size_of::<String>() + self.name.footprint()
+    }
+}
+
+impl Footprint for FightSummary {
+    fn footprint(&self) -> usize {
+        size_of::<FightSummary>() + self.target.capacity() + self.character.as_str().len()
+            - size_of::<Vec<AttackerStats>>()
+            + self.attackers.footprint()
+    }
+}
+
+impl Footprint for RosterSnapshot {
+    fn footprint(&self) -> usize {
+        size_of::<RosterSnapshot>()
+            + self.character.as_str().len()
+            + self.timestamp.capacity()
+            + self.zone.capacity()
+            - size_of::<Vec<String>>()
+            + self.players.footprint()
+    }
+}
+
+impl Footprint for RecipeStats {
+    fn footprint(&self) -> usize {
+        size_of::<RecipeStats>() + self.character.as_str().len() + self.recipe.capacity()
+    }
+}
+
+impl Footprint for ResistStats {
+    fn footprint(&self) -> usize {
+        size_of::<ResistStats>()
+            + self.character.as_str().len()
+            + self.target.capacity()
+            + self.spell.capacity()
+    }
+}
+
+impl Footprint for EarningsSession {
+    fn footprint(&self) -> usize {
+        size_of::<EarningsSession>()
+            + self.character.as_str().len()
+            + self.zone.as_ref().map_or(0, |z| z.capacity())
+    }
+}
+
+/// How many of the given entries, least recently used first, have to be
+/// evicted for the rest of them to be within the budget.
+fn evict_oldest<T: Footprint>(entries: impl Iterator<Item = T>, budget: ByteSize) -> usize {
+    let sizes: Vec<usize> = entries.map(|e| e.footprint()).collect();
+    let mut bytes: usize = sizes.iter().sum();
+    let mut evict = 0;
+    for size in sizes.iter() {
+        if bytes <= budget.0 {
+            break;
+        }
+        bytes -= size;
+        evict += 1;
+    }
+
+    evict
+}
+
+/// Brings every store back within its budget, recording what was evicted.
+pub(crate) fn enforce(tracked: &Tracked, config: &MemoryConfig) {
+    let mut evicted = Vec::new();
+
+    {
+        let mut fights = tracked.fights.lock();
+        let evict = evict_oldest(fights.iter(), config.fights);
+        fights.drain(..evict);
+        evicted.push(("fights", evict));
+    }
+
+    {
+        let mut rosters = tracked.rosters.lock();
+        let evict = evict_oldest(rosters.iter(), config.rosters);
+        rosters.drain(..evict);
+        evicted.push(("rosters", evict));
+    }
+
+    {
+        let mut earnings = tracked.earnings.lock();
+        let evict = evict_oldest(earnings.iter(), config.earnings);
+        earnings.drain(..evict);
+        evicted.push(("earnings", evict));
+    }
+
+    {
+        // Recipes keep being tallied into, so they go in the order that they
+        // were last combined instead.
+        let mut recipes = tracked.recipes.lock();
+        let mut by_use: Vec<_> = recipes.iter().map(|(k, r)| (r.last_combined, k)).collect();
+        by_use.sort();
+        let evict = evict_oldest(by_use.iter().map(|(_, k)| &recipes[*k]), config.recipes);
+        let keys: Vec<_> = by_use
+            .into_iter()
+            .take(evict)
+            .map(|(_, k)| k.clone())
+            .collect();
+        for key in keys.iter() {
+            recipes.remove(key);
+        }
+        evicted.push(("recipes", evict));
+    }
+
+    {
+        // Likewise for resists, which go in the order they were last cast.
+        let mut resists = tracked.resists.lock();
+        let mut by_use: Vec<_> = resists.iter().map(|(k, r)| (r.last_cast, k)).collect();
+        by_use.sort();
+        let evict = evict_oldest(by_use.iter().map(|(_, k)| &resists[*k]), config.resists);
+        let keys: Vec<_> = by_use
+            .into_iter()
+            .take(evict)
+            .map(|(_, k)| k.clone())
+            .collect();
+        for key in keys.iter() {
+            resists.remove(key);
+        }
+        evicted.push(("resists", evict));
+    }
+
+    let mut evictions = tracked.evictions.lock();
+    for (store, evict) in evicted.into_iter().filter(|(_, e)| *e > 0) {
+        debug!("evicted {} entries from {} to stay in budget", evict, store);
+        *evictions.entry(store).or_default() += evict as u64;
+    }
+}
+
+/// How much of its budget each store is using.
+pub(crate) fn stats(tracked: &Tracked, config: &MemoryConfig) -> Vec<StoreStats> {
+    let evictions = tracked.evictions.lock();
+    let store = |store: &'static str, entries: usize, bytes: usize, budget: ByteSize| StoreStats {
+        store,
+        entries,
+        bytes,
+        budget: budget.0,
+        evicted: evictions.get(store).copied().unwrap_or_default(),
+    };
+
+    let fights = tracked.fights.lock();
+    let rosters = tracked.rosters.lock();
+    let recipes = tracked.recipes.lock();
+    let resists = tracked.resists.lock();
+    let earnings = tracked.earnings.lock();
+    vec![
+        store(
+            "fights",
+            fights.len(),
+            fights.iter().map(Footprint::footprint).sum(),
+            config.fights,
+        ),
+        store(
+            "rosters",
+            rosters.len(),
+            rosters.iter().map(Footprint::footprint).sum(),
+            config.rosters,
+        ),
+        store(
+            "recipes",
+            recipes.len(),
+            recipes.values().map(Footprint::footprint).sum(),
+            config.recipes,
+        ),
+        store(
+            "resists",
+            resists.len(),
+            resists.values().map(Footprint::footprint).sum(),
+            config.resists,
+        ),
+        store(
+            "earnings",
+            earnings.len(),
+            earnings.iter().map(Footprint::footprint).sum(),
+            config.earnings,
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::time::SystemTime;
+
+    #[test]
+    fn evicts_the_oldest_entries_over_budget() {
+        let tracked = Tracked::default();
+        {
+            let mut fights = tracked.fights.lock();
+            for n in 0..100 {
+                fights.push_back(FightSummary {
+                    character: CharacterId::new("soandso"),
+                    target: format!("a fire drake {}", n),
+                    started: SystemTime::now(),
+                    duration: Duration::from_secs(30),
+                    attackers: Vec::new(),
+                });
+            }
+        }
+        let each = tracked.fights.lock()[0].footprint();
+        let config = MemoryConfig {
+            fights: ByteSize(each * 40),
+            ..MemoryConfig::default()
+        };
+
+        enforce(&tracked, &config);
+        let fights = tracked.fights.lock();
+        assert_eq!(fights.len(), 40);
+        assert_eq!(fights[0].target, "a fire drake 60");
+        drop(fights);
+
+        let stats = stats(&tracked, &config);
+        assert_eq!(stats[0].store, "fights");
+        assert_eq!(stats[0].evicted, 60);
+        assert!(stats[0].bytes <= stats[0].budget);
+    }
+}
@@ -0,0 +1,411 @@
+//! Metrics Export
+//!
+//! The driver counts what it sees for each character, the lines that reach
+//! it, the triggers that match, the damage they do themselves and the
+//! experience they gain, along with how well it's keeping up. Every interval
+//! those counts are written out in InfluxDB's line protocol and handed to
+//! the configured sink, on a thread of its own so that a slow database never
+//! holds up the driver, and then started over.
+//!
+//! Batches that a slow or unreachable sink hasn't gotten to yet are kept up
+//! to a point, past which new ones are dropped rather than piling up. File
+//! sinks write to the data directory, so they're skipped in read-only and
+//! demo mode.
+//!
+//! Lines are only counted once they've made it past the filter that skips
+//! lines nothing cares about, so they're the lines that Comrade had to do
+//! something with, rather than everything in the logs.
+
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, Sender, TrySendError};
+use log::warn;
+
+use crate::combat;
+use crate::config::metrics::{MetricsConfig, SinkConfig};
+use crate::config::CharacterId;
+use crate::digest;
+use crate::inflight::millis;
+use crate::time::{Instant, SystemTime};
+use crate::watcher::LogEvent;
+
+/// How many batches can wait on a sink that's still writing one.
+const QUEUE: usize = 8;
+
+/// Whether a log line is one that metrics need to see.
+pub(crate) fn is_metrics_line(line: &str) -> bool {
+    combat::parse_hit(line).is_some() || digest::experience_gained(line).is_some()
+}
+
+/// How well the driver is keeping up, at the time metrics are exported.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Health {
+    /// Lines that have been read, but not yet looked at by the driver.
+    pub(crate) backlog: usize,
+    /// Events that are waiting on the frontend to take them.
+    pub(crate) queued: usize,
+    /// Actions that haven't finished, like countdowns.
+    pub(crate) actions: usize,
+    /// Events that have been dropped since Comrade started.
+    pub(crate) dropped: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Counters {
+    lines: u64,
+    matches: u64,
+    /// The damage done by the character themselves.
+    damage: u64,
+    experience: u64,
+    experience_percent: f64,
+}
+
+/// Writes out the counts for an interval of the given length as points in
+/// InfluxDB's line protocol, all at the given time.
+fn points(
+    counters: &BTreeMap<CharacterId, Counters>,
+    interval: Duration,
+    health: &Health,
+    at: SystemTime,
+) -> String {
+    // InfluxDB takes nanoseconds unless it's told otherwise.
+    let timestamp = u128::from(millis(at)) * 1_000_000;
+    let seconds = interval.as_secs_f64().max(1.0);
+
+    let mut batch = String::new();
+    for (id, counts) in counters.iter() {
+        let _ = writeln!(
+            batch,
+            "comrade,character={} lines={}i,matches={}i,damage={}i,dps={},experience={}i,experience_percent={} {}",
+            escape_tag(id.as_str()),
+            counts.lines,
+            counts.matches,
+            counts.damage,
+            counts.damage as f64 / seconds,
+            counts.experience,
+            counts.experience_percent,
+            timestamp
+        );
+    }
+    let _ = writeln!(
+        batch,
+        "comrade_health backlog={}i,queued={}i,actions={}i,dropped={}i {}",
+        health.backlog, health.queued, health.actions, health.dropped, timestamp
+    );
+
+    batch
+}
+
+/// Escapes the characters that mean something in a tag's value.
+fn escape_tag(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, ',' | '=' | ' ' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// Somewhere batches of points can be written to.
+trait Sink {
+    fn write(&mut self, batch: &str) -> io::Result<()>;
+}
+
+struct File {
+    path: PathBuf,
+}
+
+impl Sink for File {
+    fn write(&mut self, batch: &str) -> io::Result<()> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path.as_path())?
+            .write_all(batch.as_bytes())
+    }
+}
+
+#[cfg(feature = "metrics")]
+struct Influxdb {
+    url: String,
+    token: Option<String>,
+}
+
+#[cfg(feature = "metrics")]
+impl Sink for Influxdb {
+    fn write(&mut self, batch: &str) -> io::Result<()> {
+        let mut request = ureq::post(self.url.as_str());
+        if let Some(ref token) = self.token {
+            request = request.set("Authorization", format!("Token {}", token).as_str());
+        }
+        request.send_string(batch).map_err(io::Error::other)?;
+
+        Ok(())
+    }
+}
+
+struct Running {
+    config: SinkConfig,
+    // Missing for a sink that couldn't be started, which has already been
+    // warned about.
+    batches: Option<Sender<String>>,
+}
+
+/// The sink's configuration, with a file's path made absolute, so that a
+/// change of data directory is a change of sink.
+fn resolve(config: &SinkConfig, data_dir: &Path) -> SinkConfig {
+    match config {
+        SinkConfig::File { path } => SinkConfig::File {
+            path: data_dir.join(path),
+        },
+        config => config.clone(),
+    }
+}
+
+fn start(config: &SinkConfig) -> Option<Sender<String>> {
+    let mut sink: Box<dyn Sink + Send> = match config {
+        SinkConfig::File { path } => Box::new(File { path: path.clone() }),
+        #[cfg(feature = "metrics")]
+        SinkConfig::Influxdb { url, token } => Box::new(Influxdb {
+            url: url.clone(),
+            token: token.clone(),
+        }),
+        #[cfg(not(feature = "metrics"))]
+        SinkConfig::Influxdb { .. } => {
+            warn!("cannot export metrics to InfluxDB without the metrics feature");
+            return None;
+        }
+    };
+
+    let (batches, receiver) = bounded::<String>(QUEUE);
+    let spawned = thread::Builder::new()
+        .name("comrade metrics".to_string())
+        .spawn(move || {
+            for batch in receiver.iter() {
+                if let Err(e) = sink.write(batch.as_str()) {
+                    warn!("could not export metrics: {}", e);
+                }
+            }
+        });
+    match spawned {
+        Ok(_) => Some(batches),
+        Err(e) => {
+            warn!("could not start exporting metrics: {}", e);
+            None
+        }
+    }
+}
+
+/// The counts for the current interval, and the sink they're exported to.
+pub(crate) struct Metrics {
+    counters: BTreeMap<CharacterId, Counters>,
+    exported: Instant,
+    running: Option<Running>,
+}
+
+impl Default for Metrics {
+    fn default() -> Metrics {
+        Metrics {
+            counters: BTreeMap::new(),
+            exported: Instant::now(),
+            running: None,
+        }
+    }
+}
+
+impl Metrics {
+    fn counters(&mut self, id: &CharacterId) -> &mut Counters {
+        if !self.counters.contains_key(id) {
+            self.counters.insert(id.clone(), Counters::default());
+        }
+        self.counters.get_mut(id).expect("counters were just added")
+    }
+
+    /// Counts the given log event for its character, whose name is needed
+    /// to tell their own damage apart from everyone else's.
+    pub(crate) fn log_event(&mut self, name: &str, event: &LogEvent) {
+        let line = event.message();
+        let counters = self.counters(&event.id);
+        counters.lines += 1;
+
+        if let Some(hit) = combat::parse_hit(line) {
+            if hit.attacker.is_none_or(|a| a == name) {
+                counters.damage += hit.amount;
+            }
+        } else if let Some(percent) = digest::experience_gained(line) {
+            counters.experience += 1;
+            counters.experience_percent += percent;
+        }
+    }
+
+    /// Counts a trigger matching for the given character.
+    pub(crate) fn matched(&mut self, id: &CharacterId) {
+        self.counters(id).matches += 1;
+    }
+
+    /// Exports the counts once the interval is up, starting the counts over.
+    /// Characters that have been seen once keep being exported, with zeros
+    /// when there's nothing to count, so that dashboards show them idling
+    /// rather than missing. Nothing is written to a file unless `writable`.
+    pub(crate) fn export(
+        &mut self,
+        config: &MetricsConfig,
+        data_dir: &Path,
+        writable: bool,
+        health: Health,
+    ) {
+        let sink = match config.sink {
+            Some(SinkConfig::File { .. }) if !writable => None,
+            Some(ref sink) if config.enabled => Some(resolve(sink, data_dir)),
+            _ => None,
+        };
+        let sink = match sink {
+            Some(sink) => sink,
+            _ => {
+                // Dropping the sink hangs up on its thread, which lets it
+                // finish.
+                self.running = None;
+                self.counters.clear();
+                self.exported = Instant::now();
+                return;
+            }
+        };
+
+        let elapsed = self.exported.elapsed();
+        if elapsed < config.interval {
+            return;
+        }
+        self.exported = Instant::now();
+
+        if self.running.as_ref().is_none_or(|r| r.config != sink) {
+            self.running = Some(Running {
+                batches: start(&sink),
+                config: sink,
+            });
+        }
+
+        let batch = points(&self.counters, elapsed, &health, SystemTime::now());
+        for counters in self.counters.values_mut() {
+            *counters = Counters::default();
+        }
+
+        let running = self.running.as_mut().expect("sink was just started");
+        if let Some(ref batches) = running.batches {
+            match batches.try_send(batch) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => {
+                    warn!("metrics sink is falling behind, dropping a batch");
+                }
+                Err(TrySendError::Disconnected(_)) => {
+                    warn!("metrics sink has stopped, starting it again next time");
+                    self.running = None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::process;
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn event(id: &Arc<CharacterId>, message: &str) -> LogEvent {
+        LogEvent::new(id.clone(), "[Sat Oct 17 20:15:00 2026] ", message, None)
+    }
+
+    #[test]
+    fn exports_line_protocol() {
+        let id = Arc::new(CharacterId::new("Soandso"));
+        let mut metrics = Metrics::default();
+        for line in [
+            "You slash a gnoll for 100 points of damage.",
+            "Soandso hit a gnoll for 50 points of fire damage by Flame Lick.",
+            "Gobaner hits a gnoll for 30 points of damage.",
+            "A gnoll hits YOU for 20 points of damage.",
+            "You gain experience! (0.5%)",
+        ] {
+            metrics.log_event("Soandso", &event(&id, line));
+        }
+        metrics.matched(&id);
+
+        let counts = &metrics.counters[&*id];
+        assert_eq!(counts.lines, 5);
+        // Only their own damage counts, not their pet's or the gnoll's.
+        assert_eq!(counts.damage, 150);
+        assert_eq!(counts.matches, 1);
+        assert_eq!(counts.experience, 1);
+
+        let health = Health {
+            backlog: 2,
+            ..Health::default()
+        };
+        let at = SystemTime::UNIX_EPOCH + Duration::from_secs(1);
+        let batch = points(&metrics.counters, Duration::from_secs(10), &health, at);
+        assert_eq!(
+            batch,
+            "comrade,character=Soandso lines=5i,matches=1i,damage=150i,dps=15,experience=1i,experience_percent=0.5 1000000000\n\
+             comrade_health backlog=2i,queued=0i,actions=0i,dropped=0i 1000000000\n"
+        );
+        assert_eq!(escape_tag("a b,c=d"), r"a\ b\,c\=d");
+
+        // The interval being up sends the batch to the file, relative to the
+        // data directory, and starts the counts over.
+        let data_dir = env::temp_dir().join(format!("comrade-metrics-{}", process::id()));
+        fs::create_dir_all(data_dir.as_path()).unwrap();
+        let config = MetricsConfig {
+            enabled: true,
+            interval: Duration::ZERO,
+            sink: Some(SinkConfig::File {
+                path: PathBuf::from("metrics.lp"),
+            }),
+        };
+        metrics.export(&config, data_dir.as_path(), true, health);
+        assert_eq!(metrics.counters[&*id].lines, 0);
+
+        // Hanging up on the sink's thread lets it finish writing.
+        metrics.running = None;
+        let filename = data_dir.join("metrics.lp");
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !fs::read_to_string(filename.as_path()).is_ok_and(|s| s.contains("comrade_health")) {
+            assert!(Instant::now() < deadline, "metrics were never written");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    #[test]
+    fn skips_files_unless_writable() {
+        let id = Arc::new(CharacterId::new("Soandso"));
+        let mut metrics = Metrics::default();
+        metrics.log_event("Soandso", &event(&id, "You gain experience! (0.5%)"));
+
+        let data_dir = env::temp_dir().join(format!("comrade-metrics-ro-{}", process::id()));
+        let config = MetricsConfig {
+            enabled: true,
+            interval: Duration::ZERO,
+            sink: Some(SinkConfig::File {
+                path: PathBuf::from("metrics.lp"),
+            }),
+        };
+        metrics.export(&config, data_dir.as_path(), false, Health::default());
+        assert!(metrics.running.is_none());
+        assert!(metrics.counters.is_empty());
+        assert!(!data_dir.exists());
+    }
+}
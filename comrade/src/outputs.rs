@@ -0,0 +1,310 @@
+//! Outputs
+//!
+//! An output is anything that a pattern of on and off pulses can be played
+//! on, each kind of output is its own backend behind `Output`. Playing a
+//! pattern can take a while, so each output gets a thread of its own, and
+//! patterns for an output that's still busy wait their turn, up to a point,
+//! past which they're dropped, so that a trigger that keeps firing doesn't
+//! leave the output going long after whatever set it off is over.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File};
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel::{bounded, Sender, TrySendError};
+use log::warn;
+use parking_lot::Mutex;
+
+use crate::config::outputs::OutputConfig;
+
+/// How long a dot lasts in Morse code, unless a trigger says otherwise.
+pub(crate) const DEFAULT_UNIT: Duration = Duration::from_millis(100);
+
+/// How many patterns can wait for an output that's still playing one.
+const QUEUE: usize = 2;
+
+const MORSE: &[(char, &str)] = &[
+    ('a', ".-"),
+    ('b', "-..."),
+    ('c', "-.-."),
+    ('d', "-.."),
+    ('e', "."),
+    ('f', "..-."),
+    ('g', "--."),
+    ('h', "...."),
+    ('i', ".."),
+    ('j', ".---"),
+    ('k', "-.-"),
+    ('l', ".-.."),
+    ('m', "--"),
+    ('n', "-."),
+    ('o', "---"),
+    ('p', ".--."),
+    ('q', "--.-"),
+    ('r', ".-."),
+    ('s', "..."),
+    ('t', "-"),
+    ('u', "..-"),
+    ('v', "...-"),
+    ('w', ".--"),
+    ('x', "-..-"),
+    ('y', "-.--"),
+    ('z', "--.."),
+    ('0', "-----"),
+    ('1', ".----"),
+    ('2', "..---"),
+    ('3', "...--"),
+    ('4', "....-"),
+    ('5', "....."),
+    ('6', "-...."),
+    ('7', "--..."),
+    ('8', "---.."),
+    ('9', "----."),
+];
+
+/// Alternating on and off pulses, starting with on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Pattern(Vec<Duration>);
+
+impl Pattern {
+    pub(crate) fn new(pulses: Vec<Duration>) -> Pattern {
+        Pattern(pulses)
+    }
+
+    /// The given text in Morse code, with a dot lasting `unit`, a dash three
+    /// of them, and the gaps between dots and dashes, letters, and words one,
+    /// three, and seven of them. Anything without a code is skipped.
+    pub(crate) fn morse(text: &str, unit: Duration) -> Pattern {
+        let mut pulses = Vec::new();
+        // The gap that goes before the next dot or dash, in units.
+        let mut gap = None;
+        for c in text.chars().map(|c| c.to_ascii_lowercase()) {
+            if c.is_whitespace() {
+                gap = gap.map(|_| 7);
+                continue;
+            }
+            let code = match MORSE.iter().find(|(m, _)| *m == c) {
+                Some((_, code)) => code,
+                None => continue,
+            };
+            gap = gap.map(|g: u32| g.max(3));
+
+            for symbol in code.chars() {
+                if let Some(g) = gap {
+                    pulses.push(unit * g);
+                }
+                pulses.push(if symbol == '-' { unit * 3 } else { unit });
+                gap = Some(1);
+            }
+        }
+
+        Pattern(pulses)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Each pulse, along with whether it's on.
+    fn pulses(&self) -> impl Iterator<Item = (bool, Duration)> + '_ {
+        self.0.iter().enumerate().map(|(i, d)| (i % 2 == 0, *d))
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, pulse) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}", pulse.as_millis())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Something that patterns can be played on.
+trait Output: Send {
+    fn play(&mut self, pattern: &Pattern) -> io::Result<()>;
+}
+
+/// Sends each pattern as a line of its pulse lengths in milliseconds, e.g.
+/// `100 100 300`, for whatever's on the other end to play.
+struct Serial {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl Output for Serial {
+    fn play(&mut self, pattern: &Pattern) -> io::Result<()> {
+        // The device may well be unplugged and plugged back in while we're
+        // running, so it's opened again after anything goes wrong.
+        let result = match self.file {
+            Some(ref mut file) => writeln!(file, "{}", pattern).and_then(|_| file.flush()),
+            None => {
+                let mut file = fs::OpenOptions::new()
+                    .write(true)
+                    .open(self.path.as_path())?;
+                let result = writeln!(file, "{}", pattern).and_then(|_| file.flush());
+                self.file = Some(file);
+                result
+            }
+        };
+        if result.is_err() {
+            self.file = None;
+        }
+
+        result
+    }
+}
+
+/// Switches a GPIO pin on and off by writing to its value file.
+struct Gpio {
+    path: PathBuf,
+}
+
+impl Output for Gpio {
+    fn play(&mut self, pattern: &Pattern) -> io::Result<()> {
+        let result = pattern.pulses().try_for_each(|(on, length)| {
+            set_pin(self.path.as_path(), on)?;
+            thread::sleep(length);
+            Ok(())
+        });
+
+        // Whatever happens, the pin shouldn't be left on.
+        set_pin(self.path.as_path(), false)?;
+        result
+    }
+}
+
+fn set_pin(path: &Path, on: bool) -> io::Result<()> {
+    fs::write(path, if on { "1" } else { "0" })
+}
+
+struct Running {
+    config: OutputConfig,
+    patterns: Sender<Arc<Pattern>>,
+}
+
+#[derive(Default)]
+struct State {
+    configs: BTreeMap<String, OutputConfig>,
+    running: BTreeMap<String, Running>,
+}
+
+/// The configured outputs, each of which is started the first time that it's
+/// signaled, and started over if its configuration changes.
+#[derive(Clone, Default)]
+pub(crate) struct Outputs(Arc<Mutex<State>>);
+
+impl Outputs {
+    /// Keeps up with the outputs in the configuration, which can change
+    /// whenever it's reloaded.
+    pub(crate) fn configure(&self, configs: &BTreeMap<String, OutputConfig>) {
+        let mut state = self.0.lock();
+        if state.configs != *configs {
+            // Dropping an output that's no longer configured hangs up on its
+            // thread, which lets it finish.
+            state.running.retain(|name, _| configs.contains_key(name));
+            state.configs = configs.clone();
+        }
+    }
+
+    pub(crate) fn signal(&self, name: &str, pattern: Arc<Pattern>) {
+        let mut state = self.0.lock();
+        let config = match state.configs.get(name) {
+            Some(config) => config.clone(),
+            None => {
+                warn!("cannot signal unknown output {:?}", name);
+                return;
+            }
+        };
+        let running = &mut state.running;
+        if running.get(name).is_none_or(|r| r.config != config) {
+            running.insert(name.to_string(), start(name, config));
+        }
+
+        let output = running.get(name).expect("output was just started");
+        match output.patterns.try_send(pattern) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                warn!("output {} is still busy, skipping the pattern", name);
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                warn!("output {} has stopped, starting it again next time", name);
+                running.remove(name);
+            }
+        }
+    }
+}
+
+fn start(name: &str, config: OutputConfig) -> Running {
+    let mut output: Box<dyn Output> = match config {
+        OutputConfig::Serial { ref path } => Box::new(Serial {
+            path: path.clone(),
+            file: None,
+        }),
+        OutputConfig::Gpio { ref path } => Box::new(Gpio { path: path.clone() }),
+    };
+
+    let (patterns, receiver) = bounded::<Arc<Pattern>>(QUEUE);
+    let owned = name.to_string();
+    let spawned = thread::Builder::new()
+        .name(format!("comrade output {}", name))
+        .spawn(move || {
+            for pattern in receiver.iter() {
+                if let Err(e) = output.play(&pattern) {
+                    warn!("could not signal output {}: {}", owned, e);
+                }
+            }
+        });
+    if let Err(e) = spawned {
+        warn!("could not start output {}: {}", name, e);
+    }
+
+    Running { config, patterns }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn morse_timing() {
+        let unit = Duration::from_millis(1);
+        // "e e" is a dot, a word gap, and another dot, while "et" is a dot, a
+        // letter gap, and a dash.
+        assert_eq!(Pattern::morse("e e", unit).to_string(), "1 7 1");
+        assert_eq!(Pattern::morse("Et!", unit).to_string(), "1 3 3");
+        assert_eq!(Pattern::morse("a", unit).to_string(), "1 1 3");
+        assert!(Pattern::morse("?!", unit).is_empty());
+    }
+
+    #[test]
+    fn skips_patterns_while_busy() {
+        let path = std::env::temp_dir().join(format!("comrade-gpio-{}", std::process::id()));
+        let outputs = Outputs::default();
+        outputs.configure(&BTreeMap::from([(
+            "buzzer".to_string(),
+            OutputConfig::Gpio { path: path.clone() },
+        )]));
+
+        let pattern = Arc::new(Pattern::new(vec![Duration::from_millis(500)]));
+        for _ in 0..10 {
+            outputs.signal("buzzer", pattern.clone());
+        }
+        let waiting = outputs.0.lock().running["buzzer"].patterns.len();
+        assert!(waiting <= QUEUE, "{} patterns waiting", waiting);
+
+        // Hanging up lets the output finish, with the pin left off.
+        outputs.configure(&BTreeMap::new());
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,75 @@
+//! Random Choices
+//!
+//! Random actions need something to pick with, but nothing about it has to
+//! be any good at being random, so rather than pulling in a dependency it's
+//! a splitmix64 generator. The driver seeds its own from the same source of
+//! randomness that hash maps use, while replaying fixtures always uses the
+//! same seed, so that what a fixture expects is what it gets every time.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// The seed that fixtures are replayed with.
+pub(crate) const FIXTURE_SEED: u64 = 0x5eed;
+
+#[derive(Debug, Clone)]
+pub(crate) struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub(crate) fn new() -> Rng {
+        Rng::seeded(RandomState::new().build_hasher().finish())
+    }
+
+    pub(crate) fn seeded(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Picks the index of one of the given weights, in proportion to how
+    /// heavy it is, or `None` if there's nothing with any weight to pick.
+    pub(crate) fn pick(&mut self, weights: &[u32]) -> Option<usize> {
+        let total: u64 = weights.iter().map(|w| u64::from(*w)).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut roll = self.next_u64() % total;
+        weights.iter().position(|w| {
+            let w = u64::from(*w);
+            if roll < w {
+                true
+            } else {
+                roll -= w;
+                false
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_by_weight() {
+        let mut rng = Rng::seeded(FIXTURE_SEED);
+        let mut counts = [0; 3];
+        for _ in 0..4000 {
+            counts[rng.pick(&[1, 0, 3]).unwrap()] += 1;
+        }
+        assert_eq!(counts[1], 0);
+        assert!((800..1200).contains(&counts[0]), "{:?}", counts);
+
+        assert_eq!(rng.pick(&[0, 0]), None);
+        assert_eq!(rng.pick(&[]), None);
+    }
+}
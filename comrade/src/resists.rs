@@ -0,0 +1,298 @@
+//! Resist Tracking
+//!
+//! Every spell that a character casts is tallied per target, along with how
+//! often it was resisted, interrupted, or fizzled, so that casters can see
+//! which debuffs are worth casting on what, rather than keeping count by eye.
+//! The game says when a spell is resisted, but every spell says something
+//! different when it lands, so a cast only counts as having landed once the
+//! character starts casting something else without it having been resisted.
+//!
+//! Older logs don't say who resisted either, so casts are counted against
+//! whatever the character last hit, or was last resisted by by name, which
+//! is what they're usually fighting.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+use log::debug;
+use parking_lot::Mutex;
+use regex::Regex;
+
+use crate::combat::parse_hit;
+use crate::config::CharacterId;
+use crate::time::Instant;
+use crate::watcher::LogEvent;
+
+/// What casts are counted against when the character hasn't hit anything
+/// yet, since the game doesn't always say who they were casting at.
+const UNKNOWN_TARGET: &str = "Unknown";
+
+lazy_static! {
+    // e.g. "You begin casting Malosini."
+    static ref CAST_RE: Regex = Regex::new(r"^You begin casting (?P<spell>.+)\.$").unwrap();
+    // e.g. "Your target resisted the Malosini spell.", or in newer logs,
+    // "a fire drake resisted your Malosini!"
+    static ref RESISTED_RE: Regex = Regex::new(
+        r"^(?:Your target resisted the (?P<spell>.+) spell\.|(?P<target>.+) resisted your (?P<named>.+)!)$"
+    )
+    .unwrap();
+    static ref INTERRUPTED_RE: Regex =
+        Regex::new(r"^Your (?:spell is interrupted\.|casting has been interrupted!)$").unwrap();
+    static ref FIZZLED_RE: Regex = Regex::new(r"^Your spell fizzles!$").unwrap();
+}
+
+enum Cast<'a> {
+    Begun(&'a str),
+    Resisted {
+        target: Option<&'a str>,
+        spell: &'a str,
+    },
+    Interrupted,
+    Fizzled,
+    /// The character hit something, which is who they're probably casting
+    /// at as well.
+    Hit(&'a str),
+}
+
+fn parse_cast(line: &str) -> Option<Cast<'_>> {
+    if let Some(caps) = CAST_RE.captures(line) {
+        Some(Cast::Begun(caps.name("spell")?.as_str()))
+    } else if let Some(caps) = RESISTED_RE.captures(line) {
+        Some(Cast::Resisted {
+            target: caps.name("target").map(|m| m.as_str()),
+            spell: caps.name("spell").or_else(|| caps.name("named"))?.as_str(),
+        })
+    } else if INTERRUPTED_RE.is_match(line) {
+        Some(Cast::Interrupted)
+    } else if FIZZLED_RE.is_match(line) {
+        Some(Cast::Fizzled)
+    } else {
+        parse_hit(line)
+            .filter(|hit| hit.attacker.is_none())
+            .map(|hit| Cast::Hit(hit.target))
+    }
+}
+
+/// Whether a log line is one that resist tracking needs to see.
+pub(crate) fn is_resist_line(line: &str) -> bool {
+    parse_cast(line).is_some()
+}
+
+#[derive(Debug, Clone)]
+pub struct ResistStats {
+    /// The character that cast the spell.
+    pub character: CharacterId,
+    pub target: String,
+    pub spell: String,
+    pub casts: u64,
+    pub resists: u64,
+    pub interrupts: u64,
+    pub fizzles: u64,
+    /// How many times in a row, up to now, the target has resisted the
+    /// spell.
+    pub streak: u32,
+    /// When the spell was last cast at the target, for evicting the ones
+    /// that haven't been in a while first.
+    pub(crate) last_cast: Instant,
+}
+
+impl ResistStats {
+    fn new(character: CharacterId, target: String, spell: String) -> ResistStats {
+        ResistStats {
+            character,
+            target,
+            spell,
+            casts: 0,
+            resists: 0,
+            interrupts: 0,
+            fizzles: 0,
+            streak: 0,
+            last_cast: Instant::now(),
+        }
+    }
+
+    /// The share of casts that reached the target which it resisted, as a
+    /// percentage.
+    pub fn resist_rate(&self) -> f64 {
+        let reached = self.casts - self.interrupts - self.fizzles;
+        if reached == 0 {
+            return 0.0;
+        }
+        self.resists as f64 * 100.0 / reached as f64
+    }
+}
+
+/// The stats for every spell cast at every target since Comrade started, by
+/// character, target, and spell.
+pub(crate) type ResistLog = Arc<Mutex<BTreeMap<(CharacterId, String, String), ResistStats>>>;
+
+#[derive(Debug, Default)]
+struct Caster {
+    /// Whatever the character last hit, or was last resisted by.
+    target: Option<String>,
+    /// The target and spell of the cast that's underway, until it's known
+    /// how it went.
+    casting: Option<(String, String)>,
+}
+
+/// Tallies every cast into the resist log, keeping track of what each
+/// character is casting at.
+pub(crate) struct Resists {
+    casters: HashMap<CharacterId, Caster>,
+    totals: ResistLog,
+}
+
+impl Resists {
+    pub(crate) fn new(totals: ResistLog) -> Resists {
+        Resists {
+            casters: HashMap::new(),
+            totals,
+        }
+    }
+
+    /// Tallies the event, returning the stats for the spell once its target
+    /// has resisted it at least `streak` times in a row.
+    pub(crate) fn log_event(
+        &mut self,
+        event: &LogEvent,
+        streak: Option<u32>,
+    ) -> Option<ResistStats> {
+        let cast = parse_cast(event.message())?;
+        let id = &*event.id;
+        let caster = self.casters.entry(id.clone()).or_default();
+
+        let mut totals = self.totals.lock();
+
+        match cast {
+            Cast::Begun(spell) => {
+                // Nothing was said about the last cast, so it landed.
+                if let Some((target, spell)) = caster.casting.take() {
+                    tally(&mut totals, id, target.as_str(), spell.as_str()).streak = 0;
+                }
+                let target = caster.target.as_deref().unwrap_or(UNKNOWN_TARGET);
+                caster.casting = Some((target.to_string(), spell.to_string()));
+            }
+            Cast::Resisted { target, spell } => {
+                let casting = caster.casting.take_if(|(_, s)| s == spell);
+                let target = match (target, casting) {
+                    (Some(target), _) => {
+                        caster.target = Some(target.to_string());
+                        target.to_string()
+                    }
+                    (None, Some((target, _))) => target,
+                    (None, None) => caster
+                        .target
+                        .clone()
+                        .unwrap_or_else(|| UNKNOWN_TARGET.to_string()),
+                };
+
+                let stats = tally(&mut totals, id, target.as_str(), spell);
+                stats.resists += 1;
+                stats.streak += 1;
+                debug!(
+                    "{} resisted {} {} times in a row",
+                    target, spell, stats.streak
+                );
+                if streak.is_some_and(|streak| stats.streak >= streak) {
+                    return Some(stats.clone());
+                }
+            }
+            Cast::Interrupted | Cast::Fizzled => {
+                if let Some((target, spell)) = caster.casting.take() {
+                    let stats = tally(&mut totals, id, target.as_str(), spell.as_str());
+                    match cast {
+                        Cast::Interrupted => stats.interrupts += 1,
+                        _ => stats.fizzles += 1,
+                    }
+                }
+            }
+            Cast::Hit(target) => caster.target = Some(target.to_string()),
+        }
+
+        None
+    }
+}
+
+/// Counts a cast of the spell at the target, returning its stats for
+/// whatever else there is to count.
+fn tally<'a>(
+    totals: &'a mut BTreeMap<(CharacterId, String, String), ResistStats>,
+    id: &CharacterId,
+    target: &str,
+    spell: &str,
+) -> &'a mut ResistStats {
+    let stats = totals
+        .entry((id.clone(), target.to_string(), spell.to_string()))
+        .or_insert_with(|| ResistStats::new(id.clone(), target.to_string(), spell.to_string()));
+    stats.casts += 1;
+    stats.last_cast = Instant::now();
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tallies_resists_per_target() {
+        let log = ResistLog::default();
+        let mut resists = Resists::new(log.clone());
+        let id = Arc::new(CharacterId::new("soandso"));
+        let mut streaks = Vec::new();
+        for line in [
+            "You begin casting Malosini.",
+            "Your spell fizzles!",
+            "You slash a fire drake for 312 points of damage.",
+            "You begin casting Malosini.",
+            "Your target resisted the Malosini spell.",
+            "You begin casting Malosini.",
+            "a fire drake resisted your Malosini!",
+            "You begin casting Malosini.",
+            "Your target resisted the Malosini spell.",
+            "You begin casting Malosini.",
+            "Your casting has been interrupted!",
+            "You begin casting Malosini.",
+            "You begin casting Tashanian.",
+            "an ice drake resisted your Tashanian!",
+        ] {
+            let line = format!("[Sat Oct 17 20:15:00 2026] {}", line);
+            let event = LogEvent::parse(id.clone(), line.as_str()).unwrap();
+            if let Some(stats) = resists.log_event(&event, Some(3)) {
+                streaks.push((stats.target, stats.spell, stats.streak));
+            }
+        }
+
+        assert_eq!(
+            streaks,
+            vec![("a fire drake".to_string(), "Malosini".to_string(), 3)]
+        );
+
+        let log = log.lock();
+        let counts = |target: &str, spell: &str| {
+            let stats = &log[&(
+                CharacterId::new("soandso"),
+                target.to_string(),
+                spell.to_string(),
+            )];
+            (
+                stats.casts,
+                stats.resists,
+                stats.interrupts,
+                stats.fizzles,
+                stats.streak,
+            )
+        };
+        assert_eq!(counts("Unknown", "Malosini"), (1, 0, 0, 1, 0));
+        assert_eq!(counts("a fire drake", "Malosini"), (5, 3, 1, 0, 0));
+        assert_eq!(counts("an ice drake", "Tashanian"), (1, 1, 0, 0, 1));
+        assert_eq!(log.len(), 3);
+
+        let stats = &log[&(
+            CharacterId::new("soandso"),
+            "a fire drake".to_string(),
+            "Malosini".to_string(),
+        )];
+        assert_eq!(stats.resist_rate(), 75.0);
+    }
+}
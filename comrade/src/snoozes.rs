@@ -0,0 +1,88 @@
+//! Snoozes
+//!
+//! Snoozing a trigger quiets it for a while, e.g. to stop hearing about every
+//! tell for the next ten minutes, without having to turn it off and then
+//! remember to turn it back on again. A snoozed trigger still matches, the
+//! driver just doesn't carry out anything it would have done, until the
+//! snooze is up and it wakes back up on its own.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+
+use crate::config::triggers::TriggerRef;
+use crate::time::Instant;
+
+#[derive(Debug, Default)]
+pub(crate) struct Snoozes {
+    until: BTreeMap<TriggerRef, Instant>,
+}
+
+pub(crate) type SnoozeLog = Arc<Mutex<Snoozes>>;
+
+impl Snoozes {
+    /// Snoozes the given trigger for the given duration from now, replacing
+    /// whatever snooze it already had.
+    pub(crate) fn snooze(&mut self, tref: TriggerRef, duration: Duration) {
+        self.until.insert(tref, Instant::now() + duration);
+    }
+
+    /// Wakes the given trigger back up early, returning whether it was
+    /// snoozed.
+    pub(crate) fn wake(&mut self, tref: &TriggerRef) -> bool {
+        self.until.remove(tref).is_some()
+    }
+
+    pub(crate) fn is_snoozed(&self, tref: &TriggerRef) -> bool {
+        self.until
+            .get(tref)
+            .is_some_and(|until| Instant::now() < *until)
+    }
+
+    /// How long each trigger that's snoozed has left to go.
+    pub(crate) fn remaining(&self) -> Vec<(TriggerRef, Duration)> {
+        let now = Instant::now();
+        self.until
+            .iter()
+            .filter(|(_, until)| now < **until)
+            .map(|(tref, until)| (tref.clone(), until.duration_since(now)))
+            .collect()
+    }
+
+    /// Forgets every snooze that's up, returning the triggers that woke up.
+    pub(crate) fn expire(&mut self) -> Vec<TriggerRef> {
+        let now = Instant::now();
+        let (woken, snoozed) = std::mem::take(&mut self.until)
+            .into_iter()
+            .partition(|(_, until)| now >= *until);
+        self.until = snoozed;
+        woken.into_keys().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::triggers::{TriggerId, TriggerSource};
+
+    #[test]
+    fn snoozes_wake_up() {
+        let tells = TriggerRef::new(TriggerSource::Local, TriggerId::new("tells"));
+        let invites = TriggerRef::new(TriggerSource::Local, TriggerId::new("invites"));
+
+        let mut snoozes = Snoozes::default();
+        snoozes.snooze(tells.clone(), Duration::from_secs(600));
+        snoozes.snooze(invites.clone(), Duration::ZERO);
+        assert!(snoozes.is_snoozed(&tells));
+        assert!(!snoozes.is_snoozed(&invites));
+
+        assert_eq!(snoozes.expire(), vec![invites]);
+        assert_eq!(snoozes.remaining().len(), 1);
+
+        assert!(snoozes.wake(&tells));
+        assert!(!snoozes.wake(&tells));
+        assert!(!snoozes.is_snoozed(&tells));
+    }
+}
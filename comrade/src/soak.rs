@@ -0,0 +1,317 @@
+//! Soak Tests
+//!
+//! A pack that keeps up with one character on a quiet evening can still fall
+//! behind six of them in the middle of a raid, and the first anyone hears of
+//! it shouldn't be a missed emote. A soak test makes up log lines for a number
+//! of made up characters, at a steady rate for as long as it's asked to, and
+//! hands them to a driver of its own running the loaded triggers, reporting
+//! how many lines it got through, how many it had to drop because it was too
+//! far behind, and how much memory grew along the way.
+//!
+//! The made up characters take their settings from the configured ones, so
+//! the same triggers are on for them, and nothing they do is written anywhere.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use arc_swap::ArcSwap;
+use crossbeam_channel::TrySendError;
+use serde::Serialize;
+
+use crate::config::triggers::PatternCache;
+use crate::config::{Character, CharacterId, ClockOffset, Config};
+use crate::demo;
+use crate::driver::{Driver, Tracked};
+use crate::errors::ComradeError;
+use crate::executors::Executors;
+use crate::history::{self, LogTime};
+use crate::watcher::{self, LogEvent};
+
+type Result<T, E = ComradeError> = core::result::Result<T, E>;
+
+/// How often lines are handed to the driver, in batches of however many are
+/// due by then.
+const BATCH_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How long the driver is given to catch up on the lines it's behind on once
+/// the run is over, before they're counted as dropped.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a soak test should throw at the triggers.
+#[derive(Debug, Clone)]
+pub struct SoakOptions {
+    /// How many lines a second to make up, across every character.
+    pub rate: u32,
+    pub characters: usize,
+    pub duration: Duration,
+    /// A log to take the made up lines from, rather than the built in mix of
+    /// chatter and combat.
+    pub log: Option<PathBuf>,
+    /// How many allocations the process has made so far, for frontends that
+    /// count them with a global allocator of their own, so that the report
+    /// can say how many each line costs.
+    pub allocations: Option<fn() -> u64>,
+}
+
+impl Default for SoakOptions {
+    fn default() -> SoakOptions {
+        SoakOptions {
+            rate: 5000,
+            characters: 6,
+            duration: Duration::from_secs(60),
+            log: None,
+            allocations: None,
+        }
+    }
+}
+
+/// How a soak test went.
+#[derive(Debug, Clone, Serialize)]
+pub struct SoakReport {
+    pub characters: usize,
+    /// The rate that lines were asked to be made up at.
+    pub rate: u32,
+    /// How long lines were made up for, in seconds.
+    pub seconds: f64,
+    /// How many lines were made up.
+    pub lines: u64,
+    /// How many of them the driver got through.
+    pub processed: u64,
+    /// How many of them were dropped, because the driver was so far behind
+    /// that there was nowhere left to queue them.
+    pub dropped: u64,
+    /// The most lines that were ever waiting on the driver at once.
+    pub backlog: usize,
+    /// How many events the triggers sent.
+    pub events: u64,
+    /// How much memory the process was using before and after the run, in
+    /// bytes, where that can be found out.
+    pub memory_before: Option<u64>,
+    pub memory_after: Option<u64>,
+    /// How many allocations were made over the run, if they were counted.
+    pub allocations: Option<u64>,
+}
+
+impl SoakReport {
+    /// The lines a second that the driver kept up over the run.
+    pub fn throughput(&self) -> f64 {
+        if self.seconds > 0.0 {
+            self.processed as f64 / self.seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// How much memory grew over the run, in bytes.
+    pub fn memory_growth(&self) -> Option<i64> {
+        Some(self.memory_after? as i64 - self.memory_before? as i64)
+    }
+
+    /// How many allocations each line cost, on average, if they were
+    /// counted.
+    pub fn allocations_per_line(&self) -> Option<f64> {
+        let allocations = self.allocations?;
+        (self.lines > 0).then(|| allocations as f64 / self.lines as f64)
+    }
+
+    /// Whether the driver kept up with every line it was given.
+    pub fn kept_up(&self) -> bool {
+        self.dropped == 0
+    }
+}
+
+/// Runs a soak test against the triggers of the given configuration, which
+/// blocks for as long as the test is asked to run, and then for as long as it
+/// takes the driver to catch up.
+pub(crate) fn run(loaded: &Config, options: &SoakOptions) -> Result<SoakReport> {
+    let messages = match options.log {
+        Some(ref log) => messages(log)?,
+        None => demo::SCRIPT.iter().map(|(_, m)| m.to_string()).collect(),
+    };
+
+    let config = config(loaded, options.characters);
+    let ids: Vec<Arc<CharacterId>> = config.characters.keys().cloned().map(Arc::new).collect();
+    let filters: Vec<_> = ids
+        .iter()
+        .map(|id| crate::line_filter(&config, id))
+        .collect();
+
+    let (logs, receiver) = watcher::channel();
+    let driver = Driver::create(
+        Arc::new(ArcSwap::from_pointee(config)),
+        receiver,
+        Tracked::default(),
+        // Nothing custom is carried out for made up lines either.
+        Executors::default(),
+    );
+
+    let memory_before = memory();
+    let mut report = SoakReport {
+        characters: ids.len(),
+        rate: options.rate,
+        seconds: 0.0,
+        lines: 0,
+        processed: 0,
+        dropped: 0,
+        backlog: 0,
+        events: 0,
+        memory_before,
+        memory_after: None,
+        allocations: None,
+    };
+    let allocations = options.allocations.map(|count| (count, count()));
+
+    let started = Instant::now();
+    let mut next = 0;
+    while started.elapsed() < options.duration && !messages.is_empty() && !ids.is_empty() {
+        let due = (started.elapsed().as_secs_f64() * f64::from(options.rate)) as u64;
+        let timestamp = LogTime::now().to_timestamp();
+        while report.lines < due {
+            let character = next % ids.len();
+            let message = &messages[next % messages.len()];
+            next += 1;
+            report.lines += 1;
+
+            // Lines that no trigger could match never reach the driver, just
+            // like they wouldn't from a real log.
+            if !filters[character](message) {
+                report.processed += 1;
+                continue;
+            }
+
+            let line = format!("[{}] {}", timestamp, message);
+            let event = match LogEvent::parse(ids[character].clone(), line.as_str()) {
+                Some(event) => event,
+                None => continue,
+            };
+            match logs.try_send(Arc::new(event)) {
+                Ok(()) => report.processed += 1,
+                Err(TrySendError::Full(_)) => report.dropped += 1,
+                Err(TrySendError::Disconnected(_)) => break,
+            }
+        }
+
+        report.backlog = report.backlog.max(logs.len());
+        while driver.event().is_some() {
+            report.events += 1;
+        }
+        thread::sleep(BATCH_INTERVAL);
+    }
+    report.seconds = started.elapsed().as_secs_f64();
+
+    // Whatever the driver can't catch up on in time was as good as dropped.
+    let drained = Instant::now();
+    while !logs.is_empty() && drained.elapsed() < DRAIN_TIMEOUT {
+        while driver.event().is_some() {
+            report.events += 1;
+        }
+        thread::sleep(BATCH_INTERVAL);
+    }
+    report.processed -= logs.len() as u64;
+    report.dropped += logs.len() as u64;
+    while driver.event().is_some() {
+        report.events += 1;
+    }
+
+    drop(driver);
+    report.memory_after = memory();
+    report.allocations = allocations.map(|(count, before)| count() - before);
+
+    Ok(report)
+}
+
+/// A copy of the loaded configuration with the given number of made up
+/// characters in place of the configured ones, each taking its settings from
+/// a configured character in turn, and with its triggers compiled for them.
+fn config(loaded: &Config, characters: usize) -> Config {
+    let mut config = loaded.clone();
+    // Made up lines shouldn't set off anything physical, or end up in the
+    // history.
+    config.outputs.clear();
+    config.history.record = false;
+
+    let configured: Vec<Character> = loaded.characters.values().cloned().collect();
+    config.characters.clear();
+    for idx in 0..characters {
+        let character = match configured.get(idx % configured.len().max(1)) {
+            Some(character) => Character {
+                filename: PathBuf::new(),
+                ..character.clone()
+            },
+            None => Character {
+                name: format!("Soak{}", idx + 1),
+                server: "soak".to_string(),
+                filename: PathBuf::new(),
+                aliases: Vec::new(),
+                display_name: None,
+                clock_offset: ClockOffset::default(),
+                language: None,
+                voice: None,
+                disabled_triggers: Default::default(),
+                enabled_triggers: Default::default(),
+            },
+        };
+        config
+            .characters
+            .insert(CharacterId::new(format!("soak-{}", idx + 1)), character);
+    }
+    config.load_triggers(&PatternCache::default(), |_, _, _| {});
+
+    config
+}
+
+/// The message of every line in the given log.
+fn messages(log: &Path) -> Result<Vec<String>> {
+    let id = Arc::new(CharacterId::new("soak"));
+    let mut messages = Vec::new();
+    history::replay(log, &id, 0, |event| {
+        messages.push(event.message().to_string())
+    })?;
+
+    Ok(messages)
+}
+
+/// How much memory the process is using, in bytes, which is only known on
+/// Linux.
+fn memory() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    let kb: u64 = line
+        .trim_start_matches("VmRSS:")
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+
+    Some(kb * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soaks_the_loaded_triggers() {
+        let mut loaded = demo::config();
+        loaded.load_triggers(&PatternCache::default(), |_, _, _| {});
+        let options = SoakOptions {
+            rate: 2000,
+            characters: 3,
+            duration: Duration::from_millis(250),
+            allocations: Some(|| 7),
+            ..SoakOptions::default()
+        };
+
+        let report = run(&loaded, &options).unwrap();
+        assert_eq!(report.characters, 3);
+        assert!(report.lines > 0);
+        assert_eq!(report.processed + report.dropped, report.lines);
+        assert!(report.events > 0);
+        assert!(report.throughput() > 0.0);
+        assert_eq!(report.allocations_per_line(), Some(0.0));
+    }
+}
@@ -0,0 +1,561 @@
+//! Event Store
+//!
+//! Each time a trigger fires, who it fired for, the zone they were in, and
+//! the line that it fired on are appended to the event store in the data
+//! directory, so that `history` can find them later without replaying the
+//! logs against whatever the triggers have become since. They're recorded as
+//! the driver sees them, so triggers with conditions on the current fight
+//! count too, which replaying a log can't tell.
+//!
+//! There's a file for each month, and like the audit log each record is its
+//! own element of an array of tables, so that a month's file can only ever be
+//! appended to. Months of raiding add up, so once a month is over its file is
+//! compressed, and whole months are deleted once they're older than the
+//! configuration keeps them for, or, oldest first, once the store is bigger
+//! than it's allowed to grow. That's done every so often while Comrade runs,
+//! on a thread of its own, and `vacuum` does it straight away.
+//!
+//! A month can get more records after it's been compressed, like from a log
+//! whose clock is set differently, which are compressed onto the end of it
+//! the next time around, since a compressed file can hold more than one
+//! stream one after another.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::io::prelude::*;
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::{info, warn};
+use parking_lot::{const_mutex, Mutex};
+use serde::{Deserialize, Serialize};
+
+use crate::config::create_dir;
+use crate::config::history::HistoryConfig;
+use crate::errors::HistoryError;
+use crate::history::LogTime;
+use crate::time::Instant;
+
+const STORE_DIRNAME: &str = "history";
+
+const EXTENSION: &str = ".toml";
+
+const ARCHIVE_EXTENSION: &str = ".toml.gz";
+
+/// How often the store is compacted while Comrade is running.
+const COMPACT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Only one compaction at a time, whether it's the one in the background or
+/// a `vacuum`, so that a month isn't compressed onto the end of itself twice.
+static COMPACTING: Mutex<()> = const_mutex(());
+
+pub(crate) fn store_dir(data_dir: &Path) -> PathBuf {
+    data_dir.join(STORE_DIRNAME)
+}
+
+/// A time that a trigger fired, as it's kept in the store.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Record {
+    /// In seconds since 1970, in the log's own time, like `LogTime`.
+    pub(crate) time: i64,
+    /// The timestamp of the line, as it was written in the log.
+    pub(crate) timestamp: String,
+    /// The id of the character whose log it was.
+    pub(crate) character: String,
+    pub(crate) name: String,
+    /// Which trigger fired, like `local/slowed`.
+    pub(crate) trigger: String,
+    pub(crate) trigger_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) zone: Option<String>,
+    pub(crate) message: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Records {
+    #[serde(default)]
+    entry: Vec<Record>,
+}
+
+/// A month of records, like `2026-10`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct Month(i64);
+
+impl Month {
+    pub(crate) fn new(year: i64, month: i64) -> Month {
+        Month(year * 12 + month - 1)
+    }
+
+    pub(crate) fn of(time: LogTime) -> Month {
+        let (year, month) = time.month();
+        Month::new(year, month)
+    }
+
+    /// The month that's the given number of months before this one.
+    fn minus(self, months: u32) -> Month {
+        Month(self.0 - i64::from(months))
+    }
+
+    /// The month that the given filename has the records of, and whether
+    /// it's been compressed.
+    fn from_filename(filename: &str) -> Option<(Month, bool)> {
+        let (stem, archived) = match filename.strip_suffix(ARCHIVE_EXTENSION) {
+            Some(stem) => (stem, true),
+            None => (filename.strip_suffix(EXTENSION)?, false),
+        };
+        let (year, month) = stem.split_once('-')?;
+        let (year, month) = (year.parse().ok()?, month.parse().ok()?);
+        if year < 0 || !(1..=12).contains(&month) {
+            return None;
+        }
+
+        Some((Month::new(year, month), archived))
+    }
+
+    fn filename(self, archived: bool) -> String {
+        let extension = if archived {
+            ARCHIVE_EXTENSION
+        } else {
+            EXTENSION
+        };
+        format!("{}{}", self, extension)
+    }
+}
+
+impl fmt::Display for Month {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}",
+            self.0.div_euclid(12),
+            self.0.rem_euclid(12) + 1
+        )
+    }
+}
+
+/// What compacting the store did.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct VacuumReport {
+    /// The months that were compressed, like `2026-09`.
+    pub archived: Vec<String>,
+    /// The months that were deleted, for being too old or to make room.
+    pub deleted: Vec<String>,
+    /// How much the store took up before it was compacted, in bytes.
+    pub before: u64,
+    /// How much it takes up now, in bytes.
+    pub after: u64,
+}
+
+impl VacuumReport {
+    fn is_empty(&self) -> bool {
+        self.archived.is_empty() && self.deleted.is_empty()
+    }
+}
+
+/// Records triggers as they fire, and compacts the store in the background
+/// every so often.
+#[derive(Default)]
+pub(crate) struct EventStore {
+    compacted: Option<Instant>,
+    compacting: Option<JoinHandle<()>>,
+}
+
+impl EventStore {
+    /// Appends a record to its month's file. A record that can't be written
+    /// is only warned about, since the trigger has already fired.
+    pub(crate) fn record(&self, data_dir: &Path, record: &Record) {
+        let dir = store_dir(data_dir);
+        let filename = dir.join(Month::of(LogTime::from_seconds(record.time)).filename(false));
+        let result = toml_edit::ser::to_string(record)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            .and_then(|s| {
+                create_dir(dir.as_path())?;
+                fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(filename.as_path())
+                    .and_then(|mut file| write!(file, "[[entry]]\n{}", s))
+            });
+        if let Err(e) = result {
+            warn!(
+                "could not record {} in {}: {}",
+                record.trigger,
+                filename.display(),
+                e
+            );
+        }
+    }
+
+    /// Compacts the store on a thread of its own, if it's been long enough
+    /// since it was last compacted, and it isn't still being compacted.
+    pub(crate) fn maintain(&mut self, config: &HistoryConfig, data_dir: &Path) {
+        if self
+            .compacted
+            .is_some_and(|c| c.elapsed() < COMPACT_INTERVAL)
+            || self.compacting.as_ref().is_some_and(|c| !c.is_finished())
+        {
+            return;
+        }
+        self.compacted = Some(Instant::now());
+
+        let config = config.clone();
+        let data_dir = data_dir.to_path_buf();
+        let spawned = thread::Builder::new()
+            .name("comrade history compaction".to_string())
+            .spawn(
+                move || match compact(&config, data_dir.as_path(), Month::of(LogTime::now())) {
+                    Ok(report) if !report.is_empty() => info!(
+                        "compacted the event store, archived {:?} and deleted {:?}",
+                        report.archived, report.deleted
+                    ),
+                    Ok(_) => {}
+                    Err(e) => warn!("could not compact the event store: {}", e),
+                },
+            );
+        match spawned {
+            Ok(handle) => self.compacting = Some(handle),
+            Err(e) => warn!("could not start compacting the event store: {}", e),
+        }
+    }
+}
+
+/// A month's file in the store.
+struct Stored {
+    month: Month,
+    archived: bool,
+    filename: PathBuf,
+    size: u64,
+}
+
+/// Every file in the store, oldest month first, with a month's compressed
+/// file before the one that's still being written to.
+fn stored(dir: &Path) -> Result<Vec<Stored>, HistoryError> {
+    let read_error = |source| HistoryError::IOError {
+        source,
+        filename: dir.to_path_buf(),
+    };
+
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(read_error(e)),
+    };
+
+    let mut stored = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(read_error)?;
+        let name = entry.file_name();
+        let (month, archived) = match name.to_str().and_then(Month::from_filename) {
+            Some(parsed) => parsed,
+            None => continue,
+        };
+        let size = entry.metadata().map_err(read_error)?.len();
+        stored.push(Stored {
+            month,
+            archived,
+            filename: entry.path(),
+            size,
+        });
+    }
+    stored.sort_by_key(|s| (s.month, !s.archived));
+
+    Ok(stored)
+}
+
+/// Compresses every month before the current one, then deletes the months
+/// that are older than the configuration keeps, and then the oldest months
+/// until the store fits in its size, other than the current one.
+pub(crate) fn compact(
+    config: &HistoryConfig,
+    data_dir: &Path,
+    now: Month,
+) -> Result<VacuumReport, HistoryError> {
+    let _compacting = COMPACTING.lock();
+    let dir = store_dir(data_dir);
+    let mut report = VacuumReport::default();
+
+    let before = stored(dir.as_path())?;
+    report.before = before.iter().map(|s| s.size).sum();
+    for plain in before.iter().filter(|s| !s.archived && s.month < now) {
+        archive(plain, dir.as_path())?;
+        report.archived.push(plain.month.to_string());
+    }
+
+    let mut kept = stored(dir.as_path())?;
+    if config.keep_months > 0 {
+        let oldest = now.minus(config.keep_months - 1);
+        for old in kept.iter().filter(|s| s.month < oldest) {
+            delete(old, &mut report)?;
+        }
+        kept.retain(|s| s.month >= oldest);
+    }
+
+    let budget = config.max_size.0 as u64;
+    let mut size: u64 = kept.iter().map(|s| s.size).sum();
+    if budget > 0 {
+        for old in kept.iter().filter(|s| s.month < now) {
+            if size <= budget {
+                break;
+            }
+            delete(old, &mut report)?;
+            size -= old.size;
+        }
+    }
+    report.after = stored(dir.as_path())?.iter().map(|s| s.size).sum();
+
+    Ok(report)
+}
+
+/// Compresses a month's file onto the end of its compressed file, and then
+/// removes it.
+fn archive(plain: &Stored, dir: &Path) -> Result<(), HistoryError> {
+    let archive = dir.join(plain.month.filename(true));
+    let write_error = |source| HistoryError::WriteError {
+        source,
+        filename: archive.clone(),
+    };
+
+    let contents = fs::read(plain.filename.as_path()).map_err(|source| HistoryError::IOError {
+        source,
+        filename: plain.filename.clone(),
+    })?;
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(archive.as_path())
+        .map_err(write_error)?;
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    encoder
+        .write_all(contents.as_slice())
+        .map_err(write_error)?;
+    encoder
+        .finish()
+        .and_then(|file| file.sync_all())
+        .map_err(write_error)?;
+
+    fs::remove_file(plain.filename.as_path()).map_err(|source| HistoryError::WriteError {
+        source,
+        filename: plain.filename.clone(),
+    })
+}
+
+fn delete(stored: &Stored, report: &mut VacuumReport) -> Result<(), HistoryError> {
+    fs::remove_file(stored.filename.as_path()).map_err(|source| HistoryError::WriteError {
+        source,
+        filename: stored.filename.clone(),
+    })?;
+
+    let month = stored.month.to_string();
+    if !report.deleted.contains(&month) {
+        report.deleted.push(month);
+    }
+
+    Ok(())
+}
+
+/// Every record in the store from the given months, oldest month first.
+pub(crate) fn read(
+    data_dir: &Path,
+    from: Option<Month>,
+    until: Option<Month>,
+) -> Result<Vec<Record>, HistoryError> {
+    let mut records = Vec::new();
+    for stored in stored(store_dir(data_dir).as_path())? {
+        if from.is_some_and(|f| stored.month < f) || until.is_some_and(|u| stored.month > u) {
+            continue;
+        }
+
+        let read_error = |source| HistoryError::IOError {
+            source,
+            filename: stored.filename.clone(),
+        };
+        let mut contents = String::new();
+        let file = fs::File::open(stored.filename.as_path()).map_err(read_error)?;
+        if stored.archived {
+            MultiGzDecoder::new(file).read_to_string(&mut contents)
+        } else {
+            io::BufReader::new(file).read_to_string(&mut contents)
+        }
+        .map_err(read_error)?;
+
+        let month: Records = toml_edit::de::from_str(contents.as_str()).map_err(|source| {
+            HistoryError::InvalidRecords {
+                source,
+                filename: stored.filename.clone(),
+            }
+        })?;
+        records.extend(month.entry);
+    }
+
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use super::*;
+    use crate::config::memory::ByteSize;
+
+    fn record(time: &str, trigger: &str) -> Record {
+        let time: LogTime = time.parse().unwrap();
+        Record {
+            time: time.seconds(),
+            timestamp: time.to_timestamp(),
+            character: "Soandso".to_string(),
+            name: "Soandso".to_string(),
+            trigger: format!("local/{}", trigger.to_lowercase()),
+            trigger_name: trigger.to_string(),
+            zone: Some("The Plane of Fear".to_string()),
+            message: "You have been slowed.".to_string(),
+        }
+    }
+
+    fn data_dir(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("comrade-store-{}-{}", name, process::id()))
+    }
+
+    fn files(data_dir: &Path) -> Vec<String> {
+        let mut files: Vec<String> = fs::read_dir(store_dir(data_dir))
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn records_each_month_in_its_own_file() {
+        let data_dir = data_dir("months");
+        let store = EventStore::default();
+        store.record(data_dir.as_path(), &record("2026-09-30 23:00", "Slowed"));
+        store.record(data_dir.as_path(), &record("2026-10-01 01:00", "Slowed"));
+        store.record(data_dir.as_path(), &record("2026-10-17 20:15", "Rooted"));
+
+        assert_eq!(files(data_dir.as_path()), ["2026-09.toml", "2026-10.toml"]);
+        let read = read(data_dir.as_path(), Some(Month::new(2026, 10)), None).unwrap();
+        assert_eq!(
+            read,
+            [
+                record("2026-10-01 01:00", "Slowed"),
+                record("2026-10-17 20:15", "Rooted")
+            ]
+        );
+
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    #[test]
+    fn compresses_months_that_are_over() {
+        let data_dir = data_dir("compress");
+        let store = EventStore::default();
+        store.record(data_dir.as_path(), &record("2026-09-30 23:00", "Slowed"));
+        store.record(data_dir.as_path(), &record("2026-10-01 01:00", "Slowed"));
+
+        let config = HistoryConfig::default();
+        let report = compact(&config, data_dir.as_path(), Month::new(2026, 10)).unwrap();
+        assert_eq!(report.archived, ["2026-09"]);
+        assert!(report.deleted.is_empty());
+        assert_eq!(
+            files(data_dir.as_path()),
+            ["2026-09.toml.gz", "2026-10.toml"]
+        );
+
+        // A late record for a month that's been compressed is compressed
+        // onto the end of it.
+        store.record(data_dir.as_path(), &record("2026-09-30 23:30", "Rooted"));
+        compact(&config, data_dir.as_path(), Month::new(2026, 10)).unwrap();
+        assert_eq!(
+            files(data_dir.as_path()),
+            ["2026-09.toml.gz", "2026-10.toml"]
+        );
+        assert_eq!(
+            read(data_dir.as_path(), None, None).unwrap(),
+            [
+                record("2026-09-30 23:00", "Slowed"),
+                record("2026-09-30 23:30", "Rooted"),
+                record("2026-10-01 01:00", "Slowed"),
+            ]
+        );
+
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    #[test]
+    fn deletes_months_past_keeping() {
+        let data_dir = data_dir("keep");
+        let store = EventStore::default();
+        for month in ["2026-07-01", "2026-08-01", "2026-09-01", "2026-10-01"] {
+            store.record(data_dir.as_path(), &record(month, "Slowed"));
+        }
+
+        let config = HistoryConfig {
+            keep_months: 2,
+            ..HistoryConfig::default()
+        };
+        let report = compact(&config, data_dir.as_path(), Month::new(2026, 10)).unwrap();
+        assert_eq!(report.deleted, ["2026-07", "2026-08"]);
+        assert_eq!(
+            files(data_dir.as_path()),
+            ["2026-09.toml.gz", "2026-10.toml"]
+        );
+        assert!(report.after < report.before);
+
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    #[test]
+    fn deletes_the_oldest_months_to_fit() {
+        let data_dir = data_dir("size");
+        let store = EventStore::default();
+        for month in ["2026-08-01", "2026-09-01", "2026-10-01"] {
+            store.record(data_dir.as_path(), &record(month, "Slowed"));
+        }
+
+        // Too small for anything, but the current month is kept regardless.
+        let config = HistoryConfig {
+            max_size: ByteSize(1),
+            ..HistoryConfig::default()
+        };
+        let report = compact(&config, data_dir.as_path(), Month::new(2026, 10)).unwrap();
+        assert_eq!(report.deleted, ["2026-08", "2026-09"]);
+        assert_eq!(files(data_dir.as_path()), ["2026-10.toml"]);
+
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+
+    #[test]
+    fn compacting_nothing_does_nothing() {
+        let data_dir = data_dir("nothing");
+        let report = compact(
+            &HistoryConfig::default(),
+            data_dir.as_path(),
+            Month::new(2026, 10),
+        )
+        .unwrap();
+        assert!(report.is_empty());
+        assert_eq!((report.before, report.after), (0, 0));
+        assert!(!data_dir.exists());
+    }
+
+    #[test]
+    fn reads_months_from_filenames() {
+        assert_eq!(
+            Month::from_filename("2026-10.toml"),
+            Some((Month::new(2026, 10), false))
+        );
+        assert_eq!(
+            Month::from_filename("2026-01.toml.gz"),
+            Some((Month::new(2026, 1), true))
+        );
+        assert_eq!(Month::from_filename("2026-13.toml"), None);
+        assert_eq!(Month::from_filename("notes.txt"), None);
+        assert_eq!(Month::new(2026, 1).minus(1).to_string(), "2025-12");
+    }
+}
@@ -0,0 +1,50 @@
+//! Trigger Suggestions
+//!
+//! Helpers for authoring new triggers from log lines that have already been
+//! seen, so that users don't have to hand write (and hand escape) a regex for
+//! every line they want to react to.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref TOKEN_RE: Regex = Regex::new(r"[A-Za-z]+|\d+|[^A-Za-z\d]+").unwrap();
+}
+
+/// Capitalized words that are common at the start of a line, but which are
+/// not player names.
+const NOT_NAMES: &[&str] = &[
+    "You", "Your", "Yours", "The", "They", "Their", "This", "That", "There", "Its", "His", "Her",
+];
+
+/// Builds a regex that matches the given log line, with the parts of it that
+/// are likely to vary from line to line (player names and numbers) replaced
+/// by capture groups.
+pub fn suggest_pattern(line: &str) -> String {
+    let mut pattern = String::from("^");
+
+    for token in TOKEN_RE.find_iter(line).map(|m| m.as_str()) {
+        if token.chars().all(|c| c.is_ascii_digit()) {
+            pattern.push_str(r"(\d+)");
+        } else if is_name_like(token) {
+            pattern.push_str(r"(\w+)");
+        } else {
+            pattern.push_str(regex::escape(token).as_str());
+        }
+    }
+
+    pattern.push('$');
+    pattern
+}
+
+fn is_name_like(token: &str) -> bool {
+    let mut chars = token.chars();
+
+    token.len() >= 3
+        && chars
+            .next()
+            .map(|c| c.is_ascii_uppercase())
+            .unwrap_or(false)
+        && chars.all(|c| c.is_ascii_lowercase())
+        && !NOT_NAMES.contains(&token)
+}
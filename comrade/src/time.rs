@@ -0,0 +1,11 @@
+//! Clocks
+//!
+//! `std::time` panics when asked what time it is on `wasm32-unknown-unknown`,
+//! since there's no clock that it knows how to read there, so in the browser
+//! the clocks come from `web-time` instead, which has the same API.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::time::{Instant, SystemTime};
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) use web_time::{Instant, SystemTime};
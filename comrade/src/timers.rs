@@ -0,0 +1,78 @@
+//! Manual Timers
+//!
+//! Countdowns that the user starts by hand, rather than ones that are started
+//! by a trigger matching, e.g. `6m30s Pick respawn`.
+
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::errors::TimerError;
+
+type Result<T, E = TimerError> = core::result::Result<T, E>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManualTimer {
+    pub duration: Duration,
+    pub text: String,
+}
+
+/// Parses a timer as a duration followed by the text to display for it.
+impl FromStr for ManualTimer {
+    type Err = TimerError;
+
+    fn from_str(s: &str) -> Result<ManualTimer> {
+        let s = s.trim();
+        let (duration, text) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(TimerError::MissingText);
+        }
+
+        Ok(ManualTimer {
+            duration: parse_duration(duration)?,
+            text: text.to_string(),
+        })
+    }
+}
+
+/// Parses a duration like `6m30s`, `1h`, or `90` (which is taken to be in
+/// seconds).
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let invalid = || TimerError::InvalidDuration {
+        value: s.to_string(),
+    };
+
+    if s.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut total = 0;
+    let mut digits = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+
+        let unit = match c {
+            'h' => 60 * 60,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(invalid()),
+        };
+        let value: u64 = digits.parse().map_err(|_| invalid())?;
+        total += value * unit;
+        digits.clear();
+    }
+
+    if !digits.is_empty() {
+        total += digits.parse::<u64>().map_err(|_| invalid())?;
+    }
+
+    if total == 0 {
+        return Err(invalid());
+    }
+
+    Ok(Duration::from_secs(total))
+}
@@ -0,0 +1,241 @@
+//! Tradeskill Tracking
+//!
+//! Combines are tallied per recipe, so that players grinding skill-ups can
+//! see their real success rates. The game only names the item on a
+//! successful combine, so failures, trivials, and skill-ups are counted
+//! against the last recipe that the character made. Combines are grouped
+//! into sessions, which end once the character has gone long enough without
+//! combining anything, at which point the session is summarized.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use log::debug;
+use parking_lot::Mutex;
+use regex::Regex;
+
+use crate::config::CharacterId;
+use crate::time::{Instant, SystemTime};
+use crate::watcher::LogEvent;
+
+/// What failures are counted against when the character hasn't made anything
+/// yet, since the game doesn't say what was being made.
+const UNKNOWN_RECIPE: &str = "Unknown";
+
+lazy_static! {
+    // e.g. "You have fashioned the items together to create something new:
+    // Bone Chips.", older logs leave the item off.
+    static ref SUCCESS_RE: Regex = Regex::new(
+        r"^You have fashioned the items together to create (?:something new|an alternate product)(?:: (?P<item>.+?))?[.!]$"
+    )
+    .unwrap();
+    static ref FAILURE_RE: Regex =
+        Regex::new(r"^You lacked the skills to fashion the items together\.$").unwrap();
+    static ref TRIVIAL_RE: Regex =
+        Regex::new(r"^You can no longer advance your skill from making this item\.$").unwrap();
+    static ref SKILL_UP_RE: Regex = Regex::new(
+        r"^You have become better at (?:Alchemy|Baking|Blacksmithing|Brewing|Fletching|Jewelry Making|Make Poison|Pottery|Research|Tailoring|Tinkering)! \(\d+\)$"
+    )
+    .unwrap();
+}
+
+enum Combine<'a> {
+    Success(Option<&'a str>),
+    Failure,
+    Trivial,
+    SkillUp,
+}
+
+fn parse_combine(line: &str) -> Option<Combine<'_>> {
+    if let Some(caps) = SUCCESS_RE.captures(line) {
+        Some(Combine::Success(caps.name("item").map(|m| m.as_str())))
+    } else if FAILURE_RE.is_match(line) {
+        Some(Combine::Failure)
+    } else if TRIVIAL_RE.is_match(line) {
+        Some(Combine::Trivial)
+    } else if SKILL_UP_RE.is_match(line) {
+        Some(Combine::SkillUp)
+    } else {
+        None
+    }
+}
+
+/// Whether a log line is one that tradeskill tracking needs to see.
+pub(crate) fn is_tradeskill_line(line: &str) -> bool {
+    parse_combine(line).is_some()
+}
+
+#[derive(Debug, Clone)]
+pub struct RecipeStats {
+    /// The character that made the recipe.
+    pub character: CharacterId,
+    /// The name of the item that the recipe makes.
+    pub recipe: String,
+    pub attempts: u64,
+    pub successes: u64,
+    /// How many of the combines were of an item that was already trivial.
+    pub trivials: u64,
+    pub skill_ups: u64,
+    /// When the recipe was last combined, for evicting the ones that haven't
+    /// been in a while first.
+    pub(crate) last_combined: Instant,
+}
+
+impl RecipeStats {
+    fn new(character: CharacterId, recipe: String) -> RecipeStats {
+        RecipeStats {
+            character,
+            recipe,
+            attempts: 0,
+            successes: 0,
+            trivials: 0,
+            skill_ups: 0,
+            last_combined: Instant::now(),
+        }
+    }
+
+    pub fn failures(&self) -> u64 {
+        self.attempts - self.successes
+    }
+
+    /// The share of attempts that succeeded, as a percentage.
+    pub fn success_rate(&self) -> f64 {
+        if self.attempts == 0 {
+            return 0.0;
+        }
+        self.successes as f64 * 100.0 / self.attempts as f64
+    }
+
+    fn tally(&mut self, combine: &Combine<'_>) {
+        self.last_combined = Instant::now();
+        match combine {
+            Combine::Success(_) => {
+                self.attempts += 1;
+                self.successes += 1;
+            }
+            Combine::Failure => self.attempts += 1,
+            Combine::Trivial => self.trivials += 1,
+            Combine::SkillUp => self.skill_ups += 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TradeskillSession {
+    pub character: CharacterId,
+    pub started: SystemTime,
+    /// The time from the first combine to the last.
+    pub duration: Duration,
+    /// Every recipe that was made during the session, by name.
+    pub recipes: Vec<RecipeStats>,
+}
+
+impl TradeskillSession {
+    pub fn attempts(&self) -> u64 {
+        self.recipes.iter().map(|r| r.attempts).sum()
+    }
+
+    pub fn successes(&self) -> u64 {
+        self.recipes.iter().map(|r| r.successes).sum()
+    }
+
+    pub fn skill_ups(&self) -> u64 {
+        self.recipes.iter().map(|r| r.skill_ups).sum()
+    }
+}
+
+/// The stats for every recipe made since Comrade started, by character and
+/// recipe.
+pub(crate) type RecipeLog = Arc<Mutex<BTreeMap<(CharacterId, String), RecipeStats>>>;
+
+struct Session {
+    started: SystemTime,
+    first: Instant,
+    last: Instant,
+    recipes: BTreeMap<String, RecipeStats>,
+}
+
+/// Tallies every combine into the recipe log, while tracking the tradeskill
+/// session that each character is in.
+pub(crate) struct Tradeskills {
+    sessions: HashMap<CharacterId, Session>,
+    last_recipe: HashMap<CharacterId, String>,
+    totals: RecipeLog,
+}
+
+impl Tradeskills {
+    pub(crate) fn new(totals: RecipeLog) -> Tradeskills {
+        Tradeskills {
+            sessions: HashMap::new(),
+            last_recipe: HashMap::new(),
+            totals,
+        }
+    }
+
+    pub(crate) fn log_event(&mut self, event: &LogEvent) {
+        let combine = match parse_combine(event.message()) {
+            Some(combine) => combine,
+            None => return,
+        };
+
+        let id = &*event.id;
+        let recipe = match combine {
+            Combine::Success(Some(item)) => {
+                self.last_recipe.insert(id.clone(), item.to_string());
+                item
+            }
+            _ => self
+                .last_recipe
+                .get(id)
+                .map(|r| r.as_str())
+                .unwrap_or(UNKNOWN_RECIPE),
+        };
+
+        let now = Instant::now();
+        let session = self.sessions.entry(id.clone()).or_insert_with(|| Session {
+            started: SystemTime::now(),
+            first: now,
+            last: now,
+            recipes: BTreeMap::new(),
+        });
+        session.last = now;
+
+        session
+            .recipes
+            .entry(recipe.to_string())
+            .or_insert_with(|| RecipeStats::new(id.clone(), recipe.to_string()))
+            .tally(&combine);
+        self.totals
+            .lock()
+            .entry((id.clone(), recipe.to_string()))
+            .or_insert_with(|| RecipeStats::new(id.clone(), recipe.to_string()))
+            .tally(&combine);
+    }
+
+    /// Ends every session whose character has gone at least the timeout
+    /// without combining anything, returning their summaries.
+    pub(crate) fn expire(&mut self, timeout: Duration) -> Vec<TradeskillSession> {
+        let expired: Vec<CharacterId> = self
+            .sessions
+            .iter()
+            .filter(|(_, session)| session.last.elapsed() >= timeout)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        let mut summaries = Vec::new();
+        for id in expired {
+            if let Some(session) = self.sessions.remove(&id) {
+                debug!("tradeskill session ended for {}", id);
+                summaries.push(TradeskillSession {
+                    character: id,
+                    started: session.started,
+                    duration: session.last - session.first,
+                    recipes: session.recipes.into_values().collect(),
+                });
+            }
+        }
+        summaries
+    }
+}
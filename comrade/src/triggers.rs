@@ -1,12 +1,28 @@
+use std::collections::{BTreeMap, HashMap};
+use std::ptr;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
+use log::warn;
 use regex::{Captures, Regex};
 
-use crate::config::triggers::{Action as TriggerAction, Trigger};
-use crate::config::Character;
+use crate::config::timers::{TimerCategory, TimersConfig};
+use crate::config::triggers::{
+    Action as TriggerAction, CaptureCondition, CaptureType, Choice, Comparison, SpellInfo, Trigger,
+    TriggerId, TriggerRef,
+};
+use crate::config::zones::ZoneProfile;
+use crate::config::{Character, CharacterId};
 use crate::errors::TriggerError;
-use crate::events::{Event, EventKind};
+use crate::events::{AlertId, Event, EventKind};
+use crate::executors::{ExecutorRequest, Executors};
+use crate::expand::{expand, Builtins};
+use crate::fields::{LineFields, Predicate};
+use crate::inflight::{self, Pending};
+use crate::locations::LocationLog;
+use crate::outputs::{Outputs, Pattern, DEFAULT_UNIT};
+use crate::random::Rng;
+use crate::time::{Instant, SystemTime};
 use crate::watcher::LogEvent;
 
 type Result<T, E = TriggerError> = core::result::Result<T, E>;
@@ -15,85 +31,503 @@ type Result<T, E = TriggerError> = core::result::Result<T, E>;
 enum ActionKind {
     Triggered {
         character: Arc<Character>,
+        tref: Arc<TriggerRef>,
         trigger: Arc<Trigger>,
         log: Arc<LogEvent>,
     },
     DisplayText {
         text: Arc<String>,
+        trigger: Arc<Trigger>,
+        alert: Option<AlertId>,
+        character: Arc<Character>,
+        collapse: Option<Duration>,
     },
     Countdown {
         text: Arc<String>,
         duration: Duration,
         ends_at: Instant,
+        category: Option<Arc<TimerCategory>>,
+        character: Option<Arc<Character>>,
+        icon: Option<Arc<String>>,
+        spell: Option<Arc<SpellInfo>>,
+        end_early: Option<EndEarly>,
+        restart: bool,
+        // How many more times it starts over once it runs out.
+        repeat: u32,
+    },
+    RecordWaypoint {
+        id: Arc<CharacterId>,
+        name: String,
+    },
+    RecallWaypoint {
+        id: Arc<CharacterId>,
+        name: String,
+        trigger: Arc<Trigger>,
+        character: Arc<Character>,
     },
+    Signal {
+        output: String,
+        pattern: Arc<Pattern>,
+    },
+    Custom {
+        executor: String,
+        request: ExecutorRequest,
+    },
+}
+
+/// What ends a countdown early, a line from the log of the character that
+/// started it matching the regex.
+#[derive(Debug, Clone)]
+struct EndEarly {
+    id: Arc<CharacterId>,
+    regex: Arc<Regex>,
 }
 
 #[derive(Debug)]
 pub(crate) struct Action {
     kind: ActionKind,
     delay_until: Option<Instant>,
+    // Set once the action is past its delay, if it had one.
+    fired: bool,
     finished: bool,
+    // Where the action is in the in-flight journal, while it's waiting.
+    journaled: Option<u64>,
+    // Which choice of a random action this is, if it was picked from one.
+    choice: Option<usize>,
+    // When the action was due to be carried out, if it was set off by a
+    // line, until it has been.
+    due: Option<Instant>,
 }
 
 impl Action {
-    fn new(caps: &Captures, action: &TriggerAction) -> Action {
+    fn new(
+        caps: &Captures,
+        picked: &StepAction,
+        trigger: &Arc<Trigger>,
+        character: &Arc<Character>,
+        log: &LogEvent,
+        offset: Duration,
+    ) -> Action {
+        let (action, category) = (&picked.action, picked.category.clone());
+        let id = &log.id;
+        let builtins = Builtins::new(character, log);
         // TODO: We could remove an allocation and memcpy here by turning some of
         //       these String into Arc<String>, and conditionally doing the expansion
         //       based on if there are expansion variables or not.. however that is
         //       more effort and it's not clear that it's worth it.
         let (kind, delay) = match action {
-            TriggerAction::DisplayText { text, delay } => {
-                let mut expanded = String::new();
-                caps.expand(text.as_str(), &mut expanded);
-
-                (
-                    ActionKind::DisplayText {
-                        text: Arc::new(expanded),
-                    },
-                    delay,
-                )
-            }
+            TriggerAction::DisplayText {
+                text,
+                delay,
+                collapse,
+            } => (
+                ActionKind::DisplayText {
+                    text: Arc::new(expand(text.as_str(), caps, &builtins, &[])),
+                    trigger: trigger.clone(),
+                    alert: trigger.acknowledge.then(AlertId::next),
+                    character: character.clone(),
+                    collapse: *collapse,
+                },
+                delay,
+            ),
             TriggerAction::Countdown {
                 text,
                 duration,
                 delay,
+                icon,
+                spell,
+                restart,
+                repeat,
+                ..
             } => {
-                let mut expanded = String::new();
-                caps.expand(text.as_str(), &mut expanded);
+                let expanded = expand(
+                    text.as_str(),
+                    caps,
+                    &builtins,
+                    &[("remaining_s", duration.as_secs_f64())],
+                );
 
-                let start_delay = delay.unwrap_or(Duration::ZERO);
+                let start_delay = offset + delay.unwrap_or(Duration::ZERO);
+                let icon = icon
+                    .clone()
+                    .or_else(|| category.as_ref().and_then(|c| c.icon.clone()))
+                    .map(Arc::new);
 
                 (
                     ActionKind::Countdown {
                         text: Arc::new(expanded),
                         duration: *duration,
                         ends_at: Instant::now() + *duration + start_delay,
+                        category,
+                        character: Some(character.clone()),
+                        icon,
+                        spell: spell.clone().map(Arc::new),
+                        end_early: picked.end_early.clone().map(|regex| EndEarly {
+                            id: id.clone(),
+                            regex,
+                        }),
+                        restart: *restart,
+                        repeat: *repeat,
+                    },
+                    delay,
+                )
+            }
+            TriggerAction::RecordWaypoint { name } => {
+                let expanded = expand(name.as_str(), caps, &builtins, &[]);
+
+                (
+                    ActionKind::RecordWaypoint {
+                        id: id.clone(),
+                        name: expanded,
+                    },
+                    &None,
+                )
+            }
+            TriggerAction::RecallWaypoint { name } => {
+                let expanded = expand(name.as_str(), caps, &builtins, &[]);
+
+                (
+                    ActionKind::RecallWaypoint {
+                        id: id.clone(),
+                        name: expanded,
+                        trigger: trigger.clone(),
+                        character: character.clone(),
+                    },
+                    &None,
+                )
+            }
+            TriggerAction::Signal {
+                output,
+                morse,
+                pulses,
+                unit,
+                delay,
+            } => {
+                let pattern = match morse {
+                    Some(text) => Pattern::morse(
+                        expand(text.as_str(), caps, &builtins, &[]).as_str(),
+                        unit.unwrap_or(DEFAULT_UNIT),
+                    ),
+                    None => {
+                        Pattern::new(pulses.iter().map(|p| Duration::from_millis(*p)).collect())
+                    }
+                };
+
+                (
+                    ActionKind::Signal {
+                        output: output.clone(),
+                        pattern: Arc::new(pattern),
                     },
                     delay,
                 )
             }
+            TriggerAction::Custom {
+                executor,
+                options,
+                delay,
+            } => (
+                ActionKind::Custom {
+                    executor: executor.clone(),
+                    request: ExecutorRequest {
+                        id: id.clone(),
+                        character: character.clone(),
+                        trigger: trigger.clone(),
+                        options: options
+                            .iter()
+                            .map(|(k, v)| (k.clone(), expand(v.as_str(), caps, &builtins, &[])))
+                            .collect(),
+                    },
+                },
+                delay,
+            ),
+            TriggerAction::FireTrigger { .. } => {
+                unreachable!("fired triggers are replaced by their actions when compiled")
+            }
+            TriggerAction::Random { .. } | TriggerAction::If { .. } => {
+                unreachable!("random actions and branches are worked out when executed")
+            }
+        };
+
+        // Actions from a fired trigger wait on the delay it was fired with
+        // before their own.
+        let delay = match delay {
+            Some(delay) => Some(offset + *delay),
+            None => (!offset.is_zero()).then_some(offset),
         };
 
         Action {
             kind,
             delay_until: delay.map(|d| Instant::now() + d),
+            fired: false,
+            finished: false,
+            journaled: None,
+            choice: None,
+            due: Some(log.read() + delay.unwrap_or_default()),
+        }
+    }
+
+    /// A countdown that isn't tied to any trigger, that Comrade itself starts
+    /// for the given character.
+    pub(crate) fn countdown(
+        text: Arc<String>,
+        duration: Duration,
+        character: Arc<Character>,
+    ) -> Action {
+        Action {
+            kind: ActionKind::Countdown {
+                text,
+                duration,
+                ends_at: Instant::now() + duration,
+                category: None,
+                character: Some(character),
+                icon: None,
+                spell: None,
+                end_early: None,
+                restart: false,
+                repeat: 0,
+            },
+            delay_until: None,
+            fired: false,
+            finished: false,
+            journaled: None,
+            choice: None,
+            due: None,
+        }
+    }
+
+    /// A countdown that was started by hand, which isn't for any character,
+    /// and which starts over one with the same text rather than going
+    /// alongside it.
+    pub(crate) fn timer(text: Arc<String>, duration: Duration, ends_at: Instant) -> Action {
+        Action {
+            kind: ActionKind::Countdown {
+                text,
+                duration,
+                ends_at,
+                category: None,
+                character: None,
+                icon: None,
+                spell: None,
+                end_early: None,
+                restart: true,
+                repeat: 0,
+            },
+            delay_until: None,
+            fired: false,
+            finished: false,
+            journaled: None,
+            choice: None,
+            due: None,
+        }
+    }
+
+    /// Text that Comrade itself displays, as if it were from the given
+    /// trigger.
+    pub(crate) fn display(
+        text: Arc<String>,
+        trigger: Arc<Trigger>,
+        character: Arc<Character>,
+    ) -> Action {
+        Action {
+            kind: ActionKind::DisplayText {
+                text,
+                alert: trigger.acknowledge.then(AlertId::next),
+                trigger,
+                character,
+                collapse: None,
+            },
+            delay_until: None,
+            fired: false,
             finished: false,
+            journaled: None,
+            choice: None,
+            due: None,
         }
     }
 
-    fn triggered(character: Arc<Character>, trigger: Arc<Trigger>, log: Arc<LogEvent>) -> Action {
+    fn triggered(
+        character: Arc<Character>,
+        tref: Arc<TriggerRef>,
+        trigger: Arc<Trigger>,
+        log: Arc<LogEvent>,
+    ) -> Action {
         Action {
             kind: ActionKind::Triggered {
                 character,
+                tref,
                 trigger,
                 log,
             },
             delay_until: None,
+            fired: false,
+            finished: false,
+            journaled: None,
+            choice: None,
+            due: None,
+        }
+    }
+
+    /// Puts back together an action that was waiting on its delay when
+    /// Comrade stopped, from what was journaled about it, and the character
+    /// it was for. Fails if it was for a character that's no longer known.
+    pub(crate) fn recovered(
+        pending: Pending,
+        until: Instant,
+        character: Option<(&CharacterId, Arc<Character>)>,
+        timers: &TimersConfig,
+    ) -> Option<Action> {
+        let kind = match pending {
+            Pending::Timer { text, duration } => {
+                let duration = Duration::from_millis(duration);
+                return Some(Action::timer(Arc::new(text), duration, until));
+            }
+            Pending::DisplayText { text, trigger } => ActionKind::DisplayText {
+                text: Arc::new(text),
+                alert: trigger.acknowledge.then(AlertId::next),
+                trigger: Arc::new(*trigger),
+                character: character?.1,
+                collapse: None,
+            },
+            Pending::Countdown {
+                text,
+                duration,
+                ends_at,
+                category,
+                icon,
+                spell,
+                end_early_text,
+                restart,
+                repeat,
+            } => {
+                let (id, character) = character?;
+                ActionKind::Countdown {
+                    text: Arc::new(text),
+                    duration: Duration::from_millis(duration),
+                    ends_at: inflight::instant_at_millis(ends_at),
+                    category: category.map(|name| timers.category(name.as_str())),
+                    character: Some(character),
+                    icon: icon.map(Arc::new),
+                    spell: spell.map(Arc::new),
+                    end_early: end_early_text.and_then(|text| match Regex::new(text.as_str()) {
+                        Ok(regex) => Some(EndEarly {
+                            id: Arc::new(id.clone()),
+                            regex: Arc::new(regex),
+                        }),
+                        Err(e) => {
+                            warn!("countdown {:?} can no longer end early: {}", text, e);
+                            None
+                        }
+                    }),
+                    restart,
+                    repeat,
+                }
+            }
+        };
+
+        Some(Action {
+            kind,
+            delay_until: Some(until),
+            fired: false,
             finished: false,
+            journaled: None,
+            choice: None,
+            due: None,
+        })
+    }
+
+    /// When this action's delay is up and what it'll do then, if it's still
+    /// waiting and it's the kind of action that can be journaled. Timers that
+    /// were started by hand are waiting until they run out instead.
+    pub(crate) fn pending(&self) -> Option<(SystemTime, Pending)> {
+        if let ActionKind::Countdown {
+            text,
+            duration,
+            ends_at,
+            character: None,
+            ..
+        } = &self.kind
+        {
+            let pending = Pending::Timer {
+                text: text.to_string(),
+                duration: duration.as_millis() as u64,
+            };
+            return (!self.finished).then(|| (inflight::wall_time(*ends_at), pending));
+        }
+
+        let delay_until = self.delay_until.filter(|_| !self.fired && !self.finished)?;
+        let pending = match &self.kind {
+            ActionKind::DisplayText { text, trigger, .. } => Pending::DisplayText {
+                text: text.to_string(),
+                trigger: Box::new(Trigger::clone(trigger)),
+            },
+            ActionKind::Countdown {
+                text,
+                duration,
+                ends_at,
+                category,
+                icon,
+                spell,
+                end_early,
+                restart,
+                repeat,
+                ..
+            } => Pending::Countdown {
+                text: text.to_string(),
+                duration: duration.as_millis() as u64,
+                ends_at: inflight::millis(inflight::wall_time(*ends_at)),
+                category: category.as_ref().map(|c| c.name.clone()),
+                icon: icon.as_deref().cloned(),
+                spell: spell.as_deref().cloned(),
+                end_early_text: end_early.as_ref().map(|e| e.regex.as_str().to_string()),
+                restart: *restart,
+                repeat: *repeat,
+            },
+            _ => return None,
+        };
+
+        Some((inflight::wall_time(delay_until), pending))
+    }
+
+    pub(crate) fn set_journaled(&mut self, seq: u64) {
+        self.journaled = Some(seq);
+    }
+
+    /// Whether this action is waiting in the in-flight journal, on its delay
+    /// or, for a timer that was started by hand, to run out.
+    pub(crate) fn is_journaled(&self) -> bool {
+        self.journaled.is_some()
+    }
+
+    /// Where this action was in the in-flight journal, once it's done
+    /// waiting on its delay, either because it fired or because it finished
+    /// without ever firing. A timer that was started by hand is done once
+    /// it's finished.
+    pub(crate) fn settled(&mut self) -> Option<u64> {
+        let timer = matches!(
+            self.kind,
+            ActionKind::Countdown {
+                character: None,
+                ..
+            }
+        );
+        if self.finished || (self.fired && !timer) {
+            self.journaled.take()
+        } else {
+            None
         }
     }
 
-    pub(crate) fn events(&mut self) -> Option<Vec<Event>> {
+    /// The events for this action that are due, if any. Actions that deal
+    /// with waypoints look them up in, or record them to, the given
+    /// locations, signals are played on the given outputs, and custom actions
+    /// are handed to the given executors.
+    pub(crate) fn events(
+        &mut self,
+        locations: &LocationLog,
+        outputs: &Outputs,
+        executors: &Executors,
+        timers: &TimersConfig,
+    ) -> Option<Vec<Event>> {
         if let Some(delay_until) = self.delay_until {
             if Instant::now() >= delay_until {
                 // Once we've reached our delay_until, then we'll set it to None so
@@ -103,43 +537,132 @@ impl Action {
                 return None;
             }
         }
+        self.fired = true;
 
-        match &self.kind {
+        // Only the first time an action is carried out is measured, repeats
+        // and later reports of a countdown were never late to begin with.
+        let due = self.due.take();
+        let events = self.due_events(locations, outputs, executors, timers)?;
+        Some(
+            events
+                .into_iter()
+                .map(|event| event.with_choice(self.choice).with_due(due))
+                .collect(),
+        )
+    }
+
+    fn due_events(
+        &mut self,
+        locations: &LocationLog,
+        outputs: &Outputs,
+        executors: &Executors,
+        timers: &TimersConfig,
+    ) -> Option<Vec<Event>> {
+        match &mut self.kind {
             ActionKind::Triggered {
                 character,
+                tref,
                 trigger,
                 log,
             } => {
                 self.finished = true;
                 Some(vec![Event::new(EventKind::Triggered {
                     character: character.clone(),
+                    tref: tref.clone(),
                     trigger: trigger.clone(),
                     log: log.clone(),
                 })])
             }
-            ActionKind::DisplayText { text } => {
-                self.finished = true;
-                Some(vec![Event::new(EventKind::DisplayText(text.clone()))])
+            ActionKind::DisplayText {
+                text,
+                trigger,
+                alert,
+                character,
+                ..
+            } => {
+                // Text that has to be acknowledged is displayed again every
+                // so often, until it is.
+                match trigger.repeat_interval() {
+                    Some(interval) => self.delay_until = Some(Instant::now() + interval),
+                    None => self.finished = true,
+                }
+                Some(vec![Event::new(EventKind::DisplayText {
+                    text: text.clone(),
+                    trigger: Some(trigger.clone()),
+                    alert: *alert,
+                    character: Some(character.clone()),
+                })])
             }
             ActionKind::Countdown {
                 text,
                 duration,
                 ends_at,
+                category,
+                character,
+                icon,
+                spell,
+                repeat,
+                ..
             } => {
-                if Instant::now() >= *ends_at {
-                    self.finished = true;
-                    Some(vec![Event::new(EventKind::Countdown {
-                        text: text.clone(),
-                        duration: *duration,
-                        remaining: Duration::ZERO,
-                    })])
-                } else {
-                    Some(vec![Event::new(EventKind::Countdown {
-                        text: text.clone(),
+                let countdown = |remaining| {
+                    Event::new(EventKind::Countdown {
+                        text: timers.expand_remaining(text, remaining),
+                        template: text.clone(),
                         duration: *duration,
-                        remaining: ends_at.duration_since(Instant::now()),
-                    })])
+                        remaining,
+                        category: category.clone(),
+                        character: character.clone(),
+                        icon: icon.clone(),
+                        spell: spell.clone(),
+                    })
+                };
+
+                let remaining = ends_at.saturating_duration_since(Instant::now());
+                if !remaining.is_zero() {
+                    return Some(vec![countdown(remaining)]);
                 }
+                if *repeat == 0 {
+                    self.finished = true;
+                    return Some(vec![countdown(remaining)]);
+                }
+
+                // A repeat still runs out like any other countdown, so that
+                // it's heard, and then starts over from when it ran out,
+                // rather than from now, so that it doesn't drift.
+                *repeat -= 1;
+                *ends_at += *duration;
+                let restarted = ends_at.saturating_duration_since(Instant::now());
+                Some(vec![countdown(remaining), countdown(restarted)])
+            }
+            ActionKind::RecordWaypoint { id, name } => {
+                self.finished = true;
+                locations.lock().record(id, name.as_str());
+                None
+            }
+            ActionKind::RecallWaypoint {
+                id,
+                name,
+                trigger,
+                character,
+            } => {
+                self.finished = true;
+                let waypoint = locations.lock().waypoint(id, name.as_str())?;
+                Some(vec![Event::new(EventKind::DisplayText {
+                    text: Arc::new(format!("{}: {}", waypoint.name, waypoint.position)),
+                    trigger: Some(trigger.clone()),
+                    alert: None,
+                    character: Some(character.clone()),
+                })])
+            }
+            ActionKind::Signal { output, pattern } => {
+                self.finished = true;
+                outputs.signal(output.as_str(), pattern.clone());
+                None
+            }
+            ActionKind::Custom { executor, request } => {
+                self.finished = true;
+                executors.execute(executor.as_str(), request);
+                None
             }
         }
     }
@@ -147,37 +670,1006 @@ impl Action {
     pub(crate) fn finished(&self) -> bool {
         self.finished
     }
+
+    /// The text that this action displays or counts down with, if it's
+    /// known ahead of time.
+    pub(crate) fn text(&self) -> Option<&str> {
+        match &self.kind {
+            ActionKind::DisplayText { text, .. } | ActionKind::Countdown { text, .. } => {
+                Some(text.as_str())
+            }
+            _ => None,
+        }
+    }
+
+    /// Counts this action, from the given character's log, against the text
+    /// that it displays, if it collapses repeats of it. When this is a
+    /// repeat, the event reporting it is returned, and the action should be
+    /// dropped rather than carried out.
+    pub(crate) fn repeat(&self, id: &CharacterId, repeats: &mut Repeats) -> Option<Event> {
+        let (text, trigger, character, window) = match &self.kind {
+            ActionKind::DisplayText {
+                text,
+                trigger,
+                character,
+                collapse: Some(window),
+                ..
+            } => (text, trigger, character, *window),
+            _ => return None,
+        };
+
+        let now = Instant::now();
+        let key = (id.clone(), trigger.name.clone(), text.clone());
+        match repeats.seen.get_mut(&key) {
+            Some(seen) if now < seen.last + seen.window => {
+                seen.last = now;
+                seen.count += 1;
+                let event = Event::new(EventKind::DisplayTextRepeated {
+                    text: text.clone(),
+                    trigger: trigger.clone(),
+                    character: character.clone(),
+                    count: seen.count,
+                });
+                Some(event.with_choice(self.choice))
+            }
+            _ => {
+                let seen = Seen {
+                    last: now,
+                    window,
+                    count: 1,
+                };
+                repeats.seen.insert(key, seen);
+                None
+            }
+        }
+    }
+
+    /// Ends this action if it's a countdown that the given line ends early,
+    /// returning the event reporting it, if it had started counting down.
+    pub(crate) fn end_early(&mut self, log: &LogEvent) -> Option<Event> {
+        let (text, category, character, end_early) = match &self.kind {
+            ActionKind::Countdown {
+                text,
+                category,
+                character,
+                end_early: Some(end_early),
+                ..
+            } if !self.finished => (text, category, character, end_early),
+            _ => return None,
+        };
+        if end_early.id != log.id || !end_early.regex.is_match(log.message()) {
+            return None;
+        }
+
+        self.finished = true;
+        // A countdown that's still waiting on its delay was never shown, so
+        // there's nothing to take down.
+        if !self.fired {
+            return None;
+        }
+        Some(Event::new(EventKind::TimerEnded {
+            template: text.clone(),
+            category: category.clone(),
+            character: character.clone(),
+        }))
+    }
+
+    /// Ends this action if it's a countdown that the given one starts over,
+    /// being a restarting countdown with the same text for the same
+    /// character. Returns the event reporting it if it had started counting
+    /// down and the new one hasn't yet, since otherwise the new one's report
+    /// has already taken its place.
+    pub(crate) fn restarted_by(&mut self, other: &Action) -> Option<Event> {
+        let (text, category, character) = match &self.kind {
+            ActionKind::Countdown {
+                text,
+                category,
+                character,
+                ..
+            } if !self.finished => (text, category, character),
+            _ => return None,
+        };
+        let same = |c: &Option<Arc<Character>>| {
+            c.as_ref().map(|c| (&c.name, &c.server))
+                == character.as_ref().map(|c| (&c.name, &c.server))
+        };
+        match &other.kind {
+            ActionKind::Countdown {
+                text: other_text,
+                character: other_character,
+                restart: true,
+                ..
+            } if other_text == text && same(other_character) => {}
+            _ => return None,
+        }
+
+        self.finished = true;
+        if !self.fired || other.fired {
+            return None;
+        }
+        Some(Event::new(EventKind::TimerEnded {
+            template: text.clone(),
+            category: category.clone(),
+            character: character.clone(),
+        }))
+    }
+
+    /// Stops repeating this action if it's the given alert, or if no alert
+    /// is given and it's waiting on any acknowledgement at all. Returns the
+    /// alert that was acknowledged.
+    pub(crate) fn acknowledge(&mut self, target: Option<AlertId>) -> Option<AlertId> {
+        match &self.kind {
+            ActionKind::DisplayText {
+                alert: Some(alert), ..
+            } if !self.finished && (target.is_none() || target == Some(*alert)) => {
+                self.finished = true;
+                Some(*alert)
+            }
+            _ => None,
+        }
+    }
+}
+
+struct Seen {
+    last: Instant,
+    window: Duration,
+    count: u32,
+}
+
+/// The text displayed by actions that collapse repeats of it, by character
+/// and trigger, so that a repeat can be counted against the original.
+#[derive(Default)]
+pub(crate) struct Repeats {
+    seen: HashMap<(CharacterId, String, Arc<String>), Seen>,
+}
+
+impl Repeats {
+    /// Forgets any text that's gone long enough without a repeat that the
+    /// next one will be displayed again.
+    pub(crate) fn expire(&mut self) {
+        let now = Instant::now();
+        self.seen.retain(|_, seen| now < seen.last + seen.window);
+    }
+}
+
+/// Every action that matching the given trigger carries out, along with the
+/// trigger it's from and how long after the match it starts, with the actions
+/// of any triggers that it fires standing in for firing them. Fails if a fired
+/// trigger isn't in the given pack, or if they fire each other in a circle.
+pub(crate) fn chained_actions<'a>(
+    trigger: &'a Trigger,
+    pack: &'a BTreeMap<TriggerId, Trigger>,
+) -> Result<Vec<(&'a Trigger, &'a TriggerAction, Duration)>> {
+    fn chain<'a>(
+        trigger: &'a Trigger,
+        pack: &'a BTreeMap<TriggerId, Trigger>,
+        offset: Duration,
+        firing: &mut Vec<&'a Trigger>,
+        actions: &mut Vec<(&'a Trigger, &'a TriggerAction, Duration)>,
+    ) -> Result<()> {
+        firing.push(trigger);
+        for action in trigger.actions.iter() {
+            match action {
+                TriggerAction::FireTrigger { id, delay } => {
+                    let fired = pack
+                        .get(id)
+                        .ok_or_else(|| TriggerError::UnknownChainedTrigger { id: id.clone() })?;
+                    if firing.iter().any(|t| ptr::eq(*t, fired)) {
+                        return Err(TriggerError::ChainCycle { id: id.clone() });
+                    }
+
+                    let offset = offset + delay.unwrap_or(Duration::ZERO);
+                    chain(fired, pack, offset, firing, actions)?;
+                }
+                _ => actions.push((trigger, action, offset)),
+            }
+        }
+        firing.pop();
+
+        Ok(())
+    }
+
+    let mut actions = Vec::new();
+    chain(trigger, pack, Duration::ZERO, &mut Vec::new(), &mut actions)?;
+
+    Ok(actions)
+}
+
+/// Makes sure that a signal has a pattern to play, and only the one.
+fn check_signal(output: &str, morse: Option<&str>, pulses: &[u64]) -> Result<()> {
+    let reason = match (morse, pulses.is_empty()) {
+        (Some(_), false) => "must have either morse or pulses, not both",
+        (None, true) => "must have either morse or pulses",
+        (Some(text), true) if Pattern::morse(text, DEFAULT_UNIT).is_empty() => {
+            "morse has nothing that can be sent in morse code"
+        }
+        _ => return Ok(()),
+    };
+
+    Err(TriggerError::InvalidSignal {
+        output: output.to_string(),
+        reason: reason.to_string(),
+    })
+}
+
+/// Makes sure that a random action has something to pick, and that what it
+/// picks from can be carried out on its own.
+fn check_choices(choices: &[Choice]) -> Result<()> {
+    let reason = if choices.iter().all(|c| c.weight == 0) {
+        "must have at least one choice with some weight"
+    } else if choices.iter().any(|c| {
+        matches!(
+            c.action,
+            TriggerAction::FireTrigger { .. }
+                | TriggerAction::Random { .. }
+                | TriggerAction::If { .. }
+        )
+    }) {
+        "choices cannot fire triggers, or be random actions or branches themselves"
+    } else {
+        return Ok(());
+    };
+
+    Err(TriggerError::InvalidChoice {
+        reason: reason.to_string(),
+    })
+}
+
+#[derive(Debug, Clone)]
+struct Step {
+    trigger: Arc<Trigger>,
+    plan: Plan,
+    // How long after the match the action starts, for actions of triggers
+    // that were fired with a delay.
+    offset: Duration,
+}
+
+/// What a step does, which is worked out again every time its trigger
+/// matches for random actions and branches.
+#[derive(Debug, Clone)]
+enum Plan {
+    Always(StepAction),
+    // One of these, picked by their weights.
+    Random {
+        actions: Vec<StepAction>,
+        weights: Vec<u32>,
+    },
+    // One list of plans or the other, depending on the condition.
+    Branch {
+        when: CaptureCondition,
+        then: Vec<Plan>,
+        otherwise: Vec<Plan>,
+    },
+}
+
+impl Plan {
+    fn new(action: &TriggerAction, timers: &TimersConfig) -> Result<Plan> {
+        match action {
+            TriggerAction::Random { choices } => {
+                check_choices(choices)?;
+                Ok(Plan::Random {
+                    actions: choices
+                        .iter()
+                        .map(|c| StepAction::new(&c.action, timers))
+                        .collect::<Result<_>>()?,
+                    weights: choices.iter().map(|c| c.weight).collect(),
+                })
+            }
+            TriggerAction::If {
+                when,
+                then,
+                otherwise,
+            } => {
+                let branch = |actions: &[TriggerAction]| {
+                    actions
+                        .iter()
+                        .map(|action| match action {
+                            // Which triggers are fired is worked out up
+                            // front, so it can't depend on what was captured.
+                            TriggerAction::FireTrigger { .. } => Err(TriggerError::InvalidBranch {
+                                reason: "branches cannot fire triggers".to_string(),
+                            }),
+                            action => Plan::new(action, timers),
+                        })
+                        .collect::<Result<Vec<_>>>()
+                };
+                Ok(Plan::Branch {
+                    when: when.clone(),
+                    then: branch(then)?,
+                    otherwise: branch(otherwise)?,
+                })
+            }
+            action => Ok(Plan::Always(StepAction::new(action, timers)?)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct StepAction {
+    action: TriggerAction,
+    // The timer category of the action, resolved up front so that every
+    // countdown started by it shares the same one.
+    category: Option<Arc<TimerCategory>>,
+    // What ends a countdown early, compiled up front for the same reason.
+    end_early: Option<Arc<Regex>>,
+}
+
+impl StepAction {
+    fn new(action: &TriggerAction, timers: &TimersConfig) -> Result<StepAction> {
+        if let TriggerAction::Signal {
+            output,
+            morse,
+            pulses,
+            ..
+        } = action
+        {
+            check_signal(output, morse.as_deref(), pulses)?;
+        }
+        let category = match action {
+            TriggerAction::Countdown {
+                category: Some(name),
+                ..
+            } => Some(timers.category(name.as_str())),
+            _ => None,
+        };
+        let end_early = match action {
+            TriggerAction::Countdown {
+                end_early_text: Some(text),
+                ..
+            } => Some(Arc::new(Regex::new(text.as_str())?)),
+            _ => None,
+        };
+
+        Ok(StepAction {
+            action: action.clone(),
+            category,
+            end_early,
+        })
+    }
+}
+
+/// Whether a branch's condition holds for what was captured.
+fn holds(when: &CaptureCondition, caps: &Captures, builtins: &Builtins) -> bool {
+    let side = |text: &str| expand(text, caps, builtins, &[]);
+    let (left, right) = (side(when.left.as_str()), side(when.right.as_str()));
+
+    // Sides that are both numbers are compared as numbers, so that `10 > 9`.
+    let numbers = match (left.trim().parse::<f64>(), right.trim().parse::<f64>()) {
+        (Ok(left), Ok(right)) => Some((left, right)),
+        _ => None,
+    };
+    match (when.comparison, numbers) {
+        (Comparison::Equal, Some((l, r))) => l == r,
+        (Comparison::NotEqual, Some((l, r))) => l != r,
+        (Comparison::Greater, Some((l, r))) => l > r,
+        (Comparison::GreaterOrEqual, Some((l, r))) => l >= r,
+        (Comparison::Less, Some((l, r))) => l < r,
+        (Comparison::LessOrEqual, Some((l, r))) => l <= r,
+        (Comparison::Equal, None) => left.eq_ignore_ascii_case(right.as_str()),
+        (Comparison::NotEqual, None) => !left.eq_ignore_ascii_case(right.as_str()),
+        (Comparison::Contains, _) => left.to_lowercase().contains(right.to_lowercase().as_str()),
+        _ => false,
+    }
 }
 
 #[derive(Debug, Clone)]
 pub(crate) struct CompiledTrigger {
     character: Arc<Character>,
+    tref: Arc<TriggerRef>,
     trigger: Arc<Trigger>,
     regex: Regex,
+    predicates: Vec<Predicate>,
+    captures: Vec<(String, CaptureType)>,
+    // Whether the character has this trigger turned on, outside of any zone
+    // that has a profile saying otherwise.
+    enabled: bool,
+    steps: Vec<Step>,
 }
 
 impl CompiledTrigger {
-    pub(crate) fn new(character: &Character, trigger: &Trigger) -> Result<CompiledTrigger> {
+    /// Compiles the given trigger for the given character, where `pack` is
+    /// every trigger from the same source, which any triggers it fires are
+    /// looked up in.
+    pub(crate) fn new(
+        character: &Character,
+        tref: &TriggerRef,
+        trigger: &Trigger,
+        pack: &BTreeMap<TriggerId, Trigger>,
+        timers: &TimersConfig,
+        enabled: bool,
+    ) -> Result<CompiledTrigger> {
+        let regex = Regex::new(trigger.search_text.as_str())?;
+        CompiledTrigger::with_regex(character, tref, trigger, pack, timers, enabled, regex)
+    }
+
+    /// Like `new`, but with the trigger's pattern already compiled, since
+    /// it's the same for every character that has the trigger.
+    pub(crate) fn with_regex(
+        character: &Character,
+        tref: &TriggerRef,
+        trigger: &Trigger,
+        pack: &BTreeMap<TriggerId, Trigger>,
+        timers: &TimersConfig,
+        enabled: bool,
+        regex: Regex,
+    ) -> Result<CompiledTrigger> {
+        let compiled = Arc::new(trigger.clone());
+        let mut fired: Vec<(&Trigger, Arc<Trigger>)> = Vec::new();
+        let mut steps = Vec::new();
+        for (from, action, offset) in chained_actions(trigger, pack)? {
+            // Actions are displayed with the style of whichever trigger they
+            // came from, so that has to come along with them.
+            let from = if ptr::eq(from, trigger) {
+                compiled.clone()
+            } else {
+                match fired.iter().find(|(t, _)| ptr::eq(*t, from)) {
+                    Some((_, arc)) => arc.clone(),
+                    None => {
+                        let arc = Arc::new(from.clone());
+                        fired.push((from, arc.clone()));
+                        arc
+                    }
+                }
+            };
+            steps.push(Step {
+                trigger: from,
+                plan: Plan::new(action, timers)?,
+                offset,
+            });
+        }
+
+        for name in trigger.captures.keys() {
+            let exists = match name.parse::<usize>() {
+                Ok(idx) => idx < regex.captures_len(),
+                Err(_) => regex.capture_names().flatten().any(|n| n == name),
+            };
+            if !exists {
+                return Err(TriggerError::UnknownCapture { name: name.clone() });
+            }
+        }
+
         Ok(CompiledTrigger {
             character: Arc::new(character.clone()),
-            trigger: Arc::new(trigger.clone()),
-            regex: Regex::new(trigger.search_text.as_str())?,
+            tref: Arc::new(tref.clone()),
+            trigger: compiled,
+            regex,
+            predicates: trigger.conditions.iter().map(Predicate::new).collect(),
+            captures: trigger
+                .captures
+                .iter()
+                .map(|(name, capture)| (name.clone(), *capture))
+                .collect(),
+            enabled,
+            steps,
         })
     }
 
-    pub(crate) fn execute(&self, event: &Arc<LogEvent>) -> Option<Vec<Action>> {
-        self.regex.captures(event.message()).map(|caps| {
-            let mut actions: Vec<Action> = self
-                .trigger
-                .actions
+    pub(crate) fn tref(&self) -> &TriggerRef {
+        &self.tref
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        self.trigger.name.as_str()
+    }
+
+    /// Whether this trigger is on for its character, given the profile of
+    /// the zone that they're in.
+    pub(crate) fn is_active(&self, profile: Option<&ZoneProfile>) -> bool {
+        profile
+            .and_then(|p| p.is_enabled(&self.trigger))
+            .unwrap_or(self.enabled)
+    }
+
+    /// Carries out this trigger against the given line, if it matches, with
+    /// any random actions picking their choice with the given generator.
+    pub(crate) fn execute(
+        &self,
+        event: &Arc<LogEvent>,
+        fields: &LineFields<'_>,
+        rng: &mut Rng,
+    ) -> Option<Vec<Action>> {
+        let caps = self.regex.captures(event.message())?;
+        for (name, capture) in self.captures.iter() {
+            let value = match name.parse::<usize>() {
+                Ok(idx) => caps.get(idx),
+                Err(_) => caps.name(name.as_str()),
+            };
+            // A group that didn't take part in the match has nothing to
+            // check, only what was captured has to be the right type.
+            if let Some(value) = value.filter(|v| !capture.check(v.as_str())) {
+                warn!(
+                    "skipping trigger {:?}, capture {} is {:?}, which is not a(n) {}",
+                    self.trigger.name,
+                    name,
+                    value.as_str(),
+                    capture
+                );
+                return None;
+            }
+        }
+        if !self.predicates.iter().all(|p| p.check(fields)) {
+            return None;
+        }
+        if !self.trigger.is_scheduled(event.timestamp()) {
+            return None;
+        }
+
+        let mut actions = Vec::new();
+        for step in self.steps.iter() {
+            self.carry_out(&step.plan, step, &caps, event, rng, &mut actions);
+        }
+        actions.insert(
+            0,
+            Action::triggered(
+                self.character.clone(),
+                self.tref.clone(),
+                self.trigger.clone(),
+                event.clone(),
+            ),
+        );
+        Some(actions)
+    }
+
+    /// Adds whatever the given plan comes to this time to the actions.
+    fn carry_out(
+        &self,
+        plan: &Plan,
+        step: &Step,
+        caps: &Captures,
+        event: &Arc<LogEvent>,
+        rng: &mut Rng,
+        actions: &mut Vec<Action>,
+    ) {
+        let action = |picked: &StepAction| {
+            Action::new(
+                caps,
+                picked,
+                &step.trigger,
+                &self.character,
+                event,
+                step.offset,
+            )
+        };
+
+        match plan {
+            Plan::Always(picked) => actions.push(action(picked)),
+            Plan::Random {
+                actions: choices,
+                weights,
+            } => {
+                if let Some(choice) = rng.pick(weights) {
+                    let mut picked = action(&choices[choice]);
+                    picked.choice = Some(choice);
+                    actions.push(picked);
+                }
+            }
+            Plan::Branch {
+                when,
+                then,
+                otherwise,
+            } => {
+                let builtins = Builtins::new(&self.character, event);
+                let branch = if holds(when, caps, &builtins) {
+                    then
+                } else {
+                    otherwise
+                };
+                for plan in branch.iter() {
+                    self.carry_out(plan, step, caps, event, rng, actions);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack(toml: &str) -> BTreeMap<TriggerId, Trigger> {
+        toml_edit::de::from_str(toml).unwrap()
+    }
+
+    fn character() -> Character {
+        Character {
+            name: "Soandso".to_string(),
+            server: "teek".to_string(),
+            filename: Default::default(),
+            aliases: Vec::new(),
+            display_name: None,
+            clock_offset: Default::default(),
+            language: None,
+            voice: None,
+            disabled_triggers: HashMap::new(),
+            enabled_triggers: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn chains_fired_triggers() {
+        let pack = pack(
+            r#"
+            [emote]
+            name = "Emote"
+            search_text = "^The dragon roars"
+            actions = [
+                { type = "DisplayText", text = "Spread out" },
+                { type = "FireTrigger", id = "move", delay = 10 },
+            ]
+
+            [move]
+            name = "Move"
+            search_text = "^never$"
+            actions = [
+                { type = "DisplayText", text = "Move now" },
+                { type = "FireTrigger", id = "clear", delay = 20 },
+            ]
+
+            [clear]
+            name = "Clear"
+            search_text = "^never$"
+            actions = [{ type = "DisplayText", text = "All clear", delay = 5 }]
+            "#,
+        );
+
+        let actions = chained_actions(&pack[&TriggerId::new("emote")], &pack).unwrap();
+        let steps: Vec<(&str, u64)> = actions
+            .iter()
+            .map(|(trigger, _, offset)| (trigger.name.as_str(), offset.as_secs()))
+            .collect();
+        assert_eq!(steps, [("Emote", 0), ("Move", 10), ("Clear", 30)]);
+    }
+
+    #[test]
+    fn rejects_cycles_and_unknown_triggers() {
+        let pack = pack(
+            r#"
+            [ping]
+            name = "Ping"
+            search_text = "^ping$"
+            actions = [{ type = "FireTrigger", id = "pong" }]
+
+            [pong]
+            name = "Pong"
+            search_text = "^pong$"
+            actions = [{ type = "FireTrigger", id = "ping" }]
+
+            [lost]
+            name = "Lost"
+            search_text = "^lost$"
+            actions = [{ type = "FireTrigger", id = "nowhere" }]
+            "#,
+        );
+
+        assert!(matches!(
+            chained_actions(&pack[&TriggerId::new("ping")], &pack),
+            Err(TriggerError::ChainCycle { id }) if id.as_str() == "ping"
+        ));
+        assert!(matches!(
+            chained_actions(&pack[&TriggerId::new("lost")], &pack),
+            Err(TriggerError::UnknownChainedTrigger { id }) if id.as_str() == "nowhere"
+        ));
+    }
+
+    #[test]
+    fn rejects_choices_that_cannot_be_picked() {
+        let pack = pack(
+            r#"
+            [taunt]
+            name = "Taunt"
+            search_text = "^taunt$"
+            actions = [{ type = "Random", choices = [
+                { action = { type = "DisplayText", text = "Come at me" } },
+                { weight = 3, action = { type = "DisplayText", text = "Is that all?" } },
+            ] }]
+
+            [weightless]
+            name = "Weightless"
+            search_text = "^weightless$"
+            actions = [{ type = "Random", choices = [
+                { weight = 0, action = { type = "DisplayText", text = "Never" } },
+            ] }]
+
+            [nested]
+            name = "Nested"
+            search_text = "^nested$"
+            actions = [{ type = "Random", choices = [
+                { action = { type = "FireTrigger", id = "taunt" } },
+            ] }]
+            "#,
+        );
+
+        let choices = |id: &str| match &pack[&TriggerId::new(id)].actions[0] {
+            TriggerAction::Random { choices } => choices.clone(),
+            action => panic!("not a random action: {:?}", action),
+        };
+        assert_eq!(choices("taunt")[0].weight, 1);
+        assert!(check_choices(&choices("taunt")).is_ok());
+        assert!(matches!(
+            check_choices(&choices("weightless")),
+            Err(TriggerError::InvalidChoice { .. })
+        ));
+        assert!(matches!(
+            check_choices(&choices("nested")),
+            Err(TriggerError::InvalidChoice { .. })
+        ));
+    }
+
+    #[test]
+    fn branches_on_what_was_captured() {
+        let pack = r#"
+            [meta]
+            source = "local"
+
+            [triggers.gaze]
+            name = "Gaze"
+            search_text = '^The dragon gazes at (?P<target>\w+)\.$'
+            actions = [{ type = "If", when = "${target} = {character}", then = [
+                { type = "DisplayText", text = "YOU" },
+            ], else = [
+                { type = "DisplayText", text = "${target}" },
+            ] }]
+
+            [triggers.stacks]
+            name = "Stacks"
+            search_text = '^Your skin burns \((\d+)\)\.$'
+            actions = [{ type = "If", when = "$1 >= 10", then = [
+                { type = "DisplayText", text = "Clear your stacks" },
+            ] }]
+        "#;
+        let fixture = r#"
+            lines = [
+                "[Sat Oct 17 20:15:00 2026] The dragon gazes at Tester.",
+                "[Sat Oct 17 20:15:05 2026] The dragon gazes at Soandso.",
+                "[Sat Oct 17 20:15:10 2026] Your skin burns (9).",
+                "[Sat Oct 17 20:15:15 2026] Your skin burns (10).",
+            ]
+            expect = [
+                { trigger = "gaze", text = "YOU" },
+                { trigger = "gaze", text = "Soandso" },
+                { trigger = "stacks" },
+                { trigger = "stacks", text = "Clear your stacks" },
+            ]
+        "#;
+
+        let result = crate::fixtures::test_fixture(pack, fixture, None).unwrap();
+        assert!(result.passed(), "{:?}", result.failures);
+    }
+
+    #[test]
+    fn ends_countdowns_early() {
+        let pack = pack(
+            r#"
+            [mez]
+            name = "Mez"
+            search_text = '^(\w+) has been mesmerized\.$'
+            actions = [{ type = "Countdown", text = "Mez $1", duration = 24, end_early_text = '^\w+ has been awakened by' }]
+            "#,
+        );
+        let character = character();
+        let tid = TriggerId::new("mez");
+        let tref = TriggerRef::new(crate::config::triggers::TriggerSource::Local, tid.clone());
+        let timers = TimersConfig::default();
+        let compiled =
+            CompiledTrigger::new(&character, &tref, &pack[&tid], &pack, &timers, true).unwrap();
+
+        let id = Arc::new(CharacterId::new("soandso"));
+        let line = |id: &Arc<CharacterId>, message: &str| {
+            let line = format!("[Sat Oct 17 20:15:00 2026] {}", message);
+            Arc::new(LogEvent::parse(id.clone(), line.as_str()).unwrap())
+        };
+        let locations = LocationLog::default();
+        let mezzed = line(&id, "Gnoll has been mesmerized.");
+        let fields = LineFields::new(&mezzed, "Soandso", None, &locations);
+        let mut actions = compiled
+            .execute(&mezzed, &fields, &mut Rng::seeded(1))
+            .unwrap();
+        let countdown = &mut actions[1];
+        countdown.events(
+            &locations,
+            &Outputs::default(),
+            &Executors::default(),
+            &timers,
+        );
+
+        // Only the character that started it can end it.
+        let other = Arc::new(CharacterId::new("xanthe"));
+        assert!(countdown
+            .end_early(&line(&other, "Gnoll has been awakened by Xanthe."))
+            .is_none());
+        assert!(countdown
+            .end_early(&line(&id, "Gnoll has been slain by Soandso!"))
+            .is_none());
+        match countdown
+            .end_early(&line(&id, "Gnoll has been awakened by Soandso."))
+            .as_ref()
+            .map(Event::kind)
+        {
+            Some(EventKind::TimerEnded { template, .. }) => {
+                assert_eq!(template.as_str(), "Mez Gnoll")
+            }
+            kind => panic!("unexpected event: {:?}", kind),
+        }
+        assert!(countdown.finished());
+
+        let mut broken = pack;
+        if let Some(TriggerAction::Countdown { end_early_text, .. }) =
+            broken.get_mut(&tid).unwrap().actions.first_mut()
+        {
+            *end_early_text = Some("(".to_string());
+        }
+        assert!(
+            CompiledTrigger::new(&character, &tref, &broken[&tid], &broken, &timers, true).is_err()
+        );
+    }
+
+    #[test]
+    fn collapses_repeats_per_character() {
+        let pack = pack(
+            r#"
+            [enrage]
+            name = "Enrage"
+            search_text = '^(\w+) has become ENRAGED\.$'
+            actions = [{ type = "DisplayText", text = "Enrage", collapse = 10 }]
+            "#,
+        );
+        let character = character();
+        let tid = TriggerId::new("enrage");
+        let tref = TriggerRef::new(crate::config::triggers::TriggerSource::Local, tid.clone());
+        let timers = TimersConfig::default();
+        let compiled =
+            CompiledTrigger::new(&character, &tref, &pack[&tid], &pack, &timers, true).unwrap();
+
+        let locations = LocationLog::default();
+        let mut repeats = Repeats::default();
+        let mut repeat = |id: &Arc<CharacterId>| {
+            let line = "[Sat Oct 17 20:15:00 2026] Gnoll has become ENRAGED.";
+            let log = Arc::new(LogEvent::parse(id.clone(), line).unwrap());
+            let fields = LineFields::new(&log, "Soandso", None, &locations);
+            let actions = compiled
+                .execute(&log, &fields, &mut Rng::seeded(1))
+                .unwrap();
+            let action = actions.iter().find(|a| a.text() == Some("Enrage")).unwrap();
+            match action.repeat(id, &mut repeats).as_ref().map(Event::kind) {
+                Some(EventKind::DisplayTextRepeated { count, .. }) => Some(*count),
+                None => None,
+                kind => panic!("unexpected event: {:?}", kind),
+            }
+        };
+
+        // Two characters of the same name, on different servers, each see
+        // the text for themselves before any repeats are counted.
+        let id = Arc::new(CharacterId::new("soandso"));
+        let other = Arc::new(CharacterId::new("soandso_other"));
+        assert_eq!(repeat(&id), None);
+        assert_eq!(repeat(&other), None);
+        assert_eq!(repeat(&id), Some(2));
+        assert_eq!(repeat(&id), Some(3));
+        assert_eq!(repeat(&other), Some(2));
+    }
+
+    #[test]
+    fn journals_timers_until_they_run_out() {
+        let duration = Duration::from_secs(390);
+        let timer = || {
+            let text = Arc::new("Pick respawn".to_string());
+            Action::timer(text, duration, Instant::now() + duration)
+        };
+        let mut first = timer();
+        first.events(
+            &LocationLog::default(),
+            &Outputs::default(),
+            &Executors::default(),
+            &TimersConfig::default(),
+        );
+
+        // It's still journaled once it's started counting down.
+        assert!(matches!(
+            first.pending(),
+            Some((_, Pending::Timer { ref text, .. })) if text == "Pick respawn"
+        ));
+        first.set_journaled(1);
+        assert_eq!(first.settled(), None);
+
+        // Starting it again takes its place.
+        let second = timer();
+        assert!(first.restarted_by(&second).is_some());
+        assert!(first.pending().is_none());
+        assert_eq!(first.settled(), Some(1));
+    }
+
+    #[test]
+    fn restarts_and_repeats_countdowns() {
+        let pack = pack(
+            r#"
+            [respawn]
+            name = "Respawn"
+            search_text = '^You have slain (.+)!$'
+            actions = [{ type = "Countdown", text = "Respawn $1", duration = 30, restart = true, repeat = 1 }]
+            "#,
+        );
+        let character = character();
+        let tid = TriggerId::new("respawn");
+        let tref = TriggerRef::new(crate::config::triggers::TriggerSource::Local, tid.clone());
+        let timers = TimersConfig::default();
+        let compiled =
+            CompiledTrigger::new(&character, &tref, &pack[&tid], &pack, &timers, true).unwrap();
+
+        let id = Arc::new(CharacterId::new("soandso"));
+        let locations = LocationLog::default();
+        let countdown = |message: &str| {
+            let line = format!("[Sat Oct 17 20:15:00 2026] {}", message);
+            let log = Arc::new(LogEvent::parse(id.clone(), line.as_str()).unwrap());
+            let fields = LineFields::new(&log, "Soandso", None, &locations);
+            let mut actions = compiled
+                .execute(&log, &fields, &mut Rng::seeded(1))
+                .unwrap();
+            actions.remove(1)
+        };
+        let remaining = |action: &mut Action| -> Vec<Duration> {
+            action
+                .events(
+                    &locations,
+                    &Outputs::default(),
+                    &Executors::default(),
+                    &timers,
+                )
+                .unwrap()
                 .iter()
-                .map(|a| Action::new(&caps, a))
-                .collect();
-            actions.insert(
-                0,
-                Action::triggered(self.character.clone(), self.trigger.clone(), event.clone()),
-            );
-            actions
-        })
+                .map(|e| match e.kind() {
+                    EventKind::Countdown { remaining, .. } => *remaining,
+                    kind => panic!("unexpected event: {:?}", kind),
+                })
+                .collect()
+        };
+
+        // Matching again takes down the countdown that's already going, for
+        // the new one to take its place, but only one with the same text.
+        let mut first = countdown("You have slain a gnoll!");
+        remaining(&mut first);
+        let mut second = countdown("You have slain a gnoll!");
+        assert!(first
+            .restarted_by(&countdown("You have slain an orc!"))
+            .is_none());
+        match first.restarted_by(&second).as_ref().map(Event::kind) {
+            Some(EventKind::TimerEnded { template, .. }) => {
+                assert_eq!(template.as_str(), "Respawn a gnoll")
+            }
+            kind => panic!("unexpected event: {:?}", kind),
+        }
+        assert!(first.finished());
+
+        // Once the new one has reported in, there's nothing to take down.
+        remaining(&mut second);
+        let mut third = countdown("You have slain a gnoll!");
+        remaining(&mut third);
+        assert!(second.restarted_by(&third).is_none());
+        assert!(second.finished());
+
+        // Running out starts it over, for as many times as it repeats.
+        let run_out = |action: &mut Action| {
+            if let ActionKind::Countdown { ends_at, .. } = &mut action.kind {
+                *ends_at = Instant::now();
+            }
+            remaining(action)
+        };
+        let repeated = run_out(&mut third);
+        assert_eq!(repeated.len(), 2);
+        assert!(repeated[0].is_zero());
+        assert!(repeated[1] > Duration::from_secs(29));
+        assert!(!third.finished());
+        assert_eq!(run_out(&mut third), vec![Duration::ZERO]);
+        assert!(third.finished());
+    }
+
+    #[test]
+    fn checks_capture_types() {
+        assert!(CaptureType::Int.check("-42"));
+        assert!(!CaptureType::Int.check("4.2"));
+        assert!(CaptureType::Number.check("4.2"));
+        assert!(!CaptureType::Number.check("NaN"));
+        assert!(CaptureType::PlayerName.check("Soandso"));
+        assert!(!CaptureType::PlayerName.check("a gnoll pup"));
+        assert!(!CaptureType::PlayerName.check("Bob"));
     }
 }
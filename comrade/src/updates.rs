@@ -0,0 +1,148 @@
+//! Update Checks
+//!
+//! Players rarely go back to look at the repository once they're set up, so
+//! when it's turned on Comrade checks the Atom feed of its releases and lets
+//! the frontend know when there's a newer version than the one running. The
+//! feed is only ever read, nothing is downloaded or installed.
+
+use std::fs;
+use std::path::Path;
+
+use crate::errors::UpdateError;
+use crate::meta;
+use crate::version;
+
+type Result<T, E = UpdateError> = core::result::Result<T, E>;
+
+/// A release of Comrade, from the release feed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    pub version: String,
+    /// Where to read about the release and download it, if the feed says.
+    pub url: Option<String>,
+}
+
+/// The newest release in the feed at the given url, if it's newer than this
+/// version of Comrade.
+pub(crate) fn check(url: &str) -> Result<Option<Release>> {
+    let feed = fetch(url)?;
+    let newest = newest(feed.as_str()).map_err(|source| UpdateError::InvalidFeed {
+        source,
+        url: url.to_string(),
+    })?;
+
+    Ok(
+        newest
+            .filter(|r| !version::at_least(meta::PKG_VERSION, r.version.as_str()).unwrap_or(true)),
+    )
+}
+
+/// The newest release in an Atom feed, going by their versions rather than
+/// the order they're in. Entries whose titles aren't versions, with or
+/// without a leading `v`, and pre-releases are skipped.
+fn newest(feed: &str) -> Result<Option<Release>, roxmltree::Error> {
+    let doc = roxmltree::Document::parse(feed)?;
+
+    let mut newest: Option<Release> = None;
+    for entry in doc.descendants().filter(|n| n.has_tag_name("entry")) {
+        let title = match entry
+            .children()
+            .find(|n| n.has_tag_name("title"))
+            .and_then(|n| n.text())
+        {
+            Some(title) => title.trim(),
+            None => continue,
+        };
+        let version = title.strip_prefix('v').unwrap_or(title);
+        if version.contains('-') || version::parse(version).is_none() {
+            continue;
+        }
+        let is_newer = match newest {
+            Some(ref n) => !version::at_least(n.version.as_str(), version).unwrap_or(true),
+            None => true,
+        };
+        if is_newer {
+            newest = Some(Release {
+                version: version.to_string(),
+                url: entry
+                    .children()
+                    .find(|n| n.has_tag_name("link"))
+                    .and_then(|n| n.attribute("href"))
+                    .map(String::from),
+            });
+        }
+    }
+
+    Ok(newest)
+}
+
+/// Reads the feed, from the web if Comrade was built with the `updates`
+/// feature, otherwise only from a path or a file:// url.
+fn fetch(url: &str) -> Result<String> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        return fetch_http(url);
+    }
+
+    let path = Path::new(url.strip_prefix("file://").unwrap_or(url));
+    fs::read_to_string(path).map_err(|source| UpdateError::IOError {
+        source,
+        filename: path.to_path_buf(),
+    })
+}
+
+#[cfg(feature = "updates")]
+fn fetch_http(url: &str) -> Result<String> {
+    let request_error = |source| UpdateError::RequestError {
+        source: Box::new(source),
+        url: url.to_string(),
+    };
+
+    ureq::get(url)
+        .call()
+        .map_err(request_error)?
+        .into_string()
+        .map_err(|e| request_error(e.into()))
+}
+
+#[cfg(not(feature = "updates"))]
+fn fetch_http(url: &str) -> Result<String> {
+    Err(UpdateError::UnsupportedUrl {
+        url: url.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_newest_release() {
+        let feed = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <feed xmlns="http://www.w3.org/2005/Atom">
+              <entry>
+                <title>v1.10.0-rc1</title>
+                <link rel="alternate" href="https://example.com/v1.10.0-rc1"/>
+              </entry>
+              <entry>
+                <title>v1.9.0</title>
+                <link rel="alternate" href="https://example.com/v1.9.0"/>
+              </entry>
+              <entry>
+                <title>Nightly</title>
+              </entry>
+              <entry>
+                <title>1.10.0</title>
+                <link rel="alternate" href="https://example.com/v1.10.0"/>
+              </entry>
+            </feed>"#;
+
+        assert_eq!(
+            newest(feed).unwrap(),
+            Some(Release {
+                version: "1.10.0".to_string(),
+                url: Some("https://example.com/v1.10.0".to_string()),
+            })
+        );
+        assert_eq!(newest("<feed/>").unwrap(), None);
+    }
+}
@@ -0,0 +1,42 @@
+//! Versions
+//!
+//! Packs and releases are versioned like Comrade itself, as numbers separated
+//! by dots, and all that's ever needed is to know whether one is at least as
+//! new as another, so they're compared number by number rather than pulling
+//! in all of semver.
+
+/// Whether `version` is the same as or newer than `required`, comparing them
+/// number by number, so that `1.10` is newer than `1.9` and `1.2` is the same
+/// as `1.2.0`. Anything after a `-`, like `-beta`, is ignored. None if either
+/// isn't a version.
+pub(crate) fn at_least(version: &str, required: &str) -> Option<bool> {
+    let (mut version, mut required) = (parse(version)?, parse(required)?);
+    let len = version.len().max(required.len());
+    version.resize(len, 0);
+    required.resize(len, 0);
+
+    Some(version >= required)
+}
+
+/// The numbers that make up a version, or None if it isn't one.
+pub(crate) fn parse(version: &str) -> Option<Vec<u64>> {
+    let release = version.split_once('-').map_or(version, |(r, _)| r);
+    release
+        .trim()
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_number_by_number() {
+        assert_eq!(at_least("1.10.0", "1.9"), Some(true));
+        assert_eq!(at_least("1.2", "1.2.0-beta"), Some(true));
+        assert_eq!(at_least("0.9.3", "1.0"), Some(false));
+        assert_eq!(at_least("1.x", "1.0"), None);
+    }
+}
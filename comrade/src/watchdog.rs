@@ -0,0 +1,173 @@
+//! Latency Watchdog
+//!
+//! A callout that comes too late is worse than none at all, by the time it's
+//! heard whatever it was about has already happened, and there's no telling
+//! from the callout itself that it's late. Every audible event is measured
+//! from when the line that set it off was read, plus whatever delay it was
+//! meant to have, to when it's handed to the frontend. Once that goes past the
+//! configured threshold a `Lagging` event warns about it, and the frontends
+//! can show that Comrade is behind for as long as it is.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::audio::Severity;
+use crate::config::watchdog::WatchdogConfig;
+use crate::events::{Event, EventKind};
+use crate::time::Instant;
+
+/// How far back the worst latency is looked for.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// How long to wait after warning about lag before warning again, so a
+/// stretch of it is only warned about the once.
+const WARN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How late audible events have been handed out lately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LatencyStatus {
+    /// How late the most recent audible event was, if there's been one.
+    pub last: Option<Duration>,
+    /// The most that any audible event over the last minute was late by.
+    pub worst: Duration,
+    pub threshold: Duration,
+}
+
+impl LatencyStatus {
+    /// Whether any audible event over the last minute was later than the
+    /// threshold.
+    pub fn lagging(&self) -> bool {
+        self.worst > self.threshold
+    }
+}
+
+#[derive(Debug, Default)]
+struct Measurements {
+    // When each audible event was handed out, and how late it was.
+    samples: VecDeque<(Instant, Duration)>,
+    last: Option<Duration>,
+    warned: Option<Instant>,
+}
+
+impl Measurements {
+    fn expire(&mut self, now: Instant) {
+        while let Some((at, _)) = self.samples.front() {
+            if now.saturating_duration_since(*at) <= WINDOW {
+                break;
+            }
+            self.samples.pop_front();
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct Watchdog {
+    measurements: Mutex<Measurements>,
+}
+
+impl Watchdog {
+    /// Measures how late the event is being handed out, if it's audible and
+    /// was set off by a line, returning a warning when it's late enough to
+    /// need one.
+    pub(crate) fn measure(&self, event: &Event, config: &WatchdogConfig) -> Option<EventKind> {
+        if !config.enabled {
+            return None;
+        }
+        let due = event.due()?;
+        Severity::of(event.kind())?;
+
+        let now = Instant::now();
+        let latency = now.saturating_duration_since(due);
+        let mut measurements = self.measurements.lock();
+        measurements.expire(now);
+        measurements.samples.push_back((now, latency));
+        measurements.last = Some(latency);
+
+        if latency <= config.threshold {
+            return None;
+        }
+        if let Some(warned) = measurements.warned {
+            if now.saturating_duration_since(warned) < WARN_INTERVAL {
+                return None;
+            }
+        }
+        measurements.warned = Some(now);
+
+        Some(EventKind::Lagging {
+            latency,
+            threshold: config.threshold,
+        })
+    }
+
+    pub(crate) fn status(&self, config: &WatchdogConfig) -> LatencyStatus {
+        let mut measurements = self.measurements.lock();
+        measurements.expire(Instant::now());
+
+        LatencyStatus {
+            last: measurements.last,
+            worst: measurements
+                .samples
+                .iter()
+                .map(|(_, l)| *l)
+                .max()
+                .unwrap_or_default(),
+            threshold: config.threshold,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn display(late: Duration) -> Event {
+        Event::new(EventKind::DisplayText {
+            text: Arc::new("Interrupt".to_string()),
+            trigger: None,
+            alert: None,
+            character: None,
+        })
+        .with_due(Some(Instant::now() - late))
+    }
+
+    #[test]
+    fn warns_once_about_late_callouts() {
+        let watchdog = Watchdog::default();
+        let config = WatchdogConfig {
+            enabled: true,
+            threshold: Duration::from_millis(500),
+        };
+
+        assert!(watchdog
+            .measure(&display(Duration::from_millis(10)), &config)
+            .is_none());
+        assert!(!watchdog.status(&config).lagging());
+
+        let warning = watchdog.measure(&display(Duration::from_secs(2)), &config);
+        assert!(matches!(
+            warning,
+            Some(EventKind::Lagging { latency, .. }) if latency >= Duration::from_secs(2)
+        ));
+        assert!(watchdog
+            .measure(&display(Duration::from_secs(3)), &config)
+            .is_none());
+
+        let status = watchdog.status(&config);
+        assert!(status.lagging());
+        assert!(status.worst >= Duration::from_secs(3));
+
+        // Events that weren't set off by a line aren't measured at all.
+        let manual = Event::new(EventKind::DisplayText {
+            text: Arc::new("Manual".to_string()),
+            trigger: None,
+            alert: None,
+            character: None,
+        });
+        assert!(watchdog.measure(&manual, &config).is_none());
+    }
+}
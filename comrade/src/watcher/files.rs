@@ -0,0 +1,436 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::prelude::*;
+use std::io::{self, BufReader, SeekFrom};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use log::{debug, error, log_enabled, trace, warn};
+use notify::{Event, EventHandler, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use parking_lot::Mutex;
+
+use crate::config::CharacterId;
+use crate::errors::LogWatcherError;
+use crate::watcher::{normalize, parse_raw_line, LogEvent, LogSender, WatchStatus};
+
+type Result<T, E = LogWatcherError> = core::result::Result<T, E>;
+
+/// The longest line that will be read from a log file, which is far longer
+/// than anything the game writes, so that a corrupted log can't make us
+/// buffer without limit.
+const MAX_LINE_LENGTH: usize = 16 * 1024;
+
+/// How far behind the end of a log file we can get before we're considered
+/// to be lagging, rather than just not having caught up with a write yet.
+const LAG_THRESHOLD: u64 = 64 * 1024;
+
+/// Splits what's read from a log file into lines, holding on to a partial
+/// line until the rest of it has been written, and skipping any line that's
+/// longer than `MAX_LINE_LENGTH`.
+#[derive(Debug, Default)]
+struct LineReader {
+    buffer: Vec<u8>,
+    // Whether what's being read is the rest of a line that was too long.
+    skipping: bool,
+}
+
+impl LineReader {
+    /// Calls the given function with every complete line that can be read,
+    /// including its line ending. Anything that isn't valid UTF-8 is
+    /// replaced, rather than the whole line being lost.
+    fn read<R: BufRead>(&mut self, reader: &mut R, mut f: impl FnMut(&str)) -> io::Result<()> {
+        loop {
+            let limit = (MAX_LINE_LENGTH + 1 - self.buffer.len()) as u64;
+            let read = reader
+                .by_ref()
+                .take(limit)
+                .read_until(b'\n', &mut self.buffer)?;
+            if read == 0 {
+                return Ok(());
+            }
+
+            if self.buffer.last() != Some(&b'\n') {
+                if self.buffer.len() > MAX_LINE_LENGTH {
+                    if !self.skipping {
+                        warn!("skipping a line longer than {} bytes", MAX_LINE_LENGTH);
+                    }
+                    self.skipping = true;
+                    self.buffer.clear();
+                }
+                continue;
+            }
+
+            if self.skipping {
+                self.skipping = false;
+            } else {
+                f(&String::from_utf8_lossy(&self.buffer));
+            }
+            self.buffer.clear();
+        }
+    }
+}
+
+struct LogHandler {
+    id: Arc<CharacterId>,
+    filename: PathBuf,
+    filename_short: String,
+    reader: Option<BufReader<File>>,
+    lines: LineReader,
+    filter: Box<dyn Fn(&str) -> bool + Send>,
+    sender: LogSender,
+}
+
+impl LogHandler {
+    fn new<P: Into<PathBuf>>(
+        filename: P,
+        id: Arc<CharacterId>,
+        sender: LogSender,
+    ) -> Result<LogHandler> {
+        let filename = filename.into();
+        let filename_short = filename
+            .file_name()
+            .ok_or_else(|| LogWatcherError::InvalidPath {
+                path: filename.clone(),
+            })?
+            .to_str()
+            .ok_or_else(|| LogWatcherError::InvalidPath {
+                path: filename.clone(),
+            })?
+            .to_string();
+
+        let mut lr = LogHandler {
+            id,
+            filename,
+            filename_short,
+            reader: None,
+            lines: LineReader::default(),
+            filter: Box::new(|_line| false),
+            sender,
+        };
+        lr.reader = lr.open_reader();
+
+        if let Some(ref mut reader) = lr.reader {
+            reader.seek(SeekFrom::End(0))?;
+            trace!("seeked to end of file: {}", lr.filename.to_string_lossy())
+        }
+
+        Ok(lr)
+    }
+
+    fn open_reader(&mut self) -> Option<BufReader<File>> {
+        match File::open(self.filename.as_path()) {
+            Ok(file) => {
+                debug!("opened file: {}", self.filename.to_string_lossy());
+                Some(BufReader::new(file))
+            }
+            Err(err) => {
+                debug!(
+                    "error opening file: {} error: {:?}",
+                    self.filename.to_string_lossy(),
+                    err
+                );
+                None
+            }
+        }
+    }
+
+    fn reopen_reader(&mut self) {
+        self.reader = self.open_reader();
+        self.lines = LineReader::default();
+    }
+
+    /// Skips over everything that's been written so far, so that only what's
+    /// written from here on is read.
+    fn skip_to_end(&mut self) {
+        self.reopen_reader();
+        if let Some(ref mut reader) = self.reader {
+            if let Err(e) = reader.seek(SeekFrom::End(0)) {
+                warn!(
+                    "could not skip to the end of file: {} error: {}",
+                    self.filename_short, e
+                );
+            }
+        }
+    }
+
+    fn process_lines(&mut self) {
+        if let Some(ref mut reader) = self.reader {
+            let result = self.lines.read(reader, |line| {
+                if log_enabled!(target: "comrade::watcher::raw", log::Level::Trace) {
+                    trace!(
+                        target: "comrade::watcher::raw",
+                        "filename: {} line: {}",
+                        self.filename_short,
+                        line.trim_end()
+                    );
+                }
+
+                if let Some((timestamp, raw)) = parse_raw_line(line) {
+                    let line = normalize(raw);
+                    if (self.filter)(&line) {
+                        trace!("matched line: {}", line);
+
+                        let raw = matches!(line, Cow::Owned(_)).then_some(raw);
+                        self.sender
+                            .send(Arc::new(LogEvent::new(
+                                self.id.clone(),
+                                timestamp,
+                                &line,
+                                raw,
+                            )))
+                            .expect("sender should not be disconnected");
+                    }
+                }
+            });
+
+            if let Err(e) = result {
+                error!(
+                    "error reading file; filename: {} error: {}",
+                    self.filename_short, e,
+                );
+            }
+        }
+    }
+
+    fn set_filter(&mut self, filter: Box<dyn Fn(&str) -> bool + Send>) {
+        self.filter = filter;
+    }
+
+    fn status(&mut self) -> WatchStatus {
+        let reader = match self.reader {
+            Some(ref mut reader) if self.filename.exists() => reader,
+            _ => return WatchStatus::Missing,
+        };
+
+        let position = reader.stream_position().unwrap_or(0);
+        let length = reader.get_ref().metadata().map(|m| m.len()).unwrap_or(0);
+        if length.saturating_sub(position) > LAG_THRESHOLD {
+            WatchStatus::Lagging
+        } else {
+            WatchStatus::Watching
+        }
+    }
+}
+
+impl EventHandler for LogHandler {
+    fn handle_event(&mut self, res: notify::Result<Event>) {
+        match res {
+            Ok(event) => match event.kind {
+                EventKind::Create(_) => self.reopen_reader(),
+                EventKind::Modify(_) => self.process_lines(),
+                EventKind::Remove(_) => (),
+                EventKind::Access(_) => (),
+                _ => {
+                    warn!("unexpected event received: {:?}", event)
+                }
+            },
+            Err(e) => {
+                error!("an error occured while watching files: {:?}", e);
+            }
+        }
+    }
+}
+
+struct LogWatcher {
+    filename: PathBuf,
+    handler: Arc<Mutex<LogHandler>>,
+    watcher: RecommendedWatcher,
+    running: bool,
+}
+
+impl LogWatcher {
+    fn new(filename: PathBuf, id: Arc<CharacterId>, sender: LogSender) -> Result<LogWatcher> {
+        let handler = Arc::new(Mutex::new(LogHandler::new(filename.as_path(), id, sender)?));
+        let handler_ = handler.clone();
+        let watcher = notify::recommended_watcher(move |res| handler_.lock().handle_event(res))?;
+
+        Ok(LogWatcher {
+            filename,
+            handler,
+            watcher,
+            running: false,
+        })
+    }
+
+    fn start(&mut self) -> Result<()> {
+        self.watcher
+            .watch(self.filename.as_path(), RecursiveMode::NonRecursive)?;
+        self.running = true;
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<()> {
+        self.watcher.unwatch(self.filename.as_path())?;
+        self.running = false;
+        Ok(())
+    }
+
+    /// Goes back to watching after having been stopped, without catching up
+    /// on what was written in the meantime.
+    fn resume(&mut self) -> Result<()> {
+        self.handler.lock().skip_to_end();
+        self.start()
+    }
+
+    fn set_filter(&self, filter: Box<dyn Fn(&str) -> bool + Send>) {
+        self.handler.lock().set_filter(filter);
+    }
+
+    fn status(&self) -> WatchStatus {
+        if self.running {
+            self.handler.lock().status()
+        } else {
+            WatchStatus::Paused
+        }
+    }
+}
+
+pub(crate) struct Watchers {
+    watchers: HashMap<CharacterId, LogWatcher>,
+    sender: LogSender,
+    // The characters whose logs aren't to be watched until they're resumed.
+    paused: HashSet<CharacterId>,
+    started: bool,
+}
+
+impl Watchers {
+    pub(crate) fn new(sender: LogSender) -> Watchers {
+        Watchers {
+            watchers: HashMap::default(),
+            sender,
+            paused: HashSet::new(),
+            started: false,
+        }
+    }
+
+    pub(crate) fn add(&mut self, id: CharacterId, filename: PathBuf) -> Result<()> {
+        self.watchers.insert(
+            id.clone(),
+            LogWatcher::new(filename, Arc::new(id), self.sender.clone())?,
+        );
+
+        Ok(())
+    }
+
+    pub(crate) fn start(&mut self) -> Result<()> {
+        for (id, watcher) in self.watchers.iter_mut() {
+            if !self.paused.contains(id) {
+                watcher.start()?;
+            }
+        }
+        self.started = true;
+
+        Ok(())
+    }
+
+    pub(crate) fn stop(&mut self) -> Result<()> {
+        for watcher in self.watchers.values_mut().filter(|w| w.running) {
+            watcher.stop()?;
+        }
+        self.started = false;
+
+        Ok(())
+    }
+
+    /// Stops watching the given character's log, or goes back to it, in
+    /// which case whatever was written to it while it was paused is skipped
+    /// rather than acted on late.
+    pub(crate) fn set_paused(&mut self, id: &CharacterId, paused: bool) -> Result<()> {
+        let changed = if paused {
+            self.paused.insert(id.clone())
+        } else {
+            self.paused.remove(id)
+        };
+        if !changed || !self.started {
+            return Ok(());
+        }
+
+        match self.watchers.get_mut(id) {
+            Some(watcher) if paused => watcher.stop(),
+            Some(watcher) => watcher.resume(),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn set_filter(&self, id: &CharacterId, filter: Box<dyn Fn(&str) -> bool + Send>) {
+        if let Some(watcher) = self.watchers.get(id) {
+            watcher.set_filter(filter)
+        }
+    }
+
+    pub(crate) fn status(&self, id: &CharacterId) -> Option<WatchStatus> {
+        self.watchers.get(id).map(|w| w.status())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+    use proptest::sample::Index;
+
+    use super::*;
+
+    fn read_chunks(chunks: &[&[u8]]) -> Vec<String> {
+        let mut reader = LineReader::default();
+        let mut lines = Vec::new();
+        for chunk in chunks {
+            reader
+                .read(&mut Cursor::new(chunk), |line| lines.push(line.to_string()))
+                .unwrap();
+        }
+        lines
+    }
+
+    #[test]
+    fn partial_lines_wait_for_the_rest() {
+        let lines = read_chunks(&[b"[Sat Oct 17 20:15:00 2026] You have", b" been slowed.\n"]);
+        assert_eq!(
+            lines,
+            vec!["[Sat Oct 17 20:15:00 2026] You have been slowed.\n"]
+        );
+    }
+
+    #[test]
+    fn overlong_lines_are_skipped() {
+        let long = format!(
+            "[{}]\n[Sat Oct 17 20:15:00 2026] Hi\n",
+            "a".repeat(MAX_LINE_LENGTH * 3)
+        );
+        let lines = read_chunks(&[long.as_bytes()]);
+        assert_eq!(lines, vec!["[Sat Oct 17 20:15:00 2026] Hi\n"]);
+    }
+
+    #[test]
+    fn invalid_utf8_is_replaced() {
+        let lines = read_chunks(&[b"[Sat Oct 17 20:15:00 2026] Caf\xe9\n"]);
+        assert_eq!(
+            parse_raw_line(lines[0].as_str()),
+            Some(("Sat Oct 17 20:15:00 2026", "Caf\u{fffd}"))
+        );
+    }
+
+    proptest! {
+        // Lines can be tens of kilobytes, which is slow to generate.
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn lines_are_never_longer_than_the_cap(
+            bytes in vec(prop_oneof![Just(b'\n'), any::<u8>()], 0..MAX_LINE_LENGTH * 3),
+        ) {
+            for line in read_chunks(&[bytes.as_slice()]) {
+                prop_assert!(line.ends_with('\n'));
+                prop_assert!(line.chars().count() <= MAX_LINE_LENGTH + 1);
+            }
+        }
+
+        #[test]
+        fn chunking_does_not_change_lines(bytes in vec(any::<u8>(), 0..2048), at in any::<Index>()) {
+            let (first, second) = bytes.split_at(at.index(bytes.len() + 1));
+            prop_assert_eq!(read_chunks(&[first, second]), read_chunks(&[bytes.as_slice()]));
+        }
+    }
+}
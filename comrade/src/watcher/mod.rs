@@ -0,0 +1,227 @@
+//! Log Watching
+//!
+//! Lines from each character's log are turned into `LogEvent`s for the
+//! driver, either by watching their log files, which needs the `watcher`
+//! feature, or by being handed them directly.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use crossbeam_channel::{bounded, Receiver, Sender};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::config::CharacterId;
+use crate::history::LogTime;
+use crate::time::Instant;
+
+#[cfg(feature = "watcher")]
+mod files;
+
+#[cfg(feature = "watcher")]
+pub(crate) use self::files::Watchers;
+
+lazy_static! {
+    static ref RAW_LINE_RE: Regex = Regex::new(r"^\[([^]]+)\] (.+?)\r?\n$").unwrap();
+    // Item links are the item's name wrapped in 0x12, with the link's data
+    // in hex before the name, which is a different length depending on the
+    // client.
+    static ref ITEM_LINK_RE: Regex =
+        Regex::new(r"\x12(?:[0-9A-F]{56}|[0-9A-F]{50}|[0-9A-F]{45})([^\x12]*)\x12").unwrap();
+    static ref CONTROL_RE: Regex = Regex::new(r"[\x00-\x08\x0B-\x1F\x7F]").unwrap();
+    static ref WHITESPACE_RE: Regex = Regex::new(r"\s{2,}|\t").unwrap();
+}
+
+/// How many log events can be waiting on the driver before whatever is
+/// reading the logs has to wait for it to catch up.
+const LOG_CAPACITY: usize = 1000;
+
+/// What's going on with the watching of a character's log file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchStatus {
+    /// Lines are being read from the log file as they're written.
+    Watching,
+    /// The log file doesn't exist (yet), so there's nothing to read.
+    Missing,
+    /// The log file isn't being watched.
+    Paused,
+    /// Lines are being written to the log file faster than we're reading
+    /// them.
+    Lagging,
+}
+
+impl std::fmt::Display for WatchStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let status = match self {
+            WatchStatus::Watching => "watching",
+            WatchStatus::Missing => "file missing",
+            WatchStatus::Paused => "paused",
+            WatchStatus::Lagging => "lagging",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+pub(crate) type LogSender = Sender<Arc<LogEvent>>;
+pub(crate) type LogReceiver = Receiver<Arc<LogEvent>>;
+
+pub(crate) fn channel() -> (LogSender, LogReceiver) {
+    bounded(LOG_CAPACITY)
+}
+
+#[derive(Debug, Clone)]
+pub struct LogEvent {
+    pub(crate) id: Arc<CharacterId>,
+    /// The timestamp followed by the message, so that every line that's
+    /// matched costs the one allocation for its text rather than one for each.
+    text: Box<str>,
+    /// Where the message starts in the text.
+    split: usize,
+    /// The message as it was in the log, if normalizing it changed it.
+    raw: Option<Box<str>>,
+    /// When the line was read, which is where the time it takes to act on it
+    /// is measured from.
+    read: Instant,
+}
+
+impl LogEvent {
+    pub(crate) fn new(
+        id: Arc<CharacterId>,
+        timestamp: &str,
+        message: &str,
+        raw: Option<&str>,
+    ) -> LogEvent {
+        let mut text = String::with_capacity(timestamp.len() + message.len());
+        text.push_str(timestamp);
+        text.push_str(message);
+
+        LogEvent {
+            id,
+            text: text.into_boxed_str(),
+            split: timestamp.len(),
+            raw: raw.map(Box::from),
+            read: Instant::now(),
+        }
+    }
+
+    /// Reads an event from a line of a log file, with or without the line
+    /// ending, returning `None` if it isn't a line that the game wrote.
+    pub(crate) fn parse(id: Arc<CharacterId>, line: &str) -> Option<LogEvent> {
+        let line = format!("{}\n", line.trim_end());
+        let (timestamp, raw) = parse_raw_line(line.as_str())?;
+        let message = normalize(raw);
+        let raw = matches!(message, Cow::Owned(_)).then_some(raw);
+
+        Some(LogEvent::new(id, timestamp, &message, raw))
+    }
+
+    /// The message, normalized so that triggers don't have to account for
+    /// item links and the like.
+    pub fn message(&self) -> &str {
+        &self.text[self.split..]
+    }
+
+    /// The message exactly as it was in the log.
+    pub fn raw(&self) -> &str {
+        self.raw.as_deref().unwrap_or_else(|| self.message())
+    }
+
+    /// This event with its timestamp moved by the given number of seconds,
+    /// for logs written on a clock that's set differently from ours.
+    pub(crate) fn shifted(self, seconds: i64) -> LogEvent {
+        if seconds == 0 {
+            return self;
+        }
+
+        match LogTime::from_timestamp(self.timestamp()) {
+            Some(time) => {
+                let timestamp = time.shifted(seconds).to_timestamp();
+                let shifted =
+                    LogEvent::new(self.id.clone(), timestamp.as_str(), self.message(), None);
+                LogEvent {
+                    raw: self.raw,
+                    read: self.read,
+                    ..shifted
+                }
+            }
+            None => self,
+        }
+    }
+
+    /// When the line was read.
+    pub(crate) fn read(&self) -> Instant {
+        self.read
+    }
+
+    /// When the line was written, exactly as the game wrote it, e.g.
+    /// `Sat Oct 17 20:15:00 2026`, which is in the game's local time.
+    pub fn timestamp(&self) -> &str {
+        &self.text[..self.split]
+    }
+}
+
+#[inline(always)]
+pub(crate) fn parse_raw_line(line: &str) -> Option<(&str, &str)> {
+    RAW_LINE_RE.captures(line).map(|caps| {
+        (
+            caps.get(1)
+                .expect("regex somehow matched without mandatory date capture")
+                .as_str(),
+            caps.get(2)
+                .expect("regex somehow matched without mandatory message capture")
+                .as_str(),
+        )
+    })
+}
+
+/// Cleans up a message before anything looks at it, turning item links into
+/// just the item's name, stripping any other control characters, and
+/// collapsing runs of whitespace.
+pub(crate) fn normalize(message: &str) -> Cow<'_, str> {
+    let mut message = Cow::Borrowed(message);
+    if message.contains('\x12') {
+        message = Cow::Owned(ITEM_LINK_RE.replace_all(&message, "$1").into_owned());
+    }
+    if CONTROL_RE.is_match(&message) {
+        message = Cow::Owned(CONTROL_RE.replace_all(&message, "").into_owned());
+    }
+    if WHITESPACE_RE.is_match(&message) {
+        message = Cow::Owned(WHITESPACE_RE.replace_all(&message, " ").into_owned());
+    }
+    if message.trim().len() != message.len() {
+        message = Cow::Owned(message.trim().to_string());
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn parse_raw_line_never_panics(line in any::<String>()) {
+            let _ = parse_raw_line(line.as_str());
+        }
+
+        #[test]
+        fn parse_raw_line_finds_timestamp_and_message(
+            timestamp in "[A-Za-z0-9: ]{1,24}",
+            message in "[ -~]{1,200}",
+        ) {
+            let line = format!("[{}] {}\r\n", timestamp, message);
+            prop_assert_eq!(
+                parse_raw_line(line.as_str()),
+                Some((timestamp.as_str(), message.as_str()))
+            );
+        }
+
+        #[test]
+        fn normalize_is_idempotent(message in any::<String>()) {
+            let once = normalize(message.as_str()).into_owned();
+            prop_assert_eq!(normalize(once.as_str()), once.as_str());
+        }
+    }
+}